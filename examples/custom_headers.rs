@@ -67,29 +67,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("- http://localhost:8080/api/custom-headers");
     println!("- http://localhost:8080/api/cors");
     
-    // 実行環境に応じて処理を分岐
-    #[cfg(feature = "cloud_run")]
-    {
-        // Cloud Run環境での実行
-        runbridge::cloudrun::run_cloud_run(app, "127.0.0.1", 8080).await?;
-    }
-    
-    #[cfg(feature = "lambda")]
-    {
-        // Lambda環境での実行
-        println!("Lambda環境では、このサンプルを直接実行することはできません。");
-        println!("AWS Lambdaにデプロイして実行してください。");
-    }
-    
-    #[cfg(feature = "cgi")]
-    {
-        // CGI環境での実行
-        println!("CGI環境では、このサンプルを直接実行することはできません。");
-        println!("Webサーバー経由でCGIとして実行してください。");
-    }
-    
-    #[cfg(not(any(feature = "cloud_run", feature = "lambda", feature = "cgi")))]
-    {
+    // 有効なfeatureに応じた実行環境で起動（Cloud Runのバインド先は環境変数 HOST / PORT）
+    if let Err(e) = runbridge::serve(app).await {
+        println!("起動に失敗しました: {}", e);
         println!("実行するには、次のいずれかの機能を有効にしてビルドしてください:");
         println!("  cargo run --example custom_headers --features cloud_run");
         println!("  cargo run --example custom_headers --features lambda");
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use runbridge::{
+    common::{Request, Response},
+    error::Error,
+    RunBridge,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Item {
+    name: String,
+    price: u32,
+}
+
+// 全てのルートで共有するサービス（DBプールやAPIクライアントの代わり）
+#[derive(Default)]
+struct Services {
+    items: Mutex<HashMap<String, Item>>,
+}
+
+// 一覧を返すハンドラー（状態は第1引数で受け取る）
+async fn list_items(services: Arc<Services>, _req: Request) -> Result<Response, Error> {
+    let items: Vec<Item> = services.items.lock().unwrap().values().cloned().collect();
+    Response::ok().json(&items)
+}
+
+// 追加するハンドラー（ボディは`req.json()`で取り出す）
+async fn create_item(services: Arc<Services>, req: Request) -> Result<Response, Error> {
+    let item: Item = req.json()?;
+    services.items.lock().unwrap().insert(item.name.clone(), item.clone());
+    Response::created().json(&item)
+}
+
+// 件数を返すハンドラー
+async fn count_items(services: Arc<Services>, _req: Request) -> Result<String, Error> {
+    Ok(services.items.lock().unwrap().len().to_string())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // ロガーを初期化
+    env_logger::init();
+
+    // 状態を1回だけ登録し、ルートごとにArcをcloneしない
+    let app = RunBridge::builder()
+        .with_state(Services::default(), |routes| {
+            routes
+                .get("^/api/items$", list_items)
+                .post("^/api/items$", create_item)
+                .get("^/api/items/count$", count_items)
+        })
+        .build();
+
+    println!("サーバーを起動中...");
+    println!("次のエンドポイントにアクセスしてみてください:");
+    println!("- GET  http://localhost:8080/api/items");
+    println!("- POST http://localhost:8080/api/items");
+    println!("- GET  http://localhost:8080/api/items/count");
+
+    // 有効なfeatureに応じた実行環境で起動（Cloud Runのバインド先は環境変数 HOST / PORT）
+    if let Err(e) = runbridge::serve(app).await {
+        println!("起動に失敗しました: {}", e);
+        println!("実行するには、次のいずれかの機能を有効にしてビルドしてください:");
+        println!("  cargo run --example shared_state --features cloud_run");
+        println!("  cargo run --example shared_state --features lambda");
+        println!("  cargo run --example shared_state --features cgi");
+    }
+
+    Ok(())
+}
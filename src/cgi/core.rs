@@ -1,48 +1,65 @@
 //! CGIメイン実行ロジック
 
 use std::env;
+use std::time::Instant;
 use log::{debug, error, info};
 use tokio::task;
 
-use crate::common::{Method, Request, Response, parse_query_string};
+use crate::common::{Method, Request, Response, check_method, parse_query_string_limited};
+use crate::common::deadline::{deadline_from_env, with_deadline};
+use crate::common::request_id::{generate_request_id, sanitize_request_id, with_request_id};
 use crate::error::Error;
 use crate::RunBridge;
-use super::request::{get_cgi_headers, read_request_body};
-use super::response::write_response;
+use super::request::{get_cgi_headers, get_cgi_origin, read_request_body_with_limit};
+use super::response::{write_head_response, write_response};
 use super::error_logging::{log_error_to_file, gather_cgi_panic_context};
+use super::metrics::CgiMetrics;
 
 /// CGIリクエスト情報をRunBridgeリクエストに変換し、処理を実行する
 pub async fn run_cgi(app: RunBridge) -> Result<(), Error> {
+    let mut metrics = CgiMetrics::start();
+    let started = Instant::now();
+    app.launch()?;
+
     // 環境変数からリクエスト情報を取得
     let method_str = env::var("REQUEST_METHOD").map_err(|_| {
         Error::InvalidRequestBody("REQUEST_METHOD environment variable not set".to_string())
     })?;
     
-    let method = Method::from_str(&method_str).ok_or_else(|| {
-        Error::InvalidRequestBody(format!("Invalid HTTP method: {}", method_str))
-    })?;
-    
     let path = env::var("PATH_INFO").unwrap_or_else(|_| "/".to_string());
+    // ルーティング前に拒否する場合のレスポンスの出力（アクセスログも記録）
+    let reject = |metrics: &mut CgiMetrics, res: Response| {
+        app.log_rejected(started, &method_str, &path, &res);
+        respond(metrics, res)
+    };
+
+    // 許可されていないメソッドはルーティング前に405/501で拒否
+    let method = match check_method(&method_str) {
+        Ok(method) => method,
+        Err(res) => return reject(&mut metrics, res),
+    };
+    
     let query_string = env::var("QUERY_STRING").unwrap_or_default();
+    metrics.set_request(&method_str, &path);
     
-    // クエリパラメータを解析
-    let query_params = parse_query_string(&query_string);
+    // クエリパラメータを解析（長さ・個数の上限超過時は414/400を返す）
+    let query_params = match parse_query_string_limited(&query_string) {
+        Ok(params) => params,
+        Err(e) => return reject(&mut metrics, Response::from_error(&e)),
+    };
     
     // ヘッダーを取得
     let headers = get_cgi_headers();
+    metrics.mark("env_parse");
     
-    // ボディを読み込む（上限超過時はここで413レスポンスを返す）
-    let body = match read_request_body() {
+    // ボディを読み込む（上限はルート固有 -> 全体設定、超過時はここで413レスポンスを返す）
+    let max_body_size = app.max_body_size_for(&path, &method);
+    let body = match read_request_body_with_limit(max_body_size) {
         Ok(b) => b,
-        Err(Error::PayloadTooLarge(_msg)) => {
-            let res = Response::new(413)
-                .with_header("Content-Type", "text/plain")
-                .with_body("Payload Too Large".as_bytes().to_vec());
-            write_response(res)?;
-            return Ok(());
-        }
+        Err(e @ Error::PayloadTooLarge(_)) => return reject(&mut metrics, Response::from_error(&e)),
         Err(e) => return Err(e),
     };
+    metrics.mark("body_read");
     
     // リクエストを構築
     let mut request = Request::new(method, path.clone());
@@ -53,44 +70,34 @@ pub async fn run_cgi(app: RunBridge) -> Result<(), Error> {
         .map(|(k, v)| (k.to_ascii_lowercase(), v))
         .collect();
     request.body = body;
+    request.set_origin(get_cgi_origin());
     
-    // gzipボディを解凍（必要な場合のみ）
-    if let Err(e) = request.decompress_gzip_body() {
+    // gzipボディを解凍（必要な場合のみ、解凍後のサイズもルートの上限で制限）
+    if let Err(e) = request.decompress_gzip_body_with_limit(max_body_size) {
         error!("Failed to decompress gzip body in CGI: {}", e);
-        let res = Response::new(400)
-            .with_header("Content-Type", "text/plain")
-            .with_body(format!("Bad Request: {}", e).as_bytes().to_vec());
-        write_response(res)?;
-        return Ok(());
+        return reject(&mut metrics, Response::from_error(&e));
     }
     
     // リクエストを処理
     debug!("Processing CGI request: {} {}", method, path);
     
     // ハンドラ内でのpanicを検知するためにspawnしてJoinErrorを検査
-    let task_result = task::spawn(async move {
-        process_request(app, request).await
-    }).await;
+    // リクエストIDをタスクローカルに設定（パニックフックのログで参照）
+    let request_id = env::var("HTTP_X_REQUEST_ID")
+        .or_else(|_| env::var("UNIQUE_ID"))
+        .ok()
+        .and_then(|v| sanitize_request_id(&v))
+        .unwrap_or_else(generate_request_id);
+    let app = std::sync::Arc::new(app);
+    let task_app = app.clone();
+    let deadline = deadline_from_env();
+    let task_result = task::spawn(with_request_id(request_id, with_deadline(deadline, async move {
+        process_request(&task_app, request).await
+    }))).await;
 
     let response = match task_result {
-        // タスクが正常終了し、かつハンドラがResult::Ok/Errを返した場合
-        Ok(inner_result) => match inner_result {
-            Ok(res) => res,
-            Err(err) => {
-                error!("Error processing request: {:?}", err);
-                log_error_to_file(&format!("Handler returned error at {} {}: {:?}", method, path, err));
-                match err {
-                    Error::RouteNotFound(msg) => {
-                        Response::not_found()
-                            .with_header("Content-Type", "text/plain")
-                            .with_body(format!("Not Found: {}", msg).into_bytes())
-                    }
-                    _ => Response::internal_server_error()
-                        .with_header("Content-Type", "text/plain")
-                        .with_body(format!("Internal Server Error: {}", err).into_bytes())
-                }
-            }
-        },
+        // タスクが正常終了した場合（ハンドラーのエラーはレスポンスに変換済み）
+        Ok(res) => res,
         // タスクがpanicした場合
         Err(join_err) => {
             let panic_info = if join_err.is_panic() {
@@ -105,54 +112,53 @@ pub async fn run_cgi(app: RunBridge) -> Result<(), Error> {
                 let ctx = gather_cgi_panic_context(&method.to_string(), &path);
                 log_error_to_file(&ctx);
             }
-            Response::internal_server_error()
-                .with_header("Content-Type", "text/plain")
-                .with_body("Internal Server Error".as_bytes().to_vec())
+            Response::error(500)
         }
     };
     
+    metrics.mark("dispatch");
+
     // レスポンスを標準出力に書き出す
-    write_response(response)?;
+    // HEADリクエストはボディを出力せず、GETと同じ`Content-Length`を返す
+    let status = response.status;
+    let written = if method == Method::HEAD {
+        write_head_response(response)
+    } else {
+        write_response(response)
+    };
+    metrics.mark("write");
+
+    // CGIは1リクエストごとにプロセスが終了するため、出力後に終了時の処理を実行する
+    app.shutdown().await;
+    metrics.mark("shutdown");
+    metrics.emit(status);
+    written?;
     
     info!("CGI request processed successfully");
     Ok(())
 }
 
-/// リクエストを処理する
-async fn process_request(app: RunBridge, request: Request) -> Result<Response, Error> {
-    // ハンドラを検索
-    let handler = app.find_handler(&request.path, &request.method).ok_or_else(|| {
-        Error::RouteNotFound(format!("{} {}", request.method, request.path))
-    })?;
-    
-    // ミドルウェアの前処理を適用
-    let mut processed_request = request;
-    for middleware in app.middlewares() {
-        processed_request = middleware.pre_process(processed_request).await?;
-    }
-    
-    // ハンドラでリクエストを処理
-    let handler_result = handler.handle(processed_request).await;
-    
-    // レスポンスの処理
-    let mut response = match handler_result {
-        Ok(res) => res,
-        Err(e) => {
-            error!("Handler error: {}", e);
-            return Ok(Response::from_error(&e));
-        }
-    };
-    
-    // ミドルウェアの後処理を適用
-    for middleware in app.middlewares() {
-        match middleware.post_process(response).await {
-            Ok(processed) => response = processed,
-            Err(e) => {
-                error!("Middleware error in post-processing: {}", e);
-                response = Response::from_error(&e);
-            }
-        }
+/// ハンドラーの実行前に返すレスポンスを出力し、計測結果を書き出す
+fn respond(metrics: &mut CgiMetrics, res: Response) -> Result<(), Error> {
+    let status = res.status;
+    let written = write_response(res);
+    metrics.mark("write");
+    metrics.emit(status);
+    written
+}
+
+/// リクエストを処理する（CGIとfetch JSONアダプターで共通）
+pub(crate) async fn process_request(app: &RunBridge, request: Request) -> Response {
+    // ルーティング・ミドルウェア・ハンドラーの実行（全ランタイム共通）
+    let method = request.method;
+    let mut response = app.process_request(request).await.response;
+
+    // HEADリクエストはボディを除去し、GETと同じ`Content-Length`を返す
+    if method == Method::HEAD {
+        response.strip_body_for_head();
     }
-    
-    Ok(response)
-}
\ No newline at end of file
+
+    // CGIは逐次送信できないため、Server-Sent Eventsはまとめて返す
+    response.buffer_event_stream().await;
+    response
+}
@@ -6,6 +6,9 @@ use std::io::Write;
 use chrono::Local;
 use log::error;
 
+// マスク処理は全ランタイム共通のcommon::redactionへ移動（互換性維持のため再エクスポート）
+pub use crate::common::redaction::{redact_value_for_log, is_sensitive_key_like, redact_query_string};
+
 /// エラー内容をログファイルに追記する
 pub fn log_error_to_file(message: &str) {
     let timestamp = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S%.3f UTC");
@@ -84,66 +87,3 @@ pub fn gather_cgi_panic_context(method: &str, path: &str) -> String {
 
     lines.join("\n")
 }
-
-pub fn redact_value_for_log(key: &str, value: &str) -> String {
-    let key_l = key.to_ascii_lowercase();
-    if key_l == "query_string" {
-        return redact_query_string(value);
-    }
-    if is_sensitive_key_like(&key_l) {
-        return "***redacted***".to_string();
-    }
-    // 長すぎる値は truncate（例：User-Agent）
-    if value.len() > 200 {
-        format!("{}...[truncated]", &value[..200])
-    } else {
-        value.to_string()
-    }
-}
-
-pub fn is_sensitive_key_like(lower_key: &str) -> bool {
-    let patterns = [
-        "authorization",
-        "cookie",
-        "token",
-        "secret",
-        "password",
-        "pass",
-        "api-key",
-        "api_key",
-        "apikey",
-        "x-api-key",
-        "x_api_key",
-        "jwt",
-        "auth",
-        "session",
-        "csrf",
-        "signature",
-        "private",
-        "key",
-        "credential",
-        "access_token",
-        "refresh_token",
-        "bearer",
-        "basic",
-    ];
-    patterns.iter().any(|p| lower_key.contains(p))
-}
-
-pub fn redact_query_string(qs: &str) -> String {
-    if qs.is_empty() { return qs.to_string(); }
-    let mut out_parts = Vec::new();
-    for part in qs.split('&') {
-        if part.is_empty() { continue; }
-        let mut it = part.splitn(2, '=');
-        let k = it.next().unwrap_or("");
-        let v = it.next().unwrap_or("");
-        let k_l = k.to_ascii_lowercase();
-        if is_sensitive_key_like(&k_l) {
-            out_parts.push(format!("{}=***redacted***", k));
-        } else {
-            out_parts.push(format!("{}={}", k, v));
-        }
-    }
-    out_parts.join("&")
-}
\ No newline at end of file
@@ -0,0 +1,106 @@
+//! CGIプロセスの処理時間の計測
+//!
+//! `RUNBRIDGE_CGI_METRICS=1`の場合、プロセスの開始からレスポンスの出力までの時間と、
+//! 各段階（環境変数の解析・ボディの読み込み・ハンドラーの実行・出力・終了時の処理）の内訳を
+//! 1行のJSONとして標準エラー出力に書き出します。共有ホスティングでの遅延の調査用です。
+//!
+//! ```json
+//! {"type":"runbridge.cgi.metrics","started_at":"...","method":"GET","path":"/","status":200,"total_ms":12.3,"phases":{"startup_ms":1.2,...}}
+//! ```
+
+use std::env;
+use std::io::Write;
+use std::sync::OnceLock;
+use std::time::{Instant, SystemTime};
+
+use serde_json::{Map, Value};
+
+/// プロセスの開始時刻（単調時計と実時刻）
+static PROCESS_START: OnceLock<(Instant, SystemTime)> = OnceLock::new();
+
+/// 計測を有効にするかどうか
+/// 優先順位: 環境変数 `RUNBRIDGE_CGI_METRICS`（`1` / `true`で有効） -> デフォルト 無効
+pub fn is_cgi_metrics_enabled() -> bool {
+    env::var("RUNBRIDGE_CGI_METRICS")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+/// プロセスの開始時刻を記録（`main`の先頭で呼ぶと、ランタイムの初期化も計測に含まれる）
+///
+/// 呼ばれていない場合は`run_cgi`の開始時刻をプロセスの開始とみなします。
+pub fn mark_process_start() {
+    PROCESS_START.get_or_init(|| (Instant::now(), SystemTime::now()));
+}
+
+/// 1リクエスト分の段階ごとの計測
+pub(crate) struct CgiMetrics {
+    enabled: bool,
+    started: Instant,
+    started_at: SystemTime,
+    last: Instant,
+    phases: Vec<(&'static str, f64)>,
+    method: String,
+    path: String,
+}
+
+impl CgiMetrics {
+    /// 計測を開始（プロセスの開始から現在までを`startup`として記録）
+    pub(crate) fn start() -> Self {
+        let (started, started_at) = *PROCESS_START.get_or_init(|| (Instant::now(), SystemTime::now()));
+        let mut metrics = Self {
+            enabled: is_cgi_metrics_enabled(),
+            started,
+            started_at,
+            last: started,
+            phases: Vec::new(),
+            method: String::new(),
+            path: String::new(),
+        };
+        metrics.mark("startup");
+        metrics
+    }
+
+    /// ログに含めるリクエストの情報を設定
+    pub(crate) fn set_request(&mut self, method: &str, path: &str) {
+        self.method = method.to_string();
+        self.path = path.to_string();
+    }
+
+    /// 前回の記録から現在までを段階`phase`の時間として記録
+    pub(crate) fn mark(&mut self, phase: &'static str) {
+        let now = Instant::now();
+        self.phases.push((phase, (now - self.last).as_secs_f64() * 1000.0));
+        self.last = now;
+    }
+
+    /// 計測結果のJSON
+    pub(crate) fn to_json(&self, status: u16) -> Value {
+        let phases: Map<String, Value> = self
+            .phases
+            .iter()
+            .map(|(phase, ms)| (format!("{}_ms", phase), serde_json::json!(round_ms(*ms))))
+            .collect();
+        serde_json::json!({
+            "type": "runbridge.cgi.metrics",
+            "started_at": chrono::DateTime::<chrono::Utc>::from(self.started_at).to_rfc3339(),
+            "method": self.method,
+            "path": self.path,
+            "status": status,
+            "total_ms": round_ms((self.last - self.started).as_secs_f64() * 1000.0),
+            "phases": phases,
+        })
+    }
+
+    /// 有効な場合、計測結果を標準エラー出力に1行で書き出す
+    pub(crate) fn emit(&self, status: u16) {
+        if self.enabled {
+            let _ = writeln!(std::io::stderr().lock(), "{}", self.to_json(status));
+        }
+    }
+}
+
+/// ミリ秒を小数点以下3桁に丸める
+fn round_ms(ms: f64) -> f64 {
+    (ms * 1000.0).round() / 1000.0
+}
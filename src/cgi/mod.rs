@@ -8,9 +8,15 @@ pub mod error_logging;
 pub mod request;
 pub mod response;
 pub mod core;
+pub mod replay;
+pub mod fetch;
+pub mod metrics;
 
 // 互換性維持のためのパブリックAPI再エクスポート
 pub use core::run_cgi;
+pub use replay::ReplayGuard;
+pub use fetch::run_fetch_json;
+pub use metrics::mark_process_start;
 
 #[cfg(test)]
 mod tests;
\ No newline at end of file
@@ -0,0 +1,390 @@
+//! CGI向けのステートレスな再送（リプレイ）防止ノンス
+//!
+//! CGIはリクエストごとにプロセスが終了するため、発行したノンスをメモリに保持できません。
+//! `ReplayGuard`はノンスを暗号化したトークンとして発行し、使用済みノンスの一覧も暗号化した
+//! クッキーとしてクライアントに持たせることで、サーバー側の状態なしに再送を検出します。
+//! フォームのCSRF対策（クッキーとヘッダーのダブルサブミット）や冪等キーの検証に使用します。
+//!
+//! 暗号化はChaCha20-Poly1305（AEAD）で、トークンごとにOSの乱数で生成したnonceを使用し、
+//! バージョンのバイトも認証対象に含めます。鍵は`ReplayGuard::new`に渡した秘密から
+//! HMAC-SHA256で導出します。
+//!
+//! 使用済み一覧はクライアント側に保存されるため、古いクッキーごと再送できる相手
+//! （クッキーを読み書きできる攻撃者）に対してはノンスの有効期限までしか防げません。
+
+use std::fmt;
+use std::time::Duration;
+
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::common::cookie::{Cookie, SameSite};
+use crate::common::csp::random_bytes;
+use crate::common::signed_url::{constant_time_eq, hmac_sha256};
+use crate::common::{Request, Response};
+use crate::error::Error;
+
+/// ノンスを格納するクッキーの既定名
+pub const DEFAULT_NONCE_COOKIE: &str = "rb_nonce";
+
+/// 使用済みノンスの一覧を格納するクッキーの既定名
+pub const DEFAULT_SPENT_COOKIE: &str = "rb_spent";
+
+/// ノンスを送信するリクエストヘッダーの既定名
+pub const DEFAULT_NONCE_HEADER: &str = "x-replay-nonce";
+
+const VERSION: u8 = 2;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+
+/// トークンに暗号化して格納する内容
+#[derive(Serialize, Deserialize)]
+struct NoncePayload {
+    id: String,
+    purpose: String,
+    exp: i64,
+}
+
+/// 使用済みノンスの一覧（IDと有効期限）
+#[derive(Serialize, Deserialize, Default)]
+struct SpentList {
+    spent: Vec<(String, i64)>,
+}
+
+/// 検証済みのノンス
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedNonce {
+    /// ノンスのID
+    pub id: String,
+    /// 発行時に指定した用途
+    pub purpose: String,
+    /// 有効期限（UNIX秒）
+    pub expires_at: i64,
+}
+
+/// 暗号化されたノンスの発行と、使用済み一覧による再送の検出
+#[derive(Clone)]
+pub struct ReplayGuard {
+    key: [u8; 32],
+    ttl: Duration,
+    nonce_cookie: String,
+    spent_cookie: String,
+    header: String,
+    max_spent: usize,
+    double_submit: bool,
+}
+
+impl fmt::Debug for ReplayGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReplayGuard")
+            .field("keys", &"***redacted***")
+            .field("ttl", &self.ttl)
+            .field("nonce_cookie", &self.nonce_cookie)
+            .field("spent_cookie", &self.spent_cookie)
+            .field("header", &self.header)
+            .finish()
+    }
+}
+
+impl ReplayGuard {
+    /// 秘密を指定して作成（32バイト以上のランダムな値を推奨）
+    ///
+    /// 既定値: 有効期限10分、使用済み一覧は最大32件、ダブルサブミットの検証あり
+    pub fn new(secret: impl AsRef<[u8]>) -> Self {
+        let secret = secret.as_ref();
+        Self {
+            key: hmac_sha256(secret, b"runbridge.replay.encryption"),
+            ttl: Duration::from_secs(600),
+            nonce_cookie: DEFAULT_NONCE_COOKIE.to_string(),
+            spent_cookie: DEFAULT_SPENT_COOKIE.to_string(),
+            header: DEFAULT_NONCE_HEADER.to_string(),
+            max_spent: 32,
+            double_submit: true,
+        }
+    }
+
+    /// ノンスの有効期限
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// ノンスと使用済み一覧のクッキー名
+    pub fn cookie_names(mut self, nonce: impl Into<String>, spent: impl Into<String>) -> Self {
+        self.nonce_cookie = nonce.into();
+        self.spent_cookie = spent.into();
+        self
+    }
+
+    /// ノンスを送信するリクエストヘッダー名
+    pub fn header(mut self, name: impl Into<String>) -> Self {
+        self.header = name.into().to_ascii_lowercase();
+        self
+    }
+
+    /// 使用済み一覧に保持する最大件数（超えた場合は古いものから削除）
+    pub fn max_spent(mut self, max: usize) -> Self {
+        self.max_spent = max.max(1);
+        self
+    }
+
+    /// ヘッダーのノンスとクッキーのノンスの一致を要求するかどうか
+    ///
+    /// クッキーを扱わないAPIクライアント向けの冪等キーとして使う場合は無効にします。
+    pub fn double_submit(mut self, enabled: bool) -> Self {
+        self.double_submit = enabled;
+        self
+    }
+
+    /// 用途を指定してノンスを発行
+    pub fn issue(&self, purpose: &str) -> String {
+        self.issue_at(purpose, chrono::Utc::now().timestamp())
+    }
+
+    fn issue_at(&self, purpose: &str, now: i64) -> String {
+        let payload = NoncePayload {
+            id: base64::encode_config(random_bytes(), base64::URL_SAFE_NO_PAD),
+            purpose: purpose.to_string(),
+            exp: now + self.ttl.as_secs() as i64,
+        };
+        // NoncePayloadのシリアライズは失敗しない
+        self.seal(&serde_json::to_vec(&payload).unwrap_or_default())
+    }
+
+    /// ノンスを発行してクッキーに設定し、フォームやヘッダーに埋め込む値を返す
+    pub fn issue_into(&self, res: &mut Response, purpose: &str) -> String {
+        let token = self.issue(purpose);
+        let cookie = Cookie::new(self.nonce_cookie.clone(), token.clone())
+            .with_path("/")
+            .with_max_age(self.ttl)
+            .secure(true)
+            .http_only(true)
+            .with_same_site(SameSite::Strict);
+        res.append_set_cookie(cookie.to_header_value());
+        token
+    }
+
+    /// トークンを復号し、用途と有効期限を検証（使用済みかどうかは確認しない）
+    pub fn verify(&self, token: &str, purpose: &str) -> Result<VerifiedNonce, Error> {
+        self.verify_at(token, purpose, chrono::Utc::now().timestamp())
+    }
+
+    fn verify_at(&self, token: &str, purpose: &str, now: i64) -> Result<VerifiedNonce, Error> {
+        let payload: NoncePayload = self
+            .open(token)
+            .and_then(|plain| serde_json::from_slice(&plain).ok())
+            .ok_or_else(|| Error::AuthorizationError("Invalid replay nonce".to_string()))?;
+        if payload.purpose != purpose {
+            return Err(Error::AuthorizationError("Replay nonce issued for another purpose".to_string()));
+        }
+        if payload.exp <= now {
+            return Err(Error::AuthorizationError("Replay nonce expired".to_string()));
+        }
+        Ok(VerifiedNonce {
+            id: payload.id,
+            purpose: payload.purpose,
+            expires_at: payload.exp,
+        })
+    }
+
+    /// リクエストのノンスを検証（不正・期限切れ・使用済みの場合は`AuthorizationError`（403））
+    ///
+    /// 成功した場合は、レスポンスに`mark_spent`で使用済みとして記録してください。
+    pub fn verify_request(&self, req: &Request, purpose: &str) -> Result<VerifiedNonce, Error> {
+        self.verify_request_at(req, purpose, chrono::Utc::now().timestamp())
+    }
+
+    fn verify_request_at(&self, req: &Request, purpose: &str, now: i64) -> Result<VerifiedNonce, Error> {
+        let token = req
+            .headers
+            .get(&self.header)
+            .ok_or_else(|| Error::AuthorizationError("Missing replay nonce".to_string()))?;
+        let cookies = req.cookies();
+        if self.double_submit {
+            let cookie = cookies.get(&self.nonce_cookie).unwrap_or("");
+            if !constant_time_eq(cookie.as_bytes(), token.as_bytes()) {
+                return Err(Error::AuthorizationError("Replay nonce does not match cookie".to_string()));
+            }
+        }
+
+        let nonce = self.verify_at(token, purpose, now)?;
+        if self.spent_list(cookies.get(&self.spent_cookie)).spent.iter().any(|(id, _)| *id == nonce.id) {
+            warn!("Replayed nonce rejected for purpose '{}'", purpose);
+            return Err(Error::AuthorizationError("Replay nonce already used".to_string()));
+        }
+        Ok(nonce)
+    }
+
+    /// ノンスを使用済み一覧に追加してレスポンスのクッキーを更新
+    pub fn mark_spent(&self, req: &Request, nonce: &VerifiedNonce, res: &mut Response) {
+        self.mark_spent_at(req, nonce, res, chrono::Utc::now().timestamp());
+    }
+
+    fn mark_spent_at(&self, req: &Request, nonce: &VerifiedNonce, res: &mut Response, now: i64) {
+        let mut list = self.spent_list(req.cookies().get(&self.spent_cookie));
+        list.spent.retain(|(id, exp)| *exp > now && *id != nonce.id);
+        list.spent.push((nonce.id.clone(), nonce.expires_at));
+        let excess = list.spent.len().saturating_sub(self.max_spent);
+        list.spent.drain(..excess);
+
+        // 一覧の項目は最長でもノンスの有効期限まで必要
+        let max_age = list.spent.iter().map(|(_, exp)| exp - now).max().unwrap_or(0).max(0);
+        let value = self.seal(&serde_json::to_vec(&list).unwrap_or_default());
+        let cookie = Cookie::new(self.spent_cookie.clone(), value)
+            .with_path("/")
+            .with_max_age(Duration::from_secs(max_age as u64))
+            .secure(true)
+            .http_only(true)
+            .with_same_site(SameSite::Strict);
+        res.append_set_cookie(cookie.to_header_value());
+    }
+
+    /// 使用済み一覧のクッキーを復号（改ざん・破損している場合は空として扱う）
+    fn spent_list(&self, value: Option<&str>) -> SpentList {
+        match value.map(|v| self.open(v).and_then(|plain| serde_json::from_slice(&plain).ok())) {
+            Some(Some(list)) => list,
+            Some(None) => {
+                warn!("Ignoring invalid spent nonce cookie '{}'", self.spent_cookie);
+                SpentList::default()
+            }
+            None => SpentList::default(),
+        }
+    }
+
+    /// 暗号化して認証タグを付与（`VERSION | nonce | 暗号文 | タグ`をBase64URLで返す）
+    fn seal(&self, plain: &[u8]) -> String {
+        let mut nonce = [0u8; NONCE_LEN];
+        getrandom::getrandom(&mut nonce).expect("OS random number generator is unavailable");
+        let sealed = self
+            .cipher()
+            .encrypt(Nonce::from_slice(&nonce), Payload { msg: plain, aad: &[VERSION] })
+            .expect("ChaCha20-Poly1305 encryption does not fail for in-memory payloads");
+
+        let mut data = Vec::with_capacity(1 + NONCE_LEN + sealed.len());
+        data.push(VERSION);
+        data.extend_from_slice(&nonce);
+        data.extend_from_slice(&sealed);
+        base64::encode_config(data, base64::URL_SAFE_NO_PAD)
+    }
+
+    /// タグを検証して復号（失敗した場合はNone）
+    fn open(&self, token: &str) -> Option<Vec<u8>> {
+        let data = base64::decode_config(token, base64::URL_SAFE_NO_PAD).ok()?;
+        if data.len() < 1 + NONCE_LEN + TAG_LEN || data[0] != VERSION {
+            return None;
+        }
+        let (nonce, sealed) = data[1..].split_at(NONCE_LEN);
+        self.cipher()
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: sealed, aad: &[VERSION] })
+            .ok()
+    }
+
+    fn cipher(&self) -> ChaCha20Poly1305 {
+        ChaCha20Poly1305::new(Key::from_slice(&self.key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Method;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn guard() -> ReplayGuard {
+        ReplayGuard::new("0123456789abcdef0123456789abcdef").ttl(Duration::from_secs(60))
+    }
+
+    /// Set-Cookieヘッダーから`name=value`を取り出してCookieヘッダーの形にする
+    fn cookie_pairs(res: &Response) -> String {
+        res.set_cookie_values()
+            .iter()
+            .filter_map(|c| c.split(';').next())
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    fn request(token: &str, cookie: &str) -> Request {
+        Request::new(Method::POST, "/transfer".to_string())
+            .with_header(DEFAULT_NONCE_HEADER, token)
+            .with_header("Cookie", cookie)
+    }
+
+    #[test]
+    fn test_token_is_encrypted_and_authenticated() {
+        let guard = guard();
+        let token = guard.issue_at("transfer", NOW);
+        let raw = base64::decode_config(&token, base64::URL_SAFE_NO_PAD).unwrap();
+        assert!(!String::from_utf8_lossy(&raw).contains("transfer"));
+
+        let nonce = guard.verify_at(&token, "transfer", NOW + 59).unwrap();
+        assert_eq!(nonce.expires_at, NOW + 60);
+        assert_eq!(guard.verify_at(&token, "transfer", NOW + 60).unwrap_err().status_code(), 403);
+        assert!(guard.verify_at(&token, "delete", NOW).is_err());
+
+        // 1バイトでも改ざんされたトークンや別の鍵のトークンは拒否する
+        let mut tampered = raw.clone();
+        tampered[NONCE_LEN + 2] ^= 1;
+        let tampered = base64::encode_config(tampered, base64::URL_SAFE_NO_PAD);
+        assert!(guard.verify_at(&tampered, "transfer", NOW).is_err());
+        assert!(ReplayGuard::new("other").verify_at(&token, "transfer", NOW).is_err());
+        assert!(guard.verify_at("not a token", "transfer", NOW).is_err());
+    }
+
+    #[test]
+    fn test_request_replay_is_rejected() {
+        let guard = guard();
+        let mut page = Response::ok();
+        let token = guard.issue_into(&mut page, "transfer");
+        assert!(page.set_cookie_values()[0].contains("HttpOnly"));
+        let cookie = cookie_pairs(&page);
+
+        // クッキーとヘッダーが一致しない場合は拒否
+        assert!(guard.verify_request(&request(&token, "rb_nonce=other"), "transfer").is_err());
+        assert!(guard
+            .verify_request(&Request::new(Method::POST, "/transfer".to_string()), "transfer")
+            .is_err());
+
+        let req = request(&token, &cookie);
+        let nonce = guard.verify_request_at(&req, "transfer", NOW).unwrap();
+        let mut res = Response::ok();
+        guard.mark_spent_at(&req, &nonce, &mut res, NOW);
+
+        // 使用済み一覧のクッキーを持つ再送は拒否
+        let replay = request(&token, &format!("{}; {}", cookie, cookie_pairs(&res)));
+        let err = guard.verify_request_at(&replay, "transfer", NOW).unwrap_err();
+        assert_eq!(err.to_string(), "Authorization error: Replay nonce already used");
+
+        // 新しいノンスは通る
+        let mut page = Response::ok();
+        let token = guard.issue_into(&mut page, "transfer");
+        let req = request(&token, &format!("{}; {}", cookie_pairs(&page), cookie_pairs(&res)));
+        assert!(guard.verify_request(&req, "transfer").is_ok());
+    }
+
+    #[test]
+    fn test_spent_list_is_bounded_and_pruned() {
+        let guard = guard().max_spent(2).double_submit(false);
+        let mut spent_cookie = String::new();
+        for i in 0..3 {
+            let req = request(&guard.issue_at("idem", NOW + i), &spent_cookie);
+            let nonce = guard.verify_request_at(&req, "idem", NOW + i).unwrap();
+            let mut res = Response::ok();
+            guard.mark_spent_at(&req, &nonce, &mut res, NOW + i);
+            spent_cookie = cookie_pairs(&res);
+        }
+        let value = spent_cookie.split_once('=').unwrap().1;
+        assert_eq!(guard.spent_list(Some(value)).spent.len(), 2);
+
+        // 期限切れの項目は削除される
+        let req = request(&guard.issue_at("idem", NOW + 100), &spent_cookie);
+        let nonce = guard.verify_request_at(&req, "idem", NOW + 100).unwrap();
+        let mut res = Response::ok();
+        guard.mark_spent_at(&req, &nonce, &mut res, NOW + 100);
+        let value = cookie_pairs(&res).split_once('=').unwrap().1.to_string();
+        assert_eq!(guard.spent_list(Some(&value)).spent.len(), 1);
+        assert!(guard.spent_list(Some("garbage")).spent.is_empty());
+    }
+}
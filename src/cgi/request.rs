@@ -4,7 +4,8 @@ use std::collections::HashMap;
 use std::env;
 use std::io::{self, Read};
 
-use crate::common::get_max_body_size;
+use crate::common::{get_max_body_size, read_body_limited};
+use crate::common::origin::RequestOrigin;
 use crate::error::Error;
 use super::validation::{is_valid_header_name, is_valid_header_value};
 
@@ -55,30 +56,66 @@ pub fn get_cgi_headers() -> HashMap<String, String> {
     headers
 }
 
+/// 環境変数（HTTPS / REQUEST_SCHEME / SERVER_PORT / SERVER_NAME）からスキームとホストを取得する
+pub fn get_cgi_origin() -> RequestOrigin {
+    let port = env::var("SERVER_PORT").ok();
+    let https = env::var("HTTPS")
+        .map(|v| v.eq_ignore_ascii_case("on") || v == "1")
+        .unwrap_or(false)
+        || env::var("REQUEST_SCHEME")
+            .map(|v| v.eq_ignore_ascii_case("https"))
+            .unwrap_or(false)
+        || port.as_deref() == Some("443");
+    let scheme = if https { "https" } else { "http" };
+
+    // 既定ポート以外の場合のみホストにポートを付与
+    let host = env::var("SERVER_NAME").ok().filter(|n| !n.is_empty()).map(|name| {
+        let default_port = if https { "443" } else { "80" };
+        match port.as_deref() {
+            Some(p) if !p.is_empty() && p != default_port => format!("{}:{}", name, p),
+            _ => name,
+        }
+    });
+
+    RequestOrigin {
+        scheme: scheme.to_string(),
+        host,
+    }
+}
+
 /// リクエストボディを標準入力から読み込む
+///
+/// `CONTENT_LENGTH`が上限を超える場合は標準入力を読まずに`PayloadTooLarge`を返します。
+/// `CONTENT_LENGTH`が無くチャンク転送（`HTTP_TRANSFER_ENCODING: chunked`）の場合は終端まで逐次読み込み、
+/// 上限を超えた時点で読み込みを中断します。
 pub fn read_request_body() -> Result<Option<Vec<u8>>, Error> {
-    if let Ok(content_length_str) = env::var("CONTENT_LENGTH") {
-        if let Ok(content_length) = content_length_str.parse::<usize>() {
-            if content_length > 0 {
-                let max_body_size = get_max_body_size();
-                if content_length > max_body_size {
-                    return Err(Error::PayloadTooLarge(
-                        format!(
-                            "Request body size {} bytes exceeds maximum allowed size {} bytes",
-                            content_length,
-                            max_body_size
-                        )
-                    ));
-                }
-                
-                let mut buffer = vec![0u8; content_length];
-                io::stdin().read_exact(&mut buffer).map_err(|e| {
-                    Error::InvalidRequestBody(format!("Failed to read request body: {}", e))
-                })?;
-                return Ok(Some(buffer));
-            }
+    read_request_body_with_limit(get_max_body_size())
+}
+
+/// 上限（バイト）を指定してリクエストボディを標準入力から読み込む（ルート固有の上限の適用に使用）
+pub fn read_request_body_with_limit(max_body_size: usize) -> Result<Option<Vec<u8>>, Error> {
+    let content_length = env::var("CONTENT_LENGTH").ok();
+    let chunked = env::var("HTTP_TRANSFER_ENCODING")
+        .map(|v| v.to_ascii_lowercase().contains("chunked"))
+        .unwrap_or(false);
+    read_request_body_from(io::stdin().lock(), content_length.as_deref(), chunked, max_body_size)
+}
+
+/// 任意のReaderからリクエストボディを読み込む（テスト容易化のため分離）
+pub(crate) fn read_request_body_from<R: Read>(
+    reader: R,
+    content_length: Option<&str>,
+    chunked: bool,
+    max_body_size: usize,
+) -> Result<Option<Vec<u8>>, Error> {
+    match content_length.map(|v| v.trim().parse::<usize>()) {
+        Some(Ok(0)) => Ok(None),
+        Some(Ok(length)) => read_body_limited(reader, Some(length), max_body_size).map(Some),
+        // CGIの仕様上、CONTENT_LENGTHが無い場合はボディが無いものとして扱う
+        None if chunked => {
+            let body = read_body_limited(reader, None, max_body_size)?;
+            Ok(Some(body).filter(|b| !b.is_empty()))
         }
+        _ => Ok(None),
     }
-    
-    Ok(None)
-}
\ No newline at end of file
+}
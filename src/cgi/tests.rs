@@ -3,10 +3,11 @@
 use std::io::Write;
 
 use crate::common::{parse_query_string, get_max_body_size, Response};
-use super::request::get_cgi_headers;
+use super::request::{get_cgi_headers, get_cgi_origin, read_request_body_from};
 use super::validation::{is_valid_header_name, is_valid_header_value};
-use super::response::{write_response_to, split_set_cookie_header};
+use super::response::{write_head_response_to, write_response_to, split_set_cookie_header};
 use super::error_logging::{redact_value_for_log, is_sensitive_key_like, redact_query_string, gather_cgi_panic_context};
+use super::metrics::CgiMetrics;
 
 #[test]
 fn test_parse_query_string() {
@@ -92,6 +93,33 @@ fn test_get_max_body_size_invalid_env() {
     });
 }
 
+#[test]
+fn test_get_cgi_origin() {
+    use temp_env::with_vars;
+
+    with_vars([
+        ("HTTPS", Some("on")),
+        ("REQUEST_SCHEME", None),
+        ("SERVER_NAME", Some("example.com")),
+        ("SERVER_PORT", Some("443")),
+    ], || {
+        let origin = get_cgi_origin();
+        assert_eq!(origin.scheme, "https");
+        assert_eq!(origin.host.as_deref(), Some("example.com"));
+    });
+
+    with_vars([
+        ("HTTPS", None),
+        ("REQUEST_SCHEME", None),
+        ("SERVER_NAME", Some("localhost")),
+        ("SERVER_PORT", Some("8080")),
+    ], || {
+        let origin = get_cgi_origin();
+        assert_eq!(origin.scheme, "http");
+        assert_eq!(origin.host.as_deref(), Some("localhost:8080"));
+    });
+}
+
 #[test]
 fn test_is_valid_header_name() {
     // 有効なヘッダー名
@@ -177,6 +205,68 @@ fn test_write_response_multiple_set_cookie_lines() {
     assert!(out.ends_with("\r\nok"));
 }
 
+#[test]
+fn test_write_response_status_line_uses_canonical_reason() {
+    let cases = [
+        (503, "Status: 503 Service Unavailable\r\n"),
+        (418, "Status: 418 I'm a teapot\r\n"),
+        (299, "Status: 299 Error\r\n"),
+    ];
+    for (status, line) in cases {
+        let mut buf: Vec<u8> = Vec::new();
+        write_response_to(Response::new(status), &mut buf).expect("write_response_to failed");
+        let out = String::from_utf8(buf).expect("utf8");
+        assert!(out.starts_with(line), "unexpected status line: {}", out);
+    }
+}
+
+#[test]
+fn test_write_response_ignores_reserved_headers() {
+    let response = Response::new(200)
+        .with_header("Transfer-Encoding", "chunked")
+        .with_header("Connection", "close")
+        .with_header("Content-Length", "999")
+        .with_body(b"ok".to_vec());
+
+    let mut buf: Vec<u8> = Vec::new();
+    write_response_to(response, &mut buf).expect("write_response_to failed");
+    let out = String::from_utf8(buf).expect("utf8");
+
+    assert!(!out.contains("Transfer-Encoding"));
+    assert!(!out.contains("Connection"));
+    assert!(!out.contains("Content-Length: 999"));
+    assert!(out.contains("Content-Length: 2\r"));
+}
+
+#[test]
+fn test_write_head_response_omits_body() {
+    let response = Response::new(200)
+        .with_header("Content-Type", "text/plain")
+        .with_body(b"hello".to_vec());
+
+    let mut buf: Vec<u8> = Vec::new();
+    write_head_response_to(response, &mut buf).expect("write_head_response_to failed");
+    let out = String::from_utf8(buf).expect("utf8");
+
+    assert!(out.contains("Content-Length: 5\r"));
+    assert!(out.ends_with("\r\n\r\n"));
+}
+
+#[test]
+fn test_write_head_response_after_strip() {
+    // process_requestでボディを除去済みのHEADレスポンスはGETと同じ長さを返す
+    let mut response = Response::new(200).with_body(b"hello".to_vec());
+    response.strip_body_for_head();
+
+    let mut buf: Vec<u8> = Vec::new();
+    write_head_response_to(response, &mut buf).expect("write_head_response_to failed");
+    let out = String::from_utf8(buf).expect("utf8");
+
+    assert_eq!(out.matches("Content-Length").count(), 1);
+    assert!(out.contains("Content-Length: 5\r"));
+    assert!(out.ends_with("\r\n\r\n"));
+}
+
 #[test]
 fn test_redact_value_for_log() {
     // 通常の値は変更されない
@@ -358,4 +448,44 @@ fn test_log_error_to_file() {
     
     // テスト後のクリーンアップ
     let _ = fs::remove_file(test_file);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_cgi_metrics_json() {
+    let mut metrics = CgiMetrics::start();
+    metrics.set_request("GET", "/hello");
+    for phase in ["env_parse", "body_read", "dispatch", "write"] {
+        metrics.mark(phase);
+    }
+
+    let json = metrics.to_json(200);
+    assert_eq!(json["type"], "runbridge.cgi.metrics");
+    assert_eq!(json["method"], "GET");
+    assert_eq!(json["path"], "/hello");
+    assert_eq!(json["status"], 200);
+    assert!(json["total_ms"].as_f64().unwrap() >= 0.0);
+    let phases = json["phases"].as_object().unwrap();
+    for key in ["startup_ms", "env_parse_ms", "body_read_ms", "dispatch_ms", "write_ms"] {
+        assert!(phases.contains_key(key), "{}", key);
+    }
+}
+
+#[test]
+fn test_read_request_body_from_stdin() {
+    let stdin = vec![b'a'; 4096];
+    assert_eq!(read_request_body_from(&stdin[..], Some("3"), false, 1024).unwrap(), Some(b"aaa".to_vec()));
+    assert_eq!(read_request_body_from(&stdin[..], Some("0"), false, 1024).unwrap(), None);
+    assert_eq!(read_request_body_from(&stdin[..], None, false, 1024).unwrap(), None);
+    assert_eq!(read_request_body_from(&stdin[..], Some("abc"), false, 1024).unwrap(), None);
+
+    // 宣言値が上限を超える・チャンク転送で上限を超える場合は413
+    let err = read_request_body_from(&stdin[..], Some("2048"), false, 1024).unwrap_err();
+    assert_eq!(err.status_code(), 413);
+    let err = read_request_body_from(&stdin[..], None, true, 1024).unwrap_err();
+    assert_eq!(err.status_code(), 413);
+    assert_eq!(read_request_body_from(&stdin[..], None, true, 4096).unwrap().map(|b| b.len()), Some(4096));
+
+    // 宣言より短いボディは400
+    let err = read_request_body_from(&stdin[..10], Some("20"), false, 1024).unwrap_err();
+    assert_eq!(err.status_code(), 400);
+}
@@ -4,30 +4,37 @@
 
 use env_logger::Env;
 use log::{error, info};
-use runbridge::{cgi, RunBridge};
+use runbridge::handler::DebugEchoHandler;
+use runbridge::RunBridge;
 
 // サンプルハンドラの実装
 mod sample_handler;
 
 #[tokio::main]
 async fn main() {
+    // プロセスの開始時刻を記録（RUNBRIDGE_CGI_METRICS=1 の場合に処理時間の内訳を出力）
+    runbridge::cgi::mark_process_start();
+
     // ログ設定（標準エラー出力に出力）
     // CGIでは標準出力がHTTPレスポンスとなるため、ログは標準エラー出力に出力する
     env_logger::Builder::from_env(Env::default().default_filter_or("info"))
         .target(env_logger::Target::Stderr)
         .init();
+    // パニックを構造化ログとして記録（リクエストID付き）
+    runbridge::install_panic_hook();
     
     info!("Starting RunBridge CGI application");
     
     // アプリケーションの構築
     let app = RunBridge::builder()
         .handler(sample_handler::HelloHandler::new())
-        .handler(sample_handler::EchoHandler::new())
+        // リクエスト内容を返すデバッグ用エンドポイント（RUNBRIDGE_DEBUG_ECHO=1 の場合のみ有効）
+        .handler(DebugEchoHandler::new("/echo"))
         .handler(sample_handler::PanicHandler::new())
         .build();
     
     // CGI処理の実行
-    if let Err(err) = cgi::run_cgi(app).await {
+    if let Err(err) = runbridge::serve(app).await {
         error!("Error running CGI application: {:?}", err);
         std::process::exit(1);
     }
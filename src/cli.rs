@@ -0,0 +1,121 @@
+//! ルート一覧・OpenAPIを出力するコマンドライン補助（feature `cli`）
+//!
+//! デプロイせずにCIでのルートの差分確認やドキュメント生成を行うためのものです。
+//! `main`でアプリを構築した後、実行環境を起動する前に呼び出します。
+//!
+//! ```no_run
+//! # async fn run() {
+//! let app = runbridge::RunBridge::builder().build();
+//! // `--dump-routes` / `--dump-openapi`付きで起動された場合は出力して終了する
+//! runbridge::cli::exit_if_dump_requested(&app, env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+//! runbridge::serve(app).await.unwrap();
+//! # }
+//! ```
+
+use serde_json::{json, Value};
+
+use crate::common::openapi::documented_routes;
+use crate::common::{OpenApiInfo, RouteInfo};
+use crate::RunBridge;
+
+/// ルート一覧を出力する引数
+pub const DUMP_ROUTES_FLAG: &str = "--dump-routes";
+
+/// OpenAPIドキュメントを出力する引数
+pub const DUMP_OPENAPI_FLAG: &str = "--dump-openapi";
+
+/// 出力の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpCommand {
+    /// ルート一覧（JSON）
+    Routes,
+    /// OpenAPI 3.0ドキュメント（JSON）
+    OpenApi,
+}
+
+/// コマンドライン引数から出力の種類を判定（最初に見つかった引数を優先）
+pub fn parse_args<I, S>(args: I) -> Option<DumpCommand>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    args.into_iter().find_map(|arg| match arg.as_ref() {
+        DUMP_ROUTES_FLAG => Some(DumpCommand::Routes),
+        DUMP_OPENAPI_FLAG => Some(DumpCommand::OpenApi),
+        _ => None,
+    })
+}
+
+/// 出力内容を作成（整形済みJSON）
+pub fn render(app: &RunBridge, command: DumpCommand, title: &str, version: &str) -> String {
+    let value = match command {
+        DumpCommand::Routes => json!(app.routes()),
+        DumpCommand::OpenApi => OpenApiInfo::new(title, version).document(documented_routes(&app.handlers)),
+    };
+    // Valueの整形出力は失敗しない
+    serde_json::to_string_pretty(&value).unwrap_or_default()
+}
+
+/// 引数に`--dump-routes` / `--dump-openapi`が含まれる場合は標準出力に書き出してプロセスを終了する
+///
+/// 含まれない場合は何もしません。`title`・`version`はOpenAPIの`info`に使用します。
+pub fn exit_if_dump_requested(app: &RunBridge, title: &str, version: &str) {
+    if let Some(command) = parse_args(std::env::args().skip(1)) {
+        println!("{}", render(app, command, title, version));
+        std::process::exit(0);
+    }
+}
+
+/// ルート一覧からOpenAPI 3.0ドキュメントを作成（`HandlerExt::openapi`のメタデータは含まれません）
+///
+/// パスはパターンの名前付きキャプチャを`{name}`に置き換えたものです。テンプレートに変換できない
+/// パターンや、メソッドが不明なルートは含めずに警告を出力します。
+pub fn openapi_document(routes: &[RouteInfo], title: &str, version: &str) -> Value {
+    OpenApiInfo::new(title, version).document(routes.iter().map(|route| (route.clone(), None)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{Method, OperationDoc, Request};
+    use crate::error::Error;
+    use crate::handler::{get, post, HandlerExt};
+
+    fn ok(_req: Request) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn app() -> RunBridge {
+        RunBridge::builder()
+            .handler(get("/items/{id}", ok).name("get_item").openapi(OperationDoc::new().summary("商品を取得")))
+            .handler(post("^/items$", |_req: Request, _body: serde_json::Value| Ok(())))
+            .handler(get("^/files/.*$", ok))
+            .build()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse_args(["--verbose", "--dump-openapi"]), Some(DumpCommand::OpenApi));
+        assert_eq!(parse_args(["--dump-routes", "--dump-openapi"]), Some(DumpCommand::Routes));
+        assert_eq!(parse_args(Vec::<String>::new()), None);
+    }
+
+    #[test]
+    fn test_render_routes_and_openapi() {
+        let app = app();
+        let routes: Value = serde_json::from_str(&render(&app, DumpCommand::Routes, "t", "1")).unwrap();
+        assert_eq!(routes.as_array().unwrap().len(), 3);
+        assert!(routes.as_array().unwrap().iter().any(|r| r["name"] == "get_item" && r["methods"] == json!(["GET"])));
+
+        let doc: Value = serde_json::from_str(&render(&app, DumpCommand::OpenApi, "demo", "1.2.0")).unwrap();
+        assert_eq!(doc["info"], json!({ "title": "demo", "version": "1.2.0" }));
+        let get_item = &doc["paths"]["/items/{id}"]["get"];
+        assert_eq!(get_item["operationId"], "get_item");
+        assert_eq!(get_item["summary"], "商品を取得");
+        assert_eq!(get_item["parameters"][0]["name"], "id");
+        assert!(doc["paths"]["/items"]["post"].is_object());
+        // 正規表現のままのパターンは出力しない
+        assert_eq!(doc["paths"].as_object().unwrap().len(), 2);
+        assert_eq!(app.routes().iter().find(|r| r.pattern.starts_with("^/files")).unwrap().methods, vec![Method::GET]);
+    }
+}
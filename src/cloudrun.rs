@@ -1,18 +1,60 @@
 //! Google Cloud Run向けの実装
 
 use std::collections::HashMap;
+use std::net::TcpListener;
 use std::sync::Arc;
-use log::{error, info, warn};
-use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
-use actix_web::http::header::HeaderMap;
-use actix_web::web::Bytes;
+use std::time::Instant;
+use log::{debug, error, info, warn};
+use actix_http::{HttpMessage, HttpService};
+use actix_server::Server;
+use actix_service::{fn_service, map_config};
+use actix_web::body::BoxBody;
+use actix_web::dev::AppConfig;
+use actix_web::{web, App, HttpRequest, HttpResponse};
+use actix_web::http::header::{self, HeaderMap};
+use actix_web::web::{Bytes, BytesMut};
+use futures::StreamExt;
 
-use crate::common::{Method, Request, Response, parse_query_string, get_max_body_size};
+use crate::common::{Method, Request, Response, check_method, parse_query_string_limited};
+use crate::common::body_stream::{body_limit_exceeded, check_declared_length};
+use crate::common::origin::{is_forwarded_proto_trusted, RequestOrigin};
+use crate::common::utils::get_shutdown_timeout;
+use crate::common::sse::{get_sse_keep_alive_interval, with_keep_alive};
+use crate::common::deadline::{deadline_from_env, with_deadline};
+use crate::common::request_id::{generate_request_id, sanitize_request_id, with_request_id};
+use crate::error::Error as AppError;
 use crate::RunBridge;
 
+/// レスポンスサイズ警告の閾値（バイト）を取得する
+/// 優先順位: 環境変数 `RUNBRIDGE_RESPONSE_SIZE_WARN_THRESHOLD` -> デフォルト 1MB
+pub fn get_response_size_warn_threshold() -> usize {
+    const DEFAULT_THRESHOLD: usize = 1024 * 1024; // 1MB
+    std::env::var("RUNBRIDGE_RESPONSE_SIZE_WARN_THRESHOLD")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_THRESHOLD)
+}
+
+/// レスポンスサイズを記録し、閾値を超えた場合はルート名付きで警告する
+/// 閾値を超えた場合はtrueを返す
+fn record_response_size(route: &str, method: &Method, path: &str, size: usize, threshold: usize) -> bool {
+    debug!(
+        "Response size: {} bytes for {} {} (route: {})",
+        size, method, path, route
+    );
+    if size > threshold {
+        warn!(
+            "Large response body: {} bytes exceeds warning threshold {} bytes for {} {} (route: {}). Consider pagination or streaming.",
+            size, threshold, method, path, route
+        );
+        return true;
+    }
+    false
+}
+
 /// actix-webのHeaderMapから共通形式のヘッダーに変換
 fn convert_headers(headers: &HeaderMap) -> HashMap<String, String> {
-    let mut result = HashMap::new();
+    let mut result = HashMap::with_capacity(headers.len());
     
     for (key, value) in headers.iter() {
         if let Ok(value_str) = value.to_str() {
@@ -24,29 +66,19 @@ fn convert_headers(headers: &HeaderMap) -> HashMap<String, String> {
     result
 }
 
-/// actix-webのリクエストから共通形式のRequestに変換
+/// actix-webのリクエストから共通形式のRequestに変換（メソッドは検査済みのものを使用）
 async fn convert_request(
     req: &HttpRequest,
+    method: Method,
     path: String,
     body: Option<Bytes>,
-) -> Request {
-    // HTTPメソッドの取得
-    let method = match req.method().as_str() {
-        "GET" => Method::GET,
-        "POST" => Method::POST,
-        "PUT" => Method::PUT,
-        "DELETE" => Method::DELETE,
-        "PATCH" => Method::PATCH,
-        "HEAD" => Method::HEAD,
-        "OPTIONS" => Method::OPTIONS,
-        _ => Method::GET,
-    };
-
+    max_body_size: usize,
+) -> Result<Request, AppError> {
     // ヘッダーの変換
     let headers = convert_headers(req.headers());
 
-    // クエリパラメータの取得（URLデコード対応）
-    let query_params = parse_query_string(req.query_string());
+    // クエリパラメータの取得（URLデコード対応、長さ・個数の上限を検査）
+    let query_params = parse_query_string_limited(req.query_string())?;
 
     // リクエストボディの処理
     let body = body.map(|b| b.to_vec());
@@ -55,17 +87,62 @@ async fn convert_request(
     request.query_params = query_params;
     request.headers = headers;
     request.body = body;
+
+    // 接続情報からスキームとホストを設定
+    request.set_origin(request_origin(req));
     
-    // gzipボディを解凍（必要な場合のみ）
-    if let Err(e) = request.decompress_gzip_body() {
+    // gzipボディを解凍（必要な場合のみ、解凍後のサイズもルートの上限で制限）
+    if let Err(e) = request.decompress_gzip_body_with_limit(max_body_size) {
         warn!("Failed to decompress gzip body in Cloud Run: {}", e);
+        return Err(e);
     }
     
-    request
+    Ok(request)
+}
+
+/// 接続のスキームとホストを取得
+///
+/// `connection_info()`はクライアントが任意に指定できる`X-Forwarded-*`・`Forwarded`を解決に使うため使用しない。
+/// スキームは`RUNBRIDGE_TRUST_FORWARDED_PROTO`が有効な場合のみ転送ヘッダーを反映し、ホストは`Host`ヘッダーから取得する。
+fn request_origin(req: &HttpRequest) -> RequestOrigin {
+    let scheme = if is_forwarded_proto_trusted() {
+        req.connection_info().scheme().to_string()
+    } else if let Some(scheme) = req.uri().scheme_str() {
+        scheme.to_string()
+    } else if req.app_config().secure() {
+        "https".to_string()
+    } else {
+        "http".to_string()
+    };
+    let host = req
+        .headers()
+        .get(header::HOST)
+        .and_then(|h| h.to_str().ok())
+        .map(str::to_string)
+        .or_else(|| req.uri().authority().map(|a| a.to_string()));
+    RequestOrigin { scheme, host }
+}
+
+/// ハンドラーが不正なステータスコード（100〜599以外）を返した場合、成功扱いにせず500に差し替える
+/// デバッグビルドではアサーションで即座に検出する
+fn guard_status(response: Response, route: &str) -> Response {
+    if (100..=599).contains(&response.status) {
+        return response;
+    }
+    error!(
+        "Invalid HTTP status {} returned by handler (route: {}), responding with 500",
+        response.status, route
+    );
+    debug_assert!(
+        false,
+        "Invalid HTTP status {} returned by handler (route: {})",
+        response.status, route
+    );
+    Response::error(500)
 }
 
 /// 共通形式のResponseからactix-webのHttpResponseに変換
-fn convert_to_http_response(response: Response) -> HttpResponse {
+fn convert_to_http_response(mut response: Response) -> HttpResponse {
     let mut builder = match response.status {
         200 => HttpResponse::Ok(),
         201 => HttpResponse::Created(),
@@ -75,14 +152,32 @@ fn convert_to_http_response(response: Response) -> HttpResponse {
         403 => HttpResponse::Forbidden(),
         404 => HttpResponse::NotFound(),
         500 => HttpResponse::InternalServerError(),
-        _ => HttpResponse::build(actix_web::http::StatusCode::from_u16(response.status).unwrap_or(actix_web::http::StatusCode::OK)),
+        _ => HttpResponse::build(
+            actix_web::http::StatusCode::from_u16(response.status)
+                .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR),
+        ),
     };
 
-    // ヘッダーの設定
+    let events = response.take_event_stream();
+
+    // ヘッダーの設定（Set-Cookieは連結された値を分割して1つずつ送る）
+    for cookie in response.set_cookie_values() {
+        builder.append_header(("Set-Cookie", cookie));
+    }
     for (key, value) in response.headers {
+        if key.eq_ignore_ascii_case("set-cookie") {
+            continue;
+        }
         builder.insert_header((key, value));
     }
 
+    // Server-Sent Eventsは逐次送信し、イベントが途切れている間はキープアライブを送る
+    if let Some(events) = events {
+        let chunks = with_keep_alive(events, get_sse_keep_alive_interval())
+            .map(|chunk| Ok::<_, std::convert::Infallible>(Bytes::from(chunk)));
+        return builder.streaming(chunks);
+    }
+
     // ボディの設定
     if let Some(body) = response.body {
         builder.body(body)
@@ -91,113 +186,446 @@ fn convert_to_http_response(response: Response) -> HttpResponse {
     }
 }
 
+/// リクエストボディを逐次受信する（上限を超えた時点で残りを受信せずに`PayloadTooLarge`を返す）
+///
+/// `Content-Length`が上限を超える場合は1バイトも受信せずに拒否します。チャンク転送のように
+/// 長さが分からない場合も、受信済みのサイズが上限を超えた時点で中断します。
+async fn read_payload(req: &HttpRequest, mut payload: web::Payload, limit: usize) -> Result<Bytes, AppError> {
+    let declared = req
+        .headers()
+        .get(actix_web::http::header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<usize>().ok());
+    check_declared_length(declared, limit)?;
+
+    let mut body = BytesMut::with_capacity(declared.unwrap_or(0).min(64 * 1024));
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk.map_err(|e| AppError::InvalidRequestBody(format!("Failed to read request body: {}", e)))?;
+        if body.len() + chunk.len() > limit {
+            return Err(body_limit_exceeded(limit));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body.freeze())
+}
+
+/// ボディを受信してからRunBridgeアプリケーションで処理する
+///
+/// ボディの上限は一致するルートの設定（`HandlerExt::max_body_size`）、無い場合は全体設定に従います。
+async fn handle_request_with_body(
+    req: HttpRequest,
+    payload: web::Payload,
+    app: web::Data<Arc<RunBridge>>,
+) -> HttpResponse {
+    let started = Instant::now();
+    let limit = match check_method(req.method().as_str()) {
+        Ok(method) => app.max_body_size_for(req.path(), &method),
+        // 許可されていないメソッド（TRACE、拡張メソッド等）はボディを受信せずにprocess_requestで405/501を返す
+        Err(_) => return handle_request(req, None, app).await,
+    };
+    match read_payload(&req, payload, limit).await {
+        Ok(body) => handle_request(req, Some(body), app).await,
+        Err(e) => {
+            warn!("Rejected request body for {} {}: {}", req.method(), req.path(), e);
+            let res = Response::from_error(&e);
+            app.log_rejected(started, req.method().as_str(), req.path(), &res);
+            convert_to_http_response(res)
+        }
+    }
+}
+
 /// RunBridgeアプリケーションをハンドリングするactix-web用ハンドラー
 async fn handle_request(
     req: HttpRequest, 
     body: Option<Bytes>,
     app: web::Data<Arc<RunBridge>>,
 ) -> HttpResponse {
+    // リクエストIDをタスクローカルに設定して処理（パニックフックのログで参照）
+    let request_id = ["x-request-id", "x-cloud-trace-context"]
+        .iter()
+        .filter_map(|name| req.headers().get(*name))
+        .filter_map(|v| v.to_str().ok())
+        .find_map(sanitize_request_id)
+        .unwrap_or_else(generate_request_id);
+    // `RUNBRIDGE_REQUEST_TIMEOUT_SECS`が設定されている場合は処理期限も設定
+    with_request_id(request_id, with_deadline(deadline_from_env(), process_request(req, body, app))).await
+}
+
+/// リクエストを処理してレスポンスを返す
+async fn process_request(
+    req: HttpRequest,
+    body: Option<Bytes>,
+    app: web::Data<Arc<RunBridge>>,
+) -> HttpResponse {
+    let started = Instant::now();
     let path = req.uri().path().to_string();
     let method_str = req.method().as_str();
     info!("Received request: {} {}", method_str, path);
 
-    // ボディサイズ上限チェック（共通設定）
-    if let Some(ref b) = body {
-        let max = get_max_body_size();
-        if b.len() > max {
-            warn!("Request body too large: {} bytes (limit {})", b.len(), max);
-            return HttpResponse::PayloadTooLarge().finish();
-        }
-    }
-
-    // リクエストの変換
-    let request = convert_request(&req, path.clone(), body).await;
-
-    // ハンドラーの検索
-    let handler = match app.find_handler(&path, &request.method) {
-        Some(handler) => handler,
-        None => {
-            error!("Route not found: {} {}", request.method, path);
-            return convert_to_http_response(Response::not_found()
-                .with_body("Not Found".as_bytes().to_vec()));
+    // 許可されていないメソッドはルーティング前に405/501で拒否
+    let method = match check_method(method_str) {
+        Ok(method) => method,
+        Err(res) => {
+            app.log_rejected(started, method_str, &path, &res);
+            return convert_to_http_response(res);
         }
     };
 
-    // ミドルウェアの適用（リクエスト前処理）
-    let mut req_processed = request;
-    for middleware in app.middlewares() {
-        match middleware.pre_process(req_processed).await {
-            Ok(processed) => req_processed = processed,
-            Err(e) => {
-                error!("Middleware error: {}", e);
-                let status = e.status_code();
-                return convert_to_http_response(Response::new(status)
-                    .with_body(format!("Error: {}", e).as_bytes().to_vec()));
-            }
-        }
-    }
-
-    // ハンドラーの実行
-    let handler_result = handler.handle(req_processed).await;
-
-    // レスポンスの処理
-    let response = match handler_result {
-        Ok(res) => res,
+    // リクエストの変換（解凍後のボディの上限はルート固有 -> 全体設定）
+    let max_body_size = app.max_body_size_for(&path, &method);
+    let request = match convert_request(&req, method, path.clone(), body, max_body_size).await {
+        Ok(request) => request,
         Err(e) => {
-            error!("Handler error: {}", e);
-            Response::from_error(&e)
+            error!("Request conversion error: {}", e);
+            let res = Response::from_error(&e);
+            app.log_rejected(started, method_str, &path, &res);
+            return convert_to_http_response(res);
         }
     };
 
-    // ミドルウェアの適用（レスポンス後処理）
-    let mut res_processed = response;
-    for middleware in app.middlewares() {
-        match middleware.post_process(res_processed).await {
-            Ok(processed) => res_processed = processed,
-            Err(e) => {
-                error!("Middleware error in post-processing: {}", e);
-                res_processed = Response::from_error(&e);
-            }
-        }
-    }
+    // ルーティング・ミドルウェア・ハンドラーの実行（全ランタイム共通）
+    // HEADリクエストはactix-webがボディの長さから`Content-Length`を付けてボディを送らないため、ボディは残す
+    let processed = app.process_request(request).await;
+    let route = match processed.route {
+        Some(route) => route,
+        None => return convert_to_http_response(processed.response),
+    };
+
+    // 不正なステータスコードが200等として返らないよう検査
+    let res_processed = guard_status(processed.response, &route);
+
+    // レスポンスサイズの記録（閾値超過時は警告）
+    let body_size = res_processed.body.as_ref().map(|b| b.len()).unwrap_or(0);
+    record_response_size(
+        &route,
+        &method,
+        &path,
+        body_size,
+        get_response_size_warn_threshold(),
+    );
 
     // レスポンスの変換と返却
     convert_to_http_response(res_processed)
 }
 
+/// `Expect: 100-continue`のリクエストをボディの受信前に検査（拒否する場合はボディを受信せずに応答）
+async fn expect_continue(
+    app: Arc<RunBridge>,
+    req: actix_http::Request,
+) -> Result<actix_http::Request, actix_http::Response<BoxBody>> {
+    let started = Instant::now();
+    let (method_str, path) = (req.method().to_string(), req.path().to_string());
+    let reject = |res: Response| {
+        app.log_rejected(started, &method_str, &path, &res);
+        actix_http::Response::from(convert_to_http_response(res))
+    };
+    let method = check_method(req.method().as_str()).map_err(reject)?;
+    let mut request = Request::new(method, req.path().to_string());
+    request.query_params = parse_query_string_limited(req.uri().query().unwrap_or(""))
+        .map_err(|e| reject(Response::from_error(&e)))?;
+    request.headers = convert_headers(req.headers());
+    app.check_before_body(&mut request).map_err(reject)?;
+    Ok(req)
+}
+
+/// すべてのパスとメソッドをRunBridgeで処理するルートを登録
+///
+/// GET・DELETE・HEAD・OPTIONSもボディを上限付きで受信し、`BodyPolicy`（拒否・破棄・そのまま渡す）を
+/// 他の実行環境と同じように適用します。
+fn configure_routes(cfg: &mut web::ServiceConfig) {
+    cfg.route("/{path:.*}", web::to(handle_request_with_body));
+}
+
+/// HTTPサーバーを構築
+///
+/// actix-webの`HttpServer`は`Expect: 100-continue`の検査サービスを差し替えられないため、
+/// actix-http/actix-serverで同等のサーバーを構築し、`expect_continue`を組み込みます。
+fn build_server(app: Arc<RunBridge>, listener: TcpListener) -> std::io::Result<Server> {
+    let server = Server::build()
+        // SIGTERMを受けたら新規接続を止め、処理中のリクエストの完了を待つ（Cloud Runの停止猶予内に収める）
+        .shutdown_timeout(get_shutdown_timeout().as_secs())
+        .listen("runbridge", listener, move || {
+            let app_data = web::Data::new(app.clone());
+            let expect_app = app.clone();
+
+            let web_app = App::new().app_data(app_data).configure(configure_routes);
+
+            HttpService::build()
+                // ボディを送信する前に認証・サイズ等で拒否できるよう、ルートの検査を実行
+                .expect(fn_service(move |req| expect_continue(expect_app.clone(), req)))
+                .finish(map_config(web_app, |_| AppConfig::default()))
+                .tcp()
+        })?
+        .run();
+    Ok(server)
+}
+
 /// アプリケーションをCloud Run/HTTPサーバーとして実行
 pub async fn run_cloud_run(app: RunBridge, host: &str, port: u16) -> std::io::Result<()> {
     info!("Starting HTTP server on {}:{}", host, port);
+    app.launch()
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))?;
     
     // アプリケーションをArcで包んでスレッド間で共有可能にする
-    let app_data = Arc::new(app);
-    let max_body = get_max_body_size();
+    let app = Arc::new(app);
     
     // HTTPサーバーの構築と起動
-    HttpServer::new(move || {
-        let app_data = web::Data::new(app_data.clone());
-        
-        App::new()
-            .app_data(app_data.clone())
-            // リクエストボディサイズの上限（共通設定）
-            .app_data(web::PayloadConfig::new(max_body))
-            // すべてのリクエストをキャッチする汎用ハンドラー
-            .route("/{path:.*}", web::get().to(|req, app: web::Data<Arc<RunBridge>>| 
-                handle_request(req, None, app)))
-            .route("/{path:.*}", web::post().to(|req, body: Option<Bytes>, app: web::Data<Arc<RunBridge>>| 
-                handle_request(req, body, app)))
-            .route("/{path:.*}", web::put().to(|req, body: Option<Bytes>, app: web::Data<Arc<RunBridge>>| 
-                handle_request(req, body, app)))
-            .route("/{path:.*}", web::delete().to(|req, app: web::Data<Arc<RunBridge>>| 
-                handle_request(req, None, app)))
-            .route("/{path:.*}", web::patch().to(|req, body: Option<Bytes>, app: web::Data<Arc<RunBridge>>| 
-                handle_request(req, body, app)))
-            .route("/{path:.*}", web::head().to(|req, app: web::Data<Arc<RunBridge>>| 
-                handle_request(req, None, app)))
-            .route("/{path:.*}", web::method(actix_web::http::Method::OPTIONS).to(|req, app: web::Data<Arc<RunBridge>>| 
-                handle_request(req, None, app)))
-    })
-    .bind((host, port))?
-    .run()
-    .await
-} 
+    let listener = TcpListener::bind((host, port))?;
+    let result = build_server(app.clone(), listener)?.await;
+
+    info!("HTTP server stopped");
+    app.shutdown().await;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn test_read_payload_stops_at_limit() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use actix_web::FromRequest;
+
+        // 1KBずつ64回（64KB）送るストリームで、受信したチャンク数を記録する
+        let pulled = Arc::new(AtomicUsize::new(0));
+        let counter = pulled.clone();
+        let stream = futures::stream::iter(0..64).map(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok::<_, actix_http::error::PayloadError>(Bytes::from(vec![b'a'; 1024]))
+        });
+        let req = actix_web::test::TestRequest::post().to_http_request();
+        let mut payload = actix_http::Payload::from(Box::pin(stream) as actix_http::BoxedPayloadStream);
+        let payload = web::Payload::from_request(&req, &mut payload).await.unwrap();
+        let err = read_payload(&req, payload, 4096).await.unwrap_err();
+        assert_eq!(err.status_code(), 413);
+        assert_eq!(pulled.load(Ordering::SeqCst), 5);
+
+        // 宣言された長さが上限を超える場合は受信しない
+        let (req, mut payload) = actix_web::test::TestRequest::post()
+            .insert_header(("Content-Length", "8192"))
+            .set_payload(vec![b'a'; 8192])
+            .to_http_parts();
+        let payload = web::Payload::from_request(&req, &mut payload).await.unwrap();
+        assert_eq!(read_payload(&req, payload, 4096).await.unwrap_err().status_code(), 413);
+
+        let (req, mut payload) = actix_web::test::TestRequest::post().set_payload("hello").to_http_parts();
+        let payload = web::Payload::from_request(&req, &mut payload).await.unwrap();
+        assert_eq!(read_payload(&req, payload, 4096).await.unwrap(), Bytes::from_static(b"hello"));
+    }
+
+    #[test]
+    fn test_record_response_size_threshold() {
+        assert!(!record_response_size("^/items$", &Method::GET, "/items", 512, 1024));
+        assert!(!record_response_size("^/items$", &Method::GET, "/items", 1024, 1024));
+        assert!(record_response_size("^/items$", &Method::GET, "/items", 1025, 1024));
+    }
+
+    #[test]
+    fn test_guard_status_keeps_valid_status() {
+        let res = guard_status(Response::new(418), "^/teapot$");
+        assert_eq!(res.status, 418);
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, should_panic(expected = "Invalid HTTP status 1000"))]
+    fn test_guard_status_rejects_invalid_status() {
+        let res = guard_status(Response::new(1000), "^/broken$");
+        assert_eq!(res.status, 500);
+    }
+
+    #[test]
+    fn test_unconvertible_status_is_not_success() {
+        assert_eq!(convert_to_http_response(Response::new(42)).status().as_u16(), 500);
+    }
+
+    #[test]
+    fn test_request_origin_ignores_untrusted_forwarded_headers() {
+        let forged = || {
+            actix_web::test::TestRequest::get()
+                .uri("/items")
+                .insert_header(("Host", "api.example.com"))
+                .insert_header(("X-Forwarded-Host", "evil.example.com"))
+                .insert_header(("X-Forwarded-Proto", "https"))
+                .insert_header(("Forwarded", "host=evil.example.com;proto=https"))
+                .to_http_request()
+        };
+        temp_env::with_var("RUNBRIDGE_TRUST_FORWARDED_PROTO", None::<&str>, || {
+            let origin = request_origin(&forged());
+            assert_eq!(origin.scheme, "http");
+            assert_eq!(origin.host.as_deref(), Some("api.example.com"));
+        });
+        // 信頼する設定でもホストは`Host`ヘッダーから取得する
+        temp_env::with_var("RUNBRIDGE_TRUST_FORWARDED_PROTO", Some("true"), || {
+            let origin = request_origin(&forged());
+            assert_eq!(origin.scheme, "https");
+            assert_eq!(origin.host.as_deref(), Some("api.example.com"));
+        });
+    }
+
+    #[test]
+    fn test_get_response_size_warn_threshold() {
+        temp_env::with_var("RUNBRIDGE_RESPONSE_SIZE_WARN_THRESHOLD", Some("2048"), || {
+            assert_eq!(get_response_size_warn_threshold(), 2048);
+        });
+        temp_env::with_var("RUNBRIDGE_RESPONSE_SIZE_WARN_THRESHOLD", Some("invalid"), || {
+            assert_eq!(get_response_size_warn_threshold(), 1024 * 1024);
+        });
+    }
+
+    #[actix_web::test]
+    async fn test_sse_response_is_streamed() {
+        use crate::common::SseEvent;
+
+        let events = futures::stream::iter(vec![SseEvent::new("a").event("tick"), SseEvent::new("b")]);
+        let res = convert_to_http_response(Response::sse(events));
+        assert_eq!(res.headers().get("content-type").unwrap(), "text/event-stream");
+        assert!(res.headers().get("content-length").is_none());
+        let body = actix_web::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"event: tick\ndata: a\n\ndata: b\n\n");
+    }
+
+    #[test]
+    fn test_set_cookie_is_split_into_headers() {
+        let res = Response::ok()
+            .with_header("Set-Cookie", "a=1; Path=/, b=2; Expires=Wed, 21 Oct 2026 07:28:00 GMT; HttpOnly");
+        let res = convert_to_http_response(res);
+        let cookies: Vec<_> = res.headers().get_all("set-cookie").map(|v| v.to_str().unwrap()).collect();
+        assert_eq!(cookies, vec!["a=1; Path=/", "b=2; Expires=Wed, 21 Oct 2026 07:28:00 GMT; HttpOnly"]);
+    }
+
+    #[actix_web::test]
+    async fn test_expect_continue_rejects_before_body() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use crate::handler::{self, HandlerExt};
+
+        let app = RunBridge::builder()
+            .handler(handler::post("^/upload$", |_req: Request, _body: serde_json::Value| Ok("uploaded"))
+                .pre_body_guard(|req| match req.headers.get("authorization") {
+                    Some(_) => Ok(()),
+                    None => Err(AppError::AuthenticationError("missing credentials".to_string())),
+                }))
+            .build();
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = build_server(Arc::new(app), listener).unwrap();
+        let handle = server.handle();
+        actix_rt::spawn(server);
+
+        // ボディを送らずにヘッダーだけ送信し、最初の応答を読む
+        async fn first_response(addr: std::net::SocketAddr, headers: &str) -> String {
+            let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+            let head = format!(
+                "POST /upload HTTP/1.1\r\nHost: localhost\r\nExpect: 100-continue\r\nContent-Type: application/json\r\n{}\r\n",
+                headers
+            );
+            stream.write_all(head.as_bytes()).await.unwrap();
+            let mut buf = vec![0u8; 1024];
+            let n = stream.read(&mut buf).await.unwrap();
+            String::from_utf8_lossy(&buf[..n]).to_string()
+        }
+
+        let res = first_response(addr, "Content-Length: 2\r\n").await;
+        assert!(res.starts_with("HTTP/1.1 401"), "{}", res);
+
+        let res = first_response(addr, "Content-Length: 999999999999\r\nAuthorization: Bearer t\r\n").await;
+        assert!(res.starts_with("HTTP/1.1 413"), "{}", res);
+
+        let res = first_response(addr, "Content-Length: 2\r\nAuthorization: Bearer t\r\n").await;
+        assert!(res.starts_with("HTTP/1.1 100 Continue"), "{}", res);
+
+        handle.stop(false).await;
+    }
+
+    #[actix_web::test]
+    async fn test_body_policy_applies_to_get_with_body() {
+        use crate::common::BodyPolicy;
+        use crate::handler::HandlerExt;
+        use crate::testing::raw_handler;
+
+        /// ボディの長さを返す（型付きボディの解析を行わない）
+        fn body_len(req: Request) -> Result<Response, AppError> {
+            let len = req.body.map(|b| b.len()).unwrap_or(0);
+            Ok(Response::ok().with_body(len.to_string().into_bytes()))
+        }
+
+        let app = RunBridge::builder()
+            .handler(raw_handler("^/strict$", body_len).with_body_policy(BodyPolicy::Reject))
+            .handler(raw_handler("^/allow$", body_len).with_body_policy(BodyPolicy::Allow))
+            .build();
+        let service = actix_web::test::init_service(
+            App::new().app_data(web::Data::new(Arc::new(app))).configure(configure_routes),
+        )
+        .await;
+
+        let req = actix_web::test::TestRequest::get().uri("/strict").set_payload("{}").to_request();
+        assert_eq!(actix_web::test::call_service(&service, req).await.status().as_u16(), 400);
+        let req = actix_web::test::TestRequest::get().uri("/strict").to_request();
+        assert_eq!(actix_web::test::call_service(&service, req).await.status().as_u16(), 200);
+
+        // Allowではボディをそのままハンドラーに渡す
+        let req = actix_web::test::TestRequest::get().uri("/allow").set_payload("hello").to_request();
+        let body = actix_web::test::call_and_read_body(&service, req).await;
+        assert_eq!(body, Bytes::from_static(b"5"));
+        let req = actix_web::test::TestRequest::delete().uri("/allow").set_payload("abc").to_request();
+        let body = actix_web::test::call_and_read_body(&service, req).await;
+        assert_eq!(body, Bytes::from_static(b"3"));
+
+        // 許可されていないメソッドはボディを受信せずに拒否する
+        let req = actix_web::test::TestRequest::default()
+            .method(actix_web::http::Method::TRACE)
+            .uri("/allow")
+            .to_request();
+        assert_eq!(actix_web::test::call_service(&service, req).await.status().as_u16(), 501);
+    }
+
+    #[actix_web::test]
+    async fn test_gzip_body_errors_are_returned() {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+        use std::io::Write;
+
+        use crate::handler::{post, HandlerExt};
+
+        // `/upload`は登録しないため、解凍に成功した場合は404になる
+        let small = post("/small", |_req: Request, _body: serde_json::Value| -> Result<&'static str, AppError> { Ok("ok") });
+        let app = RunBridge::builder().handler(small.max_body_size(1024)).build();
+        let service = actix_web::test::init_service(
+            App::new().app_data(web::Data::new(Arc::new(app))).configure(configure_routes),
+        )
+        .await;
+
+        // 不正なgzipは400
+        let req = actix_web::test::TestRequest::post()
+            .uri("/upload")
+            .insert_header(("Content-Encoding", "gzip"))
+            .set_payload("not gzip")
+            .to_request();
+        assert_eq!(actix_web::test::call_service(&service, req).await.status().as_u16(), 400);
+
+        // 解凍後に上限（既定5MB）を超える場合は413
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&vec![0u8; 6 * 1024 * 1024]).unwrap();
+        let req = actix_web::test::TestRequest::post()
+            .uri("/upload")
+            .insert_header(("Content-Encoding", "gzip"))
+            .set_payload(encoder.finish().unwrap())
+            .to_request();
+        assert_eq!(actix_web::test::call_service(&service, req).await.status().as_u16(), 413);
+
+        // ルート固有の上限は解凍後のサイズにも適用する
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&[b' '; 4096]).unwrap();
+        let compressed = encoder.finish().unwrap();
+        assert!(compressed.len() < 1024);
+        let gzip_post = |uri: &str| {
+            actix_web::test::TestRequest::post()
+                .uri(uri)
+                .insert_header(("Content-Encoding", "gzip"))
+                .set_payload(compressed.clone())
+                .to_request()
+        };
+        assert_eq!(actix_web::test::call_service(&service, gzip_post("/small")).await.status().as_u16(), 413);
+        assert_eq!(actix_web::test::call_service(&service, gzip_post("/upload")).await.status().as_u16(), 404);
+    }
+}
@@ -0,0 +1,311 @@
+//! リクエスト単位のアクセスログ
+//!
+//! `RunBridgeBuilder::access_log`で登録すると、リクエストごとにメソッド・パス・ステータス・
+//! 処理時間・リクエストID・ボディサイズを1行で出力します。ルートが無い場合の404、ルーティング前に
+//! 各ランタイムが拒否したリクエスト（405・501・413・414・400など）、ミドルウェアの前処理で
+//! 拒否したリクエストも含め、全てのレスポンスが記録されます。
+//! JSON形式はCloudWatch Logs / Cloud Loggingでそのまま構造化ログとして扱えます。
+//!
+//! AroundMiddlewareとして`RunBridgeBuilder::around`で登録することもできますが、その場合は
+//! ハンドラーまで到達したリクエストのみが記録されます。
+//!
+//! ```
+//! use runbridge::common::AccessLogMiddleware;
+//!
+//! let app = runbridge::RunBridge::builder()
+//!     .access_log(AccessLogMiddleware::new().json().exclude_path("/healthz"))
+//!     .build();
+//! # drop(app);
+//! ```
+
+use std::fmt;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use log::Level;
+use serde::Serialize;
+
+use crate::error::Error;
+use super::http::{Request, Response};
+use super::request_id::current_request_id;
+use super::traits::{AroundMiddleware, Next};
+
+/// `log`クレートで出力する際のターゲット
+pub const ACCESS_LOG_TARGET: &str = "runbridge::access";
+
+/// 1リクエスト分のアクセスログ
+#[derive(Debug, Clone, Serialize)]
+pub struct AccessLogEntry {
+    /// 記録時刻（RFC 3339）
+    pub timestamp: String,
+    /// 重要度（5xxは`ERROR`、4xxは`WARNING`、それ以外は`INFO`）
+    pub severity: &'static str,
+    /// HTTPメソッド
+    pub method: String,
+    /// リクエストパス（クエリ文字列は機密値を含み得るため記録しない）
+    pub path: String,
+    /// マッチしたルート（ルート名またはパスパターン）
+    pub route: Option<String>,
+    /// ステータスコード（ハンドラーのエラーはエラーレスポンスのステータス）
+    pub status: u16,
+    /// 処理時間（ミリ秒）
+    pub latency_ms: f64,
+    /// リクエストID
+    pub request_id: Option<String>,
+    /// リクエストボディのサイズ（バイト）
+    pub request_bytes: usize,
+    /// レスポンスボディのサイズ（バイト、SSEなどストリーミングの場合は0）
+    pub response_bytes: usize,
+}
+
+impl AccessLogEntry {
+    /// JSON形式の1行
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+impl fmt::Display for AccessLogEntry {
+    /// テキスト形式（`GET /items 200 1.2ms req=... in=0B out=15B`）
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {:.1}ms req={} in={}B out={}B",
+            self.method,
+            self.path,
+            self.status,
+            self.latency_ms,
+            self.request_id.as_deref().unwrap_or("-"),
+            self.request_bytes,
+            self.response_bytes
+        )
+    }
+}
+
+fn severity(status: u16) -> &'static str {
+    match status {
+        500.. => "ERROR",
+        400..=499 => "WARNING",
+        _ => "INFO",
+    }
+}
+
+/// リクエストの受信時に記録しておく項目
+pub(crate) struct AccessLogStart {
+    pub(crate) started: Instant,
+    pub(crate) method: String,
+    pub(crate) path: String,
+    pub(crate) request_bytes: usize,
+}
+
+impl AccessLogStart {
+    pub(crate) fn new(req: &Request) -> Self {
+        Self {
+            started: Instant::now(),
+            method: req.method.to_string(),
+            path: req.path.clone(),
+            request_bytes: req.body.as_ref().map_or(0, Vec::len),
+        }
+    }
+}
+
+type AccessLogSink = Arc<dyn Fn(&AccessLogEntry) + Send + Sync>;
+
+/// 出力先
+#[derive(Clone)]
+enum Output {
+    /// `log`クレート（ターゲット`runbridge::access`）
+    Log,
+    /// 標準エラー出力に1行ずつ書き込む（CGIでは標準出力がレスポンスのため標準エラー出力を使う）
+    Stderr,
+    /// 任意の関数
+    Custom(AccessLogSink),
+}
+
+/// リクエストごとにアクセスログを出力するロガー
+///
+/// `RunBridgeBuilder::access_log`で登録すると全てのレスポンスを記録し、処理時間はルーティング・
+/// ミドルウェア・ハンドラー全体の実行時間です。AroundMiddlewareとして登録した場合はハンドラーまで
+/// 到達したリクエストのみを記録し、処理時間はこのミドルウェアより内側の実行時間です。
+#[derive(Clone)]
+pub struct AccessLogMiddleware {
+    json: bool,
+    output: Output,
+    excluded_paths: Vec<String>,
+}
+
+impl Default for AccessLogMiddleware {
+    fn default() -> Self {
+        Self { json: false, output: Output::Log, excluded_paths: Vec::new() }
+    }
+}
+
+impl AccessLogMiddleware {
+    /// テキスト形式で`log`クレートに出力するAccessLogMiddlewareを作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// JSON形式で出力
+    pub fn json(mut self) -> Self {
+        self.json = true;
+        self
+    }
+
+    /// `log`クレートを経由せず、標準エラー出力に1行ずつ書き込む
+    ///
+    /// ロガーの接頭辞が付かないため、JSON形式と組み合わせるとそのまま構造化ログとして取り込まれます。
+    pub fn to_stderr(mut self) -> Self {
+        self.output = Output::Stderr;
+        self
+    }
+
+    /// 任意の関数にログを渡す（外部の収集基盤への送信やテストでの検査用）
+    pub fn with_sink<F>(mut self, sink: F) -> Self
+    where
+        F: Fn(&AccessLogEntry) + Send + Sync + 'static,
+    {
+        self.output = Output::Custom(Arc::new(sink));
+        self
+    }
+
+    /// ログを出力しないパス（ヘルスチェックなど、完全一致）
+    pub fn exclude_path(mut self, path: impl Into<String>) -> Self {
+        self.excluded_paths.push(path.into());
+        self
+    }
+
+    /// 1リクエスト分を記録（除外するパスの場合は何もしない）
+    pub(crate) fn record(&self, start: &AccessLogStart, route: Option<String>, status: u16, response_bytes: usize) {
+        if self.excluded_paths.contains(&start.path) {
+            return;
+        }
+        self.emit(&AccessLogEntry {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            severity: severity(status),
+            method: start.method.clone(),
+            path: start.path.clone(),
+            route,
+            status,
+            latency_ms: start.started.elapsed().as_secs_f64() * 1000.0,
+            request_id: current_request_id(),
+            request_bytes: start.request_bytes,
+            response_bytes,
+        });
+    }
+
+    fn emit(&self, entry: &AccessLogEntry) {
+        let line = || if self.json { entry.to_json() } else { entry.to_string() };
+        match &self.output {
+            Output::Log => {
+                let level = match entry.severity {
+                    "ERROR" => Level::Error,
+                    "WARNING" => Level::Warn,
+                    _ => Level::Info,
+                };
+                log::log!(target: ACCESS_LOG_TARGET, level, "{}", line());
+            }
+            Output::Stderr => {
+                let _ = writeln!(std::io::stderr().lock(), "{}", line());
+            }
+            Output::Custom(sink) => sink(entry),
+        }
+    }
+}
+
+#[async_trait]
+impl AroundMiddleware for AccessLogMiddleware {
+    async fn around(&self, req: Request, next: Next<'_>) -> Result<Response, Error> {
+        if self.excluded_paths.contains(&req.path) {
+            return next.run(req).await;
+        }
+
+        let start = AccessLogStart::new(&req);
+        let route = req.matched_route().map(|r| r.label().to_string());
+
+        let result = next.run(req).await;
+        let (status, response_bytes) = match &result {
+            Ok(res) => (res.status, res.body.as_ref().map_or(0, Vec::len)),
+            Err(e) => (e.status_code(), 0),
+        };
+        self.record(&start, route, status, response_bytes);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::common::request_id::with_request_id;
+    use crate::common::Method;
+    use crate::testing::raw_handler;
+
+    async fn run(logger: AccessLogMiddleware, req: Request) {
+        // `/missing`ではRouteNotFoundを返し、それ以外はパスをボディとして返すハンドラー
+        let handler = raw_handler("^/.*$", |req| {
+            if req.path == "/missing" {
+                return Err(Error::RouteNotFound("missing".to_string()));
+            }
+            Ok(Response::ok().with_body(req.path.into_bytes()))
+        });
+        let around: Vec<Box<dyn AroundMiddleware>> = vec![Box::new(logger)];
+        let _ = with_request_id("req-1".to_string(), Next::new(&handler, &around).run(req)).await;
+    }
+
+    #[tokio::test]
+    async fn test_access_log_entries() {
+        let entries = Arc::new(Mutex::new(Vec::new()));
+        let captured = entries.clone();
+        let logger = AccessLogMiddleware::new()
+            .json()
+            .exclude_path("/healthz")
+            .with_sink(move |entry| captured.lock().unwrap().push(entry.clone()));
+
+        let req = Request::new(Method::POST, "/items".to_string()).with_body(b"{}".to_vec());
+        run(logger.clone(), req).await;
+        run(logger.clone(), Request::new(Method::GET, "/missing".to_string())).await;
+        run(logger, Request::new(Method::GET, "/healthz".to_string())).await;
+
+        let entries = entries.lock().unwrap();
+        assert_eq!(entries.len(), 2);
+        let entry = &entries[0];
+        assert_eq!((entry.method.as_str(), entry.path.as_str(), entry.status), ("POST", "/items", 200));
+        assert_eq!((entry.request_bytes, entry.response_bytes), (2, 6));
+        assert_eq!(entry.request_id.as_deref(), Some("req-1"));
+        assert_eq!(entry.severity, "INFO");
+        assert_eq!((entries[1].status, entries[1].severity), (404, "WARNING"));
+
+        let json: serde_json::Value = serde_json::from_str(&entry.to_json()).unwrap();
+        assert_eq!(json["status"], 200);
+        assert_eq!(json["request_id"], "req-1");
+        assert!(entry.to_string().starts_with("POST /items 200 "));
+    }
+
+    #[tokio::test]
+    async fn test_app_access_log_records_unrouted_requests() {
+        let entries = Arc::new(Mutex::new(Vec::new()));
+        let captured = entries.clone();
+        use crate::handler::HandlerExt;
+
+        let app = crate::RunBridge::builder()
+            .handler(crate::handler::get("/items", |_| Ok("items")).name("list_items"))
+            .access_log(AccessLogMiddleware::new().with_sink(move |entry| captured.lock().unwrap().push(entry.clone())))
+            .build();
+
+        crate::testing::dispatch(&app, Request::new(Method::GET, "/items".to_string())).await;
+        crate::testing::dispatch(&app, Request::new(Method::GET, "/nowhere".to_string())).await;
+        app.log_rejected(Instant::now(), "TRACE", "/items", &Response::error(405));
+
+        let entries = entries.lock().unwrap();
+        let logged: Vec<_> = entries.iter().map(|e| (e.path.as_str(), e.status, e.route.as_deref())).collect();
+        assert_eq!(
+            logged,
+            vec![("/items", 200, Some("list_items")), ("/nowhere", 404, None), ("/items", 405, None)]
+        );
+        assert_eq!(entries[2].method, "TRACE");
+    }
+}
@@ -0,0 +1,148 @@
+//! アプリケーション全体で共有する値（DBプール・設定など）
+//!
+//! `RunBridgeBuilder::app_data`で型ごとに1つ登録し、ハンドラーやミドルウェアからは
+//! `req.data::<T>()`または`Data::<T>::from_request(&req)`で参照します。
+//! 各ランタイムはミドルウェア前処理の前にリクエストへ格納します。
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use crate::error::Error;
+use super::http::Request;
+
+/// RequestContextに格納する際のキー
+pub const APP_DATA_CONTEXT_KEY: &str = "runbridge.app_data";
+
+/// 型ごとに1つの値を保持する共有データ（クローンは参照の複製のみ）
+#[derive(Clone, Default)]
+pub struct AppData {
+    values: Arc<HashMap<TypeId, Arc<dyn Any + Send + Sync>>>,
+}
+
+impl AppData {
+    /// 値を登録（同じ型の値は置き換え）
+    pub(crate) fn insert<T: Send + Sync + 'static>(&mut self, value: T) {
+        Arc::make_mut(&mut self.values).insert(TypeId::of::<T>(), Arc::new(value));
+    }
+
+    /// 値を取得
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.values
+            .get(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_ref::<T>())
+    }
+
+    /// 値を`Arc`で取得（リクエストより長く保持する場合に使用）
+    pub fn get_arc<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        self.values
+            .get(&TypeId::of::<T>())
+            .and_then(|value| value.clone().downcast::<T>().ok())
+    }
+
+    /// 登録済みの値の数
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// 値が1つも無いか
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+impl fmt::Debug for AppData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AppData").field("len", &self.values.len()).finish()
+    }
+}
+
+/// 共有データの抽出（`Deref`で`T`として使用）
+///
+/// ```
+/// use runbridge::common::{Data, Request};
+/// use runbridge::error::Error;
+///
+/// struct Settings { greeting: String }
+///
+/// fn hello(req: Request) -> Result<String, Error> {
+///     let settings = Data::<Settings>::from_request(&req)?;
+///     Ok(settings.greeting.clone())
+/// }
+/// ```
+pub struct Data<T>(Arc<T>);
+
+impl<T: Send + Sync + 'static> Data<T> {
+    /// リクエストから取得（登録されていない場合は設定の誤りとして500）
+    pub fn from_request(req: &Request) -> Result<Self, Error> {
+        req.app_data()
+            .and_then(|data| data.get_arc::<T>())
+            .map(Data)
+            .ok_or_else(|| {
+                Error::ConfigurationError(format!(
+                    "App data of type {} is not registered; use RunBridgeBuilder::app_data",
+                    std::any::type_name::<T>()
+                ))
+            })
+    }
+
+    /// 内部の`Arc`を取り出す
+    pub fn into_inner(self) -> Arc<T> {
+        self.0
+    }
+}
+
+impl<T> Clone for Data<T> {
+    fn clone(&self) -> Self {
+        Data(self.0.clone())
+    }
+}
+
+impl<T> Deref for Data<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl Request {
+    /// アプリケーションの共有データ全体（各ランタイムが格納）
+    pub fn app_data(&self) -> Option<&AppData> {
+        self.context().get::<AppData>(APP_DATA_CONTEXT_KEY)
+    }
+
+    /// `RunBridgeBuilder::app_data`で登録した値を取得
+    pub fn data<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.app_data().and_then(|data| data.get::<T>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Method;
+
+    #[test]
+    fn test_app_data_by_type() {
+        let mut data = AppData::default();
+        data.insert(42u32);
+        data.insert("config".to_string());
+        data.insert(7u32);
+        assert_eq!(data.len(), 2);
+        assert_eq!(data.get::<u32>(), Some(&7));
+        assert!(data.get::<u64>().is_none());
+
+        let mut req = Request::new(Method::GET, "/".to_string());
+        assert!(req.data::<String>().is_none());
+        assert_eq!(Data::<String>::from_request(&req).err().map(|e| e.status_code()), Some(500));
+
+        req.context_mut().set(APP_DATA_CONTEXT_KEY, data.clone());
+        assert_eq!(req.data::<String>().map(String::as_str), Some("config"));
+        let config = Data::<String>::from_request(&req).unwrap();
+        assert_eq!(config.len(), 6);
+        assert!(Arc::ptr_eq(&config.into_inner(), &data.get_arc::<String>().unwrap()));
+    }
+}
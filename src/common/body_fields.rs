@@ -0,0 +1,388 @@
+//! 型付きボディ（JSON）のフィールドの扱い（未知のフィールド・フィールド名の照合）
+//!
+//! 構造体ごとの`#[serde(deny_unknown_fields)]`・`#[serde(rename_all = "...")]`を付けずに、
+//! ルート（`HandlerExt::body_fields`）またはアプリ全体（`RunBridgeBuilder::body_field_policy`）で
+//! 厳格・寛容なAPIの契約を指定します。
+//!
+//! 方針はデシリアライズ時に構造体のフィールド名（serdeの属性で変更した後の名前）と照合し、
+//! ネストした構造体・配列の要素にも適用します。`#[serde(flatten)]`したフィールド・enumの
+//! バリアントの中身・`#[serde(alias)]`の別名は照合の対象外です。
+//!
+//! ```
+//! use serde::Deserialize;
+//! use runbridge::common::{BodyFieldPolicy, RenameRule};
+//!
+//! #[derive(Deserialize)]
+//! struct CreateUser { user_name: String }
+//!
+//! let policy = BodyFieldPolicy::strict().rename_all(RenameRule::CamelCase);
+//! let user: CreateUser = policy.from_slice(br#"{"userName":"alice"}"#).unwrap();
+//! assert_eq!(user.user_name, "alice");
+//! assert!(policy.from_slice::<CreateUser>(br#"{"userName":"a","admin":true}"#).is_err());
+//! ```
+
+use serde::de::value::StringDeserializer;
+use serde::de::{self, DeserializeOwned, DeserializeSeed, Deserializer, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+use serde::forward_to_deserialize_any;
+use serde_json::{Map, Value};
+
+use crate::error::Error;
+
+/// ルート・アプリ全体の方針をハンドラーに渡すリクエストコンテキストのキー
+pub const BODY_FIELD_POLICY_CONTEXT_KEY: &str = "runbridge.body_field_policy";
+
+/// 構造体に無いフィールドの扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnknownFields {
+    /// 無視する（serdeの既定）
+    #[default]
+    Allow,
+    /// 400 Bad Requestで拒否する（`#[serde(deny_unknown_fields)]`相当）
+    Deny,
+}
+
+/// リクエストのフィールド名の命名規則（構造体のフィールド名からの変換、serdeの`rename_all`と同じ規則）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenameRule {
+    /// `lowercase`
+    LowerCase,
+    /// `UPPERCASE`
+    UpperCase,
+    /// `PascalCase`
+    PascalCase,
+    /// `camelCase`
+    CamelCase,
+    /// `snake_case`
+    SnakeCase,
+    /// `SCREAMING_SNAKE_CASE`
+    ScreamingSnakeCase,
+    /// `kebab-case`
+    KebabCase,
+    /// `SCREAMING-KEBAB-CASE`
+    ScreamingKebabCase,
+}
+
+impl RenameRule {
+    /// serdeの`rename_all`の値（`camelCase`など）から変換
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "lowercase" => Some(Self::LowerCase),
+            "UPPERCASE" => Some(Self::UpperCase),
+            "PascalCase" => Some(Self::PascalCase),
+            "camelCase" => Some(Self::CamelCase),
+            "snake_case" => Some(Self::SnakeCase),
+            "SCREAMING_SNAKE_CASE" => Some(Self::ScreamingSnakeCase),
+            "kebab-case" => Some(Self::KebabCase),
+            "SCREAMING-KEBAB-CASE" => Some(Self::ScreamingKebabCase),
+            _ => None,
+        }
+    }
+
+    /// 構造体のフィールド名（snake_case）をこの規則の名前に変換
+    pub fn apply(&self, field: &str) -> String {
+        match self {
+            Self::LowerCase | Self::SnakeCase => field.to_string(),
+            Self::UpperCase | Self::ScreamingSnakeCase => field.to_ascii_uppercase(),
+            Self::KebabCase => field.replace('_', "-"),
+            Self::ScreamingKebabCase => field.to_ascii_uppercase().replace('_', "-"),
+            Self::PascalCase | Self::CamelCase => {
+                let mut out = String::with_capacity(field.len());
+                let mut capitalize = *self == Self::PascalCase;
+                for c in field.chars() {
+                    if c == '_' {
+                        capitalize = true;
+                    } else if capitalize {
+                        out.push(c.to_ascii_uppercase());
+                        capitalize = false;
+                    } else {
+                        out.push(c);
+                    }
+                }
+                out
+            }
+        }
+    }
+}
+
+/// リクエストのフィールド名と構造体のフィールド名の照合方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FieldMatching {
+    /// 完全一致（serdeの既定）
+    #[default]
+    Exact,
+    /// ASCIIの大文字小文字を区別しない（完全一致するフィールドを優先）
+    CaseInsensitive,
+    /// 命名規則で変換した名前と一致する（変換前の名前は一致しない）
+    RenameAll(RenameRule),
+}
+
+/// 型付きボディのフィールドの扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BodyFieldPolicy {
+    unknown_fields: UnknownFields,
+    matching: FieldMatching,
+}
+
+impl BodyFieldPolicy {
+    /// serdeの既定と同じ方針（未知のフィールドは無視、名前は完全一致）
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 未知のフィールドを拒否する方針
+    pub fn strict() -> Self {
+        Self::new().unknown_fields(UnknownFields::Deny)
+    }
+
+    /// 未知のフィールドの扱いを指定
+    pub fn unknown_fields(mut self, unknown_fields: UnknownFields) -> Self {
+        self.unknown_fields = unknown_fields;
+        self
+    }
+
+    /// フィールド名の照合方法を指定
+    pub fn matching(mut self, matching: FieldMatching) -> Self {
+        self.matching = matching;
+        self
+    }
+
+    /// フィールド名の大文字小文字を区別しない
+    pub fn case_insensitive(self) -> Self {
+        self.matching(FieldMatching::CaseInsensitive)
+    }
+
+    /// リクエストのフィールド名の命名規則を指定（例: `RenameRule::CamelCase`）
+    pub fn rename_all(self, rule: RenameRule) -> Self {
+        self.matching(FieldMatching::RenameAll(rule))
+    }
+
+    /// serdeの既定と同じ方針か（既定の場合はフィールドを照合せずにデシリアライズする）
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// JSONのバイト列を方針に従ってデシリアライズ
+    pub fn from_slice<T: DeserializeOwned>(&self, body: &[u8]) -> Result<T, Error> {
+        if self.is_default() {
+            return serde_json::from_slice(body).map_err(|e| Error::InvalidRequestBody(e.to_string()));
+        }
+        let value: Value = serde_json::from_slice(body).map_err(|e| Error::InvalidRequestBody(e.to_string()))?;
+        self.from_value(value)
+    }
+
+    /// JSONの値を方針に従ってデシリアライズ
+    pub fn from_value<T: DeserializeOwned>(&self, value: Value) -> Result<T, Error> {
+        T::deserialize(PolicyDeserializer { value, policy: self })
+            .map_err(|e| Error::InvalidRequestBody(e.to_string()))
+    }
+
+    /// リクエストのフィールド名に対応する構造体のフィールド名
+    fn resolve(&self, key: &str, fields: &'static [&'static str]) -> Option<&'static str> {
+        match self.matching {
+            FieldMatching::Exact => fields.iter().find(|f| **f == key),
+            FieldMatching::CaseInsensitive => fields
+                .iter()
+                .find(|f| **f == key)
+                .or_else(|| fields.iter().find(|f| f.eq_ignore_ascii_case(key))),
+            FieldMatching::RenameAll(rule) => fields.iter().find(|f| rule.apply(f) == key),
+        }
+        .copied()
+    }
+
+    /// 構造体のフィールド名にキーを置き換え、未知のフィールドを検査する
+    fn rename_fields(&self, map: Map<String, Value>, fields: &'static [&'static str]) -> Result<Map<String, Value>, serde_json::Error> {
+        let mut out = Map::with_capacity(map.len());
+        for (key, value) in map {
+            match self.resolve(&key, fields) {
+                Some(field) if out.contains_key(field) => return Err(de::Error::duplicate_field(field)),
+                Some(field) => {
+                    out.insert(field.to_string(), value);
+                }
+                None if self.unknown_fields == UnknownFields::Deny => {
+                    return Err(de::Error::unknown_field(&key, fields));
+                }
+                // 構造体は未知のフィールドを無視するため取り除く（変換前の名前が別のフィールドと一致しないように）
+                None => {}
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// 構造体のデシリアライズ時に方針を適用するDeserializer（ネストした値にも適用）
+struct PolicyDeserializer<'p> {
+    value: Value,
+    policy: &'p BodyFieldPolicy,
+}
+
+impl<'de> Deserializer<'de> for PolicyDeserializer<'_> {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Array(items) => visitor.visit_seq(PolicySeqAccess { items: items.into_iter(), policy: self.policy }),
+            Value::Object(map) => visitor.visit_map(PolicyMapAccess::new(map, self.policy)),
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Object(map) => {
+                let map = self.policy.rename_fields(map, fields)?;
+                visitor.visit_map(PolicyMapAccess::new(map, self.policy))
+            }
+            Value::Array(items) => visitor.visit_seq(PolicySeqAccess { items: items.into_iter(), policy: self.policy }),
+            other => other.deserialize_struct(name, fields, visitor),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.value.deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map identifier ignored_any
+    }
+}
+
+struct PolicySeqAccess<'p> {
+    items: std::vec::IntoIter<Value>,
+    policy: &'p BodyFieldPolicy,
+}
+
+impl<'de> SeqAccess<'de> for PolicySeqAccess<'_> {
+    type Error = serde_json::Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error> {
+        match self.items.next() {
+            Some(value) => seed.deserialize(PolicyDeserializer { value, policy: self.policy }).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+struct PolicyMapAccess<'p> {
+    entries: serde_json::map::IntoIter,
+    value: Option<Value>,
+    policy: &'p BodyFieldPolicy,
+}
+
+impl<'p> PolicyMapAccess<'p> {
+    fn new(map: Map<String, Value>, policy: &'p BodyFieldPolicy) -> Self {
+        Self { entries: map.into_iter(), value: None, policy }
+    }
+}
+
+impl<'de> MapAccess<'de> for PolicyMapAccess<'_> {
+    type Error = serde_json::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                let key: StringDeserializer<serde_json::Error> = key.into_deserializer();
+                seed.deserialize(key).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Self::Error> {
+        let value = self.value.take().ok_or_else(|| de::Error::custom("value is missing"))?;
+        seed.deserialize(PolicyDeserializer { value, policy: self.policy })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Address {
+        postal_code: String,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct User {
+        user_name: String,
+        #[serde(default)]
+        addresses: Vec<Address>,
+        nickname: Option<String>,
+    }
+
+    #[test]
+    fn test_rename_rule_apply() {
+        assert_eq!(RenameRule::CamelCase.apply("postal_code"), "postalCode");
+        assert_eq!(RenameRule::PascalCase.apply("postal_code"), "PostalCode");
+        assert_eq!(RenameRule::ScreamingKebabCase.apply("postal_code"), "POSTAL-CODE");
+        assert_eq!(RenameRule::parse("kebab-case"), Some(RenameRule::KebabCase));
+    }
+
+    #[test]
+    fn test_unknown_fields_are_rejected_in_nested_structs() {
+        let strict = BodyFieldPolicy::strict();
+        let user: User = strict.from_slice(br#"{"user_name":"a","addresses":[{"postal_code":"1"}]}"#).unwrap();
+        assert_eq!(user.addresses, vec![Address { postal_code: "1".to_string() }]);
+
+        let err = strict
+            .from_slice::<User>(br#"{"user_name":"a","addresses":[{"postal_code":"1","zip":"2"}]}"#)
+            .unwrap_err();
+        assert_eq!(err.status_code(), 400);
+        assert!(err.to_string().contains("unknown field `zip`"));
+
+        // 既定の方針では無視する
+        assert!(BodyFieldPolicy::new().from_slice::<User>(br#"{"user_name":"a","zip":"2"}"#).is_ok());
+    }
+
+    #[test]
+    fn test_field_matching() {
+        let user: User = BodyFieldPolicy::new()
+            .case_insensitive()
+            .from_slice(br#"{"USER_NAME":"a","Nickname":null}"#)
+            .unwrap();
+        assert_eq!((user.user_name.as_str(), user.nickname), ("a", None));
+
+        let camel = BodyFieldPolicy::strict().rename_all(RenameRule::CamelCase);
+        let user: User = camel.from_slice(br#"{"userName":"a","addresses":[{"postalCode":"1"}],"nickname":"b"}"#).unwrap();
+        assert_eq!(user.nickname.as_deref(), Some("b"));
+        assert!(camel.from_slice::<User>(br#"{"user_name":"a"}"#).is_err());
+        let lenient = BodyFieldPolicy::new().rename_all(RenameRule::CamelCase);
+        assert!(lenient.from_slice::<User>(br#"{"userName":"a","nickname":"b","user_name":"c"}"#).is_ok());
+        assert!(lenient.from_slice::<User>(br#"{"user_name":"a"}"#).is_err());
+
+        let err = BodyFieldPolicy::new().case_insensitive().from_slice::<User>(br#"{"user_name":"a","User_Name":"b"}"#);
+        assert!(err.unwrap_err().to_string().contains("duplicate field"));
+    }
+}
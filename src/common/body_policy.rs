@@ -0,0 +1,112 @@
+//! ボディを持たないことが想定されるメソッド（GET/HEAD/DELETE）にボディが付いていた場合の扱い
+//!
+//! 中継するプロキシやゲートウェイによって挙動が異なるため、明示的に方針を選べるようにします。
+
+use std::env;
+
+use log::{debug, warn};
+
+use crate::error::Error;
+use super::http::{Method, Request};
+
+/// GET/HEAD/DELETEリクエストのボディに対する方針
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyPolicy {
+    /// そのままハンドラーに渡す（従来の挙動）
+    Allow,
+    /// ボディを破棄してハンドラーに渡す
+    Ignore,
+    /// 400 Bad Requestで拒否する
+    Reject,
+}
+
+impl BodyPolicy {
+    /// 文字列（`allow` / `ignore` / `reject`、大文字小文字を区別しない）から変換
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "allow" => Some(BodyPolicy::Allow),
+            "ignore" => Some(BodyPolicy::Ignore),
+            "reject" => Some(BodyPolicy::Reject),
+            _ => None,
+        }
+    }
+
+    /// 方針をリクエストに適用（対象外のメソッドやボディが空の場合は何もしない）
+    pub fn apply(self, req: &mut Request) -> Result<(), Error> {
+        if !matches!(req.method, Method::GET | Method::HEAD | Method::DELETE) {
+            return Ok(());
+        }
+        if req.body.as_ref().map(|b| b.is_empty()).unwrap_or(true) {
+            return Ok(());
+        }
+        match self {
+            BodyPolicy::Allow => Ok(()),
+            BodyPolicy::Ignore => {
+                debug!("Ignoring request body on {} {}", req.method, req.path);
+                req.body = None;
+                Ok(())
+            }
+            BodyPolicy::Reject => {
+                warn!("Rejected request body on {} {}", req.method, req.path);
+                Err(Error::InvalidRequestBody(format!(
+                    "{} requests must not have a body",
+                    req.method
+                )))
+            }
+        }
+    }
+}
+
+/// GET/HEAD/DELETEリクエストのボディに対する既定の方針を取得する
+/// 優先順位: 環境変数 `RUNBRIDGE_BODYLESS_METHOD_BODY`（`allow` / `ignore` / `reject`） -> デフォルト allow
+pub fn get_body_policy() -> BodyPolicy {
+    match env::var("RUNBRIDGE_BODYLESS_METHOD_BODY") {
+        Ok(value) => BodyPolicy::parse(&value).unwrap_or_else(|| {
+            warn!("Invalid RUNBRIDGE_BODYLESS_METHOD_BODY value: {:?}, using allow", value);
+            BodyPolicy::Allow
+        }),
+        Err(_) => BodyPolicy::Allow,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temp_env::with_var;
+
+    fn request(method: Method, body: &[u8]) -> Request {
+        Request::new(method, "/items/1".to_string()).with_body(body.to_vec())
+    }
+
+    #[test]
+    fn test_apply_policies() {
+        let mut req = request(Method::GET, b"{}");
+        BodyPolicy::Allow.apply(&mut req).unwrap();
+        assert!(req.body.is_some());
+
+        BodyPolicy::Ignore.apply(&mut req).unwrap();
+        assert!(req.body.is_none());
+
+        let mut req = request(Method::DELETE, b"{}");
+        assert_eq!(BodyPolicy::Reject.apply(&mut req).unwrap_err().status_code(), 400);
+
+        // 対象外のメソッドや空ボディには影響しない
+        let mut req = request(Method::POST, b"{}");
+        BodyPolicy::Reject.apply(&mut req).unwrap();
+        let mut req = request(Method::HEAD, b"");
+        BodyPolicy::Reject.apply(&mut req).unwrap();
+    }
+
+    #[test]
+    fn test_get_body_policy_from_env() {
+        with_var("RUNBRIDGE_BODYLESS_METHOD_BODY", None::<&str>, || {
+            assert_eq!(get_body_policy(), BodyPolicy::Allow);
+        });
+        with_var("RUNBRIDGE_BODYLESS_METHOD_BODY", Some("Reject"), || {
+            assert_eq!(get_body_policy(), BodyPolicy::Reject);
+        });
+        with_var("RUNBRIDGE_BODYLESS_METHOD_BODY", Some("drop"), || {
+            assert_eq!(get_body_policy(), BodyPolicy::Allow);
+        });
+    }
+}
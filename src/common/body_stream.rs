@@ -0,0 +1,301 @@
+//! リクエストボディの逐次読み込み（サイズ上限付き）
+//!
+//! gzipボディを解凍しながら読み込み、上限を超えた時点で読み込みを中断します。
+//! 解凍後のボディ全体をメモリに展開せずに処理したいハンドラーは`Request::body_reader`を使用します。
+
+use std::fmt;
+use std::io::{self, Read};
+
+use flate2::read::GzDecoder;
+
+use crate::error::Error;
+use super::content_digest::ENCODED_BODY_CONTEXT_KEY;
+use super::http::Request;
+use super::utils::get_max_body_size;
+
+/// 上限を超えたことを示す`io::Error`の中身
+#[derive(Debug)]
+struct LimitExceeded {
+    limit: usize,
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Decompressed body too large (>{} bytes)", self.limit)
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+/// 読み込んだ総バイト数が上限を超えた時点でエラーを返すReader
+pub struct LimitedReader<R> {
+    inner: R,
+    limit: usize,
+    read: usize,
+}
+
+impl<R: Read> LimitedReader<R> {
+    /// 上限（バイト）を指定して作成
+    pub fn new(inner: R, limit: usize) -> Self {
+        Self { inner, limit, read: 0 }
+    }
+
+    /// これまでに読み込んだバイト数
+    pub fn bytes_read(&self) -> usize {
+        self.read
+    }
+}
+
+impl<R: Read> Read for LimitedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // 上限ちょうどで終わるボディを許可するため、上限+1バイトまで読んで判定する
+        let allowed = (self.limit - self.read.min(self.limit)).saturating_add(1).min(buf.len());
+        let n = self.inner.read(&mut buf[..allowed])?;
+        self.read += n;
+        if self.read > self.limit {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, LimitExceeded { limit: self.limit }));
+        }
+        Ok(n)
+    }
+}
+
+/// リクエストボディのReader（`Content-Encoding: gzip`の場合は逐次解凍）
+pub enum BodyReader<'a> {
+    /// そのままのボディ
+    Plain(&'a [u8]),
+    /// gzipを解凍しながら読み込む
+    Gzip(Box<LimitedReader<GzDecoder<&'a [u8]>>>),
+}
+
+impl Read for BodyReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            BodyReader::Plain(body) => body.read(buf),
+            BodyReader::Gzip(reader) => reader.read(buf),
+        }
+    }
+}
+
+/// 読み込み中の`io::Error`を`Error`に変換（上限超過は413、それ以外は400）
+pub fn map_read_error(e: io::Error) -> Error {
+    match e.get_ref().and_then(|inner| inner.downcast_ref::<LimitExceeded>()) {
+        Some(exceeded) => {
+            log::warn!("Request body exceeded the limit of {} bytes while decompressing", exceeded.limit);
+            Error::PayloadTooLarge(exceeded.to_string())
+        }
+        None => {
+            log::warn!("Failed to decompress gzip body: {}", e);
+            Error::InvalidRequestBody(format!("Invalid gzip-encoded request body: {}", e))
+        }
+    }
+}
+
+/// 宣言されたボディの長さ（`Content-Length`）が上限を超える場合は、ボディを読まずに`PayloadTooLarge`を返す
+pub fn check_declared_length(declared_length: Option<usize>, limit: usize) -> Result<(), Error> {
+    match declared_length {
+        Some(length) if length > limit => {
+            log::warn!("Rejected request body of declared size {} bytes (limit {} bytes)", length, limit);
+            Err(Error::PayloadTooLarge(format!(
+                "Request body size {} bytes exceeds maximum allowed size {} bytes",
+                length, limit
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// 受信中のボディが上限を超えた時点の`PayloadTooLarge`
+pub fn body_limit_exceeded(limit: usize) -> Error {
+    log::warn!("Request body exceeded the limit of {} bytes while reading", limit);
+    Error::PayloadTooLarge(format!("Request body exceeds maximum allowed size {} bytes", limit))
+}
+
+/// Readerからボディを逐次読み込む（上限を超えた時点で残りを読まずに`PayloadTooLarge`を返す）
+///
+/// 宣言された長さがある場合はその長さだけ読み込み、途中で終わった場合は`InvalidRequestBody`を返します。
+/// 宣言が無い場合（チャンク転送など）は終端まで読み込みます。
+pub fn read_body_limited<R: Read>(reader: R, declared_length: Option<usize>, limit: usize) -> Result<Vec<u8>, Error> {
+    // 宣言値を信用しすぎないよう、最初に確保する領域は上限を設ける
+    const INITIAL_CAPACITY: usize = 64 * 1024;
+    check_declared_length(declared_length, limit)?;
+
+    let mut body = Vec::with_capacity(declared_length.unwrap_or(0).min(INITIAL_CAPACITY));
+    let result = match declared_length {
+        Some(length) => LimitedReader::new(reader.take(length as u64), limit).read_to_end(&mut body),
+        None => LimitedReader::new(reader, limit).read_to_end(&mut body),
+    };
+    if let Err(e) = result {
+        return Err(match e.get_ref().and_then(|inner| inner.downcast_ref::<LimitExceeded>()) {
+            Some(_) => body_limit_exceeded(limit),
+            None => Error::InvalidRequestBody(format!("Failed to read request body: {}", e)),
+        });
+    }
+    if let Some(length) = declared_length.filter(|length| body.len() < *length) {
+        return Err(Error::InvalidRequestBody(format!(
+            "Request body ended after {} of {} bytes",
+            body.len(),
+            length
+        )));
+    }
+    Ok(body)
+}
+
+fn is_gzip(req: &Request) -> bool {
+    req.headers
+        .get("content-encoding")
+        .is_some_and(|encoding| encoding.eq_ignore_ascii_case("gzip"))
+}
+
+impl Request {
+    /// ボディを逐次読み込むReaderを取得（ボディが無い場合は空）
+    ///
+    /// `Content-Encoding: gzip`が残っている場合は読み込みながら解凍し、解凍後のサイズが
+    /// `RUNBRIDGE_MAX_BODY_SIZE`を超えた時点で読み込みエラーになります（`map_read_error`で413に変換）。
+    /// ルート固有の上限を使う場合は`body_reader_with_limit`を使用します。
+    pub fn body_reader(&self) -> BodyReader<'_> {
+        self.body_reader_with_limit(get_max_body_size())
+    }
+
+    /// 解凍後のサイズの上限を指定してボディのReaderを取得（`RunBridge::max_body_size_for`の値を渡す）
+    pub fn body_reader_with_limit(&self, limit: usize) -> BodyReader<'_> {
+        let body = self.body.as_deref().unwrap_or(&[]);
+        if is_gzip(self) {
+            BodyReader::Gzip(Box::new(LimitedReader::new(GzDecoder::new(body), limit)))
+        } else {
+            BodyReader::Plain(body)
+        }
+    }
+
+    /// リクエストボディがgzipエンコードされている場合は解凍する
+    /// Content-Encodingヘッダーをチェックし、gzipの場合のみ処理を実行
+    /// 解凍後のサイズが`RUNBRIDGE_MAX_BODY_SIZE`を超える場合は、その時点で読み込みを中断してPayloadTooLargeエラーを返す
+    pub fn decompress_gzip_body(&mut self) -> Result<(), Error> {
+        self.decompress_gzip_body_with_limit(get_max_body_size())
+    }
+
+    /// 解凍後のサイズの上限を指定してgzipボディを解凍する
+    ///
+    /// 各ランタイムはマッチしたルートの上限（`RunBridge::max_body_size_for`）を渡します。
+    pub fn decompress_gzip_body_with_limit(&mut self, limit: usize) -> Result<(), Error> {
+        if !is_gzip(self) || self.body.is_none() {
+            return Ok(());
+        }
+        let mut decompressed = Vec::new();
+        self.body_reader_with_limit(limit)
+            .read_to_end(&mut decompressed)
+            .map_err(map_read_error)?;
+
+        // 解凍成功：ボディを更新し、Content-Encodingヘッダーを削除
+        let encoded = self.body.replace(decompressed);
+        // Content-Digestは転送時のバイト列に対するものなので、照合用に解凍前のボディを残す
+        if self.headers.contains_key("content-digest") || self.headers.contains_key("digest") {
+            if let Some(encoded) = encoded {
+                self.context_mut().set(ENCODED_BODY_CONTEXT_KEY, encoded);
+            }
+        }
+        self.headers.remove("content-encoding");
+        log::debug!("Successfully decompressed gzip request body");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Method;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_limited_reader_boundary() {
+        let mut exact = Vec::new();
+        LimitedReader::new(&[1u8; 16][..], 16).read_to_end(&mut exact).unwrap();
+        assert_eq!(exact.len(), 16);
+
+        let mut reader = LimitedReader::new(&[1u8; 17][..], 16);
+        let err = reader.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(map_read_error(err).status_code(), 413);
+        // 上限+1バイトで中断し、残りは読まない
+        assert_eq!(reader.bytes_read(), 17);
+    }
+
+    /// 読み込んだバイト数を記録するReader
+    struct CountingReader<'a> {
+        inner: &'a [u8],
+        read: &'a std::cell::Cell<usize>,
+    }
+
+    impl Read for CountingReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            // 1回に最大4KBずつ返す（ネットワークからの受信を模倣）
+            let len = buf.len().min(4096);
+            let n = self.inner.read(&mut buf[..len])?;
+            self.read.set(self.read.get() + n);
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_read_body_limited() {
+        let data = vec![b'x'; 64 * 1024];
+        let read = std::cell::Cell::new(0);
+        let reader = || CountingReader { inner: &data, read: &read };
+
+        assert_eq!(read_body_limited(reader(), Some(10), 1024).unwrap(), b"xxxxxxxxxx");
+        assert_eq!(read_body_limited(reader(), None, 64 * 1024).unwrap().len(), 64 * 1024);
+
+        // 宣言値が上限を超える場合は1バイトも読まない
+        read.set(0);
+        assert_eq!(read_body_limited(reader(), Some(2048), 1024).unwrap_err().status_code(), 413);
+        assert_eq!(read.get(), 0);
+
+        // 宣言が無い場合は上限を超えた時点で中断する
+        let err = read_body_limited(reader(), None, 1024).unwrap_err();
+        assert_eq!(err.status_code(), 413);
+        assert!(read.get() <= 4096, "read {} bytes", read.get());
+
+        let short = read_body_limited(&b"abc"[..], Some(5), 1024).unwrap_err();
+        assert_eq!(short.status_code(), 400);
+    }
+
+    #[test]
+    fn test_body_reader_streams_gzip() {
+        let req = Request::new(Method::POST, "/".to_string())
+            .with_header("Content-Encoding", "GZIP")
+            .with_body(gzip(b"hello streaming"));
+        let mut text = String::new();
+        req.body_reader().read_to_string(&mut text).unwrap();
+        assert_eq!(text, "hello streaming");
+
+        temp_env::with_var("RUNBRIDGE_MAX_BODY_SIZE", Some("1024"), || {
+            let mut req = Request::new(Method::POST, "/".to_string())
+                .with_header("Content-Encoding", "gzip")
+                .with_body(gzip(&[b'A'; 64 * 1024]));
+            let mut reader = req.body_reader();
+            assert!(reader.read_to_end(&mut Vec::new()).is_err());
+            match reader {
+                BodyReader::Gzip(limited) => assert_eq!(limited.bytes_read(), 1025),
+                BodyReader::Plain(_) => panic!("expected gzip reader"),
+            }
+            assert_eq!(req.decompress_gzip_body().unwrap_err().status_code(), 413);
+        });
+    }
+
+    #[test]
+    fn test_decompress_with_route_limit() {
+        let mut req = Request::new(Method::POST, "/".to_string())
+            .with_header("Content-Encoding", "gzip")
+            .with_body(gzip(&[b'A'; 4096]));
+        assert_eq!(req.decompress_gzip_body_with_limit(1024).unwrap_err().status_code(), 413);
+        req.decompress_gzip_body_with_limit(4096).unwrap();
+        assert_eq!(req.body.as_ref().map(Vec::len), Some(4096));
+        assert!(!req.headers.contains_key("content-encoding"));
+    }
+}
@@ -0,0 +1,767 @@
+//! レスポンスのキャッシュ
+//!
+//! `ResponseCacheMiddleware`をAroundMiddlewareとして登録すると、GETリクエストに対する
+//! 200レスポンスを一定時間メモリに保持し、同じキャッシュキーのリクエストにはハンドラーを
+//! 呼ばずに返します。キャッシュキーには既定でメソッド・`Host`・パス・クエリ全体を使用し、
+//! ヘッダー・認証済みの主体・クエリの一部を含めるように変更できます。
+//!
+//! キャッシュから返す場合はルートのガードやハンドラー内の認証チェックも実行されないため、
+//! `Authorization`・`Cookie`を含むリクエストは、`vary_on_header`でそのヘッダーを、または
+//! `vary_on_identity`で主体をキーに含めた場合（`key_fn`の場合は常に）のみキャッシュを使用します。
+//!
+//! クライアントが`Cache-Control: no-cache`（`max-age=0`・`no-store`を含む）または
+//! `Pragma: no-cache`を送った場合は、キャッシュを使わずにハンドラーを呼び出します。
+//!
+//! 保存先は既定でインスタンスごとのメモリ（`MemoryCacheStore`）です。複数のインスタンスで
+//! 共有する場合は`CacheStore`を実装したRedis・DynamoDBなどの保存先を`store`で指定します。
+//!
+//! ```
+//! use std::time::Duration;
+//! use runbridge::common::ResponseCacheMiddleware;
+//!
+//! let cache = ResponseCacheMiddleware::new(Duration::from_secs(30))
+//!     .vary_on_header("Accept-Language")
+//!     .vary_on_identity();
+//! let app = runbridge::RunBridge::builder().around(cache.clone()).build();
+//! # drop(app);
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use async_trait::async_trait;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+use super::content_type::replace_header;
+use super::etag::strong_etag;
+use super::http::{Method, Request, Response};
+use super::traits::{AroundMiddleware, Next};
+use super::utils::percent_encode;
+
+/// キャッシュの既定の最大件数
+pub const DEFAULT_CACHE_CAPACITY: usize = 1024;
+
+/// キャッシュの利用結果を示すレスポンスヘッダー（`HIT` / `MISS` / `BYPASS`）
+pub const CACHE_STATUS_HEADER: &str = "X-Cache";
+
+/// キャッシュキーに含めない限りキャッシュを使わないリクエストヘッダー（小文字）
+const CREDENTIAL_HEADERS: [&str; 2] = ["authorization", "cookie"];
+
+/// 認証情報（`Authorization`・`Cookie`）を持つリクエストか
+fn has_credentials(req: &Request) -> bool {
+    CREDENTIAL_HEADERS.iter().any(|name| req.headers.contains_key(*name))
+}
+
+type KeyFn = Arc<dyn Fn(&Request) -> Option<String> + Send + Sync>;
+
+/// キャッシュキーの組み立て方
+#[derive(Clone)]
+enum KeyStrategy {
+    /// メソッド・パス・クエリに、指定したヘッダーと主体を加える
+    Default {
+        /// キーに含めるクエリパラメータ（Noneの場合は全て）
+        query: Option<Vec<String>>,
+        /// キーに含めるリクエストヘッダー（小文字）
+        headers: Vec<String>,
+        /// 認証済みの主体を含めるか
+        identity: bool,
+    },
+    /// 任意の関数（Noneを返したリクエストはキャッシュしない）
+    Custom(KeyFn),
+}
+
+/// キャッシュの利用状況
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheMetrics {
+    /// キャッシュから返した回数
+    pub hits: u64,
+    /// キャッシュに無くハンドラーを呼び出した回数
+    pub misses: u64,
+    /// クライアントの指示またはキーを作れないためキャッシュを使わなかった回数
+    pub bypasses: u64,
+    /// 現在保持している件数
+    pub entries: usize,
+}
+
+/// 保存先に格納するレスポンス（JSONなどにシリアライズして外部の保存先に格納可能）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedResponse {
+    /// キャッシュしたレスポンス
+    #[serde(with = "crate::common::interop::response_serde")]
+    pub response: Response,
+    /// 格納した時刻
+    pub stored_at: SystemTime,
+}
+
+impl CachedResponse {
+    /// 格納してからの経過時間
+    pub fn age(&self) -> Duration {
+        self.stored_at.elapsed().unwrap_or_default()
+    }
+}
+
+/// キャッシュの保存先
+///
+/// 保存先のエラーはキャッシュの失敗として扱い（警告ログを出力）、リクエストはハンドラーで処理します。
+#[async_trait]
+pub trait CacheStore: Send + Sync {
+    /// キーのレスポンスを取得（無い・期限切れの場合はNone）
+    async fn get(&self, key: &str) -> Result<Option<CachedResponse>, Error>;
+
+    /// レスポンスを保存（`ttl`を過ぎたら取得できないようにする）
+    async fn put(&self, key: &str, entry: CachedResponse, ttl: Duration) -> Result<(), Error>;
+
+    /// 全てのレスポンスを削除
+    async fn clear(&self) -> Result<(), Error>;
+
+    /// 保持している件数（数えられない保存先はNone）
+    fn entry_count(&self) -> Option<usize> {
+        None
+    }
+}
+
+struct MemoryEntry {
+    entry: CachedResponse,
+    expires_at: Instant,
+}
+
+/// インスタンスのメモリに保持する保存先（件数の上限を超えた場合は最も古いものから削除）
+pub struct MemoryCacheStore {
+    capacity: usize,
+    entries: Mutex<HashMap<String, MemoryEntry>>,
+}
+
+impl fmt::Debug for MemoryCacheStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryCacheStore").field("capacity", &self.capacity).finish()
+    }
+}
+
+impl Default for MemoryCacheStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_CACHE_CAPACITY)
+    }
+}
+
+impl MemoryCacheStore {
+    /// 最大件数を指定して作成
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity: capacity.max(1), entries: Mutex::new(HashMap::new()) }
+    }
+
+    fn lock_entries(&self) -> std::sync::MutexGuard<'_, HashMap<String, MemoryEntry>> {
+        // ポイズン状態でもキャッシュは使い続ける
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl CacheStore for MemoryCacheStore {
+    async fn get(&self, key: &str) -> Result<Option<CachedResponse>, Error> {
+        let mut entries = self.lock_entries();
+        match entries.get(key) {
+            Some(stored) if stored.expires_at > Instant::now() => Ok(Some(stored.entry.clone())),
+            Some(_) => {
+                entries.remove(key);
+                Ok(None)
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn put(&self, key: &str, entry: CachedResponse, ttl: Duration) -> Result<(), Error> {
+        let mut entries = self.lock_entries();
+        if entries.len() >= self.capacity && !entries.contains_key(key) {
+            let now = Instant::now();
+            entries.retain(|_, stored| stored.expires_at > now);
+            if entries.len() >= self.capacity {
+                let oldest = entries
+                    .iter()
+                    .min_by_key(|(_, stored)| stored.entry.stored_at)
+                    .map(|(k, _)| k.clone());
+                if let Some(oldest) = oldest {
+                    entries.remove(&oldest);
+                }
+            }
+        }
+        entries.insert(key.to_string(), MemoryEntry { entry, expires_at: Instant::now() + ttl });
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<(), Error> {
+        self.lock_entries().clear();
+        Ok(())
+    }
+
+    fn entry_count(&self) -> Option<usize> {
+        Some(self.lock_entries().len())
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    bypasses: AtomicU64,
+}
+
+/// GETリクエストのレスポンスをキャッシュするAroundMiddleware
+///
+/// 次のレスポンスはキャッシュしません。
+/// - 200以外のステータス、またはハンドラーのエラー
+/// - `Set-Cookie`を含む、または`Cache-Control`に`no-store` / `private`を含む
+/// - Server-Sent Eventsのストリーム
+/// - `Vary`にキーに含まれないリクエストヘッダー（または`*`）を含む（圧縮・コンテンツネゴシエーションの
+///   結果を他のクライアントに返さないため）。`vary_on_header`でキーに含めたヘッダーのみ許可します。
+///   `key_fn`でキーを作成する場合は`Vary`を含むレスポンスを保存しません
+///
+/// キャッシュするレスポンスに`Cache-Control`・`ETag`が無い場合は、保持期間の`max-age`と
+/// ボディのハッシュによる`ETag`を付与します（`emit_cache_headers(false)`で無効化）。
+///
+/// Cloneしたインスタンスは同じキャッシュを共有するため、1つをビルダーに登録し、
+/// もう1つを`metrics`・`clear`に使用できます。
+#[derive(Clone)]
+pub struct ResponseCacheMiddleware {
+    ttl: Duration,
+    key: KeyStrategy,
+    honor_client_bypass: bool,
+    emit_cache_headers: bool,
+    store: Arc<dyn CacheStore>,
+    counters: Arc<Counters>,
+}
+
+/// `ResponseCacheMiddleware`の別名
+pub type CacheMiddleware = ResponseCacheMiddleware;
+
+impl fmt::Debug for ResponseCacheMiddleware {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseCacheMiddleware")
+            .field("ttl", &self.ttl)
+            .field("honor_client_bypass", &self.honor_client_bypass)
+            .field("emit_cache_headers", &self.emit_cache_headers)
+            .finish()
+    }
+}
+
+impl ResponseCacheMiddleware {
+    /// 保持期間を指定して作成（保存先はメモリ）
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            key: KeyStrategy::Default { query: None, headers: Vec::new(), identity: false },
+            honor_client_bypass: true,
+            emit_cache_headers: true,
+            store: Arc::new(MemoryCacheStore::default()),
+            counters: Arc::new(Counters::default()),
+        }
+    }
+
+    /// メモリに保持する最大件数（超えた場合は最も古いものから削除、保存先をメモリに置き換える）
+    pub fn capacity(self, capacity: usize) -> Self {
+        self.store(MemoryCacheStore::new(capacity))
+    }
+
+    /// 保存先を指定（Redis・DynamoDBなど、インスタンス間で共有する場合）
+    pub fn store<S: CacheStore + 'static>(mut self, store: S) -> Self {
+        self.store = Arc::new(store);
+        self
+    }
+
+    /// キャッシュするレスポンスに`Cache-Control`・`ETag`を付与するか（既定 true）
+    pub fn emit_cache_headers(mut self, emit: bool) -> Self {
+        self.emit_cache_headers = emit;
+        self
+    }
+
+    /// リクエストヘッダーの値をキーに含める（`Accept-Language`など、レスポンスが変わるヘッダー）
+    ///
+    /// レスポンスの`Vary`に含まれるヘッダーは、ここで指定した場合のみキャッシュされます。
+    /// `Authorization`・`Cookie`を指定すると、そのヘッダーを持つリクエストもキャッシュの対象になります。
+    pub fn vary_on_header(mut self, name: &str) -> Self {
+        if let KeyStrategy::Default { headers, .. } = &mut self.key {
+            headers.push(name.to_ascii_lowercase());
+        }
+        self
+    }
+
+    /// 認証済みの主体（`Identity::subject`）をキーに含める（ユーザーごとに異なるレスポンス向け）
+    ///
+    /// `Authorization`・`Cookie`を持つリクエストもキャッシュの対象になります。
+    pub fn vary_on_identity(mut self) -> Self {
+        if let KeyStrategy::Default { identity, .. } = &mut self.key {
+            *identity = true;
+        }
+        self
+    }
+
+    /// キーに含めるクエリパラメータを限定（トラッキング用のパラメータなどを無視する場合）
+    pub fn query_params<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        if let KeyStrategy::Default { query, .. } = &mut self.key {
+            *query = Some(names.into_iter().map(Into::into).collect());
+        }
+        self
+    }
+
+    /// キーを任意の関数で作成（Noneを返したリクエストはキャッシュしない）
+    ///
+    /// `vary_on_header`・`vary_on_identity`・`query_params`の設定は置き換えられます。
+    /// 認証情報を持つリクエストの扱いも関数に委ねられるため、必要に応じてNoneを返してください。
+    pub fn key_fn<F>(mut self, key: F) -> Self
+    where
+        F: Fn(&Request) -> Option<String> + Send + Sync + 'static,
+    {
+        self.key = KeyStrategy::Custom(Arc::new(key));
+        self
+    }
+
+    /// クライアントの`Cache-Control: no-cache` / `Pragma: no-cache`に従うか（既定 true）
+    pub fn honor_client_bypass(mut self, honor: bool) -> Self {
+        self.honor_client_bypass = honor;
+        self
+    }
+
+    /// 利用状況を取得（`entries`は件数を数えられない保存先では0）
+    pub fn metrics(&self) -> CacheMetrics {
+        CacheMetrics {
+            hits: self.counters.hits.load(Ordering::Relaxed),
+            misses: self.counters.misses.load(Ordering::Relaxed),
+            bypasses: self.counters.bypasses.load(Ordering::Relaxed),
+            entries: self.store.entry_count().unwrap_or(0),
+        }
+    }
+
+    /// 保持している全てのレスポンスを削除
+    pub async fn clear(&self) -> Result<(), Error> {
+        self.store.clear().await
+    }
+
+    /// リクエストのキャッシュキー（キャッシュしない場合はNone）
+    ///
+    /// 既定のキーでは、キーに含まれない`Authorization`・`Cookie`を持つリクエストはNoneになります。
+    pub fn cache_key(&self, req: &Request) -> Option<String> {
+        let (query, headers, identity) = match &self.key {
+            KeyStrategy::Custom(key) => return key(req),
+            KeyStrategy::Default { query, headers, identity } => (query, headers, *identity),
+        };
+        // 他の利用者・テナントのレスポンスを返さないよう、キーに含まれない認証情報があればキャッシュしない
+        let uncovered = CREDENTIAL_HEADERS
+            .iter()
+            .find(|name| req.headers.contains_key(**name) && !identity && !headers.iter().any(|h| h == *name));
+        if let Some(name) = uncovered {
+            debug!("Bypassing response cache for request with '{}' not covered by the cache key", name);
+            return None;
+        }
+
+        let mut params: Vec<(&String, &String)> = req
+            .query_params
+            .iter()
+            .filter(|(k, _)| query.as_ref().is_none_or(|names| names.contains(k)))
+            .collect();
+        params.sort();
+        let query = params
+            .iter()
+            .map(|(k, v)| format!("{}={}", percent_encode(k), percent_encode(v)))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let host = req.headers.get("host").map(|h| percent_encode(h.trim())).unwrap_or_default();
+        let mut key = format!("{} {}{}?{}", req.method, host, req.path, query);
+        for name in headers {
+            let value = req.headers.get(name).map(String::as_str).unwrap_or("");
+            key.push_str(&format!("\n{}: {}", name, percent_encode(value)));
+        }
+        if identity {
+            let subject = req.identity().map(|i| i.subject().to_string());
+            key.push_str(&format!("\nsubject: {}", subject.as_deref().map(percent_encode).unwrap_or_default()));
+        }
+        Some(key)
+    }
+
+    async fn lookup(&self, key: &str) -> Option<Response> {
+        let entry = match self.store.get(key).await {
+            Ok(entry) => entry?,
+            Err(e) => {
+                warn!("Response cache lookup failed: {}", e);
+                return None;
+            }
+        };
+        let age = entry.age();
+        if age >= self.ttl {
+            return None;
+        }
+        let mut res = entry.response;
+        res.headers.insert("Age".to_string(), age.as_secs().to_string());
+        Some(res)
+    }
+
+    async fn save(&self, key: &str, res: &Response) {
+        let entry = CachedResponse { response: res.clone(), stored_at: SystemTime::now() };
+        if let Err(e) = self.store.put(key, entry, self.ttl).await {
+            warn!("Response cache store failed: {}", e);
+        }
+    }
+
+    fn is_cacheable(&self, res: &Response) -> bool {
+        let cache_control = res.header("cache-control").unwrap_or("").to_ascii_lowercase();
+        res.status == 200
+            && !res.is_sse()
+            && !res.has_set_cookie()
+            && !cache_control.split(',').any(|d| matches!(d.trim(), "no-store" | "private"))
+            && self.is_vary_covered(res)
+    }
+
+    /// `Vary`の全てのヘッダーがキャッシュキーに含まれるか
+    fn is_vary_covered(&self, res: &Response) -> bool {
+        let Some(vary) = res.header("vary") else {
+            return true;
+        };
+        let key_headers: &[String] = match &self.key {
+            KeyStrategy::Default { headers, .. } => headers,
+            KeyStrategy::Custom(_) => &[],
+        };
+        let covered = vary
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .all(|name| name != "*" && key_headers.iter().any(|h| h.eq_ignore_ascii_case(name)));
+        if !covered {
+            debug!("Not caching response with Vary '{}' not covered by the cache key", vary);
+        }
+        covered
+    }
+
+    /// `Cache-Control`・`ETag`が無い場合に付与
+    ///
+    /// 認証情報を持つリクエストへのレスポンスは共有キャッシュに保存されないよう`private`にします。
+    fn add_cache_headers(&self, res: &mut Response, credentialed: bool) {
+        if res.header("cache-control").is_none() {
+            let scope = if credentialed { "private" } else { "public" };
+            res.headers.insert("Cache-Control".to_string(), format!("{}, max-age={}", scope, self.ttl.as_secs()));
+        }
+        if res.header("etag").is_none() {
+            let etag = strong_etag(res.body.as_deref().unwrap_or_default());
+            res.headers.insert("ETag".to_string(), etag);
+        }
+    }
+}
+
+/// クライアントがキャッシュの再検証を求めているか（`Cache-Control: no-cache` / `no-store` / `max-age=0`、`Pragma: no-cache`）
+pub fn client_requests_bypass(req: &Request) -> bool {
+    let cache_control = req.headers.get("cache-control").map(String::as_str).unwrap_or("");
+    let directives = cache_control.split(',').map(|d| d.trim().to_ascii_lowercase());
+    let bypass = directives
+        .into_iter()
+        .any(|d| d == "no-cache" || d == "no-store" || d.replace(' ', "") == "max-age=0");
+    // Pragmaは`Cache-Control`が無い場合のみ参照（RFC 9111）
+    bypass
+        || (cache_control.is_empty()
+            && req
+                .headers
+                .get("pragma")
+                .is_some_and(|v| v.split(',').any(|d| d.trim().eq_ignore_ascii_case("no-cache"))))
+}
+
+
+fn with_cache_status(mut res: Response, status: &str) -> Response {
+    replace_header(&mut res.headers, CACHE_STATUS_HEADER.to_string(), status.to_string());
+    res
+}
+
+#[async_trait]
+impl AroundMiddleware for ResponseCacheMiddleware {
+    async fn around(&self, req: Request, next: Next<'_>) -> Result<Response, Error> {
+        if req.method != Method::GET {
+            return next.run(req).await;
+        }
+        let key = match self.cache_key(&req) {
+            Some(key) => key,
+            None => {
+                self.counters.bypasses.fetch_add(1, Ordering::Relaxed);
+                return next.run(req).await.map(|res| with_cache_status(res, "BYPASS"));
+            }
+        };
+
+        let bypass = self.honor_client_bypass && client_requests_bypass(&req);
+        if bypass {
+            self.counters.bypasses.fetch_add(1, Ordering::Relaxed);
+        } else if let Some(res) = self.lookup(&key).await {
+            self.counters.hits.fetch_add(1, Ordering::Relaxed);
+            debug!("Response cache hit: {}", key.lines().next().unwrap_or(""));
+            return Ok(with_cache_status(res, "HIT"));
+        } else {
+            self.counters.misses.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let credentialed = has_credentials(&req);
+        let mut res = next.run(req).await?;
+        if self.is_cacheable(&res) {
+            if self.emit_cache_headers {
+                self.add_cache_headers(&mut res, credentialed);
+            }
+            // 再検証したレスポンスでキャッシュを更新
+            self.save(&key, &res).await;
+        }
+        Ok(with_cache_status(res, if bypass { "BYPASS" } else { "MISS" }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+    use crate::common::{Handler, UserIdentity};
+
+    /// 呼び出し回数をボディとして返すハンドラー
+    fn counting_handler() -> impl Handler {
+        let calls = AtomicUsize::new(0);
+        crate::handler::get("^/.*$", move |_req: Request| {
+            let n = calls.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok(Response::ok().with_body(n.to_string().into_bytes()))
+        })
+    }
+
+    async fn get(cache: &ResponseCacheMiddleware, handler: &impl Handler, req: Request) -> (String, String) {
+        let around: Vec<Box<dyn AroundMiddleware>> = vec![Box::new(cache.clone())];
+        let res = Next::new(handler, &around).run(req).await.unwrap();
+        let status = res.header(CACHE_STATUS_HEADER).unwrap().to_string();
+        (String::from_utf8(res.body.unwrap()).unwrap(), status)
+    }
+
+    fn request(path: &str) -> Request {
+        Request::new(Method::GET, path.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_hit_miss_and_client_bypass() {
+        let cache = ResponseCacheMiddleware::new(Duration::from_secs(60));
+        let handler = counting_handler();
+
+        assert_eq!(get(&cache, &handler, request("/a")).await, ("1".into(), "MISS".into()));
+        assert_eq!(get(&cache, &handler, request("/a")).await, ("1".into(), "HIT".into()));
+
+        // no-cacheは再取得してキャッシュを更新
+        let req = request("/a").with_header("Cache-Control", "no-cache");
+        assert_eq!(get(&cache, &handler, req).await, ("2".into(), "BYPASS".into()));
+        assert_eq!(get(&cache, &handler, request("/a")).await, ("2".into(), "HIT".into()));
+        let req = request("/a").with_header("Pragma", "no-cache");
+        assert_eq!(get(&cache, &handler, req).await.1, "BYPASS");
+
+        assert_eq!(
+            cache.metrics(),
+            CacheMetrics { hits: 2, misses: 1, bypasses: 2, entries: 1 }
+        );
+
+        let strict = ResponseCacheMiddleware::new(Duration::from_secs(60)).honor_client_bypass(false);
+        get(&strict, &handler, request("/a")).await;
+        let req = request("/a").with_header("Cache-Control", "no-cache");
+        assert_eq!(get(&strict, &handler, req).await.1, "HIT");
+    }
+
+    #[tokio::test]
+    async fn test_custom_cache_keys() {
+        let cache = ResponseCacheMiddleware::new(Duration::from_secs(60))
+            .query_params(["page"])
+            .vary_on_header("Accept-Language")
+            .vary_on_identity();
+
+        let mut alice = request("/items")
+            .with_query_param("page", "1")
+            .with_query_param("utm_source", "mail")
+            .with_header("Accept-Language", "ja")
+            .with_header("Host", "api.example.com");
+        alice.set_identity(UserIdentity::new("alice"), "test");
+        let key = cache.cache_key(&alice).unwrap();
+        assert_eq!(key, "GET api.example.com/items?page=1\naccept-language: ja\nsubject: alice");
+
+        // Hostが異なるリクエストは別のキー
+        let mut other_host = request("/items")
+            .with_query_param("page", "1")
+            .with_header("Accept-Language", "ja")
+            .with_header("Host", "tenant-b.example.com");
+        other_host.set_identity(UserIdentity::new("alice"), "test");
+        assert_ne!(cache.cache_key(&other_host).unwrap(), key);
+
+        let mut bob = request("/items").with_query_param("page", "1").with_header("Accept-Language", "ja");
+        bob.set_identity(UserIdentity::new("bob"), "test");
+        assert_ne!(cache.cache_key(&bob).unwrap(), key);
+
+        // キーを作れないリクエストはキャッシュしない
+        let cache = ResponseCacheMiddleware::new(Duration::from_secs(60))
+            .key_fn(|req| req.headers.get("x-tenant").map(|t| format!("{}:{}", t, req.path)));
+        let handler = counting_handler();
+        assert_eq!(get(&cache, &handler, request("/a")).await, ("1".into(), "BYPASS".into()));
+        let tenant = || request("/a").with_header("X-Tenant", "t1");
+        assert_eq!(get(&cache, &handler, tenant()).await, ("2".into(), "MISS".into()));
+        assert_eq!(get(&cache, &handler, tenant()).await, ("2".into(), "HIT".into()));
+    }
+
+    #[tokio::test]
+    async fn test_credentials_not_in_key_bypass_cache() {
+        let handler = counting_handler();
+        let with_auth = |token: &str| request("/me").with_header("Authorization", format!("Bearer {}", token));
+
+        // Authorizationだけが異なるリクエストに他の利用者のレスポンスを返さない
+        let cache = ResponseCacheMiddleware::new(Duration::from_secs(60));
+        assert_eq!(get(&cache, &handler, with_auth("alice")).await, ("1".into(), "BYPASS".into()));
+        assert_eq!(get(&cache, &handler, with_auth("bob")).await, ("2".into(), "BYPASS".into()));
+        let cookie = request("/me").with_header("Cookie", "session=abc");
+        assert_eq!(get(&cache, &handler, cookie).await, ("3".into(), "BYPASS".into()));
+        assert_eq!(cache.metrics().entries, 0);
+
+        // キーに含めた場合は認証情報ごとに保存する
+        let cache = ResponseCacheMiddleware::new(Duration::from_secs(60)).vary_on_header("Authorization");
+        assert_eq!(get(&cache, &handler, with_auth("alice")).await, ("4".into(), "MISS".into()));
+        assert_eq!(get(&cache, &handler, with_auth("bob")).await, ("5".into(), "MISS".into()));
+        assert_eq!(get(&cache, &handler, with_auth("alice")).await, ("4".into(), "HIT".into()));
+        // Cookieはキーに含まれないため使用しない
+        let both = with_auth("alice").with_header("Cookie", "session=abc");
+        assert_eq!(get(&cache, &handler, both).await.1, "BYPASS");
+    }
+
+    #[tokio::test]
+    async fn test_vary_not_in_key_is_not_cached() {
+        use crate::common::CompressionMiddleware;
+
+        let large_text = crate::handler::get("^/.*$", |_req: Request| {
+            Ok(Response::ok().with_header("Content-Type", "text/plain").with_body(vec![b'a'; 4096]))
+        });
+        let run = |cache: &ResponseCacheMiddleware, req: Request| {
+            let around: Vec<Box<dyn AroundMiddleware>> =
+                vec![Box::new(cache.clone()), Box::new(CompressionMiddleware::new().min_size(256))];
+            let handler = &large_text;
+            async move { Next::new(handler, &around).run(req).await.unwrap() }
+        };
+        let gzip = || request("/a").with_header("Accept-Encoding", "gzip");
+
+        // `Vary: Accept-Encoding`がキーに含まれない場合は保存しない
+        let cache = ResponseCacheMiddleware::new(Duration::from_secs(60));
+        let res = run(&cache, gzip()).await;
+        assert_eq!(res.header("content-encoding"), Some("gzip"));
+        let res = run(&cache, request("/a")).await;
+        assert_eq!(res.header(CACHE_STATUS_HEADER), Some("MISS"));
+        assert!(res.header("content-encoding").is_none());
+        assert_eq!(res.body.as_ref().map(Vec::len), Some(4096));
+        // 非圧縮のレスポンスも`Vary: Accept-Encoding`を持つため、どちらも保存しない
+        assert_eq!(res.header("vary"), Some("Accept-Encoding"));
+        assert_eq!(cache.metrics().entries, 0);
+        assert_eq!(run(&cache, gzip()).await.header("content-encoding"), Some("gzip"));
+
+        // キーに含めた場合はヘッダーの値ごとに保存する
+        let cache = ResponseCacheMiddleware::new(Duration::from_secs(60)).vary_on_header("Accept-Encoding");
+        run(&cache, gzip()).await;
+        let res = run(&cache, gzip()).await;
+        assert_eq!(res.header(CACHE_STATUS_HEADER), Some("HIT"));
+        assert_eq!(res.header("content-encoding"), Some("gzip"));
+        let res = run(&cache, request("/a")).await;
+        assert_eq!(res.header(CACHE_STATUS_HEADER), Some("MISS"));
+        assert!(res.header("content-encoding").is_none());
+
+        // `Vary: *`と`key_fn`は常に保存しない
+        let res = Response::ok().with_header("Vary", "*");
+        assert!(!ResponseCacheMiddleware::new(Duration::from_secs(60)).vary_on_header("*").is_cacheable(&res));
+        let custom = ResponseCacheMiddleware::new(Duration::from_secs(60)).key_fn(|req| Some(req.path.clone()));
+        assert!(!custom.is_cacheable(&Response::ok().with_header("Vary", "Accept")));
+        assert!(custom.is_cacheable(&Response::ok()));
+    }
+
+    /// JSONで保持する保存先（外部の保存先の代わり）
+    #[derive(Default)]
+    struct JsonStore {
+        entries: Mutex<HashMap<String, String>>,
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl CacheStore for JsonStore {
+        async fn get(&self, key: &str) -> Result<Option<CachedResponse>, Error> {
+            if self.fail {
+                return Err(Error::InternalServerError("store unavailable".to_string()));
+            }
+            let entries = self.entries.lock().unwrap();
+            Ok(entries.get(key).map(|json| serde_json::from_str(json).unwrap()))
+        }
+
+        async fn put(&self, key: &str, entry: CachedResponse, _ttl: Duration) -> Result<(), Error> {
+            let json = serde_json::to_string(&entry).unwrap();
+            self.entries.lock().unwrap().insert(key.to_string(), json);
+            Ok(())
+        }
+
+        async fn clear(&self) -> Result<(), Error> {
+            self.entries.lock().unwrap().clear();
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_store_and_cache_headers() {
+        let cache = ResponseCacheMiddleware::new(Duration::from_secs(60)).store(JsonStore::default());
+        let handler = counting_handler();
+        let around: Vec<Box<dyn AroundMiddleware>> = vec![Box::new(cache.clone())];
+
+        let miss = Next::new(&handler, &around).run(request("/a")).await.unwrap();
+        assert_eq!(miss.header("Cache-Control"), Some("public, max-age=60"));
+        assert_eq!(miss.header("ETag"), Some(strong_etag(b"1").as_str()));
+        let hit = Next::new(&handler, &around).run(request("/a")).await.unwrap();
+        assert_eq!(hit.header(CACHE_STATUS_HEADER), Some("HIT"));
+        assert_eq!(hit.header("ETag"), miss.header("ETag"));
+        assert_eq!(hit.header("Age"), Some("0"));
+        assert_eq!(cache.metrics().entries, 0);
+
+        cache.clear().await.unwrap();
+        assert_eq!(get(&cache, &handler, request("/a")).await, ("2".into(), "MISS".into()));
+
+        // 保存先のエラーはキャッシュの失敗として扱う
+        let failing = ResponseCacheMiddleware::new(Duration::from_secs(60))
+            .store(JsonStore { fail: true, ..Default::default() })
+            .emit_cache_headers(false);
+        let around: Vec<Box<dyn AroundMiddleware>> = vec![Box::new(failing)];
+        let res = Next::new(&handler, &around).run(request("/a")).await.unwrap();
+        assert_eq!(res.header(CACHE_STATUS_HEADER), Some("MISS"));
+        assert_eq!(res.header("ETag"), None);
+    }
+
+    #[tokio::test]
+    async fn test_credentialed_responses_are_private() {
+        let cache = ResponseCacheMiddleware::new(Duration::from_secs(60)).vary_on_header("Authorization");
+        let handler = counting_handler();
+        let around: Vec<Box<dyn AroundMiddleware>> = vec![Box::new(cache.clone())];
+        let alice = || request("/me").with_header("Authorization", "Bearer alice");
+
+        // キーに含めた認証情報ごとに保存するが、共有キャッシュ向けの`public`は付与しない
+        let miss = Next::new(&handler, &around).run(alice()).await.unwrap();
+        assert_eq!(miss.header(CACHE_STATUS_HEADER), Some("MISS"));
+        assert_eq!(miss.header("Cache-Control"), Some("private, max-age=60"));
+        let hit = Next::new(&handler, &around).run(alice()).await.unwrap();
+        assert_eq!(hit.header(CACHE_STATUS_HEADER), Some("HIT"));
+        assert_eq!(hit.header("Cache-Control"), Some("private, max-age=60"));
+
+        // キーに含まれない認証情報を持つリクエストは保存もヘッダーの付与もしない
+        let cookie = request("/me").with_header("Cookie", "session=abc");
+        let res = Next::new(&handler, &around).run(cookie).await.unwrap();
+        assert_eq!(res.header(CACHE_STATUS_HEADER), Some("BYPASS"));
+        assert_eq!(res.header("Cache-Control"), None);
+        assert_eq!(cache.metrics().entries, 1);
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_capacity() {
+        let cache = ResponseCacheMiddleware::new(Duration::from_secs(60)).capacity(2);
+        let handler = counting_handler();
+        for path in ["/a", "/b", "/c"] {
+            get(&cache, &handler, request(path)).await;
+        }
+        assert_eq!(cache.metrics().entries, 2);
+        assert_eq!(get(&cache, &handler, request("/a")).await.1, "MISS");
+    }
+}
@@ -13,6 +13,8 @@ pub mod utils {
     }
 
     /// CGI環境からクッキーを抽出
+    ///
+    /// ランタイムに依存しない`Request::cookies`の使用を推奨します。
     pub fn extract_cookies() -> HashMap<String, String> {
         let mut cookies = HashMap::new();
         
@@ -53,16 +55,13 @@ pub mod utils {
 
     /// レスポンスにクッキーを設定
     pub fn set_cookie(response: &mut Response, cookie: Cookie) {
-        response.headers.insert("Set-Cookie".to_string(), cookie.to_header_value());
+        response.push_cookie(cookie);
     }
 
     /// レスポンスに複数のクッキーを設定
     pub fn set_cookies(response: &mut Response, cookies: Vec<Cookie>) {
         for cookie in cookies {
-            // Set-Cookieヘッダーは複数設定可能だが、HashMapでは上書きされるため
-            // 既存の実装では最後のクッキーのみが有効になる
-            // 実際の実装では Vec<(String, String)> を使用するか、
-            // 複数のSet-Cookieヘッダーを連結する必要がある
+            // ヘッダーのHashMapでは上書きされるため、クッキーごとに別のSet-Cookieとして保持する
             set_cookie(response, cookie);
         }
     }
@@ -113,10 +112,11 @@ mod tests {
 
         set_cookie(&mut response, cookie);
 
-        let set_cookie_header = response.headers.get("Set-Cookie");
-        assert!(set_cookie_header.is_some());
-        
-        let header_value = set_cookie_header.unwrap();
+        set_cookies(&mut response, vec![Cookie::new("other", "1")]);
+        let values = response.set_cookie_values();
+        assert_eq!(values.len(), 2);
+
+        let header_value = &values[0];
         assert!(header_value.contains("test_cookie=test_value"));
         assert!(header_value.contains("Path=/"));
         assert!(header_value.contains("Secure"));
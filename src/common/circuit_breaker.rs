@@ -0,0 +1,367 @@
+//! 外部依存ごとのサーキットブレーカー
+//!
+//! 直近の呼び出し結果の失敗率が閾値を超えると回路を開き（open）、クールダウンの間は
+//! ハンドラーを実行せずに503を返します。クールダウン後は少数の試行のみを通し（half-open）、
+//! 成功すれば閉じ（closed）、失敗すれば再び開きます。
+//! 失敗し続ける下流の応答待ちでLambdaの同時実行数を使い切らないようにするためのものです。
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+use serde::Serialize;
+
+use super::dependency::DependencyRegistry;
+
+/// 回路の状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// 通常どおり実行
+    Closed,
+    /// 実行せずに503を返す
+    Open,
+    /// 試行のみ実行
+    HalfOpen,
+}
+
+/// 回路の統計（ログ・メトリクス出力用）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CircuitMetrics {
+    /// 依存の名前
+    pub dependency: String,
+    /// 現在の状態
+    pub state: CircuitState,
+    /// 成功した呼び出しの累計
+    pub successes: u64,
+    /// 失敗した呼び出しの累計
+    pub failures: u64,
+    /// 回路が開いていたため拒否した呼び出しの累計
+    pub rejected: u64,
+    /// 回路が開いた回数
+    pub opened: u64,
+    /// 直近のウィンドウでの失敗率（0.0〜1.0）
+    pub failure_rate: f64,
+}
+
+struct State {
+    state: CircuitState,
+    window: VecDeque<bool>,
+    opened_at: Option<Instant>,
+    half_open_in_flight: u32,
+    successes: u64,
+    failures: u64,
+    rejected: u64,
+    opened: u64,
+}
+
+impl State {
+    fn failure_rate(&self) -> f64 {
+        if self.window.is_empty() {
+            return 0.0;
+        }
+        self.window.iter().filter(|ok| !**ok).count() as f64 / self.window.len() as f64
+    }
+}
+
+/// 依存ごとのサーキットブレーカー（Cloneしたインスタンスは同じ状態を共有）
+///
+/// 同じ依存を使う複数のルートに同じインスタンスのクローンを設定します。
+#[derive(Clone)]
+pub struct CircuitBreaker {
+    dependency: String,
+    failure_rate_threshold: f64,
+    minimum_calls: usize,
+    window_size: usize,
+    cool_down: Duration,
+    half_open_max_calls: u32,
+    registry: Option<DependencyRegistry>,
+    state: Arc<Mutex<State>>,
+}
+
+impl std::fmt::Debug for CircuitBreaker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CircuitBreaker")
+            .field("dependency", &self.dependency)
+            .field("state", &self.state())
+            .finish()
+    }
+}
+
+impl CircuitBreaker {
+    /// 依存の名前を指定して作成
+    ///
+    /// 既定値: 失敗率50%以上で開く、直近20回を評価（最低10回）、クールダウン30秒、half-openの試行1回
+    pub fn new(dependency: impl Into<String>) -> Self {
+        Self {
+            dependency: dependency.into(),
+            failure_rate_threshold: 0.5,
+            minimum_calls: 10,
+            window_size: 20,
+            cool_down: Duration::from_secs(30),
+            half_open_max_calls: 1,
+            registry: None,
+            state: Arc::new(Mutex::new(State {
+                state: CircuitState::Closed,
+                window: VecDeque::new(),
+                opened_at: None,
+                half_open_in_flight: 0,
+                successes: 0,
+                failures: 0,
+                rejected: 0,
+                opened: 0,
+            })),
+        }
+    }
+
+    /// 回路を開く失敗率（0.0〜1.0）
+    pub fn failure_rate_threshold(mut self, threshold: f64) -> Self {
+        self.failure_rate_threshold = threshold.clamp(0.0, 1.0);
+        self
+    }
+
+    /// 失敗率を評価するのに必要な最低呼び出し回数
+    pub fn minimum_calls(mut self, calls: usize) -> Self {
+        self.minimum_calls = calls.max(1);
+        self
+    }
+
+    /// 失敗率の評価に使う直近の呼び出し回数
+    pub fn window_size(mut self, size: usize) -> Self {
+        self.window_size = size.max(1);
+        self
+    }
+
+    /// 回路が開いてからhalf-openに移るまでの時間
+    pub fn cool_down(mut self, cool_down: Duration) -> Self {
+        self.cool_down = cool_down;
+        self
+    }
+
+    /// half-openで同時に通す試行の数
+    pub fn half_open_max_calls(mut self, calls: u32) -> Self {
+        self.half_open_max_calls = calls.max(1);
+        self
+    }
+
+    /// 回路の開閉を`DependencyRegistry`の依存の状態にも反映する
+    pub fn registry(mut self, registry: DependencyRegistry) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
+    /// 依存の名前
+    pub fn dependency(&self) -> &str {
+        &self.dependency
+    }
+
+    /// 現在の状態
+    pub fn state(&self) -> CircuitState {
+        self.state.lock().map(|s| s.state).unwrap_or(CircuitState::Closed)
+    }
+
+    /// 統計を取得
+    pub fn metrics(&self) -> CircuitMetrics {
+        let state = match self.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        CircuitMetrics {
+            dependency: self.dependency.clone(),
+            state: state.state,
+            successes: state.successes,
+            failures: state.failures,
+            rejected: state.rejected,
+            opened: state.opened,
+            failure_rate: state.failure_rate(),
+        }
+    }
+
+    /// 呼び出しを実行してよいか判定する
+    ///
+    /// 拒否する場合は、再試行までの目安（Retry-After用）を返します。
+    /// 許可された場合は、結果を`record_success`/`record_failure`で必ず記録してください。
+    pub fn try_acquire(&self) -> Result<(), Duration> {
+        let mut state = match self.state.lock() {
+            Ok(state) => state,
+            Err(_) => return Ok(()),
+        };
+        match state.state {
+            CircuitState::Closed => Ok(()),
+            CircuitState::Open => {
+                let elapsed = state.opened_at.map(|t| t.elapsed()).unwrap_or(self.cool_down);
+                if elapsed >= self.cool_down {
+                    info!("Circuit for '{}' is half-open", self.dependency);
+                    state.state = CircuitState::HalfOpen;
+                    state.half_open_in_flight = 1;
+                    Ok(())
+                } else {
+                    state.rejected += 1;
+                    Err(self.cool_down - elapsed)
+                }
+            }
+            CircuitState::HalfOpen => {
+                if state.half_open_in_flight < self.half_open_max_calls {
+                    state.half_open_in_flight += 1;
+                    Ok(())
+                } else {
+                    state.rejected += 1;
+                    Err(Duration::ZERO)
+                }
+            }
+        }
+    }
+
+    /// 成功を記録
+    pub fn record_success(&self) {
+        self.record(true);
+    }
+
+    /// 失敗を記録
+    pub fn record_failure(&self) {
+        self.record(false);
+    }
+
+    fn record(&self, ok: bool) {
+        let mut state = match self.state.lock() {
+            Ok(state) => state,
+            Err(_) => return,
+        };
+        if ok {
+            state.successes += 1;
+        } else {
+            state.failures += 1;
+        }
+
+        match state.state {
+            CircuitState::HalfOpen => {
+                state.half_open_in_flight = state.half_open_in_flight.saturating_sub(1);
+                if ok {
+                    info!("Circuit for '{}' closed after a successful trial call", self.dependency);
+                    state.state = CircuitState::Closed;
+                    state.window.clear();
+                    state.opened_at = None;
+                    drop(state);
+                    self.sync_registry(true);
+                } else {
+                    self.open(&mut state);
+                }
+            }
+            CircuitState::Closed => {
+                state.window.push_back(ok);
+                while state.window.len() > self.window_size {
+                    state.window.pop_front();
+                }
+                if state.window.len() >= self.minimum_calls
+                    && state.failure_rate() >= self.failure_rate_threshold
+                {
+                    self.open(&mut state);
+                }
+            }
+            // 開く前に許可された呼び出しの結果は統計のみに反映
+            CircuitState::Open => {}
+        }
+    }
+
+    fn open(&self, state: &mut State) {
+        warn!(
+            "Circuit for '{}' opened (failure rate {:.0}%), rejecting calls for {:?}",
+            self.dependency,
+            state.failure_rate() * 100.0,
+            self.cool_down
+        );
+        state.state = CircuitState::Open;
+        state.opened_at = Some(Instant::now());
+        state.half_open_in_flight = 0;
+        state.opened += 1;
+        self.sync_registry(false);
+    }
+
+    fn sync_registry(&self, up: bool) {
+        if let Some(registry) = &self.registry {
+            if up {
+                registry.report_success(&self.dependency);
+            } else {
+                registry.report_failure(&self.dependency, "circuit breaker open");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::dependency::{Criticality, Dependency, DependencyStatus};
+
+    fn breaker() -> CircuitBreaker {
+        CircuitBreaker::new("payments")
+            .minimum_calls(4)
+            .window_size(4)
+            .failure_rate_threshold(0.5)
+            .cool_down(Duration::from_millis(30))
+    }
+
+    #[test]
+    fn test_opens_when_failure_rate_exceeds_threshold() {
+        let breaker = breaker();
+        for ok in [true, false, true] {
+            breaker.try_acquire().unwrap();
+            breaker.record(ok);
+        }
+        // 最低呼び出し回数に達するまでは開かない
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        breaker.try_acquire().unwrap();
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        let retry_after = breaker.try_acquire().unwrap_err();
+        assert!(retry_after <= Duration::from_millis(30));
+
+        let metrics = breaker.metrics();
+        assert_eq!((metrics.successes, metrics.failures, metrics.rejected, metrics.opened), (2, 2, 1, 1));
+    }
+
+    #[test]
+    fn test_half_open_trial() {
+        let breaker = breaker();
+        for _ in 0..4 {
+            breaker.record_failure();
+        }
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        std::thread::sleep(Duration::from_millis(40));
+        breaker.try_acquire().unwrap();
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        // 試行中は他の呼び出しを通さない
+        assert!(breaker.try_acquire().is_err());
+
+        // 試行が失敗すると再び開く
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        std::thread::sleep(Duration::from_millis(40));
+        breaker.try_acquire().unwrap();
+        breaker.record_success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert_eq!(breaker.metrics().failure_rate, 0.0);
+    }
+
+    #[test]
+    fn test_syncs_dependency_registry() {
+        let registry = DependencyRegistry::new();
+        registry.register(Dependency::new("payments", Criticality::Critical, || async { Ok(()) }));
+        let breaker = breaker().registry(registry.clone());
+
+        for _ in 0..4 {
+            breaker.record_failure();
+        }
+        assert!(registry.is_down("payments"));
+
+        std::thread::sleep(Duration::from_millis(40));
+        breaker.try_acquire().unwrap();
+        breaker.record_success();
+        assert_eq!(registry.status("payments"), Some(DependencyStatus::Up));
+    }
+}
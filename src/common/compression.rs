@@ -0,0 +1,611 @@
+//! レスポンスボディの圧縮（gzip / brotli）
+//!
+//! `Accept-Encoding`に応じてレスポンスボディを圧縮するAroundMiddlewareです。
+//! brotliはfeature `brotli`が有効な場合のみ使用します。
+//!
+//! ```
+//! use runbridge::common::CompressionMiddleware;
+//!
+//! let app = runbridge::RunBridge::builder()
+//!     .around(
+//!         CompressionMiddleware::new()
+//!             .min_size(512)
+//!             .exclude_content_type("application/x-protobuf")
+//!             .exclude_route("events"),
+//!     )
+//!     .build();
+//! # drop(app);
+//! ```
+
+use std::env;
+use std::io::Write;
+
+use async_trait::async_trait;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use log::{debug, warn};
+
+use crate::error::Error;
+use super::content_type::replace_header;
+use super::http::{Request, Response};
+use super::traits::{AroundMiddleware, Next};
+
+/// 圧縮方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// gzip
+    Gzip,
+    /// brotli（feature `brotli`）
+    #[cfg(feature = "brotli")]
+    Brotli,
+}
+
+impl Encoding {
+    /// `Content-Encoding`の値
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Encoding::Gzip => "gzip",
+            #[cfg(feature = "brotli")]
+            Encoding::Brotli => "br",
+        }
+    }
+
+    /// サーバー側の優先順（先頭ほど優先）
+    fn supported() -> &'static [Encoding] {
+        #[cfg(feature = "brotli")]
+        {
+            &[Encoding::Brotli, Encoding::Gzip]
+        }
+        #[cfg(not(feature = "brotli"))]
+        {
+            &[Encoding::Gzip]
+        }
+    }
+}
+
+/// 圧縮対象とする最小ボディサイズ（バイト）を取得する
+/// 優先順位: 環境変数 `RUNBRIDGE_COMPRESSION_MIN_SIZE` -> デフォルト 1KB
+pub fn get_compression_min_size() -> usize {
+    const DEFAULT_MIN_SIZE: usize = 1024;
+    env::var("RUNBRIDGE_COMPRESSION_MIN_SIZE")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_MIN_SIZE)
+}
+
+/// 圧縮対象から除外するContent-Typeを取得する（カンマ区切り、`image/*`のようなワイルドカード可）
+/// 環境変数 `RUNBRIDGE_COMPRESSION_EXCLUDE_TYPES` -> デフォルト なし
+pub fn get_compression_excluded_types() -> Vec<String> {
+    env::var("RUNBRIDGE_COMPRESSION_EXCLUDE_TYPES")
+        .map(|s| parse_media_type_list(&s))
+        .unwrap_or_default()
+}
+
+fn parse_media_type_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|t| t.trim().to_ascii_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+/// `Accept-Encoding`から使用する圧縮方式を選ぶ（q値が最大のもの、同値ならサーバー側の優先順）
+pub fn negotiate_encoding(accept_encoding: &str) -> Option<Encoding> {
+    let mut wildcard = None;
+    let mut explicit: Vec<(&str, f32)> = Vec::new();
+    for item in accept_encoding.split(',') {
+        let mut params = item.split(';');
+        let coding = params.next().unwrap_or("").trim();
+        if coding.is_empty() {
+            continue;
+        }
+        let q = params
+            .filter_map(|p| p.trim().strip_prefix("q="))
+            .find_map(|v| v.trim().parse::<f32>().ok())
+            .unwrap_or(1.0);
+        if coding == "*" {
+            wildcard = Some(q);
+        } else {
+            explicit.push((coding, q));
+        }
+    }
+
+    let mut best: Option<(Encoding, f32)> = None;
+    for encoding in Encoding::supported() {
+        let q = explicit
+            .iter()
+            .find(|(coding, _)| coding.eq_ignore_ascii_case(encoding.as_str()))
+            .map(|(_, q)| *q)
+            .or(wildcard)
+            .unwrap_or(0.0);
+        if q > 0.0 && best.is_none_or(|(_, best_q)| q > best_q) {
+            best = Some((*encoding, q));
+        }
+    }
+    best.map(|(encoding, _)| encoding)
+}
+
+/// `Accept-Encoding`が指定した方式（`zstd`等の任意のトークン）を受け付けるか（`q=0`は拒否）
+pub fn accepts_encoding(accept_encoding: &str, coding: &str) -> bool {
+    let mut wildcard = None;
+    for item in accept_encoding.split(',') {
+        let mut params = item.split(';');
+        let name = params.next().unwrap_or("").trim();
+        let q = params
+            .filter_map(|p| p.trim().strip_prefix("q="))
+            .find_map(|v| v.trim().parse::<f32>().ok())
+            .unwrap_or(1.0);
+        if name.eq_ignore_ascii_case(coding) {
+            return q > 0.0;
+        }
+        if name == "*" {
+            wildcard = Some(q);
+        }
+    }
+    wildcard.is_some_and(|q| q > 0.0)
+}
+
+/// `Vary`にリクエストヘッダー名を追加（既に含まれる、または`*`の場合はそのまま）
+pub(crate) fn add_vary(res: &mut Response, name: &str) {
+    let vary = match res.header("vary") {
+        Some(v) if v.split(',').any(|t| t.trim().eq_ignore_ascii_case(name) || t.trim() == "*") => v.to_string(),
+        Some(v) => format!("{}, {}", v, name),
+        None => name.to_string(),
+    };
+    replace_header(&mut res.headers, "Vary".to_string(), vary);
+}
+
+impl Request {
+    /// クライアントが指定した圧縮方式を受け付けるか（`Accept-Encoding`が無い場合は受け付けない）
+    pub fn accepts_encoding(&self, coding: &str) -> bool {
+        self.headers
+            .get("accept-encoding")
+            .is_some_and(|v| accepts_encoding(v, coding))
+    }
+}
+
+impl Response {
+    /// 圧縮済みのボディ（オブジェクトストレージに保存したzstd・gzip等）をそのまま返す
+    ///
+    /// `Content-Encoding`と`Vary: Accept-Encoding`を設定します。`CompressionMiddleware`は
+    /// 再圧縮せず、Lambdaではボディを1回だけBase64化して`isBase64Encoded`で返します。
+    /// クライアントが方式を受け付けるかは`Request::accepts_encoding`で確認してください。
+    ///
+    /// ```
+    /// use runbridge::common::Response;
+    ///
+    /// let zstd_bytes = vec![0x28, 0xb5, 0x2f, 0xfd, 0x00];
+    /// let res = Response::ok()
+    ///     .with_header("Content-Type", "application/json")
+    ///     .with_precompressed_body("zstd", zstd_bytes);
+    /// assert_eq!(res.header("content-encoding"), Some("zstd"));
+    /// assert!(res.is_content_encoded());
+    /// ```
+    pub fn with_precompressed_body(mut self, encoding: &str, body: Vec<u8>) -> Self {
+        replace_header(&mut self.headers, "Content-Encoding".to_string(), encoding.to_string());
+        add_vary(&mut self, "Accept-Encoding");
+        self.headers.retain(|k, _| !k.eq_ignore_ascii_case("content-length"));
+        self.body = Some(body);
+        self
+    }
+
+    /// ボディに`Content-Encoding`（`identity`以外）が適用されているか
+    pub fn is_content_encoded(&self) -> bool {
+        self.header("content-encoding")
+            .is_some_and(|v| !v.trim().is_empty() && !v.trim().eq_ignore_ascii_case("identity"))
+    }
+}
+
+/// 既に圧縮されている形式（SVG以外の画像・動画・音声・アーカイブ・WOFFフォント・PDF）
+/// 再圧縮しても小さくならないため、`include_content_type`で追加しても圧縮しません。
+const PRECOMPRESSED_TYPES: &[&str] = &[
+    "image/*",
+    "video/*",
+    "audio/*",
+    "font/woff",
+    "font/woff2",
+    "application/zip",
+    "application/gzip",
+    "application/x-gzip",
+    "application/x-bzip2",
+    "application/x-7z-compressed",
+    "application/x-rar-compressed",
+    "application/x-xz",
+    "application/zstd",
+    "application/pdf",
+];
+
+/// Content-Typeからパラメータを除いた小文字のメディアタイプ
+fn media_type(content_type: &str) -> String {
+    content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase()
+}
+
+/// メディアタイプがパターン（`type/subtype`または`type/*`）に一致するか
+fn media_type_matches(mime: &str, pattern: &str) -> bool {
+    match pattern.strip_suffix("/*") {
+        Some(prefix) => mime.split('/').next() == Some(prefix),
+        None => mime == pattern,
+    }
+}
+
+/// 圧縮して効果があるContent-Typeか（テキスト系・JSON・XML・JavaScript・SVG）
+fn is_compressible(mime: &str) -> bool {
+    mime.starts_with("text/")
+        || mime.ends_with("+json")
+        || mime.ends_with("+xml")
+        || matches!(
+            mime,
+            "application/json" | "application/xml" | "application/javascript" | "application/x-ndjson" | "image/svg+xml"
+        )
+}
+
+/// レスポンスボディを圧縮するミドルウェア
+///
+/// 次の場合は圧縮しません。
+/// - ボディが最小サイズ（`min_size`）未満、または圧縮しても小さくならない
+/// - 既に`Content-Encoding`が設定されている（事前に圧縮済みのボディなど）
+/// - 圧縮に適さないContent-Type（画像・動画・アーカイブ等）、または`exclude_content_type`で除外したもの
+/// - `exclude_route`・`exclude_path_prefix`で除外したルート
+/// - ステータスが204/304、またはクライアントが対応する方式を受け付けない
+///
+/// 小さなJSONレスポンスは圧縮の手間に見合わないため、全体で有効にする場合も最小サイズで除外されます。
+///
+/// 圧縮した場合は`Content-Encoding`と`Vary: Accept-Encoding`を設定し、強いETagは弱いETagに変更します。
+/// 圧縮の対象でもクライアントが対応していない等で圧縮しなかった場合も、`Vary: Accept-Encoding`を設定します。
+/// Lambdaでは圧縮したボディを`isBase64Encoded`で返します。
+#[derive(Debug, Clone)]
+pub struct CompressionMiddleware {
+    min_size: usize,
+    gzip_level: u32,
+    included_types: Vec<String>,
+    excluded_types: Vec<String>,
+    excluded_routes: Vec<String>,
+    excluded_path_prefixes: Vec<String>,
+}
+
+impl Default for CompressionMiddleware {
+    fn default() -> Self {
+        Self {
+            min_size: get_compression_min_size(),
+            gzip_level: Compression::default().level(),
+            included_types: Vec::new(),
+            excluded_types: get_compression_excluded_types(),
+            excluded_routes: Vec::new(),
+            excluded_path_prefixes: Vec::new(),
+        }
+    }
+}
+
+impl CompressionMiddleware {
+    /// 新しいCompressionMiddlewareを作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 圧縮対象とする最小ボディサイズ（バイト）
+    /// 優先順位: このメソッド -> 環境変数 `RUNBRIDGE_COMPRESSION_MIN_SIZE` -> デフォルト 1KB
+    pub fn min_size(mut self, bytes: usize) -> Self {
+        self.min_size = bytes;
+        self
+    }
+
+    /// gzipの圧縮レベル（0〜9、既定 6）
+    pub fn gzip_level(mut self, level: u32) -> Self {
+        self.gzip_level = level.min(9);
+        self
+    }
+
+    /// 圧縮対象にするContent-Typeを追加（`application/wasm`・`font/*`など）
+    /// 既定の対象（テキスト系・JSON・XML等）に加えて圧縮します。除外設定と圧縮済み形式が優先されます。
+    pub fn include_content_type(mut self, pattern: impl Into<String>) -> Self {
+        self.included_types.push(pattern.into().trim().to_ascii_lowercase());
+        self
+    }
+
+    /// 圧縮しないContent-Typeを追加（`application/x-ndjson`・`text/*`など）
+    /// 環境変数 `RUNBRIDGE_COMPRESSION_EXCLUDE_TYPES` の値に追加されます。
+    pub fn exclude_content_type(mut self, pattern: impl Into<String>) -> Self {
+        self.excluded_types.push(pattern.into().trim().to_ascii_lowercase());
+        self
+    }
+
+    /// 指定した名前のルート（`HandlerExt::name`）のレスポンスを圧縮しない
+    pub fn exclude_route(mut self, name: impl Into<String>) -> Self {
+        self.excluded_routes.push(name.into());
+        self
+    }
+
+    /// 指定したパスとその配下のパスのレスポンスを圧縮しない（SSEやストリーミング用のパスなど）
+    /// パスのセグメント単位で判定します（`/stream`は`/stream/items`に一致し、`/streaming`には一致しません）。
+    pub fn exclude_path_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.excluded_path_prefixes.push(prefix.into().trim_end_matches('/').to_string());
+        self
+    }
+
+    /// Content-Typeが圧縮対象か（除外設定と圧縮済み形式を先に判定し、追加した対象・既定の対象なら圧縮する）
+    pub fn should_compress_content_type(&self, content_type: &str) -> bool {
+        let mime = media_type(content_type);
+        if self.excluded_types.iter().any(|p| media_type_matches(&mime, p)) {
+            return false;
+        }
+        if mime != "image/svg+xml" && PRECOMPRESSED_TYPES.iter().any(|p| media_type_matches(&mime, p)) {
+            return false;
+        }
+        self.included_types.iter().any(|p| media_type_matches(&mime, p)) || is_compressible(&mime)
+    }
+
+    /// リクエストのルートが圧縮の対象外か
+    fn is_excluded_request(&self, req: &Request) -> bool {
+        let under = |prefix: &str| {
+            req.path == prefix || req.path.strip_prefix(prefix).is_some_and(|rest| rest.starts_with('/'))
+        };
+        if self.excluded_path_prefixes.iter().any(|p| under(p)) {
+            return true;
+        }
+        !self.excluded_routes.is_empty()
+            && req
+                .matched_route()
+                .and_then(|route| route.name.as_deref())
+                .is_some_and(|name| self.excluded_routes.iter().any(|r| r == name))
+    }
+
+    /// レスポンスが圧縮の対象か（ステータス・既存の`Content-Encoding`・Content-Type・ボディサイズで判定）
+    fn is_compressible_response(&self, res: &Response) -> bool {
+        !matches!(res.status, 204 | 304)
+            && res.header("content-encoding").is_none()
+            && res.content_type().is_some_and(|ct| self.should_compress_content_type(ct))
+            && res.body.as_ref().is_some_and(|body| body.len() >= self.min_size.max(1))
+    }
+
+    /// レスポンスを圧縮（条件を満たさない場合はそのまま返す）
+    ///
+    /// 圧縮の対象であれば、圧縮しなかった場合も結果が`Accept-Encoding`に依存するため`Vary`を設定します。
+    pub fn compress(&self, mut res: Response, encoding: Encoding) -> Response {
+        if !self.is_compressible_response(&res) {
+            return res;
+        }
+        add_vary(&mut res, "Accept-Encoding");
+        let body = res.body.take().unwrap_or_default();
+
+        let compressed = match self.encode(&body, encoding) {
+            Ok(compressed) if compressed.len() < body.len() => compressed,
+            Ok(_) => {
+                res.body = Some(body);
+                return res;
+            }
+            Err(e) => {
+                warn!("Failed to compress response body with {}: {}", encoding.as_str(), e);
+                res.body = Some(body);
+                return res;
+            }
+        };
+        debug!(
+            "Compressed response body with {}: {} -> {} bytes",
+            encoding.as_str(),
+            body.len(),
+            compressed.len()
+        );
+
+        replace_header(&mut res.headers, "Content-Encoding".to_string(), encoding.as_str().to_string());
+        // 表現が変わるため、強いETagは弱いETagにする
+        if let Some(etag) = res.header("etag").filter(|e| !e.starts_with("W/")).map(str::to_string) {
+            replace_header(&mut res.headers, "ETag".to_string(), format!("W/{}", etag));
+        }
+        res.headers.retain(|k, _| !k.eq_ignore_ascii_case("content-length"));
+        res.body = Some(compressed);
+        res
+    }
+
+    fn encode(&self, body: &[u8], encoding: Encoding) -> std::io::Result<Vec<u8>> {
+        match encoding {
+            Encoding::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::with_capacity(body.len() / 2), Compression::new(self.gzip_level));
+                encoder.write_all(body)?;
+                encoder.finish()
+            }
+            #[cfg(feature = "brotli")]
+            Encoding::Brotli => {
+                let mut out = Vec::with_capacity(body.len() / 2);
+                {
+                    let mut writer = brotli::CompressorWriter::new(&mut out, 4096, 5, 22);
+                    writer.write_all(body)?;
+                }
+                Ok(out)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl AroundMiddleware for CompressionMiddleware {
+    async fn around(&self, req: Request, next: Next<'_>) -> Result<Response, Error> {
+        if self.is_excluded_request(&req) {
+            return next.run(req).await;
+        }
+        let encoding = req.headers.get("accept-encoding").and_then(|v| negotiate_encoding(v));
+        let res = next.run(req).await?;
+        Ok(match encoding {
+            Some(encoding) => self.compress(res, encoding),
+            None if self.is_compressible_response(&res) => {
+                // 対応する方式を受け付けるクライアントには圧縮して返すため、キャッシュを分ける
+                let mut res = res;
+                add_vary(&mut res, "Accept-Encoding");
+                res
+            }
+            None => res,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    fn text_response(len: usize) -> Response {
+        Response::ok()
+            .with_header("Content-Type", "text/plain; charset=utf-8")
+            .with_header("ETag", "\"v1\"")
+            .with_header("Vary", "Origin")
+            .with_body("a".repeat(len).into_bytes())
+    }
+
+    #[test]
+    fn test_negotiate_encoding() {
+        assert_eq!(negotiate_encoding("gzip, deflate"), Some(Encoding::Gzip));
+        assert_eq!(negotiate_encoding("deflate"), None);
+        #[cfg(not(feature = "brotli"))]
+        assert_eq!(negotiate_encoding("gzip;q=0, *"), None);
+        #[cfg(feature = "brotli")]
+        {
+            assert_eq!(negotiate_encoding("gzip;q=0, *"), Some(Encoding::Brotli));
+            assert_eq!(negotiate_encoding("gzip, br;q=0.8"), Some(Encoding::Gzip));
+        }
+        assert_eq!(negotiate_encoding("*;q=0.5"), Some(Encoding::supported()[0]));
+        assert_eq!(negotiate_encoding("identity"), None);
+        assert_eq!(negotiate_encoding(""), None);
+    }
+
+    #[test]
+    fn test_compress_response() {
+        let middleware = CompressionMiddleware::new().min_size(100);
+        let res = middleware.compress(text_response(2048), Encoding::Gzip);
+        assert_eq!(res.header("content-encoding"), Some("gzip"));
+        assert_eq!(res.header("vary"), Some("Origin, Accept-Encoding"));
+        assert_eq!(res.header("etag"), Some("W/\"v1\""));
+        let mut decoded = String::new();
+        GzDecoder::new(res.body.as_deref().unwrap()).read_to_string(&mut decoded).unwrap();
+        assert_eq!(decoded.len(), 2048);
+
+        #[cfg(feature = "brotli")]
+        {
+            let res = middleware.compress(text_response(2048), Encoding::Brotli);
+            assert_eq!(res.header("content-encoding"), Some("br"));
+            let mut decoded = String::new();
+            brotli::Decompressor::new(res.body.as_deref().unwrap(), 4096).read_to_string(&mut decoded).unwrap();
+            assert_eq!(decoded.len(), 2048);
+        }
+
+        // 最小サイズ未満・圧縮済み・非対象のContent-Typeはそのまま
+        let res = middleware.compress(text_response(50), Encoding::Gzip);
+        assert!(res.header("content-encoding").is_none());
+        let res = middleware.compress(text_response(2048).with_header("Content-Encoding", "br"), Encoding::Gzip);
+        assert_eq!(res.header("content-encoding"), Some("br"));
+        assert_eq!(res.body.as_ref().map(Vec::len), Some(2048));
+        let image = Response::ok().with_header("Content-Type", "image/png").with_body(vec![0; 4096]);
+        assert!(middleware.compress(image, Encoding::Gzip).header("content-encoding").is_none());
+
+        // 圧縮しても小さくならない場合は非圧縮で返すが、結果はAccept-Encodingに依存する
+        let random: Vec<u8> = (0..8).flat_map(|_| crate::common::csp::random_bytes()).collect();
+        let res = middleware.compress(
+            Response::ok().with_header("Content-Type", "text/plain").with_body(random.clone()),
+            Encoding::Gzip,
+        );
+        assert!(res.header("content-encoding").is_none());
+        assert_eq!(res.header("vary"), Some("Accept-Encoding"));
+        assert_eq!(res.body, Some(random));
+    }
+
+    #[tokio::test]
+    async fn test_compression_middleware() {
+        use crate::common::Method;
+        use crate::handler::get;
+        use crate::RunBridge;
+
+        let large = get("^/large$", |_req: Request| Response::ok().json(&vec!["runbridge"; 500]));
+        let app = RunBridge::builder().handler(large).around(CompressionMiddleware::new().min_size(256)).build();
+        let request = || Request::new(Method::GET, "/large".to_string());
+
+        let res = crate::testing::dispatch(&app, request().with_header("Accept-Encoding", "gzip")).await;
+        assert_eq!(res.header("content-encoding"), Some("gzip"));
+        let res = crate::testing::dispatch(&app, request()).await;
+        assert!(res.header("content-encoding").is_none());
+        // 圧縮しなかった場合も、結果はAccept-Encodingに依存する
+        assert_eq!(res.header("vary"), Some("Accept-Encoding"));
+        let res = crate::testing::dispatch(&app, request().with_header("Accept-Encoding", "identity")).await;
+        assert_eq!(res.header("vary"), Some("Accept-Encoding"));
+    }
+
+    #[test]
+    fn test_content_type_exclusions() {
+        let middleware = CompressionMiddleware::new()
+            .min_size(100)
+            .include_content_type("application/wasm")
+            .include_content_type("image/*")
+            .exclude_content_type("application/x-ndjson")
+            .exclude_content_type("TEXT/CSV");
+        assert!(middleware.should_compress_content_type("application/json; charset=utf-8"));
+        assert!(middleware.should_compress_content_type("application/wasm"));
+        assert!(middleware.should_compress_content_type("image/svg+xml"));
+        assert!(!middleware.should_compress_content_type("text/csv; charset=utf-8"));
+        assert!(!middleware.should_compress_content_type("application/x-ndjson"));
+        // 圧縮済みの形式は対象に追加しても圧縮しない
+        assert!(!middleware.should_compress_content_type("image/png"));
+        assert!(!middleware.should_compress_content_type("font/woff2"));
+        assert!(!middleware.should_compress_content_type("application/zip"));
+
+        let wildcard = CompressionMiddleware::new().exclude_content_type("text/*");
+        assert!(!wildcard.should_compress_content_type("text/html"));
+        assert!(wildcard.should_compress_content_type("application/json"));
+
+        let csv = Response::ok().with_header("Content-Type", "text/csv").with_body(vec![b'a'; 4096]);
+        assert!(middleware.compress(csv, Encoding::Gzip).header("content-encoding").is_none());
+        let wasm = Response::ok().with_header("Content-Type", "application/wasm").with_body(vec![0; 4096]);
+        assert_eq!(middleware.compress(wasm, Encoding::Gzip).header("content-encoding"), Some("gzip"));
+        assert_eq!(parse_media_type_list(" image/* , ,Text/CSV"), vec!["image/*", "text/csv"]);
+    }
+
+    #[tokio::test]
+    async fn test_route_exclusions() {
+        use crate::common::Method;
+        use crate::handler::{get, HandlerExt};
+        use crate::RunBridge;
+
+        fn large(_req: Request) -> Result<Response, Error> {
+            Response::ok().json(&vec!["runbridge"; 500])
+        }
+        let app = RunBridge::builder()
+            .handler(get("/report", large))
+            .handler(get("/export", large).name("export"))
+            .handler(get("/stream", large))
+            .handler(get("/stream/items", large))
+            .handler(get("/streaming", large))
+            .around(CompressionMiddleware::new().min_size(256).exclude_route("export").exclude_path_prefix("/stream/"))
+            .build();
+        let request = |path: &str| Request::new(Method::GET, path.to_string()).with_header("Accept-Encoding", "gzip");
+
+        let res = crate::testing::dispatch(&app, request("/report")).await;
+        assert_eq!(res.header("content-encoding"), Some("gzip"));
+        let res = crate::testing::dispatch(&app, request("/export")).await;
+        assert!(res.header("content-encoding").is_none());
+        let res = crate::testing::dispatch(&app, request("/stream/items")).await;
+        assert!(res.header("content-encoding").is_none());
+        let res = crate::testing::dispatch(&app, request("/stream")).await;
+        assert!(res.header("content-encoding").is_none());
+        // パスのセグメント単位で判定するため、同じ文字列で始まる別のパスは除外しない
+        let res = crate::testing::dispatch(&app, request("/streaming")).await;
+        assert_eq!(res.header("content-encoding"), Some("gzip"));
+    }
+
+    #[test]
+    fn test_precompressed_body() {
+        assert!(accepts_encoding("gzip, zstd;q=0.5", "zstd"));
+        assert!(accepts_encoding("*", "zstd"));
+        assert!(!accepts_encoding("zstd;q=0, *", "zstd"));
+        assert!(!accepts_encoding("gzip", "zstd"));
+        let req = Request::new(crate::common::Method::GET, "/".to_string()).with_header("Accept-Encoding", "ZSTD");
+        assert!(req.accepts_encoding("zstd"));
+
+        let middleware = CompressionMiddleware::new().min_size(1);
+        let body = vec![0x28, 0xb5, 0x2f, 0xfd, 0xff, 0x00];
+        let res = text_response(0).with_precompressed_body("zstd", body.clone());
+        assert_eq!(res.header("vary"), Some("Origin, Accept-Encoding"));
+        assert!(res.is_content_encoded());
+        // 圧縮済みのボディは再圧縮しない
+        let res = middleware.compress(res, Encoding::Gzip);
+        assert_eq!(res.header("content-encoding"), Some("zstd"));
+        assert_eq!(res.body, Some(body));
+        assert!(!Response::ok().with_header("Content-Encoding", "identity").is_content_encoded());
+    }
+}
@@ -0,0 +1,370 @@
+//! 起動時の設定検証レポート
+//!
+//! 環境変数で与えられた上限値・方針やfeatureの組み合わせを検証し、実際に適用される設定と
+//! 問題点を1行のJSONとしてログに出力します。ログが唯一のフィードバックになるLambdaなどで、
+//! 不正な値が黙ってデフォルトにフォールバックしていることに気付けるようにするためのものです。
+//! strictモードでは、エラーがある場合に起動を拒否します。
+
+use std::env;
+use std::fmt;
+use std::sync::Once;
+
+use log::{error, info, warn};
+use serde_json::{json, Map, Value};
+
+use super::body_policy::{get_body_policy, BodyPolicy};
+use super::error_response::{get_error_format, is_error_detail_exposed, ErrorFormat};
+use super::http::Method;
+use super::methods::get_allowed_methods;
+use super::origin::{get_public_base_url, is_forwarded_proto_trusted};
+use super::utils::{get_blocking_deserialize_threshold, get_max_body_size, get_max_query_length, get_max_query_params};
+
+static LOG_ONCE: Once = Once::new();
+
+/// Lambdaの同期呼び出しのペイロード上限（6MB）
+const LAMBDA_PAYLOAD_LIMIT: usize = 6 * 1024 * 1024;
+
+/// strictモードを有効にするかどうかを取得する
+/// 優先順位: 環境変数 `RUNBRIDGE_STRICT_CONFIG`（`1` / `true`） -> デフォルト 無効
+pub fn is_strict_config() -> bool {
+    is_truthy_env("RUNBRIDGE_STRICT_CONFIG")
+}
+
+/// 問題の重大度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigSeverity {
+    /// 動作はするが意図しない可能性がある設定
+    Warning,
+    /// 無視されてデフォルト値が使われている、または矛盾している設定
+    Error,
+}
+
+impl fmt::Display for ConfigSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSeverity::Warning => write!(f, "warning"),
+            ConfigSeverity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// 検証で見つかった問題
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigIssue {
+    /// 重大度
+    pub severity: ConfigSeverity,
+    /// 対象の設定（環境変数名やfeature名）
+    pub key: String,
+    /// 内容
+    pub message: String,
+}
+
+/// 実際に適用される設定と検証結果
+#[derive(Debug, Clone, Default)]
+pub struct ConfigReport {
+    settings: Map<String, Value>,
+    issues: Vec<ConfigIssue>,
+}
+
+impl ConfigReport {
+    /// 環境変数と有効なfeatureから設定を収集して検証
+    pub fn collect() -> Self {
+        let mut report = ConfigReport::default();
+
+        let features = enabled_runtime_features();
+        if features.len() > 1 {
+            report.warning(
+                "features",
+                format!("multiple runtime features enabled ({}); serve() uses the first one", features.join(", ")),
+            );
+        }
+        report.set("features", json!(features));
+
+        report.check_usize("RUNBRIDGE_MAX_BODY_SIZE");
+        report.check_usize("RUNBRIDGE_MAX_QUERY_LENGTH");
+        report.check_usize("RUNBRIDGE_MAX_QUERY_PARAMS");
+        report.check_usize("RUNBRIDGE_BLOCKING_DESERIALIZE_THRESHOLD");
+        report.set("max_body_size", json!(get_max_body_size()));
+        report.set("max_query_length", json!(get_max_query_length()));
+        report.set("max_query_params", json!(get_max_query_params()));
+        report.set("blocking_deserialize_threshold", json!(get_blocking_deserialize_threshold()));
+
+        report.check_allowed_methods();
+        report.set(
+            "allowed_methods",
+            json!(get_allowed_methods().iter().map(|m| m.to_string()).collect::<Vec<_>>()),
+        );
+
+        if let Ok(value) = env::var("RUNBRIDGE_BODYLESS_METHOD_BODY") {
+            if BodyPolicy::parse(&value).is_none() {
+                report.error("RUNBRIDGE_BODYLESS_METHOD_BODY", format!("invalid value {:?}, expected allow/ignore/reject", value));
+            }
+        }
+        report.set("bodyless_method_body", json!(format!("{:?}", get_body_policy()).to_ascii_lowercase()));
+
+        if let Ok(value) = env::var("RUNBRIDGE_PUBLIC_BASE_URL") {
+            if get_public_base_url().is_none() {
+                report.error("RUNBRIDGE_PUBLIC_BASE_URL", format!("{:?} is not an http(s) URL and is ignored", value));
+            }
+        }
+        report.set("public_base_url", json!(get_public_base_url()));
+        report.set("trust_forwarded_proto", json!(is_forwarded_proto_trusted()));
+
+        if let Ok(value) = env::var("RUNBRIDGE_ERROR_FORMAT") {
+            if ErrorFormat::parse(&value).is_none() {
+                report.error("RUNBRIDGE_ERROR_FORMAT", format!("invalid value {:?}, expected json/text", value));
+            }
+        }
+        report.set("error_format", json!(format!("{:?}", get_error_format()).to_ascii_lowercase()));
+        if is_error_detail_exposed() {
+            report.warning("RUNBRIDGE_EXPOSE_ERROR_DETAILS", "internal error details are returned to clients".to_string());
+        }
+
+        if is_truthy_env("RUNBRIDGE_DEBUG_ECHO") {
+            report.warning("RUNBRIDGE_DEBUG_ECHO", "debug echo endpoints are enabled".to_string());
+        }
+
+        #[cfg(feature = "lambda")]
+        {
+            report.check_usize("RUNBRIDGE_LAMBDA_MAX_RESPONSE_SIZE");
+            let max_response = crate::lambda::get_max_response_size();
+            if max_response > LAMBDA_PAYLOAD_LIMIT {
+                report.warning(
+                    "RUNBRIDGE_LAMBDA_MAX_RESPONSE_SIZE",
+                    format!("{} bytes exceeds the Lambda payload limit of {} bytes", max_response, LAMBDA_PAYLOAD_LIMIT),
+                );
+            }
+            report.set("lambda_max_response_size", json!(max_response));
+        }
+        if cfg!(feature = "lambda") && get_max_body_size() > LAMBDA_PAYLOAD_LIMIT {
+            report.warning(
+                "RUNBRIDGE_MAX_BODY_SIZE",
+                format!("limit is above the Lambda payload limit of {} bytes and can never be reached", LAMBDA_PAYLOAD_LIMIT),
+            );
+        }
+
+        #[cfg(feature = "cloud_run")]
+        {
+            report.check_usize("RUNBRIDGE_RESPONSE_SIZE_WARN_THRESHOLD");
+            report.set("response_size_warn_threshold", json!(crate::cloudrun::get_response_size_warn_threshold()));
+        }
+
+        report
+    }
+
+    /// 設定値を追加
+    pub fn set(&mut self, key: &str, value: Value) {
+        self.settings.insert(key.to_string(), value);
+    }
+
+    /// 警告を追加
+    pub fn warning(&mut self, key: &str, message: String) {
+        self.push(ConfigSeverity::Warning, key, message);
+    }
+
+    /// エラーを追加
+    pub fn error(&mut self, key: &str, message: String) {
+        self.push(ConfigSeverity::Error, key, message);
+    }
+
+    fn push(&mut self, severity: ConfigSeverity, key: &str, message: String) {
+        self.issues.push(ConfigIssue { severity, key: key.to_string(), message });
+    }
+
+    /// 適用される設定値
+    pub fn settings(&self) -> &Map<String, Value> {
+        &self.settings
+    }
+
+    /// 見つかった問題
+    pub fn issues(&self) -> &[ConfigIssue] {
+        &self.issues
+    }
+
+    /// エラーが含まれるかどうか
+    pub fn has_errors(&self) -> bool {
+        self.issues.iter().any(|i| i.severity == ConfigSeverity::Error)
+    }
+
+    /// レポートをJSONに変換
+    pub fn to_json(&self) -> Value {
+        let issues: Vec<Value> = self
+            .issues
+            .iter()
+            .map(|i| json!({ "severity": i.severity.to_string(), "key": i.key, "message": i.message }))
+            .collect();
+        json!({
+            "event": "config_report",
+            "settings": self.settings,
+            "issues": issues,
+        })
+    }
+
+    /// レポートを1行のJSONとしてログ出力（問題の重大度に応じたレベル）
+    pub fn log(&self) {
+        let line = self.to_json().to_string();
+        if self.has_errors() {
+            error!("{}", line);
+        } else if !self.issues.is_empty() {
+            warn!("{}", line);
+        } else {
+            info!("{}", line);
+        }
+    }
+
+    /// プロセス内で初回のみログ出力（起動時に使用）
+    pub fn log_once(&self) {
+        LOG_ONCE.call_once(|| self.log());
+    }
+
+    /// 数値の上限を表す環境変数を検証（解釈できない値や0はデフォルト値へのフォールバックや全拒否になる）
+    fn check_usize(&mut self, key: &str) {
+        let value = match env::var(key) {
+            Ok(value) => value,
+            Err(_) => return,
+        };
+        match value.parse::<usize>() {
+            Ok(0) => self.error(key, "limit of 0 rejects any non-empty input".to_string()),
+            Ok(_) => {}
+            Err(_) => self.error(key, format!("invalid number {:?}, the default is used instead", value)),
+        }
+    }
+
+    fn check_allowed_methods(&mut self) {
+        let value = match env::var("RUNBRIDGE_ALLOWED_METHODS") {
+            Ok(value) => value,
+            Err(_) => return,
+        };
+        let names: Vec<&str> = value.split(',').map(str::trim).filter(|s| !s.is_empty()).collect();
+        let unknown: Vec<&str> = names.iter().copied().filter(|n| Method::from_str(n).is_none()).collect();
+        if unknown.len() == names.len() {
+            self.error(
+                "RUNBRIDGE_ALLOWED_METHODS",
+                format!("no valid methods in {:?}, all methods are allowed instead", value),
+            );
+        } else if !unknown.is_empty() {
+            self.warning(
+                "RUNBRIDGE_ALLOWED_METHODS",
+                format!("unsupported methods are ignored: {}", unknown.join(", ")),
+            );
+        }
+    }
+}
+
+fn is_truthy_env(key: &str) -> bool {
+    env::var(key)
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true"))
+        .unwrap_or(false)
+}
+
+/// 有効な実行環境feature（`tower`は実行環境featureと併用できるため含めない）
+fn enabled_runtime_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(feature = "lambda") {
+        features.push("lambda");
+    }
+    if cfg!(feature = "cloud_run") {
+        features.push("cloud_run");
+    }
+    if cfg!(feature = "cgi") {
+        features.push("cgi");
+    }
+    features
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temp_env::with_vars;
+
+    const KEYS: [&str; 6] = [
+        "RUNBRIDGE_MAX_BODY_SIZE",
+        "RUNBRIDGE_MAX_QUERY_PARAMS",
+        "RUNBRIDGE_ALLOWED_METHODS",
+        "RUNBRIDGE_BODYLESS_METHOD_BODY",
+        "RUNBRIDGE_PUBLIC_BASE_URL",
+        "RUNBRIDGE_DEBUG_ECHO",
+    ];
+
+    fn with_env<F: FnOnce()>(vars: &[(&str, &str)], f: F) {
+        let vars: Vec<(&str, Option<&str>)> = KEYS
+            .iter()
+            .map(|k| (*k, vars.iter().find(|(name, _)| name == k).map(|(_, v)| *v)))
+            .collect();
+        with_vars(vars, f);
+    }
+
+    #[test]
+    fn test_default_config_has_no_errors() {
+        with_env(&[], || {
+            let report = ConfigReport::collect();
+            assert!(!report.has_errors(), "{:?}", report.issues());
+            assert_eq!(report.settings()["max_body_size"], 5 * 1024 * 1024);
+            assert_eq!(report.settings()["bodyless_method_body"], "allow");
+        });
+    }
+
+    #[test]
+    fn test_invalid_values_are_reported() {
+        with_env(
+            &[
+                ("RUNBRIDGE_MAX_BODY_SIZE", "5MB"),
+                ("RUNBRIDGE_MAX_QUERY_PARAMS", "0"),
+                ("RUNBRIDGE_ALLOWED_METHODS", "GET,TRACE"),
+                ("RUNBRIDGE_BODYLESS_METHOD_BODY", "drop"),
+                ("RUNBRIDGE_PUBLIC_BASE_URL", "example.com"),
+                ("RUNBRIDGE_DEBUG_ECHO", "1"),
+            ],
+            || {
+                let report = ConfigReport::collect();
+                let find = |key: &str| {
+                    report.issues().iter().find(|i| i.key == key).map(|i| i.severity)
+                };
+                assert_eq!(find("RUNBRIDGE_MAX_BODY_SIZE"), Some(ConfigSeverity::Error));
+                assert_eq!(find("RUNBRIDGE_MAX_QUERY_PARAMS"), Some(ConfigSeverity::Error));
+                assert_eq!(find("RUNBRIDGE_ALLOWED_METHODS"), Some(ConfigSeverity::Warning));
+                assert_eq!(find("RUNBRIDGE_BODYLESS_METHOD_BODY"), Some(ConfigSeverity::Error));
+                assert_eq!(find("RUNBRIDGE_PUBLIC_BASE_URL"), Some(ConfigSeverity::Error));
+                assert_eq!(find("RUNBRIDGE_DEBUG_ECHO"), Some(ConfigSeverity::Warning));
+                assert!(report.has_errors());
+
+                let json = report.to_json();
+                assert_eq!(json["event"], "config_report");
+                assert_eq!(json["settings"]["allowed_methods"], json!(["GET"]));
+                assert_eq!(json["issues"][0]["severity"], "error");
+            },
+        );
+    }
+
+    #[test]
+    fn test_strict_mode_refuses_to_launch() {
+        with_env(&[("RUNBRIDGE_MAX_BODY_SIZE", "abc")], || {
+            let app = crate::RunBridge::builder().strict_config(true).build();
+            let err = app.launch().unwrap_err();
+            assert!(err.to_string().contains("RUNBRIDGE_MAX_BODY_SIZE"), "{}", err);
+
+            let report = app.config_report();
+            assert_eq!(report.settings()["handlers"], 0);
+            assert!(report.issues().iter().any(|i| i.key == "handlers"));
+
+            // strictモードでなければ警告のみで起動する
+            let app = crate::RunBridge::builder().strict_config(false).build();
+            assert!(app.launch().is_ok());
+        });
+    }
+
+    #[cfg(feature = "tower")]
+    #[test]
+    fn test_tower_is_not_a_runtime_feature() {
+        let features = enabled_runtime_features();
+        assert!(!features.contains(&"tower"));
+        with_env(&[], || {
+            let report = ConfigReport::collect();
+            assert_eq!(report.settings()["features"], json!(features));
+            // 実行環境featureが1つだけならtowerとの併用で警告しない
+            if features.len() <= 1 {
+                assert!(report.issues().iter().all(|i| i.key != "features"));
+            }
+        });
+    }
+}
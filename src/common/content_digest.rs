@@ -0,0 +1,313 @@
+//! ボディのダイジェストによる完全性の検査（RFC 9530 `Content-Digest`）
+//!
+//! レスポンスにはボディのハッシュを`Content-Digest: sha-256=:<base64>:`として付与し、
+//! リクエストに`Content-Digest`（または旧仕様の`Digest`、RFC 3230）がある場合はボディと照合して、
+//! 一致しなければ400を返します。サービス間のWebhookなどでペイロードの改変・欠損を検出するためのものです。
+//!
+//! ダイジェストは転送時のバイト列（`Content-Encoding`適用後）に対して計算します。
+//! gzipで送られたリクエストは各ランタイムが解凍しますが、その際に元のバイト列を保持するため、
+//! 解凍前のボディで照合されます。
+
+use async_trait::async_trait;
+use sha2::{Digest, Sha256, Sha512};
+
+use crate::error::Error;
+use super::http::{Request, Response};
+use super::signed_url::constant_time_eq;
+use super::traits::Middleware;
+
+/// gzipを解凍する前のボディを格納するRequestContextのキー（`Content-Digest`がある場合のみ）
+pub const ENCODED_BODY_CONTEXT_KEY: &str = "runbridge.encoded_body";
+
+/// ダイジェストのアルゴリズム
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DigestAlgorithm {
+    /// `sha-256`（既定）
+    #[default]
+    Sha256,
+    /// `sha-512`
+    Sha512,
+}
+
+impl DigestAlgorithm {
+    /// `Content-Digest`で使用するアルゴリズム名
+    pub fn token(&self) -> &'static str {
+        match self {
+            DigestAlgorithm::Sha256 => "sha-256",
+            DigestAlgorithm::Sha512 => "sha-512",
+        }
+    }
+
+    /// アルゴリズム名から解析（`Digest`ヘッダーの`SHA-256`等も受け付ける）
+    pub fn parse(token: &str) -> Option<Self> {
+        match token.trim().to_ascii_lowercase().as_str() {
+            "sha-256" => Some(DigestAlgorithm::Sha256),
+            "sha-512" => Some(DigestAlgorithm::Sha512),
+            _ => None,
+        }
+    }
+
+    /// ダイジェストを計算
+    pub fn digest(&self, body: &[u8]) -> Vec<u8> {
+        match self {
+            DigestAlgorithm::Sha256 => Sha256::digest(body).to_vec(),
+            DigestAlgorithm::Sha512 => Sha512::digest(body).to_vec(),
+        }
+    }
+}
+
+/// `Content-Digest`ヘッダーの値を生成（例: `sha-256=:X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=:`）
+pub fn content_digest_value(algorithm: DigestAlgorithm, body: &[u8]) -> String {
+    format!("{}=:{}:", algorithm.token(), base64::encode(algorithm.digest(body)))
+}
+
+/// `Content-Digest`ヘッダーを解析（未対応のアルゴリズムや不正な値の要素は名前だけを返し、値はNone）
+fn parse_content_digest(header: &str) -> Vec<(String, Option<Vec<u8>>)> {
+    header
+        .split(',')
+        .filter_map(|member| {
+            let (name, value) = member.trim().split_once('=')?;
+            // パラメーター（`;`以降）は無視する
+            let value = value.split(';').next().unwrap_or("").trim();
+            let bytes = value
+                .strip_prefix(':')
+                .and_then(|v| v.strip_suffix(':'))
+                .and_then(|v| base64::decode(v).ok());
+            Some((name.trim().to_ascii_lowercase(), bytes))
+        })
+        .collect()
+}
+
+/// 旧仕様の`Digest`ヘッダー（`SHA-256=<base64>`）を解析
+fn parse_legacy_digest(header: &str) -> Vec<(String, Option<Vec<u8>>)> {
+    header
+        .split(',')
+        .filter_map(|member| {
+            let (name, value) = member.trim().split_once('=')?;
+            Some((name.trim().to_ascii_lowercase(), base64::decode(value.trim()).ok()))
+        })
+        .collect()
+}
+
+impl Request {
+    /// `Content-Digest`（無い場合は`Digest`）をボディと照合
+    ///
+    /// 対応するアルゴリズムの値が1つでも一致しない場合は`InvalidRequestBody`（400）を返します。
+    /// ヘッダーが無い、または未対応のアルゴリズムだけの場合は`Ok(false)`、照合できた場合は`Ok(true)`です。
+    pub fn verify_content_digest(&self) -> Result<bool, Error> {
+        let members = match (self.headers.get("content-digest"), self.headers.get("digest")) {
+            (Some(header), _) => parse_content_digest(header),
+            (None, Some(header)) => parse_legacy_digest(header),
+            (None, None) => return Ok(false),
+        };
+        let body = self
+            .context()
+            .get::<Vec<u8>>(ENCODED_BODY_CONTEXT_KEY)
+            .map(Vec::as_slice)
+            .or(self.body.as_deref())
+            .unwrap_or(&[]);
+
+        let mut verified = false;
+        for (name, expected) in members {
+            let Some(algorithm) = DigestAlgorithm::parse(&name) else {
+                log::debug!("Ignoring unsupported digest algorithm: {}", name);
+                continue;
+            };
+            let matches = expected.is_some_and(|expected| constant_time_eq(&algorithm.digest(body), &expected));
+            if !matches {
+                log::warn!("Request body does not match its {} digest ({} bytes)", algorithm.token(), body.len());
+                return Err(Error::InvalidRequestBody("Content-Digest does not match the request body".to_string()));
+            }
+            verified = true;
+        }
+        Ok(verified)
+    }
+}
+
+impl Response {
+    /// ボディの`Content-Digest`を付与（ボディが無い場合は何もしない）
+    pub fn with_content_digest(mut self, algorithm: DigestAlgorithm) -> Self {
+        if let Some(body) = &self.body {
+            let value = content_digest_value(algorithm, body);
+            self.headers.insert("Content-Digest".to_string(), value);
+        }
+        self
+    }
+}
+
+/// リクエストの`Content-Digest`の照合と、レスポンスへの`Content-Digest`の付与を行うミドルウェア
+///
+/// 圧縮（`CompressionMiddleware`）より外側で動作するため、付与するダイジェストは圧縮後のボディに対するものです。
+///
+/// ```
+/// use runbridge::common::{ContentDigestMiddleware, DigestAlgorithm};
+/// use runbridge::RunBridge;
+///
+/// let app = RunBridge::builder()
+///     .middleware(ContentDigestMiddleware::new().algorithm(DigestAlgorithm::Sha512).require_request_digest(true))
+///     .build();
+/// # drop(app);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ContentDigestMiddleware {
+    algorithm: DigestAlgorithm,
+    verify_requests: bool,
+    require_request_digest: bool,
+    sign_responses: bool,
+    legacy_digest_header: bool,
+}
+
+impl Default for ContentDigestMiddleware {
+    fn default() -> Self {
+        Self {
+            algorithm: DigestAlgorithm::default(),
+            verify_requests: true,
+            require_request_digest: false,
+            sign_responses: true,
+            legacy_digest_header: false,
+        }
+    }
+}
+
+impl ContentDigestMiddleware {
+    /// 新しいContentDigestMiddlewareを作成（照合・付与ともに有効、`sha-256`）
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// レスポンスに付与するダイジェストのアルゴリズムを設定
+    pub fn algorithm(mut self, algorithm: DigestAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// リクエストの`Content-Digest`を照合するか（既定: 有効）
+    pub fn verify_requests(mut self, enabled: bool) -> Self {
+        self.verify_requests = enabled;
+        self
+    }
+
+    /// ボディのあるリクエストに照合可能な`Content-Digest`を必須にするか（既定: 無効）
+    pub fn require_request_digest(mut self, required: bool) -> Self {
+        self.require_request_digest = required;
+        self
+    }
+
+    /// レスポンスに`Content-Digest`を付与するか（既定: 有効）
+    pub fn sign_responses(mut self, enabled: bool) -> Self {
+        self.sign_responses = enabled;
+        self
+    }
+
+    /// 旧仕様の`Digest`ヘッダー（RFC 3230）も付与するか（既定: 無効）
+    pub fn legacy_digest_header(mut self, enabled: bool) -> Self {
+        self.legacy_digest_header = enabled;
+        self
+    }
+}
+
+#[async_trait]
+impl Middleware for ContentDigestMiddleware {
+    async fn pre_process(&self, req: Request) -> Result<Request, Error> {
+        if !self.verify_requests {
+            return Ok(req);
+        }
+        let verified = req.verify_content_digest()?;
+        let has_body = req.body.as_ref().is_some_and(|b| !b.is_empty());
+        if self.require_request_digest && has_body && !verified {
+            log::warn!("Rejected request to {} without a verifiable Content-Digest", req.path);
+            return Err(Error::InvalidRequestBody("Content-Digest is required".to_string()));
+        }
+        Ok(req)
+    }
+
+    async fn post_process(&self, res: Response) -> Result<Response, Error> {
+        if !self.sign_responses || res.is_sse() || res.header("content-digest").is_some() {
+            return Ok(res);
+        }
+        let Some(body) = &res.body else {
+            return Ok(res);
+        };
+        let digest = self.algorithm.digest(body);
+        let mut res = res.with_content_digest(self.algorithm);
+        if self.legacy_digest_header {
+            let name = self.algorithm.token().to_ascii_uppercase();
+            res.headers.insert("Digest".to_string(), format!("{}={}", name, base64::encode(digest)));
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Method;
+
+    fn request(body: &[u8]) -> Request {
+        Request::new(Method::POST, "/webhook".to_string()).with_body(body.to_vec())
+    }
+
+    #[test]
+    fn test_content_digest_value_rfc9530_example() {
+        // RFC 9530 Appendix Bの例（`{"hello": "world"}`）
+        let body = br#"{"hello": "world"}"#;
+        assert_eq!(
+            content_digest_value(DigestAlgorithm::Sha256, body),
+            "sha-256=:X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=:"
+        );
+        assert!(content_digest_value(DigestAlgorithm::Sha512, body).starts_with("sha-512=:WZDPaVn/7XgHaAy8pmojAkGWoRx2UFChF41A2svX+T"));
+    }
+
+    #[test]
+    fn test_verify_content_digest() {
+        let body = br#"{"hello": "world"}"#;
+        let valid = request(body).with_header("Content-Digest", content_digest_value(DigestAlgorithm::Sha256, body));
+        assert!(valid.verify_content_digest().unwrap());
+
+        let tampered = request(br#"{"hello": "there"}"#)
+            .with_header("Content-Digest", content_digest_value(DigestAlgorithm::Sha256, body));
+        assert_eq!(tampered.verify_content_digest().unwrap_err().status_code(), 400);
+
+        // 未対応のアルゴリズムは無視し、壊れた値は不一致として扱う
+        let unknown = request(body).with_header("Content-Digest", "md5=:AAAA:");
+        assert!(!unknown.verify_content_digest().unwrap());
+        let broken = request(body).with_header("Content-Digest", "sha-256=not-a-byte-sequence");
+        assert!(broken.verify_content_digest().is_err());
+
+        let legacy = request(body).with_header("Digest", "SHA-256=X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=");
+        assert!(legacy.verify_content_digest().unwrap());
+        assert!(!request(body).verify_content_digest().unwrap());
+    }
+
+    #[test]
+    fn test_verify_uses_encoded_body_after_gzip() {
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"payload").unwrap();
+        let encoded = encoder.finish().unwrap();
+        let mut req = request(&encoded)
+            .with_header("Content-Encoding", "gzip")
+            .with_header("Content-Digest", content_digest_value(DigestAlgorithm::Sha256, &encoded));
+        req.decompress_gzip_body().unwrap();
+        assert_eq!(req.body.as_deref(), Some(&b"payload"[..]));
+        assert!(req.verify_content_digest().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_middleware() {
+        let middleware = ContentDigestMiddleware::new().require_request_digest(true).legacy_digest_header(true);
+        assert_eq!(middleware.pre_process(request(b"x")).await.unwrap_err().status_code(), 400);
+        assert!(middleware.pre_process(Request::new(Method::GET, "/".to_string())).await.is_ok());
+
+        let res = middleware.post_process(Response::ok().with_body(b"abc".to_vec())).await.unwrap();
+        assert_eq!(
+            res.header("content-digest"),
+            Some("sha-256=:ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0=:")
+        );
+        assert_eq!(res.header("digest"), Some("SHA-256=ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0="));
+        let empty = middleware.post_process(Response::no_content()).await.unwrap();
+        assert!(empty.header("content-digest").is_none());
+    }
+}
@@ -0,0 +1,162 @@
+//! レスポンスのContent-Type
+//!
+//! `Response::set_content_type`・`ResponseBuilder::content_type`は大文字小文字の違う
+//! 既存のContent-Typeを置き換えるため、ヘッダーが重複しません。
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// Content-Typeの値（メディアタイプと文字コード）
+///
+/// 型付きのコンストラクタは、テキスト系（`text/*`）に`charset=utf-8`を付けます。
+/// `application/json`はRFC 8259でcharsetパラメータが定義されていない（常にUTF-8）ため付けません。
+/// 文字列から変換した場合は、指定された値をそのまま使用します。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentType {
+    mime: String,
+    charset: Option<String>,
+}
+
+impl ContentType {
+    /// メディアタイプを指定して作成（`text/*`には`charset=utf-8`を付与）
+    pub fn new(mime: impl Into<String>) -> Self {
+        let mime = mime.into().trim().to_ascii_lowercase();
+        let charset = mime.starts_with("text/").then(|| "utf-8".to_string());
+        Self { mime, charset }
+    }
+
+    /// `application/json`
+    pub fn json() -> Self {
+        Self::new("application/json")
+    }
+
+    /// `text/plain; charset=utf-8`
+    pub fn text() -> Self {
+        Self::new("text/plain")
+    }
+
+    /// `text/html; charset=utf-8`
+    pub fn html() -> Self {
+        Self::new("text/html")
+    }
+
+    /// `application/octet-stream`
+    pub fn octet_stream() -> Self {
+        Self::new("application/octet-stream")
+    }
+
+    /// `Content-Type`ヘッダーの値を解析（`charset`以外のパラメータは無視）
+    pub fn parse(value: &str) -> Self {
+        let mut parts = value.split(';');
+        let mime = parts.next().unwrap_or("").trim().to_ascii_lowercase();
+        let charset = parts.find_map(|p| {
+            let (k, v) = p.split_once('=')?;
+            k.trim()
+                .eq_ignore_ascii_case("charset")
+                .then(|| v.trim().trim_matches('"').to_ascii_lowercase())
+        });
+        Self { mime, charset }
+    }
+
+    /// 文字コードを指定
+    pub fn with_charset(mut self, charset: impl Into<String>) -> Self {
+        self.charset = Some(charset.into());
+        self
+    }
+
+    /// 文字コードを付けない
+    pub fn without_charset(mut self) -> Self {
+        self.charset = None;
+        self
+    }
+
+    /// メディアタイプ（小文字、パラメータなし）
+    pub fn mime(&self) -> &str {
+        &self.mime
+    }
+
+    /// 文字コード
+    pub fn charset(&self) -> Option<&str> {
+        self.charset.as_deref()
+    }
+
+    /// JSON（`application/json`または`+json`）かどうか
+    pub fn is_json(&self) -> bool {
+        self.mime == "application/json" || self.mime.ends_with("+json")
+    }
+}
+
+impl fmt::Display for ContentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.charset {
+            Some(charset) => write!(f, "{}; charset={}", self.mime, charset),
+            None => f.write_str(&self.mime),
+        }
+    }
+}
+
+impl From<&str> for ContentType {
+    fn from(value: &str) -> Self {
+        Self::parse(value)
+    }
+}
+
+impl From<String> for ContentType {
+    fn from(value: String) -> Self {
+        Self::parse(&value)
+    }
+}
+
+/// 大文字小文字を区別せずにヘッダーを置き換える（重複を防ぐ）
+pub(crate) fn replace_header(headers: &mut HashMap<String, String>, key: String, value: String) {
+    headers.retain(|k, _| !k.eq_ignore_ascii_case(&key));
+    headers.insert(key, value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{Response, ResponseBuilder};
+
+    #[test]
+    fn test_content_type_values() {
+        assert_eq!(ContentType::text().to_string(), "text/plain; charset=utf-8");
+        assert_eq!(ContentType::json().to_string(), "application/json");
+        assert_eq!(ContentType::new("text/csv").without_charset().to_string(), "text/csv");
+
+        let parsed = ContentType::parse("Text/HTML; Charset=\"ISO-8859-1\"");
+        assert_eq!(parsed.mime(), "text/html");
+        assert_eq!(parsed.charset(), Some("iso-8859-1"));
+        assert!(ContentType::parse("application/problem+json").is_json());
+        // 文字列からの変換では文字コードを補わない
+        assert_eq!(ContentType::from("text/plain").to_string(), "text/plain");
+    }
+
+    #[test]
+    fn test_set_content_type_replaces_case_variants() {
+        let mut res = Response::ok()
+            .with_header("content-type", "text/plain")
+            .with_header("CONTENT-TYPE", "text/csv");
+        assert_eq!(res.headers.keys().filter(|k| k.eq_ignore_ascii_case("content-type")).count(), 1);
+
+        res.set_content_type(ContentType::html());
+        assert_eq!(res.headers.keys().filter(|k| k.eq_ignore_ascii_case("content-type")).count(), 1);
+        assert_eq!(res.content_type(), Some("text/html; charset=utf-8"));
+
+        let res = res.json(&serde_json::json!({"a": 1})).unwrap();
+        assert_eq!(res.headers.get("Content-Type").map(String::as_str), Some("application/json"));
+        assert_eq!(res.headers.len(), Response::ok().headers.len() + 1);
+
+        let res = ResponseBuilder::new(200)
+            .header("content-type", "text/csv")
+            .text("a,b")
+            .build();
+        assert_eq!(res.content_type(), Some("text/plain; charset=utf-8"));
+        assert_eq!(res.headers.keys().filter(|k| k.eq_ignore_ascii_case("content-type")).count(), 1);
+
+        // build時の既定ヘッダー補完でも重複しない
+        let res = ResponseBuilder::new(200).header("x-frame-options", "SAMEORIGIN").build();
+        assert_eq!(res.headers.keys().filter(|k| k.eq_ignore_ascii_case("x-frame-options")).count(), 1);
+        assert_eq!(res.headers.get("x-frame-options").map(String::as_str), Some("SAMEORIGIN"));
+    }
+}
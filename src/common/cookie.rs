@@ -1,11 +1,18 @@
 //! HTTPクッキー関連の実装
 
+use std::collections::HashMap;
 use std::fmt;
 use std::time::Duration;
 use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Serialize};
 use crate::error::Error;
+use super::http::{Request, Response};
+use super::http_date::format_http_date;
 use super::utils::{validate_cookie_name_value, is_header_value_valid};
 
+/// 型付きクッキー値（Base64URL化したJSON）の最大長（ブラウザの1クッキーあたりの上限に合わせる）
+pub const MAX_TYPED_COOKIE_SIZE: usize = 4096;
+
 /// SameSite属性
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SameSite {
@@ -143,7 +150,7 @@ impl Cookie {
         }
 
         if let Some(expires) = &self.expires {
-            cookie_str.push_str(&format!("; Expires={}", expires.format("%a, %d %b %Y %H:%M:%S GMT")));
+            cookie_str.push_str(&format!("; Expires={}", format_http_date(*expires)));
         }
 
         if let Some(max_age) = &self.max_age {
@@ -166,6 +173,233 @@ impl Cookie {
     }
 }
 
+impl Cookie {
+    /// 値をJSON化してBase64URL（パディングなし）でエンコードしたクッキーを作成
+    ///
+    /// エンコード後の値が`MAX_TYPED_COOKIE_SIZE`を超える場合はエラーを返します。
+    pub fn typed<T: Serialize>(name: impl Into<String>, value: &T) -> Result<Self, Error> {
+        let json = serde_json::to_vec(value)
+            .map_err(|e| Error::ResponseSerializationError(e.to_string()))?;
+        let encoded = base64::encode_config(json, base64::URL_SAFE_NO_PAD);
+        if encoded.len() > MAX_TYPED_COOKIE_SIZE {
+            return Err(Error::InvalidCookie(format!(
+                "typed cookie value too large ({} bytes, limit {})",
+                encoded.len(),
+                MAX_TYPED_COOKIE_SIZE
+            )));
+        }
+        Self::try_new(name, encoded)
+    }
+}
+
+/// `Cookie::typed`でエンコードされた値をデコード
+fn decode_typed_value<T: DeserializeOwned>(name: &str, raw: &str) -> Result<T, Error> {
+    if raw.len() > MAX_TYPED_COOKIE_SIZE {
+        return Err(Error::InvalidCookie(format!("cookie '{}' is too large", name)));
+    }
+    let json = base64::decode_config(raw, base64::URL_SAFE_NO_PAD)
+        .map_err(|_| Error::InvalidCookie(format!("cookie '{}' is not valid base64", name)))?;
+    serde_json::from_slice(&json)
+        .map_err(|e| Error::InvalidCookie(format!("cookie '{}' has unexpected content: {}", name, e)))
+}
+
+/// リクエストのCookieヘッダーを解析したクッキー一覧
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cookies {
+    values: HashMap<String, String>,
+}
+
+impl Cookies {
+    /// Cookieヘッダー値（`a=1; b=2`）を解析（同名のクッキーは先に現れたものを優先）
+    pub fn parse(header: &str) -> Self {
+        let mut values = HashMap::new();
+        for pair in header.split(';') {
+            if let Some((name, value)) = pair.trim().split_once('=') {
+                let name = name.trim();
+                if name.is_empty() {
+                    continue;
+                }
+                let value = value.trim().trim_matches('"');
+                values.entry(name.to_string()).or_insert_with(|| value.to_string());
+            }
+        }
+        Self { values }
+    }
+
+    /// クッキーの値を取得
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    /// `Cookie::typed`で設定したクッキーをデコードして取得
+    ///
+    /// クッキーが無い場合は`Ok(None)`、デコードできない場合は`InvalidCookie`（400）を返します。
+    pub fn get_typed<T: DeserializeOwned>(&self, name: &str) -> Result<Option<T>, Error> {
+        self.get(name).map(|raw| decode_typed_value(name, raw)).transpose()
+    }
+
+    /// クッキーの数
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// クッキーが1つも無いか
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// 名前と値の組を列挙
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.values.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+}
+
+/// 連結された Set-Cookie ヘッダー値を安全に分割する（各ランタイムのレスポンス出力で使用）
+/// 注意: RFC的にはSet-Cookieは結合不可だが、実装上HashMap制約の回避として
+/// "," 区切りで結合されたケースを考慮し、Expires 属性内のカンマは分割対象から除外する。
+pub fn split_set_cookie_header(value: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut buf = String::new();
+    let mut in_expires = false;
+    let mut chars = value.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            // セミコロンで属性の区切りを検出（Expires= のスコープ終端にもなる）
+            ';' => {
+                in_expires = false; // Expires= の属性スコープを抜ける
+                buf.push(ch);
+            }
+            // カンマは、Expires= 属性中ならそのまま、それ以外ならCookie間区切りの可能性
+            ',' => {
+                if in_expires {
+                    buf.push(ch);
+                } else {
+                    // 直後の空白をスキップ
+                    while let Some(' ') = chars.peek() {
+                        chars.next();
+                    }
+                    // 次のトークンが cookie-pair らしい（= を含む）なら分割、それ以外は文字として扱う
+                    // 先読みして '=' がセミコロンより前に現れるかを確認
+                    let mut lookahead = String::new();
+                    let mut iter = chars.clone();
+                    let mut seen_eq_before_semicolon = false;
+                    while let Some(&c) = iter.peek() {
+                        if c == ';' || c == ',' { break; }
+                        if c == '=' { seen_eq_before_semicolon = true; break; }
+                        lookahead.push(c);
+                        iter.next();
+                    }
+                    if seen_eq_before_semicolon {
+                        // ここで一旦Cookieを確定
+                        let part = buf.trim();
+                        if !part.is_empty() { result.push(part.to_string()); }
+                        buf.clear();
+                        continue;
+                    } else {
+                        // Cookie間区切りではないので文字として追加
+                        buf.push(',');
+                    }
+                }
+            }
+            // 'E' または 'e' から始まる Expires= を検出してフラグを立てる
+            'E' | 'e' => {
+                // 現在位置から "xpires=" までを確認（ケースインセンシティブ）
+                let mut shadow = chars.clone();
+                let mut matches = true;
+                for expected in ['x','p','i','r','e','s','='] {
+                    if let Some(c) = shadow.next() {
+                        if c.to_ascii_lowercase() != expected { matches = false; break; }
+                    } else { matches = false; break; }
+                }
+                if matches {
+                    in_expires = true;
+                }
+                buf.push(ch);
+            }
+            _ => {
+                buf.push(ch);
+            }
+        }
+    }
+
+    let tail = buf.trim();
+    if !tail.is_empty() {
+        result.push(tail.to_string());
+    }
+
+    // 単一Cookieしか得られなかった場合は、
+    // 呼び出し側でそのまま扱えるように空ベクタではなく単一要素でも返す
+    result
+}
+
+impl Request {
+    /// Cookieヘッダーを解析したクッキー一覧を取得
+    pub fn cookies(&self) -> Cookies {
+        self.headers
+            .get("cookie")
+            .map(|h| Cookies::parse(h))
+            .unwrap_or_default()
+    }
+}
+
+impl Response {
+    /// 値をJSON＋Base64URLでエンコードしたクッキーを追加（`Path=/`、`SameSite=Lax`）
+    ///
+    /// 属性を細かく指定する場合は`Cookie::typed`を使用してください。
+    pub fn set_typed_cookie<T: Serialize>(mut self, name: impl Into<String>, value: &T) -> Result<Self, Error> {
+        let cookie = Cookie::typed(name, value)?
+            .with_path("/")
+            .with_same_site(SameSite::Lax);
+        self.append_set_cookie(cookie.to_header_value());
+        Ok(self)
+    }
+
+    /// クッキーを追加（同じレスポンスに複数追加でき、それぞれ別のSet-Cookieとして出力）
+    ///
+    /// ```
+    /// use runbridge::common::{Cookie, Response};
+    ///
+    /// let res = Response::ok()
+    ///     .add_cookie(Cookie::new("session", "abc").http_only(true))
+    ///     .add_cookie(Cookie::new("theme", "dark").with_path("/"));
+    /// assert_eq!(res.set_cookie_values(), vec!["session=abc; HttpOnly", "theme=dark; Path=/"]);
+    /// ```
+    pub fn add_cookie(mut self, cookie: Cookie) -> Self {
+        self.push_cookie(cookie);
+        self
+    }
+
+    /// クッキーを追加（`add_cookie`の`&mut self`版）
+    pub fn push_cookie(&mut self, cookie: Cookie) {
+        self.append_set_cookie(cookie.to_header_value());
+    }
+
+    /// Set-Cookieの値を1つずつ取得（カンマ区切りで連結された値は分割）
+    ///
+    /// `Set-Cookie`ヘッダーの値（1つのヘッダーに連結されたものは分割）に続けて、
+    /// `add_cookie`で追加した値を追加した順に返します。各ランタイムは出力時に
+    /// この値を1つずつ別のヘッダー（Lambdaでは`cookies`配列）として送信します。
+    pub fn set_cookie_values(&self) -> Vec<String> {
+        self.headers
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case("set-cookie"))
+            .flat_map(|(_, value)| split_set_cookie_header(value))
+            .chain(self.cookies.iter().cloned())
+            .collect()
+    }
+
+    /// Set-Cookieの値を追加（ヘッダーには連結せず、出力時に1つずつ送信する）
+    pub(crate) fn append_set_cookie(&mut self, value: String) {
+        self.cookies.push(value);
+    }
+
+    /// Set-Cookieを1つでも含むか
+    pub(crate) fn has_set_cookie(&self) -> bool {
+        !self.cookies.is_empty() || self.headers.keys().any(|k| k.eq_ignore_ascii_case("set-cookie"))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -275,4 +509,69 @@ mod tests {
         assert!(hv.contains("Path=/ok"));
         assert!(!hv.contains("Domain=bad"));
     }
+
+    #[derive(Debug, PartialEq, Serialize, serde::Deserialize)]
+    struct Prefs {
+        theme: String,
+        analytics: bool,
+    }
+
+    #[test]
+    fn test_typed_cookie_round_trip() {
+        let prefs = Prefs { theme: "dark".into(), analytics: false };
+        let res = Response::ok()
+            .set_typed_cookie("prefs", &prefs)
+            .unwrap()
+            .set_typed_cookie("consent", &vec!["ads"])
+            .unwrap();
+        assert!(!res.headers.contains_key("Set-Cookie"));
+        let values = res.set_cookie_values();
+        assert_eq!(values.len(), 2);
+        assert!(values[1].starts_with("consent="));
+
+        let value = values[0].split(';').next().unwrap().trim_start_matches("prefs=");
+        let req = Request::new(super::super::http::Method::GET, "/".into())
+            .with_header("Cookie", format!("session=abc; prefs={}", value));
+        let cookies = req.cookies();
+        assert_eq!(cookies.get("session"), Some("abc"));
+        assert_eq!(cookies.get_typed::<Prefs>("prefs").unwrap(), Some(prefs));
+        assert_eq!(cookies.get_typed::<Prefs>("missing").unwrap(), None);
+    }
+
+    #[test]
+    fn test_response_builder_keeps_cookies() {
+        let res = Response::ok().add_cookie(Cookie::new("session", "abc"));
+        let rebuilt = super::super::http::ResponseBuilder::from(res).header("X-Extra", "1").build();
+        assert_eq!(rebuilt.set_cookie_values(), vec!["session=abc"]);
+    }
+
+    #[test]
+    fn test_typed_cookie_errors() {
+        let cookies = Cookies::parse("a=!!!; b=eyJ4IjoxfQ; c=");
+        assert_eq!(cookies.get_typed::<Prefs>("a").unwrap_err().status_code(), 400);
+        assert_eq!(cookies.get_typed::<Prefs>("b").unwrap_err().status_code(), 400);
+        assert_eq!(cookies.get("c"), Some(""));
+
+        let huge = "x".repeat(MAX_TYPED_COOKIE_SIZE);
+        assert!(Cookie::typed("big", &huge).is_err());
+    }
+
+    #[test]
+    fn test_add_cookie_keeps_each_value() {
+        let expires = DateTime::parse_from_rfc2822("Wed, 21 Oct 2026 07:28:00 GMT").unwrap().with_timezone(&Utc);
+        let res = Response::ok()
+            .with_header("Set-Cookie", "legacy=1")
+            .add_cookie(Cookie::new("a", "1").with_expires(expires))
+            .add_cookie(Cookie::new("b", "2").secure(true));
+        assert!(res.has_set_cookie());
+        assert_eq!(
+            res.set_cookie_values(),
+            vec![
+                "legacy=1".to_string(),
+                "a=1; Expires=Wed, 21 Oct 2026 07:28:00 GMT".to_string(),
+                "b=2; Secure".to_string(),
+            ]
+        );
+        assert!(!Response::ok().has_set_cookie());
+    }
 }
@@ -0,0 +1,354 @@
+//! Content-Security-Policyヘッダーの型付きビルダー
+//!
+//! ディレクティブとソースを型で組み立てるため、引用符や区切り文字の付け忘れによる
+//! 無効なポリシーを防ぎます。AroundMiddlewareとして登録すると、リクエストごとに
+//! nonceを生成して`Request::csp_nonce`で参照できるようにし、レスポンスにヘッダーを設定します。
+//!
+//! ```
+//! use runbridge::common::{ContentSecurityPolicy, Source};
+//!
+//! let csp = ContentSecurityPolicy::new()
+//!     .default_src([Source::SelfOrigin])
+//!     .script_src([Source::Nonce, Source::StrictDynamic])
+//!     .img_src([Source::SelfOrigin, Source::scheme("data")])
+//!     .object_src([Source::None]);
+//! assert_eq!(
+//!     csp.render(Some("abc")),
+//!     "default-src 'self'; script-src 'nonce-abc' 'strict-dynamic'; img-src 'self' data:; object-src 'none'"
+//! );
+//! ```
+
+use std::fmt;
+
+use async_trait::async_trait;
+use log::warn;
+use sha2::{Digest, Sha256};
+
+use crate::error::Error;
+use super::http::{Request, Response};
+use super::traits::{AroundMiddleware, Next};
+
+/// RequestContextにnonceを格納する際のキー
+pub const CSP_NONCE_KEY: &str = "runbridge.csp_nonce";
+
+/// ディレクティブに指定するソース
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// `'self'`
+    SelfOrigin,
+    /// `'none'`
+    None,
+    /// `'unsafe-inline'`
+    UnsafeInline,
+    /// `'unsafe-eval'`
+    UnsafeEval,
+    /// `'strict-dynamic'`
+    StrictDynamic,
+    /// リクエストごとのnonce（`'nonce-...'`、nonceが無い場合は出力しない）
+    Nonce,
+    /// インラインスクリプト・スタイルのハッシュ（`'sha256-...'`）
+    Hash(String),
+    /// スキーム（`https:`、`data:`など）
+    Scheme(String),
+    /// ホスト（`cdn.example.com`、`*.example.com`、`https://example.com`など）
+    Host(String),
+}
+
+impl Source {
+    /// インラインのスクリプト・スタイルの内容からSHA-256のハッシュソースを作成
+    pub fn sha256(content: &str) -> Self {
+        Source::Hash(format!("sha256-{}", base64::encode(Sha256::digest(content.as_bytes()))))
+    }
+
+    /// スキームのソースを作成（末尾の`:`は省略可）
+    pub fn scheme(scheme: impl Into<String>) -> Self {
+        Source::Scheme(scheme.into().trim_end_matches(':').to_string())
+    }
+
+    /// ホストのソースを作成
+    pub fn host(host: impl Into<String>) -> Self {
+        Source::Host(host.into())
+    }
+
+    fn render(&self, nonce: Option<&str>) -> Option<String> {
+        let value = match self {
+            Source::SelfOrigin => "'self'".to_string(),
+            Source::None => "'none'".to_string(),
+            Source::UnsafeInline => "'unsafe-inline'".to_string(),
+            Source::UnsafeEval => "'unsafe-eval'".to_string(),
+            Source::StrictDynamic => "'strict-dynamic'".to_string(),
+            Source::Nonce => format!("'nonce-{}'", nonce?),
+            Source::Hash(hash) => {
+                if !is_token(hash, "+/=-") {
+                    warn!("Ignoring invalid CSP hash source: {:?}", hash);
+                    return None;
+                }
+                format!("'{}'", hash)
+            }
+            Source::Scheme(scheme) => {
+                if scheme.is_empty() || !is_token(scheme, "+.-") {
+                    warn!("Ignoring invalid CSP scheme source: {:?}", scheme);
+                    return None;
+                }
+                format!("{}:", scheme)
+            }
+            Source::Host(host) => {
+                if host.is_empty() || !is_token(host, ":/.*-_%[]") {
+                    warn!("Ignoring invalid CSP host source: {:?}", host);
+                    return None;
+                }
+                host.clone()
+            }
+        };
+        Some(value)
+    }
+}
+
+/// 英数字と指定した記号のみで構成されているかどうか（`;`・`,`・空白・引用符を含む値を拒否）
+fn is_token(value: &str, allowed: &str) -> bool {
+    value.chars().all(|c| c.is_ascii_alphanumeric() || allowed.contains(c))
+}
+
+/// Content-Security-Policyのビルダー
+///
+/// `Default`は従来の既定値`default-src 'self'`です。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentSecurityPolicy {
+    directives: Vec<(String, Vec<Source>)>,
+    upgrade_insecure_requests: bool,
+}
+
+impl Default for ContentSecurityPolicy {
+    fn default() -> Self {
+        Self::new().default_src([Source::SelfOrigin])
+    }
+}
+
+impl ContentSecurityPolicy {
+    /// ディレクティブの無いポリシーを作成
+    pub fn new() -> Self {
+        Self {
+            directives: Vec::new(),
+            upgrade_insecure_requests: false,
+        }
+    }
+
+    /// nonceベースの厳格なポリシー（インラインスクリプトはnonce付きのもののみ許可）
+    pub fn strict() -> Self {
+        Self::new()
+            .default_src([Source::SelfOrigin])
+            .script_src([Source::Nonce, Source::StrictDynamic])
+            .style_src([Source::SelfOrigin, Source::Nonce])
+            .object_src([Source::None])
+            .base_uri([Source::None])
+            .frame_ancestors([Source::None])
+    }
+
+    /// ディレクティブを設定（同名のディレクティブがある場合は置き換え）
+    ///
+    /// 名前が英小文字と`-`以外を含む場合は無視します。
+    pub fn directive(mut self, name: &str, sources: impl IntoIterator<Item = Source>) -> Self {
+        let name = name.to_ascii_lowercase();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_lowercase() || c == '-') {
+            warn!("Ignoring invalid CSP directive name: {:?}", name);
+            return self;
+        }
+        let sources: Vec<Source> = sources.into_iter().collect();
+        match self.directives.iter_mut().find(|(n, _)| *n == name) {
+            Some(entry) => entry.1 = sources,
+            None => self.directives.push((name, sources)),
+        }
+        self
+    }
+
+    /// `default-src`
+    pub fn default_src(self, sources: impl IntoIterator<Item = Source>) -> Self {
+        self.directive("default-src", sources)
+    }
+
+    /// `script-src`
+    pub fn script_src(self, sources: impl IntoIterator<Item = Source>) -> Self {
+        self.directive("script-src", sources)
+    }
+
+    /// `style-src`
+    pub fn style_src(self, sources: impl IntoIterator<Item = Source>) -> Self {
+        self.directive("style-src", sources)
+    }
+
+    /// `img-src`
+    pub fn img_src(self, sources: impl IntoIterator<Item = Source>) -> Self {
+        self.directive("img-src", sources)
+    }
+
+    /// `connect-src`
+    pub fn connect_src(self, sources: impl IntoIterator<Item = Source>) -> Self {
+        self.directive("connect-src", sources)
+    }
+
+    /// `font-src`
+    pub fn font_src(self, sources: impl IntoIterator<Item = Source>) -> Self {
+        self.directive("font-src", sources)
+    }
+
+    /// `object-src`
+    pub fn object_src(self, sources: impl IntoIterator<Item = Source>) -> Self {
+        self.directive("object-src", sources)
+    }
+
+    /// `base-uri`
+    pub fn base_uri(self, sources: impl IntoIterator<Item = Source>) -> Self {
+        self.directive("base-uri", sources)
+    }
+
+    /// `form-action`
+    pub fn form_action(self, sources: impl IntoIterator<Item = Source>) -> Self {
+        self.directive("form-action", sources)
+    }
+
+    /// `frame-ancestors`
+    pub fn frame_ancestors(self, sources: impl IntoIterator<Item = Source>) -> Self {
+        self.directive("frame-ancestors", sources)
+    }
+
+    /// `upgrade-insecure-requests`
+    pub fn upgrade_insecure_requests(mut self) -> Self {
+        self.upgrade_insecure_requests = true;
+        self
+    }
+
+    /// nonceを使用するディレクティブがあるかどうか
+    pub fn uses_nonce(&self) -> bool {
+        self.directives
+            .iter()
+            .any(|(_, sources)| sources.contains(&Source::Nonce))
+    }
+
+    /// ヘッダー値を生成（`nonce`が無い場合、`Source::Nonce`は出力しない）
+    ///
+    /// ソースがすべて出力されなかったディレクティブは`'none'`として出力します。
+    pub fn render(&self, nonce: Option<&str>) -> String {
+        let nonce = nonce.filter(|n| !n.is_empty() && is_token(n, "+/=-_"));
+        let mut parts: Vec<String> = self
+            .directives
+            .iter()
+            .map(|(name, sources)| {
+                let values: Vec<String> = sources.iter().filter_map(|s| s.render(nonce)).collect();
+                if values.is_empty() {
+                    format!("{} 'none'", name)
+                } else {
+                    format!("{} {}", name, values.join(" "))
+                }
+            })
+            .collect();
+        if self.upgrade_insecure_requests {
+            parts.push("upgrade-insecure-requests".to_string());
+        }
+        parts.join("; ")
+    }
+}
+
+impl fmt::Display for ContentSecurityPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render(None))
+    }
+}
+
+/// 推測困難なnonce（128ビット、Base64）を生成
+pub fn generate_nonce() -> String {
+    base64::encode(random_bytes())
+}
+
+/// 推測困難な128ビットの値を生成（OSの暗号論的乱数）
+///
+/// OSの乱数源が使用できない環境では推測可能な値で代用せずにパニックします。
+pub(crate) fn random_bytes() -> [u8; 16] {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).expect("OS random number generator is unavailable");
+    bytes
+}
+
+impl Request {
+    /// このリクエストのCSP nonce（`ContentSecurityPolicy`をミドルウェアとして登録した場合）
+    ///
+    /// HTMLテンプレートの`<script nonce="...">`に埋め込みます。
+    pub fn csp_nonce(&self) -> Option<&str> {
+        self.context().get::<String>(CSP_NONCE_KEY).map(String::as_str)
+    }
+}
+
+/// ハンドラーの成功レスポンスに`Content-Security-Policy`を設定する
+/// （ハンドラーが設定した値や既定値は置き換え）
+#[async_trait]
+impl AroundMiddleware for ContentSecurityPolicy {
+    async fn around(&self, mut req: Request, next: Next<'_>) -> Result<Response, Error> {
+        let nonce = if self.uses_nonce() {
+            let nonce = generate_nonce();
+            req.context_mut().set(CSP_NONCE_KEY, nonce.clone());
+            Some(nonce)
+        } else {
+            None
+        };
+
+        let mut res = next.run(req).await?;
+        res.headers.retain(|k, _| !k.eq_ignore_ascii_case("content-security-policy"));
+        res.headers
+            .insert("Content-Security-Policy".to_string(), self.render(nonce.as_deref()));
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Method;
+
+    #[test]
+    fn test_render_policy() {
+        assert_eq!(ContentSecurityPolicy::default().to_string(), "default-src 'self'");
+
+        let csp = ContentSecurityPolicy::new()
+            .script_src([Source::SelfOrigin, Source::sha256("alert(1)"), Source::host("cdn.example.com")])
+            .style_src([Source::Nonce])
+            .frame_ancestors([Source::host("evil.com; script-src *")])
+            .script_src([Source::SelfOrigin, Source::sha256("alert(1)")])
+            .upgrade_insecure_requests();
+        // 同名のディレクティブは置き換え、nonceの無いソースと不正なソースは出力しない
+        assert_eq!(
+            csp.render(None),
+            "script-src 'self' 'sha256-bhHHL3z2vDgxUt0W3dWQOrprscmda2Y5pLsLg4GF+pI='; \
+             style-src 'none'; frame-ancestors 'none'; upgrade-insecure-requests"
+        );
+        assert!(csp.uses_nonce());
+        assert!(csp.render(Some("n0nce")).contains("style-src 'nonce-n0nce'"));
+        assert!(!csp.render(Some("bad'nonce")).contains("bad"));
+        assert_eq!(ContentSecurityPolicy::new().directive("bad name", [Source::None]), ContentSecurityPolicy::new());
+    }
+
+    #[test]
+    fn test_generate_nonce() {
+        let a = generate_nonce();
+        let b = generate_nonce();
+        assert_ne!(a, b);
+        assert_eq!(base64::decode(&a).unwrap().len(), 16);
+    }
+
+    #[tokio::test]
+    async fn test_middleware_injects_nonce() {
+        let around: Vec<Box<dyn AroundMiddleware>> = vec![Box::new(ContentSecurityPolicy::strict())];
+        let req = Request::new(Method::GET, "/".to_string());
+        // nonce付きのscriptタグを返すハンドラー
+        let page = crate::handler::get("^/$", |req: Request| {
+            let nonce = req.csp_nonce().unwrap_or("");
+            Ok(Response::ok().with_body(format!("<script nonce=\"{}\"></script>", nonce).into_bytes()))
+        });
+        let res = Next::new(&page, &around).run(req).await.unwrap();
+
+        let body = String::from_utf8(res.body.unwrap()).unwrap();
+        let nonce = body.split('"').nth(1).unwrap();
+        assert!(!nonce.is_empty());
+        let header = &res.headers["Content-Security-Policy"];
+        assert!(header.contains(&format!("script-src 'nonce-{}' 'strict-dynamic'", nonce)));
+        assert!(header.contains("object-src 'none'"));
+    }
+}
@@ -0,0 +1,63 @@
+//! リクエストの処理期限とタスクローカルへの保持
+//!
+//! Lambdaでは呼び出しコンテキストの期限を、それ以外の実行環境では
+//! `RUNBRIDGE_REQUEST_TIMEOUT_SECS`が設定されている場合にリクエスト受信時刻からの期限を設定します。
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+tokio::task_local! {
+    static DEADLINE: Option<Instant>;
+}
+
+/// リクエストの処理時間の上限を取得する（期限の算出用）
+/// 優先順位: 環境変数 `RUNBRIDGE_REQUEST_TIMEOUT_SECS` -> デフォルト なし（0も無効）
+///
+/// Cloud Runのリクエストタイムアウトなど、実行環境側の設定に合わせて指定してください。
+pub fn get_request_timeout() -> Option<Duration> {
+    std::env::var("RUNBRIDGE_REQUEST_TIMEOUT_SECS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .filter(|&secs| secs > 0)
+        .map(Duration::from_secs)
+}
+
+/// `RUNBRIDGE_REQUEST_TIMEOUT_SECS`から現在のリクエストの期限を算出
+pub fn deadline_from_env() -> Option<Instant> {
+    get_request_timeout().map(|timeout| Instant::now() + timeout)
+}
+
+/// 処理期限をタスクローカルに設定して処理を実行（Noneの場合は期限なし）
+pub async fn with_deadline<F: Future>(deadline: Option<Instant>, fut: F) -> F::Output {
+    DEADLINE.scope(deadline, fut).await
+}
+
+/// 現在のタスクの処理期限を取得
+pub fn current_deadline() -> Option<Instant> {
+    DEADLINE.try_with(|deadline| *deadline).ok().flatten()
+}
+
+/// 処理期限までの残り時間（期限なしの場合はNone、期限切れの場合はゼロ）
+pub fn remaining_time() -> Option<Duration> {
+    current_deadline().map(|deadline| deadline.saturating_duration_since(Instant::now()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_deadline_task_local() {
+        assert_eq!(current_deadline(), None);
+        assert_eq!(remaining_time(), None);
+
+        let deadline = Instant::now() + Duration::from_secs(60);
+        let remaining = with_deadline(Some(deadline), async { remaining_time() }).await.unwrap();
+        assert!(remaining > Duration::from_secs(59));
+
+        let past = Instant::now();
+        let remaining = with_deadline(Some(past), async { remaining_time() }).await;
+        assert_eq!(remaining, Some(Duration::ZERO));
+        assert_eq!(with_deadline(None, async { current_deadline() }).await, None);
+    }
+}
@@ -0,0 +1,342 @@
+//! 外部依存（データベース・外部APIなど）の登録と状態管理
+//!
+//! `DependencyRegistry`は依存ごとの名前・重要度・死活確認（probe）と最新の状態を保持し、
+//! ヘルスチェックエンドポイントの集計と、停止中の依存を使うルートの短絡（503）の
+//! 両方から参照される唯一の情報源になります。
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+use serde::Serialize;
+
+/// probeの既定のタイムアウト
+pub const DEFAULT_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 死活確認の結果（失敗時は理由）
+pub type ProbeResult = Result<(), String>;
+
+type Probe = Arc<dyn Fn() -> Pin<Box<dyn Future<Output = ProbeResult> + Send>> + Send + Sync>;
+
+/// 依存の重要度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Criticality {
+    /// 停止するとサービス全体が機能しない（ヘルスチェックは503）
+    Critical,
+    /// 停止しても一部機能の低下にとどまる（ヘルスチェックはdegraded）
+    NonCritical,
+}
+
+/// 依存の状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyStatus {
+    /// まだ確認していない
+    Unknown,
+    /// 正常
+    Up,
+    /// 停止中
+    Down,
+}
+
+/// 登録する依存の定義
+#[derive(Clone)]
+pub struct Dependency {
+    name: String,
+    criticality: Criticality,
+    probe: Probe,
+    timeout: Duration,
+}
+
+impl std::fmt::Debug for Dependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Dependency")
+            .field("name", &self.name)
+            .field("criticality", &self.criticality)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl Dependency {
+    /// 名前・重要度・死活確認の非同期関数を指定して作成
+    pub fn new<F, Fut>(name: impl Into<String>, criticality: Criticality, probe: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ProbeResult> + Send + 'static,
+    {
+        Self {
+            name: name.into(),
+            criticality,
+            probe: Arc::new(move || Box::pin(probe())),
+            timeout: DEFAULT_PROBE_TIMEOUT,
+        }
+    }
+
+    /// probeのタイムアウトを指定（超えた場合は停止中とみなす）
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// 依存の名前
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 依存の重要度
+    pub fn criticality(&self) -> Criticality {
+        self.criticality
+    }
+}
+
+/// 1つの依存の確認結果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DependencyHealth {
+    /// 依存の名前
+    pub name: String,
+    /// 重要度
+    pub criticality: Criticality,
+    /// 状態
+    pub status: DependencyStatus,
+    /// 停止中の場合の理由
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// probeの所要時間（ミリ秒、probeを実行していない場合はNone）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+}
+
+/// 全体の状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverallHealth {
+    /// すべて正常
+    Up,
+    /// 重要でない依存が停止中
+    Degraded,
+    /// 重要な依存が停止中
+    Down,
+}
+
+/// すべての依存の確認結果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HealthReport {
+    /// 全体の状態
+    pub status: OverallHealth,
+    /// 依存ごとの結果（登録順）
+    pub dependencies: Vec<DependencyHealth>,
+}
+
+impl HealthReport {
+    fn from_dependencies(dependencies: Vec<DependencyHealth>) -> Self {
+        let down = |criticality| {
+            dependencies
+                .iter()
+                .any(|d| d.criticality == criticality && d.status == DependencyStatus::Down)
+        };
+        let status = if down(Criticality::Critical) {
+            OverallHealth::Down
+        } else if down(Criticality::NonCritical) {
+            OverallHealth::Degraded
+        } else {
+            OverallHealth::Up
+        };
+        Self { status, dependencies }
+    }
+
+    /// ヘルスチェックエンドポイントで返すHTTPステータス（Downの場合のみ503）
+    pub fn http_status(&self) -> u16 {
+        match self.status {
+            OverallHealth::Down => 503,
+            OverallHealth::Up | OverallHealth::Degraded => 200,
+        }
+    }
+}
+
+struct Entry {
+    dependency: Dependency,
+    status: DependencyStatus,
+    error: Option<String>,
+}
+
+/// 依存の登録と状態管理（Cloneしたインスタンスは同じ状態を共有）
+#[derive(Clone, Default)]
+pub struct DependencyRegistry {
+    entries: Arc<RwLock<Vec<Entry>>>,
+}
+
+impl std::fmt::Debug for DependencyRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.snapshot()).finish()
+    }
+}
+
+impl DependencyRegistry {
+    /// 空のレジストリを作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 依存を登録（同名の依存がある場合は置き換え）
+    pub fn register(&self, dependency: Dependency) -> &Self {
+        if let Ok(mut entries) = self.entries.write() {
+            entries.retain(|e| e.dependency.name != dependency.name);
+            entries.push(Entry { dependency, status: DependencyStatus::Unknown, error: None });
+        }
+        self
+    }
+
+    /// 依存の最新の状態（未登録の場合はNone）
+    pub fn status(&self, name: &str) -> Option<DependencyStatus> {
+        let entries = self.entries.read().ok()?;
+        entries.iter().find(|e| e.dependency.name == name).map(|e| e.status)
+    }
+
+    /// 依存が停止中と判明しているかどうか（未確認・未登録の場合はfalse）
+    pub fn is_down(&self, name: &str) -> bool {
+        self.status(name) == Some(DependencyStatus::Down)
+    }
+
+    /// 実際の呼び出しが成功したことを記録
+    pub fn report_success(&self, name: &str) {
+        self.update(name, DependencyStatus::Up, None);
+    }
+
+    /// 実際の呼び出しが失敗したことを記録（次の確認または成功の記録まで停止中として扱う）
+    pub fn report_failure(&self, name: &str, error: impl Into<String>) {
+        self.update(name, DependencyStatus::Down, Some(error.into()));
+    }
+
+    fn update(&self, name: &str, status: DependencyStatus, error: Option<String>) {
+        let mut entries = match self.entries.write() {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+        match entries.iter_mut().find(|e| e.dependency.name == name) {
+            Some(entry) => {
+                if entry.status != status {
+                    match status {
+                        DependencyStatus::Down => warn!("Dependency '{}' is down: {}", name, error.as_deref().unwrap_or("")),
+                        _ => info!("Dependency '{}' is {:?}", name, status),
+                    }
+                }
+                entry.status = status;
+                entry.error = error;
+            }
+            None => warn!("Status reported for unregistered dependency '{}'", name),
+        }
+    }
+
+    /// probeを実行せずに現在の状態を取得
+    pub fn snapshot(&self) -> Vec<DependencyHealth> {
+        match self.entries.read() {
+            Ok(entries) => entries
+                .iter()
+                .map(|e| DependencyHealth {
+                    name: e.dependency.name.clone(),
+                    criticality: e.dependency.criticality,
+                    status: e.status,
+                    error: e.error.clone(),
+                    latency_ms: None,
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// すべての依存のprobeを並行して実行し、状態を更新して結果を返す
+    pub async fn check_all(&self) -> HealthReport {
+        let dependencies: Vec<Dependency> = match self.entries.read() {
+            Ok(entries) => entries.iter().map(|e| e.dependency.clone()).collect(),
+            Err(_) => Vec::new(),
+        };
+
+        let checks = dependencies.into_iter().map(|dependency| async move {
+            let started = Instant::now();
+            let result = match tokio::time::timeout(dependency.timeout, (dependency.probe)()).await {
+                Ok(result) => result,
+                Err(_) => Err(format!("probe timed out after {:?}", dependency.timeout)),
+            };
+            (dependency, result, started.elapsed())
+        });
+
+        let mut results = Vec::new();
+        for (dependency, result, elapsed) in futures::future::join_all(checks).await {
+            let (status, error) = match result {
+                Ok(()) => (DependencyStatus::Up, None),
+                Err(e) => (DependencyStatus::Down, Some(e)),
+            };
+            self.update(&dependency.name, status, error.clone());
+            results.push(DependencyHealth {
+                name: dependency.name,
+                criticality: dependency.criticality,
+                status,
+                error,
+                latency_ms: Some(elapsed.as_millis() as u64),
+            });
+        }
+        HealthReport::from_dependencies(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> DependencyRegistry {
+        let registry = DependencyRegistry::new();
+        registry
+            .register(Dependency::new("db", Criticality::Critical, || async { Ok(()) }))
+            .register(Dependency::new("mailer", Criticality::NonCritical, || async {
+                Err("connection refused".to_string())
+            }));
+        registry
+    }
+
+    #[tokio::test]
+    async fn test_check_all_aggregates_by_criticality() {
+        let registry = registry();
+        assert_eq!(registry.status("db"), Some(DependencyStatus::Unknown));
+        assert_eq!(registry.status("missing"), None);
+
+        let report = registry.check_all().await;
+        assert_eq!(report.status, OverallHealth::Degraded);
+        assert_eq!(report.http_status(), 200);
+        assert_eq!(report.dependencies[1].error.as_deref(), Some("connection refused"));
+        assert!(registry.is_down("mailer"));
+        assert!(!registry.is_down("db"));
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["status"], "degraded");
+        assert_eq!(json["dependencies"][0]["criticality"], "critical");
+        assert!(json["dependencies"][0].get("error").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_timeout_and_reported_status() {
+        let registry = DependencyRegistry::new();
+        registry.register(
+            Dependency::new("slow", Criticality::Critical, || async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                Ok(())
+            })
+            .timeout(Duration::from_millis(10)),
+        );
+
+        let report = registry.check_all().await;
+        assert_eq!(report.status, OverallHealth::Down);
+        assert_eq!(report.http_status(), 503);
+
+        // 実際の呼び出し結果でも状態が更新される（クローンと共有）
+        let shared = registry.clone();
+        shared.report_success("slow");
+        assert_eq!(registry.status("slow"), Some(DependencyStatus::Up));
+        shared.report_failure("slow", "timeout");
+        assert!(registry.is_down("slow"));
+        assert_eq!(registry.snapshot()[0].error.as_deref(), Some("timeout"));
+    }
+}
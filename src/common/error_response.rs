@@ -0,0 +1,145 @@
+//! フレームワークが生成するエラーレスポンスの形式
+//!
+//! ルートが無い場合の404、許可されていないメソッドの405、ハンドラーのエラーや内部エラーの500など、
+//! ランタイムごとに生成していたエラーレスポンスを1か所で組み立てます。形式は既定でJSON
+//! （`{"status":404,"error":"Not Found"}`）で、環境変数でテキストに切り替えられます。
+//!
+//! エラーの詳細（`Error`の表示文字列など）は内部の情報を含む可能性があるため、既定では
+//! クライアントに返さずサーバーのログにだけ出力します。開発時は`RUNBRIDGE_EXPOSE_ERROR_DETAILS`で
+//! レスポンスの`detail`に含められます。
+
+use std::env;
+
+use log::debug;
+use serde_json::json;
+
+use super::content_type::ContentType;
+use super::http::Response;
+
+/// エラーレスポンスのボディの形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorFormat {
+    /// `application/json`（既定）
+    #[default]
+    Json,
+    /// `text/plain`
+    Text,
+}
+
+impl ErrorFormat {
+    /// 文字列から解析（`json` / `text`、大文字小文字を区別しない）
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "text" | "plain" => Some(Self::Text),
+            _ => None,
+        }
+    }
+}
+
+/// エラーレスポンスの形式を取得する
+/// 優先順位: 環境変数 `RUNBRIDGE_ERROR_FORMAT`（`json` / `text`） -> デフォルト `json`
+pub fn get_error_format() -> ErrorFormat {
+    env::var("RUNBRIDGE_ERROR_FORMAT")
+        .ok()
+        .and_then(|v| ErrorFormat::parse(&v))
+        .unwrap_or_default()
+}
+
+/// エラーの詳細をレスポンスに含めるかどうかを取得する
+/// 優先順位: 環境変数 `RUNBRIDGE_EXPOSE_ERROR_DETAILS`（`1` / `true`） -> デフォルト 無効
+pub fn is_error_detail_exposed() -> bool {
+    env::var("RUNBRIDGE_EXPOSE_ERROR_DETAILS")
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true"))
+        .unwrap_or(false)
+}
+
+/// ステータスコードに対応する固定のメッセージ（標準の理由句、未登録のコードは`Error`）
+///
+/// エラーレスポンスの本文とCGIのステータス行で共通に使用します。
+pub fn status_message(status: u16) -> &'static str {
+    ::http::StatusCode::from_u16(status)
+        .ok()
+        .and_then(|s| s.canonical_reason())
+        .unwrap_or("Error")
+}
+
+/// エラーレスポンスを作成（`detail`は`RUNBRIDGE_EXPOSE_ERROR_DETAILS`が有効な場合だけ返す）
+pub fn error_response(status: u16, detail: Option<&str>) -> Response {
+    error_response_with(status, detail, get_error_format(), is_error_detail_exposed())
+}
+
+fn error_response_with(status: u16, detail: Option<&str>, format: ErrorFormat, expose: bool) -> Response {
+    let message = status_message(status);
+    if let Some(detail) = detail {
+        debug!("Responding with {} {}: {}", status, message, detail);
+    }
+    let detail = detail.filter(|_| expose);
+    match format {
+        ErrorFormat::Json => {
+            let mut body = json!({ "status": status, "error": message });
+            if let Some(detail) = detail {
+                body["detail"] = json!(detail);
+            }
+            Response::new(status)
+                .with_content_type(ContentType::json())
+                .with_body(body.to_string().into_bytes())
+        }
+        ErrorFormat::Text => {
+            let body = match detail {
+                Some(detail) => format!("{}: {}", message, detail),
+                None => message.to_string(),
+            };
+            Response::new(status)
+                .with_content_type(ContentType::text())
+                .with_body(body.into_bytes())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temp_env::with_vars;
+
+    #[test]
+    fn test_error_response_formats() {
+        let res = error_response_with(404, Some("GET /x"), ErrorFormat::Json, false);
+        assert_eq!(res.content_type(), Some("application/json"));
+        assert_eq!(res.body.as_deref(), Some(&br#"{"error":"Not Found","status":404}"#[..]));
+
+        let res = error_response_with(500, Some("db password=x"), ErrorFormat::Text, false);
+        assert_eq!(res.content_type(), Some("text/plain; charset=utf-8"));
+        assert_eq!(res.body.as_deref(), Some(&b"Internal Server Error"[..]));
+
+        let res = error_response_with(502, Some("upstream"), ErrorFormat::Text, true);
+        assert_eq!(res.body.as_deref(), Some(&b"Bad Gateway: upstream"[..]));
+        let res = error_response_with(405, Some("PUT"), ErrorFormat::Json, true);
+        let body: serde_json::Value = serde_json::from_slice(res.body.as_deref().unwrap()).unwrap();
+        assert_eq!(body, json!({ "status": 405, "error": "Method Not Allowed", "detail": "PUT" }));
+    }
+
+    #[test]
+    fn test_status_message_uses_canonical_reasons() {
+        assert_eq!(status_message(404), "Not Found");
+        assert_eq!(status_message(418), "I'm a teapot");
+        assert_eq!(status_message(504), "Gateway Timeout");
+        assert_eq!(status_message(299), "Error");
+        assert_eq!(status_message(1000), "Error");
+    }
+
+    #[test]
+    fn test_error_response_env() {
+        with_vars(
+            [("RUNBRIDGE_ERROR_FORMAT", Some("TEXT")), ("RUNBRIDGE_EXPOSE_ERROR_DETAILS", None::<&str>)],
+            || {
+                assert_eq!(get_error_format(), ErrorFormat::Text);
+                assert!(!is_error_detail_exposed());
+                assert_eq!(error_response(413, Some("10 bytes")).body.as_deref(), Some(&b"Payload Too Large"[..]));
+            },
+        );
+        with_vars([("RUNBRIDGE_ERROR_FORMAT", Some("yaml"))], || {
+            assert_eq!(get_error_format(), ErrorFormat::Json);
+        });
+    }
+}
@@ -0,0 +1,181 @@
+//! ETagと`If-None-Match`による条件付きリクエスト
+//!
+//! `ETagMiddleware`はバッファ済みのレスポンスにボディのハッシュからETagを付与し、
+//! `If-None-Match`が一致する場合はボディなしの`304 Not Modified`を返します。
+//! ミドルウェアを使わずにハンドラーで扱う場合は`Response::with_computed_etag`と
+//! `Response::not_modified_if`を使います。
+//!
+//! ```
+//! use runbridge::common::{ETagMiddleware, Method, Request, Response};
+//!
+//! let app = runbridge::RunBridge::builder().around(ETagMiddleware::new()).build();
+//! # drop(app);
+//!
+//! // ハンドラーで扱う場合
+//! let req = Request::new(Method::GET, "/items".to_string());
+//! let res = Response::ok().with_body(b"[]".to_vec()).with_computed_etag().not_modified_if(&req);
+//! assert_eq!(res.status, 200);
+//! ```
+
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+
+use crate::error::Error;
+use super::content_type::replace_header;
+use super::http::{Method, Request, Response};
+use super::traits::{AroundMiddleware, Next};
+
+/// 304で返さない表現のヘッダー（RFC 9110 15.4.5）
+const REPRESENTATION_HEADERS: &[&str] = &[
+    "content-type",
+    "content-length",
+    "content-encoding",
+    "content-language",
+    "content-range",
+];
+
+/// ボディのSHA-256による強いETag（`"`で囲んだ先頭16バイトの16進数）
+pub fn strong_etag(body: &[u8]) -> String {
+    let digest = Sha256::digest(body);
+    let hex: String = digest[..16].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("\"{}\"", hex)
+}
+
+/// `If-None-Match`のいずれかのタグがETagと一致するか（弱い比較、`*`は常に一致）
+pub fn if_none_match_matches(if_none_match: &str, etag: &str) -> bool {
+    let etag = etag.strip_prefix("W/").unwrap_or(etag);
+    if_none_match.split(',').map(str::trim).any(|tag| {
+        tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == etag
+    })
+}
+
+impl Response {
+    /// ボディからETagを計算して設定（既にETagがある場合・ボディがない場合はそのまま）
+    pub fn with_computed_etag(mut self) -> Self {
+        if self.header("etag").is_some() {
+            return self;
+        }
+        if let Some(body) = &self.body {
+            let etag = strong_etag(body);
+            replace_header(&mut self.headers, "ETag".to_string(), etag);
+        }
+        self
+    }
+
+    /// リクエストの`If-None-Match`がETagと一致する場合は304に変換
+    ///
+    /// GET・HEADの200レスポンスだけが対象です。304ではボディと表現のヘッダー
+    /// （`Content-Type`等）を除き、`ETag`・`Cache-Control`・`Vary`などは残します。
+    pub fn not_modified_if(self, req: &Request) -> Self {
+        if !matches!(req.method, Method::GET | Method::HEAD) {
+            return self;
+        }
+        apply_if_none_match(self, req.headers.get("if-none-match").map(String::as_str))
+    }
+}
+
+/// `If-None-Match`が200レスポンスのETagと一致する場合に304へ変換
+fn apply_if_none_match(mut res: Response, if_none_match: Option<&str>) -> Response {
+    let matched = match (if_none_match, res.header("etag")) {
+        (Some(if_none_match), Some(etag)) => if_none_match_matches(if_none_match, etag),
+        _ => false,
+    };
+    if res.status != 200 || !matched {
+        return res;
+    }
+    res.status = 304;
+    res.body = None;
+    res.headers
+        .retain(|k, _| !REPRESENTATION_HEADERS.iter().any(|h| k.eq_ignore_ascii_case(h)));
+    res
+}
+
+/// ETagの付与と304の応答を行うミドルウェア
+///
+/// GET・HEADの200レスポンスのうち、ボディがありETagが無いものにETagを付与します
+/// （ハンドラーが設定したETagはそのまま使います）。Server-Sent Eventsのストリームは対象外です。
+/// ボディ全体のハッシュを計算するため、ハンドラーの処理自体は省略されません。
+#[derive(Debug, Clone, Default)]
+pub struct ETagMiddleware;
+
+impl ETagMiddleware {
+    /// 新しいETagMiddlewareを作成
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl AroundMiddleware for ETagMiddleware {
+    async fn around(&self, req: Request, next: Next<'_>) -> Result<Response, Error> {
+        if !matches!(req.method, Method::GET | Method::HEAD) {
+            return next.run(req).await;
+        }
+        let if_none_match = req.headers.get("if-none-match").cloned();
+        let res = next.run(req).await?;
+        if res.status != 200 || res.is_sse() {
+            return Ok(res);
+        }
+        Ok(apply_if_none_match(res.with_computed_etag(), if_none_match.as_deref()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    async fn run(req: Request) -> Response {
+        let handler = crate::handler::get("^/.*$", |req: Request| {
+            let res = Response::ok()
+                .with_header("Content-Type", "application/json")
+                .with_header("Cache-Control", "max-age=60")
+                .with_body(b"{\"items\":[]}".to_vec());
+            Ok(match req.path.as_str() {
+                "/fixed" => res.with_header("ETag", "W/\"v1\""),
+                _ => res,
+            })
+        });
+        let around: Vec<Box<dyn AroundMiddleware>> = vec![Box::new(ETagMiddleware::new())];
+        Next::new(&handler, &around).run(req).await.unwrap()
+    }
+
+    #[test]
+    fn test_if_none_match_matches() {
+        assert!(if_none_match_matches("\"a\", W/\"b\"", "\"b\""));
+        assert!(if_none_match_matches("\"b\"", "W/\"b\""));
+        assert!(if_none_match_matches("*", "\"c\""));
+        assert!(!if_none_match_matches("\"a\"", "\"b\""));
+        assert_eq!(strong_etag(b"abc"), strong_etag(b"abc"));
+        assert_ne!(strong_etag(b"abc"), strong_etag(b"abd"));
+    }
+
+    #[tokio::test]
+    async fn test_etag_middleware_not_modified() {
+        let res = run(Request::new(Method::GET, "/items".to_string())).await;
+        let etag = res.header("ETag").unwrap().to_string();
+        assert_eq!(etag, strong_etag(b"{\"items\":[]}"));
+        assert_eq!(res.status, 200);
+
+        let req = Request::new(Method::GET, "/items".to_string()).with_header("If-None-Match", &etag);
+        let res = run(req).await;
+        assert_eq!(res.status, 304);
+        assert!(res.body.is_none());
+        assert_eq!(res.header("ETag"), Some(etag.as_str()));
+        assert_eq!(res.header("Cache-Control"), Some("max-age=60"));
+        assert_eq!(res.header("Content-Type"), None);
+
+        let req = Request::new(Method::GET, "/items".to_string()).with_header("If-None-Match", "\"stale\"");
+        assert_eq!(run(req).await.status, 200);
+
+        // ハンドラーのETagは置き換えない
+        let req = Request::new(Method::HEAD, "/fixed".to_string()).with_header("If-None-Match", "\"v1\"");
+        let res = run(req).await;
+        assert_eq!(res.status, 304);
+        assert_eq!(res.header("ETag"), Some("W/\"v1\""));
+
+        // GET・HEAD以外は対象外
+        let req = Request::new(Method::POST, "/items".to_string()).with_header("If-None-Match", "*");
+        let res = run(req).await;
+        assert_eq!(res.status, 200);
+        assert_eq!(res.header("ETag"), None);
+    }
+}
@@ -0,0 +1,188 @@
+//! フィーチャーフラグの評価
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use crate::error::Error;
+use super::http::{Request, Response};
+use super::traits::Middleware;
+
+/// RequestContextに格納する際のキー
+pub const FEATURE_FLAGS_CONTEXT_KEY: &str = "runbridge.feature_flags";
+
+/// フィーチャーフラグの提供元
+///
+/// リモートの提供元を実装する場合は、評価を同期的に行えるよう
+/// バックグラウンドで取得した値をキャッシュして返してください。
+pub trait FeatureFlags: Send + Sync {
+    /// フラグが有効かどうか（リクエスト内容による出し分けにも利用可能）
+    fn is_enabled(&self, flag: &str, req: &Request) -> bool;
+}
+
+/// 固定値のフィーチャーフラグ
+#[derive(Debug, Clone, Default)]
+pub struct StaticFlags {
+    flags: HashMap<String, bool>,
+}
+
+impl StaticFlags {
+    /// 新しいStaticFlagsを作成（未登録のフラグは無効）
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// フラグの値を設定
+    pub fn set(mut self, flag: impl Into<String>, enabled: bool) -> Self {
+        self.flags.insert(flag.into(), enabled);
+        self
+    }
+}
+
+impl FeatureFlags for StaticFlags {
+    fn is_enabled(&self, flag: &str, _req: &Request) -> bool {
+        self.flags.get(flag).copied().unwrap_or(false)
+    }
+}
+
+/// 環境変数によるフィーチャーフラグ
+///
+/// フラグ名を大文字化し`-`/`.`を`_`に置換した名前にプレフィックスを付けて参照します
+/// （例: `new-checkout` → `RUNBRIDGE_FLAG_NEW_CHECKOUT`）。
+/// 値が `1` / `true` / `on` / `yes`（大文字小文字を区別しない）の場合に有効です。
+#[derive(Debug, Clone)]
+pub struct EnvFlags {
+    prefix: String,
+}
+
+impl Default for EnvFlags {
+    fn default() -> Self {
+        Self {
+            prefix: "RUNBRIDGE_FLAG_".to_string(),
+        }
+    }
+}
+
+impl EnvFlags {
+    /// 新しいEnvFlagsを作成（プレフィックス `RUNBRIDGE_FLAG_`）
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 環境変数名のプレフィックスを設定
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    fn var_name(&self, flag: &str) -> String {
+        let name: String = flag
+            .chars()
+            .map(|c| match c {
+                '-' | '.' => '_',
+                c => c.to_ascii_uppercase(),
+            })
+            .collect();
+        format!("{}{}", self.prefix, name)
+    }
+}
+
+impl FeatureFlags for EnvFlags {
+    fn is_enabled(&self, flag: &str, _req: &Request) -> bool {
+        std::env::var(self.var_name(flag))
+            .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "on" | "yes"))
+            .unwrap_or(false)
+    }
+}
+
+/// フィーチャーフラグをRequestContextに格納するミドルウェア
+#[derive(Clone)]
+pub struct FeatureFlagMiddleware {
+    flags: Arc<dyn FeatureFlags>,
+}
+
+impl FeatureFlagMiddleware {
+    /// 新しいFeatureFlagMiddlewareを作成
+    pub fn new<F>(flags: F) -> Self
+    where
+        F: FeatureFlags + 'static,
+    {
+        Self {
+            flags: Arc::new(flags),
+        }
+    }
+}
+
+#[async_trait]
+impl Middleware for FeatureFlagMiddleware {
+    async fn pre_process(&self, mut req: Request) -> Result<Request, Error> {
+        req.context_mut().set(FEATURE_FLAGS_CONTEXT_KEY, self.flags.clone());
+        Ok(req)
+    }
+
+    async fn post_process(&self, res: Response) -> Result<Response, Error> {
+        Ok(res)
+    }
+}
+
+impl Request {
+    /// フィーチャーフラグが有効かどうか（FeatureFlagMiddleware未登録の場合は常に無効）
+    pub fn flag_enabled(&self, flag: &str) -> bool {
+        self.context()
+            .get::<Arc<dyn FeatureFlags>>(FEATURE_FLAGS_CONTEXT_KEY)
+            .map(|flags| flags.is_enabled(flag, self))
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Method;
+
+    fn request() -> Request {
+        Request::new(Method::GET, "/".to_string())
+    }
+
+    #[tokio::test]
+    async fn test_static_flags_via_context() {
+        let middleware = FeatureFlagMiddleware::new(
+            StaticFlags::new().set("new-checkout", true).set("beta", false),
+        );
+        let req = middleware.pre_process(request()).await.unwrap();
+
+        assert!(req.flag_enabled("new-checkout"));
+        assert!(!req.flag_enabled("beta"));
+        assert!(!req.flag_enabled("unknown"));
+        // ミドルウェアを通っていない場合は無効
+        assert!(!request().flag_enabled("new-checkout"));
+    }
+
+    #[test]
+    fn test_env_flags() {
+        let flags = EnvFlags::new().prefix("RB_TEST_FLAG_");
+        assert_eq!(flags.var_name("new-checkout.v2"), "RB_TEST_FLAG_NEW_CHECKOUT_V2");
+
+        let vars = [("RB_TEST_FLAG_NEW_CHECKOUT", Some("On")), ("RB_TEST_FLAG_BETA", Some("0"))];
+        temp_env::with_vars(vars, || {
+            assert!(flags.is_enabled("new-checkout", &request()));
+            assert!(!flags.is_enabled("beta", &request()));
+            assert!(!flags.is_enabled("missing", &request()));
+        });
+    }
+
+    #[test]
+    fn test_request_aware_flags() {
+        struct HeaderFlags;
+        impl FeatureFlags for HeaderFlags {
+            fn is_enabled(&self, flag: &str, req: &Request) -> bool {
+                req.headers.contains_key("x-beta-user") && flag == "beta"
+            }
+        }
+
+        let mut req = request().with_header("X-Beta-User", "1");
+        let flags: Arc<dyn FeatureFlags> = Arc::new(HeaderFlags);
+        req.context_mut().set(FEATURE_FLAGS_CONTEXT_KEY, flags);
+        assert!(req.flag_enabled("beta"));
+    }
+}
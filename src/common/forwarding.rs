@@ -0,0 +1,288 @@
+//! 上流へリクエストを転送する際のヘッダー転送ポリシー
+//!
+//! hop-by-hopヘッダーの除去、認証情報の扱い、`X-Forwarded-*`の付与をまとめたプリセットを提供します。
+//! プロキシ処理を自前で書く場合もこのポリシーを通してヘッダーを組み立ててください。
+
+use std::collections::{HashMap, HashSet};
+
+/// 常に転送しないhop-by-hopヘッダー（RFC 9110 7.6.1）
+const HOP_BY_HOP_HEADERS: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// 転送先で再計算されるため転送しないヘッダー
+const RECOMPUTED_HEADERS: &[&str] = &["host", "content-length"];
+
+/// 認証情報を含むヘッダー
+const CREDENTIAL_HEADERS: &[&str] = &["authorization", "cookie"];
+
+/// クライアント側で偽装可能な転送元情報ヘッダー
+const FORWARDED_HEADERS: &[&str] = &[
+    "forwarded",
+    "x-forwarded-for",
+    "x-forwarded-proto",
+    "x-forwarded-host",
+    "x-real-ip",
+];
+
+/// 転送元（このアプリケーションが受けたリクエスト）の情報
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ForwardedOrigin {
+    /// クライアントIP
+    pub client_ip: Option<String>,
+    /// 受信時のスキーム（`http` / `https`）
+    pub proto: Option<String>,
+    /// 受信時のHostヘッダー
+    pub host: Option<String>,
+}
+
+/// ヘッダー転送ポリシー
+#[derive(Debug, Clone)]
+pub struct ForwardingPolicy {
+    preserve_credentials: bool,
+    trust_forwarded: bool,
+    add_forwarded: bool,
+    strip: HashSet<String>,
+}
+
+impl Default for ForwardingPolicy {
+    fn default() -> Self {
+        Self::safe()
+    }
+}
+
+impl ForwardingPolicy {
+    /// 既定のプリセット: 認証情報は転送せず、受信した`X-Forwarded-*`は破棄して付け直す
+    pub fn safe() -> Self {
+        Self {
+            preserve_credentials: false,
+            trust_forwarded: false,
+            add_forwarded: true,
+            strip: HashSet::new(),
+        }
+    }
+
+    /// 同一信頼境界内のサービス向けプリセット: `Authorization`/`Cookie`を転送する
+    pub fn internal_service() -> Self {
+        Self::safe().preserve_credentials(true)
+    }
+
+    /// 信頼できるプロキシの背後で動作する場合のプリセット: 受信した`X-Forwarded-*`を引き継ぐ
+    pub fn behind_trusted_proxy() -> Self {
+        Self::safe().trust_forwarded(true)
+    }
+
+    /// `Authorization`/`Cookie`を転送するかどうか
+    pub fn preserve_credentials(mut self, preserve: bool) -> Self {
+        self.preserve_credentials = preserve;
+        self
+    }
+
+    /// 受信した`X-Forwarded-*`/`Forwarded`を信頼して引き継ぐかどうか
+    pub fn trust_forwarded(mut self, trust: bool) -> Self {
+        self.trust_forwarded = trust;
+        self
+    }
+
+    /// `X-Forwarded-*`を付与するかどうか
+    pub fn add_forwarded(mut self, add: bool) -> Self {
+        self.add_forwarded = add;
+        self
+    }
+
+    /// 追加で転送しないヘッダーを指定（大文字小文字を区別しない）
+    pub fn strip_header(mut self, name: impl Into<String>) -> Self {
+        self.strip.insert(name.into().to_ascii_lowercase());
+        self
+    }
+
+    /// 上流へ送るリクエストヘッダーを生成（キーは小文字）
+    pub fn request_headers(
+        &self,
+        headers: &HashMap<String, String>,
+        origin: &ForwardedOrigin,
+    ) -> HashMap<String, String> {
+        let mut forwarded = self.filter(headers);
+        if !self.preserve_credentials {
+            for name in CREDENTIAL_HEADERS {
+                forwarded.remove(*name);
+            }
+        }
+        if !self.trust_forwarded {
+            for name in FORWARDED_HEADERS {
+                forwarded.remove(*name);
+            }
+        }
+        if self.add_forwarded {
+            self.append_forwarded(&mut forwarded, origin);
+        }
+        forwarded
+    }
+
+    /// 上流から受けたレスポンスヘッダーのうちクライアントへ返すものを生成（キーは小文字）
+    pub fn response_headers(&self, headers: &HashMap<String, String>) -> HashMap<String, String> {
+        self.filter(headers)
+    }
+
+    /// hop-by-hop・`Connection`で指定されたヘッダー・不正なヘッダーを除去
+    fn filter(&self, headers: &HashMap<String, String>) -> HashMap<String, String> {
+        // Connectionヘッダーに列挙されたヘッダーもhop-by-hopとして扱う
+        let connection_listed: HashSet<String> = headers
+            .iter()
+            .filter(|(k, _)| k.eq_ignore_ascii_case("connection"))
+            .flat_map(|(_, v)| v.split(','))
+            .map(|name| name.trim().to_ascii_lowercase())
+            .filter(|name| !name.is_empty())
+            .collect();
+
+        headers
+            .iter()
+            .filter_map(|(name, value)| {
+                let lower = name.to_ascii_lowercase();
+                if HOP_BY_HOP_HEADERS.contains(&lower.as_str())
+                    || RECOMPUTED_HEADERS.contains(&lower.as_str())
+                    || connection_listed.contains(&lower)
+                    || self.strip.contains(&lower)
+                {
+                    return None;
+                }
+                if !is_forwardable_name(name) || !is_forwardable_value(value) {
+                    log::warn!("Dropped malformed header while forwarding: {:?}", name);
+                    return None;
+                }
+                Some((lower, value.clone()))
+            })
+            .collect()
+    }
+
+    fn append_forwarded(&self, headers: &mut HashMap<String, String>, origin: &ForwardedOrigin) {
+        if let Some(ip) = origin.client_ip.as_deref().filter(|ip| is_forwardable_value(ip)) {
+            let value = match headers.get("x-forwarded-for") {
+                Some(existing) if !existing.trim().is_empty() => format!("{}, {}", existing.trim(), ip),
+                _ => ip.to_string(),
+            };
+            headers.insert("x-forwarded-for".to_string(), value);
+        }
+        if let Some(proto) = origin.proto.as_deref() {
+            if proto.eq_ignore_ascii_case("http") || proto.eq_ignore_ascii_case("https") {
+                headers.insert("x-forwarded-proto".to_string(), proto.to_ascii_lowercase());
+            }
+        }
+        if let Some(host) = origin.host.as_deref().filter(|h| is_forwardable_value(h)) {
+            headers.insert("x-forwarded-host".to_string(), host.to_string());
+        }
+    }
+}
+
+/// ヘッダー名がトークン文字のみで構成されているか（空白・区切り文字を含む名前は拒否）
+fn is_forwardable_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.bytes().all(|b| {
+            b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+        })
+}
+
+/// ヘッダー値に制御文字（CR/LF/NULなど、タブを除く）が含まれていないか
+fn is_forwardable_value(value: &str) -> bool {
+    value.bytes().all(|b| b == b'\t' || (b >= 0x20 && b != 0x7f))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    fn origin() -> ForwardedOrigin {
+        ForwardedOrigin {
+            client_ip: Some("203.0.113.7".to_string()),
+            proto: Some("HTTPS".to_string()),
+            host: Some("api.example.com".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_safe_preset_strips_hop_by_hop_and_credentials() {
+        let incoming = headers(&[
+            ("accept", "application/json"),
+            ("connection", "keep-alive, X-Secret-Hop"),
+            ("x-secret-hop", "1"),
+            ("keep-alive", "timeout=5"),
+            ("upgrade", "websocket"),
+            ("host", "internal"),
+            ("authorization", "Bearer abc"),
+            ("cookie", "session=1"),
+        ]);
+        let out = ForwardingPolicy::safe().request_headers(&incoming, &origin());
+
+        assert_eq!(out.get("accept").map(String::as_str), Some("application/json"));
+        for name in ["connection", "x-secret-hop", "keep-alive", "upgrade", "host", "authorization", "cookie"] {
+            assert!(!out.contains_key(name), "{} should be stripped", name);
+        }
+        assert_eq!(out.get("x-forwarded-for").map(String::as_str), Some("203.0.113.7"));
+        assert_eq!(out.get("x-forwarded-proto").map(String::as_str), Some("https"));
+        assert_eq!(out.get("x-forwarded-host").map(String::as_str), Some("api.example.com"));
+    }
+
+    #[test]
+    fn test_spoofed_forwarded_headers_are_replaced_unless_trusted() {
+        let incoming = headers(&[("x-forwarded-for", "10.0.0.1"), ("forwarded", "for=10.0.0.1")]);
+
+        let out = ForwardingPolicy::safe().request_headers(&incoming, &origin());
+        assert_eq!(out.get("x-forwarded-for").map(String::as_str), Some("203.0.113.7"));
+        assert!(!out.contains_key("forwarded"));
+
+        let out = ForwardingPolicy::behind_trusted_proxy().request_headers(&incoming, &origin());
+        assert_eq!(out.get("x-forwarded-for").map(String::as_str), Some("10.0.0.1, 203.0.113.7"));
+        assert!(out.contains_key("forwarded"));
+    }
+
+    #[test]
+    fn test_smuggling_edge_cases() {
+        let incoming = headers(&[
+            ("Transfer-Encoding", "chunked"),
+            ("Content-Length", "10"),
+            ("transfer-encoding ", "chunked"),
+            ("x-injected", "a\r\nX-Evil: 1"),
+            ("x-nul", "a\0b"),
+            ("X-Ok", "fine\tvalue"),
+        ]);
+        let out = ForwardingPolicy::internal_service().request_headers(&incoming, &ForwardedOrigin::default());
+
+        assert_eq!(out.len(), 1, "unexpected headers: {:?}", out);
+        assert_eq!(out.get("x-ok").map(String::as_str), Some("fine\tvalue"));
+    }
+
+    #[test]
+    fn test_internal_preset_keeps_credentials_and_custom_strip() {
+        let incoming = headers(&[("authorization", "Bearer abc"), ("x-debug", "1")]);
+        let out = ForwardingPolicy::internal_service()
+            .strip_header("X-Debug")
+            .add_forwarded(false)
+            .request_headers(&incoming, &origin());
+
+        assert_eq!(out, headers(&[("authorization", "Bearer abc")]));
+    }
+
+    #[test]
+    fn test_response_headers_filter() {
+        let upstream = headers(&[
+            ("content-type", "text/plain"),
+            ("transfer-encoding", "chunked"),
+            ("connection", "close"),
+            ("set-cookie", "a=1"),
+        ]);
+        let out = ForwardingPolicy::safe().response_headers(&upstream);
+        assert_eq!(out, headers(&[("content-type", "text/plain"), ("set-cookie", "a=1")]));
+    }
+}
@@ -2,12 +2,19 @@
 
 use std::collections::HashMap;
 use std::fmt;
-use std::io::Read;
+use chrono::{DateTime, Utc};
 use serde::{Serialize, Deserialize};
-use flate2::read::GzDecoder;
+use serde::de::DeserializeOwned;
 use crate::error::Error;
 use super::context::RequestContext;
-use super::utils::{is_header_value_valid, get_max_body_size};
+use super::content_type::{replace_header, ContentType};
+use super::csp::ContentSecurityPolicy;
+use super::error_response::error_response;
+use super::http_date::{format_http_date, parse_http_date, RetryAfter};
+use super::link::LinkHint;
+use super::redaction::{RedactedMap, TruncatedBody};
+use super::sse::SharedEventStream;
+use super::utils::is_header_value_valid;
 
 /// HTTPステータスコード
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -86,7 +93,7 @@ impl From<StatusCode> for u16 {
 }
 
 /// HTTPメソッド
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub enum Method {
     GET,
     POST,
@@ -129,22 +136,54 @@ impl Method {
 
 /// HTTPリクエスト
 /// 注意：意図的にCloneトレイトを省略しています（RequestContextの安全性のため）
-#[derive(Debug)]
+///
+/// シリアライズ時はRequestContextを含みません（デシリアライズ時は空のコンテキスト）。
+///
+/// `Debug`出力ではセンシティブなヘッダー・クエリパラメータの値を伏せ字にし、ボディは先頭だけを出力します。
+#[derive(Serialize, Deserialize)]
 pub struct Request {
     /// HTTPメソッド
     pub method: Method,
     /// リクエストパス
     pub path: String,
     /// クエリパラメータ
+    #[serde(default)]
     pub query_params: HashMap<String, String>,
     /// HTTPヘッダー
+    #[serde(default, deserialize_with = "deserialize_lowercase_headers")]
     pub headers: HashMap<String, String>,
     /// リクエストボディ
+    #[serde(default)]
     pub body: Option<Vec<u8>>,
     /// リクエストコンテキスト
+    #[serde(skip)]
     context: RequestContext,
 }
 
+/// デシリアライズしたヘッダーのキーを小文字に正規化（Request取り込み時と同じ扱い）
+fn deserialize_lowercase_headers<'de, D>(deserializer: D) -> Result<HashMap<String, String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let headers = HashMap::<String, String>::deserialize(deserializer)?;
+    Ok(headers
+        .into_iter()
+        .map(|(k, v)| (k.to_ascii_lowercase(), v))
+        .collect())
+}
+
+impl fmt::Debug for Request {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Request")
+            .field("method", &self.method)
+            .field("path", &self.path)
+            .field("query_params", &RedactedMap(&self.query_params))
+            .field("headers", &RedactedMap(&self.headers))
+            .field("body", &TruncatedBody(self.body.as_deref()))
+            .finish_non_exhaustive()
+    }
+}
+
 impl Request {
     /// 新しいリクエストを作成
     pub fn new(method: Method, path: String) -> Self {
@@ -186,6 +225,11 @@ impl Request {
         self
     }
 
+    /// 日時のヘッダー（`If-Modified-Since`など）を解析して取得（無い・解析できない場合はNone）
+    pub fn header_date(&self, name: &str) -> Option<DateTime<Utc>> {
+        self.headers.get(&name.to_ascii_lowercase()).and_then(|v| parse_http_date(v))
+    }
+
     /// ボディをJSONとしてパース
     pub fn json<T: for<'de> Deserialize<'de>>(&self) -> Result<T, Error> {
         if let Some(body) = &self.body {
@@ -196,6 +240,17 @@ impl Request {
         }
     }
 
+    /// リクエストボディを`application/x-www-form-urlencoded`として型`T`にデシリアライズ
+    ///
+    /// 数値や真偽値のフィールドは文字列から変換されます。
+    pub fn form<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        match &self.body {
+            Some(body) => serde_urlencoded::from_bytes(body)
+                .map_err(|e| Error::InvalidRequestBody(format!("Invalid form body: {}", e))),
+            None => Err(Error::InvalidRequestBody("No request body".to_string())),
+        }
+    }
+
     /// リクエストコンテキストの不変参照を取得
     pub fn context(&self) -> &RequestContext {
         &self.context
@@ -227,61 +282,15 @@ impl Request {
             context: RequestContext::new(),
         }
     }
-
-    /// リクエストボディがgzipエンコードされている場合は解凍する
-    /// Content-Encodingヘッダーをチェックし、gzipの場合のみ処理を実行
-    /// 解凍後のサイズが上限を超える場合はPayloadTooLargeエラーを返す
-    pub fn decompress_gzip_body(&mut self) -> Result<(), Error> {
-        // Content-Encodingヘッダーをチェック（小文字で正規化済み）
-        if let Some(encoding) = self.headers.get("content-encoding") {
-            if encoding.to_lowercase() == "gzip" {
-                if let Some(body_data) = &self.body {
-                    let max_body_size = get_max_body_size();
-                    let mut decoder = GzDecoder::new(&body_data[..]);
-                    let mut decompressed = Vec::new();
-                    let mut buffer = [0u8; 8192]; // 8KBチャンクで読み込み
-                    
-                    loop {
-                        match decoder.read(&mut buffer) {
-                            Ok(0) => break, // EOF
-                            Ok(n) => {
-                                // 新しいデータを追加する前にサイズをチェック
-                                if decompressed.len() + n > max_body_size {
-                                    log::warn!(
-                                        "Decompressed gzip body too large: {} + {} > {} bytes",
-                                        decompressed.len(),
-                                        n,
-                                        max_body_size
-                                    );
-                                    return Err(Error::PayloadTooLarge(format!(
-                                        "Decompressed body too large (>{} bytes)",
-                                        max_body_size
-                                    )));
-                                }
-                                decompressed.extend_from_slice(&buffer[..n]);
-                            }
-                            Err(e) => {
-                                log::warn!("Failed to decompress gzip body: {}", e);
-                                return Err(Error::InvalidRequestBody(
-                                    format!("Invalid gzip-encoded request body: {}", e)
-                                ));
-                            }
-                        }
-                    }
-                    
-                    // 解凍成功：ボディを更新し、Content-Encodingヘッダーを削除
-                    self.body = Some(decompressed);
-                    self.headers.remove("content-encoding");
-                    log::debug!("Successfully decompressed gzip request body");
-                }
-            }
-        }
-        Ok(())
-    }
 }
 
 /// HTTPレスポンス
-#[derive(Debug, Clone)]
+///
+/// `Serialize`を実装すると`ResponseWrapper`の包括実装によりJSONボディ化されてしまうため、
+/// シリアライズには`common::interop::response_serde`を使用してください。
+///
+/// `Debug`出力ではセンシティブなヘッダー（`Set-Cookie`等）の値を伏せ字にし、ボディは先頭だけを出力します。
+#[derive(Clone)]
 pub struct Response {
     /// HTTPステータスコード
     pub status: u16,
@@ -289,6 +298,22 @@ pub struct Response {
     pub headers: HashMap<String, String>,
     /// レスポンスボディ
     pub body: Option<Vec<u8>>,
+    /// `Response::add_cookie`で追加したSet-Cookieの値（ヘッダーとは別に1つずつ出力する）
+    pub(crate) cookies: Vec<String>,
+    /// Server-Sent Eventsのストリーム（`Response::sse`で設定）
+    pub(crate) event_stream: Option<SharedEventStream>,
+}
+
+impl fmt::Debug for Response {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Response")
+            .field("status", &self.status)
+            .field("headers", &RedactedMap(&self.headers))
+            .field("body", &TruncatedBody(self.body.as_deref()))
+            .field("cookies", &self.cookies.len())
+            .field("event_stream", &self.event_stream.is_some())
+            .finish()
+    }
 }
 
 impl Response {
@@ -301,6 +326,8 @@ impl Response {
             status,
             headers,
             body: None,
+            cookies: Vec::new(),
+            event_stream: None,
         }
     }
 
@@ -313,6 +340,8 @@ impl Response {
             status: status.as_u16(),
             headers,
             body: None,
+            cookies: Vec::new(),
+            event_stream: None,
         }
     }
 
@@ -324,10 +353,64 @@ impl Response {
             log::warn!("Response::with_header rejected invalid value for '{}': {:?}", k, v);
             return self;
         }
-        self.headers.insert(k, v);
+        // 大文字小文字だけが異なる同名ヘッダーは置き換える
+        replace_header(&mut self.headers, k, v);
         self
     }
 
+    /// `Last-Modified`ヘッダーを設定
+    pub fn with_last_modified(self, date: DateTime<Utc>) -> Self {
+        self.with_header("Last-Modified", format_http_date(date))
+    }
+
+    /// `Expires`ヘッダーを設定
+    pub fn with_expires(self, date: DateTime<Utc>) -> Self {
+        self.with_header("Expires", format_http_date(date))
+    }
+
+    /// `Retry-After`ヘッダーを設定（秒数または日時）
+    pub fn with_retry_after(self, retry_after: RetryAfter) -> Self {
+        self.with_header("Retry-After", retry_after.to_string())
+    }
+
+    /// `Link`ヘッダーにリソースのヒントを追加（既存の値にはカンマ区切りで追記）
+    pub fn with_link(mut self, hint: LinkHint) -> Self {
+        let value = match self.header("Link") {
+            Some(existing) => format!("{}, {}", existing, hint),
+            None => hint.to_string(),
+        };
+        if !is_header_value_valid(&value) {
+            log::warn!("Response::with_link rejected invalid value: {:?}", value);
+            return self;
+        }
+        replace_header(&mut self.headers, "Link".to_string(), value);
+        self
+    }
+
+    /// Content-Typeを設定（大文字小文字の違う既存の値も置き換え）
+    pub fn set_content_type(&mut self, content_type: impl Into<ContentType>) {
+        replace_header(&mut self.headers, "Content-Type".to_string(), content_type.into().to_string());
+    }
+
+    /// Content-Typeを設定
+    pub fn with_content_type(mut self, content_type: impl Into<ContentType>) -> Self {
+        self.set_content_type(content_type);
+        self
+    }
+
+    /// Content-Typeを取得（大文字小文字を区別しない）
+    pub fn content_type(&self) -> Option<&str> {
+        self.header("content-type")
+    }
+
+    /// ヘッダーの値を取得（大文字小文字を区別しない）
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
     /// ボディを追加
     pub fn with_body(mut self, body: Vec<u8>) -> Self {
         self.body = Some(body);
@@ -339,7 +422,7 @@ impl Response {
         let json = serde_json::to_vec(value)
             .map_err(|e| Error::ResponseSerializationError(e.to_string()))?;
         
-        self.headers.insert("Content-Type".to_string(), "application/json".to_string());
+        self.set_content_type(ContentType::json());
         self.body = Some(json);
         Ok(self)
     }
@@ -384,21 +467,49 @@ impl Response {
         Self::new(500)
     }
 
+    /// 予約ヘッダー（`RESERVED_RESPONSE_HEADERS`）を除去し、除去したヘッダー名を返す
+    ///
+    /// これらはランタイム側が付与・管理するため、ユーザー指定は全ランタイムで一律に無視します。
+    /// `route`は警告ログに出力する担当ルート（パスパターン）です。
+    pub fn remove_reserved_headers(&mut self, route: &str) -> Vec<String> {
+        let reserved: Vec<String> = self
+            .headers
+            .keys()
+            .filter(|name| is_reserved_response_header(name))
+            .cloned()
+            .collect();
+        for name in &reserved {
+            self.headers.remove(name);
+            log::warn!(
+                "Ignored reserved response header '{}' set by route '{}'",
+                name, route
+            );
+        }
+        reserved
+    }
+
+    /// HEADリクエストへの応答としてボディを除去（`Content-Length`にはボディの長さを設定）
+    ///
+    /// `Content-Length`は予約ヘッダーのため、`remove_reserved_headers`の後に呼び出してください。
+    /// Server-Sent Eventsのストリームは長さが決まらないため、`Content-Length`を付けずに破棄します。
+    pub fn strip_body_for_head(&mut self) {
+        self.event_stream = None;
+        if let Some(body) = self.body.take() {
+            self.headers.insert("Content-Length".to_string(), body.len().to_string());
+        }
+    }
+
     /// Error型から固定メッセージのレスポンスを生成
+    ///
+    /// 形式は`RUNBRIDGE_ERROR_FORMAT`に従い、エラーの表示文字列は`RUNBRIDGE_EXPOSE_ERROR_DETAILS`が
+    /// 有効な場合だけ含めます（`common::error_response`を参照）。
     pub fn from_error(error: &crate::error::Error) -> Self {
-        let status = error.status_code();
-        let message = match status {
-            400 => "Bad Request",
-            401 => "Unauthorized",
-            403 => "Forbidden",
-            404 => "Not Found",
-            413 => "Payload Too Large",
-            500 | 502 => "Internal Server Error",
-            _ => "Error",
-        };
-        Response::new(status)
-            .with_header("Content-Type", "text/plain")
-            .with_body(message.as_bytes().to_vec())
+        error_response(error.status_code(), Some(&error.to_string()))
+    }
+
+    /// ステータスコードに対応する固定メッセージのエラーレスポンスを生成（ルートが無い場合の404など）
+    pub fn error(status: u16) -> Self {
+        error_response(status, None)
     }
 }
 
@@ -408,6 +519,7 @@ pub struct ResponseBuilder {
     status: u16,
     headers: HashMap<String, String>,
     body: Option<Vec<u8>>,
+    cookies: Vec<String>,
 }
 
 impl ResponseBuilder {
@@ -416,7 +528,7 @@ impl ResponseBuilder {
         let mut headers = HashMap::new();
         // 既定のセキュリティヘッダーを注入（未設定の場合のみ）
         inject_default_security_headers(&mut headers);
-        Self { status, headers, body: None }
+        Self { status, headers, body: None, cookies: Vec::new() }
     }
 
     /// 新しいResponseBuilderを作成（StatusCode）
@@ -424,7 +536,7 @@ impl ResponseBuilder {
         let mut headers = HashMap::new();
         // 既定のセキュリティヘッダーを注入（未設定の場合のみ）
         inject_default_security_headers(&mut headers);
-        Self { status: status.as_u16(), headers, body: None }
+        Self { status: status.as_u16(), headers, body: None, cookies: Vec::new() }
     }
 
     /// 既存のResponseからResponseBuilderを作成
@@ -433,6 +545,7 @@ impl ResponseBuilder {
             status: response.status,
             headers: response.headers,
             body: response.body,
+            cookies: response.cookies,
         }
     }
 
@@ -444,13 +557,21 @@ impl ResponseBuilder {
             log::warn!("ResponseBuilder::header rejected invalid value for '{}': {:?}", k, v);
             return self;
         }
-        self.headers.insert(k, v);
+        replace_header(&mut self.headers, k, v);
+        self
+    }
+
+    /// Content-Typeを設定（大文字小文字の違う既存の値も置き換え）
+    pub fn content_type(mut self, content_type: impl Into<ContentType>) -> Self {
+        replace_header(&mut self.headers, "Content-Type".to_string(), content_type.into().to_string());
         self
     }
 
     /// 複数のヘッダーを一括追加
     pub fn headers(mut self, headers: HashMap<String, String>) -> Self {
-        self.headers.extend(headers);
+        for (k, v) in headers {
+            replace_header(&mut self.headers, k, v);
+        }
         self
     }
 
@@ -460,7 +581,7 @@ impl ResponseBuilder {
         self.headers.insert("X-Frame-Options".to_string(), "DENY".to_string());
         self.headers.insert("X-XSS-Protection".to_string(), "1; mode=block".to_string());
         self.headers.insert("Referrer-Policy".to_string(), "strict-origin-when-cross-origin".to_string());
-        self.headers.insert("Content-Security-Policy".to_string(), "default-src 'self'".to_string());
+        self.headers.insert("Content-Security-Policy".to_string(), ContentSecurityPolicy::default().to_string());
         self
     }
 
@@ -469,7 +590,7 @@ impl ResponseBuilder {
         let json = serde_json::to_vec(data)
             .map_err(|e| Error::ResponseSerializationError(e.to_string()))?;
         
-        self.headers.insert("Content-Type".to_string(), "application/json".to_string());
+        self = self.content_type(ContentType::json());
         self.body = Some(json);
         Ok(self)
     }
@@ -483,7 +604,7 @@ impl ResponseBuilder {
     /// テキストボディを設定
     pub fn text(mut self, text: impl Into<String>) -> Self {
         let text = text.into();
-        self.headers.insert("Content-Type".to_string(), "text/plain; charset=utf-8".to_string());
+        self = self.content_type(ContentType::text());
         self.body = Some(text.into_bytes());
         self
     }
@@ -491,7 +612,7 @@ impl ResponseBuilder {
     /// HTMLボディを設定
     pub fn html(mut self, html: impl Into<String>) -> Self {
         let html = html.into();
-        self.headers.insert("Content-Type".to_string(), "text/html; charset=utf-8".to_string());
+        self = self.content_type(ContentType::html());
         self.body = Some(html.into_bytes());
         self
     }
@@ -500,21 +621,42 @@ impl ResponseBuilder {
     pub fn build(mut self) -> Response {
         // build時にも不足があればセキュリティヘッダーを補完
         inject_default_security_headers(&mut self.headers);
-        Response { status: self.status, headers: self.headers, body: self.body }
+        Response { status: self.status, headers: self.headers, body: self.body, cookies: self.cookies, event_stream: None }
     }
 }
 
+/// フレームワーク/ランタイムが管理するため、ユーザーが設定できないレスポンスヘッダー
+pub const RESERVED_RESPONSE_HEADERS: &[&str] = &[
+    "Content-Length",
+    "Transfer-Encoding",
+    "Status",
+    "Connection",
+];
+
+/// 予約レスポンスヘッダーかどうか（大文字小文字を区別しない）
+pub fn is_reserved_response_header(name: &str) -> bool {
+    RESERVED_RESPONSE_HEADERS
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(name))
+}
+
+/// レスポンスの構築時に注入する既定のセキュリティヘッダー（`SecurityProfile`の適用時にも参照）
+pub(crate) fn default_security_headers() -> [(&'static str, String); 5] {
+    [
+        ("X-Content-Type-Options", "nosniff".to_string()),
+        ("X-Frame-Options", "DENY".to_string()),
+        ("X-XSS-Protection", "1; mode=block".to_string()),
+        ("Referrer-Policy", "strict-origin-when-cross-origin".to_string()),
+        ("Content-Security-Policy", ContentSecurityPolicy::default().to_string()),
+    ]
+}
+
 /// 既定のセキュリティヘッダーを不足時に注入する
 fn inject_default_security_headers(map: &mut HashMap<String, String>) {
-    // ユーザーが上書きしたい場合を尊重し、未設定時のみ入れる
-    map.entry("X-Content-Type-Options".to_string())
-        .or_insert_with(|| "nosniff".to_string());
-    map.entry("X-Frame-Options".to_string())
-        .or_insert_with(|| "DENY".to_string());
-    map.entry("X-XSS-Protection".to_string())
-        .or_insert_with(|| "1; mode=block".to_string());
-    map.entry("Referrer-Policy".to_string())
-        .or_insert_with(|| "strict-origin-when-cross-origin".to_string());
-    map.entry("Content-Security-Policy".to_string())
-        .or_insert_with(|| "default-src 'self'".to_string());
+    // ユーザーが上書きしたい場合を尊重し、未設定時（大文字小文字を区別しない）のみ入れる
+    for (name, value) in default_security_headers() {
+        if !map.keys().any(|k| k.eq_ignore_ascii_case(name)) {
+            map.insert(name.to_string(), value);
+        }
+    }
 }
@@ -0,0 +1,108 @@
+//! HTTPの日時（IMF-fixdate）の生成と解析
+//!
+//! `Last-Modified`・`Expires`・`If-Modified-Since`・クッキーの`Expires`属性・日時形式の
+//! `Retry-After`で使う形式（`Sun, 06 Nov 1994 08:49:37 GMT`）を1か所で扱います。
+//! 解析はRFC 9110に従い、旧形式（RFC 850・asctime）も受け付けます。
+
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+
+/// IMF-fixdateの書式
+const IMF_FIXDATE: &str = "%a, %d %b %Y %H:%M:%S GMT";
+/// 旧形式（RFC 850、`Sunday, 06-Nov-94 08:49:37 GMT`）の書式
+const RFC850_DATE: &str = "%A, %d-%b-%y %H:%M:%S GMT";
+/// 旧形式（asctime、`Sun Nov  6 08:49:37 1994`）の書式
+const ASCTIME_DATE: &str = "%a %b %e %H:%M:%S %Y";
+
+/// 日時をIMF-fixdateの文字列に変換
+///
+/// ```
+/// use chrono::{TimeZone, Utc};
+/// use runbridge::common::format_http_date;
+///
+/// let date = Utc.with_ymd_and_hms(1994, 11, 6, 8, 49, 37).unwrap();
+/// assert_eq!(format_http_date(date), "Sun, 06 Nov 1994 08:49:37 GMT");
+/// ```
+pub fn format_http_date(date: DateTime<Utc>) -> String {
+    date.format(IMF_FIXDATE).to_string()
+}
+
+/// `SystemTime`をIMF-fixdateの文字列に変換（ファイルの更新日時など）
+pub fn format_system_time(time: SystemTime) -> String {
+    format_http_date(DateTime::<Utc>::from(time))
+}
+
+/// HTTPの日時を解析（IMF-fixdate・RFC 850・asctimeに対応、解析できない場合はNone）
+pub fn parse_http_date(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    [IMF_FIXDATE, RFC850_DATE, ASCTIME_DATE]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .map(|naive| Utc.from_utc_datetime(&naive))
+}
+
+/// `Retry-After`ヘッダーの値（秒数または日時）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryAfter {
+    /// 再試行までの秒数
+    Delay(Duration),
+    /// 再試行できる日時
+    Date(DateTime<Utc>),
+}
+
+impl RetryAfter {
+    /// ヘッダーの値を解析（解析できない場合はNone）
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        match value.parse::<u64>() {
+            Ok(seconds) => Some(Self::Delay(Duration::from_secs(seconds))),
+            Err(_) => parse_http_date(value).map(Self::Date),
+        }
+    }
+
+    /// `now`から再試行できるまでの時間（過去の日時の場合はゼロ）
+    pub fn delay_from(&self, now: DateTime<Utc>) -> Duration {
+        match self {
+            Self::Delay(delay) => *delay,
+            Self::Date(date) => (*date - now).to_std().unwrap_or(Duration::ZERO),
+        }
+    }
+}
+
+impl fmt::Display for RetryAfter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Delay(delay) => write!(f, "{}", delay.as_secs()),
+            Self::Date(date) => write!(f, "{}", format_http_date(*date)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_http_date_formats() {
+        let expected = Utc.with_ymd_and_hms(1994, 11, 6, 8, 49, 37).unwrap();
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"), Some(expected));
+        assert_eq!(parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"), Some(expected));
+        assert_eq!(parse_http_date("Sun Nov  6 08:49:37 1994"), Some(expected));
+        assert_eq!(parse_http_date("1994-11-06T08:49:37Z"), None);
+        assert_eq!(parse_http_date(&format_http_date(expected)), Some(expected));
+        assert_eq!(format_system_time(SystemTime::UNIX_EPOCH), "Thu, 01 Jan 1970 00:00:00 GMT");
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(RetryAfter::parse("120"), Some(RetryAfter::Delay(Duration::from_secs(120))));
+        let date = RetryAfter::parse("Mon, 01 Jan 2024 00:01:30 GMT").unwrap();
+        assert_eq!(date.delay_from(now), Duration::from_secs(90));
+        assert_eq!(date.to_string(), "Mon, 01 Jan 2024 00:01:30 GMT");
+        assert_eq!(RetryAfter::Date(now).delay_from(now + chrono::Duration::seconds(5)), Duration::ZERO);
+        assert_eq!(RetryAfter::parse("soon"), None);
+    }
+}
@@ -0,0 +1,189 @@
+//! 認証方式に依存しない認証済みIDの抽象化
+//!
+//! JWT・APIキー・Basic認証・OIDCなどの認証ミドルウェアは、検証に成功したら
+//! `Request::set_identity`で`AuthContext`を格納します。ハンドラーは`req.identity()`や
+//! `req.require_identity()?`で、どの認証方式が設定されているかを意識せずに利用できます。
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::error::Error;
+use super::http::Request;
+
+/// RequestContextに格納する際のキー
+pub const AUTH_CONTEXT_KEY: &str = "runbridge.auth";
+
+/// 認証済みの主体
+pub trait Identity: fmt::Debug + Send + Sync {
+    /// 主体の識別子（ユーザーID、APIキーのID、`sub`クレームなど）
+    fn subject(&self) -> &str;
+
+    /// ロール・スコープを持つかどうか
+    fn has_role(&self, _role: &str) -> bool {
+        false
+    }
+
+    /// 任意の属性（メールアドレス、テナントIDなど）
+    fn attribute(&self, _key: &str) -> Option<&str> {
+        None
+    }
+}
+
+/// 汎用的なIdentityの実装（独自の型を定義しない認証ミドルウェア向け）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserIdentity {
+    /// 主体の識別子
+    pub subject: String,
+    /// ロール・スコープ
+    pub roles: Vec<String>,
+    /// 任意の属性
+    pub attributes: HashMap<String, String>,
+}
+
+impl UserIdentity {
+    /// 識別子を指定して作成
+    pub fn new(subject: impl Into<String>) -> Self {
+        Self {
+            subject: subject.into(),
+            roles: Vec::new(),
+            attributes: HashMap::new(),
+        }
+    }
+
+    /// ロールを追加
+    pub fn with_role(mut self, role: impl Into<String>) -> Self {
+        self.roles.push(role.into());
+        self
+    }
+
+    /// 属性を追加
+    pub fn with_attribute(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.attributes.insert(key.into(), value.into());
+        self
+    }
+}
+
+impl Identity for UserIdentity {
+    fn subject(&self) -> &str {
+        &self.subject
+    }
+
+    fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+
+    fn attribute(&self, key: &str) -> Option<&str> {
+        self.attributes.get(key).map(String::as_str)
+    }
+}
+
+/// 認証結果（認証済みIDと、認証に使用した方式）
+#[derive(Debug, Clone)]
+pub struct AuthContext {
+    identity: Arc<dyn Identity>,
+    mechanism: String,
+}
+
+impl AuthContext {
+    /// 認証済みIDと方式名（`jwt`、`api_key`、`basic`、`oidc`など）から作成
+    pub fn new(identity: impl Identity + 'static, mechanism: impl Into<String>) -> Self {
+        Self {
+            identity: Arc::new(identity),
+            mechanism: mechanism.into(),
+        }
+    }
+
+    /// 認証済みID
+    pub fn identity(&self) -> &dyn Identity {
+        self.identity.as_ref()
+    }
+
+    /// 認証に使用した方式名
+    pub fn mechanism(&self) -> &str {
+        &self.mechanism
+    }
+}
+
+impl Request {
+    /// 認証結果を格納（認証ミドルウェアで使用）
+    pub fn set_identity(&mut self, identity: impl Identity + 'static, mechanism: impl Into<String>) {
+        self.context_mut()
+            .set(AUTH_CONTEXT_KEY, AuthContext::new(identity, mechanism));
+    }
+
+    /// 認証結果を取得（未認証の場合はNone）
+    pub fn auth_context(&self) -> Option<&AuthContext> {
+        self.context().get::<AuthContext>(AUTH_CONTEXT_KEY)
+    }
+
+    /// 認証済みIDを取得（未認証の場合はNone）
+    pub fn identity(&self) -> Option<&dyn Identity> {
+        self.auth_context().map(AuthContext::identity)
+    }
+
+    /// 認証済みIDを取得（未認証の場合は`AuthenticationError`（401））
+    pub fn require_identity(&self) -> Result<&dyn Identity, Error> {
+        self.identity()
+            .ok_or_else(|| Error::AuthenticationError("Authentication required".to_string()))
+    }
+
+    /// 指定したロールを持つ認証済みIDを取得
+    /// （未認証の場合は`AuthenticationError`（401）、ロールが無い場合は`AuthorizationError`（403））
+    pub fn require_role(&self, role: &str) -> Result<&dyn Identity, Error> {
+        let identity = self.require_identity()?;
+        if identity.has_role(role) {
+            Ok(identity)
+        } else {
+            Err(Error::AuthorizationError(format!("Missing required role: {}", role)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Method;
+
+    /// 独自のIdentity実装（APIキー認証を想定）
+    #[derive(Debug)]
+    struct ApiKeyIdentity {
+        key_id: String,
+    }
+
+    impl Identity for ApiKeyIdentity {
+        fn subject(&self) -> &str {
+            &self.key_id
+        }
+    }
+
+    #[test]
+    fn test_identity_helpers() {
+        let mut req = Request::new(Method::GET, "/".to_string());
+        assert!(req.identity().is_none());
+        assert_eq!(req.require_identity().unwrap_err().status_code(), 401);
+        assert_eq!(req.require_role("admin").unwrap_err().status_code(), 401);
+
+        req.set_identity(
+            UserIdentity::new("user-1").with_role("admin").with_attribute("email", "a@example.com"),
+            "jwt",
+        );
+        let identity = req.require_identity().unwrap();
+        assert_eq!(identity.subject(), "user-1");
+        assert_eq!(identity.attribute("email"), Some("a@example.com"));
+        assert_eq!(req.auth_context().unwrap().mechanism(), "jwt");
+        assert!(req.require_role("admin").is_ok());
+        assert_eq!(req.require_role("billing").unwrap_err().status_code(), 403);
+    }
+
+    #[test]
+    fn test_custom_identity_type() {
+        let mut req = Request::new(Method::GET, "/".to_string());
+        req.set_identity(ApiKeyIdentity { key_id: "key-42".to_string() }, "api_key");
+
+        let identity = req.identity().unwrap();
+        assert_eq!(identity.subject(), "key-42");
+        assert!(!identity.has_role("admin"));
+        assert_eq!(identity.attribute("email"), None);
+    }
+}
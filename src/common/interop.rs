@@ -0,0 +1,338 @@
+//! `http`クレートの型との相互変換
+//!
+//! tower/hyper等のエコシステムとの橋渡しや、テスト用のトラフィック記録・再生に使用します。
+//! 変換対象は本クレートが依存している`http` 0.2系の`Request<Vec<u8>>`/`Response<Vec<u8>>`です。
+//! RequestContextは変換されません。
+//!
+//! `Request`は`Serialize`/`Deserialize`を実装しています。`Response`は`ResponseWrapper`の
+//! 包括実装と衝突するため、`#[serde(with = "runbridge::common::interop::response_serde")]`で
+//! シリアライズします。
+
+use std::collections::HashMap;
+
+use crate::error::Error;
+use super::http::{Method, Request, Response};
+use super::utils::{parse_query_string, percent_encode};
+
+impl From<Method> for ::http::Method {
+    fn from(method: Method) -> Self {
+        match method {
+            Method::GET => ::http::Method::GET,
+            Method::POST => ::http::Method::POST,
+            Method::PUT => ::http::Method::PUT,
+            Method::DELETE => ::http::Method::DELETE,
+            Method::PATCH => ::http::Method::PATCH,
+            Method::HEAD => ::http::Method::HEAD,
+            Method::OPTIONS => ::http::Method::OPTIONS,
+        }
+    }
+}
+
+impl TryFrom<&::http::Method> for Method {
+    type Error = Error;
+
+    fn try_from(method: &::http::Method) -> Result<Self, Self::Error> {
+        Method::from_str(method.as_str()).ok_or_else(|| {
+            Error::InvalidRequestBody(format!("Unsupported HTTP method: {}", method))
+        })
+    }
+}
+
+/// HeaderMapを共通形式に変換（UTF-8として解釈できない値は除外、同名ヘッダーは`, `で連結）
+///
+/// `Cookie`はカンマ区切りにできないため`; `で連結します（RFC 9113 8.2.3、HTTP/2で分割されたCookie）。
+pub(crate) fn collect_headers(headers: &::http::HeaderMap) -> HashMap<String, String> {
+    join_header_values(headers.keys_len(), headers.iter().map(|(name, value)| (name.as_str(), value.to_str().ok())))
+}
+
+/// ヘッダー名と値（文字列として解釈できない場合はNone）の列を共通形式に変換（`http`のバージョンによらず使用）
+pub(crate) fn join_header_values<'a>(
+    capacity: usize,
+    headers: impl Iterator<Item = (&'a str, Option<&'a str>)>,
+) -> HashMap<String, String> {
+    let mut result: HashMap<String, String> = HashMap::with_capacity(capacity);
+    for (name, value) in headers {
+        let value = match value {
+            Some(v) => v,
+            None => {
+                log::warn!("Skipping non UTF-8 header value: {}", name);
+                continue;
+            }
+        };
+        result
+            .entry(name.to_string())
+            .and_modify(|existing| {
+                existing.push_str(if name == "cookie" { "; " } else { ", " });
+                existing.push_str(value);
+            })
+            .or_insert_with(|| value.to_string());
+    }
+    result
+}
+
+fn invalid_header(name: &str, e: impl std::fmt::Display) -> Error {
+    Error::InvalidHeader(format!("{}: {}", name, e))
+}
+
+impl TryFrom<::http::Request<Vec<u8>>> for Request {
+    type Error = Error;
+
+    fn try_from(req: ::http::Request<Vec<u8>>) -> Result<Self, Self::Error> {
+        let (parts, body) = req.into_parts();
+        let mut request = Request::new(Method::try_from(&parts.method)?, parts.uri.path().to_string());
+        request.query_params = parse_query_string(parts.uri.query().unwrap_or(""));
+        request.headers = collect_headers(&parts.headers);
+        request.body = if body.is_empty() { None } else { Some(body) };
+        Ok(request)
+    }
+}
+
+impl TryFrom<Request> for ::http::Request<Vec<u8>> {
+    type Error = Error;
+
+    fn try_from(req: Request) -> Result<Self, Self::Error> {
+        // HashMapの順序に依存しないようキーでソートしてクエリ文字列を組み立てる
+        let mut params: Vec<_> = req.query_params.iter().collect();
+        params.sort();
+        let query = params
+            .iter()
+            .map(|(k, v)| format!("{}={}", percent_encode(k), percent_encode(v)))
+            .collect::<Vec<_>>()
+            .join("&");
+        let uri = if query.is_empty() {
+            req.path.clone()
+        } else {
+            format!("{}?{}", req.path, query)
+        };
+
+        let mut builder = ::http::Request::builder()
+            .method(::http::Method::from(req.method))
+            .uri(uri.as_str());
+        for (name, value) in &req.headers {
+            let name = ::http::header::HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| invalid_header(name, e))?;
+            let value = ::http::HeaderValue::from_str(value)
+                .map_err(|e| invalid_header(name.as_str(), e))?;
+            builder = builder.header(name, value);
+        }
+        builder
+            .body(req.body.unwrap_or_default())
+            .map_err(|e| Error::InvalidRequestBody(format!("Invalid request URI {:?}: {}", uri, e)))
+    }
+}
+
+impl From<::http::Response<Vec<u8>>> for Response {
+    /// 既定のセキュリティヘッダーは注入せず、変換元のヘッダーをそのまま使用します
+    fn from(res: ::http::Response<Vec<u8>>) -> Self {
+        let (mut parts, body) = res.into_parts();
+        // Set-Cookieは連結せずに1つずつ保持する
+        let cookies = match parts.headers.remove(::http::header::SET_COOKIE) {
+            Some(first) => std::iter::once(first)
+                .chain(parts.headers.get_all(::http::header::SET_COOKIE).iter().cloned())
+                .filter_map(|v| v.to_str().ok().map(str::to_string))
+                .collect(),
+            None => Vec::new(),
+        };
+        Response {
+            status: parts.status.as_u16(),
+            headers: collect_headers(&parts.headers),
+            body: if body.is_empty() { None } else { Some(body) },
+            cookies,
+            event_stream: None,
+        }
+    }
+}
+
+impl TryFrom<Response> for ::http::Response<Vec<u8>> {
+    type Error = Error;
+
+    fn try_from(res: Response) -> Result<Self, Self::Error> {
+        let status = ::http::StatusCode::from_u16(res.status).map_err(|e| {
+            Error::ResponseSerializationError(format!("Invalid status code {}: {}", res.status, e))
+        })?;
+        let mut builder = ::http::Response::builder().status(status);
+        // Set-Cookieは連結された値を分割して1つずつ追加する
+        for cookie in res.set_cookie_values() {
+            let value = ::http::HeaderValue::from_str(&cookie)
+                .map_err(|e| invalid_header("Set-Cookie", e))?;
+            builder = builder.header(::http::header::SET_COOKIE, value);
+        }
+        for (name, value) in res.headers.iter().filter(|(name, _)| !name.eq_ignore_ascii_case("set-cookie")) {
+            let name = ::http::header::HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| invalid_header(name, e))?;
+            let value = ::http::HeaderValue::from_str(value)
+                .map_err(|e| invalid_header(name.as_str(), e))?;
+            builder = builder.header(name, value);
+        }
+        builder
+            .body(res.body.unwrap_or_default())
+            .map_err(|e| Error::ResponseSerializationError(e.to_string()))
+    }
+}
+
+/// `Response`のシリアライズ/デシリアライズ（`#[serde(with = "...")]`で使用）
+///
+/// デシリアライズ時は既定のセキュリティヘッダーを注入せず、記録された内容をそのまま復元します。
+pub mod response_serde {
+    use std::collections::HashMap;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::common::http::Response;
+
+    #[derive(Serialize)]
+    struct ResponseRef<'a> {
+        status: u16,
+        headers: &'a HashMap<String, String>,
+        body: &'a Option<Vec<u8>>,
+        #[serde(skip_serializing_if = "<[String]>::is_empty")]
+        cookies: &'a [String],
+    }
+
+    #[derive(Deserialize)]
+    struct ResponseOwned {
+        status: u16,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+        #[serde(default)]
+        body: Option<Vec<u8>>,
+        #[serde(default)]
+        cookies: Vec<String>,
+    }
+
+    /// Responseをシリアライズ
+    pub fn serialize<S: Serializer>(res: &Response, serializer: S) -> Result<S::Ok, S::Error> {
+        ResponseRef {
+            status: res.status,
+            headers: &res.headers,
+            body: &res.body,
+            cookies: &res.cookies,
+        }
+        .serialize(serializer)
+    }
+
+    /// Responseをデシリアライズ
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Response, D::Error> {
+        let owned = ResponseOwned::deserialize(deserializer)?;
+        Ok(Response {
+            status: owned.status,
+            headers: owned.headers,
+            body: owned.body,
+            cookies: owned.cookies,
+            event_stream: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_round_trip_through_http() {
+        let req = Request::new(Method::POST, "/items".to_string())
+            .with_query_param("q", "a b&c")
+            .with_query_param("page", "2")
+            .with_header("Content-Type", "application/json")
+            .with_body(b"{}".to_vec());
+
+        let http_req = ::http::Request::<Vec<u8>>::try_from(req).unwrap();
+        assert_eq!(http_req.method(), ::http::Method::POST);
+        assert_eq!(http_req.uri(), "/items?page=2&q=a%20b%26c");
+        assert_eq!(http_req.headers()["content-type"], "application/json");
+
+        let back = Request::try_from(http_req).unwrap();
+        assert_eq!(back.method, Method::POST);
+        assert_eq!(back.path, "/items");
+        assert_eq!(back.query_params.get("q").map(String::as_str), Some("a b&c"));
+        assert_eq!(back.headers.get("content-type").map(String::as_str), Some("application/json"));
+        assert_eq!(back.body.as_deref(), Some(&b"{}"[..]));
+    }
+
+    #[test]
+    fn test_request_from_http_rejects_unsupported_method_and_joins_headers() {
+        let http_req = ::http::Request::builder()
+            .method("TRACE")
+            .uri("/")
+            .body(Vec::new())
+            .unwrap();
+        assert_eq!(Request::try_from(http_req).unwrap_err().status_code(), 400);
+
+        let http_req = ::http::Request::builder()
+            .uri("/")
+            .header("Accept", "text/html")
+            .header("Accept", "application/json")
+            .body(Vec::new())
+            .unwrap();
+        let req = Request::try_from(http_req).unwrap();
+        assert_eq!(req.headers.get("accept").map(String::as_str), Some("text/html, application/json"));
+        assert!(req.body.is_none());
+
+        let http_req = ::http::Request::builder()
+            .uri("/")
+            .header("Cookie", "session=abc")
+            .header("Cookie", "theme=dark")
+            .body(Vec::new())
+            .unwrap();
+        let req = Request::try_from(http_req).unwrap();
+        assert_eq!(req.headers.get("cookie").map(String::as_str), Some("session=abc; theme=dark"));
+        assert_eq!(req.cookies().get("theme"), Some("dark"));
+    }
+
+    #[test]
+    fn test_response_conversions() {
+        let res = Response::new(201)
+            .with_header("Location", "/items/1")
+            .with_body(b"created".to_vec());
+        let http_res = ::http::Response::<Vec<u8>>::try_from(res).unwrap();
+        assert_eq!(http_res.status(), ::http::StatusCode::CREATED);
+        assert_eq!(http_res.headers()["location"], "/items/1");
+
+        let back = Response::from(http_res);
+        assert_eq!(back.status, 201);
+        assert_eq!(back.headers.get("location").map(String::as_str), Some("/items/1"));
+        assert_eq!(back.body.as_deref(), Some(&b"created"[..]));
+
+        // 連結されたSet-Cookieは別々のヘッダーにする
+        let res = Response::ok().with_header("Set-Cookie", "a=1; Path=/, b=2; Expires=Wed, 21 Oct 2026 07:28:00 GMT");
+        let http_res = ::http::Response::<Vec<u8>>::try_from(res).unwrap();
+        let cookies: Vec<_> = http_res.headers().get_all("set-cookie").iter().collect();
+        assert_eq!(cookies, vec!["a=1; Path=/", "b=2; Expires=Wed, 21 Oct 2026 07:28:00 GMT"]);
+
+        let broken = Response { status: 42, headers: HashMap::new(), body: None, cookies: Vec::new(), event_stream: None };
+        assert!(::http::Response::<Vec<u8>>::try_from(broken).is_err());
+    }
+
+    #[test]
+    fn test_serde_fixture_round_trip() {
+        let fixture = r#"{
+            "method": "GET",
+            "path": "/search",
+            "query_params": {"q": "rust"},
+            "headers": {"X-Request-Id": "abc"}
+        }"#;
+        let req: Request = serde_json::from_str(fixture).unwrap();
+        assert_eq!(req.method, Method::GET);
+        assert_eq!(req.headers.get("x-request-id").map(String::as_str), Some("abc"));
+        assert!(req.body.is_none());
+
+        let json = serde_json::to_value(&req).unwrap();
+        assert_eq!(json["query_params"]["q"], "rust");
+        assert!(json.get("context").is_none());
+
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct Exchange {
+            request: Request,
+            #[serde(with = "response_serde")]
+            response: Response,
+        }
+
+        let exchange = Exchange { request: req, response: Response::ok().with_body(b"ok".to_vec()) };
+        let json = serde_json::to_string(&exchange).unwrap();
+        let replayed: Exchange = serde_json::from_str(&json).unwrap();
+        assert_eq!(replayed.request.path, "/search");
+        assert_eq!(replayed.response.status, 200);
+        assert_eq!(replayed.response.headers, exchange.response.headers);
+        assert_eq!(replayed.response.body, exchange.response.body);
+    }
+}
@@ -0,0 +1,428 @@
+//! 長時間の処理（非同期ジョブ）の登録と状態の管理
+//!
+//! 1回のリクエスト（Lambdaの1回の呼び出し）で終わらない処理は、ジョブとして登録して
+//! `202 Accepted`と状態確認用のURLを返し、クライアントはそのURLをポーリングして完了を待ちます。
+//! エンドポイントは`handler::JobEndpoints`で登録し、ジョブの状態は`JobStore`に保存します。
+//!
+//! ジョブIDは推測できない乱数で、状態確認のエンドポイントは登録した利用者を確認しないため、
+//! IDを知っていることが結果を参照する権限になります（ベアラートークンと同様に扱ってください）。
+//! 利用者ごとに結果を制限する場合は、状態確認のエンドポイントをガード・認証のミドルウェアで保護し、
+//! 入力に利用者を含めてハンドラー側で照合します。
+//!
+//! 保存先は既定でインスタンスごとのメモリ（`MemoryJobStore`）です。複数のインスタンス・別の
+//! ワーカーと共有する場合は、共有ボリューム（Cloud RunのCloud Storageボリューム、LambdaのEFSなど）に
+//! 保存する`FileJobStore`か、`JobStore`を実装したDynamoDB・Firestoreなどの保存先を使用します。
+//!
+//! ```
+//! use runbridge::common::{JobQueue, MemoryJobStore};
+//!
+//! # tokio::runtime::Runtime::new().unwrap().block_on(async {
+//! let queue = JobQueue::new(MemoryJobStore::default());
+//! let job = queue.submit(serde_json::json!({ "report": "monthly" })).await.unwrap();
+//! queue.progress(&job.id, 50, Some("rendering")).await.unwrap();
+//! queue.complete(&job.id, serde_json::json!({ "url": "/reports/1" })).await.unwrap();
+//! assert!(queue.get(&job.id).await.unwrap().unwrap().is_finished());
+//! # });
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::Error;
+use super::csp::random_bytes;
+use super::request_id::generate_request_id;
+
+/// メモリに保持するジョブの既定の最大件数
+pub const DEFAULT_JOB_CAPACITY: usize = 1024;
+
+/// ジョブIDの最大長（保存先のキー・ファイル名として安全に扱える長さ）
+const MAX_JOB_ID_LENGTH: usize = 128;
+
+/// ジョブの状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    /// 登録済みで未着手
+    Pending,
+    /// 処理中
+    Running,
+    /// 完了
+    Succeeded,
+    /// 失敗
+    Failed,
+}
+
+impl JobStatus {
+    /// 完了または失敗しているか
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Succeeded | Self::Failed)
+    }
+}
+
+/// 保存先に格納するジョブ（JSONなどにシリアライズして外部の保存先に格納可能）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Job {
+    /// ジョブID
+    pub id: String,
+    /// 状態
+    pub status: JobStatus,
+    /// 進捗（0〜100）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub progress: Option<u8>,
+    /// 進捗の説明
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// 登録時に受け取った入力
+    #[serde(default)]
+    pub input: Value,
+    /// 完了時の結果
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    /// 失敗時の理由
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// 登録した時刻（UNIX時刻の秒）
+    pub created_at: u64,
+    /// 最後に更新した時刻（UNIX時刻の秒）
+    pub updated_at: u64,
+}
+
+impl Job {
+    /// 入力を指定して未着手のジョブを作成（IDは自動で生成）
+    ///
+    /// IDはOSの乱数による128ビットの16進文字列で、推測されないことを前提に状態確認のURLに使用します。
+    pub fn new(input: Value) -> Self {
+        let now = unix_now();
+        Self {
+            id: random_bytes().iter().map(|b| format!("{:02x}", b)).collect(),
+            status: JobStatus::Pending,
+            progress: None,
+            message: None,
+            input,
+            result: None,
+            error: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// 完了または失敗しているか
+    pub fn is_finished(&self) -> bool {
+        self.status.is_finished()
+    }
+
+    /// クライアントに返す状態（入力は含めない）
+    pub fn status_json(&self) -> Value {
+        let mut body = serde_json::to_value(self).unwrap_or(Value::Null);
+        if let Some(body) = body.as_object_mut() {
+            body.remove("input");
+        }
+        body
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// ジョブIDとして受け付ける文字列か（英数字・`-`・`_`のみ、128文字以下）
+///
+/// URLから受け取ったIDをそのまま保存先のキー・ファイル名に使うため、`JobQueue`はこれを満たさないIDを
+/// 存在しないジョブとして扱います。
+pub fn is_valid_job_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_JOB_ID_LENGTH
+        && id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+/// ジョブの保存先
+///
+/// 登録したエンドポイントと別のワーカー（別のLambda関数など）が同じジョブを更新するため、
+/// 複数のインスタンスで共有する場合は外部の保存先を実装します。
+#[async_trait]
+pub trait JobStore: Send + Sync {
+    /// IDのジョブを取得（無い場合はNone）
+    async fn get(&self, id: &str) -> Result<Option<Job>, Error>;
+
+    /// ジョブを保存（同じIDのジョブは置き換える）
+    async fn put(&self, job: Job) -> Result<(), Error>;
+}
+
+/// インスタンスのメモリに保持する保存先
+///
+/// 件数の上限を超えた場合は、完了・失敗したジョブのうち最も古いものから削除します
+/// （全て未完了の場合は最も古いジョブを削除）。
+pub struct MemoryJobStore {
+    capacity: usize,
+    jobs: Mutex<HashMap<String, Job>>,
+}
+
+impl fmt::Debug for MemoryJobStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryJobStore").field("capacity", &self.capacity).finish()
+    }
+}
+
+impl Default for MemoryJobStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_JOB_CAPACITY)
+    }
+}
+
+impl MemoryJobStore {
+    /// 最大件数を指定して作成
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity: capacity.max(1), jobs: Mutex::new(HashMap::new()) }
+    }
+
+    fn lock_jobs(&self) -> std::sync::MutexGuard<'_, HashMap<String, Job>> {
+        // ポイズン状態でもジョブの状態は参照できるようにする
+        self.jobs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl JobStore for MemoryJobStore {
+    async fn get(&self, id: &str) -> Result<Option<Job>, Error> {
+        Ok(self.lock_jobs().get(id).cloned())
+    }
+
+    async fn put(&self, job: Job) -> Result<(), Error> {
+        let mut jobs = self.lock_jobs();
+        if jobs.len() >= self.capacity && !jobs.contains_key(&job.id) {
+            let oldest = jobs
+                .values()
+                .min_by_key(|stored| (!stored.is_finished(), stored.updated_at))
+                .map(|stored| stored.id.clone());
+            if let Some(oldest) = oldest {
+                debug!("Evicting job {} from memory store", oldest);
+                jobs.remove(&oldest);
+            }
+        }
+        jobs.insert(job.id.clone(), job);
+        Ok(())
+    }
+}
+
+/// ディレクトリにジョブごとのJSONファイル（`{id}.json`）として保存する保存先
+///
+/// Cloud RunのCloud Storageボリューム（Cloud Storage FUSE）やLambdaのEFSのように複数の
+/// インスタンスからマウントしたディレクトリを指定すると、インスタンス・ワーカー間で共有できます。
+/// 書き込みは一時ファイルへの書き込みと名前の変更で行うため、読み込み側が書き込み途中の
+/// ファイルを読むことはありません。
+#[derive(Debug, Clone)]
+pub struct FileJobStore {
+    dir: PathBuf,
+}
+
+impl FileJobStore {
+    /// 保存先のディレクトリを指定して作成（無い場合は最初の保存時に作成）
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path_for(&self, id: &str) -> Result<PathBuf, Error> {
+        if !is_valid_job_id(id) {
+            return Err(Error::InvalidPathParameter(format!("Invalid job id: {}", id)));
+        }
+        Ok(self.dir.join(format!("{}.json", id)))
+    }
+}
+
+#[async_trait]
+impl JobStore for FileJobStore {
+    async fn get(&self, id: &str) -> Result<Option<Job>, Error> {
+        let path = self.path_for(id)?;
+        match tokio::fs::read(&path).await {
+            Ok(bytes) => serde_json::from_slice(&bytes).map(Some).map_err(|e| {
+                Error::InternalServerError(format!("Failed to parse job file {}: {}", path.display(), e))
+            }),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(Error::ExternalServiceError(format!(
+                "Failed to read job file {}: {}",
+                path.display(),
+                e
+            ))),
+        }
+    }
+
+    async fn put(&self, job: Job) -> Result<(), Error> {
+        let path = self.path_for(&job.id)?;
+        let bytes = serde_json::to_vec(&job)
+            .map_err(|e| Error::InternalServerError(format!("Failed to serialize job: {}", e)))?;
+        let write_err = |e: std::io::Error| {
+            Error::ExternalServiceError(format!("Failed to write job file {}: {}", path.display(), e))
+        };
+        tokio::fs::create_dir_all(&self.dir).await.map_err(write_err)?;
+        let tmp = self.dir.join(format!(".{}.json.{}.tmp", job.id, generate_request_id()));
+        tokio::fs::write(&tmp, &bytes).await.map_err(write_err)?;
+        if let Err(e) = tokio::fs::rename(&tmp, &path).await {
+            let _ = tokio::fs::remove_file(&tmp).await;
+            return Err(write_err(e));
+        }
+        Ok(())
+    }
+}
+
+/// ジョブの登録と状態の更新（エンドポイントとワーカーで共有）
+///
+/// Cloneしたインスタンスは同じ保存先を共有します。不正なID・存在しないジョブの更新は
+/// `Error::RouteNotFound`（404）を返します。
+#[derive(Clone)]
+pub struct JobQueue {
+    store: Arc<dyn JobStore>,
+}
+
+impl fmt::Debug for JobQueue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JobQueue").finish_non_exhaustive()
+    }
+}
+
+impl JobQueue {
+    /// 保存先を指定して作成
+    pub fn new<S: JobStore + 'static>(store: S) -> Self {
+        Self { store: Arc::new(store) }
+    }
+
+    /// 入力を指定してジョブを登録
+    pub async fn submit(&self, input: Value) -> Result<Job, Error> {
+        let job = Job::new(input);
+        self.store.put(job.clone()).await?;
+        debug!("Submitted job {}", job.id);
+        Ok(job)
+    }
+
+    /// IDのジョブを取得（不正なID・無い場合はNone）
+    pub async fn get(&self, id: &str) -> Result<Option<Job>, Error> {
+        if !is_valid_job_id(id) {
+            return Ok(None);
+        }
+        self.store.get(id).await
+    }
+
+    /// ジョブを処理中にする
+    pub async fn start(&self, id: &str) -> Result<Job, Error> {
+        self.update(id, |job| job.status = JobStatus::Running).await
+    }
+
+    /// 進捗（0〜100、超える値は100）と説明を更新する（未着手の場合は処理中にする）
+    pub async fn progress(&self, id: &str, percent: u8, message: Option<&str>) -> Result<Job, Error> {
+        self.update(id, |job| {
+            job.status = JobStatus::Running;
+            job.progress = Some(percent.min(100));
+            job.message = message.map(str::to_string);
+        })
+        .await
+    }
+
+    /// 結果を指定してジョブを完了にする
+    pub async fn complete(&self, id: &str, result: Value) -> Result<Job, Error> {
+        self.update(id, |job| {
+            job.status = JobStatus::Succeeded;
+            job.progress = Some(100);
+            job.result = Some(result);
+            job.error = None;
+        })
+        .await
+    }
+
+    /// 理由を指定してジョブを失敗にする（理由はクライアントに返される）
+    pub async fn fail(&self, id: &str, reason: impl Into<String>) -> Result<Job, Error> {
+        let reason = reason.into();
+        self.update(id, |job| {
+            job.status = JobStatus::Failed;
+            job.error = Some(reason);
+        })
+        .await
+    }
+
+    async fn update(&self, id: &str, apply: impl FnOnce(&mut Job)) -> Result<Job, Error> {
+        let mut job = self
+            .get(id)
+            .await?
+            .ok_or_else(|| Error::RouteNotFound(format!("Job not found: {}", id)))?;
+        apply(&mut job);
+        job.updated_at = unix_now().max(job.updated_at);
+        self.store.put(job.clone()).await?;
+        Ok(job)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_job_lifecycle() {
+        let queue = JobQueue::new(MemoryJobStore::default());
+        let job = queue.submit(json!({ "n": 3 })).await.unwrap();
+        assert!(is_valid_job_id(&job.id));
+        assert_eq!(job.status, JobStatus::Pending);
+
+        let job = queue.progress(&job.id, 150, Some("step 2")).await.unwrap();
+        assert_eq!((job.status, job.progress), (JobStatus::Running, Some(100)));
+        let job = queue.complete(&job.id, json!(6)).await.unwrap();
+        assert!(job.is_finished());
+
+        let status = queue.get(&job.id).await.unwrap().unwrap().status_json();
+        assert_eq!(status["status"], "succeeded");
+        assert_eq!(status["result"], 6);
+        assert!(status.get("input").is_none());
+
+        assert_eq!(queue.fail("missing", "x").await.unwrap_err().status_code(), 404);
+        assert!(queue.get("../etc/passwd").await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_job_ids_are_random_128_bit_hex() {
+        let a = Job::new(Value::Null).id;
+        let b = Job::new(Value::Null).id;
+        assert_eq!(a.len(), 32);
+        assert!(a.bytes().all(|c| c.is_ascii_hexdigit()));
+        assert!(is_valid_job_id(&a));
+        assert_ne!(a, b);
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_evicts_finished_jobs_first() {
+        let queue = JobQueue::new(MemoryJobStore::new(2));
+        let running = queue.submit(Value::Null).await.unwrap();
+        let done = queue.submit(Value::Null).await.unwrap();
+        queue.complete(&done.id, Value::Null).await.unwrap();
+
+        let next = queue.submit(Value::Null).await.unwrap();
+        assert!(queue.get(&running.id).await.unwrap().is_some());
+        assert!(queue.get(&done.id).await.unwrap().is_none());
+        assert!(queue.get(&next.id).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_file_store_round_trip() {
+        let dir = std::env::temp_dir().join(format!("runbridge-jobs-{}", generate_request_id()));
+        let store = FileJobStore::new(&dir);
+        assert!(store.get("unknown").await.unwrap().is_none());
+        assert_eq!(store.get("a/b").await.unwrap_err().status_code(), 400);
+
+        let queue = JobQueue::new(store.clone());
+        let job = queue.submit(json!({ "file": "a.csv" })).await.unwrap();
+        queue.fail(&job.id, "invalid row 3").await.unwrap();
+
+        // 別のインスタンスから同じディレクトリを参照しても同じ状態を取得できる
+        let stored = JobQueue::new(FileJobStore::new(&dir)).get(&job.id).await.unwrap().unwrap();
+        assert_eq!(stored.status, JobStatus::Failed);
+        assert_eq!(stored.error.as_deref(), Some("invalid row 3"));
+        assert_eq!(stored.input, json!({ "file": "a.csv" }));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,184 @@
+//! 処理期限を考慮した大きなJSON配列のシリアライズ
+//!
+//! 集計系のエンドポイントなどで大量の要素を返す場合に、一定件数ごとに実行器へ制御を戻しながら
+//! シリアライズし、処理期限（`deadline::current_deadline`）が近づいた時点で打ち切ります。
+//! 打ち切った場合も接続を切らずに、それまでの要素とエラーを含む整形式のJSONを返します。
+//!
+//! ```json
+//! {"data":[...],"count":1200,"truncated":true,"error":{"code":"deadline_exceeded","message":"..."}}
+//! ```
+
+use std::time::{Duration, Instant};
+
+use futures::{Stream, StreamExt};
+use log::warn;
+use serde::Serialize;
+
+use crate::error::Error;
+use super::content_type::ContentType;
+use super::deadline::current_deadline;
+use super::http::Response;
+
+/// 制御を戻すまでにシリアライズする既定の要素数
+pub const DEFAULT_CHUNK_SIZE: usize = 256;
+
+/// 期限の手前で打ち切る既定の余裕（レスポンスの変換・送信に充てる時間）
+pub const DEFAULT_SAFETY_MARGIN: Duration = Duration::from_millis(500);
+
+/// 打ち切り時のエラーコード
+pub const DEADLINE_EXCEEDED_CODE: &str = "deadline_exceeded";
+
+/// 処理期限を考慮してJSON配列のエンベロープを組み立てるシリアライザー
+///
+/// 期限を明示しない場合は、実行環境が設定した現在のリクエストの期限を使用します。
+#[derive(Debug, Clone)]
+pub struct JsonArrayWriter {
+    chunk_size: usize,
+    safety_margin: Duration,
+    deadline: Option<Instant>,
+}
+
+impl Default for JsonArrayWriter {
+    fn default() -> Self {
+        Self {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            safety_margin: DEFAULT_SAFETY_MARGIN,
+            deadline: None,
+        }
+    }
+}
+
+impl JsonArrayWriter {
+    /// 新しいJsonArrayWriterを作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 制御を戻すまでにシリアライズする要素数
+    pub fn chunk_size(mut self, size: usize) -> Self {
+        self.chunk_size = size.max(1);
+        self
+    }
+
+    /// 期限の手前で打ち切る余裕
+    pub fn safety_margin(mut self, margin: Duration) -> Self {
+        self.safety_margin = margin;
+        self
+    }
+
+    /// 期限を明示的に指定（リクエストの期限より優先）
+    pub fn deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// イテレーターの要素をシリアライズしてレスポンスを作成
+    pub async fn write<I, T>(&self, items: I) -> Result<Response, Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Serialize,
+    {
+        self.write_stream(futures::stream::iter(items)).await
+    }
+
+    /// ストリームの要素をシリアライズしてレスポンスを作成
+    ///
+    /// 要素の到着を待つ間に期限を迎えた場合も、その時点で打ち切ります。
+    pub async fn write_stream<S, T>(&self, items: S) -> Result<Response, Error>
+    where
+        S: Stream<Item = T>,
+        T: Serialize,
+    {
+        let cutoff = self
+            .deadline
+            .or_else(current_deadline)
+            .map(|deadline| deadline.checked_sub(self.safety_margin).unwrap_or(deadline));
+        let mut items = std::pin::pin!(items);
+        let mut body = b"{\"data\":[".to_vec();
+        let mut count = 0usize;
+        let mut truncated = false;
+
+        loop {
+            if cutoff.is_some_and(|cutoff| Instant::now() >= cutoff) {
+                truncated = true;
+                break;
+            }
+            let next = match cutoff {
+                Some(cutoff) => match tokio::time::timeout_at(cutoff.into(), items.next()).await {
+                    Ok(next) => next,
+                    Err(_) => {
+                        truncated = true;
+                        break;
+                    }
+                },
+                None => items.next().await,
+            };
+            let Some(item) = next else { break };
+
+            if count > 0 {
+                body.push(b',');
+            }
+            serde_json::to_writer(&mut body, &item)
+                .map_err(|e| Error::ResponseSerializationError(e.to_string()))?;
+            count += 1;
+            if count.is_multiple_of(self.chunk_size) {
+                tokio::task::yield_now().await;
+            }
+        }
+
+        body.extend_from_slice(format!("],\"count\":{},\"truncated\":{}", count, truncated).as_bytes());
+        if truncated {
+            warn!("JSON array response truncated at {} items to meet the request deadline", count);
+            let error = serde_json::json!({
+                "code": DEADLINE_EXCEEDED_CODE,
+                "message": "the response was truncated because the request deadline was reached",
+            });
+            body.extend_from_slice(format!(",\"error\":{}", error).as_bytes());
+        }
+        body.push(b'}');
+
+        let mut res = Response::ok().with_body(body);
+        res.set_content_type(ContentType::json());
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::deadline::with_deadline;
+
+    fn parse(res: &Response) -> serde_json::Value {
+        serde_json::from_slice(res.body.as_ref().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_complete_and_truncated_envelopes() {
+        let res = JsonArrayWriter::new().chunk_size(2).write(0..5).await.unwrap();
+        assert_eq!(res.header("Content-Type"), Some("application/json"));
+        let json = parse(&res);
+        assert_eq!(json["data"], serde_json::json!([0, 1, 2, 3, 4]));
+        assert_eq!(json["count"], 5);
+        assert_eq!(json["truncated"], false);
+        assert!(json.get("error").is_none());
+
+        // 期限切れのリクエストでは要素を含まない打ち切りのエンベロープ
+        let res = with_deadline(Some(Instant::now()), JsonArrayWriter::new().write(0..5)).await.unwrap();
+        let json = parse(&res);
+        assert_eq!(json["data"], serde_json::json!([]));
+        assert_eq!(json["truncated"], true);
+        assert_eq!(json["error"]["code"], DEADLINE_EXCEEDED_CODE);
+    }
+
+    #[tokio::test]
+    async fn test_stalled_stream_is_cut_at_deadline() {
+        let items = futures::stream::iter(0..3).chain(futures::stream::pending());
+        let writer = JsonArrayWriter::new()
+            .safety_margin(Duration::ZERO)
+            .deadline(Instant::now() + Duration::from_millis(50));
+        let json = parse(&writer.write_stream(items).await.unwrap());
+        assert_eq!(json["data"], serde_json::json!([0, 1, 2]));
+        assert_eq!(json["count"], 3);
+        assert_eq!(json["truncated"], true);
+    }
+}
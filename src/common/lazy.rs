@@ -0,0 +1,250 @@
+//! プロセス（Lambdaのサンドボックス）ごとに一度だけ初期化するグローバル値
+//!
+//! DBクライアントやSDKクライアントのように、作成に時間がかかりリクエスト間で共有したい値を
+//! `static`に置くためのヘルパーです。初期化は非同期関数で行い、同時に呼び出された場合も
+//! 一度しか実行しません。初期化に失敗した場合の再試行は`LazyRetry`で指定します。
+//!
+//! ```
+//! use runbridge::common::{AsyncLazy, LazyRetry};
+//! use std::time::Duration;
+//!
+//! struct Client;
+//!
+//! static CLIENT: AsyncLazy<Client> = AsyncLazy::new("client", || Box::pin(async {
+//!     Ok(Client)
+//! }))
+//! .retry(LazyRetry::Backoff { initial: Duration::from_millis(100), max: Duration::from_secs(5) });
+//!
+//! # tokio::runtime::Runtime::new().unwrap().block_on(async {
+//! let client: &Client = CLIENT.get().await.unwrap();
+//! # });
+//! ```
+//!
+//! `RunBridgeBuilder::prewarm`に登録すると、実行環境の起動時（コールドスタート時）に
+//! バックグラウンドで初期化を開始します。
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+
+use crate::error::Error;
+
+/// 初期化関数が返すFuture
+pub type LazyInitFuture<T> = Pin<Box<dyn Future<Output = Result<T, Error>> + Send>>;
+
+/// 初期化に失敗した場合の再試行方針
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LazyRetry {
+    /// 次の呼び出しで再試行（既定）
+    Always,
+    /// 失敗が続くたびに待ち時間を倍にし（上限`max`）、待ち時間中は直前のエラーを返す
+    Backoff {
+        /// 最初の失敗後の待ち時間
+        initial: Duration,
+        /// 待ち時間の上限
+        max: Duration,
+    },
+    /// 再試行せず、以降も同じエラーを返す
+    Never,
+}
+
+struct Failure {
+    message: String,
+    failures: u32,
+    at: Instant,
+}
+
+/// 非同期に一度だけ初期化される値
+pub struct AsyncLazy<T> {
+    name: &'static str,
+    init: fn() -> LazyInitFuture<T>,
+    retry: LazyRetry,
+    value: OnceLock<T>,
+    init_lock: tokio::sync::Mutex<()>,
+    failure: Mutex<Option<Failure>>,
+}
+
+impl<T> AsyncLazy<T> {
+    /// 名前（ログ・エラーメッセージ用）と初期化関数を指定して作成
+    pub const fn new(name: &'static str, init: fn() -> LazyInitFuture<T>) -> Self {
+        Self {
+            name,
+            init,
+            retry: LazyRetry::Always,
+            value: OnceLock::new(),
+            init_lock: tokio::sync::Mutex::const_new(()),
+            failure: Mutex::new(None),
+        }
+    }
+
+    /// 初期化に失敗した場合の再試行方針
+    pub const fn retry(mut self, retry: LazyRetry) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// 名前
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// 初期化済みの場合のみ値を取得（初期化は行わない）
+    pub fn get_if_ready(&self) -> Option<&T> {
+        self.value.get()
+    }
+
+    /// 値を取得（未初期化の場合は初期化し、同時の呼び出しは完了を待つ）
+    ///
+    /// 初期化に失敗した場合は`InternalServerError`を返します。
+    pub async fn get(&self) -> Result<&T, Error> {
+        if let Some(value) = self.value.get() {
+            return Ok(value);
+        }
+
+        let _guard = self.init_lock.lock().await;
+        // 待っている間に他のタスクが初期化を終えている場合がある
+        if let Some(value) = self.value.get() {
+            return Ok(value);
+        }
+        if let Some(message) = self.blocked_by_previous_failure() {
+            return Err(self.error(&message));
+        }
+
+        let started = Instant::now();
+        match (self.init)().await {
+            Ok(value) => {
+                info!("Initialized '{}' in {:?}", self.name, started.elapsed());
+                if let Ok(mut failure) = self.failure.lock() {
+                    *failure = None;
+                }
+                Ok(self.value.get_or_init(|| value))
+            }
+            Err(e) => {
+                let message = e.to_string();
+                warn!("Failed to initialize '{}': {}", self.name, message);
+                if let Ok(mut failure) = self.failure.lock() {
+                    let failures = failure.as_ref().map(|f| f.failures).unwrap_or(0) + 1;
+                    *failure = Some(Failure { message: message.clone(), failures, at: Instant::now() });
+                }
+                Err(self.error(&message))
+            }
+        }
+    }
+
+    /// 再試行方針により、前回の失敗を返すべき場合はそのメッセージ
+    fn blocked_by_previous_failure(&self) -> Option<String> {
+        let failure = self.failure.lock().ok()?;
+        let failure = failure.as_ref()?;
+        match self.retry {
+            LazyRetry::Always => None,
+            LazyRetry::Never => Some(failure.message.clone()),
+            LazyRetry::Backoff { initial, max } => {
+                let wait = initial
+                    .checked_mul(1u32 << (failure.failures - 1).min(16))
+                    .unwrap_or(max)
+                    .min(max);
+                (failure.at.elapsed() < wait).then(|| failure.message.clone())
+            }
+        }
+    }
+
+    fn error(&self, message: &str) -> Error {
+        Error::InternalServerError(format!("Initialization of '{}' failed: {}", self.name, message))
+    }
+}
+
+impl<T: Send + Sync + 'static> AsyncLazy<T> {
+    /// バックグラウンドで初期化を開始（Tokioランタイム外では何もしない）
+    pub fn prewarm(&'static self) {
+        if self.value.get().is_some() {
+            return;
+        }
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(async move {
+                    let _ = self.get().await;
+                });
+            }
+            Err(_) => warn!("Cannot prewarm '{}' outside a Tokio runtime", self.name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    static SHARED: AsyncLazy<usize> = AsyncLazy::new("shared", || {
+        Box::pin(async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Ok(CALLS.fetch_add(1, Ordering::SeqCst) + 1)
+        })
+    });
+
+    #[tokio::test]
+    async fn test_initializes_once_for_concurrent_callers() {
+        let results = futures::future::join_all((0..5).map(|_| SHARED.get())).await;
+        assert!(results.iter().all(|r| *r.as_ref().unwrap() == &1));
+        assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+        assert_eq!(SHARED.get_if_ready(), Some(&1));
+    }
+
+    static FLAKY_CALLS: AtomicUsize = AtomicUsize::new(0);
+    fn flaky() -> LazyInitFuture<&'static str> {
+        Box::pin(async {
+            match FLAKY_CALLS.fetch_add(1, Ordering::SeqCst) {
+                0 => Err(Error::ExternalServiceError("connection refused".to_string())),
+                _ => Ok("ready"),
+            }
+        })
+    }
+
+    static FLAKY_BACKOFF: AsyncLazy<&'static str> = AsyncLazy::new("flaky", flaky).retry(LazyRetry::Backoff {
+        initial: Duration::from_millis(30),
+        max: Duration::from_secs(1),
+    });
+
+    #[tokio::test]
+    async fn test_backoff_retry() {
+        let err = FLAKY_BACKOFF.get().await.unwrap_err();
+        assert_eq!(err.status_code(), 500);
+        assert!(err.to_string().contains("connection refused"));
+
+        // 待ち時間中は初期化を実行せずに直前のエラーを返す
+        assert!(FLAKY_BACKOFF.get().await.is_err());
+        assert_eq!(FLAKY_CALLS.load(Ordering::SeqCst), 1);
+
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        assert_eq!(*FLAKY_BACKOFF.get().await.unwrap(), "ready");
+        assert_eq!(FLAKY_CALLS.load(Ordering::SeqCst), 2);
+    }
+
+    static NEVER: AsyncLazy<u8> = AsyncLazy::new("never", || {
+        Box::pin(async { Err(Error::ConfigurationError("missing DATABASE_URL".to_string())) })
+    })
+    .retry(LazyRetry::Never);
+
+    static PREWARMED: AsyncLazy<u8> = AsyncLazy::new("prewarmed", || Box::pin(async { Ok(7) }));
+
+    #[tokio::test]
+    async fn test_never_retry_and_prewarm() {
+        assert!(NEVER.get().await.is_err());
+        let err = NEVER.get().await.unwrap_err();
+        assert!(err.to_string().contains("missing DATABASE_URL"));
+
+        // 起動時に登録済みの値の初期化が始まる
+        crate::RunBridge::builder().prewarm(&PREWARMED).build().launch().unwrap();
+        for _ in 0..50 {
+            if PREWARMED.get_if_ready().is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        assert_eq!(PREWARMED.get_if_ready(), Some(&7));
+    }
+}
@@ -0,0 +1,172 @@
+//! `Link`ヘッダーによるリソースのヒント（preload・preconnect等）
+//!
+//! `Response::with_link`でヒントを`Link`ヘッダーに追加します。
+//!
+//! 103 Early Hintsについて: 対応する全てのランタイムで中間レスポンスを送出できないため
+//! （actix-webは`100 Continue`以外の1xxを送る手段を持たず、Lambda・CGI・towerは1つの
+//! レスポンスしか返せない）、ヒントは最終レスポンスの`Link`ヘッダーとして返します。
+//! CloudflareなどのCDNやロードバランサーは、この`Link`ヘッダーから103を生成できます。
+//!
+//! ```
+//! use runbridge::common::{LinkHint, Response};
+//!
+//! let res = Response::ok()
+//!     .with_link(LinkHint::preload("/app.css", "style"))
+//!     .with_link(LinkHint::preload("/font.woff2", "font").content_type("font/woff2").crossorigin());
+//! assert_eq!(
+//!     res.header("Link"),
+//!     Some(r#"</app.css>; rel=preload; as=style, </font.woff2>; rel=preload; as=font; type="font/woff2"; crossorigin"#)
+//! );
+//! ```
+
+use std::fmt;
+
+/// リンクの関係（`rel`）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkRel {
+    /// 現在のページで使うリソースを先に取得する
+    Preload,
+    /// ESモジュールを先に取得する
+    ModulePreload,
+    /// 接続（DNS・TCP・TLS）を先に確立する
+    Preconnect,
+    /// DNSの名前解決だけを先に行う
+    DnsPrefetch,
+    /// 次のページで使うリソースを空き時間に取得する
+    Prefetch,
+}
+
+impl fmt::Display for LinkRel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rel = match self {
+            LinkRel::Preload => "preload",
+            LinkRel::ModulePreload => "modulepreload",
+            LinkRel::Preconnect => "preconnect",
+            LinkRel::DnsPrefetch => "dns-prefetch",
+            LinkRel::Prefetch => "prefetch",
+        };
+        f.write_str(rel)
+    }
+}
+
+/// `Link`ヘッダーの1件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkHint {
+    url: String,
+    rel: LinkRel,
+    as_type: Option<String>,
+    content_type: Option<String>,
+    crossorigin: Option<&'static str>,
+}
+
+impl LinkHint {
+    /// 関係を指定して作成
+    pub fn new(url: impl Into<String>, rel: LinkRel) -> Self {
+        Self { url: url.into(), rel, as_type: None, content_type: None, crossorigin: None }
+    }
+
+    /// `rel=preload`（`as_type`は`style`・`script`・`font`・`image`・`fetch`など）
+    pub fn preload(url: impl Into<String>, as_type: impl Into<String>) -> Self {
+        let mut hint = Self::new(url, LinkRel::Preload);
+        hint.as_type = Some(as_type.into());
+        hint
+    }
+
+    /// `rel=modulepreload`
+    pub fn module_preload(url: impl Into<String>) -> Self {
+        Self::new(url, LinkRel::ModulePreload)
+    }
+
+    /// `rel=preconnect`（例: `https://cdn.example.com`）
+    pub fn preconnect(origin: impl Into<String>) -> Self {
+        Self::new(origin, LinkRel::Preconnect)
+    }
+
+    /// `rel=dns-prefetch`
+    pub fn dns_prefetch(origin: impl Into<String>) -> Self {
+        Self::new(origin, LinkRel::DnsPrefetch)
+    }
+
+    /// `rel=prefetch`
+    pub fn prefetch(url: impl Into<String>) -> Self {
+        Self::new(url, LinkRel::Prefetch)
+    }
+
+    /// リソースのMIMEタイプ（`type`、ブラウザが対応しない形式の取得を避ける）
+    pub fn content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    /// CORSで取得する（`crossorigin`、フォントは常に必要）
+    pub fn crossorigin(mut self) -> Self {
+        self.crossorigin = Some("");
+        self
+    }
+
+    /// 資格情報付きのCORSで取得する（`crossorigin=use-credentials`）
+    pub fn crossorigin_use_credentials(mut self) -> Self {
+        self.crossorigin = Some("use-credentials");
+        self
+    }
+
+    /// 関係
+    pub fn rel(&self) -> LinkRel {
+        self.rel
+    }
+
+    /// `Link`ヘッダーの値（1件分）
+    pub fn to_header_value(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for LinkHint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // URL参照の終端と区切りを壊す文字はエンコード
+        let url = self.url.replace('<', "%3C").replace('>', "%3E");
+        write!(f, "<{}>; rel={}", url, self.rel)?;
+        if let Some(as_type) = &self.as_type {
+            write!(f, "; as={}", as_type)?;
+        }
+        if let Some(content_type) = &self.content_type {
+            write!(f, "; type=\"{}\"", content_type.replace(['"', '\\'], ""))?;
+        }
+        match self.crossorigin {
+            Some("") => f.write_str("; crossorigin")?,
+            Some(value) => write!(f, "; crossorigin={}", value)?,
+            None => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Response;
+
+    #[test]
+    fn test_link_hint_values() {
+        assert_eq!(LinkHint::preload("/app.js", "script").to_string(), "</app.js>; rel=preload; as=script");
+        assert_eq!(LinkHint::module_preload("/main.mjs").to_string(), "</main.mjs>; rel=modulepreload");
+        assert_eq!(
+            LinkHint::preconnect("https://cdn.example.com").crossorigin_use_credentials().to_string(),
+            "<https://cdn.example.com>; rel=preconnect; crossorigin=use-credentials"
+        );
+        assert_eq!(LinkHint::prefetch("/next?a=<b>").to_string(), "</next?a=%3Cb%3E>; rel=prefetch");
+        assert_eq!(LinkHint::dns_prefetch("//cdn.example.com").rel(), LinkRel::DnsPrefetch);
+    }
+
+    #[test]
+    fn test_response_with_link_appends() {
+        let res = Response::ok()
+            .with_header("link", "</existing>; rel=preload; as=image")
+            .with_link(LinkHint::preconnect("https://cdn.example.com"));
+        assert_eq!(
+            res.header("Link"),
+            Some("</existing>; rel=preload; as=image, <https://cdn.example.com>; rel=preconnect")
+        );
+        assert_eq!(res.headers.len(), Response::ok().headers.len() + 1);
+    }
+}
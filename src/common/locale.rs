@@ -0,0 +1,306 @@
+//! リクエスト単位のロケール・タイムゾーン解決
+
+use async_trait::async_trait;
+use crate::error::Error;
+use super::http::{Request, Response};
+use super::traits::Middleware;
+
+/// RequestContextに格納する際のキー
+pub const LOCALE_CONTEXT_KEY: &str = "runbridge.locale";
+
+/// 解決済みのロケール情報
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Locale {
+    /// 言語タグ（例: "ja", "en-US"）
+    pub language: String,
+    /// IANAタイムゾーン名（例: "Asia/Tokyo"）
+    pub timezone: Option<String>,
+}
+
+/// ロケール/タイムゾーンの取得元
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LocaleSource {
+    /// クエリパラメータ（パラメータ名）
+    Query(String),
+    /// Cookie（Cookie名）
+    Cookie(String),
+    /// 任意のリクエストヘッダー（ヘッダー名）
+    Header(String),
+    /// Accept-Languageヘッダー（言語のみ対象）
+    AcceptLanguage,
+}
+
+/// ロケールリゾルバー（ミドルウェアとして登録するとRequestContextに`Locale`を格納）
+#[derive(Debug, Clone)]
+pub struct LocaleResolver {
+    language_sources: Vec<LocaleSource>,
+    timezone_sources: Vec<LocaleSource>,
+    supported_languages: Vec<String>,
+    default_language: String,
+    default_timezone: Option<String>,
+}
+
+impl LocaleResolver {
+    /// 既定の取得順（クエリ `lang` → Cookie `lang` → Accept-Language）でリゾルバーを作成
+    pub fn new(default_language: impl Into<String>) -> Self {
+        Self {
+            language_sources: vec![
+                LocaleSource::Query("lang".to_string()),
+                LocaleSource::Cookie("lang".to_string()),
+                LocaleSource::AcceptLanguage,
+            ],
+            timezone_sources: vec![
+                LocaleSource::Query("tz".to_string()),
+                LocaleSource::Cookie("tz".to_string()),
+                LocaleSource::Header("time-zone".to_string()),
+            ],
+            supported_languages: Vec::new(),
+            default_language: default_language.into(),
+            default_timezone: None,
+        }
+    }
+
+    /// 言語の取得元と優先順位を設定
+    pub fn language_sources(mut self, sources: Vec<LocaleSource>) -> Self {
+        self.language_sources = sources;
+        self
+    }
+
+    /// タイムゾーンの取得元と優先順位を設定
+    pub fn timezone_sources(mut self, sources: Vec<LocaleSource>) -> Self {
+        self.timezone_sources = sources;
+        self
+    }
+
+    /// サポートする言語を設定（空の場合はすべて受け入れる）
+    pub fn supported_languages<I, S>(mut self, languages: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.supported_languages = languages.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// 既定のタイムゾーンを設定
+    pub fn default_timezone(mut self, timezone: impl Into<String>) -> Self {
+        self.default_timezone = Some(timezone.into());
+        self
+    }
+
+    /// リクエストからロケールを解決
+    pub fn resolve(&self, req: &Request) -> Locale {
+        let language = self
+            .language_sources
+            .iter()
+            .find_map(|source| self.language_from(source, req))
+            .unwrap_or_else(|| self.default_language.clone());
+
+        let timezone = self
+            .timezone_sources
+            .iter()
+            .find_map(|source| timezone_from(source, req))
+            .or_else(|| self.default_timezone.clone());
+
+        Locale { language, timezone }
+    }
+
+    fn language_from(&self, source: &LocaleSource, req: &Request) -> Option<String> {
+        match source {
+            LocaleSource::AcceptLanguage => {
+                let header = req.headers.get("accept-language")?;
+                parse_accept_language(header)
+                    .into_iter()
+                    .find_map(|tag| self.match_supported(&tag))
+            }
+            other => raw_value(other, req).and_then(|v| self.match_supported(v.trim())),
+        }
+    }
+
+    /// サポート言語と照合（完全一致 → 主言語タグでの一致の順）
+    fn match_supported(&self, tag: &str) -> Option<String> {
+        if !is_language_tag_valid(tag) {
+            return None;
+        }
+        if self.supported_languages.is_empty() {
+            return Some(tag.to_string());
+        }
+        if let Some(exact) = self
+            .supported_languages
+            .iter()
+            .find(|s| s.eq_ignore_ascii_case(tag))
+        {
+            return Some(exact.clone());
+        }
+        let primary = tag.split('-').next().unwrap_or(tag);
+        self.supported_languages
+            .iter()
+            .find(|s| s.split('-').next().unwrap_or(s).eq_ignore_ascii_case(primary))
+            .cloned()
+    }
+}
+
+#[async_trait]
+impl Middleware for LocaleResolver {
+    async fn pre_process(&self, mut req: Request) -> Result<Request, Error> {
+        let locale = self.resolve(&req);
+        log::debug!("Resolved locale: {:?}", locale);
+        req.context_mut().set(LOCALE_CONTEXT_KEY, locale);
+        Ok(req)
+    }
+
+    async fn post_process(&self, res: Response) -> Result<Response, Error> {
+        Ok(res)
+    }
+}
+
+impl Request {
+    /// LocaleResolverにより解決されたロケールを取得
+    pub fn locale(&self) -> Option<&Locale> {
+        self.context().get::<Locale>(LOCALE_CONTEXT_KEY)
+    }
+}
+
+/// 取得元から生の値を取り出す（Accept-Languageは対象外）
+fn raw_value<'a>(source: &LocaleSource, req: &'a Request) -> Option<&'a str> {
+    let value = match source {
+        LocaleSource::Query(name) => req.query_params.get(name).map(|s| s.as_str()),
+        LocaleSource::Header(name) => req.headers.get(&name.to_ascii_lowercase()).map(|s| s.as_str()),
+        LocaleSource::Cookie(name) => req
+            .headers
+            .get("cookie")
+            .and_then(|header| cookie_value(header, name)),
+        LocaleSource::AcceptLanguage => None,
+    };
+    value.filter(|v| !v.trim().is_empty())
+}
+
+fn timezone_from(source: &LocaleSource, req: &Request) -> Option<String> {
+    raw_value(source, req)
+        .map(|v| v.trim())
+        .filter(|v| is_timezone_valid(v))
+        .map(|v| v.to_string())
+}
+
+/// Cookieヘッダーから指定名の値を取り出す
+fn cookie_value<'a>(header: &'a str, name: &str) -> Option<&'a str> {
+    header.split(';').find_map(|pair| {
+        let mut parts = pair.trim().splitn(2, '=');
+        match (parts.next(), parts.next()) {
+            (Some(k), Some(v)) if k == name => Some(v),
+            _ => None,
+        }
+    })
+}
+
+/// Accept-Languageをq値の降順に並べた言語タグのリストに変換
+fn parse_accept_language(header: &str) -> Vec<String> {
+    let mut entries: Vec<(String, f32)> = header
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.trim().split(';');
+            let tag = parts.next()?.trim();
+            if tag.is_empty() || tag == "*" {
+                return None;
+            }
+            let q = parts
+                .find_map(|p| p.trim().strip_prefix("q="))
+                .and_then(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if q <= 0.0 {
+                return None;
+            }
+            Some((tag.to_string(), q))
+        })
+        .collect();
+    // 安定ソートで同一q値の場合はヘッダー記載順を維持
+    entries.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    entries.into_iter().map(|(tag, _)| tag).collect()
+}
+
+/// 言語タグとして妥当か（英数字とハイフンのみ、長さ制限付き）
+fn is_language_tag_valid(tag: &str) -> bool {
+    !tag.is_empty()
+        && tag.len() <= 35
+        && tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// タイムゾーン名として妥当か（IANA名の文字種に限定）
+fn is_timezone_valid(tz: &str) -> bool {
+    !tz.is_empty()
+        && tz.len() <= 64
+        && tz
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | '_' | '-' | '+'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Method;
+
+    fn request() -> Request {
+        Request::new(Method::GET, "/".to_string())
+    }
+
+    #[test]
+    fn test_accept_language_respects_quality() {
+        let resolver = LocaleResolver::new("en").supported_languages(["en", "ja", "fr"]);
+        let req = request().with_header("Accept-Language", "de;q=0.9, fr;q=0.5, ja-JP;q=0.8");
+
+        assert_eq!(resolver.resolve(&req).language, "ja");
+    }
+
+    #[test]
+    fn test_query_overrides_cookie_and_header() {
+        let resolver = LocaleResolver::new("en");
+        let req = request()
+            .with_query_param("lang", "fr")
+            .with_header("Cookie", "session=abc; lang=ja")
+            .with_header("Accept-Language", "de");
+
+        assert_eq!(resolver.resolve(&req).language, "fr");
+
+        let req = request()
+            .with_header("Cookie", "session=abc; lang=ja")
+            .with_header("Accept-Language", "de");
+        assert_eq!(resolver.resolve(&req).language, "ja");
+    }
+
+    #[test]
+    fn test_custom_source_order_and_fallback() {
+        let resolver = LocaleResolver::new("en")
+            .supported_languages(["en", "ja"])
+            .language_sources(vec![LocaleSource::AcceptLanguage, LocaleSource::Query("lang".into())]);
+        let req = request()
+            .with_query_param("lang", "ja")
+            .with_header("Accept-Language", "ko");
+
+        // Accept-Languageはサポート外のため次の取得元（クエリ）が使われる
+        assert_eq!(resolver.resolve(&req).language, "ja");
+
+        // どこからも得られなければ既定値
+        assert_eq!(resolver.resolve(&request()).language, "en");
+    }
+
+    #[test]
+    fn test_timezone_resolution_and_validation() {
+        let resolver = LocaleResolver::new("en").default_timezone("UTC");
+        let req = request().with_query_param("tz", "Asia/Tokyo");
+        assert_eq!(resolver.resolve(&req).timezone.as_deref(), Some("Asia/Tokyo"));
+
+        let req = request().with_query_param("tz", "<script>");
+        assert_eq!(resolver.resolve(&req).timezone.as_deref(), Some("UTC"));
+    }
+
+    #[tokio::test]
+    async fn test_middleware_populates_context() {
+        let resolver = LocaleResolver::new("en");
+        let req = request().with_header("Accept-Language", "ja-JP,ja;q=0.9");
+        let req = resolver.pre_process(req).await.unwrap();
+
+        let locale = req.locale().expect("locale should be set");
+        assert_eq!(locale.language, "ja-JP");
+        assert_eq!(locale.timezone, None);
+    }
+}
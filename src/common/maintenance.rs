@@ -0,0 +1,387 @@
+//! 計画メンテナンス中のリクエストを503で応答するミドルウェア
+//!
+//! メンテナンス時間帯は開始・終了日時、またはcron形式の開始時刻と継続時間で指定します
+//! （時刻はすべてUTC）。デプロイせずに計画メンテナンスを告知するためのものです。
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use log::debug;
+
+use crate::error::Error;
+use super::error_response::error_response;
+use super::http::{Request, Response};
+use super::http_date::RetryAfter;
+use super::traits::{AroundMiddleware, Next};
+
+/// 繰り返しのメンテナンスの最大継続時間（判定時の走査範囲の上限）
+const MAX_RECURRING_DURATION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// cron形式の1フィールド（許可する値のビット集合）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CronField {
+    bits: u64,
+    /// `*`（全ての値）かどうか（日と曜日の組み合わせ判定に使用）
+    any: bool,
+}
+
+impl CronField {
+    fn parse(field: &str, min: u32, max: u32) -> Result<Self, String> {
+        let mut bits = 0u64;
+        for part in field.split(',') {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => {
+                    let step = step.parse::<u32>().ok().filter(|s| *s > 0)
+                        .ok_or_else(|| format!("invalid step '{}'", part))?;
+                    (range, step)
+                }
+                None => (part, 1),
+            };
+            let (start, end) = if range == "*" {
+                (min, max)
+            } else if let Some((start, end)) = range.split_once('-') {
+                (parse_cron_value(start, min, max)?, parse_cron_value(end, min, max)?)
+            } else {
+                let value = parse_cron_value(range, min, max)?;
+                // `5/15`は5から最大値まで15刻み
+                (value, if part.contains('/') { max } else { value })
+            };
+            if start > end {
+                return Err(format!("invalid range '{}'", part));
+            }
+            for value in (start..=end).step_by(step as usize) {
+                bits |= 1 << value;
+            }
+        }
+        Ok(Self { bits, any: field == "*" })
+    }
+
+    fn contains(&self, value: u32) -> bool {
+        self.bits & (1 << value) != 0
+    }
+}
+
+fn parse_cron_value(value: &str, min: u32, max: u32) -> Result<u32, String> {
+    value
+        .parse::<u32>()
+        .ok()
+        .filter(|v| (min..=max).contains(v))
+        .ok_or_else(|| format!("value '{}' out of range {}-{}", value, min, max))
+}
+
+/// cron形式（`分 時 日 月 曜日`）の開始時刻
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CronSchedule {
+    minute: CronField,
+    hour: CronField,
+    day_of_month: CronField,
+    month: CronField,
+    day_of_week: CronField,
+}
+
+impl CronSchedule {
+    fn parse(spec: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = spec.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!("expected 5 fields, got {}", fields.len()));
+        }
+        let mut day_of_week = CronField::parse(fields[4], 0, 7)?;
+        // 7も日曜日として扱う
+        if day_of_week.contains(7) {
+            day_of_week.bits |= 1;
+        }
+        Ok(Self {
+            minute: CronField::parse(fields[0], 0, 59)?,
+            hour: CronField::parse(fields[1], 0, 23)?,
+            day_of_month: CronField::parse(fields[2], 1, 31)?,
+            month: CronField::parse(fields[3], 1, 12)?,
+            day_of_week,
+        })
+    }
+
+    fn matches(&self, time: &DateTime<Utc>) -> bool {
+        let dom = self.day_of_month.contains(time.day());
+        let dow = self.day_of_week.contains(time.weekday().num_days_from_sunday());
+        // 日と曜日の両方が指定された場合はいずれかに一致すればよい（cronと同じ規則）
+        let day = match (self.day_of_month.any, self.day_of_week.any) {
+            (false, false) => dom || dow,
+            _ => dom && dow,
+        };
+        day && self.minute.contains(time.minute())
+            && self.hour.contains(time.hour())
+            && self.month.contains(time.month())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum WindowKind {
+    Fixed { start: DateTime<Utc>, end: DateTime<Utc> },
+    Recurring { schedule: CronSchedule, duration: Duration },
+}
+
+/// メンテナンス時間帯
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaintenanceWindow {
+    kind: WindowKind,
+}
+
+impl MaintenanceWindow {
+    /// 開始・終了日時を指定して作成（開始を含み、終了を含まない）
+    pub fn between(start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        Self { kind: WindowKind::Fixed { start, end } }
+    }
+
+    /// cron形式（`分 時 日 月 曜日`、UTC）の開始時刻と継続時間を指定して作成
+    ///
+    /// 各フィールドは`*`・数値・範囲（`1-5`）・リスト（`1,3`）・刻み（`*/15`）に対応します。
+    /// 継続時間は1分以上7日以下です。
+    pub fn recurring(spec: &str, duration: Duration) -> Result<Self, Error> {
+        if duration < Duration::from_secs(60) || duration > MAX_RECURRING_DURATION {
+            return Err(Error::ConfigurationError(format!(
+                "Maintenance window duration must be between 1 minute and 7 days: {:?}",
+                duration
+            )));
+        }
+        let schedule = CronSchedule::parse(spec).map_err(|e| {
+            Error::ConfigurationError(format!("Invalid maintenance schedule '{}': {}", spec, e))
+        })?;
+        Ok(Self { kind: WindowKind::Recurring { schedule, duration } })
+    }
+
+    /// 文字列から作成
+    ///
+    /// - `開始/終了`（RFC 3339）: `2026-10-20T01:00:00Z/2026-10-20T03:00:00Z`
+    /// - cron形式と継続時間（分）: `0 2 * * 0 90`（毎週日曜2:00から90分）
+    pub fn parse(spec: &str) -> Result<Self, Error> {
+        let spec = spec.trim();
+        // 空白を含まない場合は日時の範囲
+        if let Some((start, end)) = spec.split_once('/').filter(|_| !spec.contains(char::is_whitespace)) {
+            let parse = |value: &str| {
+                DateTime::parse_from_rfc3339(value.trim())
+                    .map(|t| t.with_timezone(&Utc))
+                    .map_err(|e| Error::ConfigurationError(format!("Invalid maintenance window '{}': {}", spec, e)))
+            };
+            return Ok(Self::between(parse(start)?, parse(end)?));
+        }
+        match spec.rsplit_once(char::is_whitespace) {
+            Some((schedule, minutes)) => {
+                let minutes = minutes.parse::<u64>().map_err(|_| {
+                    Error::ConfigurationError(format!("Invalid maintenance window duration in '{}'", spec))
+                })?;
+                Self::recurring(schedule, Duration::from_secs(minutes * 60))
+            }
+            None => Err(Error::ConfigurationError(format!("Invalid maintenance window '{}'", spec))),
+        }
+    }
+
+    /// 指定した時刻がメンテナンス中の場合、その終了時刻を返す
+    pub fn active_until(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match &self.kind {
+            WindowKind::Fixed { start, end } => (*start <= now && now < *end).then_some(*end),
+            WindowKind::Recurring { schedule, duration } => {
+                let duration = chrono::Duration::from_std(*duration).ok()?;
+                let minute_start = now.with_second(0)?.with_nanosecond(0)?;
+                // 直近の開始時刻から遡り、継続時間内に開始したものを探す（最も新しい開始を優先）
+                (0..=duration.num_minutes())
+                    .map(|offset| minute_start - chrono::Duration::minutes(offset))
+                    .find(|start| schedule.matches(start) && now < *start + duration)
+                    .map(|start| start + duration)
+            }
+        }
+    }
+}
+
+/// メンテナンス時間帯の間、許可リスト以外のリクエストに503（`Retry-After`付き）を返すAroundMiddleware
+///
+/// ヘルスチェック等のメンテナンス中も応答させたいルートは、パスのプレフィックスまたはルート名で許可します。
+pub struct MaintenanceMiddleware {
+    windows: Vec<MaintenanceWindow>,
+    allowed_paths: Vec<String>,
+    allowed_routes: Vec<String>,
+}
+
+impl MaintenanceMiddleware {
+    /// メンテナンス時間帯を指定して作成
+    pub fn new(window: MaintenanceWindow) -> Self {
+        Self {
+            windows: vec![window],
+            allowed_paths: Vec::new(),
+            allowed_routes: Vec::new(),
+        }
+    }
+
+    /// 環境変数`RUNBRIDGE_MAINTENANCE_WINDOW`から作成（`;`区切りで複数指定可、未設定・空の場合はNone）
+    ///
+    /// 書式は`MaintenanceWindow::parse`を参照してください。
+    pub fn from_env() -> Result<Option<Self>, Error> {
+        let spec = match std::env::var("RUNBRIDGE_MAINTENANCE_WINDOW") {
+            Ok(spec) if !spec.trim().is_empty() => spec,
+            _ => return Ok(None),
+        };
+        let mut windows = spec
+            .split(';')
+            .filter(|s| !s.trim().is_empty())
+            .map(MaintenanceWindow::parse)
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter();
+        match windows.next() {
+            Some(first) => Ok(Some(windows.fold(Self::new(first), Self::window))),
+            None => Ok(None),
+        }
+    }
+
+    /// メンテナンス時間帯を追加
+    pub fn window(mut self, window: MaintenanceWindow) -> Self {
+        self.windows.push(window);
+        self
+    }
+
+    /// メンテナンス中も処理するパスのプレフィックスを追加（`/healthz`は`/healthz/live`にもマッチ）
+    pub fn allow_path(mut self, prefix: impl Into<String>) -> Self {
+        self.allowed_paths.push(prefix.into());
+        self
+    }
+
+    /// メンテナンス中も処理するルート名を追加
+    pub fn allow_route(mut self, name: impl Into<String>) -> Self {
+        self.allowed_routes.push(name.into());
+        self
+    }
+
+    /// 指定した時刻がメンテナンス中の場合、最も遅い終了時刻を返す
+    pub fn active_until(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.windows.iter().filter_map(|w| w.active_until(now)).max()
+    }
+
+    fn is_allowed(&self, req: &Request) -> bool {
+        let path_allowed = self.allowed_paths.iter().any(|prefix| {
+            req.path
+                .strip_prefix(prefix.trim_end_matches('/'))
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        });
+        path_allowed
+            || req
+                .matched_route()
+                .and_then(|route| route.name.as_deref())
+                .is_some_and(|name| self.allowed_routes.iter().any(|allowed| allowed == name))
+    }
+
+    /// メンテナンス中の応答（`Retry-After`は終了までの秒数を切り上げたもの）
+    fn unavailable(until: DateTime<Utc>, now: DateTime<Utc>) -> Response {
+        let remaining = (until - now).to_std().unwrap_or_default();
+        let seconds = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+        error_response(503, Some("scheduled maintenance"))
+            .with_retry_after(RetryAfter::Delay(Duration::from_secs(seconds.max(1))))
+            .with_header("Cache-Control", "no-store")
+    }
+}
+
+#[async_trait]
+impl AroundMiddleware for MaintenanceMiddleware {
+    async fn around(&self, req: Request, next: Next<'_>) -> Result<Response, Error> {
+        let now = Utc::now();
+        match self.active_until(now) {
+            Some(until) if !self.is_allowed(&req) => {
+                debug!("Rejecting {} {} during maintenance until {}", req.method, req.path, until.to_rfc3339());
+                Ok(Self::unavailable(until, now))
+            }
+            Some(_) => {
+                debug!("Serving allowlisted route during maintenance: {} {}", req.method, req.path);
+                next.run(req).await
+            }
+            None => next.run(req).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Method;
+    use chrono::TimeZone;
+
+    fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+
+    #[test]
+    fn test_window_parse_and_active() {
+        let fixed = MaintenanceWindow::parse("2026-10-20T01:00:00Z/2026-10-20T03:00:00+00:00").unwrap();
+        assert_eq!(fixed.active_until(at(2026, 10, 20, 2, 0)), Some(at(2026, 10, 20, 3, 0)));
+        assert_eq!(fixed.active_until(at(2026, 10, 20, 3, 0)), None);
+
+        // 毎週日曜2:00から90分（2026-10-18は日曜日）
+        let weekly = MaintenanceWindow::parse("0 2 * * 0 90").unwrap();
+        assert_eq!(weekly.active_until(at(2026, 10, 18, 3, 15)), Some(at(2026, 10, 18, 3, 30)));
+        assert_eq!(weekly.active_until(at(2026, 10, 18, 3, 30)), None);
+        assert_eq!(weekly.active_until(at(2026, 10, 19, 2, 30)), None);
+        // 7も日曜日、範囲・刻みにも対応
+        assert!(MaintenanceWindow::parse("0 2 * * 7 90").unwrap().active_until(at(2026, 10, 18, 2, 0)).is_some());
+        let business = MaintenanceWindow::parse("*/30 9-17 * * 1-5 5").unwrap();
+        assert!(business.active_until(at(2026, 10, 19, 9, 34)).is_some());
+        assert!(business.active_until(at(2026, 10, 19, 9, 35)).is_none());
+        // 日をまたぐ時間帯
+        let nightly = MaintenanceWindow::recurring("30 23 * * *", Duration::from_secs(60 * 60)).unwrap();
+        assert_eq!(nightly.active_until(at(2026, 10, 20, 0, 10)), Some(at(2026, 10, 20, 0, 30)));
+
+        for invalid in ["", "0 2 * * 0", "60 2 * * 0 30", "0 2 * * 0 0", "0 2 * * 0 20000", "2026-10-20T01:00:00Z/later"] {
+            assert!(MaintenanceWindow::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_from_env() {
+        temp_env::with_var("RUNBRIDGE_MAINTENANCE_WINDOW", Some("0 2 * * 0 60; 0 3 1 * * 30"), || {
+            let middleware = MaintenanceMiddleware::from_env().unwrap().unwrap();
+            assert_eq!(middleware.windows.len(), 2);
+            assert!(middleware.active_until(at(2026, 11, 1, 3, 10)).is_some());
+        });
+        temp_env::with_var("RUNBRIDGE_MAINTENANCE_WINDOW", Some("bogus"), || {
+            assert!(MaintenanceMiddleware::from_env().is_err());
+        });
+        temp_env::with_var_unset("RUNBRIDGE_MAINTENANCE_WINDOW", || {
+            assert!(MaintenanceMiddleware::from_env().unwrap().is_none());
+        });
+    }
+
+    #[test]
+    fn test_unavailable_uses_error_format() {
+        temp_env::with_var("RUNBRIDGE_ERROR_FORMAT", Some("json"), || {
+            let now = at(2026, 10, 20, 2, 0);
+            let res = MaintenanceMiddleware::unavailable(now + chrono::Duration::seconds(30), now);
+            assert_eq!(res.status, 503);
+            assert_eq!(res.header("Content-Type"), Some("application/json"));
+            assert_eq!(res.header("Retry-After"), Some("30"));
+            assert_eq!(res.header("Cache-Control"), Some("no-store"));
+            let body: serde_json::Value = serde_json::from_slice(res.body.as_ref().unwrap()).unwrap();
+            assert_eq!(body["status"], 503);
+        });
+    }
+
+    #[tokio::test]
+    async fn test_middleware_allowlist() {
+        use crate::handler::{get, HandlerExt};
+        use crate::RunBridge;
+
+        fn ok(_req: Request) -> Result<&'static str, Error> {
+            Ok("ok")
+        }
+        let now = Utc::now();
+        let window = MaintenanceWindow::between(now - chrono::Duration::minutes(1), now + chrono::Duration::seconds(90));
+        let app = RunBridge::builder()
+            .handler(get("^/items$", ok))
+            .handler(get("^/healthz/live$", ok))
+            .handler(get("^/status$", ok).name("status"))
+            .around(MaintenanceMiddleware::new(window).allow_path("/healthz/").allow_route("status"))
+            .build();
+        let request = |path: &str| Request::new(Method::GET, path.to_string());
+
+        let res = crate::testing::dispatch(&app, request("/items")).await;
+        assert_eq!(res.status, 503);
+        let retry_after: u64 = res.header("Retry-After").unwrap().parse().unwrap();
+        assert!((1..=90).contains(&retry_after));
+        assert_eq!(crate::testing::dispatch(&app, request("/healthz/live")).await.status, 200);
+        assert_eq!(crate::testing::dispatch(&app, request("/status")).await.status, 200);
+    }
+}
@@ -0,0 +1,110 @@
+//! 受け付けるHTTPメソッドの制限（ルーティング前に適用）
+//!
+//! CGIのようにWebサーバーがすべてのメソッドを転送してくる環境で、
+//! TRACE/CONNECTや任意の拡張メソッドをハンドラーに到達させないために使用します。
+
+use std::env;
+
+use log::warn;
+
+use super::http::{Method, Response};
+
+/// 既定で受け付けるメソッド（`Method`で表現できるすべてのメソッド）
+const ALL_METHODS: [Method; 7] = [
+    Method::GET,
+    Method::POST,
+    Method::PUT,
+    Method::DELETE,
+    Method::PATCH,
+    Method::HEAD,
+    Method::OPTIONS,
+];
+
+/// 受け付けるHTTPメソッドの一覧を取得する
+/// 優先順位: 環境変数 `RUNBRIDGE_ALLOWED_METHODS`（カンマ区切り、例: `GET,POST`） -> デフォルト すべて
+pub fn get_allowed_methods() -> Vec<Method> {
+    let value = match env::var("RUNBRIDGE_ALLOWED_METHODS") {
+        Ok(value) => value,
+        Err(_) => return ALL_METHODS.to_vec(),
+    };
+
+    let mut methods = Vec::new();
+    for name in value.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        match Method::from_str(name) {
+            Some(method) if !methods.contains(&method) => methods.push(method),
+            Some(_) => {}
+            None => warn!("Ignoring unsupported method in RUNBRIDGE_ALLOWED_METHODS: {}", name),
+        }
+    }
+
+    if methods.is_empty() {
+        warn!("RUNBRIDGE_ALLOWED_METHODS has no valid methods, allowing all methods");
+        return ALL_METHODS.to_vec();
+    }
+    methods
+}
+
+/// 受信したメソッド文字列を検査し、受け付ける場合はMethodを返す
+///
+/// - 未対応のメソッド（TRACE、CONNECT、任意の拡張メソッド等）: 501 Not Implemented
+/// - 対応しているが許可されていないメソッド: 405 Method Not Allowed（`Allow`ヘッダー付き）
+pub fn check_method(method: &str) -> Result<Method, Response> {
+    check_method_with(method, &get_allowed_methods())
+}
+
+fn check_method_with(method: &str, allowed: &[Method]) -> Result<Method, Response> {
+    let parsed = match Method::from_str(method) {
+        Some(parsed) => parsed,
+        None => {
+            warn!("Rejected unsupported HTTP method: {:?}", method);
+            return Err(Response::error(501));
+        }
+    };
+
+    if allowed.contains(&parsed) {
+        return Ok(parsed);
+    }
+
+    warn!("Rejected disallowed HTTP method: {}", parsed);
+    let allow = allowed
+        .iter()
+        .map(|m| m.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    Err(Response::error(405).with_header("Allow", allow))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temp_env::with_var;
+
+    #[test]
+    fn test_get_allowed_methods_from_env() {
+        with_var("RUNBRIDGE_ALLOWED_METHODS", None::<&str>, || {
+            assert_eq!(get_allowed_methods(), ALL_METHODS.to_vec());
+        });
+        with_var("RUNBRIDGE_ALLOWED_METHODS", Some(" get, POST,TRACE,get "), || {
+            assert_eq!(get_allowed_methods(), vec![Method::GET, Method::POST]);
+        });
+        with_var("RUNBRIDGE_ALLOWED_METHODS", Some("TRACE"), || {
+            assert_eq!(get_allowed_methods(), ALL_METHODS.to_vec());
+        });
+    }
+
+    #[test]
+    fn test_check_method() {
+        let allowed = [Method::GET, Method::POST];
+
+        assert_eq!(check_method_with("post", &allowed).unwrap(), Method::POST);
+
+        let res = check_method_with("DELETE", &allowed).unwrap_err();
+        assert_eq!(res.status, 405);
+        assert_eq!(res.headers.get("Allow").map(String::as_str), Some("GET, POST"));
+
+        for method in ["TRACE", "CONNECT", "PROPFIND", ""] {
+            let res = check_method_with(method, &allowed).unwrap_err();
+            assert_eq!(res.status, 501, "{}", method);
+        }
+    }
+}
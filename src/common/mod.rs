@@ -7,13 +7,109 @@ pub mod traits;
 pub mod cookie;
 pub mod utils;
 pub mod cgi;
+pub mod locale;
+pub mod pagination;
+pub mod query;
+pub mod tenant;
+pub mod secrets;
+pub mod flags;
+pub mod route;
+pub mod forwarding;
+pub mod origin;
+pub mod redaction;
+pub mod request_id;
+pub mod panic;
+pub mod methods;
+pub mod interop;
+pub mod signed_url;
+pub mod body_policy;
+pub mod config_report;
+pub mod recording;
+pub mod identity;
+pub mod dependency;
+pub mod circuit_breaker;
+pub mod csp;
+pub mod content_type;
+pub mod lazy;
+pub mod multipart;
+pub mod body_stream;
+pub mod compression;
+pub mod maintenance;
+pub mod sse;
+pub mod app_data;
+pub mod security;
+pub mod access_log;
+pub mod deadline;
+pub mod json_stream;
+pub mod pubsub;
+pub mod cache;
+pub mod path_normalization;
+pub mod router;
+pub mod http_date;
+pub mod link;
+pub mod etag;
+pub mod error_response;
+pub mod content_digest;
+pub mod jobs;
+pub mod negotiation;
+pub mod body_fields;
+pub mod openapi;
 
 // 公開API用のre-export
 pub use http::{StatusCode, Method, Request, Response, ResponseBuilder};
 pub use context::RequestContext;
-pub use traits::{Handler, Middleware};
-pub use cookie::{SameSite, Cookie};
-pub use utils::{percent_decode, parse_query_string, get_max_body_size};
+pub use traits::{Handler, Middleware, AroundMiddleware, Next};
+pub use cookie::{SameSite, Cookie, Cookies};
+pub use utils::{percent_decode, percent_encode, parse_query_string, parse_query_string_limited, get_max_body_size, KB, MB};
+pub use locale::{Locale, LocaleResolver, LocaleSource};
+pub use pagination::Page;
+pub use query::{ListQuery, PageRequest, QuerySpec, Sort, SortDirection, SortField, Filters};
+pub use tenant::{Tenant, TenantResolver, TenantSource};
+pub use secrets::{SecretProvider, SecretStore, SecretValue, EnvSecretProvider};
+pub use flags::{FeatureFlags, FeatureFlagMiddleware, StaticFlags, EnvFlags};
+pub use route::{MatchedRoute, PathParams, PatternError, RouteInfo, RouteOptions, RouteTable};
+pub use forwarding::{ForwardingPolicy, ForwardedOrigin};
+pub use origin::RequestOrigin;
+pub use request_id::{current_request_id, with_request_id};
+pub use panic::install_panic_hook;
+pub use methods::{check_method, get_allowed_methods};
+pub use signed_url::UrlSigner;
+pub use body_policy::{BodyPolicy, get_body_policy};
+pub use body_fields::{BodyFieldPolicy, FieldMatching, RenameRule, UnknownFields};
+pub use openapi::{OpenApiInfo, OperationDoc};
+pub use config_report::{ConfigIssue, ConfigReport, ConfigSeverity};
+pub use recording::{RecordedExchange, TrafficRecorder};
+pub use identity::{AuthContext, Identity, UserIdentity};
+pub use dependency::{Criticality, Dependency, DependencyRegistry, DependencyStatus, HealthReport};
+pub use circuit_breaker::{CircuitBreaker, CircuitMetrics, CircuitState};
+pub use csp::{ContentSecurityPolicy, Source};
+pub use content_type::ContentType;
+pub use lazy::{AsyncLazy, LazyRetry};
+pub use multipart::{Multipart, MultipartForm, Part};
+pub use body_stream::{read_body_limited, BodyReader, LimitedReader};
+pub use compression::{CompressionMiddleware, Encoding};
+pub use maintenance::{MaintenanceMiddleware, MaintenanceWindow};
+pub use sse::{EventStream, SseEvent};
+pub use app_data::{AppData, Data};
+pub use security::SecurityProfile;
+pub use access_log::{AccessLogEntry, AccessLogMiddleware};
+pub use deadline::{current_deadline, remaining_time, with_deadline};
+pub use json_stream::JsonArrayWriter;
+pub use pubsub::{DropPolicy, PubSub, Subscription};
+pub use cache::{CacheMetrics, CacheMiddleware, CacheStore, CachedResponse, MemoryCacheStore, ResponseCacheMiddleware};
+pub use path_normalization::PathNormalization;
+pub use http_date::{format_http_date, format_system_time, parse_http_date, RetryAfter};
+pub use link::{LinkHint, LinkRel};
+pub use etag::{if_none_match_matches, strong_etag, ETagMiddleware};
+pub use error_response::{error_response, ErrorFormat};
+pub use content_digest::{content_digest_value, ContentDigestMiddleware, DigestAlgorithm};
+pub use jobs::{FileJobStore, Job, JobQueue, JobStatus, JobStore, MemoryJobStore};
+pub use negotiation::{ContentNegotiationMiddleware, CsvSerializer, JsonSerializer, Negotiator, ResponseSerializer};
+#[cfg(feature = "msgpack")]
+pub use negotiation::MessagePackSerializer;
+#[cfg(feature = "cbor")]
+pub use negotiation::CborSerializer;
+pub use router::CompiledRouter;
 
 // CGI関連の公開API
 #[cfg(feature = "cgi")]
@@ -0,0 +1,414 @@
+//! `multipart/form-data`ボディの解析（ファイルアップロード用）
+//!
+//! ボディ全体を受け取ってから解析します（ストリーミングではありません）。
+//! ボディのサイズは各ランタイムと同じく`RUNBRIDGE_MAX_BODY_SIZE`で制限されます。
+
+use std::collections::HashMap;
+
+use serde::de::DeserializeOwned;
+
+use crate::error::Error;
+use super::http::Request;
+use super::utils::{get_max_body_size, percent_decode, percent_encode};
+
+/// 1つのボディに含められるパートの最大数
+pub const MAX_PARTS: usize = 1000;
+
+/// 1つのパートのヘッダー部分の最大サイズ（バイト）
+pub const MAX_PART_HEADER_SIZE: usize = 8 * 1024;
+
+/// multipartの1つのパート（テキストのフィールドまたはファイル）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Part {
+    /// フィールド名（Content-Dispositionの`name`）
+    pub name: String,
+    /// ファイル名（ファイルのパートのみ、クライアントが送った値そのまま）
+    pub filename: Option<String>,
+    /// パートのContent-Type
+    pub content_type: Option<String>,
+    /// パートのヘッダー（キーは小文字）
+    pub headers: HashMap<String, String>,
+    /// 内容
+    pub data: Vec<u8>,
+}
+
+impl Part {
+    /// ファイルのパートかどうか
+    pub fn is_file(&self) -> bool {
+        self.filename.is_some()
+    }
+
+    /// 内容をUTF-8の文字列として取得
+    pub fn text(&self) -> Result<&str, Error> {
+        std::str::from_utf8(&self.data).map_err(|_| {
+            Error::InvalidRequestBody(format!("Multipart field '{}' is not valid UTF-8", self.name))
+        })
+    }
+
+    /// 保存に使用できるファイル名（ディレクトリ部分と制御文字を除去、空の場合はNone）
+    pub fn safe_filename(&self) -> Option<String> {
+        let filename = self.filename.as_deref()?;
+        let base = filename.rsplit(['/', '\\']).next().unwrap_or("");
+        let cleaned: String = base.chars().filter(|c| !c.is_control()).collect();
+        let cleaned = cleaned.trim();
+        if cleaned.is_empty() || cleaned == "." || cleaned == ".." {
+            None
+        } else {
+            Some(cleaned.to_string())
+        }
+    }
+}
+
+/// 解析済みの`multipart/form-data`ボディ
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Multipart {
+    parts: Vec<Part>,
+}
+
+impl Multipart {
+    /// Content-Typeヘッダーの値とボディから解析
+    pub fn parse(content_type: &str, body: &[u8]) -> Result<Self, Error> {
+        let boundary = boundary(content_type)?;
+        let delimiter = [b"--".as_slice(), boundary.as_bytes()].concat();
+        let mut parts = Vec::new();
+
+        let mut pos = find(body, &delimiter, 0).ok_or_else(|| invalid("missing boundary"))? + delimiter.len();
+        loop {
+            if body[pos..].starts_with(b"--") {
+                break;
+            }
+            // 境界行の末尾の空白（transport padding）と改行を読み飛ばす
+            while matches!(body.get(pos), Some(b' ') | Some(b'\t')) {
+                pos += 1;
+            }
+            if !body[pos..].starts_with(b"\r\n") {
+                return Err(invalid("malformed boundary line"));
+            }
+            pos += 2;
+
+            let header_end = find(body, b"\r\n\r\n", pos).ok_or_else(|| invalid("unterminated part headers"))?;
+            if header_end - pos > MAX_PART_HEADER_SIZE {
+                return Err(invalid("part headers too large"));
+            }
+            let headers = parse_headers(&body[pos..header_end])?;
+
+            let content_start = header_end + 4;
+            let close = [b"\r\n".as_slice(), &delimiter].concat();
+            let content_end = find(body, &close, content_start).ok_or_else(|| invalid("missing closing boundary"))?;
+
+            if parts.len() == MAX_PARTS {
+                return Err(invalid("too many parts"));
+            }
+            parts.push(build_part(headers, body[content_start..content_end].to_vec())?);
+            pos = content_end + close.len();
+        }
+        Ok(Self { parts })
+    }
+
+    /// リクエストから解析（Content-Typeが`multipart/form-data`でない場合やボディが無い場合は400）
+    pub fn from_request(req: &Request) -> Result<Self, Error> {
+        let content_type = req
+            .headers
+            .get("content-type")
+            .ok_or_else(|| Error::InvalidRequestBody("Missing Content-Type header".to_string()))?;
+        let body = req
+            .body
+            .as_deref()
+            .ok_or_else(|| Error::InvalidRequestBody("Missing request body".to_string()))?;
+        let max = get_max_body_size();
+        if body.len() > max {
+            return Err(Error::PayloadTooLarge(format!(
+                "Request body size {} exceeds limit {}",
+                body.len(),
+                max
+            )));
+        }
+        Self::parse(content_type, body)
+    }
+
+    /// すべてのパート（送信順）
+    pub fn parts(&self) -> &[Part] {
+        &self.parts
+    }
+
+    /// 指定した名前の最初のテキストフィールドの値
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields().find(|(n, _)| *n == name).map(|(_, v)| v)
+    }
+
+    /// テキストフィールドの一覧（UTF-8でない値は除く）
+    pub fn fields(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.parts
+            .iter()
+            .filter(|p| !p.is_file())
+            .filter_map(|p| p.text().ok().map(|v| (p.name.as_str(), v)))
+    }
+
+    /// 指定した名前の最初のファイル
+    pub fn file(&self, name: &str) -> Option<&Part> {
+        self.files().find(|p| p.name == name)
+    }
+
+    /// ファイルの一覧
+    pub fn files(&self) -> impl Iterator<Item = &Part> {
+        self.parts.iter().filter(|p| p.is_file())
+    }
+
+    /// テキストフィールドを構造体にデシリアライズ（数値や真偽値は文字列から変換）
+    pub fn deserialize_fields<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        let encoded = self
+            .fields()
+            .map(|(k, v)| format!("{}={}", percent_encode(k), percent_encode(v)))
+            .collect::<Vec<_>>()
+            .join("&");
+        serde_urlencoded::from_str(&encoded)
+            .map_err(|e| Error::InvalidRequestBody(format!("Invalid multipart fields: {}", e)))
+    }
+}
+
+/// テキストフィールドを型`T`に変換した`multipart/form-data`（`post_multipart`の引数）
+#[derive(Debug, Clone)]
+pub struct MultipartForm<T> {
+    /// テキストフィールドのデシリアライズ結果
+    pub fields: T,
+    /// 解析済みのボディ全体（ファイルの取得に使用）
+    pub multipart: Multipart,
+}
+
+impl<T: DeserializeOwned> MultipartForm<T> {
+    /// リクエストから解析
+    pub fn from_request(req: &Request) -> Result<Self, Error> {
+        let multipart = Multipart::from_request(req)?;
+        let fields = multipart.deserialize_fields()?;
+        Ok(Self { fields, multipart })
+    }
+}
+
+impl<T> MultipartForm<T> {
+    /// 指定した名前の最初のファイル
+    pub fn file(&self, name: &str) -> Option<&Part> {
+        self.multipart.file(name)
+    }
+
+    /// ファイルの一覧
+    pub fn files(&self) -> impl Iterator<Item = &Part> {
+        self.multipart.files()
+    }
+}
+
+impl Request {
+    /// ボディを`multipart/form-data`として解析
+    pub fn multipart(&self) -> Result<Multipart, Error> {
+        Multipart::from_request(self)
+    }
+}
+
+fn invalid(reason: &str) -> Error {
+    Error::InvalidRequestBody(format!("Invalid multipart body: {}", reason))
+}
+
+/// Content-Typeから境界文字列を取り出す
+fn boundary(content_type: &str) -> Result<String, Error> {
+    let mut params = content_type.split(';');
+    let mime = params.next().unwrap_or("").trim();
+    if !mime.eq_ignore_ascii_case("multipart/form-data") {
+        return Err(Error::InvalidRequestBody(format!(
+            "Unsupported Content-Type: {} (expected multipart/form-data)",
+            mime
+        )));
+    }
+    let boundary = params
+        .filter_map(|p| p.split_once('='))
+        .find(|(k, _)| k.trim().eq_ignore_ascii_case("boundary"))
+        .map(|(_, v)| v.trim().trim_matches('"').to_string())
+        .ok_or_else(|| invalid("missing boundary parameter"))?;
+    // RFC 2046: 1〜70文字
+    if boundary.is_empty() || boundary.len() > 70 {
+        return Err(invalid("invalid boundary parameter"));
+    }
+    Ok(boundary)
+}
+
+fn parse_headers(raw: &[u8]) -> Result<HashMap<String, String>, Error> {
+    let raw = std::str::from_utf8(raw).map_err(|_| invalid("part headers are not valid UTF-8"))?;
+    let mut headers = HashMap::new();
+    for line in raw.split("\r\n").filter(|l| !l.is_empty()) {
+        let (name, value) = line.split_once(':').ok_or_else(|| invalid("malformed part header"))?;
+        headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+    }
+    Ok(headers)
+}
+
+fn build_part(headers: HashMap<String, String>, data: Vec<u8>) -> Result<Part, Error> {
+    let disposition = headers
+        .get("content-disposition")
+        .ok_or_else(|| invalid("part without Content-Disposition"))?;
+    let params = disposition_params(disposition);
+    let name = params
+        .get("name")
+        .cloned()
+        .ok_or_else(|| invalid("part without a field name"))?;
+    // filename*（RFC 5987）をfilenameより優先
+    let filename = params
+        .get("filename*")
+        .and_then(|v| v.split_once("''").map(|(_, encoded)| percent_decode(encoded)))
+        .or_else(|| params.get("filename").cloned());
+
+    Ok(Part {
+        name,
+        filename,
+        content_type: headers.get("content-type").cloned(),
+        headers,
+        data,
+    })
+}
+
+/// `form-data; name="a"; filename="b.txt"`のパラメータ（キーは小文字）
+fn disposition_params(value: &str) -> HashMap<String, String> {
+    let mut params = HashMap::new();
+    let mut rest = value.split_once(';').map(|(_, r)| r).unwrap_or("");
+    while let Some((key, after)) = rest.split_once('=') {
+        let key = key.trim().trim_start_matches(';').trim().to_ascii_lowercase();
+        let after = after.trim_start();
+        let (value, remaining) = match after.strip_prefix('"') {
+            Some(quoted) => {
+                // 引用符内のバックスラッシュエスケープを解除
+                let mut value = String::new();
+                let mut chars = quoted.char_indices();
+                let mut end = quoted.len();
+                while let Some((i, c)) = chars.next() {
+                    match c {
+                        '\\' => {
+                            if let Some((_, escaped)) = chars.next() {
+                                value.push(escaped);
+                            }
+                        }
+                        '"' => {
+                            end = i + 1;
+                            break;
+                        }
+                        c => value.push(c),
+                    }
+                }
+                (value, &quoted[end..])
+            }
+            None => {
+                let end = after.find(';').unwrap_or(after.len());
+                (after[..end].trim().to_string(), &after[end..])
+            }
+        };
+        params.insert(key, value);
+        rest = remaining;
+    }
+    params
+}
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    if from > haystack.len() || needle.is_empty() {
+        return None;
+    }
+    haystack[from..]
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .map(|i| i + from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Method;
+    use serde::Deserialize;
+
+    const CONTENT_TYPE: &str = "multipart/form-data; boundary=\"----runbridge\"";
+
+    fn body() -> Vec<u8> {
+        [
+            "preamble\r\n",
+            "------runbridge\r\n",
+            "Content-Disposition: form-data; name=\"title\"\r\n\r\n",
+            "Hello, world\r\n",
+            "------runbridge\r\n",
+            "Content-Disposition: form-data; name=\"count\"\r\n\r\n",
+            "3\r\n",
+            "------runbridge\r\n",
+            "Content-Disposition: form-data; name=\"file\"; filename=\"../etc/pass\\\"wd.txt\"\r\n",
+            "Content-Type: text/plain\r\n\r\n",
+            "line1\r\nline2\r\n",
+            "------runbridge--\r\n",
+            "epilogue",
+        ]
+        .concat()
+        .into_bytes()
+    }
+
+    #[test]
+    fn test_parse_fields_and_files() {
+        let form = Multipart::parse(CONTENT_TYPE, &body()).unwrap();
+        assert_eq!(form.parts().len(), 3);
+        assert_eq!(form.field("title"), Some("Hello, world"));
+        assert_eq!(form.fields().count(), 2);
+
+        let file = form.file("file").unwrap();
+        assert_eq!(file.filename.as_deref(), Some("../etc/pass\"wd.txt"));
+        assert_eq!(file.safe_filename().as_deref(), Some("pass\"wd.txt"));
+        assert_eq!(file.content_type.as_deref(), Some("text/plain"));
+        // パート内のCRLFは内容として保持
+        assert_eq!(file.data, b"line1\r\nline2");
+        assert!(form.field("file").is_none());
+    }
+
+    #[test]
+    fn test_deserialize_fields() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Upload {
+            title: String,
+            count: u32,
+            note: Option<String>,
+        }
+
+        let form = Multipart::parse(CONTENT_TYPE, &body()).unwrap();
+        let upload: Upload = form.deserialize_fields().unwrap();
+        assert_eq!(upload, Upload { title: "Hello, world".to_string(), count: 3, note: None });
+
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Missing {
+            other: String,
+        }
+        assert_eq!(form.deserialize_fields::<Missing>().unwrap_err().status_code(), 400);
+    }
+
+    #[test]
+    fn test_rfc5987_filename() {
+        let body = "--b\r\nContent-Disposition: form-data; name=f; filename=\"a.txt\"; filename*=UTF-8''%E3%81%82.txt\r\n\r\nx\r\n--b--";
+        let form = Multipart::parse("multipart/form-data; boundary=b", body.as_bytes()).unwrap();
+        assert_eq!(form.file("f").unwrap().filename.as_deref(), Some("あ.txt"));
+    }
+
+    #[test]
+    fn test_invalid_bodies() {
+        let cases: [(&str, &str); 5] = [
+            ("application/json", "{}"),
+            ("multipart/form-data", "--b\r\n"),
+            ("multipart/form-data; boundary=b", "--b\r\nContent-Disposition: form-data; name=a\r\n\r\nno end"),
+            ("multipart/form-data; boundary=b", "--b\r\nContent-Type: text/plain\r\n\r\nx\r\n--b--"),
+            ("multipart/form-data; boundary=b", "no boundary here"),
+        ];
+        for (content_type, body) in cases {
+            let err = Multipart::parse(content_type, body.as_bytes()).unwrap_err();
+            assert_eq!(err.status_code(), 400, "{}: {}", content_type, body);
+        }
+    }
+
+    #[test]
+    fn test_request_body_limit() {
+        let req = Request::new(Method::POST, "/upload".to_string())
+            .with_header("Content-Type", CONTENT_TYPE)
+            .with_body(body());
+        assert_eq!(req.multipart().unwrap().parts().len(), 3);
+
+        temp_env::with_var("RUNBRIDGE_MAX_BODY_SIZE", Some("16"), || {
+            assert_eq!(req.multipart().unwrap_err().status_code(), 413);
+        });
+    }
+}
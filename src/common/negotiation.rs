@@ -0,0 +1,499 @@
+//! `Accept`ヘッダーによるコンテンツネゴシエーションとレスポンスのシリアライザー
+//!
+//! ハンドラーの戻り値（`Serialize`）は`ResponseWrapper`でJSONに変換されます。
+//! `ContentNegotiationMiddleware`をAroundMiddlewareとして登録すると、成功したJSONレスポンスを
+//! クライアントの`Accept`で最も優先される形式に変換して返します。ハンドラーで直接扱う場合は
+//! `Negotiator::render`を使います。
+//!
+//! 組み込みのシリアライザーはJSON・CSVで、MessagePackはfeature `msgpack`、CBORはfeature `cbor`が
+//! 有効な場合に使用できます。その他の形式は`ResponseSerializer`を実装して追加します。
+//!
+//! ```
+//! use runbridge::common::{ContentNegotiationMiddleware, Method, Negotiator, Request};
+//!
+//! let app = runbridge::RunBridge::builder().around(ContentNegotiationMiddleware::new()).build();
+//! # drop(app);
+//!
+//! // ハンドラーで扱う場合
+//! let mut req = Request::new(Method::GET, "/items".to_string());
+//! req.headers.insert("accept".to_string(), "text/csv, application/json;q=0.5".to_string());
+//! let res = Negotiator::new().render(&req, &serde_json::json!([{ "id": 1, "name": "a" }])).unwrap();
+//! assert_eq!(res.content_type(), Some("text/csv; charset=utf-8"));
+//! assert_eq!(res.body.as_deref(), Some(&b"id,name\r\n1,a\r\n"[..]));
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use log::debug;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::Error;
+use super::compression::add_vary;
+use super::content_type::ContentType;
+use super::error_response::error_response;
+use super::http::{Request, Response};
+use super::traits::{AroundMiddleware, Next};
+
+/// レスポンスのシリアライザー
+///
+/// 値はJSONの値（`serde_json::Value`）として受け取ります。表現できない値の場合はErrを返すと、
+/// 次に優先される形式で変換します。
+pub trait ResponseSerializer: Send + Sync {
+    /// レスポンスの`Content-Type`
+    fn content_type(&self) -> ContentType;
+
+    /// `Accept`のメディアタイプ（小文字、パラメータなし）がこの形式を指すか
+    fn matches_media_type(&self, mime: &str) -> bool {
+        self.content_type().mime() == mime
+    }
+
+    /// 値をボディに変換
+    fn serialize(&self, value: &Value) -> Result<Vec<u8>, Error>;
+}
+
+/// JSON（`application/json`）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonSerializer;
+
+impl ResponseSerializer for JsonSerializer {
+    fn content_type(&self) -> ContentType {
+        ContentType::json()
+    }
+
+    fn serialize(&self, value: &Value) -> Result<Vec<u8>, Error> {
+        serde_json::to_vec(value).map_err(|e| Error::ResponseSerializationError(e.to_string()))
+    }
+}
+
+/// CSV（`text/csv`、RFC 4180）
+///
+/// オブジェクトの配列（または1つのオブジェクト）を、キーを列名とする表に変換します。
+/// 列は最初に現れた順に並べ、ネストした値はJSONの文字列として出力します。
+/// それ以外の値は表現できないため変換しません。
+/// 表計算ソフトで数式として解釈されないよう、`=`・`+`・`-`・`@`・タブ・CRで始まる文字列には
+/// `'`を前置します（数値はそのまま出力します）。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CsvSerializer;
+
+impl ResponseSerializer for CsvSerializer {
+    fn content_type(&self) -> ContentType {
+        ContentType::new("text/csv")
+    }
+
+    fn serialize(&self, value: &Value) -> Result<Vec<u8>, Error> {
+        let not_tabular = || Error::ResponseSerializationError("CSV requires an object or an array of objects".to_string());
+        let rows = match value {
+            Value::Array(items) => items.iter().map(|item| item.as_object().ok_or_else(not_tabular)).collect::<Result<Vec<_>, _>>()?,
+            Value::Object(row) => vec![row],
+            _ => return Err(not_tabular()),
+        };
+        let mut columns: Vec<&String> = Vec::new();
+        for key in rows.iter().flat_map(|row| row.keys()) {
+            if !columns.contains(&key) {
+                columns.push(key);
+            }
+        }
+        if columns.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut out = String::new();
+        push_csv_record(&mut out, columns.iter().map(|c| escape_formula(c)));
+        for row in rows {
+            push_csv_record(&mut out, columns.iter().map(|c| match row.get(c.as_str()) {
+                None | Some(Value::Null) => "".into(),
+                Some(Value::String(s)) => escape_formula(s),
+                Some(other) => other.to_string().into(),
+            }));
+        }
+        Ok(out.into_bytes())
+    }
+}
+
+/// 数式として解釈される先頭文字（CSVインジェクション対策）で始まる場合に`'`を前置
+fn escape_formula(field: &str) -> std::borrow::Cow<'_, str> {
+    if field.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", field).into()
+    } else {
+        field.into()
+    }
+}
+
+fn push_csv_record<'a>(out: &mut String, fields: impl Iterator<Item = std::borrow::Cow<'a, str>>) {
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        if field.contains([',', '"', '\r', '\n']) {
+            out.push('"');
+            out.push_str(&field.replace('"', "\"\""));
+            out.push('"');
+        } else {
+            out.push_str(&field);
+        }
+    }
+    out.push_str("\r\n");
+}
+
+/// MessagePack（`application/msgpack`、`application/x-msgpack`も受け付ける、feature `msgpack`）
+#[cfg(feature = "msgpack")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MessagePackSerializer;
+
+#[cfg(feature = "msgpack")]
+impl ResponseSerializer for MessagePackSerializer {
+    fn content_type(&self) -> ContentType {
+        ContentType::new("application/msgpack")
+    }
+
+    fn matches_media_type(&self, mime: &str) -> bool {
+        matches!(mime, "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack")
+    }
+
+    fn serialize(&self, value: &Value) -> Result<Vec<u8>, Error> {
+        rmp_serde::to_vec_named(value).map_err(|e| Error::ResponseSerializationError(e.to_string()))
+    }
+}
+
+/// CBOR（`application/cbor`、feature `cbor`）
+#[cfg(feature = "cbor")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CborSerializer;
+
+#[cfg(feature = "cbor")]
+impl ResponseSerializer for CborSerializer {
+    fn content_type(&self) -> ContentType {
+        ContentType::new("application/cbor")
+    }
+
+    fn serialize(&self, value: &Value) -> Result<Vec<u8>, Error> {
+        let mut out = Vec::new();
+        ciborium::ser::into_writer(value, &mut out).map_err(|e| Error::ResponseSerializationError(e.to_string()))?;
+        Ok(out)
+    }
+}
+
+/// `Accept`の1つのメディアレンジ
+struct MediaRange {
+    mime: String,
+    q: f32,
+}
+
+impl MediaRange {
+    /// メディアタイプに一致する場合の具体性（完全一致 2、`type/*` 1、`*/*` 0）
+    fn specificity(&self, serializer: &dyn ResponseSerializer) -> Option<u8> {
+        if serializer.matches_media_type(&self.mime) {
+            return Some(2);
+        }
+        let mime = serializer.content_type().mime().to_string();
+        match self.mime.split_once('/') {
+            Some(("*", "*")) => Some(0),
+            Some((kind, "*")) if mime.split('/').next() == Some(kind) => Some(1),
+            _ => None,
+        }
+    }
+}
+
+fn parse_accept(accept: &str) -> Vec<MediaRange> {
+    accept
+        .split(',')
+        .filter_map(|item| {
+            let mut parts = item.split(';');
+            let mime = parts.next()?.trim().to_ascii_lowercase();
+            if !mime.contains('/') {
+                return None;
+            }
+            let q = parts
+                .filter_map(|p| p.split_once('='))
+                .find(|(k, _)| k.trim().eq_ignore_ascii_case("q"))
+                .and_then(|(_, v)| v.trim().parse::<f32>().ok())
+                .unwrap_or(1.0)
+                .clamp(0.0, 1.0);
+            Some(MediaRange { mime, q })
+        })
+        .collect()
+}
+
+/// 登録したシリアライザーから`Accept`に従って形式を選ぶ
+///
+/// `Accept`が無い場合は最初に登録した形式（既定はJSON）を使い、同じ優先度の形式は登録順に選びます。
+#[derive(Clone)]
+pub struct Negotiator {
+    serializers: Vec<Arc<dyn ResponseSerializer>>,
+}
+
+impl fmt::Debug for Negotiator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let types: Vec<String> = self.serializers.iter().map(|s| s.content_type().mime().to_string()).collect();
+        f.debug_struct("Negotiator").field("serializers", &types).finish()
+    }
+}
+
+impl Default for Negotiator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Negotiator {
+    /// 組み込みのシリアライザー（JSON・CSV、有効なfeatureのMessagePack・CBOR）で作成
+    pub fn new() -> Self {
+        let negotiator = Self::empty().serializer(JsonSerializer).serializer(CsvSerializer);
+        #[cfg(feature = "msgpack")]
+        let negotiator = negotiator.serializer(MessagePackSerializer);
+        #[cfg(feature = "cbor")]
+        let negotiator = negotiator.serializer(CborSerializer);
+        negotiator
+    }
+
+    /// シリアライザーを登録せずに作成（`Accept`が無い場合は最初に登録したものを使用）
+    pub fn empty() -> Self {
+        Self { serializers: Vec::new() }
+    }
+
+    /// シリアライザーを追加（同じメディアタイプのものは置き換える）
+    pub fn serializer<S: ResponseSerializer + 'static>(mut self, serializer: S) -> Self {
+        let mime = serializer.content_type().mime().to_string();
+        let serializer: Arc<dyn ResponseSerializer> = Arc::new(serializer);
+        match self.serializers.iter().position(|s| s.content_type().mime() == mime) {
+            Some(index) => self.serializers[index] = serializer,
+            None => self.serializers.push(serializer),
+        }
+        self
+    }
+
+    /// `Accept`で受け付けられる形式を優先順に返す（q=0の形式は含めない）
+    pub fn candidates(&self, accept: Option<&str>) -> Vec<&dyn ResponseSerializer> {
+        let ranges = match accept.map(parse_accept) {
+            Some(ranges) if !ranges.is_empty() => ranges,
+            _ => return self.serializers.iter().map(|s| s.as_ref()).collect(),
+        };
+        let mut scored: Vec<(f32, &dyn ResponseSerializer)> = self
+            .serializers
+            .iter()
+            .filter_map(|serializer| {
+                let serializer = serializer.as_ref();
+                // 最も具体的なメディアレンジのqを使う（`*/*;q=0.1, text/csv`のCSVはq=1）
+                let q = ranges
+                    .iter()
+                    .filter_map(|range| range.specificity(serializer).map(|s| (s, range.q)))
+                    .max_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)))?
+                    .1;
+                (q > 0.0).then_some((q, serializer))
+            })
+            .collect();
+        // 安定ソートのため同じqの形式は登録順のまま
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.into_iter().map(|(_, serializer)| serializer).collect()
+    }
+
+    /// 値をクライアントが優先する形式で変換する
+    ///
+    /// 受け付けられる形式が無い、またはどの形式でも表現できない場合はJSONで返します。
+    pub fn render<T: Serialize>(&self, req: &Request, value: &T) -> Result<Response, Error> {
+        let value = serde_json::to_value(value).map_err(|e| Error::ResponseSerializationError(e.to_string()))?;
+        let accept = req.headers.get("accept").map(String::as_str);
+        let mut res = match self.encode(accept, &value) {
+            Some((content_type, body)) => Response::ok().with_content_type(content_type).with_body(body),
+            None => Response::ok().json(&value)?,
+        };
+        add_vary(&mut res, "Accept");
+        Ok(res)
+    }
+
+    fn encode(&self, accept: Option<&str>, value: &Value) -> Option<(ContentType, Vec<u8>)> {
+        self.candidates(accept).into_iter().find_map(|serializer| match serializer.serialize(value) {
+            Ok(body) => Some((serializer.content_type(), body)),
+            Err(e) => {
+                debug!("Skipping {} for negotiated response: {}", serializer.content_type().mime(), e);
+                None
+            }
+        })
+    }
+}
+
+/// 成功したJSONレスポンスを`Accept`で優先される形式に変換するAroundMiddleware
+///
+/// 2xxで`Content-Type`がJSONのレスポンスだけを変換し、エラーのレスポンスはJSONのまま返します。
+/// 変換した場合はボディから計算された`ETag`・`Content-Digest`を取り除きます（`ETagMiddleware`は
+/// このミドルウェアより外側に登録してください）。
+#[derive(Debug, Clone, Default)]
+pub struct ContentNegotiationMiddleware {
+    negotiator: Negotiator,
+    reject_unacceptable: bool,
+}
+
+impl ContentNegotiationMiddleware {
+    /// 組み込みのシリアライザーで作成
+    pub fn new() -> Self {
+        Self::with_negotiator(Negotiator::new())
+    }
+
+    /// シリアライザーを登録した`Negotiator`を指定して作成
+    pub fn with_negotiator(negotiator: Negotiator) -> Self {
+        Self { negotiator, reject_unacceptable: false }
+    }
+
+    /// シリアライザーを追加（同じメディアタイプのものは置き換える）
+    pub fn serializer<S: ResponseSerializer + 'static>(mut self, serializer: S) -> Self {
+        self.negotiator = self.negotiator.serializer(serializer);
+        self
+    }
+
+    /// `Accept`で受け付けられる形式が無い場合に406を返すか（既定 false、JSONで返す）
+    pub fn reject_unacceptable(mut self, reject: bool) -> Self {
+        self.reject_unacceptable = reject;
+        self
+    }
+}
+
+#[async_trait]
+impl AroundMiddleware for ContentNegotiationMiddleware {
+    async fn around(&self, req: Request, next: Next<'_>) -> Result<Response, Error> {
+        let accept = req.headers.get("accept").cloned();
+        let mut res = next.run(req).await?;
+        let is_json = res.content_type().is_some_and(|v| ContentType::parse(v).is_json());
+        if !(200..300).contains(&res.status) || !is_json || res.is_sse() {
+            return Ok(res);
+        }
+        add_vary(&mut res, "Accept");
+
+        let candidates = self.negotiator.candidates(accept.as_deref());
+        let Some(preferred) = candidates.first() else {
+            if self.reject_unacceptable {
+                return Ok(error_response(406, accept.as_deref()));
+            }
+            return Ok(res);
+        };
+        if preferred.matches_media_type("application/json") {
+            return Ok(res);
+        }
+        let value = match res.body.as_deref().map(serde_json::from_slice::<Value>) {
+            Some(Ok(value)) => value,
+            _ => return Ok(res),
+        };
+        if let Some((content_type, body)) = self.negotiator.encode(accept.as_deref(), &value) {
+            res.headers.retain(|k, _| !k.eq_ignore_ascii_case("etag") && !k.eq_ignore_ascii_case("content-digest"));
+            res.set_content_type(content_type);
+            res.body = Some(body);
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Method;
+    use crate::handler::get;
+    use crate::RunBridge;
+    use serde_json::json;
+
+    fn request(accept: Option<&str>) -> Request {
+        let mut req = Request::new(Method::GET, "/items".to_string());
+        if let Some(accept) = accept {
+            req.headers.insert("accept".to_string(), accept.to_string());
+        }
+        req
+    }
+
+    fn preferred(negotiator: &Negotiator, accept: Option<&str>) -> Option<String> {
+        negotiator.candidates(accept).first().map(|s| s.content_type().mime().to_string())
+    }
+
+    #[test]
+    fn test_candidates_follow_accept() {
+        let negotiator = Negotiator::new();
+        assert_eq!(preferred(&negotiator, None).as_deref(), Some("application/json"));
+        assert_eq!(preferred(&negotiator, Some("*/*")).as_deref(), Some("application/json"));
+        assert_eq!(preferred(&negotiator, Some("text/*")).as_deref(), Some("text/csv"));
+        assert_eq!(preferred(&negotiator, Some("application/json;q=0.5, text/csv")).as_deref(), Some("text/csv"));
+        assert_eq!(preferred(&negotiator, Some("*/*;q=0.1, text/csv;q=0")).as_deref(), Some("application/json"));
+        assert_eq!(preferred(&negotiator, Some("image/png")), None);
+    }
+
+    #[test]
+    fn test_csv_serializer() {
+        let value = json!([{ "id": 1, "name": "a, b" }, { "id": 2, "tags": ["x"], "name": "say \"hi\"" }]);
+        let body = CsvSerializer.serialize(&value).unwrap();
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            "id,name,tags\r\n1,\"a, b\",\r\n2,\"say \"\"hi\"\"\",\"[\"\"x\"\"]\"\r\n"
+        );
+        assert!(CsvSerializer.serialize(&json!([1, 2])).is_err());
+    }
+
+    #[test]
+    fn test_csv_serializer_neutralizes_formulas() {
+        let value = json!([
+            { "=cmd": "=HYPERLINK(\"http://evil\")", "n": -1 },
+            { "=cmd": "+1", "n": "-1" },
+            { "=cmd": "@SUM(A1)", "n": "\tx" },
+            { "=cmd": "a=b", "n": "\rx" },
+        ]);
+        let body = CsvSerializer.serialize(&value).unwrap();
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            "'=cmd,n\r\n\"'=HYPERLINK(\"\"http://evil\"\")\",-1\r\n'+1,'-1\r\n'@SUM(A1),'\tx\r\na=b,\"'\rx\"\r\n"
+        );
+    }
+
+    #[test]
+    fn test_render_falls_back_to_json() {
+        let negotiator = Negotiator::new();
+        // CSVで表現できない値はJSONで返す
+        let res = negotiator.render(&request(Some("text/csv")), &json!(42)).unwrap();
+        assert_eq!(res.content_type(), Some("application/json"));
+        assert_eq!(res.header("Vary"), Some("Accept"));
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn test_msgpack_serializer() {
+        let negotiator = Negotiator::new();
+        let res = negotiator.render(&request(Some("application/x-msgpack")), &json!({ "a": 1 })).unwrap();
+        assert_eq!(res.content_type(), Some("application/msgpack"));
+        let decoded: Value = rmp_serde::from_slice(res.body.as_deref().unwrap()).unwrap();
+        assert_eq!(decoded, json!({ "a": 1 }));
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn test_cbor_serializer() {
+        let negotiator = Negotiator::new();
+        let res = negotiator.render(&request(Some("application/cbor")), &json!({ "a": [1, 2] })).unwrap();
+        assert_eq!(res.content_type(), Some("application/cbor"));
+        let decoded: Value = ciborium::de::from_reader(res.body.as_deref().unwrap()).unwrap();
+        assert_eq!(decoded, json!({ "a": [1, 2] }));
+    }
+
+    #[tokio::test]
+    async fn test_middleware_converts_json_responses() {
+        let app = RunBridge::builder()
+            .around(ContentNegotiationMiddleware::new().reject_unacceptable(true))
+            .handler(get("^/items$", |_req: Request| Ok(json!([{ "id": 1 }]))))
+            .handler(get("^/missing$", |_req: Request| -> Result<Response, Error> {
+                Err(Error::RouteNotFound("missing".to_string()))
+            }))
+            .build();
+
+        let res = crate::testing::dispatch(&app, request(Some("text/csv"))).await;
+        assert_eq!(res.content_type(), Some("text/csv; charset=utf-8"));
+        assert_eq!(res.body.as_deref(), Some(&b"id\r\n1\r\n"[..]));
+        assert_eq!(res.header("Vary"), Some("Accept"));
+
+        let res = crate::testing::dispatch(&app, request(None)).await;
+        assert_eq!(res.body.as_deref(), Some(&br#"[{"id":1}]"#[..]));
+
+        assert_eq!(crate::testing::dispatch(&app, request(Some("image/png"))).await.status, 406);
+
+        // エラーのレスポンスは変換しない
+        let mut req = request(Some("text/csv"));
+        req.path = "/missing".to_string();
+        let res = crate::testing::dispatch(&app, req).await;
+        assert_eq!((res.status, res.content_type()), (404, Some("application/json")));
+    }
+}
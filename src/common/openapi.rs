@@ -0,0 +1,399 @@
+//! ルートのメタデータからのOpenAPI 3.0ドキュメントの生成
+//!
+//! ルートに`HandlerExt::openapi`で`OperationDoc`（概要・リクエスト/レスポンスのスキーマ）を付与すると、
+//! `RunBridge::openapi_spec`や`RunBridgeBuilder::openapi`で登録したエンドポイント（既定 `/openapi.json`）
+//! のドキュメントに含まれます。feature `openapi`を有効にすると、`schemars::JsonSchema`を実装した型から
+//! スキーマを生成できます（無効の場合はJSON Schemaを`serde_json::Value`で指定します）。
+//!
+//! ```
+//! use runbridge::common::{OpenApiInfo, OperationDoc, Request};
+//! use runbridge::handler::{get, HandlerExt, OpenApiEndpoint};
+//! use runbridge::RunBridge;
+//! use serde_json::json;
+//!
+//! let app = RunBridge::builder()
+//!     .handler(
+//!         get("/items/{id}", |_req: Request| Ok(json!({ "id": 1 })))
+//!             .name("get_item")
+//!             .openapi(
+//!                 OperationDoc::new()
+//!                     .summary("商品を取得")
+//!                     .response_schema(200, "商品", json!({ "type": "object" })),
+//!             ),
+//!     )
+//!     .openapi(OpenApiEndpoint::new(OpenApiInfo::new("shop", "1.0.0")))
+//!     .build();
+//! let spec = app.openapi_spec();
+//! assert_eq!(spec["paths"]["/items/{id}"]["get"]["summary"], "商品を取得");
+//! ```
+
+use std::collections::BTreeMap;
+
+use serde_json::{json, Map, Value};
+
+use super::route::RouteInfo;
+use super::traits::Handler;
+
+/// OpenAPIのバージョン
+pub const OPENAPI_VERSION: &str = "3.0.3";
+
+/// `OperationDoc`のボディの既定のContent-Type
+const JSON_CONTENT_TYPE: &str = "application/json";
+
+/// ドキュメント全体の情報（`info`・`servers`）
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenApiInfo {
+    title: String,
+    version: String,
+    description: Option<String>,
+    servers: Vec<String>,
+}
+
+impl Default for OpenApiInfo {
+    fn default() -> Self {
+        Self::new("RunBridge API", "0.0.0")
+    }
+}
+
+impl OpenApiInfo {
+    /// タイトルとAPIのバージョンを指定して作成
+    pub fn new(title: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            version: version.into(),
+            description: None,
+            servers: Vec::new(),
+        }
+    }
+
+    /// APIの説明
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// 接続先のURLを追加（`servers`）
+    pub fn server(mut self, url: impl Into<String>) -> Self {
+        self.servers.push(url.into());
+        self
+    }
+
+    /// ルート一覧からドキュメントを作成
+    ///
+    /// パスはパターンの名前付きキャプチャを`{name}`に置き換えたものです。テンプレートに変換できない
+    /// パターンや、メソッドが不明なルートは含めずに警告を出力します。
+    pub fn document<'a, I>(&self, routes: I) -> Value
+    where
+        I: IntoIterator<Item = (RouteInfo, Option<&'a OperationDoc>)>,
+    {
+        let mut paths = Map::new();
+        let mut schemas = Map::new();
+        for (route, doc) in routes {
+            let template = match route.template() {
+                Some(template) if !route.methods.is_empty() => template,
+                _ => {
+                    log::warn!("Skipping route '{}' in OpenAPI output: no path template or methods", route.pattern);
+                    continue;
+                }
+            };
+            if let Some(doc) = doc {
+                for (name, schema) in &doc.components {
+                    schemas.entry(name.clone()).or_insert_with(|| schema.clone());
+                }
+            }
+
+            let item = paths
+                .entry(template)
+                .or_insert_with(|| Value::Object(Map::new()))
+                .as_object_mut()
+                .expect("path item is always an object");
+            for method in &route.methods {
+                item.entry(method.to_string().to_ascii_lowercase())
+                    .or_insert_with(|| operation(&route, doc));
+            }
+        }
+
+        let mut info = Map::new();
+        info.insert("title".to_string(), json!(self.title));
+        info.insert("version".to_string(), json!(self.version));
+        if let Some(description) = &self.description {
+            info.insert("description".to_string(), json!(description));
+        }
+        let mut document = Map::new();
+        document.insert("openapi".to_string(), json!(OPENAPI_VERSION));
+        document.insert("info".to_string(), Value::Object(info));
+        if !self.servers.is_empty() {
+            let servers: Vec<Value> = self.servers.iter().map(|url| json!({ "url": url })).collect();
+            document.insert("servers".to_string(), json!(servers));
+        }
+        document.insert("paths".to_string(), Value::Object(paths));
+        if !schemas.is_empty() {
+            document.insert("components".to_string(), json!({ "schemas": schemas }));
+        }
+        Value::Object(document)
+    }
+}
+
+/// ハンドラーのルート情報とメタデータの組（照合順）
+pub(crate) fn documented_routes(handlers: &[Box<dyn Handler>]) -> impl Iterator<Item = (RouteInfo, Option<&OperationDoc>)> {
+    handlers
+        .iter()
+        .map(|handler| (RouteInfo::from_handler(handler.as_ref()), handler.operation_doc()))
+}
+
+/// 1つのメソッドのOperation Objectを作成
+fn operation(route: &RouteInfo, doc: Option<&OperationDoc>) -> Value {
+    let mut operation = Map::new();
+    if let Some(name) = &route.name {
+        operation.insert("operationId".to_string(), json!(name));
+    }
+    let parameters: Vec<Value> = route
+        .param_names()
+        .into_iter()
+        .map(|name| json!({ "name": name, "in": "path", "required": true, "schema": { "type": "string" } }))
+        .collect();
+    if !parameters.is_empty() {
+        operation.insert("parameters".to_string(), json!(parameters));
+    }
+
+    let Some(doc) = doc else {
+        operation.insert("responses".to_string(), json!({ "default": { "description": "Response" } }));
+        return Value::Object(operation);
+    };
+    if let Some(summary) = &doc.summary {
+        operation.insert("summary".to_string(), json!(summary));
+    }
+    if let Some(description) = &doc.description {
+        operation.insert("description".to_string(), json!(description));
+    }
+    if !doc.tags.is_empty() {
+        operation.insert("tags".to_string(), json!(doc.tags));
+    }
+    if doc.deprecated {
+        operation.insert("deprecated".to_string(), json!(true));
+    }
+    if let Some(body) = &doc.request_body {
+        operation.insert(
+            "requestBody".to_string(),
+            json!({ "required": true, "content": { body.content_type.clone(): { "schema": body.schema } } }),
+        );
+    }
+    let responses: Map<String, Value> = if doc.responses.is_empty() {
+        Map::from_iter([("default".to_string(), json!({ "description": "Response" }))])
+    } else {
+        doc.responses
+            .iter()
+            .map(|(status, response)| {
+                let mut object = Map::new();
+                object.insert("description".to_string(), json!(response.description));
+                if let Some(body) = &response.body {
+                    object.insert(
+                        "content".to_string(),
+                        json!({ body.content_type.clone(): { "schema": body.schema } }),
+                    );
+                }
+                (status.to_string(), Value::Object(object))
+            })
+            .collect()
+    };
+    operation.insert("responses".to_string(), Value::Object(responses));
+    Value::Object(operation)
+}
+
+/// ボディのContent-TypeとJSON Schema
+#[derive(Debug, Clone, PartialEq)]
+struct BodyDoc {
+    content_type: String,
+    schema: Value,
+}
+
+/// レスポンスの説明とボディ
+#[derive(Debug, Clone, PartialEq)]
+struct ResponseDoc {
+    description: String,
+    body: Option<BodyDoc>,
+}
+
+/// ルートのOpenAPIのメタデータ（`HandlerExt::openapi`で付与）
+///
+/// レスポンスを1つも指定しない場合は`default`のレスポンスを出力します。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OperationDoc {
+    summary: Option<String>,
+    description: Option<String>,
+    tags: Vec<String>,
+    deprecated: bool,
+    request_body: Option<BodyDoc>,
+    responses: BTreeMap<u16, ResponseDoc>,
+    components: BTreeMap<String, Value>,
+}
+
+impl OperationDoc {
+    /// 新しいOperationDocを作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 概要（1行）
+    pub fn summary(mut self, summary: impl Into<String>) -> Self {
+        self.summary = Some(summary.into());
+        self
+    }
+
+    /// 説明
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// タグを追加
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// 非推奨のルートとして出力
+    pub fn deprecated(mut self) -> Self {
+        self.deprecated = true;
+        self
+    }
+
+    /// リクエストボディ（JSON）のスキーマを指定
+    pub fn request_schema(self, schema: Value) -> Self {
+        self.request_schema_with_content_type(JSON_CONTENT_TYPE, schema)
+    }
+
+    /// Content-Typeを指定してリクエストボディのスキーマを指定
+    pub fn request_schema_with_content_type(mut self, content_type: impl Into<String>, schema: Value) -> Self {
+        self.request_body = Some(BodyDoc { content_type: content_type.into(), schema });
+        self
+    }
+
+    /// ボディの無いレスポンスを追加（同じステータスは置き換え）
+    pub fn response(mut self, status: u16, description: impl Into<String>) -> Self {
+        self.responses.insert(status, ResponseDoc { description: description.into(), body: None });
+        self
+    }
+
+    /// ボディ（JSON）のスキーマ付きのレスポンスを追加（同じステータスは置き換え）
+    pub fn response_schema(mut self, status: u16, description: impl Into<String>, schema: Value) -> Self {
+        let body = BodyDoc { content_type: JSON_CONTENT_TYPE.to_string(), schema };
+        self.responses.insert(status, ResponseDoc { description: description.into(), body: Some(body) });
+        self
+    }
+
+    /// `components.schemas`に出力するスキーマを追加（`#/components/schemas/{name}`で参照）
+    pub fn component(mut self, name: impl Into<String>, schema: Value) -> Self {
+        self.components.insert(name.into(), schema);
+        self
+    }
+}
+
+#[cfg(feature = "openapi")]
+impl OperationDoc {
+    /// 型からリクエストボディ（JSON）のスキーマを生成して指定（feature `openapi`）
+    pub fn request_body<T: schemars::JsonSchema>(mut self) -> Self {
+        let schema = self.schema_for::<T>();
+        self.request_schema(schema)
+    }
+
+    /// 型から生成したボディ（JSON）のスキーマ付きのレスポンスを追加（feature `openapi`）
+    pub fn response_body<T: schemars::JsonSchema>(mut self, status: u16, description: impl Into<String>) -> Self {
+        let schema = self.schema_for::<T>();
+        self.response_schema(status, description, schema)
+    }
+
+    /// 型のスキーマを生成し、構造体などの参照される定義は`components`に追加する
+    fn schema_for<T: schemars::JsonSchema>(&mut self) -> Value {
+        let mut generator = schemars::gen::SchemaSettings::openapi3().into_generator();
+        let mut schema = generator.subschema_for::<T>();
+        let mut definitions = generator.take_definitions();
+        // subschema_forはOpenAPI向けの変換（nullable等）を適用しないため、ここで適用する
+        for visitor in generator.visitors_mut() {
+            visitor.visit_schema(&mut schema);
+            for definition in definitions.values_mut() {
+                visitor.visit_schema(definition);
+            }
+        }
+        for (name, definition) in definitions {
+            // Schemaのシリアライズは失敗しない
+            self.components
+                .entry(name)
+                .or_insert_with(|| serde_json::to_value(definition).unwrap_or_default());
+        }
+        serde_json::to_value(schema).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Method;
+
+    fn route(methods: Vec<Method>, pattern: &str, name: Option<&str>) -> RouteInfo {
+        RouteInfo { methods, pattern: pattern.to_string(), name: name.map(str::to_string) }
+    }
+
+    #[test]
+    fn test_document_with_operation_docs() {
+        let doc = OperationDoc::new()
+            .summary("商品を作成")
+            .tag("items")
+            .request_schema(json!({ "$ref": "#/components/schemas/NewItem" }))
+            .response_schema(201, "作成した商品", json!({ "type": "object" }))
+            .response(409, "重複")
+            .component("NewItem", json!({ "type": "object", "required": ["name"] }));
+        let info = OpenApiInfo::new("shop", "1.0.0").description("商品API").server("https://api.example.com");
+        let spec = info.document([
+            (route(vec![Method::POST], "^/items$", Some("create_item")), Some(&doc)),
+            (route(vec![Method::GET, Method::HEAD], "^/items/(?P<id>[^/]+)$", None), None),
+            (route(vec![Method::GET], "^/files/.*$", None), None),
+        ]);
+
+        assert_eq!(spec["openapi"], OPENAPI_VERSION);
+        assert_eq!(spec["info"], json!({ "title": "shop", "version": "1.0.0", "description": "商品API" }));
+        assert_eq!(spec["servers"], json!([{ "url": "https://api.example.com" }]));
+        let create = &spec["paths"]["/items"]["post"];
+        assert_eq!(create["operationId"], "create_item");
+        assert_eq!(create["summary"], "商品を作成");
+        assert_eq!(create["tags"], json!(["items"]));
+        assert_eq!(
+            create["requestBody"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/NewItem"
+        );
+        assert_eq!(create["responses"]["201"]["content"]["application/json"]["schema"]["type"], "object");
+        assert_eq!(create["responses"]["409"], json!({ "description": "重複" }));
+        assert_eq!(spec["components"]["schemas"]["NewItem"]["required"], json!(["name"]));
+
+        let show = &spec["paths"]["/items/{id}"];
+        assert_eq!(show["get"]["parameters"][0]["name"], "id");
+        assert_eq!(show["head"]["responses"]["default"]["description"], "Response");
+        assert_eq!(spec["paths"].as_object().unwrap().len(), 2);
+    }
+
+    #[cfg(feature = "openapi")]
+    #[test]
+    fn test_schemas_from_types() {
+        #[derive(schemars::JsonSchema)]
+        #[allow(dead_code)]
+        struct Item {
+            name: String,
+            price: Option<u32>,
+        }
+
+        let doc = OperationDoc::new().request_body::<Item>().response_body::<Vec<Item>>(200, "商品の一覧");
+        let spec = OpenApiInfo::default().document([(route(vec![Method::POST], "^/items$", None), Some(&doc))]);
+        let operation = &spec["paths"]["/items"]["post"];
+        assert_eq!(
+            operation["requestBody"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/Item"
+        );
+        assert_eq!(
+            operation["responses"]["200"]["content"]["application/json"]["schema"]["items"]["$ref"],
+            "#/components/schemas/Item"
+        );
+        let item = &spec["components"]["schemas"]["Item"];
+        assert_eq!(item["required"], json!(["name"]));
+        assert_eq!(item["properties"]["price"]["nullable"], true);
+    }
+}
@@ -0,0 +1,181 @@
+//! リクエストのスキーム・ホスト解決と絶対URLの生成
+
+use std::env;
+
+use super::http::Request;
+
+/// RequestContextに格納する際のキー
+pub const ORIGIN_CONTEXT_KEY: &str = "runbridge.origin";
+
+/// 各ランタイムが受信時に把握しているスキームとホスト
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestOrigin {
+    /// スキーム（`http` / `https`）
+    pub scheme: String,
+    /// ホスト（ポートを含む場合あり）
+    pub host: Option<String>,
+}
+
+/// 公開URLのベースを取得（リバースプロキシ配下などでHostヘッダーを信頼しない場合に設定）
+/// 優先順位: 環境変数 `RUNBRIDGE_PUBLIC_BASE_URL` -> デフォルト なし（リクエストから解決）
+pub fn get_public_base_url() -> Option<String> {
+    env::var("RUNBRIDGE_PUBLIC_BASE_URL")
+        .ok()
+        .map(|v| v.trim().trim_end_matches('/').to_string())
+        .filter(|v| v.starts_with("http://") || v.starts_with("https://"))
+}
+
+/// クライアントから受信した`X-Forwarded-Proto`を信頼するかどうか
+///
+/// ヘッダーはクライアントが任意に指定できるため、受信した値を上書きする信頼できるプロキシの背後で
+/// 動作する場合にだけ有効にします（`ForwardingPolicy::behind_trusted_proxy`と同じ考え方）。
+/// 優先順位: 環境変数 `RUNBRIDGE_TRUST_FORWARDED_PROTO`（`1` / `true`） -> デフォルト 無効
+pub fn is_forwarded_proto_trusted() -> bool {
+    env::var("RUNBRIDGE_TRUST_FORWARDED_PROTO")
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true"))
+        .unwrap_or(false)
+}
+
+impl Request {
+    /// ランタイムが把握しているスキーム・ホストを設定（各ランタイムの取り込み時に使用）
+    pub fn set_origin(&mut self, origin: RequestOrigin) {
+        self.context_mut().set(ORIGIN_CONTEXT_KEY, origin);
+    }
+
+    /// リクエストのスキーム
+    ///
+    /// 優先順位: `X-Forwarded-Proto`（先頭の値、`RUNBRIDGE_TRUST_FORWARDED_PROTO`が有効な場合のみ）
+    /// -> ランタイムの情報 -> `http`
+    pub fn scheme(&self) -> &'static str {
+        let forwarded = self
+            .headers
+            .get("x-forwarded-proto")
+            .filter(|_| is_forwarded_proto_trusted())
+            .and_then(|v| v.split(',').next())
+            .map(|v| v.trim());
+        let runtime = self
+            .context()
+            .get::<RequestOrigin>(ORIGIN_CONTEXT_KEY)
+            .map(|o| o.scheme.as_str());
+
+        match forwarded.or(runtime) {
+            Some(s) if s.eq_ignore_ascii_case("https") => "https",
+            _ => "http",
+        }
+    }
+
+    /// リクエストのホスト
+    ///
+    /// 優先順位: `Host`ヘッダー -> ランタイムの情報。ホストとして不正な文字を含む値は無視します。
+    pub fn host(&self) -> Option<&str> {
+        self.headers
+            .get("host")
+            .map(|h| h.trim())
+            .filter(|h| is_host_valid(h))
+            .or_else(|| {
+                self.context()
+                    .get::<RequestOrigin>(ORIGIN_CONTEXT_KEY)
+                    .and_then(|o| o.host.as_deref())
+                    .filter(|h| is_host_valid(h))
+            })
+    }
+
+    /// ベースURL（例: `https://api.example.com`）
+    ///
+    /// `RUNBRIDGE_PUBLIC_BASE_URL`が設定されていればそれを優先します。
+    /// Hostヘッダーはクライアントが任意に指定できるため、外部に送るURLでは設定を推奨します。
+    pub fn base_url(&self) -> Option<String> {
+        if let Some(base) = get_public_base_url() {
+            return Some(base);
+        }
+        self.host().map(|host| format!("{}://{}", self.scheme(), host))
+    }
+
+    /// パスから絶対URLを生成（例: `/items/42` -> `https://api.example.com/items/42`）
+    pub fn absolute_url(&self, path: &str) -> Option<String> {
+        let base = self.base_url()?;
+        if path.starts_with('/') {
+            Some(format!("{}{}", base, path))
+        } else {
+            Some(format!("{}/{}", base, path))
+        }
+    }
+}
+
+/// ホスト名として妥当か（英数字・`.`・`-`・`:`・IPv6用の角括弧のみ）
+fn is_host_valid(host: &str) -> bool {
+    !host.is_empty()
+        && host.len() <= 255
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':' | '[' | ']'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Method;
+    use temp_env::with_var;
+
+    fn request() -> Request {
+        Request::new(Method::GET, "/".to_string())
+    }
+
+    #[test]
+    fn test_scheme_resolution_order() {
+        with_var("RUNBRIDGE_TRUST_FORWARDED_PROTO", Some("true"), || {
+            assert_eq!(request().scheme(), "http");
+
+            let mut req = request();
+            req.set_origin(RequestOrigin { scheme: "https".into(), host: None });
+            assert_eq!(req.scheme(), "https");
+
+            let req = req.with_header("X-Forwarded-Proto", "http, https");
+            assert_eq!(req.scheme(), "http");
+
+            let req = request().with_header("X-Forwarded-Proto", "HTTPS");
+            assert_eq!(req.scheme(), "https");
+        });
+    }
+
+    #[test]
+    fn test_forwarded_proto_ignored_by_default() {
+        with_var("RUNBRIDGE_TRUST_FORWARDED_PROTO", None::<&str>, || {
+            let req = request().with_header("X-Forwarded-Proto", "https");
+            assert_eq!(req.scheme(), "http");
+
+            let mut req = request().with_header("X-Forwarded-Proto", "http");
+            req.set_origin(RequestOrigin { scheme: "https".into(), host: None });
+            assert_eq!(req.scheme(), "https");
+        });
+    }
+
+    #[test]
+    fn test_base_url_and_absolute_url() {
+        let vars = [("RUNBRIDGE_PUBLIC_BASE_URL", None), ("RUNBRIDGE_TRUST_FORWARDED_PROTO", Some("1"))];
+        temp_env::with_vars(vars, || {
+            let req = request()
+                .with_header("Host", "api.example.com:8443")
+                .with_header("X-Forwarded-Proto", "https");
+            assert_eq!(req.base_url().as_deref(), Some("https://api.example.com:8443"));
+            assert_eq!(
+                req.absolute_url("/items/42").as_deref(),
+                Some("https://api.example.com:8443/items/42")
+            );
+
+            // 不正なHostは無視してランタイムの情報を使う
+            let mut req = request().with_header("Host", "evil.com/path");
+            assert_eq!(req.base_url(), None);
+            req.set_origin(RequestOrigin { scheme: "http".into(), host: Some("localhost".into()) });
+            assert_eq!(req.base_url().as_deref(), Some("http://localhost"));
+        });
+    }
+
+    #[test]
+    fn test_public_base_url_overrides_request() {
+        with_var("RUNBRIDGE_PUBLIC_BASE_URL", Some("https://public.example.com/"), || {
+            let req = request().with_header("Host", "internal:8080");
+            assert_eq!(req.absolute_url("cb").as_deref(), Some("https://public.example.com/cb"));
+        });
+    }
+}
@@ -0,0 +1,144 @@
+//! ページネーション用のレスポンスヘルパー（RFC 5988 Linkヘッダー生成）
+
+use serde::Serialize;
+use crate::error::Error;
+use super::http::{Request, Response};
+use super::utils::percent_encode;
+
+/// ページネーション付きのレスポンスボディ
+#[derive(Debug, Clone, Serialize)]
+pub struct Page<T> {
+    /// 現在のページの要素
+    pub items: Vec<T>,
+    /// 現在のページ番号（1始まり）
+    pub page: u64,
+    /// 1ページあたりの件数
+    pub per_page: u64,
+    /// 全件数
+    pub total: u64,
+    /// 全ページ数
+    pub total_pages: u64,
+    #[serde(skip)]
+    page_param: String,
+    #[serde(skip)]
+    per_page_param: String,
+}
+
+impl<T: Serialize> Page<T> {
+    /// 新しいページを作成（`per_page`が0の場合は1として扱う）
+    pub fn new(items: Vec<T>, page: u64, per_page: u64, total: u64) -> Self {
+        let per_page = per_page.max(1);
+        Self {
+            items,
+            page: page.max(1),
+            per_page,
+            total,
+            total_pages: total.div_ceil(per_page),
+            page_param: "page".to_string(),
+            per_page_param: "per_page".to_string(),
+        }
+    }
+
+    /// Linkヘッダー生成時に使用するクエリパラメータ名を設定（既定: `page` / `per_page`）
+    pub fn param_names(mut self, page: impl Into<String>, per_page: impl Into<String>) -> Self {
+        self.page_param = page.into();
+        self.per_page_param = per_page.into();
+        self
+    }
+
+    /// リクエストのパスとクエリからLinkヘッダー値（first/prev/next/last）を生成
+    pub fn link_header(&self, req: &Request) -> String {
+        let last_page = self.total_pages.max(1);
+        let mut links = vec![format!("<{}>; rel=\"first\"", self.page_url(req, 1))];
+        if self.page > 1 {
+            let prev = (self.page - 1).min(last_page);
+            links.push(format!("<{}>; rel=\"prev\"", self.page_url(req, prev)));
+        }
+        if self.page < last_page {
+            links.push(format!("<{}>; rel=\"next\"", self.page_url(req, self.page + 1)));
+        }
+        links.push(format!("<{}>; rel=\"last\"", self.page_url(req, last_page)));
+        links.join(", ")
+    }
+
+    /// JSONボディとLinkヘッダーを持つレスポンスに変換
+    pub fn into_response_with_links(self, req: &Request) -> Result<Response, Error> {
+        let link = self.link_header(req);
+        Response::ok().with_header("Link", link).json(&self)
+    }
+
+    /// 指定ページのURL（パス + クエリ）を生成。既存のクエリは維持し、キー順で安定化する
+    fn page_url(&self, req: &Request, page: u64) -> String {
+        let mut params: Vec<(&str, String)> = req
+            .query_params
+            .iter()
+            .filter(|(k, _)| **k != self.page_param && **k != self.per_page_param)
+            .map(|(k, v)| (k.as_str(), v.clone()))
+            .collect();
+        params.push((self.page_param.as_str(), page.to_string()));
+        params.push((self.per_page_param.as_str(), self.per_page.to_string()));
+        params.sort_by(|a, b| a.0.cmp(b.0));
+
+        let query = params
+            .iter()
+            .map(|(k, v)| format!("{}={}", percent_encode(k), percent_encode(v)))
+            .collect::<Vec<_>>()
+            .join("&");
+        format!("{}?{}", req.path, query)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Method;
+
+    #[test]
+    fn test_page_metadata() {
+        let page = Page::new(vec![1, 2, 3], 2, 3, 10);
+        assert_eq!(page.total_pages, 4);
+
+        let empty: Page<i32> = Page::new(vec![], 1, 0, 0);
+        assert_eq!(empty.per_page, 1);
+        assert_eq!(empty.total_pages, 0);
+    }
+
+    #[test]
+    fn test_link_header_middle_page() {
+        let req = Request::new(Method::GET, "/items".to_string())
+            .with_query_param("page", "2")
+            .with_query_param("q", "a b");
+        let page = Page::new(vec!["x"], 2, 10, 35);
+
+        assert_eq!(
+            page.link_header(&req),
+            "</items?page=1&per_page=10&q=a%20b>; rel=\"first\", \
+             </items?page=1&per_page=10&q=a%20b>; rel=\"prev\", \
+             </items?page=3&per_page=10&q=a%20b>; rel=\"next\", \
+             </items?page=4&per_page=10&q=a%20b>; rel=\"last\""
+        );
+    }
+
+    #[test]
+    fn test_link_header_edges_and_custom_params() {
+        let req = Request::new(Method::GET, "/items".to_string());
+        let first = Page::new(vec![1], 1, 5, 5).param_names("p", "size");
+        let header = first.link_header(&req);
+        assert!(!header.contains("rel=\"prev\""));
+        assert!(!header.contains("rel=\"next\""));
+        assert!(header.contains("</items?p=1&size=5>; rel=\"last\""));
+    }
+
+    #[test]
+    fn test_into_response_with_links() {
+        let req = Request::new(Method::GET, "/items".to_string());
+        let res = Page::new(vec![1, 2], 1, 2, 4).into_response_with_links(&req).unwrap();
+
+        assert_eq!(res.status, 200);
+        assert!(res.headers.get("Link").unwrap().contains("rel=\"next\""));
+        let body: serde_json::Value = serde_json::from_slice(res.body.as_ref().unwrap()).unwrap();
+        assert_eq!(body["items"], serde_json::json!([1, 2]));
+        assert_eq!(body["total_pages"], 2);
+        assert!(body.get("page_param").is_none());
+    }
+}
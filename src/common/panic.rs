@@ -0,0 +1,104 @@
+//! 構造化ログを出力するパニックフック（オプトイン）
+//!
+//! `install_panic_hook`を呼ぶと、プロセス内のすべてのパニック（ハンドラー外のミドルウェアや
+//! 変換処理を含む）を1行のJSONとして`runbridge::panic`ターゲットにerrorレベルで出力します。
+//! 各ランタイムはリクエスト処理をリクエストIDのタスクローカルで包むため、
+//! パニック記録にはリクエストIDが含まれます。
+
+use std::panic::{self, PanicHookInfo};
+use std::sync::Once;
+
+use super::redaction::redact_query_string;
+use super::request_id::current_request_id;
+
+static INSTALL: Once = Once::new();
+
+/// 構造化ログを出力するパニックフックをインストール（複数回呼んでも1回のみ有効）
+pub fn install_panic_hook() {
+    INSTALL.call_once(|| {
+        panic::set_hook(Box::new(|info| {
+            let record = panic_record(info, current_request_id());
+            if log::log_enabled!(target: "runbridge::panic", log::Level::Error) {
+                log::error!(target: "runbridge::panic", "{}", record);
+            } else {
+                // ロガー未初期化でも記録が失われないよう標準エラー出力に書き出す
+                eprintln!("{}", record);
+            }
+        }));
+    });
+}
+
+/// パニック情報をJSON文字列に整形
+fn panic_record(info: &PanicHookInfo<'_>, request_id: Option<String>) -> String {
+    let payload = info.payload();
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "Box<dyn Any>".to_string());
+
+    serde_json::json!({
+        "event": "panic",
+        "message": redact_message(&message),
+        "location": info.location().map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+        "thread": std::thread::current().name().unwrap_or("<unnamed>"),
+        "request_id": request_id,
+    })
+    .to_string()
+}
+
+/// メッセージ中の`key=value`形式のうちセンシティブなキーの値を伏せ字にする
+fn redact_message(message: &str) -> String {
+    message
+        .split(' ')
+        .map(|token| {
+            if token.contains('=') {
+                redact_query_string(token)
+            } else {
+                token.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::request_id::with_request_id;
+
+    #[test]
+    fn test_redact_message() {
+        assert_eq!(
+            redact_message("failed to call api token=abc&user=bob timeout"),
+            "failed to call api token=***redacted***&user=bob timeout"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_panic_record_contains_request_id() {
+        let captured = std::sync::Arc::new(std::sync::Mutex::new(None));
+        let sink = captured.clone();
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            // 並行して実行される他のテストのパニックは無視
+            if current_request_id().as_deref() == Some("req-7") {
+                *sink.lock().unwrap() = Some(panic_record(info, current_request_id()));
+            }
+        }));
+
+        let result = with_request_id("req-7".to_string(), async {
+            std::panic::catch_unwind(|| panic!("boom password=hunter2")).is_err()
+        })
+        .await;
+        panic::set_hook(previous);
+        assert!(result);
+
+        let record: serde_json::Value =
+            serde_json::from_str(captured.lock().unwrap().as_deref().unwrap()).unwrap();
+        assert_eq!(record["event"], "panic");
+        assert_eq!(record["request_id"], "req-7");
+        assert_eq!(record["message"], "boom password=***redacted***");
+        assert!(record["location"].as_str().unwrap().contains("panic.rs"));
+    }
+}
@@ -0,0 +1,128 @@
+//! ルーティング前のリクエストパスの正規化
+//!
+//! `RunBridgeBuilder::path_normalization`で設定すると、各ランタイムがハンドラーの検索前に
+//! `Request::path`を書き換えます（既定では何もしません）。
+
+/// パスの正規化の設定
+///
+/// ```
+/// use runbridge::common::PathNormalization;
+///
+/// let normalization = PathNormalization::new().trim_trailing_slash().collapse_slashes();
+/// assert_eq!(normalization.normalize("//hello//world/"), "/hello/world");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PathNormalization {
+    trim_trailing_slash: bool,
+    collapse_slashes: bool,
+    percent_decode: bool,
+}
+
+impl PathNormalization {
+    /// 何も正規化しない設定を作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 全ての正規化を有効にした設定を作成
+    pub fn all() -> Self {
+        Self { trim_trailing_slash: true, collapse_slashes: true, percent_decode: true }
+    }
+
+    /// 末尾のスラッシュを取り除く（`/hello/` -> `/hello`、`/`はそのまま）
+    pub fn trim_trailing_slash(mut self) -> Self {
+        self.trim_trailing_slash = true;
+        self
+    }
+
+    /// 連続するスラッシュを1つにまとめる（`/a//b` -> `/a/b`）
+    pub fn collapse_slashes(mut self) -> Self {
+        self.collapse_slashes = true;
+        self
+    }
+
+    /// パーセントエンコードされた文字をデコードする（`/caf%C3%A9` -> `/café`）
+    ///
+    /// セグメントの区切りが変わらないよう`%2F`は、パスパラメータの二重デコードを防ぐため`%25`は
+    /// エンコードされたまま残します。デコード結果がUTF-8として不正な場合はデコードしません。
+    pub fn percent_decode(mut self) -> Self {
+        self.percent_decode = true;
+        self
+    }
+
+    /// いずれかの正規化が有効かどうか
+    pub fn is_enabled(&self) -> bool {
+        self.trim_trailing_slash || self.collapse_slashes || self.percent_decode
+    }
+
+    /// パスを正規化（デコード -> スラッシュの集約 -> 末尾のスラッシュの除去の順）
+    pub fn normalize(&self, path: &str) -> String {
+        let mut path = if self.percent_decode {
+            decode_path(path)
+        } else {
+            path.to_string()
+        };
+        if self.collapse_slashes {
+            let mut collapsed = String::with_capacity(path.len());
+            for c in path.chars() {
+                if !(c == '/' && collapsed.ends_with('/')) {
+                    collapsed.push(c);
+                }
+            }
+            path = collapsed;
+        }
+        if self.trim_trailing_slash {
+            let trimmed = path.trim_end_matches('/');
+            path = if trimmed.is_empty() { "/".to_string() } else { trimmed.to_string() };
+        }
+        path
+    }
+}
+
+/// `%2F`・`%25`以外のパーセントエンコードをデコード（`+`は空白にしない）
+fn decode_path(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() && bytes[i + 1..i + 3].iter().all(u8::is_ascii_hexdigit) {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(byte) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                if byte != b'/' && byte != b'%' {
+                    decoded.push(byte);
+                    i += 3;
+                    continue;
+                }
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8(decoded).unwrap_or_else(|_| path.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_modes() {
+        assert_eq!(PathNormalization::new().normalize("//a/b/"), "//a/b/");
+
+        let trim = PathNormalization::new().trim_trailing_slash();
+        assert_eq!(trim.normalize("/hello/"), "/hello");
+        assert_eq!(trim.normalize("/hello//"), "/hello");
+        assert_eq!(trim.normalize("/"), "/");
+
+        let collapse = PathNormalization::new().collapse_slashes();
+        assert_eq!(collapse.normalize("//a///b/"), "/a/b/");
+
+        let decode = PathNormalization::new().percent_decode();
+        assert_eq!(decode.normalize("/caf%C3%A9/a%20b"), "/café/a b");
+        assert_eq!(decode.normalize("/a%2Fb/%2541/c+d"), "/a%2Fb/%2541/c+d");
+        assert_eq!(decode.normalize("/bad%FF/%+4/%4"), "/bad%FF/%+4/%4");
+
+        // エンコードされたスラッシュはセグメントの区切りとして扱わない
+        assert_eq!(PathNormalization::all().normalize("/%61//b%2F/"), "/a/b%2F");
+    }
+}
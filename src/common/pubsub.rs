@@ -0,0 +1,316 @@
+//! プロセス内のトピック単位のブロードキャスト（SSEなどのリアルタイム配信用）
+//!
+//! `PubSub`を`RunBridgeBuilder::app_data`で登録し、SSEのハンドラーで`subscribe`したストリームを
+//! 返し、他のハンドラーから`publish`すると、同じインスタンス内の購読者に配信されます。
+//! 外部の基盤を使わない簡易的な仕組みのため、複数インスタンス間では共有されません。
+//!
+//! ```
+//! use runbridge::common::{Data, PubSub, Request, Response, SseEvent};
+//! use runbridge::error::Error;
+//!
+//! fn events(req: Request) -> Result<Response, Error> {
+//!     let hub = Data::<PubSub<SseEvent>>::from_request(&req)?;
+//!     Ok(Response::sse(hub.subscribe("news").into_stream()))
+//! }
+//!
+//! fn post(req: Request) -> Result<Response, Error> {
+//!     let hub = Data::<PubSub<SseEvent>>::from_request(&req)?;
+//!     hub.publish("news", SseEvent::new("hello"));
+//!     Ok(Response::new(202))
+//! }
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+
+use futures::Stream;
+use log::debug;
+use tokio::sync::Notify;
+
+/// 購読者ごとのキューの既定の容量
+pub const DEFAULT_SUBSCRIBER_CAPACITY: usize = 64;
+
+/// 購読者のキューが一杯の場合の扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DropPolicy {
+    /// 新しいメッセージを捨てる
+    DropNewest,
+    /// 最も古いメッセージを捨てて新しいメッセージを入れる
+    #[default]
+    DropOldest,
+    /// 購読を終了する（受信済みのメッセージを読み終えるとストリームが終了）
+    Disconnect,
+}
+
+struct Queue<T> {
+    messages: Mutex<VecDeque<T>>,
+    closed: AtomicBool,
+    notify: Notify,
+}
+
+impl<T> Queue<T> {
+    fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        self.notify.notify_one();
+    }
+}
+
+type Topics<T> = HashMap<String, Vec<(u64, Arc<Queue<T>>)>>;
+
+struct Hub<T> {
+    topics: Mutex<Topics<T>>,
+    capacity: usize,
+    policy: DropPolicy,
+    next_id: AtomicU64,
+}
+
+/// トピックごとの購読者にメッセージを配信するハブ（クローンは同じハブを共有）
+pub struct PubSub<T> {
+    hub: Arc<Hub<T>>,
+}
+
+impl<T> Clone for PubSub<T> {
+    fn clone(&self) -> Self {
+        Self { hub: self.hub.clone() }
+    }
+}
+
+impl<T: Clone + Send + 'static> Default for PubSub<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for PubSub<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PubSub")
+            .field("capacity", &self.hub.capacity)
+            .field("policy", &self.hub.policy)
+            .finish()
+    }
+}
+
+impl<T: Clone + Send + 'static> PubSub<T> {
+    /// 既定の容量（64件）と`DropPolicy::DropOldest`でハブを作成
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_SUBSCRIBER_CAPACITY, DropPolicy::default())
+    }
+
+    /// 購読者ごとのキューの容量と、一杯の場合の扱いを指定してハブを作成
+    pub fn with_capacity(capacity: usize, policy: DropPolicy) -> Self {
+        Self {
+            hub: Arc::new(Hub {
+                topics: Mutex::new(HashMap::new()),
+                capacity: capacity.max(1),
+                policy,
+                next_id: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// トピックを購読（`Subscription`を破棄すると購読を終了）
+    pub fn subscribe(&self, topic: impl Into<String>) -> Subscription<T> {
+        let topic = topic.into();
+        let id = self.hub.next_id.fetch_add(1, Ordering::Relaxed);
+        let queue = Arc::new(Queue {
+            messages: Mutex::new(VecDeque::new()),
+            closed: AtomicBool::new(false),
+            notify: Notify::new(),
+        });
+        if let Ok(mut topics) = self.hub.topics.lock() {
+            topics.entry(topic.clone()).or_default().push((id, queue.clone()));
+        }
+        Subscription { hub: Arc::downgrade(&self.hub), topic, id, queue }
+    }
+
+    /// トピックの購読者にメッセージを配信し、キューに入れた購読者の数を返す
+    pub fn publish(&self, topic: &str, message: T) -> usize {
+        let mut topics = match self.hub.topics.lock() {
+            Ok(topics) => topics,
+            Err(_) => return 0,
+        };
+        let subscribers = match topics.get_mut(topic) {
+            Some(subscribers) => subscribers,
+            None => return 0,
+        };
+
+        let mut delivered = 0;
+        subscribers.retain(|(id, queue)| {
+            let mut messages = match queue.messages.lock() {
+                Ok(messages) => messages,
+                Err(_) => return false,
+            };
+            if messages.len() >= self.hub.capacity {
+                match self.hub.policy {
+                    DropPolicy::DropNewest => return true,
+                    DropPolicy::DropOldest => {
+                        messages.pop_front();
+                    }
+                    DropPolicy::Disconnect => {
+                        debug!("Disconnecting slow subscriber {} of topic '{}'", id, topic);
+                        queue.close();
+                        return false;
+                    }
+                }
+            }
+            messages.push_back(message.clone());
+            delivered += 1;
+            queue.notify.notify_one();
+            true
+        });
+        if subscribers.is_empty() {
+            topics.remove(topic);
+        }
+        delivered
+    }
+
+    /// トピックの購読者数
+    pub fn subscriber_count(&self, topic: &str) -> usize {
+        self.hub
+            .topics
+            .lock()
+            .map(|topics| topics.get(topic).map_or(0, Vec::len))
+            .unwrap_or(0)
+    }
+
+    /// 購読者のいるトピック
+    pub fn topics(&self) -> Vec<String> {
+        self.hub
+            .topics
+            .lock()
+            .map(|topics| topics.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// トピックの購読（破棄するとハブから取り除かれる）
+pub struct Subscription<T> {
+    hub: Weak<Hub<T>>,
+    topic: String,
+    id: u64,
+    queue: Arc<Queue<T>>,
+}
+
+impl<T: Send + 'static> Subscription<T> {
+    /// 購読しているトピック
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    /// 次のメッセージを待って受信（購読が終了した場合はNone）
+    pub async fn recv(&mut self) -> Option<T> {
+        loop {
+            if let Some(message) = self.queue.messages.lock().ok().and_then(|mut m| m.pop_front()) {
+                return Some(message);
+            }
+            if self.queue.closed.load(Ordering::SeqCst) {
+                return None;
+            }
+            self.queue.notify.notified().await;
+        }
+    }
+
+    /// メッセージのストリームに変換（`Response::sse`に渡す場合など）
+    pub fn into_stream(self) -> impl Stream<Item = T> + Send {
+        futures::stream::unfold(self, |mut subscription| async move {
+            subscription.recv().await.map(|message| (message, subscription))
+        })
+    }
+}
+
+impl<T> Drop for Subscription<T> {
+    fn drop(&mut self) {
+        let hub = match self.hub.upgrade() {
+            Some(hub) => hub,
+            None => return,
+        };
+        if let Ok(mut topics) = hub.topics.lock() {
+            if let Some(subscribers) = topics.get_mut(&self.topic) {
+                subscribers.retain(|(id, _)| *id != self.id);
+                if subscribers.is_empty() {
+                    topics.remove(&self.topic);
+                }
+            }
+        };
+    }
+}
+
+impl<T> fmt::Debug for Subscription<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Subscription").field("topic", &self.topic).field("id", &self.id).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fan_out_and_unsubscribe() {
+        let hub = PubSub::<String>::new();
+        let mut a = hub.subscribe("news");
+        let b = hub.subscribe("news");
+        let _other = hub.subscribe("sports");
+        assert_eq!(hub.subscriber_count("news"), 2);
+
+        assert_eq!(hub.publish("news", "hello".to_string()), 2);
+        assert_eq!(a.recv().await.as_deref(), Some("hello"));
+
+        // 後から届くメッセージを待って受信
+        let publisher = hub.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            publisher.publish("news", "later".to_string());
+        });
+        assert_eq!(a.recv().await.as_deref(), Some("later"));
+        let received: Vec<String> = b.into_stream().take(2).collect().await;
+        assert_eq!(received, ["hello", "later"]);
+
+        // 破棄した購読はハブから取り除かれる
+        assert_eq!(hub.subscriber_count("news"), 1);
+        drop(a);
+        assert_eq!(hub.subscriber_count("news"), 0);
+        assert_eq!(hub.publish("news", "nobody".to_string()), 0);
+        assert_eq!(hub.topics(), ["sports"]);
+    }
+
+    #[tokio::test]
+    async fn test_drop_policies() {
+        async fn drain(subscription: &mut Subscription<u32>) -> Vec<u32> {
+            let mut received = Vec::new();
+            while let Ok(Some(message)) =
+                tokio::time::timeout(std::time::Duration::from_millis(10), subscription.recv()).await
+            {
+                received.push(message);
+            }
+            received
+        }
+
+        let cases = [
+            (DropPolicy::DropNewest, vec![1, 2]),
+            (DropPolicy::DropOldest, vec![2, 3]),
+            (DropPolicy::Disconnect, vec![1, 2]),
+        ];
+        for (policy, expected) in cases {
+            let hub = PubSub::with_capacity(2, policy);
+            let mut subscription = hub.subscribe("t");
+            for n in 1..=3 {
+                hub.publish("t", n);
+            }
+            assert_eq!(drain(&mut subscription).await, expected, "{:?}", policy);
+        }
+
+        // 切断された購読者は受信済みのメッセージの後に終了する
+        let hub = PubSub::with_capacity(1, DropPolicy::Disconnect);
+        let mut subscription = hub.subscribe("t");
+        hub.publish("t", 1);
+        hub.publish("t", 2);
+        assert_eq!(subscription.recv().await, Some(1));
+        assert_eq!(subscription.recv().await, None);
+        assert_eq!(hub.subscriber_count("t"), 0);
+    }
+}
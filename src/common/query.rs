@@ -0,0 +1,295 @@
+//! 慣習的なクエリパラメータ（ソート・フィルター・ページ指定）のパーサー
+//!
+//! `?sort=-created_at,name&filter[status]=active&page[number]=2&page[size]=20`
+//! のような形式を型付きの構造体に変換します。検証エラーは400として扱われます。
+
+use std::collections::HashMap;
+use crate::error::Error;
+use super::http::Request;
+
+/// ソート方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+/// ソート対象のフィールドと方向
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortField {
+    pub field: String,
+    pub direction: SortDirection,
+}
+
+/// ソート指定（記載順が優先順位）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sort {
+    pub fields: Vec<SortField>,
+}
+
+impl Sort {
+    /// `-created_at,name` 形式の値をパース（`allowed`が空の場合は識別子として妥当なものを許容）
+    pub fn parse(value: &str, allowed: &[String]) -> Result<Self, Error> {
+        let mut fields = Vec::new();
+        for raw in value.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (direction, field) = match raw.strip_prefix('-') {
+                Some(rest) => (SortDirection::Desc, rest),
+                None => (SortDirection::Asc, raw.strip_prefix('+').unwrap_or(raw)),
+            };
+            validate_field_name(field, allowed, "sort")?;
+            if fields.iter().any(|f: &SortField| f.field == field) {
+                return Err(Error::InvalidQueryParameter(format!(
+                    "duplicate sort field: {}",
+                    field
+                )));
+            }
+            fields.push(SortField { field: field.to_string(), direction });
+        }
+        Ok(Self { fields })
+    }
+
+    /// ソート指定が空かどうか
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+/// フィルター指定（`filter[<field>]=<value>`）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filters {
+    values: HashMap<String, String>,
+}
+
+impl Filters {
+    /// フィルター値を取得
+    pub fn get(&self, field: &str) -> Option<&str> {
+        self.values.get(field).map(|s| s.as_str())
+    }
+
+    /// フィルターのイテレーター
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.values.iter()
+    }
+
+    /// フィルター数
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// フィルター指定が空かどうか
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+/// ページ指定（`page[number]` は1始まり）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageRequest {
+    pub number: u64,
+    pub size: u64,
+}
+
+impl PageRequest {
+    /// 取得開始位置（0始まり）
+    pub fn offset(&self) -> u64 {
+        (self.number - 1).saturating_mul(self.size)
+    }
+
+    /// 取得件数
+    pub fn limit(&self) -> u64 {
+        self.size
+    }
+}
+
+/// パース結果一式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListQuery {
+    pub sort: Sort,
+    pub filters: Filters,
+    pub page: PageRequest,
+}
+
+/// 受け入れるソート・フィルター項目とページサイズの設定
+///
+/// `Page<T>`でLinkヘッダーを生成する場合は
+/// `param_names("page[number]", "page[size]")` を指定すると同じ形式になります。
+#[derive(Debug, Clone)]
+pub struct QuerySpec {
+    sortable: Vec<String>,
+    filterable: Vec<String>,
+    default_page_size: u64,
+    max_page_size: u64,
+}
+
+impl Default for QuerySpec {
+    fn default() -> Self {
+        Self {
+            sortable: Vec::new(),
+            filterable: Vec::new(),
+            default_page_size: 20,
+            max_page_size: 100,
+        }
+    }
+}
+
+impl QuerySpec {
+    /// 新しいQuerySpecを作成（既定ページサイズ20、最大100）
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ソート可能なフィールドを設定
+    pub fn sortable<I, S>(mut self, fields: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.sortable = fields.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// フィルター可能なフィールドを設定
+    pub fn filterable<I, S>(mut self, fields: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.filterable = fields.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// 既定ページサイズと最大ページサイズを設定
+    pub fn page_size(mut self, default_size: u64, max_size: u64) -> Self {
+        self.max_page_size = max_size.max(1);
+        self.default_page_size = default_size.clamp(1, self.max_page_size);
+        self
+    }
+
+    /// クエリパラメータをパース
+    pub fn parse(&self, params: &HashMap<String, String>) -> Result<ListQuery, Error> {
+        let sort = match params.get("sort") {
+            Some(value) => Sort::parse(value, &self.sortable)?,
+            None => Sort::default(),
+        };
+
+        let mut filters = Filters::default();
+        for (key, value) in params {
+            if let Some(field) = key.strip_prefix("filter[").and_then(|k| k.strip_suffix(']')) {
+                validate_field_name(field, &self.filterable, "filter")?;
+                filters.values.insert(field.to_string(), value.clone());
+            }
+        }
+
+        let number = match params.get("page[number]") {
+            Some(v) => parse_positive(v, "page[number]")?,
+            None => 1,
+        };
+        let size = match params.get("page[size]") {
+            Some(v) => parse_positive(v, "page[size]")?,
+            None => self.default_page_size,
+        };
+        if size > self.max_page_size {
+            return Err(Error::InvalidQueryParameter(format!(
+                "page[size] must be at most {}",
+                self.max_page_size
+            )));
+        }
+
+        Ok(ListQuery {
+            sort,
+            filters,
+            page: PageRequest { number, size },
+        })
+    }
+
+    /// リクエストのクエリパラメータをパース
+    pub fn from_request(&self, req: &Request) -> Result<ListQuery, Error> {
+        self.parse(&req.query_params)
+    }
+}
+
+/// フィールド名の検証（許可リスト指定時はそれに限定）
+fn validate_field_name(field: &str, allowed: &[String], kind: &str) -> Result<(), Error> {
+    let is_identifier = !field.is_empty()
+        && field.len() <= 64
+        && field.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+    if !is_identifier || (!allowed.is_empty() && !allowed.iter().any(|a| a == field)) {
+        return Err(Error::InvalidQueryParameter(format!(
+            "unsupported {} field: {}",
+            kind, field
+        )));
+    }
+    Ok(())
+}
+
+/// 1以上の整数をパース
+fn parse_positive(value: &str, name: &str) -> Result<u64, Error> {
+    match value.trim().parse::<u64>() {
+        Ok(n) if n >= 1 => Ok(n),
+        _ => Err(Error::InvalidQueryParameter(format!(
+            "{} must be a positive integer",
+            name
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::parse_query_string;
+
+    #[test]
+    fn test_parse_full_query() {
+        let params = parse_query_string(
+            "sort=-created_at,name&filter%5Bstatus%5D=active&filter[owner]=me&page[number]=3&page[size]=10",
+        );
+        let spec = QuerySpec::new()
+            .sortable(["created_at", "name"])
+            .filterable(["status", "owner"]);
+        let query = spec.parse(&params).unwrap();
+
+        assert_eq!(
+            query.sort.fields,
+            vec![
+                SortField { field: "created_at".into(), direction: SortDirection::Desc },
+                SortField { field: "name".into(), direction: SortDirection::Asc },
+            ]
+        );
+        assert_eq!(query.filters.get("status"), Some("active"));
+        assert_eq!(query.filters.get("owner"), Some("me"));
+        assert_eq!(query.page, PageRequest { number: 3, size: 10 });
+        assert_eq!(query.page.offset(), 20);
+    }
+
+    #[test]
+    fn test_defaults() {
+        let query = QuerySpec::new().page_size(25, 50).parse(&HashMap::new()).unwrap();
+        assert!(query.sort.is_empty());
+        assert!(query.filters.is_empty());
+        assert_eq!(query.page, PageRequest { number: 1, size: 25 });
+    }
+
+    #[test]
+    fn test_validation_errors_map_to_400() {
+        let spec = QuerySpec::new().sortable(["name"]).filterable(["status"]);
+        let cases = [
+            "sort=password",
+            "sort=name,-name",
+            "filter[secret]=1",
+            "page[number]=0",
+            "page[size]=abc",
+            "page[size]=1000",
+        ];
+        for case in cases {
+            let err = spec.parse(&parse_query_string(case)).unwrap_err();
+            assert!(matches!(err, Error::InvalidQueryParameter(_)), "case: {}", case);
+            assert_eq!(err.status_code(), 400);
+        }
+    }
+
+    #[test]
+    fn test_sort_without_allow_list_requires_identifier() {
+        assert!(Sort::parse("created_at,+name", &[]).is_ok());
+        assert!(Sort::parse("name;drop", &[]).is_err());
+    }
+}
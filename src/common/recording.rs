@@ -0,0 +1,267 @@
+//! リクエスト/レスポンスの記録（デバッグ用）
+//!
+//! `TrafficRecorder`をAroundMiddlewareとして登録すると、ハンドラーに渡ったリクエストと
+//! その結果のレスポンスをセンシティブな値を伏せ字にしたうえで記録します。
+//! 記録はJSON Lines形式で、`runbridge::testing::replay`でローカルに再生できます。
+//! 本番環境で有効にする場合は、記録先の保護とディスク容量に注意してください。
+
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+use super::http::{Request, Response};
+use super::interop::response_serde;
+use super::redaction::is_sensitive_key_like;
+use super::traits::{AroundMiddleware, Next};
+
+const REDACTED: &str = "***redacted***";
+
+/// 記録するボディの既定の最大サイズ（バイト）
+pub const DEFAULT_MAX_RECORDED_BODY: usize = 64 * 1024;
+
+/// 記録された1組のリクエストとレスポンス
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordedExchange {
+    /// 記録時刻（RFC 3339）
+    pub recorded_at: String,
+    /// マッチしたルート（ルート名またはパスパターン）
+    #[serde(default)]
+    pub route: Option<String>,
+    /// ボディが上限を超えたため記録から除外されたかどうか
+    #[serde(default)]
+    pub body_omitted: bool,
+    /// 伏せ字済みのリクエスト
+    pub request: Request,
+    /// 伏せ字済みのレスポンス（ハンドラーのエラーはエラーレスポンスとして記録）
+    #[serde(with = "response_serde")]
+    pub response: Response,
+}
+
+impl RecordedExchange {
+    /// JSON Linesの1行からデシリアライズ
+    pub fn from_json_line(line: &str) -> Result<Self, Error> {
+        serde_json::from_str(line)
+            .map_err(|e| Error::InvalidRequestBody(format!("Invalid recorded exchange: {}", e)))
+    }
+}
+
+enum Sink {
+    Ring { buffer: Mutex<VecDeque<String>>, capacity: usize },
+    File { path: PathBuf, lock: Mutex<()> },
+}
+
+/// リクエスト/レスポンスをリングバッファまたはファイルに記録するAroundMiddleware
+///
+/// Cloneしたインスタンスは同じ記録先を共有するため、1つをビルダーに登録し、
+/// もう1つを`recordings`での参照に使用できます。
+#[derive(Clone)]
+pub struct TrafficRecorder {
+    sink: Arc<Sink>,
+    max_body_size: usize,
+}
+
+impl TrafficRecorder {
+    /// 直近`capacity`件をメモリに保持する記録器を作成
+    pub fn ring_buffer(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            sink: Arc::new(Sink::Ring { buffer: Mutex::new(VecDeque::with_capacity(capacity)), capacity }),
+            max_body_size: DEFAULT_MAX_RECORDED_BODY,
+        }
+    }
+
+    /// ファイルにJSON Lines形式で追記する記録器を作成
+    pub fn file(path: impl Into<PathBuf>) -> Self {
+        Self {
+            sink: Arc::new(Sink::File { path: path.into(), lock: Mutex::new(()) }),
+            max_body_size: DEFAULT_MAX_RECORDED_BODY,
+        }
+    }
+
+    /// 記録するボディの最大サイズ（超えたボディは記録しない）
+    pub fn max_body_size(mut self, size: usize) -> Self {
+        self.max_body_size = size;
+        self
+    }
+
+    /// リングバッファに保持している記録（古い順、ファイルに記録する場合は空）
+    pub fn recordings(&self) -> Vec<RecordedExchange> {
+        self.json_lines()
+            .iter()
+            .filter_map(|line| RecordedExchange::from_json_line(line).ok())
+            .collect()
+    }
+
+    /// リングバッファに保持している記録をJSON Lines形式で取得
+    pub fn json_lines(&self) -> Vec<String> {
+        match self.sink.as_ref() {
+            Sink::Ring { buffer, .. } => match buffer.lock() {
+                Ok(buffer) => buffer.iter().cloned().collect(),
+                Err(_) => Vec::new(),
+            },
+            Sink::File { .. } => Vec::new(),
+        }
+    }
+
+    fn store(&self, exchange: &RecordedExchange) {
+        let line = match serde_json::to_string(exchange) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!("Failed to serialize recorded exchange: {}", e);
+                return;
+            }
+        };
+        match self.sink.as_ref() {
+            Sink::Ring { buffer, capacity } => {
+                if let Ok(mut buffer) = buffer.lock() {
+                    if buffer.len() == *capacity {
+                        buffer.pop_front();
+                    }
+                    buffer.push_back(line);
+                }
+            }
+            Sink::File { path, lock } => {
+                let _guard = lock.lock();
+                let result = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .and_then(|mut file| writeln!(file, "{}", line));
+                if let Err(e) = result {
+                    warn!("Failed to write recorded exchange to {}: {}", path.display(), e);
+                }
+            }
+        }
+    }
+
+    /// 記録用のリクエストのコピー（伏せ字済み）を作成
+    fn snapshot_request(&self, req: &Request, omitted: &mut bool) -> Request {
+        let mut copy = Request::new(req.method, req.path.clone());
+        copy.query_params = req
+            .query_params
+            .iter()
+            .map(|(k, v)| (k.clone(), redact(k, v)))
+            .collect();
+        copy.headers = req.headers.iter().map(|(k, v)| (k.clone(), redact(k, v))).collect();
+        copy.body = self.limit_body(req.body.as_ref(), omitted);
+        copy
+    }
+
+    fn snapshot_response(&self, res: &Response, omitted: &mut bool) -> Response {
+        Response {
+            status: res.status,
+            headers: res.headers.iter().map(|(k, v)| (k.clone(), redact(k, v))).collect(),
+            body: self.limit_body(res.body.as_ref(), omitted),
+            cookies: res.cookies.iter().map(|_| REDACTED.to_string()).collect(),
+            event_stream: None,
+        }
+    }
+
+    fn limit_body(&self, body: Option<&Vec<u8>>, omitted: &mut bool) -> Option<Vec<u8>> {
+        match body {
+            Some(body) if body.len() > self.max_body_size => {
+                *omitted = true;
+                None
+            }
+            other => other.cloned(),
+        }
+    }
+}
+
+fn redact(key: &str, value: &str) -> String {
+    if is_sensitive_key_like(&key.to_ascii_lowercase()) {
+        REDACTED.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+#[async_trait]
+impl AroundMiddleware for TrafficRecorder {
+    async fn around(&self, req: Request, next: Next<'_>) -> Result<Response, Error> {
+        let mut body_omitted = false;
+        let request = self.snapshot_request(&req, &mut body_omitted);
+        let route = req.matched_route().map(|r| r.label().to_string());
+
+        let result = next.run(req).await;
+        let response = match &result {
+            Ok(res) => self.snapshot_response(res, &mut body_omitted),
+            Err(e) => Response::from_error(e),
+        };
+
+        self.store(&RecordedExchange {
+            recorded_at: chrono::Utc::now().to_rfc3339(),
+            route,
+            body_omitted,
+            request,
+            response,
+        });
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Method;
+    use crate::testing::raw_handler;
+
+    async fn record(recorder: &TrafficRecorder, req: Request) {
+        // パスをボディとして返し、Set-Cookieを付与するハンドラー
+        let handler = raw_handler("^/items$", |req| {
+            Ok(Response::ok().with_header("Set-Cookie", "session=abc").with_body(req.path.into_bytes()))
+        });
+        let around: Vec<Box<dyn AroundMiddleware>> = vec![Box::new(recorder.clone())];
+        let res = Next::new(&handler, &around).run(req).await.unwrap();
+        // 記録は複製に対して行われ、実際のレスポンスは伏せ字にならない
+        assert_eq!(res.headers.get("Set-Cookie").map(String::as_str), Some("session=abc"));
+    }
+
+    #[tokio::test]
+    async fn test_ring_buffer_records_redacted_exchanges() {
+        let recorder = TrafficRecorder::ring_buffer(2);
+        for id in ["1", "2", "3"] {
+            let req = Request::new(Method::GET, "/items".to_string())
+                .with_query_param("id", id)
+                .with_query_param("api_key", "k")
+                .with_header("Authorization", "Bearer secret");
+            record(&recorder, req).await;
+        }
+
+        let recordings = recorder.recordings();
+        assert_eq!(recordings.len(), 2);
+        let first = &recordings[0];
+        assert_eq!(first.request.query_params.get("id").map(String::as_str), Some("2"));
+        assert_eq!(first.request.query_params.get("api_key").map(String::as_str), Some(REDACTED));
+        assert_eq!(first.request.headers.get("authorization").map(String::as_str), Some(REDACTED));
+        assert_eq!(first.response.headers.get("Set-Cookie").map(String::as_str), Some(REDACTED));
+        assert_eq!(first.response.body.as_deref(), Some(&b"/items"[..]));
+    }
+
+    #[tokio::test]
+    async fn test_file_sink_and_body_limit() {
+        let path = std::env::temp_dir().join(format!("runbridge-recording-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let recorder = TrafficRecorder::file(&path).max_body_size(4);
+
+        let req = Request::new(Method::GET, "/items".to_string()).with_body(b"too large".to_vec());
+        record(&recorder, req).await;
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 1);
+        let exchange = RecordedExchange::from_json_line(lines[0]).unwrap();
+        assert!(exchange.body_omitted);
+        assert!(exchange.request.body.is_none());
+        assert!(exchange.response.body.is_none());
+        assert!(recorder.recordings().is_empty());
+    }
+}
@@ -0,0 +1,138 @@
+//! ログ出力時のセンシティブ情報のマスク処理
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// ログ出力用に値をマスク（センシティブなキーは伏せ字、長い値は切り詰め）
+pub fn redact_value_for_log(key: &str, value: &str) -> String {
+    let key_l = key.to_ascii_lowercase();
+    if key_l == "query_string" {
+        return redact_query_string(value);
+    }
+    if is_sensitive_key_like(&key_l) {
+        return "***redacted***".to_string();
+    }
+    // 長すぎる値は truncate（例：User-Agent）
+    if value.len() > 200 {
+        format!("{}...[truncated]", &value[..200])
+    } else {
+        value.to_string()
+    }
+}
+
+/// キー名（小文字）がセンシティブな情報を示すかどうか
+pub fn is_sensitive_key_like(lower_key: &str) -> bool {
+    let patterns = [
+        "authorization",
+        "cookie",
+        "token",
+        "secret",
+        "password",
+        "pass",
+        "api-key",
+        "api_key",
+        "apikey",
+        "x-api-key",
+        "x_api_key",
+        "jwt",
+        "auth",
+        "session",
+        "csrf",
+        "signature",
+        "private",
+        "key",
+        "credential",
+        "access_token",
+        "refresh_token",
+        "bearer",
+        "basic",
+    ];
+    patterns.iter().any(|p| lower_key.contains(p))
+}
+
+/// クエリ文字列のうちセンシティブなキーの値を伏せ字にする
+pub fn redact_query_string(qs: &str) -> String {
+    if qs.is_empty() { return qs.to_string(); }
+    let mut out_parts = Vec::new();
+    for part in qs.split('&') {
+        if part.is_empty() { continue; }
+        let mut it = part.splitn(2, '=');
+        let k = it.next().unwrap_or("");
+        let v = it.next().unwrap_or("");
+        let k_l = k.to_ascii_lowercase();
+        if is_sensitive_key_like(&k_l) {
+            out_parts.push(format!("{}=***redacted***", k));
+        } else {
+            out_parts.push(format!("{}={}", k, v));
+        }
+    }
+    out_parts.join("&")
+}
+/// `Debug`出力に含めるボディの最大長（バイト）
+pub const MAX_DEBUG_BODY_LEN: usize = 128;
+
+const REDACTED: &str = "***redacted***";
+
+/// センシティブなキーの値を伏せ字にしてキー順に出力するマップ（`Debug`用）
+pub(crate) struct RedactedMap<'a>(pub(crate) &'a HashMap<String, String>);
+
+impl fmt::Debug for RedactedMap<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut entries: Vec<(&String, &String)> = self.0.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        f.debug_map()
+            .entries(entries.into_iter().map(|(k, v)| {
+                let value = if is_sensitive_key_like(&k.to_ascii_lowercase()) { REDACTED } else { v.as_str() };
+                (k, value)
+            }))
+            .finish()
+    }
+}
+
+/// 長さを示し、先頭だけを出力するボディ（`Debug`用、バイナリは長さのみ）
+pub(crate) struct TruncatedBody<'a>(pub(crate) Option<&'a [u8]>);
+
+impl fmt::Debug for TruncatedBody<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let body = match self.0 {
+            Some(body) => body,
+            None => return f.write_str("None"),
+        };
+        let head = &body[..body.len().min(MAX_DEBUG_BODY_LEN)];
+        // 切り詰めた位置がUTF-8の途中でもテキストとして扱う
+        let text = match std::str::from_utf8(head) {
+            Ok(text) => Some(text),
+            Err(e) if e.error_len().is_none() => std::str::from_utf8(&head[..e.valid_up_to()]).ok(),
+            Err(_) => None,
+        };
+        match text {
+            Some(text) if text.len() < body.len() => write!(f, "{:?}...[{} bytes]", text, body.len()),
+            Some(text) => write!(f, "{:?}", text),
+            None => write!(f, "<binary data of {} bytes>", body.len()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redacted_debug_helpers() {
+        let headers = HashMap::from([
+            ("authorization".to_string(), "Bearer abc".to_string()),
+            ("accept".to_string(), "*/*".to_string()),
+        ]);
+        assert_eq!(
+            format!("{:?}", RedactedMap(&headers)),
+            r#"{"accept": "*/*", "authorization": "***redacted***"}"#
+        );
+
+        assert_eq!(format!("{:?}", TruncatedBody(Some(b"hi"))), r#""hi""#);
+        assert_eq!(format!("{:?}", TruncatedBody(None)), "None");
+        let long = "あ".repeat(100);
+        let out = format!("{:?}", TruncatedBody(Some(long.as_bytes())));
+        assert!(out.ends_with("...[300 bytes]"), "{}", out);
+        assert_eq!(format!("{:?}", TruncatedBody(Some(&[0xff, 0xfe]))), "<binary data of 2 bytes>");
+    }
+}
@@ -0,0 +1,82 @@
+//! リクエストIDの解決とタスクローカルへの保持
+
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::http::Request;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// リクエストIDをタスクローカルに設定して処理を実行
+pub async fn with_request_id<F: Future>(request_id: String, fut: F) -> F::Output {
+    REQUEST_ID.scope(request_id, fut).await
+}
+
+/// 現在のタスクのリクエストIDを取得
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// リクエストヘッダーからリクエストIDを取得（`X-Request-Id` -> `X-Amzn-Trace-Id` -> `X-Cloud-Trace-Context`）
+pub fn request_id_from_headers(req: &Request) -> Option<String> {
+    ["x-request-id", "x-amzn-trace-id", "x-cloud-trace-context"]
+        .iter()
+        .filter_map(|name| req.headers.get(*name))
+        .find_map(|v| sanitize_request_id(v))
+}
+
+/// 外部から受け取ったリクエストIDを検証（空・200文字超・表示可能なASCII以外を含む値は不採用）
+pub fn sanitize_request_id(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() || value.len() > 200 || !value.chars().all(|c| c.is_ascii_graphic()) {
+        return None;
+    }
+    Some(value.to_string())
+}
+
+/// リクエストIDを生成（時刻とプロセス内カウンターによる一意な値）
+pub fn generate_request_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    format!(
+        "{:x}-{:x}-{:x}",
+        nanos,
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Method;
+
+    #[test]
+    fn test_request_id_from_headers() {
+        let req = Request::new(Method::GET, "/".to_string())
+            .with_header("X-Amzn-Trace-Id", "Root=1-abc")
+            .with_header("X-Request-Id", "req-1");
+        assert_eq!(request_id_from_headers(&req).as_deref(), Some("req-1"));
+
+        let req = Request::new(Method::GET, "/".to_string()).with_header("X-Request-Id", "bad\u{7f}");
+        assert_eq!(request_id_from_headers(&req), None);
+    }
+
+    #[tokio::test]
+    async fn test_request_id_task_local() {
+        assert_eq!(current_request_id(), None);
+        let id = with_request_id("req-42".to_string(), async { current_request_id() }).await;
+        assert_eq!(id.as_deref(), Some("req-42"));
+    }
+
+    #[test]
+    fn test_generate_request_id_is_unique() {
+        assert_ne!(generate_request_id(), generate_request_id());
+    }
+}
@@ -0,0 +1,466 @@
+//! マッチしたルート情報のリクエストへの受け渡し
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use regex::Regex;
+use serde::Serialize;
+use crate::error::Error;
+use super::body_fields::BodyFieldPolicy;
+use super::body_policy::BodyPolicy;
+use super::http::{Method, Request};
+use super::openapi::OperationDoc;
+use super::security::SecurityProfile;
+use super::traits::Handler;
+use super::utils::{percent_decode, percent_encode};
+
+/// RequestContextに格納する際のキー
+pub const MATCHED_ROUTE_CONTEXT_KEY: &str = "runbridge.matched_route";
+
+/// ルートテーブルをRequestContextに格納する際のキー
+pub const ROUTE_TABLE_CONTEXT_KEY: &str = "runbridge.route_table";
+
+/// パスパラメータをRequestContextに格納する際のキー
+pub const PATH_PARAMS_CONTEXT_KEY: &str = "runbridge.path_params";
+
+/// リクエストにマッチしたルート（ログやメトリクスでは生のパスの代わりにこちらを使う）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchedRoute {
+    /// ルートのパスパターン（アンカー付き正規表現）
+    pub pattern: String,
+    /// ルート名
+    pub name: Option<String>,
+}
+
+impl MatchedRoute {
+    /// ハンドラーからルート情報を作成
+    pub fn from_handler(handler: &dyn Handler) -> Self {
+        Self {
+            pattern: handler.path_pattern().to_string(),
+            name: handler.route_name().map(|n| n.to_string()),
+        }
+    }
+
+    /// ルート名があればルート名、なければパターン（低カーディナリティのラベル用）
+    pub fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.pattern)
+    }
+}
+
+/// ルート固有の設定（`HandlerExt`・`RouterGroup`で設定、未設定の項目は全体設定に従う）
+///
+/// `Handler::options`で参照します。ハンドラーを包むラッパーは内側のハンドラーの設定を引き継ぎ、
+/// 変更する項目だけを置き換えます。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RouteOptions {
+    /// ルート名（`HandlerExt::name`）
+    pub name: Option<String>,
+    /// GET/HEAD/DELETEのボディに対する方針（`HandlerExt::with_body_policy`）
+    pub body_policy: Option<BodyPolicy>,
+    /// リクエストボディの上限（バイト、`HandlerExt::max_body_size`）
+    pub body_size_limit: Option<usize>,
+    /// 型付きボディのフィールドの扱い（`HandlerExt::body_fields`）
+    pub body_field_policy: Option<BodyFieldPolicy>,
+    /// OpenAPIドキュメントに出力するメタデータ（`HandlerExt::openapi`）
+    pub operation_doc: Option<OperationDoc>,
+    /// セキュリティヘッダーのプロファイル（`RouterGroup::security_profile`）
+    pub security_profile: Option<SecurityProfile>,
+}
+
+/// 設定の無いハンドラーが返すRouteOptions
+static NO_ROUTE_OPTIONS: RouteOptions = RouteOptions::new();
+
+impl RouteOptions {
+    /// 全ての項目が未設定のRouteOptionsを作成
+    pub const fn new() -> Self {
+        Self {
+            name: None,
+            body_policy: None,
+            body_size_limit: None,
+            body_field_policy: None,
+            operation_doc: None,
+            security_profile: None,
+        }
+    }
+
+    /// 全ての項目が未設定のRouteOptions（`Handler::options`の既定値）
+    pub fn none() -> &'static Self {
+        &NO_ROUTE_OPTIONS
+    }
+}
+
+/// マッチしたルートのパスパラメータ（名前付きキャプチャの値、パーセントデコード済み）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PathParams(Vec<(String, String)>);
+
+impl PathParams {
+    /// パターンの名前付きキャプチャをパスから取り出す（マッチしない場合は空）
+    pub fn extract(pattern: &str, path: &str) -> Self {
+        // 名前付きキャプチャの無いパターンは正規表現を使わない
+        if !pattern.contains("(?P<") && !pattern.contains("(?<") {
+            return Self::default();
+        }
+        let regex = match cached_regex(pattern) {
+            Some(regex) => regex,
+            None => return Self::default(),
+        };
+        let captures = match regex.captures(path) {
+            Some(captures) => captures,
+            None => return Self::default(),
+        };
+        Self(
+            regex
+                .capture_names()
+                .flatten()
+                .filter_map(|name| {
+                    captures
+                        .name(name)
+                        .map(|m| (name.to_string(), percent_decode(m.as_str())))
+                })
+                .collect(),
+        )
+    }
+
+    /// パラメータの値を取得
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+    }
+
+    /// パラメータの一覧（パターン中の順）
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// パラメータが無いかどうか
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// パターンごとにコンパイル済みの正規表現をキャッシュ（ハンドラーの数だけ保持）
+fn cached_regex(pattern: &str) -> Option<Arc<Regex>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, Arc<Regex>>>> = OnceLock::new();
+    let mut cache = CACHE.get_or_init(Default::default).lock().ok()?;
+    if let Some(regex) = cache.get(pattern) {
+        return Some(regex.clone());
+    }
+    let regex = Arc::new(Regex::new(pattern).ok()?);
+    cache.insert(pattern.to_string(), regex.clone());
+    Some(regex)
+}
+
+/// 登録済みのルートの情報（ルート一覧・ドキュメント生成用）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RouteInfo {
+    /// 受け付けるHTTPメソッド（空の場合は不明）
+    pub methods: Vec<Method>,
+    /// ルートのパスパターン
+    pub pattern: String,
+    /// ルート名
+    pub name: Option<String>,
+}
+
+impl RouteInfo {
+    /// ハンドラーからルート情報を作成
+    pub fn from_handler(handler: &dyn Handler) -> Self {
+        Self {
+            methods: handler.methods(),
+            pattern: handler.path_pattern().to_string(),
+            name: handler.route_name().map(|n| n.to_string()),
+        }
+    }
+
+    /// `/items/{id}`形式のパステンプレート（リテラルと名前付きキャプチャ以外を含むパターンではNone）
+    pub fn template(&self) -> Option<String> {
+        substitute_groups(&self.pattern, |name| Ok(format!("{{{}}}", name))).ok()
+    }
+
+    /// パスパラメータ名（名前付きキャプチャ、出現順）
+    pub fn param_names(&self) -> Vec<String> {
+        Regex::new(&self.pattern)
+            .map(|regex| regex.capture_names().flatten().map(str::to_string).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// 正規表現としてコンパイルできないルートのパターン（`RunBridge::validate`で検出）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternError {
+    /// ルートのパスパターン
+    pub pattern: String,
+    /// ルート名
+    pub name: Option<String>,
+    /// 正規表現エンジンのエラーメッセージ
+    pub message: String,
+}
+
+impl PatternError {
+    /// ハンドラーのパターンを検証（正常な場合はNone）
+    pub fn check(handler: &dyn Handler) -> Option<Self> {
+        let pattern = handler.path_pattern();
+        Regex::new(pattern).err().map(|e| Self {
+            pattern: pattern.to_string(),
+            name: handler.route_name().map(|n| n.to_string()),
+            message: e.to_string(),
+        })
+    }
+}
+
+impl std::fmt::Display for PatternError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "route '{}' has invalid pattern '{}': {}", name, self.pattern, self.message),
+            None => write!(f, "invalid route pattern '{}': {}", self.pattern, self.message),
+        }
+    }
+}
+
+/// ルート名からURLを逆引きするためのテーブル
+///
+/// パスパラメータはパターン中の名前付きキャプチャ（`(?P<id>\d+)` / `(?<id>\d+)`）で表します。
+/// 逆引きできるのはキャプチャ以外がリテラル（エスケープ済み記号を含む）のパターンのみです。
+#[derive(Debug, Clone, Default)]
+pub struct RouteTable {
+    patterns: HashMap<String, String>,
+}
+
+impl RouteTable {
+    /// 空のテーブルを作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 名前付きハンドラーからテーブルを作成（重複した名前は先に登録されたものを優先）
+    pub fn from_handlers<'a, I>(handlers: I) -> Self
+    where
+        I: IntoIterator<Item = &'a dyn Handler>,
+    {
+        let mut table = Self::new();
+        for handler in handlers {
+            if let Some(name) = handler.route_name() {
+                table.insert(name, handler.path_pattern());
+            }
+        }
+        table
+    }
+
+    /// ルートを登録（既に同名のルートがある場合は無視して警告）
+    pub fn insert(&mut self, name: &str, pattern: &str) {
+        if self.patterns.contains_key(name) {
+            log::warn!("Duplicate route name '{}' ignored for pattern '{}'", name, pattern);
+            return;
+        }
+        self.patterns.insert(name.to_string(), pattern.to_string());
+    }
+
+    /// ルート名とパスパラメータからURLパスを生成
+    pub fn url_for(&self, name: &str, params: &[(&str, &str)]) -> Result<String, Error> {
+        let pattern = self
+            .patterns
+            .get(name)
+            .ok_or_else(|| Error::ConfigurationError(format!("Unknown route name: {}", name)))?;
+        reverse_pattern(pattern, params)
+            .map_err(|e| Error::ConfigurationError(format!("Cannot build URL for route '{}': {}", name, e)))
+    }
+}
+
+/// パターンの名前付きキャプチャをパラメータで置き換えてパスを生成
+fn reverse_pattern(pattern: &str, params: &[(&str, &str)]) -> Result<String, String> {
+    let mut used = Vec::new();
+    let path = substitute_groups(pattern, |group_name| {
+        let value = params
+            .iter()
+            .find(|(k, _)| *k == group_name)
+            .map(|(_, v)| *v)
+            .ok_or_else(|| format!("missing parameter '{}'", group_name))?;
+        used.push(group_name.to_string());
+        Ok(percent_encode(value))
+    })?;
+
+    if let Some((unknown, _)) = params.iter().find(|(k, _)| !used.iter().any(|u| u == k)) {
+        return Err(format!("unknown parameter '{}'", unknown));
+    }
+
+    // 生成したパスが元のパターンにマッチすることを確認（値の形式チェック）
+    let regex = Regex::new(pattern).map_err(|e| e.to_string())?;
+    if !regex.is_match(&path) {
+        return Err(format!("generated path '{}' does not match the route pattern", path));
+    }
+    Ok(path)
+}
+
+/// パターンをリテラル部分と名前付きキャプチャに分解し、キャプチャを`replace`の結果で置き換える
+///
+/// 名前付きキャプチャの外に正規表現の構文がある場合はエラーを返します。
+fn substitute_groups<F>(pattern: &str, mut replace: F) -> Result<String, String>
+where
+    F: FnMut(&str) -> Result<String, String>,
+{
+    let inner = pattern.strip_prefix('^').unwrap_or(pattern);
+    let inner = inner.strip_suffix('$').unwrap_or(inner);
+    let chars: Vec<char> = inner.chars().collect();
+    let mut path = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => {
+                let escaped = *chars.get(i + 1).ok_or("pattern ends with a backslash")?;
+                if escaped.is_ascii_alphanumeric() {
+                    return Err(format!("unsupported escape '\\{}' outside a named group", escaped));
+                }
+                path.push(escaped);
+                i += 2;
+            }
+            '(' => {
+                let rest: String = chars[i..].iter().collect();
+                let name_start = if rest.starts_with("(?P<") {
+                    4
+                } else if rest.starts_with("(?<") {
+                    3
+                } else {
+                    return Err("only named groups can be reversed".to_string());
+                };
+                let name_len = rest[name_start..]
+                    .find('>')
+                    .ok_or("unterminated group name")?;
+                let group_name = &rest[name_start..name_start + name_len];
+                path.push_str(&replace(group_name)?);
+                i = group_end(&chars, i)? + 1;
+            }
+            c if "[]{}*+?|.^$)".contains(c) => {
+                return Err(format!("unsupported regex syntax '{}' outside a named group", c));
+            }
+            c => {
+                path.push(c);
+                i += 1;
+            }
+        }
+    }
+    Ok(path)
+}
+
+/// `start`位置の開き括弧に対応する閉じ括弧の位置（エスケープと文字クラスを考慮）
+fn group_end(chars: &[char], start: usize) -> Result<usize, String> {
+    let mut depth = 0;
+    let mut in_class = false;
+    let mut i = start;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '[' if !in_class => in_class = true,
+            ']' if in_class => in_class = false,
+            '(' if !in_class => depth += 1,
+            ')' if !in_class => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(i);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    Err("unbalanced parentheses".to_string())
+}
+
+impl Request {
+    /// ルート名とパスパラメータからURLパスを生成（各ランタイムが格納したルートテーブルを使用）
+    pub fn url_for(&self, name: &str, params: &[(&str, &str)]) -> Result<String, Error> {
+        self.context()
+            .get::<Arc<RouteTable>>(ROUTE_TABLE_CONTEXT_KEY)
+            .ok_or_else(|| Error::ConfigurationError("Route table is not available".to_string()))?
+            .url_for(name, params)
+    }
+
+    /// マッチしたルート情報を取得（各ランタイムがミドルウェア前処理の前に設定）
+    pub fn matched_route(&self) -> Option<&MatchedRoute> {
+        self.context().get::<MatchedRoute>(MATCHED_ROUTE_CONTEXT_KEY)
+    }
+
+    /// マッチしたルート情報とパスパラメータを設定
+    pub fn set_matched_route(&mut self, handler: &dyn Handler) {
+        let params = PathParams::extract(handler.path_pattern(), &self.path);
+        self.context_mut().set(PATH_PARAMS_CONTEXT_KEY, params);
+        self.context_mut()
+            .set(MATCHED_ROUTE_CONTEXT_KEY, MatchedRoute::from_handler(handler));
+    }
+
+    /// パスパラメータを取得（`/items/{id}`や`(?P<id>\d+)`のようなパターンの名前付きキャプチャ）
+    ///
+    /// 全ランタイムでミドルウェア前処理の前に設定されます。
+    pub fn path_params(&self) -> &PathParams {
+        static EMPTY: PathParams = PathParams(Vec::new());
+        self.context()
+            .get::<PathParams>(PATH_PARAMS_CONTEXT_KEY)
+            .unwrap_or(&EMPTY)
+    }
+
+    /// パスパラメータを型変換して取得
+    /// （パラメータが無い場合や変換できない場合は`InvalidPathParameter`（400））
+    pub fn path_param<T: FromStr>(&self, name: &str) -> Result<T, Error> {
+        let value = self
+            .path_params()
+            .get(name)
+            .ok_or_else(|| Error::InvalidPathParameter(format!("Missing path parameter: {}", name)))?;
+        value
+            .parse()
+            .map_err(|_| Error::InvalidPathParameter(format!("Invalid value for path parameter '{}'", name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> RouteTable {
+        let mut table = RouteTable::new();
+        table.insert("get_item", r"^/items/(?P<id>\d+)$");
+        table.insert("get_file", r"^/files/(?<bucket>[a-z]+)/(?<key>[^/]+)\.json$");
+        table.insert("regex_only", r"^/search/.*$");
+        table
+    }
+
+    #[test]
+    fn test_url_for_named_groups() {
+        let table = table();
+        assert_eq!(table.url_for("get_item", &[("id", "42")]).unwrap(), "/items/42");
+        assert_eq!(
+            table.url_for("get_file", &[("bucket", "docs"), ("key", "a b")]).unwrap(),
+            "/files/docs/a%20b.json"
+        );
+    }
+
+    #[test]
+    fn test_url_for_errors() {
+        let table = table();
+        for (name, params) in [
+            ("missing", vec![]),
+            ("get_item", vec![]),
+            ("get_item", vec![("id", "abc")]),
+            ("get_item", vec![("id", "1"), ("extra", "2")]),
+            ("regex_only", vec![]),
+        ] {
+            let err = table.url_for(name, &params).unwrap_err();
+            assert!(matches!(err, Error::ConfigurationError(_)), "{}: {:?}", name, err);
+        }
+    }
+
+    #[test]
+    fn test_path_params_extract() {
+        let params = PathParams::extract(r"^/files/(?<bucket>[a-z]+)/(?P<key>[^/]+)$", "/files/docs/a%20b");
+        assert_eq!(params.get("bucket"), Some("docs"));
+        assert_eq!(params.get("key"), Some("a b"));
+        assert_eq!(params.iter().map(|(k, _)| k).collect::<Vec<_>>(), vec!["bucket", "key"]);
+        assert!(PathParams::extract(r"^/files/(?<bucket>[a-z]+)$", "/other").is_empty());
+        assert!(PathParams::extract(r"^/search/.*$", "/search/x").is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_name_keeps_first() {
+        let mut table = table();
+        table.insert("get_item", "^/other$");
+        assert_eq!(table.url_for("get_item", &[("id", "1")]).unwrap(), "/items/1");
+    }
+}
@@ -0,0 +1,162 @@
+//! コンパイル済みルーター（静的なセグメントによる候補の絞り込み）
+//!
+//! `RunBridgeBuilder::compiled_router(true)`で有効にすると、`build()`時に各ルートのパターン
+//! 先頭の静的なセグメント（`^/api/users/(?P<id>\d+)$`なら`api`・`users`）でトライ木を作り、
+//! リクエストのパスのセグメントをたどって候補のハンドラーだけを照合します。
+//! 候補は登録順に`Handler::matches`で照合するため、マッチするハンドラーは線形探索と同じです。
+//!
+//! 静的な接頭辞を取り出せないパターン（`^`で始まらない・`|`を含む・`(?i)`などのフラグで始まる）や、
+//! `Handler::matches_by_pattern`がfalseの独自ハンドラーは、常に照合する候補として扱います。
+
+use std::collections::HashMap;
+
+use super::traits::Handler;
+
+#[derive(Debug, Default)]
+struct Node {
+    children: HashMap<String, Node>,
+    /// 静的な接頭辞がこのノードまでのハンドラー（登録順のインデックス）
+    handlers: Vec<usize>,
+}
+
+/// ハンドラーの候補を絞り込むトライ木
+#[derive(Debug, Default)]
+pub struct CompiledRouter {
+    root: Node,
+    /// 常に照合するハンドラー
+    fallback: Vec<usize>,
+}
+
+impl CompiledRouter {
+    /// 登録順のハンドラーからルーターを構築
+    pub fn build<'a>(handlers: impl IntoIterator<Item = &'a dyn Handler>) -> Self {
+        let mut router = Self::default();
+        for (index, handler) in handlers.into_iter().enumerate() {
+            let segments = if handler.matches_by_pattern() {
+                static_segments(handler.path_pattern())
+            } else {
+                None
+            };
+            match segments {
+                Some(segments) => {
+                    let mut node = &mut router.root;
+                    for segment in segments {
+                        node = node.children.entry(segment).or_default();
+                    }
+                    node.handlers.push(index);
+                }
+                None => router.fallback.push(index),
+            }
+        }
+        router
+    }
+
+    /// パスにマッチし得るハンドラーのインデックス（登録順）
+    pub fn candidates(&self, path: &str) -> Vec<usize> {
+        let mut candidates = self.fallback.clone();
+        let mut node = &self.root;
+        candidates.extend_from_slice(&node.handlers);
+        if let Some(rest) = path.strip_prefix('/') {
+            for segment in rest.split('/') {
+                match node.children.get(segment) {
+                    Some(child) => {
+                        node = child;
+                        candidates.extend_from_slice(&node.handlers);
+                    }
+                    None => break,
+                }
+            }
+        }
+        candidates.sort_unstable();
+        candidates
+    }
+
+    /// 常に照合するハンドラーの数
+    pub fn fallback_len(&self) -> usize {
+        self.fallback.len()
+    }
+}
+
+/// パターンにマッチする全てのパスが持つ、先頭の完全な静的セグメント（取り出せない場合はNone）
+///
+/// 最後のセグメントは、直後がパターンの終端（`$`）で空でない場合のみ含めます。
+pub fn static_segments(pattern: &str) -> Option<Vec<String>> {
+    let body = pattern.strip_prefix('^')?;
+    // トップレベルの選択はリテラルの接頭辞を保証しないため、`|`を含むパターンは扱わない
+    if body.contains('|') || !body.starts_with('/') {
+        return None;
+    }
+
+    let mut literal = String::new();
+    let mut terminated = false;
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(escaped) if escaped.is_ascii_punctuation() => literal.push(escaped),
+                _ => break,
+            },
+            // 量指定子は直前の文字を任意にするため、その文字をリテラルから外す
+            '?' | '*' | '+' | '{' => {
+                literal.pop();
+                break;
+            }
+            '$' => {
+                terminated = chars.peek().is_none();
+                break;
+            }
+            '.' | '[' | '(' | ')' | '^' => break,
+            c => literal.push(c),
+        }
+    }
+
+    // `^/?`のように先頭のスラッシュも任意の場合は、ルートのみに登録
+    let rest = match literal.strip_prefix('/') {
+        Some(rest) => rest,
+        None => return Some(Vec::new()),
+    };
+    let mut segments: Vec<String> = rest.split('/').map(str::to_string).collect();
+    let complete_last = terminated && segments.last().is_some_and(|s| !s.is_empty());
+    if !complete_last {
+        segments.pop();
+    }
+    Some(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(segments: &[&str]) -> Option<Vec<String>> {
+        Some(segments.iter().map(|s| s.to_string()).collect())
+    }
+
+    #[test]
+    fn test_static_segments() {
+        assert_eq!(static_segments("^/api/users$"), strings(&["api", "users"]));
+        assert_eq!(static_segments("^/api/users/(?P<id>\\d+)$"), strings(&["api", "users"]));
+        assert_eq!(static_segments("^/api/users"), strings(&["api"]));
+        assert_eq!(static_segments("^/api/users?$"), strings(&["api"]));
+        assert_eq!(static_segments("^/v1\\.0/items/$"), strings(&["v1.0", "items"]));
+        assert_eq!(static_segments("^/files/.*$"), strings(&["files"]));
+        assert_eq!(static_segments("^/$"), strings(&[]));
+        assert_eq!(static_segments("^/?api$"), strings(&[]));
+        assert_eq!(static_segments("^/a/\\d+/b$"), strings(&["a"]));
+        assert_eq!(static_segments("^/(a|b)$"), None);
+        assert_eq!(static_segments("(?i)^/api$"), None);
+        assert_eq!(static_segments("/healthz"), None);
+    }
+
+    #[test]
+    fn test_candidates_are_narrowed_by_segments() {
+        fn handler(_req: crate::common::Request) -> Result<String, crate::error::Error> {
+            Ok(String::new())
+        }
+        let handlers = ["^/api/users$", "^/api/teams$", "^/(a|b)$", "^/$"]
+            .map(|pattern| crate::handler::get(pattern, handler));
+        let router = CompiledRouter::build(handlers.iter().map(|h| h as &dyn Handler));
+        assert_eq!(router.fallback_len(), 1);
+        assert_eq!(router.candidates("/api/users"), vec![0, 2, 3]);
+        assert_eq!(router.candidates("/other"), vec![2, 3]);
+    }
+}
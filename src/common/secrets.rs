@@ -0,0 +1,240 @@
+//! シークレット読み込みのヘルパー（キャッシュ・TTL付き）
+//!
+//! 取得元は`SecretProvider`で抽象化し、`SecretStore`がキャッシュとローテーション用のTTLを扱います。
+//! Cloud Runでシークレットをファイルとしてマウントする構成は`EnvSecretProvider`の
+//! `<NAME>_FILE`で扱えます。
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use crate::error::Error;
+
+/// シークレット値（Debug/Display出力では値を伏せる）
+#[derive(Clone, PartialEq, Eq)]
+pub struct SecretValue(String);
+
+impl SecretValue {
+    /// 新しいSecretValueを作成
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    /// 生の値を取得
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for SecretValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretValue(***redacted***)")
+    }
+}
+
+impl fmt::Display for SecretValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("***redacted***")
+    }
+}
+
+/// シークレットの取得元
+#[async_trait]
+pub trait SecretProvider: Send + Sync {
+    /// 指定名のシークレットを取得（存在しない場合はNone）
+    async fn fetch(&self, name: &str) -> Result<Option<SecretValue>, Error>;
+}
+
+/// 環境変数からシークレットを取得するプロバイダー
+///
+/// `<PREFIX><NAME>` を優先し、未設定の場合は `<PREFIX><NAME>_FILE` が指すファイルの内容
+/// （末尾の改行は除去）を使用します。
+#[derive(Debug, Clone, Default)]
+pub struct EnvSecretProvider {
+    prefix: String,
+}
+
+impl EnvSecretProvider {
+    /// 新しいEnvSecretProviderを作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 環境変数名のプレフィックスを設定
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+}
+
+#[async_trait]
+impl SecretProvider for EnvSecretProvider {
+    async fn fetch(&self, name: &str) -> Result<Option<SecretValue>, Error> {
+        let var = format!("{}{}", self.prefix, name);
+        if let Ok(value) = std::env::var(&var) {
+            return Ok(Some(SecretValue::new(value)));
+        }
+        let file_var = format!("{}_FILE", var);
+        match std::env::var(&file_var) {
+            Ok(path) => {
+                let content = tokio::fs::read_to_string(&path).await.map_err(|e| {
+                    Error::ConfigurationError(format!("Failed to read secret file for {}: {}", var, e))
+                })?;
+                Ok(Some(SecretValue::new(content.trim_end_matches(['\r', '\n']))))
+            }
+            Err(_) => Ok(None),
+        }
+    }
+}
+
+/// TTL付きでシークレットをキャッシュするストア
+///
+/// コールドスタート時に`preload`で必要なシークレットを読み込み、
+/// TTL経過後は次回アクセス時に取得元から再取得します（ローテーション対応）。
+pub struct SecretStore {
+    provider: Box<dyn SecretProvider>,
+    ttl: Option<Duration>,
+    cache: Mutex<HashMap<String, (SecretValue, Instant)>>,
+}
+
+impl SecretStore {
+    /// 新しいSecretStoreを作成（既定ではTTLなし = プロセス存続中はキャッシュを保持）
+    pub fn new<P>(provider: P) -> Self
+    where
+        P: SecretProvider + 'static,
+    {
+        Self {
+            provider: Box::new(provider),
+            ttl: None,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// キャッシュのTTLを設定
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// シークレットを取得（存在しない場合はConfigurationError）
+    pub async fn get(&self, name: &str) -> Result<SecretValue, Error> {
+        if let Some(value) = self.cached(name) {
+            return Ok(value);
+        }
+        let value = self
+            .provider
+            .fetch(name)
+            .await?
+            .ok_or_else(|| Error::ConfigurationError(format!("Secret not found: {}", name)))?;
+        log::debug!("Loaded secret: {}", name);
+        self.lock_cache()
+            .insert(name.to_string(), (value.clone(), Instant::now()));
+        Ok(value)
+    }
+
+    /// 指定したシークレットをまとめて読み込む（コールドスタート時の検証用）
+    pub async fn preload(&self, names: &[&str]) -> Result<(), Error> {
+        for name in names {
+            self.get(name).await?;
+        }
+        Ok(())
+    }
+
+    /// キャッシュを破棄して次回アクセス時に再取得させる
+    pub fn invalidate(&self, name: &str) {
+        self.lock_cache().remove(name);
+    }
+
+    /// 有効期限内のキャッシュ値を取得
+    fn cached(&self, name: &str) -> Option<SecretValue> {
+        let cache = self.lock_cache();
+        let (value, loaded_at) = cache.get(name)?;
+        match self.ttl {
+            Some(ttl) if loaded_at.elapsed() >= ttl => None,
+            _ => Some(value.clone()),
+        }
+    }
+
+    fn lock_cache(&self) -> std::sync::MutexGuard<'_, HashMap<String, (SecretValue, Instant)>> {
+        // ロック保持中のパニックでもキャッシュは破損しないため、ポイズンは無視する
+        self.cache.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl fmt::Debug for SecretStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretStore")
+            .field("ttl", &self.ttl)
+            .field("cached", &self.lock_cache().len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// 呼び出し回数を数えるテスト用プロバイダー
+    struct CountingProvider {
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl SecretProvider for CountingProvider {
+        async fn fetch(&self, name: &str) -> Result<Option<SecretValue>, Error> {
+            let n = self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok((name != "missing").then(|| SecretValue::new(format!("{}-v{}", name, n))))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_store_caches_and_expires() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let store = SecretStore::new(CountingProvider { calls: calls.clone() })
+            .ttl(Duration::from_millis(20));
+
+        assert_eq!(store.get("db").await.unwrap().expose(), "db-v0");
+        assert_eq!(store.get("db").await.unwrap().expose(), "db-v0");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert_eq!(store.get("db").await.unwrap().expose(), "db-v1");
+
+        store.invalidate("db");
+        assert_eq!(store.get("db").await.unwrap().expose(), "db-v2");
+
+        let err = store.preload(&["db", "missing"]).await.unwrap_err();
+        assert!(matches!(err, Error::ConfigurationError(_)));
+    }
+
+    #[test]
+    fn test_env_provider_reads_value_and_file() {
+        let dir = std::env::temp_dir().join(format!("runbridge-secret-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("token");
+        std::fs::write(&path, "from-file\n").unwrap();
+
+        let vars = [
+            ("RB_TEST_SECRET_API_KEY", Some("from-env")),
+            ("RB_TEST_SECRET_TOKEN_FILE", path.to_str()),
+        ];
+        temp_env::with_vars(vars, || {
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+            runtime.block_on(async {
+                let provider = EnvSecretProvider::new().prefix("RB_TEST_SECRET_");
+
+                let api_key = provider.fetch("API_KEY").await.unwrap().unwrap();
+                assert_eq!(api_key.expose(), "from-env");
+                assert_eq!(format!("{:?}", api_key), "SecretValue(***redacted***)");
+                let token = provider.fetch("TOKEN").await.unwrap().unwrap();
+                assert_eq!(token.expose(), "from-file");
+                assert!(provider.fetch("UNSET").await.unwrap().is_none());
+            });
+        });
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
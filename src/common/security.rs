@@ -0,0 +1,106 @@
+//! 用途別のセキュリティヘッダーのプリセット
+//!
+//! `Response::new`などは従来どおり汎用の既定ヘッダーを注入しますが、プロファイルを設定すると
+//! 各ランタイムがレスポンスの後処理の後に、既定値のまま残っているヘッダーをプロファイルの
+//! ヘッダーに置き換えます。ハンドラーやミドルウェアが明示的に設定した値は変更しません。
+//!
+//! プロファイルは`RunBridgeBuilder::security_profile`（アプリ全体）と
+//! `RouterGroup::security_profile`（グループ単位、内側のグループが優先）で設定します。
+
+use super::csp::{ContentSecurityPolicy, Source};
+use super::http::{default_security_headers, Response};
+
+/// `SecurityProfile::Html`で設定するStrict-Transport-Security
+pub const HTML_HSTS: &str = "max-age=31536000; includeSubDomains";
+
+/// セキュリティヘッダーのプリセット
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecurityProfile {
+    /// JSONなどを返すAPI向け（CSPなし、MIMEスニッフィング・フレーム埋め込み・リファラー送信を禁止）
+    Api,
+    /// HTMLページ向け（厳格なCSP・HSTS・クロスオリジンのウィンドウ分離）
+    Html,
+    /// セキュリティヘッダーを付けない（前段のプロキシで付与する場合など）
+    None,
+}
+
+impl SecurityProfile {
+    /// プロファイルが設定するヘッダー
+    pub fn headers(&self) -> Vec<(&'static str, String)> {
+        match self {
+            SecurityProfile::Api => vec![
+                ("X-Content-Type-Options", "nosniff".to_string()),
+                ("X-Frame-Options", "DENY".to_string()),
+                ("Referrer-Policy", "no-referrer".to_string()),
+            ],
+            SecurityProfile::Html => vec![
+                ("X-Content-Type-Options", "nosniff".to_string()),
+                ("X-Frame-Options", "DENY".to_string()),
+                ("Referrer-Policy", "strict-origin-when-cross-origin".to_string()),
+                ("Content-Security-Policy", html_content_security_policy().to_string()),
+                ("Strict-Transport-Security", HTML_HSTS.to_string()),
+                ("Cross-Origin-Opener-Policy", "same-origin".to_string()),
+            ],
+            SecurityProfile::None => Vec::new(),
+        }
+    }
+
+    /// レスポンスにプロファイルを適用
+    ///
+    /// 構築時に注入された既定値のままのヘッダーを除去し、未設定のヘッダーにプロファイルの値を設定します。
+    pub fn apply(&self, res: &mut Response) {
+        for (name, value) in default_security_headers() {
+            if res.header(name) == Some(value.as_str()) {
+                res.headers.retain(|k, _| !k.eq_ignore_ascii_case(name));
+            }
+        }
+        for (name, value) in self.headers() {
+            if res.header(name).is_none() {
+                res.headers.insert(name.to_string(), value);
+            }
+        }
+    }
+}
+
+/// `SecurityProfile::Html`のCSP（nonceを使う場合は`ContentSecurityPolicy`をAroundMiddlewareとして登録）
+fn html_content_security_policy() -> ContentSecurityPolicy {
+    ContentSecurityPolicy::new()
+        .default_src([Source::SelfOrigin])
+        .object_src([Source::None])
+        .base_uri([Source::None])
+        .form_action([Source::SelfOrigin])
+        .frame_ancestors([Source::None])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles_replace_untouched_defaults() {
+        let mut res = Response::ok().with_header("X-Frame-Options", "SAMEORIGIN");
+        SecurityProfile::Api.apply(&mut res);
+        assert!(res.header("Content-Security-Policy").is_none());
+        assert!(res.header("X-XSS-Protection").is_none());
+        assert_eq!(res.header("Referrer-Policy"), Some("no-referrer"));
+        // 明示的に設定した値は維持
+        assert_eq!(res.header("X-Frame-Options"), Some("SAMEORIGIN"));
+
+        let mut res = Response::ok().with_header("content-security-policy", "default-src https:");
+        SecurityProfile::Html.apply(&mut res);
+        assert_eq!(res.header("Content-Security-Policy"), Some("default-src https:"));
+        assert_eq!(res.header("Strict-Transport-Security"), Some(HTML_HSTS));
+        assert_eq!(res.header("Referrer-Policy"), Some("strict-origin-when-cross-origin"));
+
+        let mut res = Response::ok();
+        SecurityProfile::Html.apply(&mut res);
+        assert_eq!(
+            res.header("Content-Security-Policy"),
+            Some("default-src 'self'; object-src 'none'; base-uri 'none'; form-action 'self'; frame-ancestors 'none'")
+        );
+
+        let mut res = Response::ok().with_header("Content-Type", "application/json");
+        SecurityProfile::None.apply(&mut res);
+        assert_eq!(res.headers.len(), 1);
+    }
+}
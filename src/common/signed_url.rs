@@ -0,0 +1,193 @@
+//! 有効期限付き署名URLの生成と検証
+//!
+//! パス・有効期限・任意のクレーム（クエリパラメータ）をHMAC-SHA256で署名します。
+//! ダウンロードリンクや配信停止リンクのように、認証なしで一時的に公開するURLに使用します。
+
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::error::Error;
+use super::http::Request;
+use super::utils::percent_encode;
+
+/// 検証済みクレームをRequestContextに格納する際のキー
+pub const SIGNED_CLAIMS_CONTEXT_KEY: &str = "runbridge.signed_url_claims";
+
+/// 有効期限（UNIX秒）を格納するクエリパラメータ名
+pub const EXPIRES_PARAM: &str = "expires";
+
+/// 署名を格納するクエリパラメータ名
+pub const SIGNATURE_PARAM: &str = "signature";
+
+/// 署名URLの生成・検証を行う署名器
+#[derive(Clone)]
+pub struct UrlSigner {
+    key: Vec<u8>,
+}
+
+impl fmt::Debug for UrlSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UrlSigner").field("key", &"***redacted***").finish()
+    }
+}
+
+impl UrlSigner {
+    /// 署名鍵を指定して作成（32バイト以上のランダムな鍵を推奨）
+    pub fn new(key: impl Into<Vec<u8>>) -> Self {
+        Self { key: key.into() }
+    }
+
+    /// 現在時刻から`ttl`後に失効する署名付きURL（パス＋クエリ）を生成
+    pub fn sign(&self, path: &str, ttl: Duration, claims: &[(&str, &str)]) -> String {
+        let expires_at = chrono::Utc::now().timestamp() + ttl.as_secs() as i64;
+        self.sign_until(path, expires_at, claims)
+    }
+
+    /// 指定時刻（UNIX秒）に失効する署名付きURL（パス＋クエリ）を生成
+    pub fn sign_until(&self, path: &str, expires_at: i64, claims: &[(&str, &str)]) -> String {
+        let mut params: Vec<(String, String)> = claims
+            .iter()
+            .filter(|(k, _)| *k != EXPIRES_PARAM && *k != SIGNATURE_PARAM)
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        params.push((EXPIRES_PARAM.to_string(), expires_at.to_string()));
+
+        let query = canonical_query(params.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+        let signature = self.signature(path, &query);
+        format!("{}?{}&{}={}", path, query, SIGNATURE_PARAM, signature)
+    }
+
+    /// リクエストの署名と有効期限を検証し、署名対象のクレームを返す
+    ///
+    /// 署名が無い・一致しない・期限切れの場合は`AuthorizationError`（403）を返します。
+    pub fn verify(&self, req: &Request) -> Result<HashMap<String, String>, Error> {
+        self.verify_at(&req.path, &req.query_params, chrono::Utc::now().timestamp())
+    }
+
+    fn verify_at(
+        &self,
+        path: &str,
+        query_params: &HashMap<String, String>,
+        now: i64,
+    ) -> Result<HashMap<String, String>, Error> {
+        let signature = query_params
+            .get(SIGNATURE_PARAM)
+            .ok_or_else(|| Error::AuthorizationError("missing URL signature".to_string()))?;
+        let expires_at = query_params
+            .get(EXPIRES_PARAM)
+            .and_then(|v| v.parse::<i64>().ok())
+            .ok_or_else(|| Error::AuthorizationError("missing or invalid URL expiry".to_string()))?;
+
+        let signed = query_params
+            .iter()
+            .filter(|(k, _)| k.as_str() != SIGNATURE_PARAM)
+            .map(|(k, v)| (k.as_str(), v.as_str()));
+        let expected = self.signature(path, &canonical_query(signed));
+        if !constant_time_eq(expected.as_bytes(), signature.as_bytes()) {
+            return Err(Error::AuthorizationError("invalid URL signature".to_string()));
+        }
+        // 署名が正しい場合のみ期限を判定（期限の改ざんは署名不一致として扱われる）
+        if now > expires_at {
+            return Err(Error::AuthorizationError("signed URL has expired".to_string()));
+        }
+
+        Ok(query_params
+            .iter()
+            .filter(|(k, _)| k.as_str() != SIGNATURE_PARAM && k.as_str() != EXPIRES_PARAM)
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect())
+    }
+
+    fn signature(&self, path: &str, canonical_query: &str) -> String {
+        let message = format!("{}\n{}", path, canonical_query);
+        base64::encode_config(hmac_sha256(&self.key, message.as_bytes()), base64::URL_SAFE_NO_PAD)
+    }
+}
+
+impl Request {
+    /// `SignedUrlGuard`で検証済みの署名クレーム（未検証の場合はNone）
+    pub fn signed_url_claims(&self) -> Option<&HashMap<String, String>> {
+        self.context().get::<HashMap<String, String>>(SIGNED_CLAIMS_CONTEXT_KEY)
+    }
+}
+
+/// キーでソートしたパーセントエンコード済みクエリ文字列（署名対象の正規形）
+fn canonical_query<'a>(params: impl Iterator<Item = (&'a str, &'a str)>) -> String {
+    let mut pairs: Vec<String> = params
+        .map(|(k, v)| format!("{}={}", percent_encode(k), percent_encode(v)))
+        .collect();
+    pairs.sort();
+    pairs.join("&")
+}
+
+/// HMAC-SHA256（RFC 2104）
+pub(crate) fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    // HMACは任意長の鍵を受け付けるため失敗しない
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().into()
+}
+
+/// 長さ以外の情報を処理時間から漏らさない比較
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::parse_query_string;
+
+    fn split(url: &str) -> (String, HashMap<String, String>) {
+        let (path, query) = url.split_once('?').unwrap();
+        (path.to_string(), parse_query_string(query))
+    }
+
+    #[test]
+    fn test_hmac_sha256_rfc4231_vector() {
+        // RFC 4231 Test Case 2
+        let mac = hmac_sha256(b"Jefe", b"what do ya want for nothing?");
+        let hex: String = mac.iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(hex, "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let signer = UrlSigner::new("secret-key");
+        let url = signer.sign_until("/files/report.pdf", 1_000, &[("user", "42"), ("disposition", "a b")]);
+        let (path, params) = split(&url);
+
+        let claims = signer.verify_at(&path, &params, 999).unwrap();
+        assert_eq!(claims.get("user").map(String::as_str), Some("42"));
+        assert_eq!(claims.get("disposition").map(String::as_str), Some("a b"));
+        assert!(!claims.contains_key(EXPIRES_PARAM));
+
+        // 期限切れ
+        assert!(signer.verify_at(&path, &params, 1_001).is_err());
+        // 別の鍵
+        assert!(UrlSigner::new("other").verify_at(&path, &params, 999).is_err());
+        // パスの差し替え
+        assert!(signer.verify_at("/files/other.pdf", &params, 999).is_err());
+    }
+
+    #[test]
+    fn test_tampered_params_are_rejected() {
+        let signer = UrlSigner::new("secret-key");
+        let (path, params) = split(&signer.sign_until("/unsubscribe", 1_000, &[("user", "42")]));
+
+        for (key, value) in [("user", "43"), (EXPIRES_PARAM, "9999"), ("extra", "1")] {
+            let mut tampered = params.clone();
+            tampered.insert(key.to_string(), value.to_string());
+            let err = signer.verify_at(&path, &tampered, 0).unwrap_err();
+            assert_eq!(err.status_code(), 403, "{}", key);
+        }
+
+        let mut unsigned = params.clone();
+        unsigned.remove(SIGNATURE_PARAM);
+        assert!(signer.verify_at(&path, &unsigned, 0).is_err());
+    }
+}
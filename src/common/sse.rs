@@ -0,0 +1,239 @@
+//! Server-Sent Events（`text/event-stream`）のレスポンス
+//!
+//! Cloud Runではイベントを逐次送信し、一定時間イベントが無い場合はコメント行でキープアライブします。
+//! Lambda・CGI・towerなど逐次送信できない実行環境では、ストリームが終了するまで（最長で
+//! `RUNBRIDGE_SSE_BUFFER_TIMEOUT`）のイベントをまとめて1つのレスポンスとして返します。
+//! そのためこれらの環境では、有限のストリーム（進捗通知など）だけを返してください。
+
+use std::fmt;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::{Stream, StreamExt};
+use serde::Serialize;
+
+use crate::error::Error;
+use super::http::Response;
+
+/// キープアライブで送信するコメント行
+pub const SSE_KEEP_ALIVE_COMMENT: &[u8] = b": keep-alive\n\n";
+
+/// イベントのストリーム
+pub type EventStream = Pin<Box<dyn Stream<Item = SseEvent> + Send>>;
+
+/// キープアライブの間隔を取得する
+/// 優先順位: 環境変数 `RUNBRIDGE_SSE_KEEP_ALIVE_SECS` -> デフォルト 15秒（0で無効）
+pub fn get_sse_keep_alive_interval() -> Option<Duration> {
+    const DEFAULT_SECS: u64 = 15;
+    let secs = std::env::var("RUNBRIDGE_SSE_KEEP_ALIVE_SECS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_SECS);
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+/// 逐次送信できない実行環境でイベントを待つ最長時間を取得する
+/// 優先順位: 環境変数 `RUNBRIDGE_SSE_BUFFER_TIMEOUT_SECS` -> デフォルト 25秒
+pub fn get_sse_buffer_timeout() -> Duration {
+    const DEFAULT_SECS: u64 = 25;
+    let secs = std::env::var("RUNBRIDGE_SSE_BUFFER_TIMEOUT_SECS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_SECS);
+    Duration::from_secs(secs)
+}
+
+/// 1件のイベント
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SseEvent {
+    id: Option<String>,
+    event: Option<String>,
+    data: String,
+    retry: Option<Duration>,
+}
+
+impl SseEvent {
+    /// データを指定して作成（改行を含む場合は複数の`data:`行に分割）
+    pub fn new(data: impl Into<String>) -> Self {
+        Self { data: data.into(), ..Self::default() }
+    }
+
+    /// 値をJSONにしたデータで作成
+    pub fn json<T: Serialize>(value: &T) -> Result<Self, Error> {
+        serde_json::to_string(value)
+            .map(Self::new)
+            .map_err(|e| Error::ResponseSerializationError(e.to_string()))
+    }
+
+    /// イベント名（`event:`）を設定
+    pub fn event(mut self, name: impl Into<String>) -> Self {
+        self.event = Some(name.into());
+        self
+    }
+
+    /// イベントID（`id:`、再接続時の`Last-Event-ID`）を設定
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// 再接続までの待ち時間（`retry:`）を設定
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// ワイヤー形式に変換（`id`・`event`の改行は取り除く）
+    pub fn to_bytes(&self) -> Vec<u8> {
+        fn single_line(value: &str) -> String {
+            value.chars().filter(|c| *c != '\r' && *c != '\n').collect()
+        }
+        let mut out = String::with_capacity(self.data.len() + 16);
+        if let Some(id) = &self.id {
+            out.push_str(&format!("id: {}\n", single_line(id)));
+        }
+        if let Some(event) = &self.event {
+            out.push_str(&format!("event: {}\n", single_line(event)));
+        }
+        if let Some(retry) = self.retry {
+            out.push_str(&format!("retry: {}\n", retry.as_millis()));
+        }
+        for line in self.data.split('\n') {
+            out.push_str("data: ");
+            out.push_str(line.strip_suffix('\r').unwrap_or(line));
+            out.push('\n');
+        }
+        out.push('\n');
+        out.into_bytes()
+    }
+}
+
+/// レスポンスに格納するストリーム（`Response`の`Clone`のため共有し、最初に取り出した側が使用）
+#[derive(Clone)]
+pub(crate) struct SharedEventStream(Arc<Mutex<Option<EventStream>>>);
+
+impl SharedEventStream {
+    fn take(&self) -> Option<EventStream> {
+        self.0.lock().ok().and_then(|mut stream| stream.take())
+    }
+}
+
+impl fmt::Debug for SharedEventStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SharedEventStream")
+    }
+}
+
+/// イベントのバイト列に、一定時間イベントが無い間のキープアライブを挟んだストリーム
+pub fn with_keep_alive(events: EventStream, interval: Option<Duration>) -> impl Stream<Item = Vec<u8>> + Send {
+    futures::stream::unfold(events, move |mut events| async move {
+        let next = match interval {
+            Some(interval) => match tokio::time::timeout(interval, events.next()).await {
+                Ok(next) => next.map(|event| event.to_bytes()),
+                Err(_) => Some(SSE_KEEP_ALIVE_COMMENT.to_vec()),
+            },
+            None => events.next().await.map(|event| event.to_bytes()),
+        };
+        next.map(|chunk| (chunk, events))
+    })
+}
+
+impl Response {
+    /// Server-Sent Eventsのレスポンスを作成（200、`Content-Type: text/event-stream`）
+    ///
+    /// ```
+    /// use runbridge::common::{Response, SseEvent};
+    ///
+    /// let events = futures::stream::iter((1..=3).map(|i| SseEvent::new(i.to_string()).event("progress")));
+    /// let res = Response::sse(events);
+    /// assert!(res.is_sse());
+    /// ```
+    pub fn sse<S>(events: S) -> Self
+    where
+        S: Stream<Item = SseEvent> + Send + 'static,
+    {
+        let mut res = Response::ok()
+            .with_header("Content-Type", "text/event-stream")
+            .with_header("Cache-Control", "no-cache")
+            // リバースプロキシのバッファリングを無効化
+            .with_header("X-Accel-Buffering", "no");
+        res.event_stream = Some(SharedEventStream(Arc::new(Mutex::new(Some(Box::pin(events))))));
+        res
+    }
+
+    /// Server-Sent Eventsのストリームを持つかどうか
+    pub fn is_sse(&self) -> bool {
+        self.event_stream.is_some()
+    }
+
+    /// イベントのストリームを取り出す（逐次送信できる実行環境やテストで使用）
+    pub fn take_event_stream(&mut self) -> Option<EventStream> {
+        self.event_stream.take().and_then(|stream| stream.take())
+    }
+
+    /// イベントのストリームをボディにまとめる（逐次送信できない実行環境用）
+    ///
+    /// ストリームの終了まで、または`RUNBRIDGE_SSE_BUFFER_TIMEOUT_SECS`が経過するまでのイベントを
+    /// ボディに書き込みます。ストリームを持たないレスポンスは変更しません。
+    pub async fn buffer_event_stream(&mut self) {
+        let mut events = match self.take_event_stream() {
+            Some(events) => events,
+            None => return,
+        };
+        let mut body = Vec::new();
+        let buffered = tokio::time::timeout(get_sse_buffer_timeout(), async {
+            while let Some(event) = events.next().await {
+                body.extend_from_slice(&event.to_bytes());
+            }
+        })
+        .await;
+        if buffered.is_err() {
+            log::warn!(
+                "Event stream did not finish within the buffer timeout; returning {} buffered bytes",
+                body.len()
+            );
+        }
+        self.body = Some(body);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_wire_format() {
+        let event = SseEvent::new("line1\nline2")
+            .event("update\r\ninjected: x")
+            .id("7")
+            .retry(Duration::from_secs(3));
+        assert_eq!(
+            String::from_utf8(event.to_bytes()).unwrap(),
+            "id: 7\nevent: updateinjected: x\nretry: 3000\ndata: line1\ndata: line2\n\n"
+        );
+        assert_eq!(SseEvent::json(&serde_json::json!({ "n": 1 })).unwrap().to_bytes(), b"data: {\"n\":1}\n\n");
+    }
+
+    #[tokio::test]
+    async fn test_buffered_fallback_and_keep_alive() {
+        let mut res = Response::sse(futures::stream::iter(vec![SseEvent::new("a"), SseEvent::new("b")]));
+        assert_eq!(res.header("Content-Type"), Some("text/event-stream"));
+        // 複製したレスポンスはストリームを共有し、最初に取り出した側だけが使用する
+        let mut copy = res.clone();
+        res.buffer_event_stream().await;
+        assert_eq!(res.body.as_deref(), Some(&b"data: a\n\ndata: b\n\n"[..]));
+        assert!(!res.is_sse());
+        assert!(copy.take_event_stream().is_none());
+
+        // イベントの間隔がキープアライブより長い場合はコメント行を挟む
+        let slow = futures::stream::iter(vec![SseEvent::new("late")]).then(|event| async move {
+            tokio::time::sleep(Duration::from_millis(80)).await;
+            event
+        });
+        let chunks: Vec<Vec<u8>> = with_keep_alive(Box::pin(slow), Some(Duration::from_millis(30))).collect().await;
+        assert!(chunks.len() >= 2);
+        assert_eq!(chunks[0], SSE_KEEP_ALIVE_COMMENT);
+        assert_eq!(chunks.last().unwrap(), b"data: late\n\n");
+    }
+}
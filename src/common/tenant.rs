@@ -0,0 +1,300 @@
+//! マルチテナント向けのテナント解決ミドルウェア
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use crate::error::Error;
+use super::http::{Request, Response};
+use super::traits::Middleware;
+
+/// RequestContextに格納する際のキー
+pub const TENANT_CONTEXT_KEY: &str = "runbridge.tenant";
+
+/// 解決済みのテナント情報
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tenant {
+    /// テナントID
+    pub id: String,
+    /// 表示名
+    pub name: Option<String>,
+    /// 任意の属性（プラン名など）
+    pub attributes: HashMap<String, String>,
+}
+
+impl Tenant {
+    /// IDのみを持つテナントを作成
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            name: None,
+            attributes: HashMap::new(),
+        }
+    }
+
+    /// 表示名を設定
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// 属性を追加
+    pub fn with_attribute(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.attributes.insert(key.into(), value.into());
+        self
+    }
+
+    /// テナント単位のレート制限キー（例: `tenant:acme:api`）
+    pub fn rate_limit_key(&self, bucket: &str) -> String {
+        format!("tenant:{}:{}", self.id, bucket)
+    }
+}
+
+/// テナントIDを取り出す関数
+pub type TenantExtractor = Arc<dyn Fn(&Request) -> Option<String> + Send + Sync>;
+
+/// テナントIDの取得元
+#[derive(Clone)]
+pub enum TenantSource {
+    /// Hostヘッダーのサブドメイン（`acme.example.com` + base_domain `example.com` → `acme`）
+    Subdomain(String),
+    /// 任意のリクエストヘッダー（ヘッダー名）
+    Header(String),
+    /// 任意の関数（認証ミドルウェアが格納したトークンのクレームなど）
+    Custom(TenantExtractor),
+}
+
+impl fmt::Debug for TenantSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TenantSource::Subdomain(base) => f.debug_tuple("Subdomain").field(base).finish(),
+            TenantSource::Header(name) => f.debug_tuple("Header").field(name).finish(),
+            TenantSource::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+impl TenantSource {
+    /// 関数を取得元として作成
+    pub fn custom<F>(f: F) -> Self
+    where
+        F: Fn(&Request) -> Option<String> + Send + Sync + 'static,
+    {
+        TenantSource::Custom(Arc::new(f))
+    }
+
+    fn extract(&self, req: &Request) -> Option<String> {
+        let value = match self {
+            TenantSource::Subdomain(base_domain) => {
+                let host = req.headers.get("host")?;
+                subdomain_of(host, base_domain)
+            }
+            TenantSource::Header(name) => req
+                .headers
+                .get(&name.to_ascii_lowercase())
+                .map(|v| v.trim().to_string()),
+            TenantSource::Custom(f) => f(req),
+        }?;
+        if is_tenant_id_valid(&value) {
+            Some(value)
+        } else {
+            None
+        }
+    }
+}
+
+/// テナント情報の検索関数
+type TenantLookup = Arc<dyn Fn(&str) -> Option<Tenant> + Send + Sync>;
+
+/// テナントリゾルバー（ミドルウェアとして登録するとRequestContextに`Tenant`を格納）
+///
+/// 検索関数または登録済みテナントが設定されている場合はそれらに存在するIDのみを受け入れ、
+/// 未設定の場合は取得したIDからそのままテナントを作成します。
+#[derive(Clone)]
+pub struct TenantResolver {
+    sources: Vec<TenantSource>,
+    lookup: Option<TenantLookup>,
+    reject_unknown: bool,
+}
+
+impl fmt::Debug for TenantResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TenantResolver")
+            .field("sources", &self.sources)
+            .field("lookup", &self.lookup.is_some())
+            .field("reject_unknown", &self.reject_unknown)
+            .finish()
+    }
+}
+
+impl TenantResolver {
+    /// 取得元（優先順）を指定してリゾルバーを作成
+    pub fn new(sources: Vec<TenantSource>) -> Self {
+        Self {
+            sources,
+            lookup: None,
+            reject_unknown: false,
+        }
+    }
+
+    /// 既知のテナントを登録（IDで検索）
+    pub fn tenants<I>(self, tenants: I) -> Self
+    where
+        I: IntoIterator<Item = Tenant>,
+    {
+        let registry: HashMap<String, Tenant> = tenants
+            .into_iter()
+            .map(|t| (t.id.clone(), t))
+            .collect();
+        self.lookup(move |id| registry.get(id).cloned())
+    }
+
+    /// テナントの検索関数を設定
+    pub fn lookup<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str) -> Option<Tenant> + Send + Sync + 'static,
+    {
+        self.lookup = Some(Arc::new(f));
+        self
+    }
+
+    /// テナントを解決できないリクエストを403で拒否するかどうか（既定: false）
+    pub fn reject_unknown(mut self, reject: bool) -> Self {
+        self.reject_unknown = reject;
+        self
+    }
+
+    /// リクエストからテナントを解決
+    pub fn resolve(&self, req: &Request) -> Option<Tenant> {
+        let id = self.sources.iter().find_map(|source| source.extract(req))?;
+        match &self.lookup {
+            Some(lookup) => lookup(&id),
+            None => Some(Tenant::new(id)),
+        }
+    }
+}
+
+#[async_trait]
+impl Middleware for TenantResolver {
+    async fn pre_process(&self, mut req: Request) -> Result<Request, Error> {
+        match self.resolve(&req) {
+            Some(tenant) => {
+                log::debug!("Resolved tenant: {}", tenant.id);
+                req.context_mut().set(TENANT_CONTEXT_KEY, tenant);
+            }
+            None if self.reject_unknown => {
+                log::warn!("Rejected request without a known tenant: {} {}", req.method, req.path);
+                return Err(Error::AuthorizationError("Unknown tenant".to_string()));
+            }
+            None => {}
+        }
+        Ok(req)
+    }
+
+    async fn post_process(&self, res: Response) -> Result<Response, Error> {
+        Ok(res)
+    }
+}
+
+impl Request {
+    /// TenantResolverにより解決されたテナントを取得
+    pub fn tenant(&self) -> Option<&Tenant> {
+        self.context().get::<Tenant>(TENANT_CONTEXT_KEY)
+    }
+}
+
+/// Hostヘッダーからベースドメイン直下のサブドメインを取り出す（ポートは無視）
+fn subdomain_of(host: &str, base_domain: &str) -> Option<String> {
+    let host = host.trim().to_ascii_lowercase();
+    let host = host.split(':').next().unwrap_or(&host);
+    let base = base_domain.trim_start_matches('.').to_ascii_lowercase();
+    let prefix = host.strip_suffix(&base)?.strip_suffix('.')?;
+    if prefix.is_empty() || prefix.contains('.') {
+        return None;
+    }
+    Some(prefix.to_string())
+}
+
+/// テナントIDとして妥当か（英数字・ハイフン・アンダースコアのみ、長さ制限付き）
+fn is_tenant_id_valid(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= 63
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Method;
+
+    fn request() -> Request {
+        Request::new(Method::GET, "/".to_string())
+    }
+
+    #[test]
+    fn test_subdomain_source() {
+        let resolver = TenantResolver::new(vec![TenantSource::Subdomain("example.com".into())]);
+
+        let req = request().with_header("Host", "Acme.example.com:8080");
+        assert_eq!(resolver.resolve(&req).unwrap().id, "acme");
+
+        // ベースドメインそのもの・多段サブドメイン・別ドメインは対象外
+        for host in ["example.com", "a.b.example.com", "acme.example.org", "evilexample.com"] {
+            let req = request().with_header("Host", host);
+            assert!(resolver.resolve(&req).is_none(), "host: {}", host);
+        }
+    }
+
+    #[test]
+    fn test_source_order_and_custom_source() {
+        let resolver = TenantResolver::new(vec![
+            TenantSource::Header("X-Tenant-Id".into()),
+            TenantSource::custom(|req| req.query_params.get("tenant").cloned()),
+        ]);
+
+        let req = request()
+            .with_header("X-Tenant-Id", "from-header")
+            .with_query_param("tenant", "from-query");
+        assert_eq!(resolver.resolve(&req).unwrap().id, "from-header");
+
+        // 不正なIDは無視して次の取得元へ
+        let req = request()
+            .with_header("X-Tenant-Id", "../etc")
+            .with_query_param("tenant", "from-query");
+        assert_eq!(resolver.resolve(&req).unwrap().id, "from-query");
+    }
+
+    #[tokio::test]
+    async fn test_registry_and_reject_unknown() {
+        let resolver = TenantResolver::new(vec![TenantSource::Header("x-tenant-id".into())])
+            .tenants(vec![Tenant::new("acme").with_attribute("plan", "pro")])
+            .reject_unknown(true);
+
+        let req = resolver
+            .pre_process(request().with_header("X-Tenant-Id", "acme"))
+            .await
+            .unwrap();
+        let tenant = req.tenant().expect("tenant should be set");
+        assert_eq!(tenant.attributes.get("plan").map(String::as_str), Some("pro"));
+        assert_eq!(tenant.rate_limit_key("api"), "tenant:acme:api");
+
+        let err = resolver
+            .pre_process(request().with_header("X-Tenant-Id", "other"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), 403);
+        assert!(resolver.pre_process(request()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_tenant_passes_when_not_rejected() {
+        let resolver = TenantResolver::new(vec![TenantSource::Header("x-tenant-id".into())])
+            .tenants(vec![Tenant::new("acme")]);
+        let req = resolver
+            .pre_process(request().with_header("X-Tenant-Id", "other"))
+            .await
+            .unwrap();
+        assert!(req.tenant().is_none());
+    }
+}
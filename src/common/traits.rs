@@ -1,7 +1,15 @@
-//! コアトレイト定義（Handler、Middleware）
+//! コアトレイト定義（Handler、Middleware、AroundMiddleware）
+
+use std::future::Future;
+use std::pin::Pin;
 
 use async_trait::async_trait;
 use crate::error::Error;
+use super::body_fields::BodyFieldPolicy;
+use super::body_policy::BodyPolicy;
+use super::openapi::OperationDoc;
+use super::route::RouteOptions;
+use super::security::SecurityProfile;
 use super::http::{Request, Response, Method};
 
 /// ハンドラーの特性
@@ -13,6 +21,64 @@ pub trait Handler: Send + Sync {
     /// ハンドラに関連付けられたパスパターン文字列を取得
     fn path_pattern(&self) -> &str;
 
+    /// ルート固有の設定（`HandlerExt`・`RouterGroup`で設定、未設定の場合は全て未設定）
+    ///
+    /// 他のハンドラーを包むハンドラーは、内側のハンドラーの`options()`を返すか、それを引き継いで
+    /// 変更した値を返してください。以下の`route_name`〜`security_profile`はこの値を参照します。
+    fn options(&self) -> &RouteOptions {
+        RouteOptions::none()
+    }
+
+    /// ルート名（`HandlerExt::name`で設定、未設定の場合はNone）
+    fn route_name(&self) -> Option<&str> {
+        self.options().name.as_deref()
+    }
+
+    /// GET/HEAD/DELETEのボディに対するルート固有の方針（`HandlerExt::with_body_policy`で設定、未設定の場合は全体設定）
+    fn body_policy(&self) -> Option<BodyPolicy> {
+        self.options().body_policy
+    }
+
+    /// ルート固有のリクエストボディの上限（バイト、`HandlerExt::max_body_size`で設定、未設定の場合は全体設定）
+    fn body_size_limit(&self) -> Option<usize> {
+        self.options().body_size_limit
+    }
+
+    /// ルート固有の型付きボディのフィールドの扱い（`HandlerExt::body_fields`で設定、未設定の場合は全体設定）
+    fn body_field_policy(&self) -> Option<BodyFieldPolicy> {
+        self.options().body_field_policy
+    }
+
+    /// OpenAPIドキュメントに出力するメタデータ（`HandlerExt::openapi`で設定、未設定の場合はNone）
+    fn operation_doc(&self) -> Option<&OperationDoc> {
+        self.options().operation_doc.as_ref()
+    }
+
+    /// ルート固有のセキュリティヘッダーのプロファイル（`RouterGroup::security_profile`で設定、未設定の場合は全体設定）
+    fn security_profile(&self) -> Option<SecurityProfile> {
+        self.options().security_profile
+    }
+
+    /// `matches`がメソッドと`path_pattern`の正規表現だけで判定されるか（コンパイル済みルーターの索引に使用）
+    ///
+    /// falseの場合、コンパイル済みルーターでも常に照合の候補になります。
+    fn matches_by_pattern(&self) -> bool {
+        false
+    }
+
+    /// 受け付けるHTTPメソッド（ルート一覧・ドキュメント生成用、空の場合は不明）
+    fn methods(&self) -> Vec<Method> {
+        Vec::new()
+    }
+
+    /// リクエストボディの受信前に行う検査（`HandlerExt::pre_body_guard`で設定）
+    ///
+    /// `req`はヘッダー・パス・クエリのみでボディを含みません。`Expect: 100-continue`の
+    /// リクエストでErrを返すと、クライアントがボディを送信する前にエラーのレスポンスで拒否します。
+    fn check_pre_body(&self, _req: &Request) -> Result<(), Error> {
+        Ok(())
+    }
+
     /// リクエストを処理
     async fn handle(&self, req: Request) -> Result<Response, Error>;
 }
@@ -25,4 +91,36 @@ pub trait Middleware: Send + Sync {
     
     /// レスポンス後の処理
     async fn post_process(&self, res: Response) -> Result<Response, Error>;
-}
\ No newline at end of file
+}
+
+/// ハンドラー呼び出しを包むミドルウェアの特性
+///
+/// リクエストとレスポンスの両方を1つのスコープで扱えるため、リクエスト単位の
+/// トランザクション（begin → `next.run(req)` → commit/rollback）等に使用します。
+/// `Middleware`の前処理の後、後処理の前に、登録順に外側から実行されます。
+#[async_trait]
+pub trait AroundMiddleware: Send + Sync {
+    /// `next.run(req)`で後続（次のAroundMiddlewareまたはハンドラー）を実行
+    async fn around(&self, req: Request, next: Next<'_>) -> Result<Response, Error>;
+}
+
+/// AroundMiddlewareから後続の処理を呼び出すためのハンドル
+pub struct Next<'a> {
+    handler: &'a dyn Handler,
+    rest: &'a [Box<dyn AroundMiddleware>],
+}
+
+impl<'a> Next<'a> {
+    /// ハンドラーと、その外側で実行するAroundMiddlewareの列からNextを作成
+    pub fn new(handler: &'a dyn Handler, around: &'a [Box<dyn AroundMiddleware>]) -> Self {
+        Self { handler, rest: around }
+    }
+
+    /// 後続の処理を実行
+    pub fn run(self, req: Request) -> Pin<Box<dyn Future<Output = Result<Response, Error>> + Send + 'a>> {
+        match self.rest.split_first() {
+            Some((first, rest)) => first.around(req, Next { handler: self.handler, rest }),
+            None => self.handler.handle(req),
+        }
+    }
+}
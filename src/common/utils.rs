@@ -27,6 +27,22 @@ pub fn percent_decode(input: &str) -> String {
     String::from_utf8_lossy(&result).into_owned()
 }
 
+/// URLエンコーディングのエンコード関数（RFC 3986の非予約文字以外をエンコード）
+pub fn percent_encode(input: &str) -> String {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+    let mut result = String::with_capacity(input.len());
+    for &b in input.as_bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') {
+            result.push(b as char);
+        } else {
+            result.push('%');
+            result.push(HEX[(b >> 4) as usize] as char);
+            result.push(HEX[(b & 0x0f) as usize] as char);
+        }
+    }
+    result
+}
+
 /// 16進数文字をバイト値に変換するヘルパー関数
 fn from_hex(byte: u8) -> Option<u8> {
     match byte {
@@ -39,12 +55,13 @@ fn from_hex(byte: u8) -> Option<u8> {
 
 /// クエリ文字列をパースしてURLデコードを行う共通関数
 pub fn parse_query_string(query_string: &str) -> HashMap<String, String> {
-    let mut params = HashMap::new();
-
     if query_string.is_empty() {
-        return params;
+        return HashMap::new();
     }
 
+    // ペア数分を事前に確保して再ハッシュを避ける
+    let mut params = HashMap::with_capacity(query_string.split('&').count());
+
     for pair in query_string.split('&') {
         let mut parts = pair.splitn(2, '=');
         if let Some(key) = parts.next() {
@@ -58,8 +75,16 @@ pub fn parse_query_string(query_string: &str) -> HashMap<String, String> {
     params
 }
 
+/// 1KiB（バイト数の指定用、例: `64 * KB`）
+pub const KB: usize = 1024;
+
+/// 1MiB（バイト数の指定用、例: `handler.max_body_size(2 * MB)`）
+pub const MB: usize = 1024 * KB;
+
 /// リクエストボディの最大サイズ（バイト）を取得する
 /// 優先順位: 環境変数 `RUNBRIDGE_MAX_BODY_SIZE` -> デフォルト 5MB
+///
+/// ルート固有の上限（`HandlerExt::max_body_size`）がある場合はそちらが優先されます。
 pub fn get_max_body_size() -> usize {
     const DEFAULT_MAX_SIZE: usize = 5 * 1024 * 1024; // 5MB
     env::var("RUNBRIDGE_MAX_BODY_SIZE")
@@ -68,6 +93,77 @@ pub fn get_max_body_size() -> usize {
         .unwrap_or(DEFAULT_MAX_SIZE)
 }
 
+/// JSONボディのデシリアライズを`spawn_blocking`で行うボディサイズ（バイト）を取得する
+/// 優先順位: 環境変数 `RUNBRIDGE_BLOCKING_DESERIALIZE_THRESHOLD` -> デフォルト 無効（`0`も無効）
+pub fn get_blocking_deserialize_threshold() -> Option<usize> {
+    env::var("RUNBRIDGE_BLOCKING_DESERIALIZE_THRESHOLD")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .filter(|&n| n > 0)
+}
+
+/// グレースフルシャットダウンの各段階（接続の完了待ち・終了時の処理）の最長時間を取得する
+/// 優先順位: 環境変数 `RUNBRIDGE_SHUTDOWN_TIMEOUT_SECS` -> デフォルト 5秒
+///
+/// Cloud RunはSIGTERMの送信から10秒後にプロセスを停止するため、2段階の合計がこれに収まる値にしています。
+pub fn get_shutdown_timeout() -> std::time::Duration {
+    const DEFAULT_SECS: u64 = 5;
+    let secs = env::var("RUNBRIDGE_SHUTDOWN_TIMEOUT_SECS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_SECS);
+    std::time::Duration::from_secs(secs)
+}
+
+/// クエリ文字列の最大長（バイト）を取得する
+/// 優先順位: 環境変数 `RUNBRIDGE_MAX_QUERY_LENGTH` -> デフォルト 8KB
+pub fn get_max_query_length() -> usize {
+    const DEFAULT_MAX_LENGTH: usize = 8 * 1024; // 8KB
+    env::var("RUNBRIDGE_MAX_QUERY_LENGTH")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_MAX_LENGTH)
+}
+
+/// クエリパラメータの最大個数を取得する
+/// 優先順位: 環境変数 `RUNBRIDGE_MAX_QUERY_PARAMS` -> デフォルト 100
+pub fn get_max_query_params() -> usize {
+    const DEFAULT_MAX_PARAMS: usize = 100;
+    env::var("RUNBRIDGE_MAX_QUERY_PARAMS")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_MAX_PARAMS)
+}
+
+/// クエリの長さとパラメータ数が上限内か検査する
+/// 長さ超過はUriTooLong（414）、個数超過はInvalidQueryParameter（400）を返す
+pub fn check_query_limits(length: usize, count: usize) -> Result<(), Error> {
+    let max_length = get_max_query_length();
+    if length > max_length {
+        log::warn!("Query string too long: {} bytes (limit {})", length, max_length);
+        return Err(Error::UriTooLong(format!(
+            "Query string too long (>{} bytes)",
+            max_length
+        )));
+    }
+    let max_params = get_max_query_params();
+    if count > max_params {
+        log::warn!("Too many query parameters: {} (limit {})", count, max_params);
+        return Err(Error::InvalidQueryParameter(format!(
+            "Too many query parameters (>{})",
+            max_params
+        )));
+    }
+    Ok(())
+}
+
+/// 上限を検査したうえでクエリ文字列をパースする（各ランタイムの取り込み時に使用）
+pub fn parse_query_string_limited(query_string: &str) -> Result<HashMap<String, String>, Error> {
+    let count = query_string.split('&').filter(|pair| !pair.is_empty()).count();
+    check_query_limits(query_string.len(), count)?;
+    Ok(parse_query_string(query_string))
+}
+
 /// ヘッダー値に使用可能な文字かを判定（CRLF・制御文字を拒否）
 pub fn is_header_value_valid(value: &str) -> bool {
     // RFC的にはobs-text等もありうるが、ここでは保守的にUS-ASCII可視範囲に限定し、
@@ -164,6 +260,33 @@ mod tests {
         assert_eq!(percent_decode("plus+space"), "plus space"); // +もスペースに変換
         assert_eq!(percent_decode("%E3%81%82%E3%81%84%E3%81%86%E3%81%88%E3%81%8A"), "あいうえお");
     }
+
+    #[test]
+    fn test_percent_encode() {
+        assert_eq!(percent_encode("Hello World"), "Hello%20World");
+        assert_eq!(percent_encode("a+b&c=d"), "a%2Bb%26c%3Dd");
+        assert_eq!(percent_encode("safe-._~"), "safe-._~");
+        assert_eq!(percent_encode("あ"), "%E3%81%82");
+        // エンコード→デコードで元に戻る
+        assert_eq!(percent_decode(&percent_encode("x y+z/あ")), "x y+z/あ");
+    }
+
+    #[test]
+    fn test_parse_query_string_limited() {
+        temp_env::with_vars(
+            [("RUNBRIDGE_MAX_QUERY_LENGTH", Some("32")), ("RUNBRIDGE_MAX_QUERY_PARAMS", Some("3"))],
+            || {
+                let params = parse_query_string_limited("a=1&b=2&c=3").unwrap();
+                assert_eq!(params.len(), 3);
+
+                let err = parse_query_string_limited("a=1&b=2&c=3&d=4").unwrap_err();
+                assert_eq!(err.status_code(), 400);
+
+                let err = parse_query_string_limited(&format!("q={}", "x".repeat(31))).unwrap_err();
+                assert_eq!(err.status_code(), 414);
+            },
+        );
+    }
 }
 
 #[cfg(test)]
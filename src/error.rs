@@ -52,6 +52,22 @@ pub enum Error {
     /// 無効なCookie
     #[error("Invalid cookie: {0}")]
     InvalidCookie(String),
+
+    /// 無効なクエリパラメータ
+    #[error("Invalid query parameter: {0}")]
+    InvalidQueryParameter(String),
+
+    /// 無効なパスパラメータ
+    #[error("Invalid path parameter: {0}")]
+    InvalidPathParameter(String),
+
+    /// URI（クエリ文字列）が長すぎる
+    #[error("URI too long: {0}")]
+    UriTooLong(String),
+
+    /// アプリケーション固有のエラー（`IntoStatus`で指定したステータスを返す）
+    #[error("{1}")]
+    Status(u16, String),
 }
 
 impl Error {
@@ -70,6 +86,144 @@ impl Error {
             Error::AuthorizationError(_) => 403,
             Error::InvalidHeader(_) => 400,
             Error::InvalidCookie(_) => 400,
+            Error::InvalidQueryParameter(_) => 400,
+            Error::InvalidPathParameter(_) => 400,
+            Error::UriTooLong(_) => 414,
+            Error::Status(status, _) => *status,
+        }
+    }
+
+    /// `IntoStatus`を実装したアプリケーション固有のエラーから変換
+    ///
+    /// エラーを表さないステータス（400未満・600以上）は500として扱います。
+    pub fn from_status<E: IntoStatus + ?Sized>(error: &E) -> Self {
+        let status = match error.status_code() {
+            status @ 400..=599 => status,
+            _ => 500,
+        };
+        Error::Status(status, error.to_string())
+    }
+}
+
+/// ハンドラー固有のエラー型をHTTPステータスに対応付けるトレイト
+///
+/// 通常は`status_map!`マクロで実装します。マクロは`From<E> for Error`も実装するため、
+/// `Result<T, Error>`を返すハンドラー内で`?`を使うだけで指定したステータスのレスポンスになります。
+pub trait IntoStatus: std::fmt::Display {
+    /// HTTPステータスコード
+    fn status_code(&self) -> u16;
+}
+
+/// エラー型のバリアントとHTTPステータスの対応を宣言的に定義する
+///
+/// `IntoStatus`と`From<E> for runbridge::error::Error`を実装します。
+/// エラーのメッセージはステータスに関わらずログにのみ出力され、レスポンスには
+/// ステータスの定型文だけが含まれます（`RUNBRIDGE_EXPOSE_ERROR_DETAILS`が有効な場合のみメッセージも返します）。
+///
+/// ```
+/// use runbridge::status_map;
+///
+/// #[derive(Debug, thiserror::Error)]
+/// enum OrderError {
+///     #[error("order {0} not found")]
+///     NotFound(u64),
+///     #[error("order is already shipped")]
+///     AlreadyShipped,
+///     #[error("database error: {0}")]
+///     Database(String),
+/// }
+///
+/// status_map! {
+///     OrderError {
+///         OrderError::NotFound(_) => 404,
+///         OrderError::AlreadyShipped => 409,
+///         _ => 500,
+///     }
+/// }
+///
+/// let err: runbridge::error::Error = OrderError::NotFound(1).into();
+/// assert_eq!(err.status_code(), 404);
+/// assert_eq!(err.to_string(), "order 1 not found");
+/// ```
+#[macro_export]
+macro_rules! status_map {
+    ($ty:ty { $($pattern:pat => $status:expr),+ $(,)? }) => {
+        impl $crate::error::IntoStatus for $ty {
+            fn status_code(&self) -> u16 {
+                #[allow(unreachable_patterns)]
+                match self {
+                    $($pattern => $status,)+
+                }
+            }
+        }
+
+        impl ::std::convert::From<$ty> for $crate::error::Error {
+            fn from(error: $ty) -> Self {
+                $crate::error::Error::from_status(&error)
+            }
         }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{Handler, Method, Request, Response};
+    use crate::handler::get;
+
+    #[derive(Debug, thiserror::Error)]
+    enum AccountError {
+        #[error("account {0} not found")]
+        NotFound(String),
+        #[error("insufficient balance")]
+        InsufficientBalance { required: u64 },
+        #[error("ledger unavailable")]
+        LedgerUnavailable,
+        #[error("redirect")]
+        NotAnError,
+    }
+
+    crate::status_map! {
+        AccountError {
+            AccountError::NotFound(_) => 404,
+            AccountError::InsufficientBalance { .. } => 422,
+            AccountError::LedgerUnavailable => 503,
+            _ => 302,
+        }
+    }
+
+    #[test]
+    fn test_status_map() {
+        let err = Error::from(AccountError::InsufficientBalance { required: 10 });
+        assert_eq!(err.status_code(), 422);
+        assert_eq!(err.to_string(), "insufficient balance");
+        assert_eq!(Error::from(AccountError::LedgerUnavailable).status_code(), 503);
+        // エラーを表さないステータスは500に丸める
+        assert_eq!(AccountError::NotAnError.status_code(), 302);
+        assert_eq!(Error::from(AccountError::NotAnError).status_code(), 500);
+    }
+
+    #[tokio::test]
+    async fn test_question_mark_in_handler() {
+        fn find(id: &str) -> Result<String, AccountError> {
+            match id {
+                "1" => Ok("alice".to_string()),
+                _ => Err(AccountError::NotFound(id.to_string())),
+            }
+        }
+        let handler = get("/accounts", |req: Request| -> Result<String, Error> {
+            let id = req.query_params.get("id").cloned().unwrap_or_default();
+            Ok(find(&id)?)
+        });
+
+        let req = Request::new(Method::GET, "/accounts".to_string()).with_query_param("id", "1");
+        assert_eq!(handler.handle(req).await.unwrap().status, 200);
+
+        let req = Request::new(Method::GET, "/accounts".to_string()).with_query_param("id", "2");
+        let err = handler.handle(req).await.unwrap_err();
+        assert_eq!(err.status_code(), 404);
+        let res = Response::from_error(&err);
+        assert_eq!(res.status, 404);
+        assert_eq!(res.body.as_deref(), Some(&br#"{"error":"Not Found","status":404}"#[..]));
     }
 }
@@ -0,0 +1,353 @@
+//! プロパティテスト用のリクエストのジェネレーターと不変条件の検査（feature `proptest`）
+//!
+//! 任意のメソッド・パス・クエリ・ヘッダー・ボディ（JSON・フォーム・バイナリ・gzip等）を持つ
+//! `Request`を生成するproptestの`Strategy`と、`testing::dispatch`を通したときに満たすべき
+//! 不変条件（panicしない・出力するステータスとヘッダーが妥当）の検査を提供します。
+//! フレームワーク自身のテストと同じジェネレーターで、アプリのハンドラーを検査できます。
+//!
+//! ```
+//! use proptest::prelude::*;
+//! use runbridge::fuzz::{arb_request_for, check_dispatch};
+//! use runbridge::handler::get;
+//! use runbridge::RunBridge;
+//!
+//! let app = RunBridge::builder()
+//!     .handler(get("^/hello$", |req| Ok(req.query_params.get("name").cloned().unwrap_or_default())))
+//!     .build();
+//!
+//! proptest!(ProptestConfig::with_cases(32), |(req in arb_request_for("/hello|/other"))| {
+//!     check_dispatch(&app, req)?;
+//! });
+//! ```
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::panic::AssertUnwindSafe;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use futures::FutureExt;
+use proptest::collection::{hash_map, vec};
+use proptest::prelude::*;
+use proptest::test_runner::TestCaseError;
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::common::utils::{is_header_name_valid, is_header_value_valid};
+use crate::common::{Method, Request, Response};
+use crate::testing::dispatch;
+use crate::RunBridge;
+
+/// 生成するボディの最大長（バイト）
+pub const MAX_GENERATED_BODY_LEN: usize = 2048;
+
+/// `dispatch`の結果が満たさない不変条件
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum InvariantViolation {
+    /// 処理中にpanicした
+    #[error("dispatch panicked: {0}")]
+    Panicked(String),
+    /// ステータスコードが100〜599の範囲外
+    #[error("invalid status code {0}")]
+    InvalidStatus(u16),
+    /// ヘッダー名がトークンとして不正
+    #[error("invalid header name {0:?}")]
+    InvalidHeaderName(String),
+    /// ヘッダー値にCR/LF・制御文字を含む
+    #[error("invalid value for header {name:?}: {value:?}")]
+    InvalidHeaderValue { name: String, value: String },
+}
+
+/// HTTPメソッドを生成
+pub fn arb_method() -> impl Strategy<Value = Method> {
+    prop_oneof![
+        4 => Just(Method::GET),
+        3 => Just(Method::POST),
+        1 => Just(Method::PUT),
+        1 => Just(Method::PATCH),
+        1 => Just(Method::DELETE),
+        1 => Just(Method::HEAD),
+        1 => Just(Method::OPTIONS),
+    ]
+}
+
+/// パスを生成（パーセントエンコード・`..`・連続したスラッシュ・非ASCII文字を含む）
+pub fn arb_path() -> impl Strategy<Value = String> {
+    let segment = prop_oneof![
+        6 => "[a-zA-Z0-9._~-]{1,12}",
+        1 => Just("..".to_string()),
+        1 => Just(String::new()),
+        1 => "(%[0-9A-Fa-f]{2}){1,4}",
+        1 => "%[0-9a-zA-Z]{0,2}",
+        1 => "\\PC{1,6}",
+    ];
+    vec(segment, 0..6).prop_map(|segments| format!("/{}", segments.join("/")))
+}
+
+/// ヘッダー名を生成（小文字に正規化済み。よく使われるヘッダーを優先）
+pub fn arb_header_name() -> impl Strategy<Value = String> {
+    prop_oneof![
+        3 => prop::sample::select(vec![
+            "accept",
+            "accept-encoding",
+            "accept-language",
+            "authorization",
+            "cookie",
+            "if-none-match",
+            "if-modified-since",
+            "origin",
+            "range",
+            "user-agent",
+            "x-forwarded-for",
+            "x-forwarded-proto",
+            "x-request-id",
+        ])
+        .prop_map(str::to_string),
+        1 => "x-[a-z0-9-]{1,16}",
+    ]
+}
+
+/// ヘッダー値を生成（まれに不正な制御文字・非ASCII文字を含む）
+pub fn arb_header_value() -> impl Strategy<Value = String> {
+    prop_oneof![
+        8 => "[ -~]{0,64}",
+        1 => "\\PC{0,16}",
+        1 => "[ -~]{0,8}[\\x00-\\x1f\\x7f][ -~]{0,8}",
+    ]
+}
+
+/// ヘッダーを生成（キーは`Request`と同じく小文字）
+pub fn arb_headers() -> impl Strategy<Value = HashMap<String, String>> {
+    hash_map(arb_header_name(), arb_header_value(), 0..8)
+}
+
+/// クエリパラメータを生成
+pub fn arb_query_params() -> impl Strategy<Value = HashMap<String, String>> {
+    hash_map("[a-z_]{1,8}", "\\PC{0,16}", 0..4)
+}
+
+/// JSONの値を生成（ネストしたオブジェクト・配列を含む）
+pub fn arb_json() -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::from),
+        any::<i64>().prop_map(Value::from),
+        any::<f64>().prop_map(Value::from),
+        "\\PC{0,16}".prop_map(Value::from),
+    ];
+    leaf.prop_recursive(3, 32, 4, |inner| {
+        prop_oneof![
+            vec(inner.clone(), 0..4).prop_map(Value::Array),
+            hash_map("[a-z_]{1,8}", inner, 0..4).prop_map(|map| Value::Object(map.into_iter().collect())),
+        ]
+    })
+}
+
+/// 生成したボディと、それに対応する（ときに食い違う）`Content-Type`・`Content-Encoding`
+#[derive(Debug, Clone)]
+pub struct GeneratedBody {
+    /// ボディ（無い場合はNone）
+    pub body: Option<Vec<u8>>,
+    /// `Content-Type`
+    pub content_type: Option<String>,
+    /// `Content-Encoding`
+    pub content_encoding: Option<String>,
+}
+
+impl GeneratedBody {
+    fn new(body: Option<Vec<u8>>, content_type: Option<&str>) -> Self {
+        Self { body, content_type: content_type.map(str::to_string), content_encoding: None }
+    }
+
+    fn gzip(mut self) -> Self {
+        if let Some(body) = &self.body {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+            // Vecへの書き込みは失敗しない
+            let _ = encoder.write_all(body);
+            self.body = encoder.finish().ok();
+        }
+        self.content_encoding = Some("gzip".to_string());
+        self
+    }
+
+    /// `Request`にボディとヘッダーを設定
+    pub fn apply(self, req: &mut Request) {
+        req.body = self.body;
+        if let Some(content_type) = self.content_type {
+            req.headers.insert("content-type".to_string(), content_type);
+        }
+        if let Some(content_encoding) = self.content_encoding {
+            req.headers.insert("content-encoding".to_string(), content_encoding);
+        }
+    }
+}
+
+/// ボディを生成（JSON・フォーム・テキスト・バイナリ・gzip・壊れたgzip・型の食い違い）
+pub fn arb_body() -> impl Strategy<Value = GeneratedBody> {
+    let json = arb_json().prop_map(|v| serde_json::to_vec(&v).unwrap_or_default()).boxed();
+    let bytes = vec(any::<u8>(), 0..MAX_GENERATED_BODY_LEN);
+    let plain = prop_oneof![
+        2 => Just(GeneratedBody::new(None, None)),
+        3 => json.clone().prop_map(|b| GeneratedBody::new(Some(b), Some("application/json"))),
+        2 => arb_query_params().prop_map(|params| {
+            let body = serde_urlencoded::to_string(params).unwrap_or_default().into_bytes();
+            GeneratedBody::new(Some(body), Some("application/x-www-form-urlencoded"))
+        }),
+        1 => "\\PC{0,64}".prop_map(|s| GeneratedBody::new(Some(s.into_bytes()), Some("text/plain; charset=utf-8"))),
+        1 => bytes.clone().prop_map(|b| GeneratedBody::new(Some(b), Some("application/octet-stream"))),
+        // Content-Typeと中身が食い違うボディ
+        1 => bytes.clone().prop_map(|b| GeneratedBody::new(Some(b), Some("application/json"))),
+        1 => json.prop_map(|b| GeneratedBody::new(Some(b), None)),
+    ]
+    .boxed();
+    prop_oneof![
+        6 => plain.clone(),
+        1 => plain.prop_map(GeneratedBody::gzip),
+        // gzipを名乗る壊れたボディ
+        1 => bytes.prop_map(|b| GeneratedBody {
+            body: Some(b),
+            content_type: Some("application/json".to_string()),
+            content_encoding: Some("gzip".to_string()),
+        }),
+    ]
+}
+
+/// 任意のパスへのリクエストを生成
+pub fn arb_request() -> impl Strategy<Value = Request> {
+    arb_request_for(arb_path())
+}
+
+/// 指定したパスのジェネレーターを使ってリクエストを生成
+///
+/// ルートに届くリクエストを増やすには、`"/items/[0-9]{1,4}"`のような正規表現や
+/// `prop::sample::select`でアプリのパスを指定します。
+pub fn arb_request_for<P>(paths: P) -> impl Strategy<Value = Request>
+where
+    P: Strategy<Value = String>,
+{
+    (arb_method(), paths, arb_query_params(), arb_headers(), arb_body()).prop_map(
+        |(method, path, query_params, headers, body)| {
+            let mut req = Request::new(method, path);
+            req.query_params = query_params;
+            req.headers = headers;
+            body.apply(&mut req);
+            req
+        },
+    )
+}
+
+/// レスポンスが出力可能か検査（ステータスの範囲・ヘッダー名・ヘッダー値・Set-Cookieの値）
+pub fn check_response(res: &Response) -> Result<(), InvariantViolation> {
+    if !(100..=599).contains(&res.status) {
+        return Err(InvariantViolation::InvalidStatus(res.status));
+    }
+    for (name, value) in &res.headers {
+        if !is_header_name_valid(name) {
+            return Err(InvariantViolation::InvalidHeaderName(name.clone()));
+        }
+        if !is_header_value_valid(value) {
+            return Err(InvariantViolation::InvalidHeaderValue { name: name.clone(), value: value.clone() });
+        }
+    }
+    for value in res.set_cookie_values() {
+        if !is_header_value_valid(&value) {
+            return Err(InvariantViolation::InvalidHeaderValue { name: "Set-Cookie".to_string(), value });
+        }
+    }
+    Ok(())
+}
+
+/// `dispatch`でリクエストを処理し、panicしないこととレスポンスの不変条件を検査
+pub async fn dispatch_checked(app: &RunBridge, request: Request) -> Result<Response, InvariantViolation> {
+    let res = AssertUnwindSafe(dispatch(app, request))
+        .catch_unwind()
+        .await
+        .map_err(|payload| {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "non-string panic payload".to_string());
+            InvariantViolation::Panicked(message)
+        })?;
+    check_response(&res)?;
+    Ok(res)
+}
+
+/// `proptest!`の中から`dispatch_checked`を呼び出す（違反はテストケースの失敗になる）
+///
+/// 内部でシングルスレッドのランタイムを作成するため、非同期のコンテキストの中では
+/// `dispatch_checked`を直接使用してください。
+pub fn check_dispatch(app: &RunBridge, request: Request) -> Result<Response, TestCaseError> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| TestCaseError::fail(format!("failed to start runtime: {}", e)))?;
+    runtime
+        .block_on(dispatch_checked(app, request))
+        .map_err(|e| TestCaseError::fail(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{CompressionMiddleware, ETagMiddleware};
+    use crate::handler::{get, post};
+
+    fn app() -> RunBridge {
+        RunBridge::builder()
+            .handler(get("^/items/(?P<id>[^/]+)$", |req: Request| req.path_param::<String>("id")))
+            .handler(post("^/items$", |_req, body: Value| Ok(body)))
+            .handler(get("^/echo$", |req: Request| {
+                // リクエストヘッダーをそのまま返すと不正な値も出力されうる
+                let value = req.headers.get("x-echo").cloned().unwrap_or_default();
+                Ok(Response::ok().with_header("X-Echo", value))
+            }))
+            .around(CompressionMiddleware::new())
+            .around(ETagMiddleware)
+            .build()
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn test_dispatch_never_panics(req in arb_request_for("/items(/[0-9a-z%]{1,6})?|/echo|/other")) {
+            check_dispatch(&app(), req)?;
+        }
+
+        #[test]
+        fn test_generated_requests_are_lowercase(req in arb_request()) {
+            prop_assert!(req.path.starts_with('/'));
+            prop_assert!(req.headers.keys().all(|k| k.chars().all(|c| !c.is_ascii_uppercase())));
+        }
+    }
+
+    #[test]
+    fn test_check_response_reports_violations() {
+        assert!(check_response(&Response::ok()).is_ok());
+        assert_eq!(check_response(&Response::new(42)), Err(InvariantViolation::InvalidStatus(42)));
+
+        let mut res = Response::ok();
+        res.headers.insert("X-Bad".to_string(), "a\r\nb".to_string());
+        assert!(matches!(check_response(&res), Err(InvariantViolation::InvalidHeaderValue { .. })));
+
+        let mut res = Response::ok();
+        res.headers.insert("Bad Name".to_string(), "v".to_string());
+        assert_eq!(check_response(&res), Err(InvariantViolation::InvalidHeaderName("Bad Name".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_checked_catches_panics() {
+        let app = RunBridge::builder()
+            .handler(get("^/boom$", |_req| -> Result<String, crate::error::Error> { panic!("boom") }))
+            .build();
+        let result = dispatch_checked(&app, Request::new(Method::GET, "/boom".to_string())).await;
+        match result {
+            Err(InvariantViolation::Panicked(message)) => assert!(message.contains("boom")),
+            // ハンドラーのpanicをフレームワークが500に変換する場合も許容する
+            Ok(res) => assert_eq!(res.status, 500),
+            Err(other) => panic!("unexpected violation: {}", other),
+        }
+    }
+}
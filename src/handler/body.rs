@@ -1,3 +1,11 @@
+use log::debug;
+use serde::de::DeserializeOwned;
+
+use crate::common::body_fields::{BodyFieldPolicy, BODY_FIELD_POLICY_CONTEXT_KEY};
+use crate::common::utils::get_blocking_deserialize_threshold;
+use crate::common::Request;
+use crate::error::Error;
+
 /// Content-Typeの許容範囲を判定（拡張しやすい実装）
 pub fn is_json_like_content_type(ct: &str) -> bool {
     let main_type = ct
@@ -18,3 +26,49 @@ pub fn is_json_like_content_type(ct: &str) -> bool {
         || EXTRA_ALLOWED.contains(&main_type.as_str())
 }
 
+/// `application/x-www-form-urlencoded`かを判定（HTMLフォームの送信）
+pub fn is_form_urlencoded_content_type(ct: &str) -> bool {
+    ct.split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .eq_ignore_ascii_case("application/x-www-form-urlencoded")
+}
+
+/// JSONボディをデシリアライズ
+///
+/// ボディが`RUNBRIDGE_BLOCKING_DESERIALIZE_THRESHOLD`以上の場合は、非同期ランタイムの
+/// ワーカースレッドを塞がないよう`spawn_blocking`で実行します（ランタイム外では通常どおり実行）。
+/// フィールドの扱いはリクエストコンテキストの`BodyFieldPolicy`（ルート固有 -> 全体設定）に従います。
+pub(crate) async fn deserialize_json_body<T>(req: &mut Request) -> Result<T, Error>
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    let policy = req
+        .context()
+        .get::<BodyFieldPolicy>(BODY_FIELD_POLICY_CONTEXT_KEY)
+        .copied()
+        .unwrap_or_default();
+    let size = req.body.as_ref().map(Vec::len).unwrap_or(0);
+    let handle = match (get_blocking_deserialize_threshold(), tokio::runtime::Handle::try_current()) {
+        (Some(threshold), Ok(handle)) if size >= threshold => handle,
+        _ if policy.is_default() => return req.json::<T>(),
+        _ => {
+            let body = req.body.as_deref().ok_or_else(|| Error::InvalidRequestBody("No request body".to_string()))?;
+            return policy.from_slice::<T>(body);
+        }
+    };
+
+    debug!("Deserializing {} byte body on the blocking pool", size);
+    // ボディはコピーせずに移動し、デシリアライズ後にリクエストへ戻す
+    let body = req.body.take().unwrap_or_default();
+    let (body, result) = handle
+        .spawn_blocking(move || {
+            let result = policy.from_slice::<T>(&body);
+            (body, result)
+        })
+        .await
+        .map_err(|e| Error::InternalServerError(format!("Body deserialization task failed: {}", e)))?;
+    req.body = Some(body);
+    result
+}
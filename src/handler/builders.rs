@@ -35,7 +35,7 @@ where
     F: Fn(Request, T) -> Fut + Send + Sync + 'static,
     T: serde::de::DeserializeOwned + Send + Sync + 'static,
     R: ResponseWrapper + Send + Sync + 'static,
-    Fut: Future<Output = Result<R, Error>> + Send + Sync + 'static,
+    Fut: Future<Output = Result<R, Error>> + Send + 'static,
 {
     move |req, body_data| {
         if let Some(data) = body_data {
@@ -72,7 +72,7 @@ pub fn async_get<F, R, Fut>(path: impl Into<String>, handler: F) -> AsyncRouteHa
 where
     F: Fn(Request) -> Fut + Send + Sync + 'static,
     R: ResponseWrapper + Send + Sync + 'static,
-    Fut: Future<Output = Result<R, Error>> + Send + Sync + 'static,
+    Fut: Future<Output = Result<R, Error>> + Send + 'static,
 {
     #[allow(deprecated)]
     AsyncRouteHandler::new(Method::GET, path, move |req, _| handler(req))
@@ -83,7 +83,7 @@ pub fn try_async_get<F, R, Fut>(path: impl Into<String>, handler: F) -> Result<A
 where
     F: Fn(Request) -> Fut + Send + Sync + 'static,
     R: ResponseWrapper + Send + Sync + 'static,
-    Fut: Future<Output = Result<R, Error>> + Send + Sync + 'static,
+    Fut: Future<Output = Result<R, Error>> + Send + 'static,
 {
     AsyncRouteHandler::try_new(Method::GET, path, move |req, _| handler(req))
 }
@@ -105,7 +105,7 @@ where
     F: Fn(Request, T) -> Fut + Send + Sync + 'static,
     T: DeserializeOwned + Send + Sync + 'static,
     R: ResponseWrapper + Send + Sync + 'static,
-    Fut: Future<Output = Result<R, Error>> + Send + Sync + 'static,
+    Fut: Future<Output = Result<R, Error>> + Send + 'static,
 {
     #[allow(deprecated)]
     AsyncRouteHandler::new(Method::POST, path, require_body_async(handler))
@@ -128,7 +128,7 @@ where
     F: Fn(Request, T) -> Fut + Send + Sync + 'static,
     T: DeserializeOwned + Send + Sync + 'static,
     R: ResponseWrapper + Send + Sync + 'static,
-    Fut: Future<Output = Result<R, Error>> + Send + Sync + 'static,
+    Fut: Future<Output = Result<R, Error>> + Send + 'static,
 {
     #[allow(deprecated)]
     AsyncRouteHandler::new(Method::PUT, path, require_body_async(handler))
@@ -149,7 +149,7 @@ pub fn async_delete<F, R, Fut>(path: impl Into<String>, handler: F) -> AsyncRout
 where
     F: Fn(Request) -> Fut + Send + Sync + 'static,
     R: ResponseWrapper + Send + Sync + 'static,
-    Fut: Future<Output = Result<R, Error>> + Send + Sync + 'static,
+    Fut: Future<Output = Result<R, Error>> + Send + 'static,
 {
     #[allow(deprecated)]
     AsyncRouteHandler::new(Method::DELETE, path, move |req, _| handler(req))
@@ -170,7 +170,7 @@ pub fn async_options<F, R, Fut>(path: impl Into<String>, handler: F) -> AsyncRou
 where
     F: Fn(Request) -> Fut + Send + Sync + 'static,
     R: ResponseWrapper + Send + Sync + 'static,
-    Fut: Future<Output = Result<R, Error>> + Send + Sync + 'static,
+    Fut: Future<Output = Result<R, Error>> + Send + 'static,
 {
     #[allow(deprecated)]
     AsyncRouteHandler::new(Method::OPTIONS, path, move |req, _| handler(req))
@@ -0,0 +1,139 @@
+//! 同一パターンに2つのハンドラーを登録し、リクエスト単位で振り分けるカナリア/ABルーティング
+
+use async_trait::async_trait;
+use log::{debug, warn};
+
+use crate::common::{Handler, Method, Request, Response, RouteOptions};
+use crate::error::Error;
+
+/// カナリア判定用の述語
+type Predicate = Box<dyn Fn(&Request) -> bool + Send + Sync>;
+
+/// 安定版とカナリア版のハンドラーを振り分けるハンドラー
+///
+/// 判定順序: ヘッダー/述語に一致 → カナリア、一致しなければ重み（%）に従って振り分け。
+/// 重みはリクエストごとの乱数で判定するため、プロセスがリクエストごとに起動するCGIや
+/// 起動直後のLambdaでも指定割合で振り分けられます。
+/// ルーティング上のパターンは安定版ハンドラーのものを使用します。
+pub struct CanaryHandler<S, C>
+where
+    S: Handler,
+    C: Handler,
+{
+    stable: S,
+    canary: C,
+    predicates: Vec<Predicate>,
+    weight: u8,
+}
+
+impl<S, C> CanaryHandler<S, C>
+where
+    S: Handler,
+    C: Handler,
+{
+    /// 新しいCanaryHandlerを作成（初期状態ではすべて安定版に振り分け）
+    pub fn new(stable: S, canary: C) -> Self {
+        Self {
+            stable,
+            canary,
+            predicates: Vec::new(),
+            weight: 0,
+        }
+    }
+
+    /// 指定ヘッダーが指定値（大文字小文字を区別しない）の場合にカナリアへ振り分け
+    pub fn header(self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        let name = name.into().to_ascii_lowercase();
+        let value = value.into();
+        self.when(move |req| {
+            req.headers
+                .get(&name)
+                .map(|v| v.trim().eq_ignore_ascii_case(&value))
+                .unwrap_or(false)
+        })
+    }
+
+    /// 任意の条件に一致する場合にカナリアへ振り分け
+    pub fn when<P>(mut self, predicate: P) -> Self
+    where
+        P: Fn(&Request) -> bool + Send + Sync + 'static,
+    {
+        self.predicates.push(Box::new(predicate));
+        self
+    }
+
+    /// 条件に一致しないリクエストのうちカナリアへ振り分ける割合（0〜100%、超過分は100に丸める）
+    pub fn weight(mut self, percent: u8) -> Self {
+        self.weight = percent.min(100);
+        self
+    }
+
+    /// リクエストをカナリアへ振り分けるかどうか
+    fn select_canary(&self, req: &Request) -> bool {
+        if !self.canary.matches(&req.path, &req.method) {
+            return false;
+        }
+        if self.predicates.iter().any(|p| p(req)) {
+            return true;
+        }
+        if self.weight == 0 {
+            return false;
+        }
+        // プロセス内の状態に依存しないよう、リクエストごとに0〜99の値を引いて判定する
+        let mut bytes = [0u8; 8];
+        if let Err(e) = getrandom::getrandom(&mut bytes) {
+            warn!("Failed to draw a random value for canary routing, using stable handler: {}", e);
+            return false;
+        }
+        u64::from_le_bytes(bytes) % 100 < u64::from(self.weight)
+    }
+}
+
+#[async_trait]
+impl<S, C> Handler for CanaryHandler<S, C>
+where
+    S: Handler,
+    C: Handler,
+{
+    fn matches(&self, path: &str, method: &Method) -> bool {
+        self.stable.matches(path, method)
+    }
+
+    fn path_pattern(&self) -> &str {
+        self.stable.path_pattern()
+    }
+
+    fn options(&self) -> &RouteOptions {
+        self.stable.options()
+    }
+
+    fn matches_by_pattern(&self) -> bool {
+        self.stable.matches_by_pattern()
+    }
+
+    fn methods(&self) -> Vec<Method> {
+        self.stable.methods()
+    }
+
+    fn check_pre_body(&self, req: &Request) -> Result<(), Error> {
+        self.stable.check_pre_body(req)
+    }
+
+    async fn handle(&self, req: Request) -> Result<Response, Error> {
+        if self.select_canary(&req) {
+            debug!("Routing {} {} to canary handler", req.method, req.path);
+            self.canary.handle(req).await
+        } else {
+            self.stable.handle(req).await
+        }
+    }
+}
+
+/// 安定版とカナリア版のハンドラーからCanaryHandlerを作成
+pub fn canary<S, C>(stable: S, canary: C) -> CanaryHandler<S, C>
+where
+    S: Handler,
+    C: Handler,
+{
+    CanaryHandler::new(stable, canary)
+}
@@ -0,0 +1,147 @@
+//! 適用中の設定を返す運用向けのデバッグエンドポイント
+//!
+//! 上限値・有効なfeature・ルート数・ミドルウェアの一覧・ビルド情報をJSONで返します。
+//! 本番環境での調査用のため、トークンによる認証が必須で、センシティブなキーの値は伏せ字にします。
+//! トークンが未設定の場合はどのリクエストにもマッチしません。
+
+use std::env;
+use std::sync::{Arc, OnceLock};
+
+use async_trait::async_trait;
+use log::warn;
+use serde_json::{json, Value};
+
+use crate::common::signed_url::constant_time_eq;
+use crate::common::{Handler, Method, Request, Response};
+use crate::error::Error;
+use super::echo::{redact_json, REDACTED};
+use super::health::get_app_version;
+
+/// 設定エンドポイントの既定のパス
+pub const DEFAULT_CONFIG_ENDPOINT_PATH: &str = "/__runbridge/config";
+
+/// 設定エンドポイントの認証トークンを取得する
+/// 優先順位: 環境変数 `RUNBRIDGE_CONFIG_TOKEN` -> デフォルト なし（無効）
+pub fn get_config_endpoint_token() -> Option<String> {
+    env::var("RUNBRIDGE_CONFIG_TOKEN").ok().filter(|v| !v.trim().is_empty())
+}
+
+/// 適用中の設定をJSONで返すハンドラー（GET、`RunBridgeBuilder::config_endpoint`で登録）
+///
+/// `Authorization: Bearer <token>`が一致しない場合は401を返します。設定は`build()`の時点の
+/// `RunBridge::config_report`を基にしたものです。
+///
+/// ```
+/// use runbridge::handler::ConfigEndpoint;
+/// use runbridge::RunBridge;
+///
+/// let app = RunBridge::builder()
+///     .config_endpoint(ConfigEndpoint::new().token("change-me"))
+///     .build();
+/// # drop(app);
+/// ```
+#[derive(Clone)]
+pub struct ConfigEndpoint {
+    path: String,
+    token: Option<String>,
+    snapshot: Arc<OnceLock<Value>>,
+}
+
+impl std::fmt::Debug for ConfigEndpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConfigEndpoint")
+            .field("path", &self.path)
+            .field("token", &self.token.as_ref().map(|_| REDACTED))
+            .finish()
+    }
+}
+
+impl Default for ConfigEndpoint {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConfigEndpoint {
+    /// 既定のパスで作成（トークンは環境変数 `RUNBRIDGE_CONFIG_TOKEN` に従う）
+    pub fn new() -> Self {
+        Self {
+            path: DEFAULT_CONFIG_ENDPOINT_PATH.to_string(),
+            token: get_config_endpoint_token(),
+            snapshot: Arc::new(OnceLock::new()),
+        }
+    }
+
+    /// パスを変更
+    pub fn path(mut self, path: impl Into<String>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// 環境変数に関わらず認証トークンを指定
+    pub fn token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into()).filter(|t| !t.trim().is_empty());
+        self
+    }
+
+    /// 返す設定を確定（`build()`で1回だけ呼び出す）
+    pub(crate) fn publish(&self, mut report: Value) {
+        if self.token.is_none() {
+            warn!("Config endpoint at {} is disabled because no token is configured", self.path);
+        }
+        // 問題の一覧はキー名（`key`）を含むため、設定値だけを伏せ字にする
+        if let Some(settings) = report.get_mut("settings") {
+            redact_json(settings);
+        }
+        let body = json!({
+            "config": report,
+            "build": {
+                "runbridge_version": env!("CARGO_PKG_VERSION"),
+                "app_version": get_app_version(),
+                "profile": if cfg!(debug_assertions) { "debug" } else { "release" },
+            },
+        });
+        let _ = self.snapshot.set(body);
+    }
+
+    fn is_authorized(&self, req: &Request) -> bool {
+        let (Some(token), Some(header)) = (&self.token, req.headers.get("authorization")) else {
+            return false;
+        };
+        let presented = header
+            .split_once(' ')
+            .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("bearer"))
+            .map(|(_, value)| value.trim())
+            .unwrap_or("");
+        constant_time_eq(presented.as_bytes(), token.as_bytes())
+    }
+}
+
+#[async_trait]
+impl Handler for ConfigEndpoint {
+    fn matches(&self, path: &str, method: &Method) -> bool {
+        self.token.is_some() && path == self.path && *method == Method::GET
+    }
+
+    fn path_pattern(&self) -> &str {
+        &self.path
+    }
+
+    fn methods(&self) -> Vec<Method> {
+        vec![Method::GET]
+    }
+
+    async fn handle(&self, req: Request) -> Result<Response, Error> {
+        if !self.is_authorized(&req) {
+            warn!("Rejected unauthenticated request to config endpoint {}", self.path);
+            return Err(Error::AuthenticationError("invalid config endpoint token".to_string()));
+        }
+        let snapshot = self.snapshot.get().cloned().unwrap_or(Value::Null);
+        let body = serde_json::to_vec(&snapshot)
+            .map_err(|e| Error::ResponseSerializationError(e.to_string()))?;
+        Ok(Response::ok()
+            .with_header("Content-Type", "application/json")
+            .with_header("Cache-Control", "no-store")
+            .with_body(body))
+    }
+}
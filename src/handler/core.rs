@@ -13,7 +13,7 @@ use std::time::{Duration, Instant};
 use crate::common::{Handler, Method, Request, Response};
 use crate::error::Error;
 
-use super::body::is_json_like_content_type;
+use super::body::{deserialize_json_body, is_form_urlencoded_content_type, is_json_like_content_type};
 use super::pattern::ensure_safe_pattern;
 use super::response::ResponseWrapper;
 
@@ -91,7 +91,7 @@ where
     F: Fn(Request, Option<T>) -> Fut + Send + Sync + 'static,
     T: DeserializeOwned + Send + Sync + 'static,
     R: ResponseWrapper + Send + Sync + 'static,
-    Fut: Future<Output = Result<R, Error>> + Send + Sync + 'static,
+    Fut: Future<Output = Result<R, Error>> + Send + 'static,
 {
     /// ルートパス（正規表現パターン）
     pub path_pattern: String,
@@ -105,8 +105,8 @@ where
     pub _request_type: PhantomData<T>,
     /// レスポンスボディの型
     pub _response_type: PhantomData<R>,
-    /// Future型
-    pub _future_type: PhantomData<Fut>,
+    /// Future型（FutureがSyncでなくてもハンドラーがSyncになるよう関数ポインタで保持）
+    pub _future_type: PhantomData<fn() -> Fut>,
 }
 
 impl<F, T, R, Fut> AsyncRouteHandler<F, T, R, Fut>
@@ -114,7 +114,7 @@ where
     F: Fn(Request, Option<T>) -> Fut + Send + Sync + 'static,
     T: DeserializeOwned + Send + Sync + 'static,
     R: ResponseWrapper + Send + Sync + 'static,
-    Fut: Future<Output = Result<R, Error>> + Send + Sync + 'static,
+    Fut: Future<Output = Result<R, Error>> + Send + 'static,
 {
     /// 新しいAsyncRouteHandlerを作成
     pub fn try_new(
@@ -219,8 +219,16 @@ where
         &self.path_pattern
     }
 
-    async fn handle(&self, req: Request) -> Result<Response, Error> {
-        // リクエストボディが長さ>0のときのみContent-Type検証とJSONパースを行う
+    fn matches_by_pattern(&self) -> bool {
+        true
+    }
+
+    fn methods(&self) -> Vec<Method> {
+        vec![self.method]
+    }
+
+    async fn handle(&self, mut req: Request) -> Result<Response, Error> {
+        // リクエストボディが長さ>0のときのみContent-Type検証とパースを行う
         let has_non_empty_body = req.body.as_ref().map(|b| !b.is_empty()).unwrap_or(false);
         let body_data = if has_non_empty_body {
             // 取込み時にヘッダーは小文字化されている前提
@@ -231,15 +239,17 @@ where
                 Error::InvalidRequestBody("Missing Content-Type header".to_string())
             })?;
 
-            if !is_json_like_content_type(&ct) {
-                warn!("Unsupported Content-Type for JSON parsing: {}", ct);
+            if is_form_urlencoded_content_type(&ct) {
+                Some(req.form::<T>()?)
+            } else if is_json_like_content_type(&ct) {
+                Some(deserialize_json_body::<T>(&mut req).await?)
+            } else {
+                warn!("Unsupported Content-Type for body parsing: {}", ct);
                 return Err(Error::InvalidRequestBody(format!(
-                    "Unsupported Content-Type: {} (expected application/json or *+json)",
+                    "Unsupported Content-Type: {} (expected application/json or *+json, or application/x-www-form-urlencoded)",
                     ct
                 )));
             }
-
-            Some(req.json::<T>()?)
         } else {
             None
         };
@@ -255,7 +265,7 @@ where
     F: Fn(Request, Option<T>) -> Fut + Send + Sync + 'static,
     T: DeserializeOwned + Send + Sync + 'static,
     R: ResponseWrapper + Send + Sync + 'static,
-    Fut: Future<Output = Result<R, Error>> + Send + Sync + 'static,
+    Fut: Future<Output = Result<R, Error>> + Send + 'static,
 {
     fn matches(&self, path: &str, method: &Method) -> bool {
         if method != &self.method {
@@ -311,8 +321,16 @@ where
         &self.path_pattern
     }
 
-    async fn handle(&self, req: Request) -> Result<Response, Error> {
-        // リクエストボディが長さ>0のときのみContent-Type検証とJSONパースを行う
+    fn matches_by_pattern(&self) -> bool {
+        true
+    }
+
+    fn methods(&self) -> Vec<Method> {
+        vec![self.method]
+    }
+
+    async fn handle(&self, mut req: Request) -> Result<Response, Error> {
+        // リクエストボディが長さ>0のときのみContent-Type検証とパースを行う
         let has_non_empty_body = req.body.as_ref().map(|b| !b.is_empty()).unwrap_or(false);
         let body_data = if has_non_empty_body {
             // 取込み時にヘッダーは小文字化されている前提
@@ -323,15 +341,17 @@ where
                 Error::InvalidRequestBody("Missing Content-Type header".to_string())
             })?;
 
-            if !is_json_like_content_type(&ct) {
-                warn!("Unsupported Content-Type for JSON parsing: {}", ct);
+            if is_form_urlencoded_content_type(&ct) {
+                Some(req.form::<T>()?)
+            } else if is_json_like_content_type(&ct) {
+                Some(deserialize_json_body::<T>(&mut req).await?)
+            } else {
+                warn!("Unsupported Content-Type for body parsing: {}", ct);
                 return Err(Error::InvalidRequestBody(format!(
-                    "Unsupported Content-Type: {} (expected application/json or *+json)",
+                    "Unsupported Content-Type: {} (expected application/json or *+json, or application/x-www-form-urlencoded)",
                     ct
                 )));
             }
-
-            Some(req.json::<T>()?)
         } else {
             None
         };
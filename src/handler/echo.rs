@@ -0,0 +1,258 @@
+//! 開発用のデバッグエコーハンドラー
+//!
+//! 受信したリクエスト（メソッド・パス・クエリ・ヘッダー・ボディ）をJSONで返します。
+//! Authorization等のセンシティブな値は伏せ字にし、大きな値は切り詰めます。
+//! 誤って本番環境に公開しないよう、明示的に有効化しない限りどのリクエストにもマッチしません。
+
+use std::env;
+
+use async_trait::async_trait;
+use log::{info, warn};
+use serde_json::{Map, Value};
+
+use crate::common::redaction::is_sensitive_key_like;
+use crate::common::{Handler, Method, Request, Response};
+use crate::error::Error;
+
+pub(crate) const REDACTED: &str = "***redacted***";
+
+/// ヘッダー・クエリの値の既定の最大長（バイト）
+pub const DEFAULT_MAX_VALUE_LEN: usize = 256;
+
+/// ボディの既定の最大長（バイト）
+pub const DEFAULT_MAX_BODY_LEN: usize = 4096;
+
+/// デバッグエコーを有効にするかどうかを取得する
+/// 優先順位: 環境変数 `RUNBRIDGE_DEBUG_ECHO`（`1` / `true`） -> デフォルト 無効
+pub fn is_debug_echo_enabled() -> bool {
+    env::var("RUNBRIDGE_DEBUG_ECHO")
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true"))
+        .unwrap_or(false)
+}
+
+/// リクエスト内容をJSONで返すデバッグ用ハンドラー（GET/POST）
+#[derive(Debug, Clone)]
+pub struct DebugEchoHandler {
+    path: String,
+    enabled: bool,
+    max_value_len: usize,
+    max_body_len: usize,
+}
+
+impl DebugEchoHandler {
+    /// 指定したパスで作成（有効/無効は環境変数 `RUNBRIDGE_DEBUG_ECHO` に従う）
+    pub fn new(path: impl Into<String>) -> Self {
+        let path = path.into();
+        let enabled = is_debug_echo_enabled();
+        if enabled {
+            warn!("Debug echo endpoint is enabled at {}; do not expose it in production", path);
+        }
+        Self {
+            path,
+            enabled,
+            max_value_len: DEFAULT_MAX_VALUE_LEN,
+            max_body_len: DEFAULT_MAX_BODY_LEN,
+        }
+    }
+
+    /// 環境変数に関わらず有効/無効を指定
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    /// ヘッダー・クエリの値の最大長（バイト）を指定
+    pub fn max_value_len(mut self, len: usize) -> Self {
+        self.max_value_len = len;
+        self
+    }
+
+    /// ボディの最大長（バイト）を指定
+    pub fn max_body_len(mut self, len: usize) -> Self {
+        self.max_body_len = len;
+        self
+    }
+
+    /// キーと値の一覧を伏せ字・切り詰め済みのJSONオブジェクトに変換
+    fn echo_pairs<'a>(&self, pairs: impl Iterator<Item = (&'a String, &'a String)>) -> Value {
+        let mut map = Map::new();
+        for (key, value) in pairs {
+            let value = if is_sensitive_key_like(&key.to_ascii_lowercase()) {
+                REDACTED.to_string()
+            } else {
+                truncate(value, self.max_value_len)
+            };
+            map.insert(key.clone(), Value::String(value));
+        }
+        Value::Object(map)
+    }
+
+    fn echo_body(&self, req: &Request, data: &mut Map<String, Value>) {
+        let body = match &req.body {
+            Some(body) if !body.is_empty() => body,
+            _ => return,
+        };
+        data.insert("body_size".to_string(), Value::from(body.len()));
+
+        let text = match std::str::from_utf8(body) {
+            Ok(text) => text,
+            Err(_) => {
+                data.insert(
+                    "body".to_string(),
+                    Value::String(format!("<binary data of {} bytes>", body.len())),
+                );
+                return;
+            }
+        };
+
+        // JSONボディは文字列ではなく展開して表示（センシティブなキーは伏せ字）
+        let is_json = req
+            .headers
+            .get("content-type")
+            .is_some_and(|ct| ct.contains("application/json"));
+        let parsed = if is_json && body.len() <= self.max_body_len {
+            serde_json::from_str::<Value>(text).ok()
+        } else {
+            None
+        };
+        if let Some(mut json) = parsed {
+            redact_json(&mut json);
+            data.insert("body".to_string(), json);
+            return;
+        }
+        data.insert("body".to_string(), Value::String(truncate(text, self.max_body_len)));
+    }
+}
+
+#[async_trait]
+impl Handler for DebugEchoHandler {
+    fn matches(&self, path: &str, method: &Method) -> bool {
+        self.enabled && path == self.path && matches!(method, Method::GET | Method::POST)
+    }
+
+    fn path_pattern(&self) -> &str {
+        &self.path
+    }
+
+    fn methods(&self) -> Vec<Method> {
+        vec![Method::GET, Method::POST]
+    }
+
+    async fn handle(&self, req: Request) -> Result<Response, Error> {
+        info!("Handling debug echo request");
+
+        let mut data = Map::new();
+        data.insert("method".to_string(), Value::String(req.method.to_string()));
+        data.insert("path".to_string(), Value::String(req.path.clone()));
+        data.insert("query".to_string(), self.echo_pairs(req.query_params.iter()));
+        data.insert("headers".to_string(), self.echo_pairs(req.headers.iter()));
+        self.echo_body(&req, &mut data);
+
+        let body = serde_json::to_vec(&Value::Object(data))
+            .map_err(|e| Error::ResponseSerializationError(e.to_string()))?;
+        Ok(Response::ok()
+            .with_header("Content-Type", "application/json")
+            .with_header("Cache-Control", "no-store")
+            .with_body(body))
+    }
+}
+
+/// 文字境界を保ったまま最大長に切り詰める
+fn truncate(value: &str, max_len: usize) -> String {
+    if value.len() <= max_len {
+        return value.to_string();
+    }
+    let mut end = max_len;
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}...[truncated {} bytes]", &value[..end], value.len() - end)
+}
+
+/// JSON内のセンシティブなキーの値を再帰的に伏せ字にする
+pub(crate) fn redact_json(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, v) in map.iter_mut() {
+                if is_sensitive_key_like(&key.to_ascii_lowercase()) {
+                    *v = Value::String(REDACTED.to_string());
+                } else {
+                    redact_json(v);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_json),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temp_env::with_var;
+
+    async fn echo(handler: &DebugEchoHandler, req: Request) -> Value {
+        let res = handler.handle(req).await.unwrap();
+        assert_eq!(res.headers.get("Cache-Control").map(String::as_str), Some("no-store"));
+        serde_json::from_slice(res.body.as_deref().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_disabled_by_default() {
+        with_var("RUNBRIDGE_DEBUG_ECHO", None::<&str>, || {
+            let handler = DebugEchoHandler::new("/echo");
+            assert!(!handler.matches("/echo", &Method::GET));
+            assert!(handler.enabled(true).matches("/echo", &Method::GET));
+        });
+        with_var("RUNBRIDGE_DEBUG_ECHO", Some("true"), || {
+            let handler = DebugEchoHandler::new("/echo");
+            assert!(handler.matches("/echo", &Method::POST));
+            assert!(!handler.matches("/echo", &Method::PUT));
+            assert!(!handler.matches("/other", &Method::GET));
+        });
+    }
+
+    #[tokio::test]
+    async fn test_redacts_and_truncates() {
+        let handler = DebugEchoHandler::new("/echo").enabled(true).max_value_len(8);
+        let req = Request::new(Method::GET, "/echo".to_string())
+            .with_query_param("access_token", "abc")
+            .with_query_param("q", "rust")
+            .with_header("Authorization", "Bearer secret")
+            .with_header("Cookie", "session=1")
+            .with_header("User-Agent", "あいうえお");
+
+        let json = echo(&handler, req).await;
+        assert_eq!(json["query"]["access_token"], REDACTED);
+        assert_eq!(json["query"]["q"], "rust");
+        assert_eq!(json["headers"]["authorization"], REDACTED);
+        assert_eq!(json["headers"]["cookie"], REDACTED);
+        // 文字の途中で切らない
+        assert_eq!(json["headers"]["user-agent"], "あい...[truncated 9 bytes]");
+    }
+
+    #[tokio::test]
+    async fn test_body_echo() {
+        let handler = DebugEchoHandler::new("/echo").enabled(true).max_body_len(16);
+
+        let req = Request::new(Method::POST, "/echo".to_string())
+            .with_header("Content-Type", "application/json")
+            .with_body(br#"{"user":{"password":"x","name":"a"}}"#.to_vec());
+        let json = echo(&handler, req).await;
+        assert_eq!(json["body_size"], 36);
+        // 上限を超えるJSONは展開せず文字列として切り詰める
+        assert_eq!(json["body"], r#"{"user":{"passwo...[truncated 20 bytes]"#);
+
+        let handler = handler.max_body_len(DEFAULT_MAX_BODY_LEN);
+        let req = Request::new(Method::POST, "/echo".to_string())
+            .with_header("Content-Type", "application/json")
+            .with_body(br#"{"user":{"password":"x","name":"a"}}"#.to_vec());
+        let json = echo(&handler, req).await;
+        assert_eq!(json["body"]["user"]["password"], REDACTED);
+        assert_eq!(json["body"]["user"]["name"], "a");
+
+        let req = Request::new(Method::POST, "/echo".to_string()).with_body(vec![0xff, 0xfe]);
+        let json = echo(&handler, req).await;
+        assert_eq!(json["body"], "<binary data of 2 bytes>");
+    }
+}
@@ -0,0 +1,87 @@
+//! ハンドラーに対する拡張メソッド
+
+use crate::common::{BodyFieldPolicy, BodyPolicy, Handler, OperationDoc, Request};
+use crate::common::circuit_breaker::CircuitBreaker;
+use crate::common::dependency::DependencyRegistry;
+use crate::common::signed_url::UrlSigner;
+use crate::error::Error;
+
+use super::fields::SparseFieldsHandler;
+use super::guard::{CircuitBreakerGuard, DependencyGuard, FlagGuard, OriginGuard, PreBodyGuard, SignedUrlGuard};
+use super::named::{BodyFieldsHandler, BodyLimitHandler, BodyPolicyHandler, DocumentedHandler, NamedHandler, RouteOptionsHandler};
+
+/// ハンドラーに対する拡張メソッド
+pub trait HandlerExt: Handler + Sized {
+    /// ルート名を付与する（`Request::matched_route`やログで参照可能）
+    fn name(self, name: impl Into<String>) -> NamedHandler<Self> {
+        let name = name.into();
+        RouteOptionsHandler::new(self, |options| options.name = Some(name))
+    }
+
+    /// フィーチャーフラグが有効な場合のみ実行する
+    fn when_flag(self, flag: impl Into<String>) -> FlagGuard<Self> {
+        FlagGuard::new(self, flag)
+    }
+
+    /// 指定した鍵で署名された有効期限内のURLでのみ実行する（`UrlSigner`で生成）
+    fn require_signed_url(self, key: impl Into<Vec<u8>>) -> SignedUrlGuard<Self> {
+        SignedUrlGuard::new(self, UrlSigner::new(key))
+    }
+
+    /// `Origin`ヘッダーが許可リストに含まれる場合のみ実行する（WebSocket・SSE向け、CORSとは別の検査）
+    fn allow_origins<I, S>(self, origins: I) -> OriginGuard<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        OriginGuard::new(self, origins)
+    }
+
+    /// 依存が停止中と判明している間は503を返す（状態は`DependencyRegistry`を参照）
+    fn depends_on(self, registry: DependencyRegistry, dependency: impl Into<String>) -> DependencyGuard<Self> {
+        DependencyGuard::new(self, registry, dependency)
+    }
+
+    /// サーキットブレーカーを適用する（同じ依存を使うルートには同じインスタンスのクローンを渡す）
+    fn circuit_breaker(self, breaker: CircuitBreaker) -> CircuitBreakerGuard<Self> {
+        CircuitBreakerGuard::new(self, breaker)
+    }
+
+    /// リクエストボディの受信前に検査する（`Expect: 100-continue`ではボディの送信前に拒否できる）
+    fn pre_body_guard<F>(self, check: F) -> PreBodyGuard<Self>
+    where
+        F: Fn(&Request) -> Result<(), Error> + Send + Sync + 'static,
+    {
+        PreBodyGuard::new(self, check)
+    }
+
+    /// GET/HEAD/DELETEのボディに対する方針をこのルートだけ変更する
+    fn with_body_policy(self, policy: BodyPolicy) -> BodyPolicyHandler<Self> {
+        RouteOptionsHandler::new(self, |options| options.body_policy = Some(policy))
+    }
+
+    /// リクエストボディの上限（バイト）をこのルートだけ変更する（例: `.max_body_size(20 * MB)`）
+    ///
+    /// 全体設定（`RUNBRIDGE_MAX_BODY_SIZE`）より大きい値・小さい値のどちらも指定でき、
+    /// 各ランタイムはボディの受信時とハンドラーの実行前（JSONの解析前）にこの上限を検査します。
+    fn max_body_size(self, bytes: usize) -> BodyLimitHandler<Self> {
+        RouteOptionsHandler::new(self, |options| options.body_size_limit = Some(bytes))
+    }
+
+    /// 型付きボディ（JSON）の未知のフィールド・フィールド名の照合の方針をこのルートだけ変更する
+    fn body_fields(self, policy: BodyFieldPolicy) -> BodyFieldsHandler<Self> {
+        RouteOptionsHandler::new(self, |options| options.body_field_policy = Some(policy))
+    }
+
+    /// OpenAPIドキュメント（`RunBridge::openapi_spec`）に出力する概要・スキーマを付与する
+    fn openapi(self, doc: OperationDoc) -> DocumentedHandler<Self> {
+        RouteOptionsHandler::new(self, |options| options.operation_doc = Some(doc))
+    }
+
+    /// `?fields=a,b.c`で指定されたフィールドだけをJSONレスポンスに残す
+    fn sparse_fields(self) -> SparseFieldsHandler<Self> {
+        SparseFieldsHandler::new(self)
+    }
+}
+
+impl<H: Handler> HandlerExt for H {}
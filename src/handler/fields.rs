@@ -0,0 +1,222 @@
+//! `?fields=a,b,c`によるJSONレスポンスのフィールド選択（スパースフィールドセット）
+//!
+//! 低速な回線のクライアントが、ハンドラーを変更せずにレスポンスを必要な項目だけに絞れるようにします。
+//! ルートごとに`HandlerExt::sparse_fields`で有効化した場合のみ適用されます。
+
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use log::{debug, warn};
+use serde_json::{Map, Value};
+
+use crate::common::{Handler, Method, Request, Response, RouteOptions};
+use crate::error::Error;
+
+/// フィールド一覧を指定するクエリパラメータ名
+pub const FIELDS_PARAM: &str = "fields";
+
+/// `?fields=`で指定されたフィールドだけをJSONレスポンスに残すハンドラー
+///
+/// - フィールドはカンマ区切りで、`author.name`のようにドットで入れ子を指定できます
+/// - 本体が配列の場合は各要素に適用します
+/// - 2xxかつ`application/json`のレスポンスのみ対象で、存在しないフィールドは無視します
+pub struct SparseFieldsHandler<H: Handler> {
+    inner: H,
+    within: Option<String>,
+}
+
+impl<H: Handler> SparseFieldsHandler<H> {
+    /// 新しいSparseFieldsHandlerを作成
+    pub fn new(inner: H) -> Self {
+        Self { inner, within: None }
+    }
+
+    /// ルートではなく指定したメンバーに適用（例: `Page`の`items`）
+    pub fn within(mut self, member: impl Into<String>) -> Self {
+        self.within = Some(member.into());
+        self
+    }
+}
+
+#[async_trait]
+impl<H: Handler> Handler for SparseFieldsHandler<H> {
+    fn matches(&self, path: &str, method: &Method) -> bool {
+        self.inner.matches(path, method)
+    }
+
+    fn path_pattern(&self) -> &str {
+        self.inner.path_pattern()
+    }
+
+    fn options(&self) -> &RouteOptions {
+        self.inner.options()
+    }
+
+    fn matches_by_pattern(&self) -> bool {
+        self.inner.matches_by_pattern()
+    }
+
+    fn methods(&self) -> Vec<Method> {
+        self.inner.methods()
+    }
+
+    fn check_pre_body(&self, req: &Request) -> Result<(), Error> {
+        self.inner.check_pre_body(req)
+    }
+
+    async fn handle(&self, req: Request) -> Result<Response, Error> {
+        let fields = parse_fields(req.query_params.get(FIELDS_PARAM).map(String::as_str));
+        let response = self.inner.handle(req).await?;
+        if fields.is_empty() {
+            return Ok(response);
+        }
+        Ok(apply_fields(response, &fields, self.within.as_deref()))
+    }
+}
+
+/// 選択するフィールドの木（子が空の場合はその値全体を選択）
+#[derive(Debug, Default, PartialEq)]
+struct FieldTree {
+    children: BTreeMap<String, FieldTree>,
+    whole: bool,
+}
+
+impl FieldTree {
+    fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+}
+
+/// `a,b.c`形式のフィールド一覧を木に変換（`a`と`a.b`の両方がある場合は`a`全体を選択）
+fn parse_fields(value: Option<&str>) -> FieldTree {
+    let mut root = FieldTree::default();
+    for field in value.unwrap_or("").split(',').map(str::trim).filter(|f| !f.is_empty()) {
+        let mut node = &mut root;
+        for part in field.split('.').filter(|p| !p.is_empty()) {
+            node = node.children.entry(part.to_string()).or_default();
+        }
+        node.whole = true;
+    }
+    root
+}
+
+fn apply_fields(mut response: Response, fields: &FieldTree, within: Option<&str>) -> Response {
+    let is_json = response
+        .headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("content-type"))
+        .is_some_and(|(_, v)| v.contains("application/json"));
+    if !(200..300).contains(&response.status) || !is_json {
+        return response;
+    }
+    let mut json = match response.body.as_deref().map(serde_json::from_slice::<Value>) {
+        Some(Ok(json)) => json,
+        _ => return response,
+    };
+
+    let target = match within {
+        Some(member) => match json.get_mut(member) {
+            Some(target) => target,
+            None => return response,
+        },
+        None => &mut json,
+    };
+    *target = select(target.take(), fields);
+
+    match serde_json::to_vec(&json) {
+        Ok(body) => {
+            debug!("Applied sparse fieldset to response");
+            response.body = Some(body);
+        }
+        Err(e) => warn!("Failed to serialize sparse fieldset response: {}", e),
+    }
+    response
+}
+
+/// 値から指定したフィールドだけを残す（配列は各要素に適用）
+fn select(value: Value, fields: &FieldTree) -> Value {
+    match value {
+        Value::Array(items) => Value::Array(items.into_iter().map(|v| select(v, fields)).collect()),
+        Value::Object(mut map) => {
+            let mut selected = Map::new();
+            for (key, child) in &fields.children {
+                if let Some(value) = map.remove(key) {
+                    let value = if child.whole { value } else { select(value, child) };
+                    selected.insert(key.clone(), value);
+                }
+            }
+            Value::Object(selected)
+        }
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handler::{get, HandlerExt};
+    use serde_json::json;
+
+    async fn call<H: Handler>(handler: &H, fields: Option<&str>) -> (Response, Value) {
+        let mut req = Request::new(Method::GET, "/posts".to_string());
+        if let Some(fields) = fields {
+            req = req.with_query_param(FIELDS_PARAM, fields);
+        }
+        let res = handler.handle(req).await.unwrap();
+        let json = serde_json::from_slice(res.body.as_deref().unwrap()).unwrap();
+        (res, json)
+    }
+
+    fn posts(_req: Request) -> Result<Value, Error> {
+        Ok(json!([
+            {"id": 1, "title": "a", "body": "long", "author": {"name": "x", "email": "x@example.com"}},
+            {"id": 2, "title": "b", "body": "long", "author": {"name": "y", "email": "y@example.com"}},
+        ]))
+    }
+
+    #[test]
+    fn test_parse_fields() {
+        let tree = parse_fields(Some(" id, author.name ,,author.email"));
+        assert_eq!(tree.children.keys().collect::<Vec<_>>(), vec!["author", "id"]);
+        assert!(tree.children["id"].whole);
+        assert!(!tree.children["author"].whole);
+        assert_eq!(tree.children["author"].children.len(), 2);
+        assert!(parse_fields(None).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sparse_fields_on_array() {
+        let handler = get("/posts", posts).sparse_fields();
+
+        let (_, json) = call(&handler, Some("id,author.name,missing")).await;
+        assert_eq!(json, json!([{"id": 1, "author": {"name": "x"}}, {"id": 2, "author": {"name": "y"}}]));
+
+        // fieldsが無い場合はそのまま
+        let (_, json) = call(&handler, None).await;
+        assert_eq!(json[0]["body"], "long");
+
+        // 親の全体指定が入れ子指定より優先される
+        let (_, json) = call(&handler, Some("author,author.name")).await;
+        assert_eq!(json[0]["author"]["email"], "x@example.com");
+    }
+
+    #[tokio::test]
+    async fn test_sparse_fields_within_member_and_non_json() {
+        fn page(_req: Request) -> Result<Value, Error> {
+            Ok(json!({"items": [{"id": 1, "title": "a"}], "page": 1, "total": 1}))
+        }
+        let handler = get("/posts", page).sparse_fields().within("items");
+        let (_, json) = call(&handler, Some("id")).await;
+        assert_eq!(json, json!({"items": [{"id": 1}], "page": 1, "total": 1}));
+
+        fn error(_req: Request) -> Result<Response, Error> {
+            Ok(Response::new(404)
+                .with_header("Content-Type", "application/json")
+                .with_body(br#"{"error":"not found","code":404}"#.to_vec()))
+        }
+        let handler = get("/posts", error).sparse_fields();
+        let (res, json) = call(&handler, Some("code")).await;
+        assert_eq!(res.status, 404);
+        assert_eq!(json["error"], "not found");
+    }
+}
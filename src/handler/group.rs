@@ -0,0 +1,206 @@
+//! 共通のパスプレフィックスとミドルウェアを持つルートのグループ
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use log::error;
+
+use crate::common::{
+    AroundMiddleware, Handler, Method, Middleware, Next, Request, Response, RouteOptions, SecurityProfile,
+};
+use crate::error::Error;
+
+/// 共通のプレフィックス（例: `/api/v1`）の下にハンドラーをまとめるグループ
+///
+/// `RunBridgeBuilder::scope`・`RunBridgeBuilder::group`で登録します。ハンドラーのパターンは
+/// プレフィックスを除いたパスに対して記述します（`/api/v1`の下の`^/users$`は`/api/v1/users`にマッチ）。
+///
+/// グループのミドルウェアは、アプリ全体のミドルウェア（前処理・AroundMiddleware）の内側で、
+/// グループ内のルートにマッチしたリクエストにだけ適用されます。
+pub struct RouterGroup {
+    prefix: String,
+    handlers: Vec<Box<dyn Handler>>,
+    middlewares: Vec<Box<dyn Middleware>>,
+    around: Vec<Box<dyn AroundMiddleware>>,
+    security_profile: Option<SecurityProfile>,
+}
+
+impl RouterGroup {
+    /// プレフィックスを指定して作成（末尾の`/`は無視、`/`や空文字はプレフィックスなし）
+    pub fn new(prefix: impl Into<String>) -> Self {
+        let prefix = prefix.into();
+        let trimmed = prefix.trim_end_matches('/');
+        let prefix = if trimmed.is_empty() || trimmed.starts_with('/') {
+            trimmed.to_string()
+        } else {
+            format!("/{}", trimmed)
+        };
+        Self {
+            prefix,
+            handlers: Vec::new(),
+            middlewares: Vec::new(),
+            around: Vec::new(),
+            security_profile: None,
+        }
+    }
+
+    /// プレフィックス
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// ハンドラを追加
+    pub fn handler<H>(mut self, handler: H) -> Self
+    where
+        H: Handler + 'static,
+    {
+        self.handlers.push(Box::new(handler));
+        self
+    }
+
+    /// グループ内のルートにだけ適用するミドルウェアを追加
+    pub fn middleware<M>(mut self, middleware: M) -> Self
+    where
+        M: Middleware + 'static,
+    {
+        self.middlewares.push(Box::new(middleware));
+        self
+    }
+
+    /// グループ内のルートにだけ適用するAroundMiddlewareを追加（先に追加したものが外側）
+    pub fn around<M>(mut self, middleware: M) -> Self
+    where
+        M: AroundMiddleware + 'static,
+    {
+        self.around.push(Box::new(middleware));
+        self
+    }
+
+    /// グループ内のルートのセキュリティヘッダーのプロファイルを設定（アプリ全体の設定より優先）
+    ///
+    /// ネストしたグループでは内側のグループの設定が優先されます。
+    pub fn security_profile(mut self, profile: SecurityProfile) -> Self {
+        self.security_profile = Some(profile);
+        self
+    }
+
+    /// ネストしたグループを追加（プレフィックスは連結、ミドルウェアは外側のグループが先）
+    pub fn scope<F>(self, prefix: impl Into<String>, configure: F) -> Self
+    where
+        F: FnOnce(RouterGroup) -> RouterGroup,
+    {
+        self.group(configure(RouterGroup::new(prefix)))
+    }
+
+    /// 作成済みのグループをネストして追加
+    pub fn group(mut self, group: RouterGroup) -> Self {
+        self.handlers.extend(group.into_handlers());
+        self
+    }
+
+    /// プレフィックスとミドルウェアを適用したハンドラーに変換
+    pub(crate) fn into_handlers(self) -> Vec<Box<dyn Handler>> {
+        let middlewares: Arc<[Box<dyn Middleware>]> = self.middlewares.into();
+        let around: Arc<[Box<dyn AroundMiddleware>]> = self.around.into();
+        let prefix = self.prefix;
+        let security_profile = self.security_profile;
+        self.handlers
+            .into_iter()
+            .map(|inner| {
+                Box::new(ScopedHandler::new(
+                    inner,
+                    prefix.clone(),
+                    middlewares.clone(),
+                    around.clone(),
+                    security_profile,
+                )) as Box<dyn Handler>
+            })
+            .collect()
+    }
+}
+
+/// グループに登録されたハンドラー（プレフィックスを除いたパスで内側のハンドラーを照合）
+struct ScopedHandler {
+    inner: Box<dyn Handler>,
+    prefix: String,
+    path_pattern: String,
+    middlewares: Arc<[Box<dyn Middleware>]>,
+    around: Arc<[Box<dyn AroundMiddleware>]>,
+    options: RouteOptions,
+}
+
+impl ScopedHandler {
+    fn new(
+        inner: Box<dyn Handler>,
+        prefix: String,
+        middlewares: Arc<[Box<dyn Middleware>]>,
+        around: Arc<[Box<dyn AroundMiddleware>]>,
+        security_profile: Option<SecurityProfile>,
+    ) -> Self {
+        // ルートテーブル・パスパラメータ・ログ用に、完全なパスに対するパターンを作る
+        let escaped = regex::escape(&prefix);
+        let path_pattern = match inner.path_pattern().strip_prefix('^') {
+            Some(rest) => format!("^{}{}", escaped, rest),
+            None => format!("{}{}", escaped, inner.path_pattern()),
+        };
+        // ルート固有のセキュリティヘッダーのプロファイルをグループの設定より優先する
+        let mut options = inner.options().clone();
+        options.security_profile = options.security_profile.or(security_profile);
+        Self { inner, prefix, path_pattern, middlewares, around, options }
+    }
+}
+
+#[async_trait]
+impl Handler for ScopedHandler {
+    fn matches(&self, path: &str, method: &Method) -> bool {
+        match path.strip_prefix(self.prefix.as_str()) {
+            Some("") => self.inner.matches("/", method),
+            Some(rest) if rest.starts_with('/') => self.inner.matches(rest, method),
+            _ => false,
+        }
+    }
+
+    fn path_pattern(&self) -> &str {
+        &self.path_pattern
+    }
+
+    fn options(&self) -> &RouteOptions {
+        &self.options
+    }
+
+    fn matches_by_pattern(&self) -> bool {
+        // プレフィックスのみのパス（`/api`）は`/api/`として照合するが、
+        // コンパイル済みルーターは末尾の空のセグメントを索引に使わないため内側の設定に従える
+        self.inner.matches_by_pattern()
+    }
+
+    fn methods(&self) -> Vec<Method> {
+        self.inner.methods()
+    }
+
+    fn check_pre_body(&self, req: &Request) -> Result<(), Error> {
+        self.inner.check_pre_body(req)
+    }
+
+    async fn handle(&self, req: Request) -> Result<Response, Error> {
+        let mut req = req;
+        for middleware in self.middlewares.iter() {
+            req = middleware.pre_process(req).await?;
+        }
+
+        let result = Next::new(self.inner.as_ref(), &self.around).run(req).await;
+        if self.middlewares.is_empty() {
+            return result;
+        }
+
+        // アプリ全体の後処理と同様に、ハンドラーのエラーもレスポンスに変換してから後処理する
+        let mut res = result.unwrap_or_else(|e| {
+            error!("Handler error: {}", e);
+            Response::from_error(&e)
+        });
+        for middleware in self.middlewares.iter() {
+            res = middleware.post_process(res).await?;
+        }
+        Ok(res)
+    }
+}
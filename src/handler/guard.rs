@@ -0,0 +1,419 @@
+//! ハンドラーを包むガード（フィーチャーフラグ・署名URL・オリジンによる公開制御、ボディ受信前の検査）
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use log::debug;
+
+use crate::common::circuit_breaker::CircuitBreaker;
+use crate::common::dependency::DependencyRegistry;
+use crate::common::error_response::error_response;
+use crate::common::signed_url::{UrlSigner, SIGNED_CLAIMS_CONTEXT_KEY};
+use crate::common::{Handler, Method, Request, Response, RetryAfter, RouteOptions};
+use crate::error::Error;
+
+/// フラグが無効な場合の応答
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum FlagFallback {
+    NotFound,
+    Redirect(String),
+}
+
+/// フィーチャーフラグが有効な場合のみハンドラーを実行するガード
+///
+/// フラグは`FeatureFlagMiddleware`がRequestContextに格納したものを参照します。
+/// 無効な場合は404（既定）または指定URLへの302リダイレクトを返します。
+pub struct FlagGuard<H: Handler> {
+    inner: H,
+    flag: String,
+    fallback: FlagFallback,
+}
+
+impl<H: Handler> FlagGuard<H> {
+    /// 新しいFlagGuardを作成
+    pub fn new(inner: H, flag: impl Into<String>) -> Self {
+        Self {
+            inner,
+            flag: flag.into(),
+            fallback: FlagFallback::NotFound,
+        }
+    }
+
+    /// フラグが無効な場合に指定URLへリダイレクト（302）
+    pub fn or_redirect(mut self, location: impl Into<String>) -> Self {
+        self.fallback = FlagFallback::Redirect(location.into());
+        self
+    }
+}
+
+#[async_trait]
+impl<H: Handler> Handler for FlagGuard<H> {
+    fn matches(&self, path: &str, method: &Method) -> bool {
+        self.inner.matches(path, method)
+    }
+
+    fn path_pattern(&self) -> &str {
+        self.inner.path_pattern()
+    }
+
+    fn options(&self) -> &RouteOptions {
+        self.inner.options()
+    }
+
+    fn matches_by_pattern(&self) -> bool {
+        self.inner.matches_by_pattern()
+    }
+
+    fn methods(&self) -> Vec<Method> {
+        self.inner.methods()
+    }
+
+    fn check_pre_body(&self, req: &Request) -> Result<(), Error> {
+        self.inner.check_pre_body(req)
+    }
+
+    async fn handle(&self, req: Request) -> Result<Response, Error> {
+        if req.flag_enabled(&self.flag) {
+            return self.inner.handle(req).await;
+        }
+        debug!("Feature flag '{}' is off for {} {}", self.flag, req.method, req.path);
+        Ok(match &self.fallback {
+            FlagFallback::NotFound => Response::error(404),
+            FlagFallback::Redirect(location) => Response::new(302).with_header("Location", location.clone()),
+        })
+    }
+}
+
+/// 有効な署名URLでアクセスされた場合のみハンドラーを実行するガード
+///
+/// 署名が無い・不正・期限切れの場合は403を返します。
+/// 検証済みのクレームは`Request::signed_url_claims`で参照できます。
+pub struct SignedUrlGuard<H: Handler> {
+    inner: H,
+    signer: UrlSigner,
+}
+
+impl<H: Handler> SignedUrlGuard<H> {
+    /// 新しいSignedUrlGuardを作成
+    pub fn new(inner: H, signer: UrlSigner) -> Self {
+        Self { inner, signer }
+    }
+}
+
+#[async_trait]
+impl<H: Handler> Handler for SignedUrlGuard<H> {
+    fn matches(&self, path: &str, method: &Method) -> bool {
+        self.inner.matches(path, method)
+    }
+
+    fn path_pattern(&self) -> &str {
+        self.inner.path_pattern()
+    }
+
+    fn options(&self) -> &RouteOptions {
+        self.inner.options()
+    }
+
+    fn matches_by_pattern(&self) -> bool {
+        self.inner.matches_by_pattern()
+    }
+
+    fn methods(&self) -> Vec<Method> {
+        self.inner.methods()
+    }
+
+    fn check_pre_body(&self, req: &Request) -> Result<(), Error> {
+        self.inner.check_pre_body(req)
+    }
+
+    async fn handle(&self, mut req: Request) -> Result<Response, Error> {
+        match self.signer.verify(&req) {
+            Ok(claims) => {
+                req.context_mut().set(SIGNED_CLAIMS_CONTEXT_KEY, claims);
+                self.inner.handle(req).await
+            }
+            Err(e) => {
+                debug!("Rejected signed URL for {} {}: {}", req.method, req.path, e);
+                Ok(error_response(403, Some(&e.to_string())))
+            }
+        }
+    }
+}
+
+/// 依存が停止中と判明している間は、ハンドラーを実行せずに503を返すガード
+///
+/// 状態は`DependencyRegistry`（ヘルスチェックや実際の呼び出し結果で更新）を参照します。
+/// 未確認の依存は停止中とみなしません。
+pub struct DependencyGuard<H: Handler> {
+    inner: H,
+    registry: DependencyRegistry,
+    dependency: String,
+}
+
+impl<H: Handler> DependencyGuard<H> {
+    /// 新しいDependencyGuardを作成
+    pub fn new(inner: H, registry: DependencyRegistry, dependency: impl Into<String>) -> Self {
+        Self { inner, registry, dependency: dependency.into() }
+    }
+}
+
+#[async_trait]
+impl<H: Handler> Handler for DependencyGuard<H> {
+    fn matches(&self, path: &str, method: &Method) -> bool {
+        self.inner.matches(path, method)
+    }
+
+    fn path_pattern(&self) -> &str {
+        self.inner.path_pattern()
+    }
+
+    fn options(&self) -> &RouteOptions {
+        self.inner.options()
+    }
+
+    fn matches_by_pattern(&self) -> bool {
+        self.inner.matches_by_pattern()
+    }
+
+    fn methods(&self) -> Vec<Method> {
+        self.inner.methods()
+    }
+
+    fn check_pre_body(&self, req: &Request) -> Result<(), Error> {
+        self.inner.check_pre_body(req)
+    }
+
+    async fn handle(&self, req: Request) -> Result<Response, Error> {
+        if self.registry.is_down(&self.dependency) {
+            debug!("Dependency '{}' is down, short-circuiting {} {}", self.dependency, req.method, req.path);
+            let detail = format!("dependency '{}' is down", self.dependency);
+            return Ok(error_response(503, Some(&detail)));
+        }
+        self.inner.handle(req).await
+    }
+}
+
+/// サーキットブレーカーを適用するガード
+///
+/// 回路が開いている間はハンドラーを実行せずに503（`Retry-After`付き）を返します。
+/// ハンドラーのエラーと5xxレスポンスを失敗として記録します。
+pub struct CircuitBreakerGuard<H: Handler> {
+    inner: H,
+    breaker: CircuitBreaker,
+}
+
+impl<H: Handler> CircuitBreakerGuard<H> {
+    /// 新しいCircuitBreakerGuardを作成
+    pub fn new(inner: H, breaker: CircuitBreaker) -> Self {
+        Self { inner, breaker }
+    }
+}
+
+#[async_trait]
+impl<H: Handler> Handler for CircuitBreakerGuard<H> {
+    fn matches(&self, path: &str, method: &Method) -> bool {
+        self.inner.matches(path, method)
+    }
+
+    fn path_pattern(&self) -> &str {
+        self.inner.path_pattern()
+    }
+
+    fn options(&self) -> &RouteOptions {
+        self.inner.options()
+    }
+
+    fn matches_by_pattern(&self) -> bool {
+        self.inner.matches_by_pattern()
+    }
+
+    fn methods(&self) -> Vec<Method> {
+        self.inner.methods()
+    }
+
+    fn check_pre_body(&self, req: &Request) -> Result<(), Error> {
+        self.inner.check_pre_body(req)
+    }
+
+    async fn handle(&self, req: Request) -> Result<Response, Error> {
+        if let Err(retry_after) = self.breaker.try_acquire() {
+            debug!(
+                "Circuit for '{}' is open, rejecting {} {}",
+                self.breaker.dependency(),
+                req.method,
+                req.path
+            );
+            // 切り上げて秒単位にする（最低1秒）
+            let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            let detail = format!("circuit for '{}' is open", self.breaker.dependency());
+            return Ok(error_response(503, Some(&detail))
+                .with_retry_after(RetryAfter::Delay(Duration::from_secs(seconds.max(1)))));
+        }
+
+        let result = self.inner.handle(req).await;
+        match &result {
+            Ok(res) if res.status < 500 => self.breaker.record_success(),
+            _ => self.breaker.record_failure(),
+        }
+        result
+    }
+}
+
+/// `Origin`ヘッダーが許可リストに含まれる場合のみハンドラーを実行するガード
+///
+/// WebSocketのハンドシェイクやSSEのようにCORSでは保護されない長時間の接続向けです。
+/// 接続の開始時に検査し、許可されていないオリジンには403を返します。リクエスト自身の
+/// オリジン（`Request::base_url`）は常に許可します。`Origin`を送らないクライアント（ブラウザ以外や
+/// 同一オリジンのGET）は既定で許可し、`require_origin`で拒否できます。
+pub struct OriginGuard<H: Handler> {
+    inner: H,
+    allowed: Vec<String>,
+    require_origin: bool,
+}
+
+impl<H: Handler> OriginGuard<H> {
+    /// 許可するオリジン（`https://app.example.com`、サブドメインは`https://*.example.com`）を指定して作成
+    pub fn new<I, S>(inner: H, origins: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            inner,
+            allowed: origins.into_iter().map(|o| normalize_origin(&o.into())).collect(),
+            require_origin: false,
+        }
+    }
+
+    /// `Origin`ヘッダーが無いリクエストも拒否する
+    pub fn require_origin(mut self) -> Self {
+        self.require_origin = true;
+        self
+    }
+
+    fn is_allowed(&self, req: &Request) -> bool {
+        let origin = match req.headers.get("origin") {
+            Some(origin) => normalize_origin(origin),
+            None => return !self.require_origin,
+        };
+        if req.base_url().is_some_and(|base| normalize_origin(&base) == origin) {
+            return true;
+        }
+        self.allowed.iter().any(|allowed| origin_matches(allowed, &origin))
+    }
+}
+
+fn normalize_origin(origin: &str) -> String {
+    origin.trim().trim_end_matches('/').to_ascii_lowercase()
+}
+
+/// 許可リストの1件とオリジンを比較（`scheme://*.`で始まる場合はサブドメインのみ一致）
+fn origin_matches(allowed: &str, origin: &str) -> bool {
+    match allowed.split_once("://*.") {
+        Some((scheme, domain)) => origin
+            .strip_prefix(scheme)
+            .and_then(|rest| rest.strip_prefix("://"))
+            .and_then(|host| host.strip_suffix(domain))
+            .is_some_and(|sub| sub.len() > 1 && sub.ends_with('.') && !sub[..sub.len() - 1].contains(['/', ':'])),
+        None => allowed == origin,
+    }
+}
+
+#[async_trait]
+impl<H: Handler> Handler for OriginGuard<H> {
+    fn matches(&self, path: &str, method: &Method) -> bool {
+        self.inner.matches(path, method)
+    }
+
+    fn path_pattern(&self) -> &str {
+        self.inner.path_pattern()
+    }
+
+    fn options(&self) -> &RouteOptions {
+        self.inner.options()
+    }
+
+    fn matches_by_pattern(&self) -> bool {
+        self.inner.matches_by_pattern()
+    }
+
+    fn methods(&self) -> Vec<Method> {
+        self.inner.methods()
+    }
+
+    fn check_pre_body(&self, req: &Request) -> Result<(), Error> {
+        self.inner.check_pre_body(req)
+    }
+
+    async fn handle(&self, req: Request) -> Result<Response, Error> {
+        if self.is_allowed(&req) {
+            return self.inner.handle(req).await;
+        }
+        debug!(
+            "Origin {:?} is not allowed for {} {}",
+            req.headers.get("origin"),
+            req.method,
+            req.path
+        );
+        Ok(error_response(403, Some("origin not allowed")))
+    }
+}
+
+/// ボディの検査前に呼ぶ関数
+type PreBodyCheck = Arc<dyn Fn(&Request) -> Result<(), Error> + Send + Sync>;
+
+/// リクエストボディの受信前に検査するガード（認証・宣言されたサイズによる拒否等）
+///
+/// Cloud Runでは`Expect: 100-continue`のリクエストをボディの送信前に検査し、Errの場合は
+/// そのエラーのレスポンスを返します。それ以外のリクエスト・ランタイムではハンドラーの実行前に検査します。
+pub struct PreBodyGuard<H: Handler> {
+    inner: H,
+    check: PreBodyCheck,
+}
+
+impl<H: Handler> PreBodyGuard<H> {
+    /// 新しいPreBodyGuardを作成
+    pub fn new<F>(inner: H, check: F) -> Self
+    where
+        F: Fn(&Request) -> Result<(), Error> + Send + Sync + 'static,
+    {
+        Self { inner, check: Arc::new(check) }
+    }
+}
+
+#[async_trait]
+impl<H: Handler> Handler for PreBodyGuard<H> {
+    fn matches(&self, path: &str, method: &Method) -> bool {
+        self.inner.matches(path, method)
+    }
+
+    fn path_pattern(&self) -> &str {
+        self.inner.path_pattern()
+    }
+
+    fn options(&self) -> &RouteOptions {
+        self.inner.options()
+    }
+
+    fn matches_by_pattern(&self) -> bool {
+        self.inner.matches_by_pattern()
+    }
+
+    fn methods(&self) -> Vec<Method> {
+        self.inner.methods()
+    }
+
+    fn check_pre_body(&self, req: &Request) -> Result<(), Error> {
+        self.inner.check_pre_body(req)?;
+        (self.check)(req)
+    }
+
+    async fn handle(&self, req: Request) -> Result<Response, Error> {
+        if let Err(e) = (self.check)(&req) {
+            debug!("Pre-body check rejected {} {}: {}", req.method, req.path, e);
+            return Err(e);
+        }
+        self.inner.handle(req).await
+    }
+}
@@ -0,0 +1,248 @@
+//! 依存の状態を集計するヘルスチェックハンドラーと、生存確認・準備完了確認のエンドポイント
+
+use std::env;
+use std::sync::Arc;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use log::warn;
+use serde_json::json;
+
+use crate::common::dependency::{Dependency, DependencyRegistry};
+use crate::common::{Handler, Method, Request, Response};
+use crate::error::Error;
+
+/// `DependencyRegistry`の全依存を確認し、結果をJSONで返すハンドラー（GET）
+///
+/// 重要な依存が停止中の場合は503、それ以外は200を返します。
+pub struct HealthHandler {
+    path: String,
+    registry: DependencyRegistry,
+}
+
+impl HealthHandler {
+    /// パスと参照するレジストリを指定して作成
+    pub fn new(path: impl Into<String>, registry: DependencyRegistry) -> Self {
+        Self { path: path.into(), registry }
+    }
+}
+
+#[async_trait]
+impl Handler for HealthHandler {
+    fn matches(&self, path: &str, method: &Method) -> bool {
+        path == self.path && *method == Method::GET
+    }
+
+    fn path_pattern(&self) -> &str {
+        &self.path
+    }
+
+    fn methods(&self) -> Vec<Method> {
+        vec![Method::GET]
+    }
+
+    async fn handle(&self, _req: Request) -> Result<Response, Error> {
+        let report = self.registry.check_all().await;
+        if report.http_status() != 200 {
+            warn!("Health check failed: {:?}", report.status);
+        }
+        let body = serde_json::to_vec(&report)
+            .map_err(|e| Error::ResponseSerializationError(e.to_string()))?;
+        Ok(Response::new(report.http_status())
+            .with_header("Content-Type", "application/json")
+            .with_header("Cache-Control", "no-store")
+            .with_body(body))
+    }
+}
+
+/// ヘルスチェックで返すアプリケーションのバージョンを取得する
+/// 優先順位: 環境変数 `RUNBRIDGE_APP_VERSION` -> `K_REVISION`（Cloud Run） -> `AWS_LAMBDA_FUNCTION_VERSION` -> デフォルト `unknown`
+pub fn get_app_version() -> String {
+    ["RUNBRIDGE_APP_VERSION", "K_REVISION", "AWS_LAMBDA_FUNCTION_VERSION"]
+        .iter()
+        .find_map(|name| env::var(name).ok().filter(|v| !v.trim().is_empty()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// 生存確認・準備完了確認のエンドポイントの設定（`RunBridgeBuilder::health_check`で登録）
+///
+/// 生存確認（既定 `path`）はプロセスが応答できれば常に200を返し、準備完了確認（既定 `{path}/ready`）は
+/// 登録したprobeを実行して重要な依存が停止中の場合に503を返します。どちらもバージョンと起動からの
+/// 経過秒数を含むJSONを返します。
+///
+/// ```
+/// use runbridge::common::{Criticality, Dependency};
+/// use runbridge::handler::HealthCheck;
+/// use runbridge::RunBridge;
+///
+/// let app = RunBridge::builder()
+///     .health_check(HealthCheck::new("/healthz").probe(Dependency::new("db", Criticality::Critical, || async {
+///         Ok(())
+///     })))
+///     .build();
+/// assert!(app.find_handler("/healthz/ready", &runbridge::common::Method::GET).is_some());
+/// ```
+#[derive(Debug, Clone)]
+pub struct HealthCheck {
+    path: String,
+    readiness_path: String,
+    version: Option<String>,
+    registry: DependencyRegistry,
+}
+
+impl HealthCheck {
+    /// 生存確認のパスを指定して作成（準備完了確認は`{path}/ready`）
+    pub fn new(path: impl Into<String>) -> Self {
+        let path = path.into();
+        let readiness_path = format!("{}/ready", path.trim_end_matches('/'));
+        Self { path, readiness_path, version: None, registry: DependencyRegistry::new() }
+    }
+
+    /// 準備完了確認のパスを変更（例: `/readyz`）
+    pub fn readiness_path(mut self, path: impl Into<String>) -> Self {
+        self.readiness_path = path.into();
+        self
+    }
+
+    /// 返すバージョンを指定（未指定の場合は`get_app_version`）
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    /// 準備完了確認で実行するprobeを追加（例: DBへのping）
+    pub fn probe(self, dependency: Dependency) -> Self {
+        self.registry.register(dependency);
+        self
+    }
+
+    /// 準備完了確認で参照するレジストリを指定（`HandlerExt::depends_on`と状態を共有する場合）
+    pub fn dependencies(mut self, registry: DependencyRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// 生存確認と準備完了確認のハンドラーに変換
+    pub fn into_handlers(self) -> (HealthEndpoint, HealthEndpoint) {
+        let state = Arc::new(HealthState {
+            version: self.version.unwrap_or_else(get_app_version),
+            started: Instant::now(),
+            registry: self.registry,
+        });
+        (
+            HealthEndpoint { path: self.path, readiness: false, state: state.clone() },
+            HealthEndpoint { path: self.readiness_path, readiness: true, state },
+        )
+    }
+}
+
+#[derive(Debug)]
+struct HealthState {
+    version: String,
+    started: Instant,
+    registry: DependencyRegistry,
+}
+
+/// 生存確認または準備完了確認のエンドポイント（`HealthCheck::into_handlers`で作成、GET）
+#[derive(Debug)]
+pub struct HealthEndpoint {
+    path: String,
+    readiness: bool,
+    state: Arc<HealthState>,
+}
+
+#[async_trait]
+impl Handler for HealthEndpoint {
+    fn matches(&self, path: &str, method: &Method) -> bool {
+        path == self.path && *method == Method::GET
+    }
+
+    fn path_pattern(&self) -> &str {
+        &self.path
+    }
+
+    fn methods(&self) -> Vec<Method> {
+        vec![Method::GET]
+    }
+
+    async fn handle(&self, _req: Request) -> Result<Response, Error> {
+        let mut body = json!({
+            "status": "up",
+            "version": self.state.version,
+            "runbridge_version": env!("CARGO_PKG_VERSION"),
+            "uptime_secs": self.state.started.elapsed().as_secs(),
+        });
+        let mut status = 200;
+        if self.readiness {
+            let report = self.state.registry.check_all().await;
+            status = report.http_status();
+            if status != 200 {
+                warn!("Readiness check failed: {:?}", report.status);
+            }
+            body["status"] = json!(report.status);
+            body["dependencies"] = json!(report.dependencies);
+        }
+        let body = serde_json::to_vec(&body)
+            .map_err(|e| Error::ResponseSerializationError(e.to_string()))?;
+        Ok(Response::new(status)
+            .with_header("Content-Type", "application/json")
+            .with_header("Cache-Control", "no-store")
+            .with_body(body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::dependency::{Criticality, Dependency};
+
+    #[tokio::test]
+    async fn test_health_handler_status() {
+        let registry = DependencyRegistry::new();
+        registry.register(Dependency::new("db", Criticality::Critical, || async {
+            Err("down".to_string())
+        }));
+        let handler = HealthHandler::new("/healthz", registry);
+
+        assert!(handler.matches("/healthz", &Method::GET));
+        assert!(!handler.matches("/healthz", &Method::POST));
+
+        let res = handler.handle(Request::new(Method::GET, "/healthz".to_string())).await.unwrap();
+        assert_eq!(res.status, 503);
+        let json: serde_json::Value = serde_json::from_slice(res.body.as_deref().unwrap()).unwrap();
+        assert_eq!(json["status"], "down");
+        assert_eq!(json["dependencies"][0]["name"], "db");
+    }
+
+    #[tokio::test]
+    async fn test_liveness_and_readiness() {
+        let healthy = Arc::new(std::sync::atomic::AtomicBool::new(true));
+        let probe_state = healthy.clone();
+        let (liveness, readiness) = HealthCheck::new("/healthz")
+            .version("1.2.3")
+            .probe(Dependency::new("db", Criticality::Critical, move || {
+                let up = probe_state.load(std::sync::atomic::Ordering::SeqCst);
+                async move { if up { Ok(()) } else { Err("timeout".to_string()) } }
+            }))
+            .into_handlers();
+        assert_eq!(readiness.path_pattern(), "/healthz/ready");
+
+        let json = |res: &Response| serde_json::from_slice::<serde_json::Value>(res.body.as_deref().unwrap()).unwrap();
+        let req = || Request::new(Method::GET, "/".to_string());
+
+        let res = liveness.handle(req()).await.unwrap();
+        assert_eq!(res.status, 200);
+        assert_eq!(json(&res)["version"], "1.2.3");
+        assert!(json(&res)["uptime_secs"].is_u64());
+        assert!(json(&res).get("dependencies").is_none());
+
+        let res = readiness.handle(req()).await.unwrap();
+        assert_eq!(res.status, 200);
+        assert_eq!(json(&res)["dependencies"][0]["status"], "up");
+
+        // 依存が停止しても生存確認は成功し、準備完了確認のみ503になる
+        healthy.store(false, std::sync::atomic::Ordering::SeqCst);
+        assert_eq!(readiness.handle(req()).await.unwrap().status, 503);
+        assert_eq!(liveness.handle(req()).await.unwrap().status, 200);
+    }
+}
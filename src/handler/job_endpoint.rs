@@ -0,0 +1,329 @@
+//! 非同期ジョブの登録（`POST /jobs`）と状態確認（`GET /jobs/{id}`）のエンドポイント
+//!
+//! 登録のエンドポイントはリクエストボディ（JSON、無い場合はnull）を入力としてジョブを保存し、
+//! `202 Accepted`・`Location`ヘッダー・状態確認用のURLを返します。状態確認のエンドポイントは
+//! 進捗・結果・失敗の理由をJSONで返します。
+//!
+//! 状態確認のエンドポイントは登録した利用者を確認せず、推測できないジョブID（128ビットの乱数）を
+//! 知っていることを参照の権限とします。IDを含むURLはベアラートークンと同様に扱い、ログや
+//! 第三者と共有しないでください。
+//!
+//! `runner`を指定すると登録と同時にバックグラウンドのタスクで処理します（Cloud Run・CGIのfetchモードなど、
+//! レスポンスを返した後もプロセスが動き続ける環境向け）。Lambdaではレスポンスを返すと実行環境が
+//! 停止するため、`runner`を指定せずに別のワーカーが`JobQueue`で保存先のジョブを処理してください。
+//!
+//! ```
+//! use runbridge::common::MemoryJobStore;
+//! use runbridge::handler::JobEndpoints;
+//! use runbridge::RunBridge;
+//!
+//! let app = RunBridge::builder()
+//!     .jobs(JobEndpoints::new("/jobs", MemoryJobStore::default()).runner(|job| async move {
+//!         job.progress(50, Some("halfway")).await?;
+//!         Ok(serde_json::json!({ "echo": job.input }))
+//!     }))
+//!     .build();
+//! assert!(app.find_handler("/jobs/abc", &runbridge::common::Method::GET).is_some());
+//! ```
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use log::{error, info};
+use serde_json::{json, Value};
+
+use crate::common::error_response::{is_error_detail_exposed, status_message};
+use crate::common::jobs::{Job, JobQueue, JobStore};
+use crate::common::{Handler, Method, Request, Response, RetryAfter};
+use crate::error::Error;
+
+type BoxedJobFuture = Pin<Box<dyn Future<Output = Result<Value, Error>> + Send>>;
+type JobRunner = Arc<dyn Fn(JobContext) -> BoxedJobFuture + Send + Sync>;
+
+/// `runner`に渡す実行中のジョブ
+#[derive(Debug, Clone)]
+pub struct JobContext {
+    /// ジョブID
+    pub id: String,
+    /// 登録時に受け取った入力
+    pub input: Value,
+    queue: JobQueue,
+}
+
+impl JobContext {
+    /// 進捗（0〜100）と説明を更新する
+    pub async fn progress(&self, percent: u8, message: Option<&str>) -> Result<(), Error> {
+        self.queue.progress(&self.id, percent, message).await.map(|_| ())
+    }
+
+    /// ジョブの保存先（他のジョブの参照などに使用）
+    pub fn queue(&self) -> &JobQueue {
+        &self.queue
+    }
+}
+
+/// ジョブのエンドポイントの設定（`RunBridgeBuilder::jobs`で登録）
+#[derive(Clone)]
+pub struct JobEndpoints {
+    path: String,
+    queue: JobQueue,
+    runner: Option<JobRunner>,
+    retry_after: Option<Duration>,
+}
+
+impl std::fmt::Debug for JobEndpoints {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JobEndpoints")
+            .field("path", &self.path)
+            .field("runner", &self.runner.is_some())
+            .field("retry_after", &self.retry_after)
+            .finish()
+    }
+}
+
+impl JobEndpoints {
+    /// 登録のパス（状態確認は`{path}/{id}`）と保存先を指定して作成
+    pub fn new<S: JobStore + 'static>(path: impl Into<String>, store: S) -> Self {
+        Self::with_queue(path, JobQueue::new(store))
+    }
+
+    /// ワーカーと共有する`JobQueue`を指定して作成
+    pub fn with_queue(path: impl Into<String>, queue: JobQueue) -> Self {
+        let path = path.into();
+        let path = match path.trim_end_matches('/') {
+            "" => "/".to_string(),
+            trimmed => trimmed.to_string(),
+        };
+        Self { path, queue, runner: None, retry_after: None }
+    }
+
+    /// 登録したジョブをバックグラウンドのタスクで処理する関数（Okの値が結果、Errで失敗）
+    pub fn runner<F, Fut>(mut self, runner: F) -> Self
+    where
+        F: Fn(JobContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Value, Error>> + Send + 'static,
+    {
+        self.runner = Some(Arc::new(move |job| Box::pin(runner(job))));
+        self
+    }
+
+    /// 未完了のジョブの状態確認・登録のレスポンスに付与する`Retry-After`（ポーリング間隔の目安）
+    pub fn retry_after(mut self, interval: Duration) -> Self {
+        self.retry_after = Some(interval);
+        self
+    }
+
+    /// 共有している`JobQueue`
+    pub fn queue(&self) -> &JobQueue {
+        &self.queue
+    }
+
+    /// 登録と状態確認のハンドラーに変換
+    pub fn into_handlers(self) -> (JobSubmitHandler, JobStatusHandler) {
+        let status_pattern = format!("^{}/[^/]+$", regex::escape(self.path.trim_end_matches('/')));
+        let config = Arc::new(self);
+        (
+            JobSubmitHandler { config: config.clone() },
+            JobStatusHandler { status_pattern, config },
+        )
+    }
+
+    fn status_url(&self, id: &str) -> String {
+        format!("{}/{}", self.path.trim_end_matches('/'), id)
+    }
+
+    fn status_response(&self, status: u16, job: &Job) -> Result<Response, Error> {
+        let mut body = job.status_json();
+        body["status_url"] = json!(self.status_url(&job.id));
+        let mut res = Response::new(status)
+            .with_header("Cache-Control", "no-store")
+            .json(&body)?;
+        if let Some(interval) = self.retry_after.filter(|_| !job.is_finished()) {
+            res = res.with_retry_after(RetryAfter::Delay(interval));
+        }
+        Ok(res)
+    }
+}
+
+/// ジョブを登録するエンドポイント（`JobEndpoints::into_handlers`で作成、POST）
+pub struct JobSubmitHandler {
+    config: Arc<JobEndpoints>,
+}
+
+#[async_trait]
+impl Handler for JobSubmitHandler {
+    fn matches(&self, path: &str, method: &Method) -> bool {
+        path == self.config.path && *method == Method::POST
+    }
+
+    fn path_pattern(&self) -> &str {
+        &self.config.path
+    }
+
+    fn methods(&self) -> Vec<Method> {
+        vec![Method::POST]
+    }
+
+    async fn handle(&self, req: Request) -> Result<Response, Error> {
+        let input = match req.body.as_deref() {
+            None | Some([]) => Value::Null,
+            Some(_) => req.json::<Value>()?,
+        };
+        let job = self.config.queue.submit(input).await?;
+        info!("Accepted job {}", job.id);
+
+        if let Some(runner) = self.config.runner.clone() {
+            let queue = self.config.queue.clone();
+            let context = JobContext { id: job.id.clone(), input: job.input.clone(), queue: queue.clone() };
+            tokio::spawn(async move {
+                let id = context.id.clone();
+                let outcome = match queue.start(&id).await {
+                    Ok(_) => runner(context).await,
+                    Err(e) => Err(e),
+                };
+                let stored = match outcome {
+                    Ok(result) => queue.complete(&id, result).await,
+                    Err(e) => {
+                        error!("Job {} failed: {}", id, e);
+                        // エラーの詳細は内部の情報を含む可能性があるため、エラーレスポンスと同じ方針で返す
+                        let reason = if is_error_detail_exposed() {
+                            e.to_string()
+                        } else {
+                            status_message(e.status_code()).to_string()
+                        };
+                        queue.fail(&id, reason).await
+                    }
+                };
+                if let Err(e) = stored {
+                    error!("Failed to store the outcome of job {}: {}", id, e);
+                }
+            });
+        }
+
+        let location = self.config.status_url(&job.id);
+        Ok(self.config.status_response(202, &job)?.with_header("Location", location))
+    }
+}
+
+/// ジョブの状態を返すエンドポイント（`JobEndpoints::into_handlers`で作成、GET）
+///
+/// 存在しないジョブ・不正なIDの場合は404を返します。
+pub struct JobStatusHandler {
+    status_pattern: String,
+    config: Arc<JobEndpoints>,
+}
+
+#[async_trait]
+impl Handler for JobStatusHandler {
+    fn matches(&self, path: &str, method: &Method) -> bool {
+        *method == Method::GET && self.job_id(path).is_some()
+    }
+
+    fn path_pattern(&self) -> &str {
+        &self.status_pattern
+    }
+
+    fn methods(&self) -> Vec<Method> {
+        vec![Method::GET]
+    }
+
+    async fn handle(&self, req: Request) -> Result<Response, Error> {
+        let id = self.job_id(&req.path).unwrap_or_default();
+        match self.config.queue.get(id).await? {
+            Some(job) => self.config.status_response(200, &job),
+            None => Err(Error::RouteNotFound(format!("Job not found: {}", id))),
+        }
+    }
+}
+
+impl JobStatusHandler {
+    fn job_id<'a>(&self, path: &'a str) -> Option<&'a str> {
+        let id = path
+            .strip_prefix(self.config.path.trim_end_matches('/'))?
+            .strip_prefix('/')?;
+        (!id.is_empty() && !id.contains('/')).then_some(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::jobs::{JobStatus, MemoryJobStore};
+    use crate::testing::dispatch;
+    use crate::RunBridge;
+
+    fn post(path: &str, body: &[u8]) -> Request {
+        let mut req = Request::new(Method::POST, path.to_string());
+        req.headers.insert("content-type".to_string(), "application/json".to_string());
+        req.body = Some(body.to_vec());
+        req
+    }
+
+    #[tokio::test]
+    async fn test_submit_and_poll() {
+        let endpoints = JobEndpoints::new("/jobs/", MemoryJobStore::default()).retry_after(Duration::from_secs(2));
+        let queue = endpoints.queue().clone();
+        let app = RunBridge::builder().jobs(endpoints).build();
+
+        let res = dispatch(&app, post("/jobs", br#"{"n":1}"#)).await;
+        assert_eq!(res.status, 202);
+        let body: Value = serde_json::from_slice(res.body.as_deref().unwrap()).unwrap();
+        let id = body["id"].as_str().unwrap().to_string();
+        assert_eq!(body["status"], "pending");
+        assert_eq!(res.header("Location"), Some(format!("/jobs/{}", id).as_str()));
+        assert_eq!(body["status_url"], format!("/jobs/{}", id));
+        assert_eq!(res.header("Retry-After"), Some("2"));
+
+        // ワーカーが保存先を更新すると状態確認に反映される
+        queue.complete(&id, json!({ "total": 2 })).await.unwrap();
+        let res = dispatch(&app, Request::new(Method::GET, format!("/jobs/{}", id))).await;
+        assert_eq!(res.status, 200);
+        assert_eq!(res.header("Retry-After"), None);
+        let body: Value = serde_json::from_slice(res.body.as_deref().unwrap()).unwrap();
+        assert_eq!((body["status"].clone(), body["result"].clone()), (json!("succeeded"), json!({ "total": 2 })));
+
+        assert_eq!(dispatch(&app, Request::new(Method::GET, "/jobs/unknown".to_string())).await.status, 404);
+        assert_eq!(dispatch(&app, post("/jobs", b"{")).await.status, 400);
+    }
+
+    #[tokio::test]
+    async fn test_runner_processes_job_in_background() {
+        let endpoints = JobEndpoints::new("/jobs", MemoryJobStore::default()).runner(|job| async move {
+            job.progress(10, Some("started")).await?;
+            match job.input["n"].as_i64() {
+                Some(n) => Ok(json!(n * 2)),
+                None => Err(Error::InvalidRequestBody("secret detail".to_string())),
+            }
+        });
+        let queue = endpoints.queue().clone();
+        let app = RunBridge::builder().jobs(endpoints).build();
+
+        let wait = |id: String| {
+            let queue = queue.clone();
+            async move {
+                for _ in 0..100 {
+                    let job = queue.get(&id).await.unwrap().unwrap();
+                    if job.is_finished() {
+                        return job;
+                    }
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                }
+                panic!("job {} did not finish", id);
+            }
+        };
+        let id_of = |res: Response| {
+            let body: Value = serde_json::from_slice(res.body.as_deref().unwrap()).unwrap();
+            body["id"].as_str().unwrap().to_string()
+        };
+
+        let job = wait(id_of(dispatch(&app, post("/jobs", br#"{"n":21}"#)).await)).await;
+        assert_eq!((job.status, job.result), (JobStatus::Succeeded, Some(json!(42))));
+
+        let job = wait(id_of(dispatch(&app, post("/jobs", b"")).await)).await;
+        assert_eq!(job.status, JobStatus::Failed);
+        assert_eq!(job.error.as_deref(), Some("Bad Request"));
+    }
+}
@@ -5,9 +5,37 @@ pub mod pattern;
 pub mod body;
 pub mod core;
 pub mod builders;
+pub mod canary;
+pub mod guard;
+pub mod named;
+pub mod ext;
+pub mod echo;
+pub mod fields;
+pub mod health;
+pub mod upload;
+pub mod group;
+pub mod static_json;
+pub mod config_endpoint;
+pub mod stateful;
+pub mod job_endpoint;
+pub mod openapi_endpoint;
 
 pub use response::ResponseWrapper;
 pub use core::{RouteHandler, AsyncRouteHandler};
+pub use canary::{CanaryHandler, canary};
+pub use guard::{CircuitBreakerGuard, DependencyGuard, FlagGuard, OriginGuard, PreBodyGuard, SignedUrlGuard};
+pub use named::{BodyFieldsHandler, BodyLimitHandler, BodyPolicyHandler, DocumentedHandler, NamedHandler, RouteOptionsHandler};
+pub use ext::HandlerExt;
+pub use echo::DebugEchoHandler;
+pub use fields::SparseFieldsHandler;
+pub use health::{HealthCheck, HealthEndpoint, HealthHandler};
+pub use group::RouterGroup;
+pub use static_json::{StaticJsonHandler, static_json};
+pub use config_endpoint::ConfigEndpoint;
+pub use stateful::{StatefulHandler, StatefulRoutes};
+pub use job_endpoint::{JobContext, JobEndpoints, JobStatusHandler, JobSubmitHandler};
+pub use openapi_endpoint::OpenApiEndpoint;
+pub use upload::{MultipartHandler, post_multipart, async_post_multipart};
 pub use builders::{
     get, try_get, async_get, try_async_get,
     post, async_post,
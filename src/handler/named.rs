@@ -0,0 +1,70 @@
+//! ルート名・ルート固有の設定を付与するハンドラーラッパー
+
+use async_trait::async_trait;
+
+use crate::common::{Handler, Method, Request, Response, RouteOptions};
+use crate::error::Error;
+
+/// ルート固有の設定（`RouteOptions`）を付与したハンドラー
+///
+/// 内側のハンドラーの設定を引き継ぎ、指定した項目だけを置き換えます。
+/// `HandlerExt::name`・`with_body_policy`・`max_body_size`・`body_fields`・`openapi`で作成します。
+pub struct RouteOptionsHandler<H: Handler> {
+    inner: H,
+    options: RouteOptions,
+}
+
+impl<H: Handler> RouteOptionsHandler<H> {
+    /// 内側のハンドラーの設定を`update`で変更したRouteOptionsHandlerを作成
+    pub fn new(inner: H, update: impl FnOnce(&mut RouteOptions)) -> Self {
+        let mut options = inner.options().clone();
+        update(&mut options);
+        Self { inner, options }
+    }
+}
+
+/// ルート名付きのハンドラー（`HandlerExt::name`で作成）
+pub type NamedHandler<H> = RouteOptionsHandler<H>;
+
+/// GET/HEAD/DELETEのボディに対する方針を指定したハンドラー（`HandlerExt::with_body_policy`で作成）
+pub type BodyPolicyHandler<H> = RouteOptionsHandler<H>;
+
+/// リクエストボディの上限を指定したハンドラー（`HandlerExt::max_body_size`で作成）
+pub type BodyLimitHandler<H> = RouteOptionsHandler<H>;
+
+/// 型付きボディのフィールドの扱いを指定したハンドラー（`HandlerExt::body_fields`で作成）
+pub type BodyFieldsHandler<H> = RouteOptionsHandler<H>;
+
+/// OpenAPIのメタデータを付与したハンドラー（`HandlerExt::openapi`で作成）
+pub type DocumentedHandler<H> = RouteOptionsHandler<H>;
+
+#[async_trait]
+impl<H: Handler> Handler for RouteOptionsHandler<H> {
+    fn matches(&self, path: &str, method: &Method) -> bool {
+        self.inner.matches(path, method)
+    }
+
+    fn path_pattern(&self) -> &str {
+        self.inner.path_pattern()
+    }
+
+    fn options(&self) -> &RouteOptions {
+        &self.options
+    }
+
+    fn matches_by_pattern(&self) -> bool {
+        self.inner.matches_by_pattern()
+    }
+
+    fn methods(&self) -> Vec<Method> {
+        self.inner.methods()
+    }
+
+    fn check_pre_body(&self, req: &Request) -> Result<(), Error> {
+        self.inner.check_pre_body(req)
+    }
+
+    async fn handle(&self, req: Request) -> Result<Response, Error> {
+        self.inner.handle(req).await
+    }
+}
@@ -0,0 +1,69 @@
+//! OpenAPIドキュメントを返すエンドポイント
+
+use serde_json::Value;
+
+use crate::common::OpenApiInfo;
+use crate::error::Error;
+use super::static_json::StaticJsonHandler;
+
+/// OpenAPIエンドポイントの既定のパス
+pub const DEFAULT_OPENAPI_PATH: &str = "/openapi.json";
+
+/// OpenAPIドキュメントをJSONで返すエンドポイントの設定（GET、`RunBridgeBuilder::openapi`で登録）
+///
+/// ドキュメントは`build()`の時点で登録済みのルートから1回だけ生成し、ETag付きで返します。
+/// エンドポイント自身はドキュメントに含まれません。
+///
+/// ```
+/// use runbridge::common::OpenApiInfo;
+/// use runbridge::handler::OpenApiEndpoint;
+/// use runbridge::RunBridge;
+///
+/// let app = RunBridge::builder()
+///     .openapi(OpenApiEndpoint::new(OpenApiInfo::new("shop", "1.0.0")).path("/docs/openapi.json"))
+///     .build();
+/// # drop(app);
+/// ```
+#[derive(Debug, Clone)]
+pub struct OpenApiEndpoint {
+    info: OpenApiInfo,
+    path: String,
+    cache_control: Option<String>,
+}
+
+impl OpenApiEndpoint {
+    /// ドキュメントの情報を指定して既定のパス（`/openapi.json`）で作成
+    pub fn new(info: OpenApiInfo) -> Self {
+        Self {
+            info,
+            path: DEFAULT_OPENAPI_PATH.to_string(),
+            cache_control: None,
+        }
+    }
+
+    /// パスを変更
+    pub fn path(mut self, path: impl Into<String>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Cache-Controlを変更（既定は`static_json`と同じ）
+    pub fn cache_control(mut self, value: impl Into<String>) -> Self {
+        self.cache_control = Some(value.into());
+        self
+    }
+
+    /// ドキュメントの情報
+    pub fn info(&self) -> &OpenApiInfo {
+        &self.info
+    }
+
+    /// 生成したドキュメントを返すハンドラーを作成
+    pub(crate) fn into_handler(self, spec: &Value) -> Result<StaticJsonHandler, Error> {
+        let handler = StaticJsonHandler::try_new(self.path, spec)?;
+        Ok(match self.cache_control {
+            Some(value) => handler.cache_control(value),
+            None => handler,
+        })
+    }
+}
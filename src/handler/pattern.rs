@@ -1,11 +1,13 @@
 use log::warn;
 use crate::error::Error;
 
-/// パターンの安全性を確保（アンカーの確認と追加）
+/// パターンの安全性を確保（`{name}`形式のパスパラメータの展開と、アンカーの確認と追加）
 pub fn ensure_safe_pattern(pattern: &str) -> Result<String, Error> {
     if pattern.is_empty() {
         return Err(Error::InvalidRequestBody("Empty regex pattern is not allowed".to_string()));
     }
+    let expanded = expand_path_params(pattern)?;
+    let pattern = expanded.as_str();
 
     let has_start_anchor = pattern.starts_with('^');
     let has_end_anchor = pattern.ends_with('$');
@@ -26,3 +28,80 @@ pub fn ensure_safe_pattern(pattern: &str) -> Result<String, Error> {
     }
 }
 
+
+/// `{name}`・`{name:正規表現}`形式のパスパラメータを名前付きキャプチャに展開
+///
+/// `{id}`は`(?P<id>[^/]+)`、`{id:\d+}`は`(?P<id>\d+)`になります。
+/// 名前が英字または`_`で始まらない`{`（`\d{3}`などの量指定子）はそのまま残します。
+pub fn expand_path_params(pattern: &str) -> Result<String, Error> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut result = String::with_capacity(pattern.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => {
+                result.push('\\');
+                if let Some(&escaped) = chars.get(i + 1) {
+                    result.push(escaped);
+                }
+                i += 2;
+            }
+            '{' if chars.get(i + 1).is_some_and(|c| c.is_ascii_alphabetic() || *c == '_') => {
+                let mut end = i + 1;
+                while end < chars.len() && (chars[end].is_ascii_alphanumeric() || chars[end] == '_') {
+                    end += 1;
+                }
+                let name: String = chars[i + 1..end].iter().collect();
+                let regex = match chars.get(end) {
+                    Some('}') => {
+                        i = end + 1;
+                        "[^/]+".to_string()
+                    }
+                    Some(':') => {
+                        let close = closing_brace(&chars, end + 1).ok_or_else(|| {
+                            Error::InvalidRequestBody(format!("Unterminated path parameter '{}' in pattern: {}", name, pattern))
+                        })?;
+                        i = close + 1;
+                        chars[end + 1..close].iter().collect()
+                    }
+                    _ => {
+                        return Err(Error::InvalidRequestBody(format!(
+                            "Invalid path parameter '{}' in pattern: {}",
+                            name, pattern
+                        )))
+                    }
+                };
+                if regex.is_empty() {
+                    return Err(Error::InvalidRequestBody(format!(
+                        "Empty regex for path parameter '{}' in pattern: {}",
+                        name, pattern
+                    )));
+                }
+                result.push_str(&format!("(?P<{}>{})", name, regex));
+            }
+            c => {
+                result.push(c);
+                i += 1;
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// `start`以降で、対応する閉じ波括弧の位置（エスケープと入れ子の量指定子を考慮）
+fn closing_brace(chars: &[char], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = start;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(i),
+            '}' => depth -= 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
@@ -0,0 +1,223 @@
+//! 共有する状態（`Arc<S>`）を受け取るハンドラー
+//!
+//! サービスやDBプールを使うルートが多い場合に、ルートごとに`Arc`をcloneしてクロージャに
+//! 捕捉する代わりに、状態を1回だけ登録して各ハンドラーの引数で受け取ります。
+//!
+//! ```
+//! use std::sync::Arc;
+//! use runbridge::common::{Request, Response};
+//! use runbridge::error::Error;
+//! use runbridge::RunBridge;
+//!
+//! struct Services { greeting: String }
+//!
+//! async fn hello(services: Arc<Services>, _req: Request) -> Result<String, Error> {
+//!     Ok(services.greeting.clone())
+//! }
+//!
+//! async fn create(_services: Arc<Services>, req: Request) -> Result<Response, Error> {
+//!     let item: serde_json::Value = req.json()?;
+//!     Response::created().json(&item)
+//! }
+//!
+//! let app = RunBridge::builder()
+//!     .with_state(Services { greeting: "hello".to_string() }, |routes| {
+//!         routes.get("^/hello$", hello).post("^/items$", create)
+//!     })
+//!     .build();
+//! # drop(app);
+//! ```
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, OnceLock};
+
+use async_trait::async_trait;
+use log::error;
+use regex::Regex;
+
+use crate::common::{Handler, Method, Request, Response};
+use crate::error::Error;
+
+use super::pattern::ensure_safe_pattern;
+use super::response::ResponseWrapper;
+
+type BoxedResponseFuture = Pin<Box<dyn Future<Output = Result<Response, Error>> + Send>>;
+type BoxedStateFn<S> = Box<dyn Fn(Arc<S>, Request) -> BoxedResponseFuture + Send + Sync>;
+
+/// 共有する状態とリクエストを受け取る非同期関数を呼び出すハンドラー
+///
+/// ボディの変換は行わないため、`req.json()`・`req.form()`などで取り出します。
+pub struct StatefulHandler<S> {
+    path_pattern: String,
+    compiled_regex: OnceLock<Result<Regex, regex::Error>>,
+    method: Method,
+    state: Arc<S>,
+    handler_fn: BoxedStateFn<S>,
+}
+
+impl<S> StatefulHandler<S>
+where
+    S: Send + Sync + 'static,
+{
+    /// メソッド・パス・状態・関数を指定して作成（不正なパターンの場合はエラー）
+    pub fn try_new<F, R, Fut>(method: Method, path_pattern: impl Into<String>, state: Arc<S>, handler: F) -> Result<Self, Error>
+    where
+        F: Fn(Arc<S>, Request) -> Fut + Send + Sync + 'static,
+        R: ResponseWrapper + Send + 'static,
+        Fut: Future<Output = Result<R, Error>> + Send + 'static,
+    {
+        let handler_fn: BoxedStateFn<S> = Box::new(move |state, req| {
+            let fut = handler(state, req);
+            Box::pin(async move { fut.await?.into_response() })
+        });
+        Ok(Self {
+            path_pattern: ensure_safe_pattern(&path_pattern.into())?,
+            compiled_regex: OnceLock::new(),
+            method,
+            state,
+            handler_fn,
+        })
+    }
+
+    /// メソッド・パス・状態・関数を指定して作成（不正なパターンの場合はpanic）
+    pub fn new<F, R, Fut>(method: Method, path_pattern: impl Into<String>, state: Arc<S>, handler: F) -> Self
+    where
+        F: Fn(Arc<S>, Request) -> Fut + Send + Sync + 'static,
+        R: ResponseWrapper + Send + 'static,
+        Fut: Future<Output = Result<R, Error>> + Send + 'static,
+    {
+        Self::try_new(method, path_pattern, state, handler)
+            .unwrap_or_else(|e| panic!("Failed to create StatefulHandler: {}", e))
+    }
+
+    /// 共有している状態
+    pub fn state(&self) -> &Arc<S> {
+        &self.state
+    }
+}
+
+#[async_trait]
+impl<S> Handler for StatefulHandler<S>
+where
+    S: Send + Sync + 'static,
+{
+    fn matches(&self, path: &str, method: &Method) -> bool {
+        if method != &self.method {
+            return false;
+        }
+        match self.compiled_regex.get_or_init(|| Regex::new(&self.path_pattern)) {
+            Ok(regex) => regex.is_match(path),
+            Err(e) => {
+                error!(
+                    "Invalid regex pattern: {} - {}. Pattern will be rejected for security.",
+                    self.path_pattern, e
+                );
+                false
+            }
+        }
+    }
+
+    fn path_pattern(&self) -> &str {
+        &self.path_pattern
+    }
+
+    fn matches_by_pattern(&self) -> bool {
+        true
+    }
+
+    fn methods(&self) -> Vec<Method> {
+        vec![self.method]
+    }
+
+    async fn handle(&self, req: Request) -> Result<Response, Error> {
+        (self.handler_fn)(self.state.clone(), req).await
+    }
+}
+
+/// 同じ状態を共有するルートの一覧（`RunBridgeBuilder::with_state`で登録）
+pub struct StatefulRoutes<S> {
+    state: Arc<S>,
+    handlers: Vec<StatefulHandler<S>>,
+}
+
+impl<S> StatefulRoutes<S>
+where
+    S: Send + Sync + 'static,
+{
+    /// 共有する状態を指定して作成
+    pub fn new(state: Arc<S>) -> Self {
+        Self { state, handlers: Vec::new() }
+    }
+
+    /// 共有している状態
+    pub fn state(&self) -> &Arc<S> {
+        &self.state
+    }
+
+    /// メソッドを指定してルートを追加
+    pub fn route<F, R, Fut>(mut self, method: Method, path: impl Into<String>, handler: F) -> Self
+    where
+        F: Fn(Arc<S>, Request) -> Fut + Send + Sync + 'static,
+        R: ResponseWrapper + Send + 'static,
+        Fut: Future<Output = Result<R, Error>> + Send + 'static,
+    {
+        self.handlers.push(StatefulHandler::new(method, path, self.state.clone(), handler));
+        self
+    }
+
+    /// GETのルートを追加
+    pub fn get<F, R, Fut>(self, path: impl Into<String>, handler: F) -> Self
+    where
+        F: Fn(Arc<S>, Request) -> Fut + Send + Sync + 'static,
+        R: ResponseWrapper + Send + 'static,
+        Fut: Future<Output = Result<R, Error>> + Send + 'static,
+    {
+        self.route(Method::GET, path, handler)
+    }
+
+    /// POSTのルートを追加
+    pub fn post<F, R, Fut>(self, path: impl Into<String>, handler: F) -> Self
+    where
+        F: Fn(Arc<S>, Request) -> Fut + Send + Sync + 'static,
+        R: ResponseWrapper + Send + 'static,
+        Fut: Future<Output = Result<R, Error>> + Send + 'static,
+    {
+        self.route(Method::POST, path, handler)
+    }
+
+    /// PUTのルートを追加
+    pub fn put<F, R, Fut>(self, path: impl Into<String>, handler: F) -> Self
+    where
+        F: Fn(Arc<S>, Request) -> Fut + Send + Sync + 'static,
+        R: ResponseWrapper + Send + 'static,
+        Fut: Future<Output = Result<R, Error>> + Send + 'static,
+    {
+        self.route(Method::PUT, path, handler)
+    }
+
+    /// PATCHのルートを追加
+    pub fn patch<F, R, Fut>(self, path: impl Into<String>, handler: F) -> Self
+    where
+        F: Fn(Arc<S>, Request) -> Fut + Send + Sync + 'static,
+        R: ResponseWrapper + Send + 'static,
+        Fut: Future<Output = Result<R, Error>> + Send + 'static,
+    {
+        self.route(Method::PATCH, path, handler)
+    }
+
+    /// DELETEのルートを追加
+    pub fn delete<F, R, Fut>(self, path: impl Into<String>, handler: F) -> Self
+    where
+        F: Fn(Arc<S>, Request) -> Fut + Send + Sync + 'static,
+        R: ResponseWrapper + Send + 'static,
+        Fut: Future<Output = Result<R, Error>> + Send + 'static,
+    {
+        self.route(Method::DELETE, path, handler)
+    }
+
+    /// 登録するハンドラーの一覧に変換
+    pub fn into_handlers(self) -> Vec<Box<dyn Handler>> {
+        self.handlers.into_iter().map(|h| Box::new(h) as Box<dyn Handler>).collect()
+    }
+}
@@ -0,0 +1,136 @@
+//! 固定値をJSONで返すハンドラー（ETag・キャッシュヘッダー付き）
+
+use async_trait::async_trait;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::common::{if_none_match_matches, Handler, Method, Request, Response};
+use crate::error::Error;
+
+/// `static_json`の既定のCache-Control
+pub const DEFAULT_STATIC_JSON_CACHE_CONTROL: &str = "public, max-age=60";
+
+/// 構築時にシリアライズ済みのJSONを返すハンドラー（GET）
+///
+/// フィーチャーフラグや公開設定のように内容が変わらないレスポンス向けです。ボディとETagは
+/// 作成時に一度だけ計算し、`If-None-Match`が一致する場合はボディなしの304を返します。
+pub struct StaticJsonHandler {
+    path: String,
+    body: Vec<u8>,
+    etag: String,
+    cache_control: String,
+}
+
+impl StaticJsonHandler {
+    /// パスと値を指定して作成（シリアライズに失敗した場合はエラー）
+    pub fn try_new<T>(path: impl Into<String>, value: &T) -> Result<Self, Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        let body = serde_json::to_vec(value)
+            .map_err(|e| Error::ResponseSerializationError(e.to_string()))?;
+        let digest = Sha256::digest(&body);
+        let etag = format!("\"{}\"", base64::encode_config(&digest[..16], base64::URL_SAFE_NO_PAD));
+        Ok(Self {
+            path: path.into(),
+            body,
+            etag,
+            cache_control: DEFAULT_STATIC_JSON_CACHE_CONTROL.to_string(),
+        })
+    }
+
+    /// Cache-Controlを変更
+    pub fn cache_control(mut self, value: impl Into<String>) -> Self {
+        self.cache_control = value.into();
+        self
+    }
+
+    /// 計算済みのETag（引用符付き）
+    pub fn etag(&self) -> &str {
+        &self.etag
+    }
+
+    /// `If-None-Match`のいずれかのタグが一致するか（弱い比較）
+    fn is_not_modified(&self, if_none_match: &str) -> bool {
+        if_none_match_matches(if_none_match, &self.etag)
+    }
+}
+
+#[async_trait]
+impl Handler for StaticJsonHandler {
+    fn matches(&self, path: &str, method: &Method) -> bool {
+        path == self.path && *method == Method::GET
+    }
+
+    fn path_pattern(&self) -> &str {
+        &self.path
+    }
+
+    fn methods(&self) -> Vec<Method> {
+        vec![Method::GET]
+    }
+
+    async fn handle(&self, req: Request) -> Result<Response, Error> {
+        let not_modified = req
+            .headers
+            .get("if-none-match")
+            .is_some_and(|value| self.is_not_modified(value));
+        let res = Response::new(if not_modified { 304 } else { 200 })
+            .with_header("ETag", &self.etag)
+            .with_header("Cache-Control", &self.cache_control);
+        if not_modified {
+            return Ok(res);
+        }
+        Ok(res
+            .with_header("Content-Type", "application/json")
+            .with_body(self.body.clone()))
+    }
+}
+
+/// 固定値をJSONで返すGETハンドラーを作成
+///
+/// ```
+/// use runbridge::handler::static_json;
+/// use serde_json::json;
+///
+/// let handler = static_json("/config.json", &json!({ "features": { "beta": true } }))
+///     .cache_control("public, max-age=300");
+/// ```
+pub fn static_json<T>(path: impl Into<String>, value: &T) -> StaticJsonHandler
+where
+    T: Serialize + ?Sized,
+{
+    StaticJsonHandler::try_new(path, value)
+        .unwrap_or_else(|e| panic!("Failed to create StaticJsonHandler: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_static_json_etag() {
+        let handler = static_json("/config.json", &json!({ "beta": true }));
+        assert!(handler.matches("/config.json", &Method::GET));
+        assert!(!handler.matches("/config.json", &Method::POST));
+        assert_eq!(handler.etag(), static_json("/other", &json!({ "beta": true })).etag());
+        assert_ne!(handler.etag(), static_json("/config.json", &json!({ "beta": false })).etag());
+
+        let res = handler.handle(Request::new(Method::GET, "/config.json".to_string())).await.unwrap();
+        assert_eq!(res.status, 200);
+        assert_eq!(res.body.as_deref(), Some(&br#"{"beta":true}"#[..]));
+        assert_eq!(res.header("ETag"), Some(handler.etag()));
+        assert_eq!(res.header("Cache-Control"), Some(DEFAULT_STATIC_JSON_CACHE_CONTROL));
+
+        let weak = format!("\"stale\", W/{}", handler.etag());
+        let req = Request::new(Method::GET, "/config.json".to_string()).with_header("If-None-Match", &weak);
+        let res = handler.handle(req).await.unwrap();
+        assert_eq!(res.status, 304);
+        assert!(res.body.is_none());
+        assert_eq!(res.header("ETag"), Some(handler.etag()));
+
+        let req = Request::new(Method::GET, "/config.json".to_string()).with_header("If-None-Match", "\"stale\"");
+        assert_eq!(handler.handle(req).await.unwrap().status, 200);
+    }
+}
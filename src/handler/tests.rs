@@ -324,6 +324,54 @@ async fn test_anchor_normalization() {
     assert!(!handler3.matches("/prefix/partial", &Method::GET));
 }
 
+#[test]
+fn test_expand_path_params() {
+    use super::pattern::expand_path_params;
+
+    assert_eq!(expand_path_params("/items/{id}").unwrap(), "/items/(?P<id>[^/]+)");
+    assert_eq!(
+        expand_path_params(r"/users/{user_id:\d{1,8}}/posts/{slug}").unwrap(),
+        r"/users/(?P<user_id>\d{1,8})/posts/(?P<slug>[^/]+)"
+    );
+    // 量指定子やエスケープされた波括弧はそのまま
+    assert_eq!(expand_path_params(r"/codes/\d{3}/\{x\}").unwrap(), r"/codes/\d{3}/\{x\}");
+    assert!(expand_path_params("/items/{id").is_err());
+    assert!(expand_path_params("/items/{id:}").is_err());
+    assert!(try_get("/items/{id-x}", test_get_handler).is_err());
+}
+
+#[tokio::test]
+async fn test_path_params() {
+    use crate::RunBridge;
+
+    fn show(req: Request) -> Result<serde_json::Value, Error> {
+        let id: u32 = req.path_param("id")?;
+        let name = req.path_params().get("name").unwrap_or_default().to_string();
+        Ok(serde_json::json!({ "id": id, "name": name }))
+    }
+
+    let app = RunBridge::builder()
+        .handler(get(r"/items/{id:\d+}/{name}", show).name("show_item"))
+        .build();
+    let res = crate::testing::dispatch(&app, Request::new(Method::GET, "/items/42/a%20b".to_string())).await;
+    assert_eq!(res.status, 200);
+    let body: serde_json::Value = serde_json::from_slice(res.body.as_ref().unwrap()).unwrap();
+    assert_eq!(body, serde_json::json!({ "id": 42, "name": "a b" }));
+
+    // 型変換できない値は400
+    let handler = get("/users/{id}", show);
+    let mut req = Request::new(Method::GET, "/users/abc".to_string());
+    req.set_matched_route(&handler);
+    let err = handler.handle(req).await.unwrap_err();
+    assert_eq!(err.status_code(), 400);
+
+    // 名前付きルートは従来どおり逆引きできる
+    let mut req = Request::new(Method::GET, "/".to_string());
+    app.attach_route_context(app.find_handler("/items/1/x", &Method::GET).unwrap().as_ref(), &mut req);
+    assert_eq!(req.url_for("show_item", &[("id", "7"), ("name", "b c")]).unwrap(), "/items/7/b%20c");
+    assert!(req.path_params().is_empty());
+}
+
 #[tokio::test]
 async fn test_try_new_api() {
     // try_new APIの動作テスト
@@ -470,3 +518,843 @@ async fn test_empty_body_treated_as_missing_for_post() {
         e => panic!("unexpected error variant: {:?}", e),
     }
 }
+
+#[derive(Serialize, Debug)]
+struct Variant {
+    variant: &'static str,
+}
+
+fn stable_variant(_req: Request) -> Result<Variant, Error> {
+    Ok(Variant { variant: "stable" })
+}
+
+fn canary_variant(_req: Request) -> Result<Variant, Error> {
+    Ok(Variant { variant: "canary" })
+}
+
+async fn handled_variant(handler: &impl Handler, req: Request) -> String {
+    let res = handler.handle(req).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(res.body.as_ref().unwrap()).unwrap();
+    body["variant"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn test_canary_header_predicate() {
+    let handler = canary(get("/items", stable_variant), get("/items", canary_variant))
+        .header("X-Canary", "true");
+    assert!(handler.matches("/items", &Method::GET));
+    assert_eq!(handler.path_pattern(), "^/items$");
+
+    let req = Request::new(Method::GET, "/items".to_string()).with_header("X-Canary", "TRUE");
+    assert_eq!(handled_variant(&handler, req).await, "canary");
+
+    let req = Request::new(Method::GET, "/items".to_string());
+    assert_eq!(handled_variant(&handler, req).await, "stable");
+}
+
+#[tokio::test]
+async fn test_canary_weight_distribution() {
+    let handler = canary(get("/items", stable_variant), get("/items", canary_variant)).weight(10);
+    let mut canary_count = 0;
+    for _ in 0..2000 {
+        let req = Request::new(Method::GET, "/items".to_string());
+        if handled_variant(&handler, req).await == "canary" {
+            canary_count += 1;
+        }
+    }
+    // 期待値200（標準偏差は約13）
+    assert!((100..300).contains(&canary_count), "canary_count = {}", canary_count);
+}
+
+#[tokio::test]
+async fn test_canary_weight_in_fresh_process() {
+    // CGIや起動直後のLambdaのように、リクエストごとにハンドラーを作り直してもカナリアへ振り分けられる
+    let mut variants = std::collections::HashSet::new();
+    for _ in 0..200 {
+        let handler = canary(get("/items", stable_variant), get("/items", canary_variant)).weight(50);
+        let req = Request::new(Method::GET, "/items".to_string());
+        variants.insert(handled_variant(&handler, req).await);
+    }
+    assert!(variants.contains("canary"));
+    assert!(variants.contains("stable"));
+}
+
+#[tokio::test]
+async fn test_canary_requires_matching_method() {
+    // カナリア側がメソッドに一致しない場合は安定版へ
+    let handler = canary(get("/items", stable_variant), post("/items", test_post_handler)).weight(100);
+    let req = Request::new(Method::GET, "/items".to_string());
+    assert_eq!(handled_variant(&handler, req).await, "stable");
+}
+
+#[tokio::test]
+async fn test_when_flag_guard() {
+    use crate::common::{FeatureFlagMiddleware, Middleware, StaticFlags};
+
+    let enabled = FeatureFlagMiddleware::new(StaticFlags::new().set("new-checkout", true));
+    let disabled = FeatureFlagMiddleware::new(StaticFlags::new());
+    let handler = get("/checkout", stable_variant).when_flag("new-checkout");
+    assert!(handler.matches("/checkout", &Method::GET));
+
+    let req = enabled
+        .pre_process(Request::new(Method::GET, "/checkout".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(handled_variant(&handler, req).await, "stable");
+
+    let req = disabled
+        .pre_process(Request::new(Method::GET, "/checkout".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(handler.handle(req).await.unwrap().status, 404);
+
+    let handler = get("/checkout", stable_variant)
+        .when_flag("new-checkout")
+        .or_redirect("/legacy-checkout");
+    let res = handler
+        .handle(Request::new(Method::GET, "/checkout".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(res.status, 302);
+    assert_eq!(res.headers.get("Location").map(String::as_str), Some("/legacy-checkout"));
+}
+
+#[test]
+fn test_route_options_survive_stacked_wrappers() {
+    use crate::common::circuit_breaker::CircuitBreaker;
+    use crate::common::dependency::DependencyRegistry;
+    use crate::common::{BodyFieldPolicy, BodyPolicy, OperationDoc, RouteOptions, SecurityProfile};
+
+    let stacked = get("/items", test_get_handler)
+        .name("list_items")
+        .with_body_policy(BodyPolicy::Reject)
+        .max_body_size(1024)
+        .body_fields(BodyFieldPolicy::strict())
+        .openapi(OperationDoc::new().summary("List items"))
+        .when_flag("items")
+        .require_signed_url(b"secret".to_vec())
+        .allow_origins(["https://example.com"])
+        .depends_on(DependencyRegistry::new(), "db")
+        .circuit_breaker(CircuitBreaker::new("db"))
+        .pre_body_guard(|_| Ok(()))
+        .sparse_fields();
+    let stacked = canary(stacked, get("/items", test_get_handler));
+    let expected = RouteOptions {
+        name: Some("list_items".to_string()),
+        body_policy: Some(BodyPolicy::Reject),
+        body_size_limit: Some(1024),
+        body_field_policy: Some(BodyFieldPolicy::strict()),
+        operation_doc: Some(OperationDoc::new().summary("List items")),
+        security_profile: None,
+    };
+    assert_eq!(stacked.options(), &expected);
+    assert_eq!(stacked.methods(), vec![Method::GET]);
+    assert!(stacked.matches_by_pattern());
+
+    // グループではルートの設定を引き継ぎ、セキュリティヘッダーのプロファイルを追加する
+    let grouped = RouterGroup::new("/api")
+        .security_profile(SecurityProfile::Api)
+        .handler(stacked)
+        .into_handlers()
+        .remove(0);
+    assert_eq!(grouped.options(), &RouteOptions { security_profile: Some(SecurityProfile::Api), ..expected });
+    assert_eq!(grouped.route_name(), Some("list_items"));
+    assert_eq!(grouped.body_policy(), Some(BodyPolicy::Reject));
+    assert_eq!(grouped.body_size_limit(), Some(1024));
+    assert_eq!(grouped.body_field_policy(), Some(BodyFieldPolicy::strict()));
+    assert_eq!(grouped.operation_doc(), Some(&OperationDoc::new().summary("List items")));
+    assert_eq!(grouped.security_profile(), Some(SecurityProfile::Api));
+    assert_eq!(grouped.methods(), vec![Method::GET]);
+}
+
+#[tokio::test]
+async fn test_named_route_is_exposed_through_context() {
+    fn route_echo(req: Request) -> Result<serde_json::Value, Error> {
+        let route = req.matched_route().expect("matched route should be set");
+        Ok(serde_json::json!({ "pattern": route.pattern, "name": route.name, "label": route.label() }))
+    }
+
+    let handler = get("/items/\\d+", route_echo).name("get_item").when_flag("unused");
+    assert_eq!(handler.route_name(), Some("get_item"));
+    assert_eq!(get("/items", route_echo).route_name(), None);
+
+    let named = get("/items/\\d+", route_echo).name("get_item");
+    let mut req = Request::new(Method::GET, "/items/42".to_string());
+    req.set_matched_route(&named);
+    let res = named.handle(req).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(res.body.as_ref().unwrap()).unwrap();
+    assert_eq!(body["pattern"], "^/items/\\d+$");
+    assert_eq!(body["name"], "get_item");
+    assert_eq!(body["label"], "get_item");
+}
+
+#[tokio::test]
+async fn test_require_signed_url_guard() {
+    use crate::common::{parse_query_string, UrlSigner};
+    use std::time::Duration;
+
+    fn download(req: Request) -> Result<String, Error> {
+        let claims = req.signed_url_claims().expect("claims should be set");
+        Ok(claims.get("file").cloned().unwrap_or_default())
+    }
+
+    let handler = get("/download", download).require_signed_url("download-key");
+    let url = UrlSigner::new("download-key").sign("/download", Duration::from_secs(60), &[("file", "a.pdf")]);
+    let (path, query) = url.split_once('?').unwrap();
+
+    let mut req = Request::new(Method::GET, path.to_string());
+    req.query_params = parse_query_string(query);
+    let res = handler.handle(req).await.unwrap();
+    assert_eq!(res.status, 200);
+    assert_eq!(res.body.as_deref(), Some(&b"\"a.pdf\""[..]));
+
+    let req = Request::new(Method::GET, "/download".to_string()).with_query_param("file", "a.pdf");
+    assert_eq!(handler.handle(req).await.unwrap().status, 403);
+}
+
+#[tokio::test]
+async fn test_allow_origins_guard() {
+    fn events(_req: Request) -> Result<&'static str, Error> {
+        Ok("stream")
+    }
+
+    let handler = get("/events", events).allow_origins(["https://app.example.com/", "https://*.example.org"]);
+    let request = |origin: Option<&str>| {
+        let req = Request::new(Method::GET, "/events".to_string()).with_header("Host", "api.example.com");
+        match origin {
+            Some(origin) => req.with_header("Origin", origin),
+            None => req,
+        }
+    };
+
+    for allowed in [Some("https://APP.example.com"), Some("https://a.b.example.org"), Some("http://api.example.com"), None] {
+        assert_eq!(handler.handle(request(allowed)).await.unwrap().status, 200, "{:?}", allowed);
+    }
+    for denied in ["https://evil.com", "https://example.org", "https://evilexample.org", "http://app.example.com", "null"] {
+        assert_eq!(handler.handle(request(Some(denied))).await.unwrap().status, 403, "{}", denied);
+    }
+
+    let strict = get("/events", events).allow_origins(["https://app.example.com"]).require_origin();
+    assert_eq!(strict.handle(request(None)).await.unwrap().status, 403);
+}
+
+#[tokio::test]
+async fn test_route_body_policy_overrides_global() {
+    use crate::common::BodyPolicy;
+    use crate::RunBridge;
+
+    // ボディの長さを返すだけのハンドラー（get()はボディを()としてパースするため使用しない）
+    fn body_len(req: Request) -> Result<Response, Error> {
+        let len = req.body.map(|b| b.len()).unwrap_or(0);
+        Ok(Response::ok().with_body(len.to_string().into_bytes()))
+    }
+    let search = || crate::testing::raw_handler("^/search$", body_len);
+
+    let app = RunBridge::builder().build();
+    let req = |method: Method| Request::new(method, "/search".to_string()).with_body(b"{\"q\":1}".to_vec());
+
+    // 既定（allow）ではボディがそのまま渡される
+    let res = app.run_handler(&search(), req(Method::GET)).await.unwrap();
+    assert_eq!(res.body.as_deref(), Some(&b"7"[..]));
+
+    let handler = search().with_body_policy(BodyPolicy::Ignore);
+    let res = app.run_handler(&handler, req(Method::GET)).await.unwrap();
+    assert_eq!(res.body.as_deref(), Some(&b"0"[..]));
+
+    // 名前付けなど他のラッパーを重ねても方針は維持される
+    let handler = search().with_body_policy(BodyPolicy::Reject).name("search");
+    let err = app.run_handler(&handler, req(Method::DELETE)).await.unwrap_err();
+    assert_eq!(err.status_code(), 400);
+
+    // POSTには適用されない
+    let handler = search().with_body_policy(BodyPolicy::Reject);
+    let res = app.run_handler(&handler, req(Method::POST)).await.unwrap();
+    assert_eq!(res.body.as_deref(), Some(&b"7"[..]));
+}
+
+#[tokio::test]
+async fn test_route_max_body_size_overrides_global_limit() {
+    use crate::common::{KB, MB};
+
+    let app = crate::RunBridge::builder()
+        .handler(post("^/small$", test_post_handler).max_body_size(32).name("small"))
+        .handler(post("^/large$", test_post_handler).max_body_size(8 * MB))
+        .build();
+    assert_eq!(app.max_body_size_for("/small", &Method::POST), 32);
+    assert_eq!(app.max_body_size_for("/large", &Method::POST), 8 * MB);
+
+    let request = |path: &str, body: &[u8]| {
+        let mut req = Request::new(Method::POST, path.to_string());
+        req.headers.insert("content-type".to_string(), "application/json".to_string());
+        req.body = Some(body.to_vec());
+        req
+    };
+    let res = crate::testing::dispatch(&app, request("/small", br#"{"name":"a","value":1}"#)).await;
+    assert_eq!(res.status, 200);
+
+    // JSONの解析前にルートの上限で拒否する（不正なJSONでも400ではなく413）
+    let res = crate::testing::dispatch(&app, request("/small", &[b'x'; 33])).await;
+    assert_eq!(res.status, 413);
+    let res = crate::testing::dispatch(&app, request("/large", &[b'x'; 64 * KB])).await;
+    assert_eq!(res.status, 400);
+
+    // ボディの受信前の検査もルートの上限に従う
+    let mut req = Request::new(Method::POST, "/small".to_string());
+    req.headers.insert("content-length".to_string(), "64".to_string());
+    assert_eq!(app.check_before_body(&mut req).unwrap_err().status, 413);
+}
+
+#[tokio::test]
+async fn test_body_field_policy_route_overrides_app_default() {
+    use crate::common::{BodyFieldPolicy, RenameRule};
+
+    let app = crate::RunBridge::builder()
+        .body_field_policy(BodyFieldPolicy::strict())
+        .handler(post("^/strict$", test_post_handler))
+        .handler(async_post("^/camel$", test_async_post_handler).body_fields(BodyFieldPolicy::new().rename_all(RenameRule::UpperCase)))
+        .build();
+    let request = |path: &str, body: &[u8]| {
+        let mut req = Request::new(Method::POST, path.to_string());
+        req.headers.insert("content-type".to_string(), "application/json".to_string());
+        req.body = Some(body.to_vec());
+        req
+    };
+
+    let res = crate::testing::dispatch(&app, request("/strict", br#"{"name":"a","value":1}"#)).await;
+    assert_eq!(res.status, 200);
+    let res = crate::testing::dispatch(&app, request("/strict", br#"{"name":"a","value":1,"extra":true}"#)).await;
+    assert_eq!(res.status, 400);
+
+    // ルートの方針が全体設定より優先される（未知のフィールドは無視）
+    let res = crate::testing::dispatch(&app, request("/camel", br#"{"NAME":"a","VALUE":2,"extra":true}"#)).await;
+    assert_eq!(res.status, 200);
+    let body: TestResponse = serde_json::from_slice(res.body.as_ref().unwrap()).unwrap();
+    assert_eq!(body.value, 6);
+}
+
+#[tokio::test]
+async fn test_depends_on_short_circuits_when_dependency_is_down() {
+    use crate::common::{Criticality, Dependency, DependencyRegistry};
+
+    let registry = DependencyRegistry::new();
+    registry.register(Dependency::new("db", Criticality::Critical, || async { Ok(()) }));
+    let handler = get("/orders", |_req: Request| Ok("orders")).depends_on(registry.clone(), "db");
+    let req = || Request::new(Method::GET, "/orders".to_string());
+
+    // 未確認の間は通常どおり実行
+    assert_eq!(handler.handle(req()).await.unwrap().status, 200);
+
+    registry.report_failure("db", "connection refused");
+    assert_eq!(handler.handle(req()).await.unwrap().status, 503);
+
+    // ヘルスチェックで回復が確認されると再び実行される
+    registry.check_all().await;
+    assert_eq!(handler.handle(req()).await.unwrap().status, 200);
+}
+
+#[tokio::test]
+async fn test_circuit_breaker_guard() {
+    use crate::common::{CircuitBreaker, CircuitState};
+    use std::time::Duration;
+
+    fn charge(req: Request) -> Result<Response, Error> {
+        let status = if req.query_params.contains_key("fail") { 502 } else { 200 };
+        Ok(Response::new(status))
+    }
+
+    let breaker = CircuitBreaker::new("payments")
+        .minimum_calls(2)
+        .window_size(2)
+        .cool_down(Duration::from_secs(60));
+    let handler = get("/charge", charge).circuit_breaker(breaker.clone());
+    let failing = || Request::new(Method::GET, "/charge".to_string()).with_query_param("fail", "1");
+
+    for _ in 0..2 {
+        assert_eq!(handler.handle(failing()).await.unwrap().status, 502);
+    }
+    assert_eq!(breaker.state(), CircuitState::Open);
+
+    // 開いている間はハンドラーを実行せずに503を返す
+    let res = handler.handle(Request::new(Method::GET, "/charge".to_string())).await.unwrap();
+    assert_eq!(res.status, 503);
+    assert_eq!(res.headers.get("Retry-After").map(String::as_str), Some("60"));
+    assert_eq!(breaker.metrics().rejected, 1);
+}
+
+#[tokio::test]
+async fn test_post_multipart() {
+    use crate::common::MultipartForm;
+
+    #[derive(Deserialize)]
+    struct Upload {
+        title: String,
+    }
+
+    let handler = post_multipart("/upload", |_req, form: MultipartForm<Upload>| {
+        let size = form.file("file").map(|f| f.data.len()).ok_or_else(|| Error::InvalidRequestBody("file is required".to_string()))?;
+        Ok(TestResponse { message: form.fields.title, value: size as i32 })
+    });
+    assert!(handler.matches("/upload", &Method::POST));
+    assert!(!handler.matches("/upload", &Method::GET));
+
+    let body = "--x\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\nreport\r\n--x\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.bin\"\r\nContent-Type: application/octet-stream\r\n\r\n\x00\x01\x02\r\n--x--\r\n";
+    let req = Request::new(Method::POST, "/upload".to_string())
+        .with_header("Content-Type", "multipart/form-data; boundary=x")
+        .with_body(body.as_bytes().to_vec());
+    let res = handler.handle(req).await.unwrap();
+    let json: TestResponse = serde_json::from_slice(res.body.as_deref().unwrap()).unwrap();
+    assert_eq!(json, TestResponse { message: "report".to_string(), value: 3 });
+
+    // JSONボディや必須フィールドの欠落は400
+    let req = Request::new(Method::POST, "/upload".to_string())
+        .with_header("Content-Type", "application/json")
+        .with_body(b"{}".to_vec());
+    assert_eq!(handler.handle(req).await.unwrap_err().status_code(), 400);
+    let req = Request::new(Method::POST, "/upload".to_string())
+        .with_header("Content-Type", "multipart/form-data; boundary=x")
+        .with_body(b"--x--\r\n".to_vec());
+    assert_eq!(handler.handle(req).await.unwrap_err().status_code(), 400);
+}
+
+#[test]
+fn test_blocking_body_deserialization() {
+    let handler = post("/items", |req: Request, body: TestRequest| {
+        // ボディはデシリアライズ後もリクエストに残る
+        assert!(req.body.as_ref().is_some_and(|b| !b.is_empty()));
+        Ok(TestResponse { message: body.name, value: body.value })
+    });
+    let payload = serde_json::json!({"name": "x".repeat(4096), "value": 7});
+    let request = || {
+        Request::new(Method::POST, "/items".to_string())
+            .with_header("Content-Type", "application/json")
+            .with_body(serde_json::to_vec(&payload).unwrap())
+    };
+
+    temp_env::with_var("RUNBRIDGE_BLOCKING_DESERIALIZE_THRESHOLD", Some("1024"), || {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let res = runtime.block_on(handler.handle(request())).unwrap();
+        let json: TestResponse = serde_json::from_slice(res.body.as_deref().unwrap()).unwrap();
+        assert_eq!(json.value, 7);
+        assert_eq!(json.message.len(), 4096);
+
+        let invalid = request().with_body(b"{\"name\": 1}".repeat(200));
+        assert_eq!(runtime.block_on(handler.handle(invalid)).unwrap_err().status_code(), 400);
+    });
+}
+
+#[tokio::test]
+async fn test_form_urlencoded_body() {
+    let handler = post("/form", test_post_handler);
+
+    let req = Request::new(Method::POST, "/form".to_string())
+        .with_header("Content-Type", "application/x-www-form-urlencoded; charset=UTF-8")
+        .with_body(b"name=John+Doe%21&value=21".to_vec());
+    let res = handler.handle(req).await.expect("handler should accept form bodies");
+    let json: TestResponse = serde_json::from_slice(res.body.as_deref().unwrap()).unwrap();
+    assert_eq!(json, TestResponse { message: "Hello, John Doe!".to_string(), value: 42 });
+
+    // 型に合わない値は400
+    let req = Request::new(Method::POST, "/form".to_string())
+        .with_header("Content-Type", "application/x-www-form-urlencoded")
+        .with_body(b"name=x&value=abc".to_vec());
+    assert!(matches!(handler.handle(req).await, Err(Error::InvalidRequestBody(_))));
+}
+
+#[tokio::test]
+async fn test_router_group() {
+    use crate::common::Middleware;
+    use crate::RunBridge;
+
+    struct Tag(&'static str);
+
+    #[async_trait::async_trait]
+    impl Middleware for Tag {
+        async fn pre_process(&self, req: Request) -> Result<Request, Error> {
+            if req.headers.contains_key("x-deny") {
+                return Err(Error::AuthorizationError("denied".to_string()));
+            }
+            Ok(req)
+        }
+
+        async fn post_process(&self, res: Response) -> Result<Response, Error> {
+            let tags = res.headers.get("X-Tags").map(|t| format!("{},{}", t, self.0)).unwrap_or_else(|| self.0.to_string());
+            Ok(res.with_header("X-Tags", tags))
+        }
+    }
+
+    fn show(req: Request) -> Result<serde_json::Value, Error> {
+        Ok(serde_json::json!({ "id": req.path_params().get("id"), "path": req.path }))
+    }
+
+    let app = RunBridge::builder()
+        .handler(get("^/health$", test_get_handler))
+        .scope("/api/v1/", |api| {
+            api.middleware(Tag("api"))
+                .handler(get("^/$", test_get_handler))
+                .handler(get("^/items/{id}$", show).name("item"))
+                .scope("admin", |admin| admin.middleware(Tag("admin")).handler(get("^/stats$", test_get_handler)))
+        })
+        .build();
+    let request = |path: &str| Request::new(Method::GET, path.to_string());
+
+    let res = crate::testing::dispatch(&app, request("/api/v1/items/7")).await;
+    assert_eq!(res.status, 200);
+    let body: serde_json::Value = serde_json::from_slice(res.body.as_ref().unwrap()).unwrap();
+    assert_eq!(body, serde_json::json!({ "id": "7", "path": "/api/v1/items/7" }));
+    assert_eq!(res.headers.get("X-Tags").map(String::as_str), Some("api"));
+
+    // ネストしたグループは外側のミドルウェアの内側で実行される
+    let res = crate::testing::dispatch(&app, request("/api/v1/admin/stats")).await;
+    assert_eq!(res.headers.get("X-Tags").map(String::as_str), Some("admin,api"));
+    assert_eq!(crate::testing::dispatch(&app, request("/api/v1")).await.status, 200);
+
+    // グループ外のルートにはグループのミドルウェアを適用しない
+    let res = crate::testing::dispatch(&app, request("/health")).await;
+    assert!(!res.headers.contains_key("X-Tags"));
+    assert_eq!(crate::testing::dispatch(&app, request("/api/v1x/items/7")).await.status, 404);
+    assert_eq!(crate::testing::dispatch(&app, request("/items/7")).await.status, 404);
+    let denied = request("/api/v1/items/7").with_header("X-Deny", "1");
+    assert_eq!(crate::testing::dispatch(&app, denied).await.status, 403);
+
+    let mut req = request("/");
+    app.attach_route_context(app.find_handler("/api/v1/items/1", &Method::GET).unwrap().as_ref(), &mut req);
+    assert_eq!(req.url_for("item", &[("id", "9")]).unwrap(), "/api/v1/items/9");
+}
+
+#[test]
+fn test_validate_route_patterns() {
+    let app = crate::RunBridge::builder()
+        .handler(get("^/ok$", test_get_handler))
+        .handler(get("^/broken($", test_get_handler).name("broken"))
+        .handler(get("^/also[broken$", test_get_handler))
+        .build();
+    // 不正なパターンは照合時に常に不一致になる
+    assert!(app.find_handler("/broken(", &Method::GET).is_none());
+
+    let errors = app.validate().unwrap_err();
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].name.as_deref(), Some("broken"));
+    assert_eq!(errors[0].pattern, "^/broken($");
+    assert!(errors[1].to_string().starts_with("invalid route pattern '^/also[broken$'"));
+
+    let report = app.config_report();
+    assert_eq!(report.issues().iter().filter(|i| i.key == "routes").count(), 2);
+    assert!(crate::RunBridge::builder().handler(get("^/ok$", test_get_handler)).build().validate().is_ok());
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "Invalid route patterns")]
+fn test_invalid_patterns_panic_on_launch() {
+    let app = crate::RunBridge::builder()
+        .handler(get("^/broken($", test_get_handler))
+        .panic_on_invalid_patterns(true)
+        .build();
+    let _ = app.launch();
+}
+
+#[tokio::test]
+async fn test_shutdown_hooks_run_once_in_order() {
+    use std::sync::{Arc, Mutex};
+
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let (first, second) = (calls.clone(), calls.clone());
+    let app = crate::RunBridge::builder()
+        .on_shutdown(move || {
+            let calls = first.clone();
+            async move { calls.lock().unwrap().push("flush cache") }
+        })
+        .on_shutdown(move || {
+            let calls = second.clone();
+            async move { calls.lock().unwrap().push("close pool") }
+        })
+        .build();
+
+    app.shutdown().await;
+    app.shutdown().await;
+    assert_eq!(*calls.lock().unwrap(), vec!["flush cache", "close pool"]);
+}
+
+#[tokio::test]
+async fn test_app_data_injection() {
+    struct Settings {
+        greeting: String,
+    }
+
+    fn greet(req: Request) -> Result<String, Error> {
+        let settings = crate::common::Data::<Settings>::from_request(&req)?;
+        let visits = req.data::<std::sync::atomic::AtomicUsize>().unwrap();
+        let n = visits.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+        Ok(format!("{} #{}", settings.greeting, n))
+    }
+
+    let app = crate::RunBridge::builder()
+        .app_data(Settings { greeting: "hello".to_string() })
+        .app_data(std::sync::atomic::AtomicUsize::new(0))
+        .handler(get("/greet", greet))
+        .build();
+    assert_eq!(app.data::<Settings>().map(|s| s.greeting.as_str()), Some("hello"));
+
+    for expected in ["\"hello #1\"", "\"hello #2\""] {
+        let res = crate::testing::dispatch(&app, Request::new(Method::GET, "/greet".to_string())).await;
+        assert_eq!(res.status, 200);
+        assert_eq!(res.body.as_deref(), Some(expected.as_bytes()));
+    }
+
+    // 登録されていない型は500
+    let app = crate::RunBridge::builder().handler(get("/greet", greet)).build();
+    let res = crate::testing::dispatch(&app, Request::new(Method::GET, "/greet".to_string())).await;
+    assert_eq!(res.status, 500);
+}
+
+#[tokio::test]
+async fn test_security_profiles_global_and_group() {
+    use crate::common::SecurityProfile;
+
+    fn page(_req: Request) -> Result<Response, Error> {
+        Ok(Response::ok().with_header("Content-Type", "text/html"))
+    }
+
+    let app = crate::RunBridge::builder()
+        .security_profile(SecurityProfile::Api)
+        .handler(get("^/api/items$", test_get_handler))
+        .scope("/app", |g| {
+            g.security_profile(SecurityProfile::Html)
+                .handler(get("^/$", page))
+                .scope("/embed", |g| g.security_profile(SecurityProfile::None).handler(get("^/$", page)))
+        })
+        .build();
+    let request = |path: &str| Request::new(Method::GET, path.to_string());
+
+    let res = crate::testing::dispatch(&app, request("/api/items")).await;
+    assert_eq!(res.status, 200);
+    assert!(res.header("Content-Security-Policy").is_none());
+    assert!(res.header("Strict-Transport-Security").is_none());
+    assert_eq!(res.header("X-Content-Type-Options"), Some("nosniff"));
+
+    let res = crate::testing::dispatch(&app, request("/app")).await;
+    assert!(res.header("Content-Security-Policy").unwrap().contains("frame-ancestors 'none'"));
+    assert!(res.header("Strict-Transport-Security").is_some());
+
+    // 内側のグループの設定が優先
+    let res = crate::testing::dispatch(&app, request("/app/embed")).await;
+    assert_eq!(res.headers.len(), 1);
+    assert_eq!(res.header("Content-Type"), Some("text/html"));
+
+    // プロファイル未設定では従来の既定ヘッダー
+    let app = crate::RunBridge::builder().handler(get("^/api/items$", test_get_handler)).build();
+    let res = crate::testing::dispatch(&app, request("/api/items")).await;
+    assert_eq!(res.header("Content-Security-Policy"), Some("default-src 'self'"));
+}
+
+#[tokio::test]
+async fn test_path_normalization() {
+    use crate::common::PathNormalization;
+
+    let request = |path: &str| Request::new(Method::GET, path.to_string());
+    let app = crate::RunBridge::builder().handler(get("^/api/items$", test_get_handler)).build();
+    assert_eq!(crate::testing::dispatch(&app, request("/api/items/")).await.status, 404);
+
+    let app = crate::RunBridge::builder()
+        .path_normalization(PathNormalization::all())
+        .handler(get("^/api/items$", test_get_handler))
+        .build();
+    for path in ["/api/items/", "//api//items", "/api/%69tems"] {
+        assert_eq!(crate::testing::dispatch(&app, request(path)).await.status, 200, "{}", path);
+    }
+    assert_eq!(crate::testing::dispatch(&app, request("/api%2Fitems")).await.status, 404);
+}
+
+#[test]
+fn test_compiled_router_matches_linear_scan() {
+    use crate::common::DependencyRegistry;
+
+    fn build(compiled: bool) -> crate::RunBridge {
+        crate::RunBridge::builder()
+            .compiled_router(compiled)
+            .handler(get("^/api/users$", test_get_handler))
+            .handler(get(r"^/api/users/(?P<id>\d+)$", test_get_handler))
+            .handler(get("^/api/(users|teams)/export$", test_get_handler))
+            .handler(get("^/api/.*$", test_get_handler))
+            .handler(HealthHandler::new("/healthz", DependencyRegistry::new()))
+            .scope("/v1", |g| g.handler(get("^/$", test_get_handler)).handler(get("^/items$", test_get_handler)))
+            .handler(get("^/$", test_get_handler))
+            .build()
+    }
+
+    let (linear, compiled) = (build(false), build(true));
+    for path in [
+        "/api/users", "/api/users/7", "/api/users/x", "/api/teams/export", "/api/other",
+        "/healthz", "/v1", "/v1/", "/v1/items", "/", "/missing", "", "api/users",
+    ] {
+        let pattern = |app: &crate::RunBridge| app.find_handler(path, &Method::GET).map(|h| h.path_pattern().to_string());
+        assert_eq!(pattern(&compiled), pattern(&linear), "{}", path);
+    }
+    assert_eq!(compiled.find_handler("/api/users/7", &Method::GET).unwrap().path_pattern(), r"^/api/users/(?P<id>\d+)$");
+}
+
+#[tokio::test]
+async fn test_head_falls_back_to_get_route() {
+    fn head_handler(_req: Request, _body: Option<()>) -> Result<Response, Error> {
+        Ok(Response::ok().with_header("X-Head", "explicit"))
+    }
+
+    let app = crate::RunBridge::builder()
+        .handler(get("^/items$", test_get_handler))
+        .handler(post("^/submit$", test_post_handler))
+        .handler(get("^/explicit$", test_get_handler))
+        .handler(RouteHandler::try_new(Method::HEAD, "^/explicit$", head_handler).unwrap())
+        .build();
+
+    let get_res = crate::testing::dispatch(&app, Request::new(Method::GET, "/items".to_string())).await;
+    let res = crate::testing::dispatch(&app, Request::new(Method::HEAD, "/items".to_string())).await;
+    assert_eq!(res.status, 200);
+    assert!(res.body.is_none());
+    let length = get_res.body.as_ref().unwrap().len().to_string();
+    assert_eq!(res.headers.get("Content-Length"), Some(&length));
+    assert_eq!(res.headers.get("Content-Type"), get_res.headers.get("Content-Type"));
+
+    // HEADのルートが登録されている場合はそちらを優先
+    let res = crate::testing::dispatch(&app, Request::new(Method::HEAD, "/explicit".to_string())).await;
+    assert_eq!(res.headers.get("X-Head").map(String::as_str), Some("explicit"));
+
+    // GET以外のルートには代替しない
+    let res = crate::testing::dispatch(&app, Request::new(Method::HEAD, "/submit".to_string())).await;
+    assert_eq!(res.status, 404);
+}
+
+#[tokio::test]
+async fn test_pre_body_guard() {
+    let app = crate::RunBridge::builder()
+        .handler(post("^/upload$", test_post_handler).name("upload").pre_body_guard(|req| {
+            match req.headers.get("authorization") {
+                Some(_) => Ok(()),
+                None => Err(Error::AuthenticationError("missing credentials".to_string())),
+            }
+        }))
+        .build();
+
+    // ボディの受信前の検査
+    let mut req = Request::new(Method::POST, "/upload".to_string());
+    assert_eq!(app.check_before_body(&mut req).unwrap_err().status, 401);
+    req.headers.insert("authorization".to_string(), "Bearer t".to_string());
+    assert!(app.check_before_body(&mut req).is_ok());
+    req.headers.insert("content-length".to_string(), usize::MAX.to_string());
+    assert_eq!(app.check_before_body(&mut req).unwrap_err().status, 413);
+    let mut missing = Request::new(Method::POST, "/missing".to_string());
+    assert_eq!(app.check_before_body(&mut missing).unwrap_err().status, 404);
+
+    // `Expect`を使わないリクエストはハンドラーの実行前に検査
+    let mut req = Request::new(Method::POST, "/upload".to_string());
+    req.body = Some(br#"{"name":"a","value":1}"#.to_vec());
+    assert_eq!(crate::testing::dispatch(&app, req).await.status, 401);
+}
+
+#[tokio::test]
+async fn test_config_endpoint() {
+    let app = crate::RunBridge::builder()
+        .handler(get("^/ok$", test_get_handler))
+        .around(crate::common::CompressionMiddleware::new())
+        .config_endpoint(super::ConfigEndpoint::new().token("s3cret"))
+        .build();
+    let request = |auth: Option<&str>| {
+        let req = Request::new(Method::GET, "/__runbridge/config".to_string());
+        match auth {
+            Some(auth) => req.with_header("Authorization", auth),
+            None => req,
+        }
+    };
+
+    assert_eq!(crate::testing::dispatch(&app, request(None)).await.status, 401);
+    assert_eq!(crate::testing::dispatch(&app, request(Some("Bearer wrong"))).await.status, 401);
+    let res = crate::testing::dispatch(&app, request(Some("Bearer s3cret"))).await;
+    assert_eq!(res.status, 200);
+    assert_eq!(res.headers.get("Cache-Control").map(String::as_str), Some("no-store"));
+    let body: serde_json::Value = serde_json::from_slice(res.body.as_ref().unwrap()).unwrap();
+    assert_eq!(body["config"]["settings"]["handlers"], 2);
+    assert_eq!(
+        body["config"]["settings"]["around_middleware_names"],
+        serde_json::json!(["runbridge::common::compression::CompressionMiddleware"])
+    );
+    assert_eq!(body["build"]["runbridge_version"], env!("CARGO_PKG_VERSION"));
+    assert!(!String::from_utf8_lossy(res.body.as_ref().unwrap()).contains("s3cret"));
+
+    // トークンが未設定の場合は公開しない
+    let app = crate::RunBridge::builder().config_endpoint(super::ConfigEndpoint::new().token("")).build();
+    assert_eq!(crate::testing::dispatch(&app, request(Some("Bearer "))).await.status, 404);
+}
+
+#[tokio::test]
+async fn test_async_handler_future_need_not_be_sync() {
+    // Cellを保持したままawaitするFutureはSendだがSyncではない
+    let app = crate::RunBridge::builder()
+        .handler(async_get("^/cell$", |_req| async {
+            let counter = std::cell::Cell::new(1);
+            tokio::task::yield_now().await;
+            counter.set(counter.get() + 1);
+            Ok(Response::ok().with_body(counter.get().to_string().into_bytes()))
+        }))
+        .build();
+    let res = crate::testing::dispatch(&app, Request::new(Method::GET, "/cell".to_string())).await;
+    assert_eq!(res.status, 200);
+    assert_eq!(res.body.as_deref(), Some(&b"2"[..]));
+}
+
+#[tokio::test]
+async fn test_with_state_routes() {
+    struct Counter(std::sync::atomic::AtomicUsize);
+
+    async fn increment(counter: std::sync::Arc<Counter>, _req: Request) -> Result<String, Error> {
+        let n = counter.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+        Ok(n.to_string())
+    }
+
+    let app = crate::RunBridge::builder()
+        .with_state(Counter(std::sync::atomic::AtomicUsize::new(0)), |routes| {
+            routes
+                .post("^/count$", increment)
+                .get("^/count$", |counter: std::sync::Arc<Counter>, _req| async move {
+                    Ok(counter.0.load(std::sync::atomic::Ordering::SeqCst).to_string())
+                })
+        })
+        .build();
+
+    let request = |method| Request::new(method, "/count".to_string());
+    crate::testing::dispatch(&app, request(Method::POST)).await;
+    let res = crate::testing::dispatch(&app, request(Method::POST)).await;
+    assert_eq!(res.body.as_deref(), Some(&br#""2""#[..]));
+    let res = crate::testing::dispatch(&app, request(Method::GET)).await;
+    assert_eq!(res.status, 200);
+    assert_eq!(res.body.as_deref(), Some(&br#""2""#[..]));
+    assert_eq!(crate::testing::dispatch(&app, request(Method::PUT)).await.status, 404);
+}
+
+#[tokio::test]
+async fn test_openapi_endpoint_serves_documented_routes() {
+    use crate::common::{OpenApiInfo, OperationDoc};
+
+    let app = crate::RunBridge::builder()
+        .handler(
+            get("/items/{id}", |_req: Request| Ok(()))
+                .openapi(OperationDoc::new().summary("商品を取得").tag("items"))
+                .name("get_item")
+                .when_flag("unused"),
+        )
+        .handler(post("^/items$", |_req: Request, _body: serde_json::Value| Ok(())))
+        .openapi(super::OpenApiEndpoint::new(OpenApiInfo::new("shop", "2.0.0")))
+        .build();
+
+    let res = crate::testing::dispatch(&app, Request::new(Method::GET, "/openapi.json".to_string())).await;
+    assert_eq!(res.status, 200);
+    assert!(res.header("etag").is_some());
+    let served: serde_json::Value = serde_json::from_slice(res.body.as_deref().unwrap()).unwrap();
+    assert_eq!(served, app.openapi_spec());
+
+    assert_eq!(served["info"]["title"], "shop");
+    let get_item = &served["paths"]["/items/{id}"]["get"];
+    assert_eq!(get_item["operationId"], "get_item");
+    assert_eq!(get_item["summary"], "商品を取得");
+    assert_eq!(served["paths"]["/items"]["post"]["responses"]["default"]["description"], "Response");
+    // エンドポイント自身はドキュメントに含めない
+    assert!(served["paths"].get("/openapi.json").is_none());
+}
@@ -0,0 +1,129 @@
+//! `multipart/form-data`を受け取るPOSTハンドラー
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::OnceLock;
+
+use async_trait::async_trait;
+use log::error;
+use regex::Regex;
+use serde::de::DeserializeOwned;
+
+use crate::common::multipart::MultipartForm;
+use crate::common::{Handler, Method, Request, Response};
+use crate::error::Error;
+
+use super::pattern::ensure_safe_pattern;
+use super::response::ResponseWrapper;
+
+type BoxedResponseFuture = Pin<Box<dyn Future<Output = Result<Response, Error>> + Send>>;
+type BoxedFormFn<T> = Box<dyn Fn(Request, MultipartForm<T>) -> BoxedResponseFuture + Send + Sync>;
+
+/// ボディを`MultipartForm<T>`に変換してから関数を呼び出すハンドラー
+///
+/// Content-Typeが`multipart/form-data`でない場合やフィールドを`T`に変換できない場合は400、
+/// ボディが`RUNBRIDGE_MAX_BODY_SIZE`を超える場合は413を返します。
+pub struct MultipartHandler<T> {
+    path_pattern: String,
+    compiled_regex: OnceLock<Result<Regex, regex::Error>>,
+    method: Method,
+    handler_fn: BoxedFormFn<T>,
+}
+
+impl<T> MultipartHandler<T>
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    fn try_new(method: Method, path_pattern: impl Into<String>, handler_fn: BoxedFormFn<T>) -> Result<Self, Error> {
+        Ok(Self {
+            path_pattern: ensure_safe_pattern(&path_pattern.into())?,
+            compiled_regex: OnceLock::new(),
+            method,
+            handler_fn,
+        })
+    }
+}
+
+#[async_trait]
+impl<T> Handler for MultipartHandler<T>
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    fn matches(&self, path: &str, method: &Method) -> bool {
+        if method != &self.method {
+            return false;
+        }
+        match self.compiled_regex.get_or_init(|| Regex::new(&self.path_pattern)) {
+            Ok(regex) => regex.is_match(path),
+            Err(e) => {
+                error!(
+                    "Invalid regex pattern: {} - {}. Pattern will be rejected for security.",
+                    self.path_pattern, e
+                );
+                false
+            }
+        }
+    }
+
+    fn path_pattern(&self) -> &str {
+        &self.path_pattern
+    }
+
+    fn matches_by_pattern(&self) -> bool {
+        true
+    }
+
+    fn methods(&self) -> Vec<Method> {
+        vec![self.method]
+    }
+
+    async fn handle(&self, req: Request) -> Result<Response, Error> {
+        let form = MultipartForm::<T>::from_request(&req)?;
+        (self.handler_fn)(req, form).await
+    }
+}
+
+/// `multipart/form-data`を受け取るPOSTハンドラーを作成
+///
+/// ```
+/// use runbridge::common::MultipartForm;
+/// use runbridge::handler::post_multipart;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Upload { title: String }
+///
+/// let handler = post_multipart("/upload", |_req, form: MultipartForm<Upload>| {
+///     let size = form.file("file").map(|f| f.data.len()).unwrap_or(0);
+///     Ok(format!("{}: {} bytes", form.fields.title, size))
+/// });
+/// ```
+pub fn post_multipart<F, T, R>(path: impl Into<String>, handler: F) -> MultipartHandler<T>
+where
+    F: Fn(Request, MultipartForm<T>) -> Result<R, Error> + Send + Sync + 'static,
+    T: DeserializeOwned + Send + 'static,
+    R: ResponseWrapper + Send + Sync + 'static,
+{
+    let handler_fn: BoxedFormFn<T> = Box::new(move |req, form| {
+        let result = handler(req, form).and_then(|r| r.into_response());
+        Box::pin(futures::future::ready(result))
+    });
+    MultipartHandler::try_new(Method::POST, path, handler_fn)
+        .unwrap_or_else(|e| panic!("Failed to create MultipartHandler: {}", e))
+}
+
+/// `multipart/form-data`を受け取る非同期POSTハンドラーを作成
+pub fn async_post_multipart<F, T, R, Fut>(path: impl Into<String>, handler: F) -> MultipartHandler<T>
+where
+    F: Fn(Request, MultipartForm<T>) -> Fut + Send + Sync + 'static,
+    T: DeserializeOwned + Send + 'static,
+    R: ResponseWrapper + Send + Sync + 'static,
+    Fut: Future<Output = Result<R, Error>> + Send + 'static,
+{
+    let handler_fn: BoxedFormFn<T> = Box::new(move |req, form| {
+        let fut = handler(req, form);
+        Box::pin(async move { fut.await?.into_response() })
+    });
+    MultipartHandler::try_new(Method::POST, path, handler_fn)
+        .unwrap_or_else(|e| panic!("Failed to create MultipartHandler: {}", e))
+}
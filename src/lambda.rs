@@ -1,47 +1,111 @@
 //! AWS Lambda向けの実装
 
 use std::collections::HashMap;
-use log::{debug, info, warn, error};
+use log::{info, warn, error};
 use lambda_runtime::{run, service_fn, Error as LambdaError, LambdaEvent};
 use aws_lambda_events::event::apigw::{ApiGatewayV2httpRequest, ApiGatewayV2httpResponse};
 use aws_lambda_events::http::header::{HeaderMap, HeaderName, HeaderValue};
 use aws_lambda_events::encodings::Body;
+use aws_lambda_events::query_map::QueryMap;
 
-use crate::common::{Method, Request, Response, get_max_body_size};
+use crate::common::{Method, Request, Response, check_method, parse_query_string_limited};
+use crate::common::error_response::error_response;
+use crate::common::utils::check_query_limits;
+use crate::common::origin::RequestOrigin;
+use crate::common::deadline::with_deadline;
+use crate::common::request_id::with_request_id;
 use crate::error::Error as AppError;
 use crate::RunBridge;
 
-// 共有の get_max_body_size を使用（common/utils.rs）
-
-/// API Gateway Proxyリクエストから共通のRequestに変換
-fn convert_apigw_request(event: ApiGatewayV2httpRequest) -> Result<Request, AppError> {
-    // HTTPメソッドの変換
-    let method = match event.request_context.http.method.as_str() {
-        "GET" => Method::GET,
-        "POST" => Method::POST,
-        "PUT" => Method::PUT,
-        "DELETE" => Method::DELETE,
-        "PATCH" => Method::PATCH,
-        "HEAD" => Method::HEAD,
-        "OPTIONS" => Method::OPTIONS,
-        _ => {
-            debug!("Unknown HTTP method: {}, fallback to GET", event.request_context.http.method);
-            Method::GET
-        }
-    };
+// ボディの上限は RunBridge::max_body_size_for（ルート固有 -> common/utils.rs の全体設定）を使用
+
+/// Lambda同期呼び出しのレスポンスペイロード上限（6MB）
+const DEFAULT_MAX_RESPONSE_SIZE: usize = 6 * 1024 * 1024;
+
+/// ステータス・ヘッダー等、ボディ以外のJSONエンベロープ分として見込むバイト数
+const RESPONSE_ENVELOPE_OVERHEAD: usize = 1024;
+
+/// Lambdaレスポンスの最大サイズ（バイト）を取得する
+/// 優先順位: 環境変数 `RUNBRIDGE_LAMBDA_MAX_RESPONSE_SIZE` -> デフォルト 6MB
+pub fn get_max_response_size() -> usize {
+    std::env::var("RUNBRIDGE_LAMBDA_MAX_RESPONSE_SIZE")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_MAX_RESPONSE_SIZE)
+}
+
+/// エンコード後（テキストはそのまま、バイナリと`Content-Encoding`付きのボディはBase64）のボディサイズを見積もる
+fn estimate_encoded_body_size(body: &[u8], content_encoded: bool) -> usize {
+    if !content_encoded && std::str::from_utf8(body).is_ok() {
+        body.len()
+    } else {
+        body.len().div_ceil(3).saturating_mul(4)
+    }
+}
+
+/// レスポンスがLambdaのペイロード上限を超える場合、安全な500レスポンスに差し替える
+/// プラットフォーム側で不透明なエラーになる前に、ログとクリーンなエラーを返す
+fn guard_response_size(response: Response, max_size: usize) -> Response {
+    let body_size = response
+        .body
+        .as_deref()
+        .map(|body| estimate_encoded_body_size(body, response.is_content_encoded()))
+        .unwrap_or(0);
+    let header_size: usize = response
+        .headers
+        .iter()
+        .map(|(k, v)| k.len() + v.len())
+        .chain(response.cookies.iter().map(String::len))
+        .sum();
+    let estimated = body_size + header_size + RESPONSE_ENVELOPE_OVERHEAD;
 
+    if estimated <= max_size {
+        return response;
+    }
+
+    error!(
+        "Response too large for Lambda: status {} with estimated payload {} bytes (body {} bytes encoded, limit {} bytes). Consider pagination or offloading to object storage.",
+        response.status,
+        estimated,
+        body_size,
+        max_size
+    );
+    error_response(500, Some("response too large"))
+}
+
+/// 解析済みのクエリパラメータを変換（生のクエリ文字列が無いイベント向け）
+fn convert_query_map(query: &QueryMap) -> Result<HashMap<String, String>, AppError> {
+    // API Gatewayが解析済みのため、キーと値の長さからクエリ文字列長を見積もって上限を検査
+    let query_length = query.iter().map(|(k, v)| k.len() + v.len() + 2).sum();
+    let query_count = query.iter().count();
+    check_query_limits(query_length, query_count)?;
+
+    let mut query_params = HashMap::with_capacity(query_count);
+    for (key, value) in query.iter() {
+        query_params.insert(key.to_string(), value.to_string());
+    }
+    Ok(query_params)
+}
+
+/// API Gateway Proxyリクエストから共通のRequestに変換（メソッドは検査済みのもの、ボディの上限はルートに応じたものを使用）
+fn convert_apigw_request(
+    event: ApiGatewayV2httpRequest,
+    method: Method,
+    max_body_bytes: usize,
+) -> Result<Request, AppError> {
     // パスの取得
     let path = event.request_context.http.path.unwrap_or_else(|| "/".to_string());
 
     // クエリパラメータの解析
-    let mut query_params = HashMap::new();
-    // クエリストリングパラメータはオプションではなく、デフォルト値が空のマップ
-    for (key, value) in event.query_string_parameters.iter() {
-        query_params.insert(key.to_string(), value.to_string());
-    }
+    // Function URL・HTTP API（v2ペイロード）は生のクエリ文字列を含むため、他のランタイムと同じ規則で解析する
+    // （`queryStringParameters`は同名のキーをカンマで連結しており、元の値を復元できない）
+    let query_params = match event.raw_query_string.as_deref() {
+        Some(raw) => parse_query_string_limited(raw)?,
+        None => convert_query_map(&event.query_string_parameters)?,
+    };
 
     // ヘッダーの変換
-    let headers: HashMap<String, String> = event.headers.iter()
+    let mut headers: HashMap<String, String> = event.headers.iter()
         .filter_map(|(k, v)| {
             if let Ok(v_str) = v.to_str() {
                 // Request取り込み時は小文字キーに正規化
@@ -52,10 +116,16 @@ fn convert_apigw_request(event: ApiGatewayV2httpRequest) -> Result<Request, AppE
         })
         .collect();
 
+    // v2ペイロードではCookieヘッダーが`cookies`配列に移されるため、ヘッダーに戻す
+    if let Some(cookies) = event.cookies.as_ref().filter(|cookies| !cookies.is_empty()) {
+        if !headers.contains_key("cookie") {
+            headers.insert("cookie".to_string(), cookies.join("; "));
+        }
+    }
+
     // ボディの変換（境界検査とサイズ上限チェック）
     let body = match event.body {
         Some(body_str) => {
-            let max_body_bytes = get_max_body_size();
             if event.is_base64_encoded {
                 // 入力長から概算のデコード後サイズを見積り（4文字→3バイト、端数切り上げ）
                 let estimated_decoded = ((body_str.len() + 3) / 4).saturating_mul(3);
@@ -117,14 +187,19 @@ fn convert_apigw_request(event: ApiGatewayV2httpRequest) -> Result<Request, AppE
     request.query_params = query_params;
     request.headers = headers;
     request.body = body;
+    // API Gatewayは常にHTTPSで受け付ける
+    request.set_origin(RequestOrigin {
+        scheme: "https".to_string(),
+        host: event.request_context.domain_name.clone(),
+    });
 
-    // gzipボディを解凍（必要な場合のみ）
-    if let Err(e) = request.decompress_gzip_body() {
+    // gzipボディを解凍（必要な場合のみ、解凍後のサイズもルートの上限で制限）
+    if let Err(e) = request.decompress_gzip_body_with_limit(max_body_bytes) {
         warn!("Failed to decompress gzip body in Lambda: {}", e);
         return Err(e);
     }
 
-    // パスパラメータの処理
+    // API Gatewayのパスパラメータ（互換性のため残す。ルートパターンのパラメータは`req.path_params()`を使用）
     for (key, value) in event.path_parameters.iter() {
         request.query_params.insert(format!("path_{}", key), value.to_string());
     }
@@ -134,23 +209,31 @@ fn convert_apigw_request(event: ApiGatewayV2httpRequest) -> Result<Request, AppE
 
 /// 共通のResponseからAPI Gateway Proxyレスポンスに変換
 fn convert_to_apigw_response(response: Response) -> ApiGatewayV2httpResponse {
-    // ボディの変換
-    let (body, is_base64_encoded) = if let Some(body) = response.body {
-        // テキストとして解釈できるかチェック
-        match String::from_utf8(body.clone()) {
+    // ペイロード上限を超えるレスポンスはここで差し替える
+    let response = guard_response_size(response, get_max_response_size());
+
+    // Set-Cookieはv2ペイロードの`cookies`配列で返す（1つのヘッダーに連結するとブラウザが解釈できない）
+    let cookies = response.set_cookie_values();
+
+    // ボディの変換（圧縮済みのボディは常にバイナリとして扱い、Base64化は1回だけ行う）
+    let is_encoded = response.is_content_encoded();
+    let (body, is_base64_encoded) = match response.body {
+        Some(body) if is_encoded => (Some(base64::encode(&body)), true),
+        // テキストとして解釈できる場合はそのまま返す
+        Some(body) => match String::from_utf8(body) {
             Ok(text) => (Some(text), false),
-            Err(_) => {
-                // バイナリデータの場合はBase64エンコード
-                (Some(base64::encode(&body)), true)
-            }
-        }
-    } else {
-        (None, false)
+            // バイナリデータの場合はBase64エンコード
+            Err(e) => (Some(base64::encode(e.as_bytes())), true),
+        },
+        None => (None, false),
     };
 
     // ヘッダーの変換
     let mut headers = HeaderMap::new();
     for (key, value) in response.headers {
+        if key.eq_ignore_ascii_case("set-cookie") {
+            continue;
+        }
         if let (Ok(header_name), Ok(header_value)) = (
             HeaderName::try_from(key),
             HeaderValue::try_from(value)
@@ -171,7 +254,7 @@ fn convert_to_apigw_response(response: Response) -> ApiGatewayV2httpResponse {
         multi_value_headers,
         body,
         is_base64_encoded: is_base64_encoded,
-        cookies: Vec::new(),
+        cookies,
     }
 }
 
@@ -180,68 +263,65 @@ async fn lambda_handler(
     app: &RunBridge,
     event: LambdaEvent<ApiGatewayV2httpRequest>,
 ) -> Result<ApiGatewayV2httpResponse, LambdaError> {
-    let (event, _context) = event.into_parts();
-    
-    // リクエストの変換
-    let req = match convert_apigw_request(event) {
+    let (event, context) = event.into_parts();
+
+    // 呼び出しの期限（エポックからのミリ秒）を単調時計の期限に変換
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    let deadline = std::time::Instant::now()
+        + std::time::Duration::from_millis(context.deadline.saturating_sub(now_ms));
+
+    // Lambdaのリクエストと期限をタスクローカルに設定して処理（パニックフックのログ・期限の参照用）
+    with_request_id(
+        context.request_id.clone(),
+        with_deadline(Some(deadline), process_event(app, event)),
+    )
+    .await
+}
+
+/// API Gatewayイベントを処理してレスポンスを返す
+async fn process_event(
+    app: &RunBridge,
+    event: ApiGatewayV2httpRequest,
+) -> Result<ApiGatewayV2httpResponse, LambdaError> {
+    let started = std::time::Instant::now();
+    let method_str = event.request_context.http.method.to_string();
+    let path = event.request_context.http.path.clone().unwrap_or_else(|| "/".to_string());
+
+    // 許可されていないメソッドはルーティング前に405/501で拒否
+    let method = match check_method(&method_str) {
+        Ok(method) => method,
+        Err(res) => {
+            app.log_rejected(started, &method_str, &path, &res);
+            return Ok(convert_to_apigw_response(res));
+        }
+    };
+
+    // リクエストの変換（ボディの上限はルート固有 -> 全体設定）
+    let max_body_bytes = app.max_body_size_for(&path, &method);
+    let req = match convert_apigw_request(event, method, max_body_bytes) {
         Ok(req) => req,
         Err(e) => {
             error!("Request conversion error: {}", e);
             let error_response = Response::from_error(&e);
+            app.log_rejected(started, &method_str, &path, &error_response);
             return Ok(convert_to_apigw_response(error_response));
         }
     };
     info!("Received request: {} {}", req.method, req.path);
 
-    // ハンドラーの検索
-    let handler = match app.find_handler(&req.path, &req.method) {
-        Some(handler) => handler,
-        None => {
-            error!("Route not found: {} {}", req.method, req.path);
-            let error_response = Response::not_found()
-                .with_body("Not Found".as_bytes().to_vec());
-            return Ok(convert_to_apigw_response(error_response));
-        }
-    };
+    // ルーティング・ミドルウェア・ハンドラーの実行（全ランタイム共通）
+    let mut res_processed = app.process_request(req).await.response;
 
-    // ミドルウェアの適用（リクエスト前処理）
-    let mut req_processed = req;
-    for middleware in app.middlewares() {
-        match middleware.pre_process(req_processed).await {
-            Ok(processed) => req_processed = processed,
-            Err(e) => {
-                error!("Middleware error: {}", e);
-                let status = e.status_code();
-                let error_response = Response::new(status)
-                    .with_body(format!("Error: {}", e).as_bytes().to_vec());
-                return Ok(convert_to_apigw_response(error_response));
-            }
-        }
+    // HEADリクエストはボディを除去し、GETと同じ`Content-Length`を返す
+    if method == Method::HEAD {
+        res_processed.strip_body_for_head();
     }
 
-    // ハンドラーの実行
-    let handler_result = handler.handle(req_processed).await;
-    
-    // レスポンスの処理
-    let response = match handler_result {
-        Ok(res) => res,
-        Err(e) => {
-            error!("Handler error: {}", e);
-            Response::from_error(&e)
-        }
-    };
-
-    // ミドルウェアの適用（レスポンス後処理）
-    let mut res_processed = response;
-    for middleware in app.middlewares() {
-        match middleware.post_process(res_processed).await {
-            Ok(processed) => res_processed = processed,
-            Err(e) => {
-                error!("Middleware error in post-processing: {}", e);
-                res_processed = Response::from_error(&e);
-            }
-        }
-    }
+    // Lambdaは逐次送信できないため、Server-Sent Eventsはまとめて返す
+    res_processed.buffer_event_stream().await;
 
     // レスポンスの変換と返却
     Ok(convert_to_apigw_response(res_processed))
@@ -250,6 +330,7 @@ async fn lambda_handler(
 /// アプリケーションをLambda関数として実行
 pub async fn run_lambda(app: RunBridge) -> Result<(), LambdaError> {
     info!("Starting Lambda handler");
+    app.launch()?;
     
     let app = std::sync::Arc::new(app);
 
@@ -265,4 +346,145 @@ pub async fn run_lambda(app: RunBridge) -> Result<(), LambdaError> {
     run(handler_func).await?;
     
     Ok(())
-} 
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::get_max_body_size;
+    use aws_lambda_events::event::apigw::{ApiGatewayV2httpRequestContext, ApiGatewayV2httpRequestContextHttpDescription};
+
+    #[test]
+    fn test_estimate_encoded_body_size() {
+        assert_eq!(estimate_encoded_body_size(b"hello", false), 5);
+        // 非UTF-8はBase64化されるため4/3倍（切り上げ）
+        assert_eq!(estimate_encoded_body_size(&[0xff, 0xfe, 0xfd], false), 4);
+        assert_eq!(estimate_encoded_body_size(&[0xff, 0xfe, 0xfd, 0xfc], false), 8);
+        // Content-Encoding付きのボディはUTF-8として解釈できてもBase64化される
+        assert_eq!(estimate_encoded_body_size(b"hello", true), 8);
+    }
+
+    #[test]
+    fn test_guard_response_size_passes_small_response() {
+        let res = Response::ok().with_body(b"small".to_vec());
+        let guarded = guard_response_size(res, DEFAULT_MAX_RESPONSE_SIZE);
+        assert_eq!(guarded.status, 200);
+        assert_eq!(guarded.body.as_deref(), Some(&b"small"[..]));
+    }
+
+    #[test]
+    fn test_guard_response_size_replaces_oversized_response() {
+        let res = Response::ok().with_body(vec![b'a'; 4096]);
+        let guarded = guard_response_size(res, 2048);
+        assert_eq!(guarded.status, 500);
+        assert_eq!(guarded.body.as_deref(), Response::error(500).body.as_deref());
+    }
+
+    #[test]
+    fn test_encoded_body_is_base64() {
+        let res = convert_to_apigw_response(Response::ok().with_body(b"plain".to_vec()));
+        assert!(!res.is_base64_encoded);
+
+        // Content-Encoding付きのボディはUTF-8として解釈できてもBase64で返す
+        let res = Response::ok()
+            .with_header("Content-Encoding", "br")
+            .with_body(b"plain".to_vec());
+        let res = convert_to_apigw_response(res);
+        assert!(res.is_base64_encoded);
+    }
+
+    #[test]
+    fn test_precompressed_body_is_base64_encoded_once() {
+        // zstdのフレーム（マジックナンバー付きのバイナリ）をそのまま返す
+        let zstd = vec![0x28, 0xb5, 0x2f, 0xfd, 0x20, 0x05, 0x29, 0x00, 0x00, b'h', b'e', b'l', b'l', b'o'];
+        let res = Response::ok()
+            .with_header("Content-Type", "application/json")
+            .with_precompressed_body("zstd", zstd.clone());
+        let res = convert_to_apigw_response(res);
+        assert!(res.is_base64_encoded);
+        assert_eq!(res.headers.get("content-encoding").unwrap(), "zstd");
+        assert_eq!(res.headers.get("vary").unwrap(), "Accept-Encoding");
+        match res.body {
+            Some(Body::Text(encoded)) => assert_eq!(base64::decode(encoded).unwrap(), zstd),
+            other => panic!("unexpected body: {:?}", other),
+        }
+
+        // identityは圧縮されていないためテキストのまま返す
+        let res = Response::ok()
+            .with_header("Content-Encoding", "identity")
+            .with_body(b"plain".to_vec());
+        let res = convert_to_apigw_response(res);
+        assert!(!res.is_base64_encoded);
+        assert!(matches!(res.body, Some(Body::Text(ref text)) if text == "plain"));
+
+        // 上限の見積もりもBase64化後のサイズで行う
+        let res = Response::ok().with_precompressed_body("gzip", vec![b'a'; 1800]);
+        assert_eq!(guard_response_size(res, 3200).status, 500);
+    }
+
+    #[test]
+    fn test_set_cookie_is_returned_in_cookies() {
+        let res = Response::ok()
+            .with_header("Set-Cookie", "a=1; Path=/, b=2; Expires=Wed, 21 Oct 2026 07:28:00 GMT; HttpOnly");
+        let res = convert_to_apigw_response(res);
+        assert_eq!(res.cookies, vec!["a=1; Path=/", "b=2; Expires=Wed, 21 Oct 2026 07:28:00 GMT; HttpOnly"]);
+        assert!(res.headers.get("set-cookie").is_none());
+    }
+
+    /// Function URLのイベント（API Gatewayを経由しないv2ペイロード）
+    fn function_url_event() -> ApiGatewayV2httpRequest {
+        let http = ApiGatewayV2httpRequestContextHttpDescription {
+            method: aws_lambda_events::http::Method::POST,
+            path: Some("/upload".to_string()),
+            ..Default::default()
+        };
+        ApiGatewayV2httpRequest {
+            version: Some("2.0".to_string()),
+            route_key: Some("$default".to_string()),
+            raw_path: Some("/upload".to_string()),
+            raw_query_string: Some("tag=a&tag=b&q=hello%20world".to_string()),
+            cookies: Some(vec!["session=abc".to_string(), "theme=dark".to_string()]),
+            request_context: ApiGatewayV2httpRequestContext {
+                domain_name: Some("abc123.lambda-url.ap-northeast-1.on.aws".to_string()),
+                http,
+                ..Default::default()
+            },
+            body: Some(base64::encode([0xffu8, 0x00, 0x01])),
+            is_base64_encoded: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_function_url_request() {
+        let req = convert_apigw_request(function_url_event(), Method::POST, get_max_body_size()).unwrap();
+        assert_eq!(req.path, "/upload");
+        // 生のクエリ文字列を解析する（同名のキーは他のランタイムと同じく後勝ち）
+        assert_eq!(req.query_params.get("tag").map(String::as_str), Some("b"));
+        assert_eq!(req.query_params.get("q").map(String::as_str), Some("hello world"));
+        // cookies配列はCookieヘッダーに戻す
+        let cookies = req.cookies();
+        assert_eq!(cookies.get("session"), Some("abc"));
+        assert_eq!(cookies.get("theme"), Some("dark"));
+        assert_eq!(req.body.as_deref(), Some(&[0xffu8, 0x00, 0x01][..]));
+
+        // 不正なBase64は400
+        let mut event = function_url_event();
+        event.body = Some("***".to_string());
+        assert_eq!(convert_apigw_request(event, Method::POST, get_max_body_size()).unwrap_err().status_code(), 400);
+    }
+
+    #[test]
+    fn test_function_url_query_limits() {
+        temp_env::with_var("RUNBRIDGE_MAX_QUERY_PARAMS", Some("2"), || {
+            let err = convert_apigw_request(function_url_event(), Method::POST, get_max_body_size()).unwrap_err();
+            assert_eq!(err.status_code(), 400);
+
+            // 生のクエリ文字列が無い場合は解析済みのパラメータを使用
+            let mut event = function_url_event();
+            event.raw_query_string = None;
+            let req = convert_apigw_request(event, Method::POST, get_max_body_size()).unwrap();
+            assert!(req.query_params.is_empty());
+        });
+    }
+}
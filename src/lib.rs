@@ -57,6 +57,8 @@ const _: () = {
 pub mod common;
 pub mod error;
 pub mod handler;
+pub mod testing;
+mod serve;
 
 #[cfg(feature = "lambda")]
 pub mod lambda;
@@ -67,14 +69,47 @@ pub mod cloudrun;
 #[cfg(feature = "cgi")]
 pub mod cgi;
 
+#[cfg(feature = "tower")]
+pub mod tower;
+
+#[cfg(feature = "cli")]
+pub mod cli;
+
+#[cfg(feature = "proptest")]
+pub mod fuzz;
+
 pub use common::*;
 pub use error::*;
 pub use handler::*;
+pub use serve::{serve, ServeError};
+#[cfg(feature = "cloud_run")]
+pub use serve::get_bind_address;
+
+/// 実行環境の終了時に実行する処理
+type ShutdownHook = Box<
+    dyn Fn() -> std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send>> + Send + Sync,
+>;
 
 /// リクエストを処理するアプリケーションを構築するためのビルダー
 pub struct RunBridgeBuilder {
     handlers: Vec<Box<dyn common::Handler>>,
     middlewares: Vec<Box<dyn common::Middleware>>,
+    around: Vec<Box<dyn common::AroundMiddleware>>,
+    middleware_names: Vec<&'static str>,
+    around_names: Vec<&'static str>,
+    prewarm: Vec<Box<dyn Fn() + Send + Sync>>,
+    shutdown_hooks: Vec<ShutdownHook>,
+    config_endpoint: Option<handler::ConfigEndpoint>,
+    openapi_endpoint: Option<handler::OpenApiEndpoint>,
+    openapi_info: common::OpenApiInfo,
+    app_data: common::AppData,
+    security_profile: Option<common::SecurityProfile>,
+    body_field_policy: Option<common::BodyFieldPolicy>,
+    access_log: Option<common::AccessLogMiddleware>,
+    path_normalization: common::PathNormalization,
+    compiled_router: bool,
+    strict_config: bool,
+    panic_on_invalid_patterns: bool,
 }
 
 impl Default for RunBridgeBuilder {
@@ -82,6 +117,22 @@ impl Default for RunBridgeBuilder {
         Self {
             handlers: Vec::new(),
             middlewares: Vec::new(),
+            around: Vec::new(),
+            middleware_names: Vec::new(),
+            around_names: Vec::new(),
+            prewarm: Vec::new(),
+            shutdown_hooks: Vec::new(),
+            config_endpoint: None,
+            openapi_endpoint: None,
+            openapi_info: common::OpenApiInfo::default(),
+            app_data: common::AppData::default(),
+            security_profile: None,
+            body_field_policy: None,
+            access_log: None,
+            path_normalization: common::PathNormalization::default(),
+            compiled_router: false,
+            strict_config: common::config_report::is_strict_config(),
+            panic_on_invalid_patterns: false,
         }
     }
 }
@@ -97,7 +148,39 @@ impl RunBridgeBuilder {
     where 
         H: common::Handler + 'static
     {
-        self.handlers.push(Box::new(handler));
+        self.push_handlers(vec![Box::new(handler)]);
+        self
+    }
+
+    /// 共通のプレフィックスの下にハンドラーをまとめて追加（例: `scope("/api/v1", |g| g.handler(...))`）
+    pub fn scope<F>(self, prefix: impl Into<String>, configure: F) -> Self
+    where
+        F: FnOnce(handler::RouterGroup) -> handler::RouterGroup,
+    {
+        self.group(configure(handler::RouterGroup::new(prefix)))
+    }
+
+    /// 共有する状態を受け取るルートをまとめて追加（例: `with_state(services, |r| r.get("^/items$", list))`）
+    ///
+    /// 状態は`Arc`に包んで各ハンドラーの第1引数に渡されるため、ルートごとにcloneして捕捉する必要はありません。
+    pub fn with_state<S, F>(mut self, state: impl Into<std::sync::Arc<S>>, configure: F) -> Self
+    where
+        S: Send + Sync + 'static,
+        F: FnOnce(handler::StatefulRoutes<S>) -> handler::StatefulRoutes<S>,
+    {
+        let routes = configure(handler::StatefulRoutes::new(state.into()));
+        self.push_handlers(routes.into_handlers());
+        self
+    }
+
+    /// 作成済みのルートグループを追加
+    pub fn group(mut self, group: handler::RouterGroup) -> Self {
+        self.push_handlers(group.into_handlers());
+        self
+    }
+
+    fn push_handlers(&mut self, handlers: Vec<Box<dyn common::Handler>>) {
+        self.handlers.extend(handlers);
         // ハンドラーを追加するたびにパスの `/` の数で降順ソート
         self.handlers.sort_unstable_by(|a, b| {
             let count_a = a.path_pattern().matches('/').count();
@@ -105,6 +188,48 @@ impl RunBridgeBuilder {
             // 降順ソート (多い方が先)
             count_b.cmp(&count_a)
         });
+    }
+
+    /// 生存確認（`path`）と準備完了確認（`{path}/ready`）のエンドポイントを登録
+    ///
+    /// バージョンと起動からの経過秒数をJSONで返します。probeを追加する場合は`health_check`を使用します。
+    pub fn with_health_check(self, path: impl Into<String>) -> Self {
+        self.health_check(handler::HealthCheck::new(path))
+    }
+
+    /// 設定した生存確認・準備完了確認のエンドポイントを登録
+    pub fn health_check(self, check: handler::HealthCheck) -> Self {
+        let (liveness, readiness) = check.into_handlers();
+        self.handler(liveness).handler(readiness)
+    }
+
+    /// 非同期ジョブの登録（`POST {path}`）と状態確認（`GET {path}/{id}`）のエンドポイントを登録
+    pub fn jobs(self, endpoints: handler::JobEndpoints) -> Self {
+        let (submit, status) = endpoints.into_handlers();
+        self.handler(submit).handler(status)
+    }
+
+    /// 適用中の設定を返す認証付きのデバッグエンドポイント（既定 `/__runbridge/config`）を登録
+    ///
+    /// トークンは`ConfigEndpoint::token`または環境変数 `RUNBRIDGE_CONFIG_TOKEN` で指定します。
+    /// 未設定の場合はエンドポイントを公開しません。
+    pub fn config_endpoint(mut self, endpoint: handler::ConfigEndpoint) -> Self {
+        self.config_endpoint = Some(endpoint.clone());
+        self.handler(endpoint)
+    }
+
+    /// 登録済みのルートから生成したOpenAPIドキュメントを返すエンドポイント（既定 `/openapi.json`）を登録
+    ///
+    /// ドキュメントには`HandlerExt::openapi`で付与した概要・スキーマが含まれます。
+    pub fn openapi(mut self, endpoint: handler::OpenApiEndpoint) -> Self {
+        self.openapi_info = endpoint.info().clone();
+        self.openapi_endpoint = Some(endpoint);
+        self
+    }
+
+    /// エンドポイントを登録せずに`RunBridge::openapi_spec`のドキュメントの情報を設定
+    pub fn openapi_info(mut self, info: common::OpenApiInfo) -> Self {
+        self.openapi_info = info;
         self
     }
 
@@ -114,22 +239,175 @@ impl RunBridgeBuilder {
         M: common::Middleware + 'static
     {
         self.middlewares.push(Box::new(middleware));
+        self.middleware_names.push(std::any::type_name::<M>());
+        self
+    }
+
+    /// ハンドラー呼び出しを包むミドルウェアを追加（先に追加したものが外側）
+    pub fn around<M>(mut self, middleware: M) -> Self
+    where
+        M: common::AroundMiddleware + 'static
+    {
+        self.around.push(Box::new(middleware));
+        self.around_names.push(std::any::type_name::<M>());
+        self
+    }
+
+    /// 実行環境の起動時（コールドスタート時）にバックグラウンドで初期化を開始する値を登録
+    pub fn prewarm<T: Send + Sync + 'static>(mut self, lazy: &'static common::AsyncLazy<T>) -> Self {
+        self.prewarm.push(Box::new(move || lazy.prewarm()));
+        self
+    }
+
+    /// アプリ全体のセキュリティヘッダーのプロファイルを設定（グループの設定が優先）
+    ///
+    /// 未設定の場合は`Response::new`などが注入する汎用の既定ヘッダーをそのまま返します。
+    pub fn security_profile(mut self, profile: common::SecurityProfile) -> Self {
+        self.security_profile = Some(profile);
+        self
+    }
+
+    /// 型付きボディ（JSON）の未知のフィールド・フィールド名の照合の方針をアプリ全体に設定（ルートの設定が優先）
+    ///
+    /// 例えば`BodyFieldPolicy::strict()`で、全ルートの型付きボディに含まれる未知のフィールドを400で拒否します。
+    pub fn body_field_policy(mut self, policy: common::BodyFieldPolicy) -> Self {
+        self.body_field_policy = Some(policy);
+        self
+    }
+
+    /// 全てのレスポンスのアクセスログを出力するロガーを設定
+    ///
+    /// ハンドラーの結果に加えて、ルートが無い場合の404、ミドルウェアの前処理での拒否、
+    /// ルーティング前に各ランタイムが拒否したリクエスト（405・413など）も記録します。
+    pub fn access_log(mut self, logger: common::AccessLogMiddleware) -> Self {
+        self.access_log = Some(logger);
+        self
+    }
+
+    /// ルーティング前のリクエストパスの正規化を設定（既定では正規化しない）
+    ///
+    /// 例えば`PathNormalization::new().trim_trailing_slash()`で`/hello/`が`^/hello$`にマッチします。
+    /// ハンドラー・ミドルウェアには正規化後のパスが渡されます。
+    pub fn path_normalization(mut self, normalization: common::PathNormalization) -> Self {
+        self.path_normalization = normalization;
+        self
+    }
+
+    /// `build()`時にルートの静的なセグメントからルーターを構築し、照合するハンドラーを絞り込む（既定 無効）
+    ///
+    /// ルートが多い場合に、リクエストごとの正規表現の照合回数を減らします。照合は登録順のままで、
+    /// マッチするハンドラーは変わりません。
+    pub fn compiled_router(mut self, enabled: bool) -> Self {
+        self.compiled_router = enabled;
+        self
+    }
+
+    /// 全てのハンドラー・ミドルウェアで共有する値を登録（型ごとに1つ、同じ型は置き換え）
+    ///
+    /// DBプールや設定など、クロージャで`Arc`を捕捉する代わりに使用します。
+    /// 参照は`req.data::<T>()`または`common::Data::<T>::from_request(&req)`で行います。
+    pub fn app_data<T: Send + Sync + 'static>(mut self, value: T) -> Self {
+        self.app_data.insert(value);
+        self
+    }
+
+    /// 実行環境の終了時に実行する処理を登録（キャッシュのフラッシュやDBプールのクローズ等）
+    ///
+    /// Cloud RunではSIGTERMを受けて処理中のリクエストを完了させた後、CGIではレスポンスの出力後に、
+    /// 登録順に実行されます。全体で`RUNBRIDGE_SHUTDOWN_TIMEOUT_SECS`（デフォルト 5秒）を超えた場合は
+    /// 残りを打ち切ります。Lambdaには終了の通知が無いため実行されません。
+    pub fn on_shutdown<F, Fut>(mut self, hook: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = ()> + Send + 'static,
+    {
+        self.shutdown_hooks.push(Box::new(move || Box::pin(hook())));
+        self
+    }
+
+    /// 設定検証でエラーがある場合に起動を拒否するかどうか
+    /// 優先順位: このメソッド -> 環境変数 `RUNBRIDGE_STRICT_CONFIG` -> デフォルト 無効
+    pub fn strict_config(mut self, strict: bool) -> Self {
+        self.strict_config = strict;
+        self
+    }
+
+    /// 不正なルートパターンがある場合に起動時にパニックするかどうか（デバッグビルドのみ、デフォルト無効）
+    ///
+    /// リリースビルドでは無視され、不正なパターンはログ出力と設定レポートのエラーになります。
+    pub fn panic_on_invalid_patterns(mut self, enabled: bool) -> Self {
+        self.panic_on_invalid_patterns = enabled;
         self
     }
 
     /// アプリケーションをビルドして返却
-    pub fn build(self) -> RunBridge {
-        RunBridge {
+    pub fn build(mut self) -> RunBridge {
+        if let Some(endpoint) = self.openapi_endpoint.take() {
+            let spec = self.openapi_info.document(common::openapi::documented_routes(&self.handlers));
+            match endpoint.into_handler(&spec) {
+                Ok(handler) => self.push_handlers(vec![Box::new(handler)]),
+                Err(e) => log::error!("Failed to register OpenAPI endpoint: {}", e),
+            }
+        }
+        let routes = common::RouteTable::from_handlers(self.handlers.iter().map(|h| h.as_ref()));
+        let router = self
+            .compiled_router
+            .then(|| common::CompiledRouter::build(self.handlers.iter().map(|h| h.as_ref())));
+        let app = RunBridge {
             handlers: self.handlers,
             middlewares: self.middlewares,
+            around: self.around,
+            middleware_names: self.middleware_names,
+            around_names: self.around_names,
+            prewarm: self.prewarm,
+            shutdown_hooks: self.shutdown_hooks,
+            shutdown_started: std::sync::atomic::AtomicBool::new(false),
+            app_data: self.app_data,
+            security_profile: self.security_profile,
+            body_field_policy: self.body_field_policy,
+            access_log: self.access_log,
+            openapi_info: self.openapi_info,
+            path_normalization: self.path_normalization,
+            router,
+            routes: std::sync::Arc::new(routes),
+            strict_config: self.strict_config,
+            panic_on_invalid_patterns: self.panic_on_invalid_patterns,
+            #[cfg(debug_assertions)]
+            launched: std::sync::atomic::AtomicBool::new(false),
+        };
+        if let Some(endpoint) = &self.config_endpoint {
+            endpoint.publish(app.config_report().to_json());
         }
+        app
     }
 }
 
 /// リクエストを処理するアプリケーション
+///
+/// デバッグビルドでは、実行環境のエントリポイント（`serve`・`run_*`・`into_service`）に
+/// 渡されないまま破棄された場合に警告を出力します（`let _app = ...`のような設定漏れの検出用）。
 pub struct RunBridge {
     handlers: Vec<Box<dyn common::Handler>>,
     middlewares: Vec<Box<dyn common::Middleware>>,
+    around: Vec<Box<dyn common::AroundMiddleware>>,
+    middleware_names: Vec<&'static str>,
+    around_names: Vec<&'static str>,
+    prewarm: Vec<Box<dyn Fn() + Send + Sync>>,
+    shutdown_hooks: Vec<ShutdownHook>,
+    shutdown_started: std::sync::atomic::AtomicBool,
+    app_data: common::AppData,
+    security_profile: Option<common::SecurityProfile>,
+    body_field_policy: Option<common::BodyFieldPolicy>,
+    access_log: Option<common::AccessLogMiddleware>,
+    openapi_info: common::OpenApiInfo,
+    path_normalization: common::PathNormalization,
+    router: Option<common::CompiledRouter>,
+    routes: std::sync::Arc<common::RouteTable>,
+    strict_config: bool,
+    #[cfg_attr(not(debug_assertions), allow(dead_code))]
+    panic_on_invalid_patterns: bool,
+    #[cfg(debug_assertions)]
+    launched: std::sync::atomic::AtomicBool,
 }
 
 impl RunBridge {
@@ -139,12 +417,387 @@ impl RunBridge {
     }
 
     /// 指定されたパスにマッチするハンドラを取得
+    ///
+    /// HEADリクエストにマッチするルートがない場合は、同じパスのGETルートを返します。
+    /// 各ランタイムはHEADリクエストのレスポンスからボディを除去し、`Content-Length`はGETと同じ値を返します。
     pub fn find_handler(&self, path: &str, method: &common::Method) -> Option<&Box<dyn common::Handler>> {
-        self.handlers.iter().find(|handler| handler.matches(path, method))
+        let index = self.find_handler_index(path, method).or_else(|| match method {
+            common::Method::HEAD => self.find_handler_index(path, &common::Method::GET),
+            _ => None,
+        })?;
+        Some(&self.handlers[index])
+    }
+
+    /// メソッドが完全に一致するハンドラのインデックスを取得
+    fn find_handler_index(&self, path: &str, method: &common::Method) -> Option<usize> {
+        match &self.router {
+            Some(router) => router
+                .candidates(path)
+                .into_iter()
+                .find(|&index| self.handlers[index].matches(path, method)),
+            None => self.handlers.iter().position(|handler| handler.matches(path, method)),
+        }
+    }
+
+    /// 設定に従ってリクエストパスを正規化（各ランタイムでハンドラーの検索前に使用）
+    pub fn normalize_path(&self, req: &mut common::Request) {
+        if self.path_normalization.is_enabled() {
+            req.path = self.path_normalization.normalize(&req.path);
+        }
+    }
+
+    /// パスとメソッドに一致するルートのリクエストボディの上限（ルート固有 -> 全体設定）
+    ///
+    /// 各ランタイムがボディの受信時に使用します。パスは設定に従って正規化してから照合します。
+    pub fn max_body_size_for(&self, path: &str, method: &common::Method) -> usize {
+        let normalized;
+        let path = if self.path_normalization.is_enabled() {
+            normalized = self.path_normalization.normalize(path);
+            normalized.as_str()
+        } else {
+            path
+        };
+        self.find_handler(path, method)
+            .and_then(|handler| handler.body_size_limit())
+            .unwrap_or_else(common::get_max_body_size)
+    }
+
+    /// 登録済みのルートの一覧（照合順）
+    pub fn routes(&self) -> Vec<common::RouteInfo> {
+        self.handlers.iter().map(|h| common::RouteInfo::from_handler(h.as_ref())).collect()
+    }
+
+    /// 登録済みのルートと`HandlerExt::openapi`のメタデータから生成したOpenAPI 3.0ドキュメント
+    ///
+    /// `info`は`RunBridgeBuilder::openapi`・`openapi_info`で設定したものです（未設定の場合は既定値）。
+    pub fn openapi_spec(&self) -> serde_json::Value {
+        self.openapi_info.document(common::openapi::documented_routes(&self.handlers))
+    }
+
+    /// 全ルートのパターンを検証し、正規表現としてコンパイルできないものをすべて返す
+    ///
+    /// 不正なパターンのルートは照合時に常に不一致（fail-closed）になるため、テストで
+    /// `assert!(app.validate().is_ok())`のように確認してください。起動時にも同じ検証が行われます。
+    pub fn validate(&self) -> Result<(), Vec<common::PatternError>> {
+        let errors: Vec<_> = self
+            .handlers
+            .iter()
+            .filter_map(|h| common::PatternError::check(h.as_ref()))
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// `RunBridgeBuilder::app_data`で登録した値を取得
+    pub fn data<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.app_data.get::<T>()
     }
 
     /// ミドルウェアのリストを取得
     pub fn middlewares(&self) -> &[Box<dyn common::Middleware>] {
         &self.middlewares
     }
+
+    /// AroundMiddlewareを適用してハンドラーを実行（各ランタイムで使用）
+    ///
+    /// 実行前にルート固有のボディの上限（`HandlerExt::max_body_size`）を検査し、
+    /// GET/HEAD/DELETEのボディに対する方針（ルート固有 -> 全体設定）を適用します。
+    /// 型付きボディのフィールドの扱い（ルート固有 -> 全体設定）はリクエストコンテキストで渡します。
+    pub async fn run_handler(
+        &self,
+        handler: &dyn common::Handler,
+        mut req: common::Request,
+    ) -> Result<common::Response, error::Error> {
+        if let Some(limit) = handler.body_size_limit() {
+            let length = req.body.as_ref().map_or(0, |body| body.len());
+            if length > limit {
+                return Err(error::Error::PayloadTooLarge(format!(
+                    "Request body of {} bytes exceeds the route limit of {} bytes",
+                    length, limit
+                )));
+            }
+        }
+        handler
+            .body_policy()
+            .unwrap_or_else(common::get_body_policy)
+            .apply(&mut req)?;
+        if let Some(policy) = handler.body_field_policy().or(self.body_field_policy) {
+            req.context_mut().set(common::body_fields::BODY_FIELD_POLICY_CONTEXT_KEY, policy);
+        }
+        common::Next::new(handler, &self.around).run(req).await
+    }
+
+    /// 変換済みのリクエストをルーティング・ミドルウェア・ハンドラーに通して処理（全ランタイム共通）
+    ///
+    /// 順序: パスの正規化 → ルートの検索（無い場合は404）→ ルート情報の格納 → ミドルウェアの前処理 →
+    /// ハンドラー → ミドルウェアの後処理 → セキュリティヘッダー → 予約ヘッダーの除去。
+    /// HEADのボディの除去とServer-Sent Eventsの扱いは出力形式に依存するため、各ランタイムで行います。
+    /// アクセスログが設定されている場合は、404・ミドルウェアでの拒否を含めて全ての結果を記録します。
+    pub(crate) async fn process_request(&self, request: common::Request) -> ProcessedResponse {
+        let Some(logger) = &self.access_log else {
+            return self.route_request(request).await;
+        };
+        let start = common::access_log::AccessLogStart::new(&request);
+        let processed = self.route_request(request).await;
+        let response = &processed.response;
+        logger.record(
+            &start,
+            processed.route_label.clone(),
+            response.status,
+            response.body.as_ref().map_or(0, Vec::len),
+        );
+        processed
+    }
+
+    /// ルーティング前に各ランタイムが拒否したリクエストのアクセスログを記録（ボディは受信していないものとする）
+    #[cfg_attr(
+        not(any(feature = "lambda", feature = "cloud_run", feature = "cgi", feature = "tower")),
+        allow(dead_code)
+    )]
+    pub(crate) fn log_rejected(&self, started: std::time::Instant, method: &str, path: &str, response: &common::Response) {
+        if let Some(logger) = &self.access_log {
+            let start = common::access_log::AccessLogStart {
+                started,
+                method: method.to_string(),
+                path: path.to_string(),
+                request_bytes: 0,
+            };
+            logger.record(&start, None, response.status, response.body.as_ref().map_or(0, Vec::len));
+        }
+    }
+
+    async fn route_request(&self, mut request: common::Request) -> ProcessedResponse {
+        // 設定に従ってパスを正規化（末尾のスラッシュ等）
+        self.normalize_path(&mut request);
+
+        // ハンドラーの検索
+        let handler = match self.find_handler(&request.path, &request.method) {
+            Some(handler) => handler,
+            None => {
+                log::error!("Route not found: {} {}", request.method, request.path);
+                return ProcessedResponse { response: common::Response::error(404), route: None, route_label: None };
+            }
+        };
+        let route = Some(handler.path_pattern().to_string());
+        let route_label = Some(handler.route_name().unwrap_or(handler.path_pattern()).to_string());
+
+        // マッチしたルート情報とルートテーブルをハンドラー/ミドルウェアから参照できるようにする
+        let mut req_processed = request;
+        self.attach_route_context(handler.as_ref(), &mut req_processed);
+
+        // ミドルウェアの適用（リクエスト前処理）
+        for middleware in self.middlewares() {
+            match middleware.pre_process(req_processed).await {
+                Ok(processed) => req_processed = processed,
+                Err(e) => {
+                    log::error!("Middleware error: {}", e);
+                    return ProcessedResponse { response: common::Response::from_error(&e), route, route_label };
+                }
+            }
+        }
+
+        // ハンドラーの実行
+        let mut response = match self.run_handler(handler.as_ref(), req_processed).await {
+            Ok(res) => res,
+            Err(e) => {
+                log::error!("Handler error: {}", e);
+                common::Response::from_error(&e)
+            }
+        };
+
+        // ミドルウェアの適用（レスポンス後処理）
+        for middleware in self.middlewares() {
+            match middleware.post_process(response).await {
+                Ok(processed) => response = processed,
+                Err(e) => {
+                    log::error!("Middleware error in post-processing: {}", e);
+                    response = common::Response::from_error(&e);
+                }
+            }
+        }
+
+        // ルート・アプリ全体のセキュリティヘッダーのプロファイルを適用
+        self.apply_security_profile(handler.as_ref(), &mut response);
+
+        // 予約ヘッダーはランタイム側で管理するため除去
+        response.remove_reserved_headers(handler.path_pattern());
+
+        ProcessedResponse { response, route, route_label }
+    }
+
+    /// ルート（グループ）またはアプリ全体のセキュリティヘッダーのプロファイルを適用（各ランタイムで使用）
+    pub fn apply_security_profile(&self, handler: &dyn common::Handler, res: &mut common::Response) {
+        if let Some(profile) = handler.security_profile().or(self.security_profile) {
+            profile.apply(res);
+        }
+    }
+
+    /// ボディの受信前（`Expect: 100-continue`）にリクエストを検査し、拒否する場合はレスポンスを返す
+    ///
+    /// `req`はボディを含まないリクエストです。宣言された`Content-Length`が上限（ルート固有 -> 全体設定）を
+    /// 超える場合は413、ルートが無い場合は404、ルートの`Handler::check_pre_body`がErrの場合はそのエラーの
+    /// レスポンスを返します。
+    pub fn check_before_body(&self, req: &mut common::Request) -> Result<(), common::Response> {
+        self.normalize_path(req);
+        let handler = self.find_handler(&req.path, &req.method);
+
+        let limit = handler
+            .and_then(|handler| handler.body_size_limit())
+            .unwrap_or_else(common::get_max_body_size);
+        let declared = req.headers.get("content-length").and_then(|v| v.trim().parse::<usize>().ok());
+        if declared.is_some_and(|length| length > limit) {
+            log::warn!("Rejected request body before upload: declared {:?} bytes (limit {})", declared, limit);
+            return Err(common::Response::error(413));
+        }
+
+        let handler = match handler {
+            Some(handler) => handler,
+            None => return Err(common::Response::error(404)),
+        };
+        handler.check_pre_body(req).map_err(|e| {
+            log::debug!("Pre-body check rejected {} {}: {}", req.method, req.path, e);
+            common::Response::from_error(&e)
+        })
+    }
+
+    /// ルート名とパスパラメータからURLパスを生成（例: `url_for("get_item", &[("id", "42")])`）
+    pub fn url_for(&self, name: &str, params: &[(&str, &str)]) -> Result<String, error::Error> {
+        self.routes.url_for(name, params)
+    }
+
+    /// 適用される設定と検証結果のレポートを作成
+    pub fn config_report(&self) -> common::ConfigReport {
+        let mut report = common::ConfigReport::collect();
+        report.set("handlers", serde_json::json!(self.handlers.len()));
+        report.set("middlewares", serde_json::json!(self.middlewares.len()));
+        report.set("around_middlewares", serde_json::json!(self.around.len()));
+        report.set("middleware_names", serde_json::json!(self.middleware_names));
+        report.set("around_middleware_names", serde_json::json!(self.around_names));
+        report.set("app_data", serde_json::json!(self.app_data.len()));
+        report.set("security_profile", serde_json::json!(self.security_profile.map(|p| format!("{:?}", p))));
+        report.set("body_field_policy", serde_json::json!(self.body_field_policy.map(|p| format!("{:?}", p))));
+        report.set("access_log", serde_json::json!(self.access_log.is_some()));
+        report.set("path_normalization", serde_json::json!(format!("{:?}", self.path_normalization)));
+        report.set(
+            "compiled_router",
+            serde_json::json!(self.router.as_ref().map(|r| serde_json::json!({ "fallback_routes": r.fallback_len() }))),
+        );
+        report.set("strict_config", serde_json::json!(self.strict_config));
+        if self.handlers.is_empty() {
+            report.warning("handlers", "no handlers registered; every request returns 404".to_string());
+        }
+        if let Err(errors) = self.validate() {
+            for e in errors {
+                report.error("routes", e.to_string());
+            }
+        }
+        report
+    }
+
+    /// 起動時の処理（各ランタイムのエントリポイントで使用）
+    ///
+    /// 設定レポートを1回だけログ出力し、strictモードでエラーがある場合は`ConfigurationError`を返します。
+    #[cfg_attr(
+        not(any(feature = "lambda", feature = "cloud_run", feature = "cgi", feature = "tower")),
+        allow(dead_code)
+    )]
+    pub(crate) fn launch(&self) -> Result<(), error::Error> {
+        self.mark_launched();
+
+        let report = self.config_report();
+        report.log_once();
+
+        // 不正なパターンのルートは照合時に常に不一致になるため、起動時にまとめて出力する
+        if let Err(errors) = self.validate() {
+            for e in &errors {
+                log::error!("Route will never match: {}", e);
+            }
+            #[cfg(debug_assertions)]
+            if self.panic_on_invalid_patterns {
+                let errors = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("; ");
+                panic!("Invalid route patterns: {}", errors);
+            }
+        }
+        if self.strict_config && report.has_errors() {
+            let errors = report
+                .issues()
+                .iter()
+                .filter(|i| i.severity == common::ConfigSeverity::Error)
+                .map(|i| format!("{}: {}", i.key, i.message))
+                .collect::<Vec<_>>()
+                .join("; ");
+            return Err(error::Error::ConfigurationError(format!(
+                "Refusing to start with invalid configuration: {}",
+                errors
+            )));
+        }
+
+        for prewarm in &self.prewarm {
+            prewarm();
+        }
+        Ok(())
+    }
+
+    /// エントリポイント（各ランタイム・tower・`testing::dispatch`）に渡されたことを記録
+    ///
+    /// 記録されないまま破棄された場合、デバッグビルドでは`serve`の呼び忘れとして警告します。
+    pub(crate) fn mark_launched(&self) {
+        #[cfg(debug_assertions)]
+        self.launched.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// `on_shutdown`で登録した処理を実行（各ランタイムの終了時に使用、2回目以降は何もしない）
+    ///
+    /// 独自のサーバーに組み込む場合は、サーバーの停止後に呼び出してください。
+    pub async fn shutdown(&self) {
+        if self.shutdown_started.swap(true, std::sync::atomic::Ordering::SeqCst) || self.shutdown_hooks.is_empty() {
+            return;
+        }
+        let timeout = common::utils::get_shutdown_timeout();
+        log::info!("Running {} shutdown hook(s)", self.shutdown_hooks.len());
+        let hooks = async {
+            for hook in &self.shutdown_hooks {
+                hook().await;
+            }
+        };
+        if tokio::time::timeout(timeout, hooks).await.is_err() {
+            log::warn!("Shutdown hooks did not finish within {:?}; remaining hooks were skipped", timeout);
+        }
+    }
+
+    /// マッチしたルート情報・ルートテーブル・共有データをリクエストに格納（各ランタイムで使用）
+    pub fn attach_route_context(&self, handler: &dyn common::Handler, req: &mut common::Request) {
+        req.set_matched_route(handler);
+        req.context_mut()
+            .set(common::route::ROUTE_TABLE_CONTEXT_KEY, self.routes.clone());
+        req.context_mut()
+            .set(common::app_data::APP_DATA_CONTEXT_KEY, self.app_data.clone());
+    }
 } 
+
+/// `RunBridge::process_request`の結果
+pub(crate) struct ProcessedResponse {
+    /// レスポンス（予約ヘッダーは除去済み）
+    pub(crate) response: common::Response,
+    /// マッチしたルートのパターン（ルートが無く404を返した場合はNone）
+    #[cfg_attr(not(feature = "cloud_run"), allow(dead_code))]
+    pub(crate) route: Option<String>,
+    /// マッチしたルートのルート名（無い場合はパターン、アクセスログ用）
+    pub(crate) route_label: Option<String>,
+}
+
+#[cfg(debug_assertions)]
+impl Drop for RunBridge {
+    fn drop(&mut self) {
+        if self.launched.load(std::sync::atomic::Ordering::Relaxed) || std::thread::panicking() {
+            return;
+        }
+        log::warn!(
+            "RunBridge app with {} handler(s) was dropped without being passed to a runtime entry point. Did you forget to call runbridge::serve(app)?",
+            self.handlers.len()
+        );
+    }
+}
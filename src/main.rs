@@ -1,7 +1,6 @@
 use env_logger;
 use log::info;
 use serde::{Serialize, Deserialize};
-use std::env;
 
 use runbridge::{RunBridge, common::Request, handler, error::Error};
 
@@ -58,52 +57,21 @@ fn create_item(_req: Request, item: Item) -> Result<Item, Error> {
 async fn main() {
     // ロガーの初期化
     env_logger::init();
+    // パニックを構造化ログとして記録
+    runbridge::install_panic_hook();
 
     info!("Starting RunBridge application");
 
-    // 環境に応じて実行方法を切り替え
-    #[cfg(feature = "lambda")]
-    {
-        info!("Running as AWS Lambda");
-        let app = RunBridge::builder()
-            .handler(handler::get("^/$", health_handler))
-            .handler(handler::get("^/items$", get_items))
-            .handler(handler::post("^/items$", create_item))
-            .build();
-            
-        if let Err(e) = runbridge::lambda::run_lambda(app).await {
-            eprintln!("Lambda error: {}", e);
-            std::process::exit(1);
-        }
-    }
-
-    #[cfg(feature = "cloud_run")]
-    {
-        let port = match env::var("PORT").unwrap_or_else(|_| "8080".to_string()).parse::<u16>() {
-            Ok(p) => p,
-            Err(e) => {
-                eprintln!("Error parsing port: {}", e);
-                std::process::exit(1);
-            }
-        };
-        let host = "0.0.0.0";
-        info!("Running as HTTP server on port {}", port);
-        
-        let app = RunBridge::builder()
-            .handler(handler::get("^/$", health_handler))
-            .handler(handler::get("^/items$", get_items))
-            .handler(handler::post("^/items$", create_item))
-            .build();
-            
-        if let Err(e) = runbridge::cloudrun::run_cloud_run(app, host, port).await {
-            eprintln!("Cloud Run error: {}", e);
-            std::process::exit(1);
-        }
-    }
+    let app = RunBridge::builder()
+        .handler(handler::get("^/$", health_handler))
+        .handler(handler::get("^/items$", get_items))
+        .handler(handler::post("^/items$", create_item))
+        .build();
 
-    #[cfg(not(any(feature = "lambda", feature = "cloud_run")))]
-    {
-        println!("Please enable either 'lambda' or 'cloud_run' feature to run the application.");
+    // 有効なfeatureに応じた実行環境で起動
+    if let Err(e) = runbridge::serve(app).await {
+        eprintln!("RunBridge error: {}", e);
+        #[cfg(not(any(feature = "lambda", feature = "cloud_run", feature = "cgi")))]
         println!("Example: cargo run --features cloud_run");
         std::process::exit(1);
     }
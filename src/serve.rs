@@ -0,0 +1,84 @@
+//! 有効なfeatureに応じて実行環境を選択する共通エントリポイント
+
+use crate::RunBridge;
+
+/// `serve`が返すエラー型（実行環境ごとのエラーをまとめて扱う）
+pub type ServeError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Cloud Runのバインド先ホストの既定値
+#[cfg(feature = "cloud_run")]
+const DEFAULT_HOST: &str = "0.0.0.0";
+
+/// Cloud Runのバインド先ポートの既定値
+#[cfg(feature = "cloud_run")]
+const DEFAULT_PORT: u16 = 8080;
+
+/// Cloud Runのバインド先を取得
+/// 優先順位: 環境変数 `HOST` / `PORT` -> デフォルト 0.0.0.0:8080
+#[cfg(feature = "cloud_run")]
+pub fn get_bind_address() -> Result<(String, u16), ServeError> {
+    let host = std::env::var("HOST")
+        .ok()
+        .filter(|h| !h.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_HOST.to_string());
+    let port = match std::env::var("PORT") {
+        Ok(value) => value
+            .trim()
+            .parse::<u16>()
+            .map_err(|e| format!("Invalid PORT value '{}': {}", value, e))?,
+        Err(_) => DEFAULT_PORT,
+    };
+    Ok((host, port))
+}
+
+/// 有効なfeatureに応じた実行環境でアプリケーションを起動
+///
+/// - `lambda`: Lambdaランタイムとして実行
+/// - `cloud_run`: HTTPサーバーとして実行（バインド先は`get_bind_address`）
+/// - `cgi`: 1リクエストをCGIとして処理
+///
+/// `allow_feature_conflicts`で複数有効な場合は lambda → cloud_run → cgi の順で優先します。
+pub async fn serve(app: RunBridge) -> Result<(), ServeError> {
+    #[cfg(feature = "lambda")]
+    {
+        crate::lambda::run_lambda(app).await
+    }
+
+    #[cfg(all(feature = "cloud_run", not(feature = "lambda")))]
+    {
+        let (host, port) = get_bind_address()?;
+        crate::cloudrun::run_cloud_run(app, &host, port).await?;
+        Ok(())
+    }
+
+    #[cfg(all(feature = "cgi", not(feature = "lambda"), not(feature = "cloud_run")))]
+    {
+        crate::cgi::run_cgi(app).await?;
+        Ok(())
+    }
+
+    #[cfg(not(any(feature = "lambda", feature = "cloud_run", feature = "cgi")))]
+    {
+        let _ = app;
+        Err("No target feature enabled. Enable one of: 'lambda', 'cloud_run', or 'cgi'.".into())
+    }
+}
+
+#[cfg(all(test, feature = "cloud_run"))]
+mod tests {
+    use super::*;
+    use temp_env::with_vars;
+
+    #[test]
+    fn test_get_bind_address() {
+        with_vars(vec![("HOST", None::<&str>), ("PORT", None)], || {
+            assert_eq!(get_bind_address().unwrap(), ("0.0.0.0".to_string(), 8080));
+        });
+        with_vars(vec![("HOST", Some("127.0.0.1")), ("PORT", Some("9000"))], || {
+            assert_eq!(get_bind_address().unwrap(), ("127.0.0.1".to_string(), 9000));
+        });
+        with_vars(vec![("PORT", Some("not-a-port"))], || {
+            assert!(get_bind_address().is_err());
+        });
+    }
+}
@@ -0,0 +1,160 @@
+//! テスト用ユーティリティ
+//!
+//! 実行環境を起動せずにアプリケーションへリクエストを送る`dispatch`と、
+//! `TrafficRecorder`で記録したトラフィックを再生する`replay`を提供します。
+//! 記録は伏せ字済みのため、認証ヘッダー等に依存するルートは再生時に値を補ってください。
+
+use std::path::Path;
+
+
+use crate::common::recording::RecordedExchange;
+use crate::common::{Method, Request, Response};
+use crate::error::Error;
+use crate::RunBridge;
+
+/// ルーティング・ミドルウェア・ハンドラーを通してリクエストを処理（実行環境のパイプラインと同じ順序）
+pub async fn dispatch(app: &RunBridge, request: Request) -> Response {
+    app.mark_launched();
+    let method = request.method;
+    let mut response = app.process_request(request).await.response;
+    if method == Method::HEAD {
+        response.strip_body_for_head();
+    }
+    response
+}
+
+/// JSON Lines形式の記録ファイルを読み込む（空行は無視）
+pub fn load_recordings(path: impl AsRef<Path>) -> Result<Vec<RecordedExchange>, Error> {
+    let content = std::fs::read_to_string(path.as_ref()).map_err(|e| {
+        Error::InternalServerError(format!("Failed to read {}: {}", path.as_ref().display(), e))
+    })?;
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(RecordedExchange::from_json_line)
+        .collect()
+}
+
+/// 再生結果
+#[derive(Debug)]
+pub struct ReplayOutcome {
+    /// 記録時のルート
+    pub route: Option<String>,
+    /// 記録されたレスポンス
+    pub expected: Response,
+    /// 再生して得られたレスポンス
+    pub actual: Response,
+}
+
+impl ReplayOutcome {
+    /// ステータスコードが記録と一致するか
+    pub fn status_matches(&self) -> bool {
+        self.expected.status == self.actual.status
+    }
+
+    /// ボディが記録と一致するか（記録からボディが除外されている場合は比較しない）
+    pub fn body_matches(&self) -> bool {
+        self.expected.body.is_none() || self.expected.body == self.actual.body
+    }
+}
+
+/// 記録されたリクエストを再生し、記録されたレスポンスと並べて返す
+pub async fn replay(app: &RunBridge, exchange: RecordedExchange) -> ReplayOutcome {
+    let actual = dispatch(app, exchange.request).await;
+    ReplayOutcome {
+        route: exchange.route,
+        expected: exchange.response,
+        actual,
+    }
+}
+
+/// ボディを解析せずにリクエストを関数へ渡すハンドラー（クレート内のテスト用、全メソッドに一致）
+///
+/// `handler::get`等はボディを型付きで解析するため、生のボディを扱うテストで使用します。
+#[cfg(test)]
+pub(crate) struct RawHandler<F> {
+    pattern: regex::Regex,
+    handler_fn: F,
+}
+
+#[cfg(test)]
+pub(crate) fn raw_handler<F>(pattern: &str, handler_fn: F) -> RawHandler<F>
+where
+    F: Fn(Request) -> Result<Response, Error> + Send + Sync + 'static,
+{
+    RawHandler { pattern: regex::Regex::new(pattern).unwrap(), handler_fn }
+}
+
+#[cfg(test)]
+#[async_trait::async_trait]
+impl<F> crate::common::Handler for RawHandler<F>
+where
+    F: Fn(Request) -> Result<Response, Error> + Send + Sync + 'static,
+{
+    fn matches(&self, path: &str, _method: &Method) -> bool {
+        self.pattern.is_match(path)
+    }
+
+    fn path_pattern(&self) -> &str {
+        self.pattern.as_str()
+    }
+
+    async fn handle(&self, req: Request) -> Result<Response, Error> {
+        (self.handler_fn)(req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::recording::TrafficRecorder;
+    use crate::common::Method;
+    use crate::handler;
+
+    fn app(recorder: Option<TrafficRecorder>, greeting: &'static str) -> RunBridge {
+        let mut builder = RunBridge::builder().handler(handler::get("^/hello$", move |req: Request| {
+            let name = req.query_params.get("name").cloned().unwrap_or_default();
+            Ok(format!("{} {}", greeting, name))
+        }));
+        if let Some(recorder) = recorder {
+            builder = builder.around(recorder);
+        }
+        builder.build()
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let recorder = TrafficRecorder::ring_buffer(10);
+        let recorded_app = app(Some(recorder.clone()), "hello");
+        let req = Request::new(Method::GET, "/hello".to_string()).with_query_param("name", "bob");
+        assert_eq!(dispatch(&recorded_app, req).await.status, 200);
+        drop(recorded_app);
+
+        let lines = recorder.json_lines();
+        assert_eq!(lines.len(), 1);
+
+        // 同じ挙動のアプリでは一致する
+        let exchange = RecordedExchange::from_json_line(&lines[0]).unwrap();
+        let outcome = replay(&app(None, "hello"), exchange).await;
+        assert!(outcome.status_matches() && outcome.body_matches(), "{:?}", outcome);
+        assert_eq!(outcome.route.as_deref(), Some("^/hello$"));
+
+        // 挙動が変わった場合は差分として検出される
+        let exchange = RecordedExchange::from_json_line(&lines[0]).unwrap();
+        let outcome = replay(&app(None, "hi"), exchange).await;
+        assert!(outcome.status_matches());
+        assert!(!outcome.body_matches());
+    }
+
+    #[cfg(debug_assertions)]
+    #[tokio::test]
+    async fn test_dispatch_marks_app_as_launched() {
+        use std::sync::atomic::Ordering;
+
+        // dispatchに渡したアプリは破棄時に`serve`の呼び忘れとして警告しない
+        let app = app(None, "hello");
+        assert!(!app.launched.load(Ordering::Relaxed));
+        dispatch(&app, Request::new(Method::GET, "/missing".to_string())).await;
+        assert!(app.launched.load(Ordering::Relaxed));
+    }
+}
@@ -0,0 +1,309 @@
+//! tower::Service アダプター
+//!
+//! ビルド済みの`RunBridge`を`tower_service::Service<http::Request<B>>`として扱い、
+//! hyper 1.x/axum等の既存サーバーへの組み込みやtowerミドルウェアとの併用を可能にします。
+//! リクエスト・レスポンスはhyper 1.xと同じ`http` 1.x系の型で、リクエストボディは
+//! `http_body::Body`を実装する任意の型（`hyper::body::Incoming`など）を受け付けます。
+//! レスポンスボディは`http_body_util::Full<Bytes>`で、Server-Sent Eventsはまとめて返します。
+
+use std::convert::Infallible;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use bytes::Bytes;
+use http_body::Body;
+use http_body_util::{BodyExt, Full, LengthLimitError, Limited};
+use log::{error, info, warn};
+use tower_service::Service;
+
+use crate::common::interop::join_header_values;
+use crate::common::deadline::{deadline_from_env, with_deadline};
+use crate::common::origin::RequestOrigin;
+use crate::common::request_id::{generate_request_id, sanitize_request_id, with_request_id};
+use crate::common::{check_method, parse_query_string_limited, Method, Request, Response};
+use crate::error::Error;
+use crate::RunBridge;
+
+/// `RunBridge`を`tower_service::Service`として公開するラッパー（Cloneで共有可能）
+#[derive(Clone)]
+pub struct RunBridgeService {
+    app: Arc<RunBridge>,
+}
+
+impl RunBridgeService {
+    /// 新しいRunBridgeServiceを作成（strictモードで設定エラーがある場合はエラー）
+    pub fn try_new(app: RunBridge) -> Result<Self, Error> {
+        app.launch()?;
+        Ok(Self { app: Arc::new(app) })
+    }
+}
+
+impl TryFrom<RunBridge> for RunBridgeService {
+    type Error = Error;
+
+    fn try_from(app: RunBridge) -> Result<Self, Self::Error> {
+        Self::try_new(app)
+    }
+}
+
+impl RunBridge {
+    /// tower::Serviceとして扱えるRunBridgeServiceに変換（strictモードで設定エラーがある場合はエラー）
+    pub fn into_service(self) -> Result<RunBridgeService, Error> {
+        RunBridgeService::try_new(self)
+    }
+}
+
+impl<B> Service<http1::Request<B>> for RunBridgeService
+where
+    B: Body + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    type Response = http1::Response<Full<Bytes>>;
+    type Error = Infallible;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http1::Request<B>) -> Self::Future {
+        let app = self.app.clone();
+        // リクエストIDをタスクローカルに設定して処理（パニックフックのログで参照）
+        let request_id = ["x-request-id", "x-cloud-trace-context"]
+            .iter()
+            .filter_map(|name| req.headers().get(*name))
+            .filter_map(|v| v.to_str().ok())
+            .find_map(sanitize_request_id)
+            .unwrap_or_else(generate_request_id);
+        let deadline = deadline_from_env();
+        Box::pin(with_request_id(request_id, with_deadline(deadline, async move {
+            Ok(convert_to_http_response(process_request(&app, req).await))
+        })))
+    }
+}
+
+/// リクエストのスキームとホストを取得（転送ヘッダーは`Request::scheme`が設定に従って扱う）
+fn request_origin(parts: &http1::request::Parts) -> RequestOrigin {
+    let host = parts
+        .headers
+        .get(http1::header::HOST)
+        .and_then(|h| h.to_str().ok())
+        .map(str::to_string)
+        .or_else(|| parts.uri.authority().map(|a| a.to_string()));
+    RequestOrigin {
+        scheme: parts.uri.scheme_str().unwrap_or("http").to_string(),
+        host,
+    }
+}
+
+/// http::Requestを共通のRequestに変換（拒否する場合はそのままレスポンスを返す）
+async fn convert_request<B>(app: &RunBridge, req: http1::Request<B>) -> Result<Request, Response>
+where
+    B: Body,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    let (parts, body) = req.into_parts();
+
+    // 許可されていないメソッドはルーティング前に405/501で拒否
+    let method = check_method(parts.method.as_str())?;
+
+    // クエリパラメータの取得（長さ・個数の上限を検査）
+    let query_params = parse_query_string_limited(parts.uri.query().unwrap_or(""))
+        .map_err(|e| Response::from_error(&e))?;
+
+    // ボディの受信（上限はルート固有 -> 全体設定、超過した時点で受信を打ち切る）
+    let max = app.max_body_size_for(parts.uri.path(), &method);
+    let body = match Limited::new(body, max).collect().await {
+        Ok(collected) => collected.to_bytes(),
+        Err(e) if e.is::<LengthLimitError>() => {
+            warn!("Request body too large (limit {})", max);
+            return Err(Response::error(413));
+        }
+        Err(e) => {
+            warn!("Failed to read request body in tower service: {}", e);
+            return Err(Response::from_error(&Error::InvalidRequestBody(format!("Failed to read body: {}", e))));
+        }
+    };
+
+    let mut request = Request::new(method, parts.uri.path().to_string());
+    request.query_params = query_params;
+    request.headers = join_header_values(
+        parts.headers.keys_len(),
+        parts.headers.iter().map(|(name, value)| (name.as_str(), value.to_str().ok())),
+    );
+    request.body = if body.is_empty() { None } else { Some(body.to_vec()) };
+    request.set_origin(request_origin(&parts));
+
+    // gzipボディを解凍（必要な場合のみ、解凍後のサイズもルートの上限で制限）
+    if let Err(e) = request.decompress_gzip_body_with_limit(max) {
+        warn!("Failed to decompress gzip body in tower service: {}", e);
+        return Err(Response::from_error(&e));
+    }
+
+    Ok(request)
+}
+
+/// リクエストを処理してレスポンスを返す
+async fn process_request<B>(app: &RunBridge, req: http1::Request<B>) -> Response
+where
+    B: Body,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    let started = Instant::now();
+    let (method_str, path) = (req.method().to_string(), req.uri().path().to_string());
+    let request = match convert_request(app, req).await {
+        Ok(request) => request,
+        Err(res) => {
+            app.log_rejected(started, &method_str, &path, &res);
+            return res;
+        }
+    };
+    info!("Received request: {} {}", request.method, request.path);
+
+    // ルーティング・ミドルウェア・ハンドラーの実行（全ランタイム共通）
+    let method = request.method;
+    let mut response = app.process_request(request).await.response;
+
+    // HEADリクエストはボディを除去し、GETと同じ`Content-Length`を返す
+    if method == Method::HEAD {
+        response.strip_body_for_head();
+    }
+
+    // ボディ型が`Full<Bytes>`のため、Server-Sent Eventsはまとめて返す
+    response.buffer_event_stream().await;
+    response
+}
+
+/// 共通のResponseをhttp::Responseに変換（変換できない場合は500）
+///
+/// ヘッダーの検証と`Set-Cookie`の分割は`interop`の変換（`http` 0.2系）をそのまま使用します。
+fn convert_to_http_response(response: Response) -> http1::Response<Full<Bytes>> {
+    let status = response.status;
+    let converted = ::http::Response::<Vec<u8>>::try_from(response).and_then(|res| {
+        let (parts, body) = res.into_parts();
+        let mut builder = http1::Response::builder().status(parts.status.as_u16());
+        for (name, value) in parts.headers.iter() {
+            builder = builder.header(name.as_str(), value.as_bytes());
+        }
+        builder
+            .body(Full::new(Bytes::from(body)))
+            .map_err(|e| Error::InternalServerError(e.to_string()))
+    });
+    converted.unwrap_or_else(|e| {
+        error!("Failed to convert response with status {}: {}", status, e);
+        let fallback = Response::error(500);
+        let mut res = http1::Response::new(Full::new(Bytes::from(fallback.body.clone().unwrap_or_default())));
+        *res.status_mut() = http1::StatusCode::INTERNAL_SERVER_ERROR;
+        if let Some(content_type) = fallback.content_type().and_then(|v| http1::HeaderValue::from_str(v).ok()) {
+            res.headers_mut().insert(http1::header::CONTENT_TYPE, content_type);
+        }
+        res
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handler::{self, HandlerExt};
+
+    fn app() -> RunBridge {
+        RunBridge::builder()
+            .handler(handler::get("^/hello$", |_req: Request| {
+                Ok(Response::ok().with_header("Content-Length", "999").with_body(b"hello".to_vec()))
+            }))
+            .handler(handler::get("^/origin$", |req: Request| Ok(req.base_url().unwrap_or_default())))
+            .handler(handler::post("^/echo$", |_req: Request, body: Option<serde_json::Value>| Ok(body)).max_body_size(16))
+            .build()
+    }
+
+    async fn send(service: &mut RunBridgeService, req: http1::Request<Full<Bytes>>) -> (http1::Response<Full<Bytes>>, Bytes) {
+        let res = service.call(req).await.unwrap();
+        let (parts, body) = res.into_parts();
+        let body = body.collect().await.unwrap().to_bytes();
+        (http1::Response::from_parts(parts, Full::new(body.clone())), body)
+    }
+
+    async fn call(service: &mut RunBridgeService, method: &str, uri: &str) -> (http1::Response<Full<Bytes>>, Bytes) {
+        let req = http1::Request::builder().method(method).uri(uri).body(Full::new(Bytes::new())).unwrap();
+        send(service, req).await
+    }
+
+    #[tokio::test]
+    async fn test_service_routes_request() {
+        let mut service = app().into_service().unwrap();
+
+        let (res, body) = call(&mut service, "GET", "/hello?x=1").await;
+        assert_eq!(res.status(), http1::StatusCode::OK);
+        assert_eq!(body, "hello");
+        // 予約ヘッダーはサーバー側で付与されるため除去される
+        assert!(res.headers().get("content-length").is_none());
+
+        let (res, _) = call(&mut service, "GET", "/missing").await;
+        assert_eq!(res.status(), http1::StatusCode::NOT_FOUND);
+
+        let (res, _) = call(&mut service, "TRACE", "/hello").await;
+        assert_eq!(res.status(), http1::StatusCode::NOT_IMPLEMENTED);
+
+        // HEADはGETのルートで処理し、ボディを除いて長さだけ返す
+        let (res, body) = call(&mut service, "HEAD", "/hello").await;
+        assert_eq!(res.status(), http1::StatusCode::OK);
+        assert!(body.is_empty());
+        assert_eq!(res.headers().get("content-length").unwrap(), "5");
+    }
+
+    #[tokio::test]
+    async fn test_service_reads_body_with_route_limit() {
+        let mut service = app().into_service().unwrap();
+        let post = |body: &'static str| {
+            http1::Request::builder()
+                .method("POST")
+                .uri("/echo")
+                .header("Content-Type", "application/json")
+                .body(Full::new(Bytes::from_static(body.as_bytes())))
+                .unwrap()
+        };
+
+        let (res, body) = send(&mut service, post(r#"{"a":1}"#)).await;
+        assert_eq!(res.status(), http1::StatusCode::OK);
+        assert_eq!(body, r#"{"a":1}"#);
+
+        let (res, _) = send(&mut service, post(r#"{"a":"0123456789abcdef"}"#)).await;
+        assert_eq!(res.status(), http1::StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn test_service_sets_origin_from_uri_and_host() {
+        let mut service = app().into_service().unwrap();
+
+        let (_, body) = call(&mut service, "GET", "https://api.example.com/origin").await;
+        assert_eq!(body, r#""https://api.example.com""#);
+
+        let req = http1::Request::builder()
+            .uri("/origin")
+            .header("Host", "tenant.example.com")
+            .body(Full::new(Bytes::new()))
+            .unwrap();
+        let (_, body) = send(&mut service, req).await;
+        assert_eq!(body, r#""http://tenant.example.com""#);
+    }
+
+    #[test]
+    fn test_try_from_reports_strict_config_errors() {
+        let app = RunBridge::builder()
+            .handler(handler::get("^/(unclosed$", |_req: Request| Ok("x")))
+            .strict_config(true)
+            .build();
+        assert!(RunBridgeService::try_from(app).is_err());
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn test_into_service_marks_app_as_launched() {
+        let service = app().into_service().unwrap();
+        assert!(service.app.launched.load(std::sync::atomic::Ordering::Relaxed));
+    }
+}
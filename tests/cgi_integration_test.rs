@@ -38,6 +38,7 @@ fn test_cgi_echo_endpoint_get() {
         vec![
             ("REQUEST_METHOD", Some("GET")),
             ("PATH_INFO", Some("/echo")),
+            ("RUNBRIDGE_DEBUG_ECHO", Some("1")),
             ("QUERY_STRING", Some("name=test&value=123")),
             ("HTTP_CONTENT_TYPE", Some("application/json")),
             ("HTTP_X_CUSTOM_HEADER", Some("TestValue")),
@@ -64,6 +65,25 @@ fn test_cgi_echo_endpoint_get() {
     assert!(stdout.contains("\"x-custom-header\":\"TestValue\""));
 }
 
+#[test]
+fn test_cgi_echo_endpoint_disabled_by_default() {
+    let output = run_cgi_with_env(
+        vec![
+            ("REQUEST_METHOD", Some("GET")),
+            ("PATH_INFO", Some("/echo")),
+            ("QUERY_STRING", Some("")),
+            ("RUNBRIDGE_DEBUG_ECHO", None),
+            ("HTTP_AUTHORIZATION", Some("Bearer secret")),
+        ],
+        "".as_bytes(),
+    );
+
+    // 明示的に有効化しない限りエコーエンドポイントは存在しない
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Status: 404 Not Found"));
+    assert!(!stdout.contains("Bearer secret"));
+}
+
 #[test]
 fn test_cgi_echo_endpoint_post() {
     let json_body = r#"{"message":"Hello, world!"}"#;
@@ -72,6 +92,7 @@ fn test_cgi_echo_endpoint_post() {
         vec![
             ("REQUEST_METHOD", Some("POST")),
             ("PATH_INFO", Some("/echo")),
+            ("RUNBRIDGE_DEBUG_ECHO", Some("1")),
             ("QUERY_STRING", Some("")),
             ("CONTENT_TYPE", Some("application/json")),
             ("CONTENT_LENGTH", Some(&json_body.len().to_string())),
@@ -129,8 +150,8 @@ fn test_cgi_panic_handling() {
     
     // パニックが発生しても500エラーが返されることを確認
     assert!(stdout.contains("Status: 500 Internal Server Error"));
-    assert!(stdout.contains("Content-Type: text/plain"));
-    assert!(stdout.contains("Internal Server Error"));
+    assert!(stdout.contains("Content-Type: application/json"));
+    assert!(stdout.contains(r#"{"error":"Internal Server Error","status":500}"#));
 }
 
 /// CGI環境をシミュレートして実行
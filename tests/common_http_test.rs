@@ -75,7 +75,9 @@ fn test_from_error_payload_too_large() {
     let res = Response::from_error(&err);
     assert_eq!(res.status, 413);
     let body = String::from_utf8(res.body.unwrap()).unwrap();
-    assert_eq!(body, "Payload Too Large");
+    // 既定はJSONで、エラーの詳細はクライアントに返さない
+    assert_eq!(body, r#"{"error":"Payload Too Large","status":413}"#);
+    assert_eq!(res.headers.get("Content-Type"), Some(&"application/json".to_string()));
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
@@ -472,3 +474,35 @@ fn test_gzip_decompression_uses_same_body_size_limit() {
     assert!(max_size > 0);
 }
 
+
+#[test]
+fn test_remove_reserved_headers() {
+    let mut res = Response::ok()
+        .with_header("content-length", "999")
+        .with_header("Transfer-Encoding", "chunked")
+        .with_header("Connection", "close")
+        .with_header("Status", "204")
+        .with_header("X-Custom", "kept");
+
+    let mut removed = res.remove_reserved_headers("^/test$");
+    removed.sort();
+    assert_eq!(removed, vec!["Connection", "Status", "Transfer-Encoding", "content-length"]);
+    assert_eq!(res.headers.get("X-Custom"), Some(&"kept".to_string()));
+    assert!(res.headers.keys().all(|k| !runbridge::common::http::is_reserved_response_header(k)));
+}
+
+#[test]
+fn test_debug_output_is_redacted() {
+    let req = Request::new(Method::POST, "/login".to_string())
+        .with_query_param("token", "q-secret")
+        .with_header("Authorization", "Bearer h-secret")
+        .with_body(vec![b'a'; 1000]);
+    let out = format!("{:?}", req);
+    assert!(!out.contains("q-secret") && !out.contains("h-secret"), "{}", out);
+    assert!(out.contains("...[1000 bytes]"), "{}", out);
+
+    let res = Response::ok().with_header("Set-Cookie", "session=s-secret").with_body(b"ok".to_vec());
+    let out = format!("{:?}", res);
+    assert!(!out.contains("s-secret"), "{}", out);
+    assert!(out.contains(r#"body: "ok""#), "{}", out);
+}
@@ -149,4 +149,79 @@ mod tests {
         // ミドルウェアが適切に適用されたか検証
         assert_eq!(response.headers.get("X-Middleware-Response").unwrap(), "Test2");
     }
-} 
+
+    #[tokio::test]
+    async fn test_url_for_named_routes() {
+        use runbridge::handler::HandlerExt;
+
+        fn redirect_handler(req: Request) -> Result<Response, Error> {
+            let location = req.url_for("get_item", &[("id", "42")])?;
+            Ok(Response::new(303).with_header("Location", location))
+        }
+
+        let app = RunBridge::builder()
+            .handler(handler::get(r"^/items/(?P<id>\d+)$", get_item_handler).name("get_item"))
+            .handler(handler::get("^/latest$", redirect_handler))
+            .build();
+
+        assert_eq!(app.url_for("get_item", &[("id", "7")]).unwrap(), "/items/7");
+        assert!(app.url_for("unknown", &[]).is_err());
+
+        let mut req = Request::new(Method::GET, "/latest".to_string());
+        let handler = app.find_handler(&req.path, &req.method).expect("Handler not found");
+        app.attach_route_context(handler.as_ref(), &mut req);
+        let res = handler.handle(req).await.unwrap();
+        assert_eq!(res.headers.get("Location").unwrap(), "/items/42");
+    }
+
+    // 包み込み型ミドルウェアのテスト（トランザクション風のbegin/commit/rollback）
+    struct TransactionMiddleware {
+        log: Arc<std::sync::Mutex<Vec<String>>>,
+        name: &'static str,
+    }
+
+    #[async_trait::async_trait]
+    impl runbridge::common::AroundMiddleware for TransactionMiddleware {
+        async fn around(&self, req: Request, next: runbridge::common::Next<'_>) -> Result<Response, Error> {
+            self.log.lock().unwrap().push(format!("{}:begin", self.name));
+            let result = next.run(req).await;
+            let outcome = match &result {
+                Ok(res) if res.status < 400 => "commit",
+                _ => "rollback",
+            };
+            self.log.lock().unwrap().push(format!("{}:{}", self.name, outcome));
+            result
+        }
+    }
+
+    #[tokio::test]
+    async fn test_around_middleware() {
+        let log = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let app = RunBridge::builder()
+            .around(TransactionMiddleware { log: log.clone(), name: "outer" })
+            .around(TransactionMiddleware { log: log.clone(), name: "inner" })
+            .handler(handler::get("^/ok$", |_| Ok("done")))
+            .handler(handler::get("^/fail$", |_| -> Result<Response, Error> {
+                Err(Error::InternalServerError("boom".to_string()))
+            }))
+            .build();
+
+        let req = Request::new(Method::GET, "/ok".to_string());
+        let handler = app.find_handler(&req.path, &req.method).expect("Handler not found");
+        let res = app.run_handler(handler.as_ref(), req).await.unwrap();
+        assert_eq!(res.status, 200);
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec!["outer:begin", "inner:begin", "inner:commit", "outer:commit"]
+        );
+
+        log.lock().unwrap().clear();
+        let req = Request::new(Method::GET, "/fail".to_string());
+        let handler = app.find_handler(&req.path, &req.method).expect("Handler not found");
+        assert!(app.run_handler(handler.as_ref(), req).await.is_err());
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec!["outer:begin", "inner:begin", "inner:rollback", "outer:rollback"]
+        );
+    }
+}